@@ -0,0 +1,88 @@
+// Copyright 2015 Ted Mielczarek. See the COPYRIGHT
+// file at the top-level directory of this distribution.
+
+//! Compare looking up one address's STACK CFI in the index with parsing all
+//! of a large symbol file's rules up front.
+//!
+//! Run with `cargo bench -p breakpad-symbols`.
+
+use breakpad_symbols::SymbolFile;
+use std::fmt::Write;
+use std::time::{Duration, Instant};
+
+/// The number of `STACK CFI INIT` records in the generated symbol file.
+const RANGES: u64 = 200_000;
+/// How many times to repeat each measurement, keeping the fastest.
+const RUNS: u32 = 5;
+
+/// A symbol file with `RANGES` functions, each with a few rules, which is
+/// about the size of the symbols of a large browser.
+fn large_sym_file() -> String {
+    let mut text = String::from("MODULE Linux x86_64 D3096ED481217FD4C16B29CD9BC208BA0 big\n");
+    for i in 0..RANGES {
+        let address = 0x1000 + i * 0x40;
+        writeln!(
+            text,
+            "FUNC {:x} 40 0 function_{}\n{:x} 40 {} 0",
+            address, i, address, i
+        )
+        .unwrap();
+        writeln!(
+            text,
+            "STACK CFI INIT {:x} 40 .cfa: $rsp 8 + .ra: .cfa -8 + ^",
+            address
+        )
+        .unwrap();
+        for offset in &[1, 4, 8] {
+            writeln!(
+                text,
+                "STACK CFI {:x} .cfa: $rsp {} + $rbp: .cfa -16 + ^",
+                address + offset,
+                16 + offset * 8
+            )
+            .unwrap();
+        }
+    }
+    text
+}
+
+/// The fastest of `RUNS` runs of `f`.
+fn time<T>(mut f: impl FnMut() -> T) -> Duration {
+    (0..RUNS)
+        .map(|_| {
+            let start = Instant::now();
+            let result = f();
+            let elapsed = start.elapsed();
+            drop(result);
+            elapsed
+        })
+        .min()
+        .unwrap()
+}
+
+fn main() {
+    let text = large_sym_file();
+    println!(
+        "symbol file: {} MB, {} CFI ranges",
+        text.len() / 1_000_000,
+        RANGES
+    );
+    let address = 0x1000 + (RANGES / 2) * 0x40 + 8;
+
+    let load = time(|| SymbolFile::from_bytes(text.as_bytes()).unwrap());
+    let sym = SymbolFile::from_bytes(text.as_bytes()).unwrap();
+    let indexed = time(|| sym.cfi_stack_info.get(address).unwrap());
+    let full = time(|| sym.cfi_stack_info.to_range_map());
+    let cfi = sym.cfi_stack_info.to_range_map();
+    let full_lookup = time(|| cfi.get(address).unwrap().clone());
+
+    println!("load and index:         {:?}", load);
+    println!("indexed lookup:         {:?}", indexed);
+    println!("parse all rules:        {:?}", full);
+    println!("lookup in parsed rules: {:?}", full_lookup);
+    println!(
+        "first lookup, indexed:  {:?}\nfirst lookup, full:     {:?}",
+        load + indexed,
+        load + full + full_lookup
+    );
+}
@@ -0,0 +1,72 @@
+// Copyright 2015 Ted Mielczarek. See the COPYRIGHT
+// file at the top-level directory of this distribution.
+
+//! Reading symbol files that were compressed to save space.
+//!
+//! gzip and zstd are understood when the `flate2` and `zstd` features are
+//! enabled, respectively.
+
+use failure::Error;
+
+const GZIP_MAGIC: &[u8] = &[0x1f, 0x8b];
+const ZSTD_MAGIC: &[u8] = &[0x28, 0xb5, 0x2f, 0xfd];
+
+/// The extensions that compressed symbol files can have on top of `.sym`, for
+/// the compression formats that can be read.
+pub(crate) fn compressed_extensions() -> Vec<&'static str> {
+    let mut extensions = vec![];
+    if cfg!(feature = "flate2") {
+        extensions.push("gz");
+    }
+    if cfg!(feature = "zstd") {
+        extensions.push("zst");
+    }
+    extensions
+}
+
+/// `path`, followed by `path` with each of the [`compressed_extensions`] added.
+pub(crate) fn with_compressed_extensions(path: &str) -> Vec<String> {
+    let mut paths = vec![path.to_owned()];
+    paths.extend(
+        compressed_extensions()
+            .into_iter()
+            .map(|extension| format!("{}.{}", path, extension)),
+    );
+    paths
+}
+
+/// Decompress `bytes` if they are a compressed symbol file, telling by their
+/// magic number. Anything else is returned as is.
+pub(crate) fn decompress(bytes: Vec<u8>) -> Result<Vec<u8>, Error> {
+    if bytes.starts_with(GZIP_MAGIC) {
+        gunzip(&bytes)
+    } else if bytes.starts_with(ZSTD_MAGIC) {
+        unzstd(&bytes)
+    } else {
+        Ok(bytes)
+    }
+}
+
+#[cfg(feature = "flate2")]
+fn gunzip(bytes: &[u8]) -> Result<Vec<u8>, Error> {
+    use std::io::Read;
+
+    let mut decompressed = vec![];
+    flate2::read::GzDecoder::new(bytes).read_to_end(&mut decompressed)?;
+    Ok(decompressed)
+}
+
+#[cfg(not(feature = "flate2"))]
+fn gunzip(_bytes: &[u8]) -> Result<Vec<u8>, Error> {
+    failure::bail!("symbol file is gzip-compressed, but the `flate2` feature is disabled")
+}
+
+#[cfg(feature = "zstd")]
+fn unzstd(bytes: &[u8]) -> Result<Vec<u8>, Error> {
+    Ok(zstd::stream::decode_all(bytes)?)
+}
+
+#[cfg(not(feature = "zstd"))]
+fn unzstd(_bytes: &[u8]) -> Result<Vec<u8>, Error> {
+    failure::bail!("symbol file is zstd-compressed, but the `zstd` feature is disabled")
+}
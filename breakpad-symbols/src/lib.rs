@@ -42,11 +42,16 @@ use std::fmt;
 use std::fs::{self, File};
 use std::io::{self, Read, Write};
 use std::path::{Path, PathBuf};
+use std::sync::Arc;
+use std::time::Instant;
 
 pub use minidump_common::traits::Module;
 
-pub use crate::sym_file::{CfiRules, SymbolFile};
+pub use crate::sym_file::{
+    CfiCache, CfiIndex, CfiRules, StackInfoCfi, SymbolFile, SymbolParseError,
+};
 
+mod compression;
 mod sym_file;
 
 /// A `Module` implementation that holds arbitrary data.
@@ -144,29 +149,76 @@ fn replace_or_add_extension(filename: &str, match_extension: &str, new_extension
 /// You can use [this script][packagesymbols] to run dump_syms and put the
 /// resulting symbol files in the proper directory structure.
 ///
+/// Modules that have no debug information, such as unloaded modules, are
+/// looked up by their code file and code identifier instead:
+/// `<code filename>/<code identifier>/<code filename stem>.sym`.
+///
 /// [module_line]: https://chromium.googlesource.com/breakpad/breakpad/+/master/docs/symbol_files.md#MODULE-records
 /// [packagesymbols]: https://gist.github.com/luser/2ad32d290f224782fcfc#file-packagesymbols-py
 pub fn relative_symbol_path(module: &dyn Module, extension: &str) -> Option<String> {
-    module.debug_file().and_then(|debug_file| {
-        module.debug_identifier().map(|debug_id| {
-            // Can't use PathBuf::file_name here, it doesn't handle
-            // Windows file paths on non-Windows.
-            let leaf = leafname(&debug_file);
-            let filename = replace_or_add_extension(leaf, "pdb", extension);
-            [leaf, &debug_id[..], &filename[..]].join("/")
-        })
-    })
+    match (module.debug_file(), module.debug_identifier()) {
+        (Some(debug_file), Some(debug_id)) => {
+            Some(debug_symbol_path(&debug_file, &debug_id, extension))
+        }
+        (None, None) => {
+            let code_file = module.code_file();
+            let code_id = module.code_identifier();
+            let leaf = leafname(&code_file);
+            if leaf.is_empty() || code_id.is_empty() {
+                return None;
+            }
+            let stem = leaf.rsplitn(2, '.').last().unwrap_or(leaf);
+            let filename = [stem, extension].join(".");
+            Some([leaf, &code_id[..], &filename[..]].join("/"))
+        }
+        _ => None,
+    }
+}
+
+/// The relative symbol path for a module with `debug_file` and `debug_id`.
+fn debug_symbol_path(debug_file: &str, debug_id: &str, extension: &str) -> String {
+    // Can't use PathBuf::file_name here, it doesn't handle
+    // Windows file paths on non-Windows.
+    let leaf = leafname(debug_file);
+    let filename = replace_or_add_extension(leaf, "pdb", extension);
+    [leaf, debug_id, &filename[..]].join("/")
+}
+
+/// The relative paths to look for symbols for `module` at in a local symbol store.
+///
+/// This is [`relative_symbol_path`], followed by the same path with the debug
+/// identifier in uppercase if that's different. Breakpad's tools store symbols
+/// under uppercase identifiers, but a module's debug identifier may have its age
+/// in lowercase.
+///
+/// [`relative_symbol_path`]: fn.relative_symbol_path.html
+fn symbol_store_paths(module: &dyn Module, extension: &str) -> Vec<String> {
+    let mut paths = relative_symbol_path(module, extension)
+        .into_iter()
+        .collect::<Vec<_>>();
+    if let (Some(debug_file), Some(debug_id)) = (module.debug_file(), module.debug_identifier()) {
+        let uppercase_id = debug_id.to_uppercase();
+        if uppercase_id != debug_id {
+            paths.push(debug_symbol_path(&debug_file, &uppercase_id, extension));
+        }
+    }
+    paths
 }
 
 /// Possible results of locating symbols.
 #[derive(Debug)]
 pub enum SymbolResult {
     /// Symbols loaded successfully.
-    Ok(SymbolFile),
+    ///
+    /// They're shared, so a supplier that keeps parsed symbols around can hand
+    /// them out without copying them.
+    Ok(Arc<SymbolFile>),
     /// Symbol file could not be found.
     NotFound,
     /// Error loading symbol file.
     LoadError(Error),
+    /// Symbol file was found but could not be parsed.
+    Corrupt(SymbolParseError),
 }
 
 impl PartialEq for SymbolResult {
@@ -175,21 +227,94 @@ impl PartialEq for SymbolResult {
             (&SymbolResult::Ok(ref a), &SymbolResult::Ok(ref b)) => a == b,
             (&SymbolResult::NotFound, &SymbolResult::NotFound) => true,
             (&SymbolResult::LoadError(_), &SymbolResult::LoadError(_)) => true,
+            (&SymbolResult::Corrupt(ref a), &SymbolResult::Corrupt(ref b)) => a == b,
             _ => false,
         }
     }
 }
 
+impl SymbolResult {
+    /// Whether these are usable symbols, missing ones, or corrupt ones.
+    pub fn status(&self) -> SymbolStatus {
+        match *self {
+            SymbolResult::Ok(_) => SymbolStatus::Loaded,
+            SymbolResult::NotFound | SymbolResult::LoadError(_) => SymbolStatus::Missing,
+            SymbolResult::Corrupt(_) => SymbolStatus::Corrupt,
+        }
+    }
+}
+
 impl fmt::Display for SymbolResult {
     fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
         match *self {
             SymbolResult::Ok(_) => write!(f, "Ok"),
             SymbolResult::NotFound => write!(f, "Not found"),
             SymbolResult::LoadError(ref e) => write!(f, "Load error: {}", e),
+            SymbolResult::Corrupt(ref e) => write!(f, "Corrupt: {}", e),
         }
     }
 }
 
+/// The outcome of looking for a module's symbols.
+#[derive(Clone, Copy, Debug, PartialEq)]
+pub enum SymbolStatus {
+    /// Symbols were found and loaded.
+    Loaded,
+    /// No symbols were found, or they could not be read.
+    Missing,
+    /// A symbol file was found but could not be parsed.
+    Corrupt,
+}
+
+/// Where a symbol file that was loaded came from.
+///
+/// Suppliers that only read local files leave this at its default.
+#[derive(Clone, Debug, Default, PartialEq)]
+pub struct SymbolOrigin {
+    /// Whether the file was in the disk cache of downloaded symbol files.
+    pub from_cache: bool,
+    /// The URL the file was downloaded from, if it was downloaded.
+    pub url: Option<String>,
+    /// How long downloading the file took, in milliseconds, if it was downloaded.
+    pub fetch_ms: Option<f64>,
+}
+
+/// Check that the `MODULE` record of `sym`, if it has one, is for the same build
+/// as `module`.
+///
+/// Symbol files are found by the module's debug identifier, so a mismatch means
+/// the file was stored under the wrong one. Usually only the age differs, and the
+/// file's line numbers belong to a different build of the module, so it's
+/// reported as corrupt rather than used. Breakpad tools disagree on the case of
+/// the age, so that is ignored.
+fn check_debug_id(module: &dyn Module, sym: impl Into<Arc<SymbolFile>>) -> SymbolResult {
+    let sym = sym.into();
+    match (sym.debug_id.as_ref(), module.debug_identifier()) {
+        (Some(sym_id), Some(module_id)) if !sym_id.eq_ignore_ascii_case(&module_id) => {
+            SymbolResult::Corrupt(SymbolParseError {
+                line: 1,
+                reason: format!(
+                    "MODULE debug id `{}` does not match module debug id `{}`",
+                    sym_id, module_id
+                ),
+            })
+        }
+        _ => SymbolResult::Ok(sym),
+    }
+}
+
+/// Load the symbol file at `path` for `module`, telling a file that can't be
+/// parsed apart from one that can't be read.
+fn load_symbol_file(module: &dyn Module, path: &Path) -> SymbolResult {
+    match SymbolFile::from_file(path) {
+        Ok(sym) => check_debug_id(module, sym),
+        Err(e) => match e.downcast::<SymbolParseError>() {
+            Ok(e) => SymbolResult::Corrupt(e),
+            Err(e) => SymbolResult::LoadError(e),
+        },
+    }
+}
+
 /// A trait for things that can locate symbols for a given module.
 pub trait SymbolSupplier {
     /// Locate and load a symbol file for `module`.
@@ -197,12 +322,29 @@ pub trait SymbolSupplier {
     /// Implementations may use any strategy for locating and loading
     /// symbols.
     fn locate_symbols(&self, module: &dyn Module) -> SymbolResult;
+
+    /// Like `locate_symbols`, and also say where the symbols came from.
+    ///
+    /// Suppliers that download symbols should implement this, the default
+    /// gives a default `SymbolOrigin`.
+    fn locate_symbols_with_origin(&self, module: &dyn Module) -> (SymbolResult, SymbolOrigin) {
+        (self.locate_symbols(module), SymbolOrigin::default())
+    }
 }
 
 /// An implementation of `SymbolSupplier` that loads Breakpad text-format symbols from local disk
 /// paths.
 ///
-/// See [`relative_symbol_path`] for details on how paths are searched.
+/// Each path is the root of a symbol store, laid out like the output of Breakpad's tools:
+/// `<debug filename>/<debug identifier>/<debug filename>.sym`. The paths are searched in order.
+/// Debug identifiers are tried as the module gives them, then in uppercase, which is how
+/// Breakpad's tools store them.
+/// Each symbol file may also be compressed, with a `.sym.gz` or `.sym.zst` extension, if the
+/// `flate2` or `zstd` feature is enabled.
+///
+/// See [`relative_symbol_path`] for details on how paths are searched. A symbol
+/// file whose `MODULE` record has a different debug identifier than the module
+/// is reported as corrupt.
 ///
 /// [`relative_symbol_path`]: fn.relative_symbol_path.html
 pub struct SimpleSymbolSupplier {
@@ -219,13 +361,15 @@ impl SimpleSymbolSupplier {
 
 impl SymbolSupplier for SimpleSymbolSupplier {
     fn locate_symbols(&self, module: &dyn Module) -> SymbolResult {
-        if let Some(rel_path) = relative_symbol_path(module, "sym") {
-            for ref path in self.paths.iter() {
-                let test_path = path.join(&rel_path);
+        let rel_paths = symbol_store_paths(module, "sym")
+            .iter()
+            .flat_map(|rel_path| compression::with_compressed_extensions(rel_path))
+            .collect::<Vec<_>>();
+        for ref path in self.paths.iter() {
+            for rel_path in &rel_paths {
+                let test_path = path.join(rel_path);
                 if fs::metadata(&test_path).ok().map_or(false, |m| m.is_file()) {
-                    return SymbolFile::from_file(&test_path)
-                        .map(SymbolResult::Ok)
-                        .unwrap_or_else(SymbolResult::LoadError);
+                    return load_symbol_file(module, &test_path);
                 }
             }
         }
@@ -233,12 +377,90 @@ impl SymbolSupplier for SimpleSymbolSupplier {
     }
 }
 
+/// An implementation of `SymbolSupplier` that supplies symbol files that were already parsed.
+///
+/// Symbol files are looked up by the debug file name and debug identifier of the
+/// module, like [`relative_symbol_path`] does on disk, rather than by its code
+/// file: those are what a symbol file's `MODULE` record names, and a Windows
+/// module's code file is its DLL or executable, not the PDB its symbols are for.
+/// A symbol file whose `MODULE` record is for another build of the module is
+/// reported as corrupt, as it is on disk.
+///
+/// Symbol files are shared, not copied, with each `Symbolizer` that uses them,
+/// so one supplier can be shared between `Symbolizer`s with an `Arc` without
+/// parsing or copying any symbol file twice. It's also handy for supplying
+/// symbols in tests without touching the filesystem.
+///
+/// [`relative_symbol_path`]: fn.relative_symbol_path.html
+#[derive(Default)]
+pub struct InMemorySymbolSupplier {
+    /// Symbol files, keyed by debug file name and debug identifier.
+    symbols: HashMap<(String, String), Arc<SymbolFile>>,
+}
+
+impl InMemorySymbolSupplier {
+    /// Instantiate an empty `InMemorySymbolSupplier`.
+    pub fn new() -> InMemorySymbolSupplier {
+        Default::default()
+    }
+
+    /// Supply `symbols` for modules with `debug_file` and `debug_id`.
+    ///
+    /// Only the file name of `debug_file` is used. This replaces any symbols
+    /// previously supplied for the same module. `symbols` can be a `SymbolFile`,
+    /// or an `Arc<SymbolFile>` that's shared with other suppliers.
+    pub fn insert(
+        &mut self,
+        debug_file: &str,
+        debug_id: &str,
+        symbols: impl Into<Arc<SymbolFile>>,
+    ) {
+        let key = (leafname(debug_file).to_owned(), debug_id.to_owned());
+        self.symbols.insert(key, symbols.into());
+    }
+}
+
+impl SymbolSupplier for InMemorySymbolSupplier {
+    fn locate_symbols(&self, module: &dyn Module) -> SymbolResult {
+        let symbols = module.debug_file().and_then(|debug_file| {
+            let debug_id = module.debug_identifier()?;
+            let key = (leafname(&debug_file).to_owned(), debug_id.into_owned());
+            self.symbols.get(&key)
+        });
+        match symbols {
+            Some(symbols) => check_debug_id(module, Arc::clone(symbols)),
+            None => SymbolResult::NotFound,
+        }
+    }
+}
+
+impl<T: SymbolSupplier + ?Sized> SymbolSupplier for Box<T> {
+    fn locate_symbols(&self, module: &dyn Module) -> SymbolResult {
+        (**self).locate_symbols(module)
+    }
+    fn locate_symbols_with_origin(&self, module: &dyn Module) -> (SymbolResult, SymbolOrigin) {
+        (**self).locate_symbols_with_origin(module)
+    }
+}
+
+impl<T: SymbolSupplier + ?Sized> SymbolSupplier for Arc<T> {
+    fn locate_symbols(&self, module: &dyn Module) -> SymbolResult {
+        (**self).locate_symbols(module)
+    }
+    fn locate_symbols_with_origin(&self, module: &dyn Module) -> (SymbolResult, SymbolOrigin) {
+        (**self).locate_symbols_with_origin(module)
+    }
+}
+
 /// An implementation of `SymbolSupplier` that loads Breakpad text-format symbols from HTTP
 /// URLs.
 ///
-/// See [`relative_symbol_path`] for details on how paths are searched.
+/// See [`relative_symbol_path`] for details on how paths are searched. Compressed symbol
+/// files are looked for after uncompressed ones, like [`SimpleSymbolSupplier`] does, and
+/// are cached compressed.
 ///
 /// [`relative_symbol_path`]: fn.relative_symbol_path.html
+/// [`SimpleSymbolSupplier`]: struct.SimpleSymbolSupplier.html
 pub struct HttpSymbolSupplier {
     /// HTTP Client to use for fetching symbols.
     client: Client,
@@ -246,6 +468,8 @@ pub struct HttpSymbolSupplier {
     urls: Vec<Url>,
     /// A `SimpleSymbolSupplier` to use for local symbol paths.
     local: SimpleSymbolSupplier,
+    /// A `SimpleSymbolSupplier` to use for symbols that were downloaded before.
+    cached: SimpleSymbolSupplier,
     /// A path at which to cache downloaded symbols.
     cache: PathBuf,
 }
@@ -255,11 +479,7 @@ impl HttpSymbolSupplier {
     ///
     /// Symbols will be searched for in each of `local_paths` and `cache` first, then via HTTP
     /// at each of `urls`. If a symbol file is found via HTTP it will be saved under `cache`.
-    pub fn new(
-        urls: Vec<String>,
-        cache: PathBuf,
-        mut local_paths: Vec<PathBuf>,
-    ) -> HttpSymbolSupplier {
+    pub fn new(urls: Vec<String>, cache: PathBuf, local_paths: Vec<PathBuf>) -> HttpSymbolSupplier {
         let client = Client::new();
         let urls = urls
             .into_iter()
@@ -270,12 +490,13 @@ impl HttpSymbolSupplier {
                 Url::parse(&u).ok()
             })
             .collect();
-        local_paths.push(cache.clone());
         let local = SimpleSymbolSupplier::new(local_paths);
+        let cached = SimpleSymbolSupplier::new(vec![cache.clone()]);
         HttpSymbolSupplier {
             client,
             urls,
             local,
+            cached,
             cache,
         }
     }
@@ -315,24 +536,48 @@ fn fetch_symbol_file(
 
 impl SymbolSupplier for HttpSymbolSupplier {
     fn locate_symbols(&self, module: &dyn Module) -> SymbolResult {
-        // Check local paths first.
+        self.locate_symbols_with_origin(module).0
+    }
+
+    fn locate_symbols_with_origin(&self, module: &dyn Module) -> (SymbolResult, SymbolOrigin) {
+        // Check local paths first, then symbols that were downloaded before.
         match self.local.locate_symbols(module) {
-            res @ SymbolResult::Ok(_) | res @ SymbolResult::LoadError(_) => res,
-            SymbolResult::NotFound => {
-                if let Some(rel_path) = relative_symbol_path(module, "sym") {
-                    for ref url in self.urls.iter() {
-                        if let Ok(buf) =
-                            fetch_symbol_file(&self.client, url, &rel_path, &self.cache)
-                        {
-                            return SymbolFile::from_bytes(&buf)
-                                .map(SymbolResult::Ok)
-                                .unwrap_or_else(SymbolResult::LoadError);
-                        }
-                    }
+            SymbolResult::NotFound => {}
+            res => return (res, SymbolOrigin::default()),
+        }
+        match self.cached.locate_symbols(module) {
+            SymbolResult::NotFound => {}
+            res => {
+                let origin = SymbolOrigin {
+                    from_cache: true,
+                    ..SymbolOrigin::default()
+                };
+                return (res, origin);
+            }
+        }
+        let rel_paths = relative_symbol_path(module, "sym")
+            .map(|rel_path| compression::with_compressed_extensions(&rel_path))
+            .unwrap_or_default();
+        for rel_path in &rel_paths {
+            for ref url in self.urls.iter() {
+                let start = Instant::now();
+                if let Ok(buf) = fetch_symbol_file(&self.client, url, rel_path, &self.cache) {
+                    let origin = SymbolOrigin {
+                        from_cache: false,
+                        url: url.join(rel_path).ok().map(|url| url.to_string()),
+                        fetch_ms: Some(start.elapsed().as_secs_f64() * 1000.0),
+                    };
+                    let res = match compression::decompress(buf) {
+                        Ok(buf) => SymbolFile::from_bytes(&buf)
+                            .map(|sym| check_debug_id(module, sym))
+                            .unwrap_or_else(SymbolResult::Corrupt),
+                        Err(e) => SymbolResult::LoadError(e),
+                    };
+                    return (res, origin);
                 }
-                SymbolResult::NotFound
             }
         }
+        (SymbolResult::NotFound, SymbolOrigin::default())
     }
 }
 
@@ -345,6 +590,11 @@ pub trait FrameSymbolizer {
     fn set_function(&mut self, name: &str, base: u64, parameter_size: u32);
     /// Set the source file and (1-based) line number this frame represents.
     fn set_source_file(&mut self, file: &str, line: u32, base: u64);
+    /// Add a function that was inlined at this frame's instruction.
+    ///
+    /// Inlined functions are added innermost first. `file` and `line` are the source
+    /// location within the inlined function. The default implementation ignores them.
+    fn add_inline_frame(&mut self, _name: &str, _file: Option<&str>, _line: Option<u32>) {}
 }
 
 pub trait FrameWalker {
@@ -451,6 +701,8 @@ pub struct Symbolizer {
     /// Cache of symbol locating results.
     //TODO: use lru-cache: https://crates.io/crates/lru-cache/
     symbols: RefCell<HashMap<ModuleKey, SymbolResult>>,
+    /// Where each module's symbols came from.
+    origins: RefCell<HashMap<ModuleKey, SymbolOrigin>>,
 }
 
 impl Symbolizer {
@@ -459,6 +711,7 @@ impl Symbolizer {
         Symbolizer {
             supplier: Box::new(supplier),
             symbols: RefCell::new(HashMap::new()),
+            origins: RefCell::new(HashMap::new()),
         }
     }
 
@@ -525,11 +778,63 @@ impl Symbolizer {
         }
     }
 
+    /// Like `walk_frame`, but reuses STACK CFI rules already parsed into `cache`.
+    ///
+    /// Unwinding many threads that run the same functions parses their rules
+    /// once, rather than once per frame.
+    pub fn walk_frame_with_cache(
+        &self,
+        module: &dyn Module,
+        walker: &mut dyn FrameWalker,
+        cache: &CfiCache,
+    ) -> Option<()> {
+        let k = key(module);
+        self.ensure_module(module, &k);
+        if let Some(SymbolResult::Ok(ref sym)) = self.symbols.borrow().get(&k) {
+            sym.walk_frame_with_cache(module, walker, cache)
+        } else {
+            None
+        }
+    }
+
+    /// Whether symbols for `module` were loaded, missing, or corrupt.
+    ///
+    /// Returns `None` if this `Symbolizer` hasn't looked for symbols for `module` yet.
+    pub fn symbol_status(&self, module: &dyn Module) -> Option<SymbolStatus> {
+        self.symbols
+            .borrow()
+            .get(&key(module))
+            .map(|res| res.status())
+    }
+
+    /// Why the symbol file for `module` couldn't be parsed.
+    ///
+    /// Returns `None` unless this `Symbolizer` found symbols for `module` and
+    /// reported them as `SymbolStatus::Corrupt`.
+    pub fn symbol_parse_error(&self, module: &dyn Module) -> Option<SymbolParseError> {
+        match self.symbols.borrow().get(&key(module)) {
+            Some(SymbolResult::Corrupt(e)) => Some(e.clone()),
+            _ => None,
+        }
+    }
+
+    /// Where the symbols for `module` came from.
+    ///
+    /// Returns `None` unless this `Symbolizer` loaded symbols for `module`.
+    pub fn symbol_origin(&self, module: &dyn Module) -> Option<SymbolOrigin> {
+        let k = key(module);
+        match self.symbols.borrow().get(&k) {
+            Some(SymbolResult::Ok(_)) => self.origins.borrow().get(&k).cloned(),
+            _ => None,
+        }
+    }
+
     fn ensure_module(&self, module: &dyn Module, k: &ModuleKey) {
         if !self.symbols.borrow().contains_key(&k) {
-            let res = self.supplier.locate_symbols(module);
+            let (res, origin) = self.supplier.locate_symbols_with_origin(module);
             debug!("locate_symbols for {}: {}", module.code_file(), res);
             self.symbols.borrow_mut().insert(k.clone(), res);
+            self.origins.borrow_mut().insert(k.clone(), origin);
         }
     }
 }
@@ -614,6 +919,34 @@ mod test {
         assert!(relative_symbol_path(&bad3, "sym").is_none());
     }
 
+    #[test]
+    fn test_relative_symbol_path_code_file() {
+        let m = SimpleModule {
+            code_file: Some("c:\\windows\\system32\\foo.dll".to_string()),
+            code_identifier: Some("5A9832E5a000".to_string()),
+            ..SimpleModule::default()
+        };
+        assert_eq!(
+            &relative_symbol_path(&m, "sym").unwrap(),
+            "foo.dll/5A9832E5a000/foo.sym"
+        );
+
+        let no_id = SimpleModule {
+            code_file: Some("foo.dll".to_string()),
+            ..SimpleModule::default()
+        };
+        assert!(relative_symbol_path(&no_id, "sym").is_none());
+
+        // A module with partial debug information shouldn't fall back.
+        let partial = SimpleModule {
+            code_file: Some("foo.dll".to_string()),
+            code_identifier: Some("5A9832E5a000".to_string()),
+            debug_file: Some("foo.pdb".to_string()),
+            ..SimpleModule::default()
+        };
+        assert!(relative_symbol_path(&partial, "sym").is_none());
+    }
+
     #[test]
     fn test_relative_symbol_path_abs_paths() {
         {
@@ -660,8 +993,9 @@ mod test {
         f.write_all(contents).unwrap();
     }
 
-    fn write_good_symbol_file(path: &Path) {
-        write_symbol_file(path, b"MODULE Linux x86 abcd1234 foo\n");
+    fn write_good_symbol_file(path: &Path, debug_id: &str) {
+        let contents = format!("MODULE Linux x86 {} foo\n", debug_id);
+        write_symbol_file(path, contents.as_bytes());
     }
 
     fn write_bad_symbol_file(path: &Path) {
@@ -688,7 +1022,7 @@ mod test {
             let m = SimpleModule::new(file, id);
             // No symbols present yet.
             assert_eq!(supplier.locate_symbols(&m), SymbolResult::NotFound);
-            write_good_symbol_file(&path.join(sym));
+            write_good_symbol_file(&path.join(sym), id);
             // Should load OK now that it exists.
             assert!(
                 matches!(supplier.locate_symbols(&m), SymbolResult::Ok(_)),
@@ -697,19 +1031,146 @@ mod test {
             );
         }
 
-        // Write a malformed symbol file, verify that it's found but fails to load.
+        // Write a malformed symbol file, verify that it's found but reported corrupt.
         let mal = SimpleModule::new("baz.pdb", "ffff0000");
         let sym = "baz.pdb/ffff0000/baz.sym";
         assert_eq!(supplier.locate_symbols(&mal), SymbolResult::NotFound);
         write_bad_symbol_file(&paths[0].join(sym));
         let res = supplier.locate_symbols(&mal);
         assert!(
-            matches!(res, SymbolResult::LoadError(_)),
+            matches!(res, SymbolResult::Corrupt(SymbolParseError { line: 1, .. })),
             "{}",
             format!("Correctly failed to parse {}, result: {:?}", sym, res)
         );
     }
 
+    #[test]
+    fn test_simple_symbol_supplier_debug_id_mismatch() {
+        let t = TempDir::new("symtest").unwrap();
+        let path = t.path();
+        let supplier = SimpleSymbolSupplier::new(vec![PathBuf::from(path)]);
+
+        // Two builds of the same PDB, differing only in age.
+        let age1 = SimpleModule::new("foo.pdb", "5A9832E5287241C1838ED98914E9B7FF1");
+        let age2 = SimpleModule::new("foo.pdb", "5A9832E5287241C1838ED98914E9B7FF2");
+        write_symbol_file(
+            &path.join("foo.pdb/5A9832E5287241C1838ED98914E9B7FF1/foo.sym"),
+            b"MODULE windows x86 5A9832E5287241C1838ED98914E9B7FF1 foo.pdb\n",
+        );
+        // Symbols for the first build, stored under the second build's age.
+        write_symbol_file(
+            &path.join("foo.pdb/5A9832E5287241C1838ED98914E9B7FF2/foo.sym"),
+            b"MODULE windows x86 5A9832E5287241C1838ED98914E9B7FF1 foo.pdb\n",
+        );
+        assert!(matches!(
+            supplier.locate_symbols(&age1),
+            SymbolResult::Ok(_)
+        ));
+        let res = supplier.locate_symbols(&age2);
+        assert!(
+            matches!(res, SymbolResult::Corrupt(SymbolParseError { line: 1, .. })),
+            "{}",
+            format!("Rejected mismatched symbols, result: {:?}", res)
+        );
+
+        // The case of the age doesn't matter.
+        let age10 = SimpleModule::new("foo.pdb", "5A9832E5287241C1838ED98914E9B7FFa");
+        write_symbol_file(
+            &path.join("foo.pdb/5A9832E5287241C1838ED98914E9B7FFa/foo.sym"),
+            b"MODULE windows x86 5A9832E5287241C1838ED98914E9B7FFA foo.pdb\n",
+        );
+        assert!(matches!(
+            supplier.locate_symbols(&age10),
+            SymbolResult::Ok(_)
+        ));
+
+        let symbolizer = Symbolizer::new(supplier);
+        let mut f = SimpleFrame::with_instruction(0x1010);
+        symbolizer.fill_symbol(&age2, &mut f);
+        assert_eq!(symbolizer.symbol_status(&age2), Some(SymbolStatus::Corrupt));
+        assert!(symbolizer
+            .symbol_parse_error(&age2)
+            .unwrap()
+            .reason
+            .contains("does not match"));
+    }
+
+    #[test]
+    fn test_simple_symbol_supplier_symbol_store() {
+        let t = TempDir::new("symtest").unwrap();
+        let paths = mksubdirs(t.path(), &["one", "two"]);
+        let supplier = SimpleSymbolSupplier::new(paths.clone());
+
+        // Breakpad's tools store symbols under an uppercase debug id, even if
+        // the module writes its age in lowercase.
+        let m = SimpleModule::new("c:\\foo.pdb", "5A9832E5287241C1838ED98914E9B7FFa");
+        assert_eq!(
+            symbol_store_paths(&m, "sym"),
+            vec![
+                "foo.pdb/5A9832E5287241C1838ED98914E9B7FFa/foo.sym",
+                "foo.pdb/5A9832E5287241C1838ED98914E9B7FFA/foo.sym",
+            ]
+        );
+        assert_eq!(supplier.locate_symbols(&m), SymbolResult::NotFound);
+        write_symbol_file(
+            &paths[1].join("foo.pdb/5A9832E5287241C1838ED98914E9B7FFA/foo.sym"),
+            b"MODULE windows x86 5A9832E5287241C1838ED98914E9B7FFA foo.pdb\n",
+        );
+        assert!(matches!(supplier.locate_symbols(&m), SymbolResult::Ok(_)));
+
+        // Uppercase ids aren't looked for twice.
+        let m = SimpleModule::new("foo.pdb", "5A9832E5287241C1838ED98914E9B7FF1");
+        assert_eq!(symbol_store_paths(&m, "sym").len(), 1);
+    }
+
+    #[test]
+    fn test_in_memory_symbol_supplier() {
+        let mut supplier = InMemorySymbolSupplier::new();
+        let symbols = Arc::new(
+            SymbolFile::from_bytes(
+                b"MODULE Linux x86 abcd1234 foo
+FILE 1 foo.c
+FUNC 1000 30 10 some func
+1000 30 100 1
+",
+            )
+            .unwrap(),
+        );
+        supplier.insert("c:\\symbols\\foo.pdb", "abcd1234", symbols.clone());
+
+        let m1 = SimpleModule::new("foo.pdb", "abcd1234");
+        // The symbols are shared, not copied.
+        match supplier.locate_symbols(&m1) {
+            SymbolResult::Ok(found) => assert!(Arc::ptr_eq(&found, &symbols)),
+            other => panic!("unexpected result: {}", other),
+        }
+        let m2 = SimpleModule::new("foo.pdb", "ffff0000");
+        assert_eq!(supplier.locate_symbols(&m2), SymbolResult::NotFound);
+
+        // Symbols supplied for a different build of the module are rejected.
+        let mut mismatched = InMemorySymbolSupplier::new();
+        mismatched.insert("foo.pdb", "ffff0000", symbols.clone());
+        let res = mismatched.locate_symbols(&m2);
+        assert!(
+            matches!(res, SymbolResult::Corrupt(SymbolParseError { line: 1, .. })),
+            "{}",
+            format!("Rejected mismatched symbols, result: {:?}", res)
+        );
+
+        // Symbolizers can share a supplier.
+        let supplier = Arc::new(supplier);
+        for _ in 0..2 {
+            let symbolizer = Symbolizer::new(supplier.clone());
+            assert_eq!(
+                symbolizer
+                    .get_symbol_at_address("foo.pdb", "abcd1234", 0x1010)
+                    .unwrap(),
+                "some func"
+            );
+            assert_eq!(symbolizer.symbol_status(&m1), Some(SymbolStatus::Loaded));
+        }
+    }
+
     #[test]
     fn test_symbolizer() {
         let t = TempDir::new("symtest").unwrap();
@@ -734,6 +1195,7 @@ FUNC 1000 30 10 some func
         assert_eq!(f1.source_file.unwrap(), "foo.c");
         assert_eq!(f1.source_line.unwrap(), 100);
         assert_eq!(f1.source_line_base.unwrap(), 0x1000);
+        assert_eq!(symbolizer.symbol_status(&m1), Some(SymbolStatus::Loaded));
 
         assert_eq!(
             symbolizer
@@ -744,8 +1206,11 @@ FUNC 1000 30 10 some func
 
         let m2 = SimpleModule::new("bar.pdb", "ffff0000");
         let mut f2 = SimpleFrame::with_instruction(0x1010);
+        // Nothing has been looked up for this module yet.
+        assert_eq!(symbolizer.symbol_status(&m2), None);
         // No symbols present, should not find anything.
         symbolizer.fill_symbol(&m2, &mut f2);
+        assert_eq!(symbolizer.symbol_status(&m2), Some(SymbolStatus::Missing));
         assert!(f2.function.is_none());
         assert!(f2.function_base.is_none());
         assert!(f2.source_file.is_none());
@@ -768,5 +1233,134 @@ FUNC 1000 30 10 another func
         assert!(symbolizer
             .get_symbol_at_address("bar.pdb", "ffff0000", 0x1010)
             .is_none());
+        assert_eq!(symbolizer.symbol_parse_error(&m2), None);
+    }
+
+    const FOO_SYMBOLS: &[u8] = b"MODULE Linux x86 abcd1234 foo
+FILE 1 foo.c
+FUNC 1000 30 10 some func
+1000 30 100 1
+";
+
+    /// Symbolize an address in `foo.pdb` with the symbol store at `path`.
+    fn symbolize_foo(path: &Path) -> (Option<String>, Option<String>, Option<u32>) {
+        let symbolizer = Symbolizer::new(SimpleSymbolSupplier::new(vec![PathBuf::from(path)]));
+        let mut frame = SimpleFrame::with_instruction(0x1010);
+        symbolizer.fill_symbol(&SimpleModule::new("foo.pdb", "abcd1234"), &mut frame);
+        (frame.function, frame.source_file, frame.source_line)
+    }
+
+    #[cfg(feature = "flate2")]
+    #[test]
+    fn test_symbolizer_gzip_symbols() {
+        use flate2::write::GzEncoder;
+
+        let t = TempDir::new("symtest").unwrap();
+        let paths = mksubdirs(t.path(), &["plain", "gzip"]);
+        write_symbol_file(&paths[0].join("foo.pdb/abcd1234/foo.sym"), FOO_SYMBOLS);
+        let mut encoder = GzEncoder::new(vec![], flate2::Compression::default());
+        encoder.write_all(FOO_SYMBOLS).unwrap();
+        write_symbol_file(
+            &paths[1].join("foo.pdb/abcd1234/foo.sym.gz"),
+            &encoder.finish().unwrap(),
+        );
+
+        let symbols = symbolize_foo(&paths[1]);
+        assert_eq!(symbols.0.as_deref(), Some("some func"));
+        assert_eq!(symbols, symbolize_foo(&paths[0]));
+    }
+
+    #[cfg(feature = "zstd")]
+    #[test]
+    fn test_symbolizer_zstd_symbols() {
+        let t = TempDir::new("symtest").unwrap();
+        let paths = mksubdirs(t.path(), &["plain", "zstd"]);
+        write_symbol_file(&paths[0].join("foo.pdb/abcd1234/foo.sym"), FOO_SYMBOLS);
+        write_symbol_file(
+            &paths[1].join("foo.pdb/abcd1234/foo.sym.zst"),
+            &zstd::stream::encode_all(FOO_SYMBOLS, 0).unwrap(),
+        );
+
+        let symbols = symbolize_foo(&paths[1]);
+        assert_eq!(symbols.0.as_deref(), Some("some func"));
+        assert_eq!(symbols, symbolize_foo(&paths[0]));
+    }
+
+    #[test]
+    fn test_simple_symbol_supplier_bad_compressed_symbols() {
+        let t = TempDir::new("symtest").unwrap();
+        let supplier = SimpleSymbolSupplier::new(vec![PathBuf::from(t.path())]);
+        // Compression is detected by magic number, whatever the extension.
+        write_symbol_file(
+            &t.path().join("foo.pdb/abcd1234/foo.sym"),
+            b"\x1f\x8bnot really gzip",
+        );
+        let m = SimpleModule::new("foo.pdb", "abcd1234");
+        assert!(matches!(
+            supplier.locate_symbols(&m),
+            SymbolResult::LoadError(_)
+        ));
+    }
+
+    #[test]
+    fn test_symbolizer_corrupt_symbols() {
+        let t = TempDir::new("symtest").unwrap();
+        let path = t.path();
+        let symbolizer = Symbolizer::new(SimpleSymbolSupplier::new(vec![PathBuf::from(path)]));
+        let m1 = SimpleModule::new("foo.pdb", "abcd1234");
+        write_symbol_file(
+            &path.join("foo.pdb/abcd1234/foo.sym"),
+            b"MODULE Linux x86 abcd1234 foo
+FILE 1 foo.c
+FUNC 1000 30 10 some func
+this is not a line record
+",
+        );
+        let mut f1 = SimpleFrame::with_instruction(0x1010);
+        symbolizer.fill_symbol(&m1, &mut f1);
+        assert!(f1.function.is_none());
+        assert_eq!(symbolizer.symbol_status(&m1), Some(SymbolStatus::Corrupt));
+        let error = symbolizer.symbol_parse_error(&m1).unwrap();
+        assert_eq!(error.line, 4);
+        assert!(!error.reason.is_empty());
+    }
+
+    #[test]
+    fn test_symbolizer_symbol_origin() {
+        let t = TempDir::new("symtest").unwrap();
+        let paths = mksubdirs(t.path(), &["local", "cache"]);
+        write_good_symbol_file(&paths[0].join("foo.pdb/abcd1234/foo.sym"), "abcd1234");
+        write_good_symbol_file(&paths[1].join("bar.pdb/abcd1234/bar.sym"), "abcd1234");
+        // Nothing is downloaded, so no server is needed.
+        let supplier = HttpSymbolSupplier::new(vec![], paths[1].clone(), vec![paths[0].clone()]);
+        let symbolizer = Symbolizer::new(supplier);
+        let mut frame = SimpleFrame::with_instruction(0x1010);
+
+        let local = SimpleModule::new("foo.pdb", "abcd1234");
+        symbolizer.fill_symbol(&local, &mut frame);
+        assert_eq!(
+            symbolizer.symbol_origin(&local),
+            Some(SymbolOrigin::default())
+        );
+
+        let cached = SimpleModule::new("bar.pdb", "abcd1234");
+        symbolizer.fill_symbol(&cached, &mut frame);
+        assert_eq!(
+            symbolizer.symbol_origin(&cached),
+            Some(SymbolOrigin {
+                from_cache: true,
+                url: None,
+                fetch_ms: None,
+            })
+        );
+
+        // There's no origin for symbols that weren't loaded.
+        let missing = SimpleModule::new("baz.pdb", "abcd1234");
+        symbolizer.fill_symbol(&missing, &mut frame);
+        assert_eq!(
+            symbolizer.symbol_status(&missing),
+            Some(SymbolStatus::Missing)
+        );
+        assert_eq!(symbolizer.symbol_origin(&missing), None);
     }
 }
@@ -2,11 +2,16 @@
 // file at the top-level directory of this distribution.
 
 use failure::Error;
+use range_map::RangeMap;
 
+use std::cell::RefCell;
 use std::path::Path;
+use std::rc::Rc;
+use std::sync::atomic::{AtomicU64, Ordering as AtomicOrdering};
 
-use crate::sym_file::parser::{parse_symbol_bytes, parse_symbol_file};
+use crate::sym_file::parser::{parse_stack_cfi, parse_symbol_bytes, parse_symbol_file};
 use crate::{FrameSymbolizer, FrameWalker, Module};
+use minidump_common::traits::IntoRangeMapSafe;
 
 pub use crate::sym_file::types::*;
 
@@ -16,12 +21,15 @@ mod walker;
 
 impl SymbolFile {
     /// Parse a `SymbolFile` from `path`.
+    ///
+    /// gzip- and zstd-compressed files are decompressed first, if the `flate2`
+    /// and `zstd` features are enabled, respectively.
     pub fn from_file(path: &Path) -> Result<SymbolFile, Error> {
         parse_symbol_file(path)
     }
 
     /// Parse an in-memory `SymbolFile` from `bytes`.
-    pub fn from_bytes(bytes: &[u8]) -> Result<SymbolFile, Error> {
+    pub fn from_bytes(bytes: &[u8]) -> Result<SymbolFile, SymbolParseError> {
         parse_symbol_bytes(bytes)
     }
 
@@ -32,7 +40,17 @@ impl SymbolFile {
             return;
         }
         let addr = frame.get_instruction() - module.base_address();
-        if let Some(ref func) = self.functions.get(addr) {
+        // If the FUNC covering this address runs over the next FUNC, its size
+        // is probably bogus, and a PUBLIC that starts inside it is a tighter
+        // match. Otherwise such PUBLICs are just labels within the function.
+        let public = self.find_nearest_public(addr);
+        let func = self.functions.get(addr).filter(|func| {
+            !self
+                .oversized_functions
+                .contains(&(func.address, func.size))
+                || public.map_or(true, |public| public.address <= func.address)
+        });
+        if let Some(ref func) = func {
             frame.set_function(
                 &func.name,
                 func.address + module.base_address(),
@@ -44,7 +62,7 @@ impl SymbolFile {
                     frame.set_source_file(file, line.line, line.address + module.base_address());
                 })
             });
-        } else if let Some(ref public) = self.find_nearest_public(addr) {
+        } else if let Some(ref public) = public {
             // Settle for a PUBLIC.
             frame.set_function(
                 &public.name,
@@ -55,6 +73,25 @@ impl SymbolFile {
     }
 
     pub fn walk_frame(&self, module: &dyn Module, walker: &mut dyn FrameWalker) -> Option<()> {
+        self.walk_frame_impl(module, walker, None)
+    }
+
+    /// Like `walk_frame`, but looks up STACK CFI rules in `cache` before parsing them.
+    pub fn walk_frame_with_cache(
+        &self,
+        module: &dyn Module,
+        walker: &mut dyn FrameWalker,
+        cache: &CfiCache,
+    ) -> Option<()> {
+        self.walk_frame_impl(module, walker, Some(cache))
+    }
+
+    fn walk_frame_impl(
+        &self,
+        module: &dyn Module,
+        walker: &mut dyn FrameWalker,
+        cache: Option<&CfiCache>,
+    ) -> Option<()> {
         if walker.get_instruction() < module.base_address() {
             return None;
         }
@@ -72,7 +109,11 @@ impl SymbolFile {
 
         // If STACK WIN failed, try STACK CFI
         win_stack_result.or_else(|| {
-            if let Some(info) = self.cfi_stack_info.get(addr) {
+            let info = match cache {
+                Some(cache) => self.cfi_stack_info.get_cached(addr, cache),
+                None => self.cfi_stack_info.get(addr).map(Rc::new),
+            };
+            if let Some(info) = info {
                 // Don't use add_rules that come after this address
                 let mut count = 0;
                 let len = info.add_rules.len();
@@ -89,13 +130,123 @@ impl SymbolFile {
 
     /// Find the nearest `PublicSymbol` whose address is less than or equal to `addr`.
     pub fn find_nearest_public(&self, addr: u64) -> Option<&PublicSymbol> {
-        for p in self.publics.iter().rev() {
-            if p.address <= addr {
-                return Some(p);
+        // `publics` is sorted by address.
+        let idx = self.publics.partition_point(|p| p.address <= addr);
+        idx.checked_sub(1).map(|idx| &self.publics[idx])
+    }
+}
+
+impl CfiIndex {
+    /// Create an index of the CFI in `records`, whose address ranges are `spans`.
+    pub(crate) fn new(records: Vec<u8>, spans: RangeMap<u64, (usize, usize)>) -> CfiIndex {
+        static NEXT_ID: AtomicU64 = AtomicU64::new(0);
+        CfiIndex {
+            id: NEXT_ID.fetch_add(1, AtomicOrdering::Relaxed),
+            records,
+            spans,
+        }
+    }
+
+    /// Get the CFI for the address range containing `addr`.
+    ///
+    /// Its rules are parsed on every call, so callers that need them more than
+    /// once should hold on to the result.
+    pub fn get(&self, addr: u64) -> Option<StackInfoCfi> {
+        let &(start, end) = self.spans.get(addr)?;
+        parse_stack_cfi(&self.records[start..end])
+    }
+
+    /// Get the CFI for the address range containing `addr` from `cache`, parsing
+    /// and adding it if it isn't there.
+    pub fn get_cached(&self, addr: u64, cache: &CfiCache) -> Option<Rc<StackInfoCfi>> {
+        let &(start, end) = self.spans.get(addr)?;
+        let key = (self.id, start);
+        let mut entries = cache.entries.borrow_mut();
+        let entries = &mut *entries;
+        entries.clock += 1;
+        let now = entries.clock;
+        if let Some((info, last_used)) = entries.by_key.get_mut(&key) {
+            entries.by_use.remove(last_used);
+            entries.by_use.insert(now, key);
+            *last_used = now;
+            return Some(info.clone());
+        }
+        let info = Rc::new(parse_stack_cfi(&self.records[start..end])?);
+        if cache.capacity > 0 {
+            if entries.by_key.len() >= cache.capacity {
+                // Forget the least recently used range.
+                let (&oldest, _) = entries.by_use.iter().next().unwrap();
+                let old_key = entries.by_use.remove(&oldest).unwrap();
+                entries.by_key.remove(&old_key);
             }
+            entries.by_key.insert(key, (info.clone(), now));
+            entries.by_use.insert(now, key);
         }
+        Some(info)
+    }
+
+    /// The number of address ranges with CFI.
+    pub fn len(&self) -> usize {
+        self.spans.ranges_values().count()
+    }
+
+    /// Whether there's no CFI at all.
+    pub fn is_empty(&self) -> bool {
+        self.len() == 0
+    }
+
+    /// The CFI for every address range, parsed into a `RangeMap`.
+    ///
+    /// That's how `SymbolFile::cfi_stack_info` held it before it became a
+    /// `CfiIndex`, for code that still needs it that way. This parses all of
+    /// the rules, so it's as slow as parsing them up front was.
+    pub fn to_range_map(&self) -> RangeMap<u64, StackInfoCfi> {
+        self.iter()
+            .map(|info| (info.memory_range(), info))
+            .into_rangemap_safe()
+    }
 
-        None
+    /// Iterate over the CFI for every address range, in order of address.
+    ///
+    /// This parses all of the rules, which `get` avoids for large symbol files.
+    pub fn iter(&self) -> impl Iterator<Item = StackInfoCfi> + '_ {
+        self.spans
+            .ranges_values()
+            .filter_map(move |&(_, (start, end))| parse_stack_cfi(&self.records[start..end]))
+    }
+}
+
+impl CfiCache {
+    /// The number of address ranges a cache holds by default.
+    pub const DEFAULT_CAPACITY: usize = 4096;
+
+    /// Create an empty cache holding up to `DEFAULT_CAPACITY` address ranges.
+    pub fn new() -> CfiCache {
+        CfiCache::with_capacity(CfiCache::DEFAULT_CAPACITY)
+    }
+
+    /// Create an empty cache holding up to `capacity` address ranges.
+    pub fn with_capacity(capacity: usize) -> CfiCache {
+        CfiCache {
+            capacity,
+            entries: RefCell::new(CfiCacheEntries::default()),
+        }
+    }
+
+    /// The number of address ranges in the cache.
+    pub fn len(&self) -> usize {
+        self.entries.borrow().by_key.len()
+    }
+
+    /// Whether the cache is empty.
+    pub fn is_empty(&self) -> bool {
+        self.len() == 0
+    }
+}
+
+impl Default for CfiCache {
+    fn default() -> CfiCache {
+        CfiCache::new()
     }
 }
 
@@ -175,6 +326,71 @@ mod test {
         assert_eq!(sym.functions.get(0x1001).unwrap().name, "another func");
     }
 
+    #[test]
+    fn test_fill_symbol_overlapping_funcs() {
+        let sym = SymbolFile::from_bytes(
+            b"MODULE Linux x86 ffff0000 bar
+FILE 53 bar.c
+FUNC 1000 1000 0 too big func
+1000 10 7 53
+FUNC 1100 30 0 inner func
+1100 30 12 53
+PUBLIC 1800 0 some public
+",
+        )
+        .unwrap();
+        let module = crate::SimpleModule {
+            base_address: Some(0x40000),
+            ..crate::SimpleModule::default()
+        };
+
+        let mut frame = crate::SimpleFrame::with_instruction(0x41004);
+        sym.fill_symbol(&module, &mut frame);
+        assert_eq!(frame.function.unwrap(), "too big func");
+        assert_eq!(frame.source_line.unwrap(), 7);
+
+        // Inside a function that the oversized FUNC runs over.
+        let mut frame = crate::SimpleFrame::with_instruction(0x41110);
+        sym.fill_symbol(&module, &mut frame);
+        assert_eq!(frame.function.unwrap(), "inner func");
+        assert_eq!(frame.function_base.unwrap(), 0x41100);
+        assert_eq!(frame.source_line.unwrap(), 12);
+
+        // Past a PUBLIC that the oversized FUNC runs over.
+        let mut frame = crate::SimpleFrame::with_instruction(0x41810);
+        sym.fill_symbol(&module, &mut frame);
+        assert_eq!(frame.function.unwrap(), "some public");
+        assert_eq!(frame.function_base.unwrap(), 0x41800);
+        assert!(frame.source_file.is_none());
+    }
+
+    #[test]
+    fn test_fill_symbol_public_inside_func() {
+        // PUBLIC labels and aliases inside a FUNC that doesn't run over any
+        // other FUNC don't replace it.
+        let sym = SymbolFile::from_bytes(
+            b"MODULE Linux x86 ffff0000 bar
+FILE 53 bar.c
+FUNC 1000 100 0 some func
+1000 100 7 53
+PUBLIC 1000 0 some func
+PUBLIC 1040 0 some label
+FUNC 1100 30 0 next func
+1100 30 12 53
+",
+        )
+        .unwrap();
+        let module = crate::SimpleModule {
+            base_address: Some(0x40000),
+            ..crate::SimpleModule::default()
+        };
+        let mut frame = crate::SimpleFrame::with_instruction(0x41050);
+        sym.fill_symbol(&module, &mut frame);
+        assert_eq!(frame.function.unwrap(), "some func");
+        assert_eq!(frame.function_base.unwrap(), 0x41000);
+        assert_eq!(frame.source_line.unwrap(), 7);
+    }
+
     #[test]
     fn test_symbolfile_from_bytes_with_lf() {
         test_symbolfile_from_bytes(
@@ -1,13 +1,14 @@
 // Copyright 2015 Ted Mielczarek. See the COPYRIGHT
 // file at the top-level directory of this distribution.
 
-use failure::{format_err, Error};
+use failure::Error;
+use log::warn;
 use nom::IResult::*;
 use nom::*;
-use range_map::Range;
+use range_map::{Range, RangeMap};
 
 use std::borrow::Cow;
-use std::collections::HashMap;
+use std::collections::{HashMap, HashSet};
 use std::fs::File;
 use std::io::Read;
 use std::path::Path;
@@ -16,6 +17,7 @@ use std::str::FromStr;
 
 use minidump_common::traits::IntoRangeMapSafe;
 
+use crate::compression;
 use crate::sym_file::types::*;
 
 enum Line<'a> {
@@ -24,7 +26,9 @@ enum Line<'a> {
     Public(PublicSymbol),
     Function(Function),
     StackWin(WinFrameType),
-    StackCfi(StackInfoCfi),
+    /// The address and size of a `STACK CFI INIT` record, and its text along
+    /// with the `STACK CFI` records that follow it.
+    StackCfi(u64, u32, &'a [u8]),
 }
 
 // Nom's `eol` doesn't use complete! so it will return Incomplete.
@@ -40,8 +44,8 @@ named!(hex_str_u64<&[u8], u64>,
 // Match a decimal string, parse it to a u32.
 named!(decimal_u32<&[u8], u32>, map_res!(map_res!(digit, str::from_utf8), FromStr::from_str));
 
-// Matches a MODULE record.
-named!(module_line<&[u8], ()>,
+// Matches a MODULE record, returning its debug id.
+named!(module_line<&[u8], &str>,
   chain!(
     tag!("MODULE") ~
           space     ~
@@ -52,12 +56,12 @@ named!(module_line<&[u8], ()>,
     take_until!(" ") ~
           space ~
           // debug id
-    hex_digit ~
+    debug_id: map_res!(hex_digit, str::from_utf8) ~
           space ~
           // filename
     not_line_ending ~
     my_eol ,
-    || {}
+    || { debug_id }
 ));
 
 // Matches an INFO record.
@@ -268,6 +272,56 @@ named!(stack_cfi_lines<&[u8], StackInfoCfi>,
       }
 ));
 
+// Matches a STACK CFI record without parsing its rules.
+named!(skip_stack_cfi<&[u8], ()>,
+  chain!(
+    tag!("STACK CFI") ~
+    space ~
+    hex_str_u64 ~
+    space ~
+    not_line_ending ~
+    my_eol ,
+      || {}
+));
+
+// Matches a STACK CFI INIT record followed by zero or more STACK CFI records,
+// only parsing the address range they cover.
+named!(stack_cfi_range<&[u8], (u64, u32)>,
+  chain!(
+    tag!("STACK CFI INIT") ~
+    space ~
+    address: hex_str_u64 ~
+    space ~
+    size: hex_u32 ~
+    space ~
+    not_line_ending ~
+    my_eol ~
+    many0!(skip_stack_cfi) ,
+      || { (address, size) }
+));
+
+// Like `stack_cfi_range`, but also return the text of the records, so their
+// rules can be parsed with `stack_cfi_lines` when they're needed.
+fn stack_cfi_block(input: &[u8]) -> IResult<&[u8], (u64, u32, &[u8])> {
+    match stack_cfi_range(input) {
+        Done(rest, (address, size)) => {
+            let text = &input[..input.len() - rest.len()];
+            Done(rest, (address, size, text))
+        }
+        Error(e) => Error(e),
+        Incomplete(needed) => Incomplete(needed),
+    }
+}
+
+/// Parse the `STACK CFI INIT` record at the start of `bytes` and the `STACK CFI`
+/// records that follow it.
+pub fn parse_stack_cfi(bytes: &[u8]) -> Option<StackInfoCfi> {
+    match stack_cfi_lines(bytes) {
+        Done(_, info) => Some(info),
+        _ => None,
+    }
+}
+
 // Parse any of the line data that can occur in the body of a symbol file.
 named!(line<&[u8], Line>,
   alt!(
@@ -276,15 +330,93 @@ named!(line<&[u8], Line>,
     public_line => { |p| Line::Public(p) } |
     func_lines => { |f| Line::Function(f) } |
     stack_win_line => { |s| Line::StackWin(s) } |
-    stack_cfi_lines => { |s| Line::StackCfi(s) }
+    stack_cfi_block => { |(a, s, t)| Line::StackCfi(a, s, t) }
 ));
 
+// Build a map of functions by address, resolving overlapping FUNC records so
+// that each address maps to the smallest function containing it. Also return
+// the address and size of each FUNC that runs over the next one.
+//
+// Symbol files occasionally contain a FUNC whose size is bogus and runs over
+// the functions that follow it. Preferring the tightest match keeps frames in
+// those later functions from being attributed to the oversized one.
+fn functions_by_addr(mut funcs: Vec<Function>) -> (RangeMap<u64, Function>, HashSet<(u64, u32)>) {
+    // FUNCs past the end of the address space are bogus.
+    funcs.retain(|f| f.size != 0 && f.address.checked_add(f.size as u64).is_some());
+    funcs.sort_by_key(|f| (f.address, f.size));
+
+    let end = |f: &Function| f.address + f.size as u64;
+    let oversized = funcs
+        .iter()
+        .filter(|f| {
+            let next = funcs.partition_point(|next| next.address <= f.address);
+            matches!(funcs.get(next), Some(next) if end(f) > next.address)
+        })
+        .map(|f| (f.address, f.size))
+        .collect();
+    let mut bounds: Vec<u64> = funcs.iter().flat_map(|f| vec![f.address, end(f)]).collect();
+    bounds.sort_unstable();
+    bounds.dedup();
+
+    // Sweep over every range between two function boundaries, keeping track
+    // of which functions cover it.
+    let mut segments: Vec<(Range<u64>, usize)> = vec![];
+    let mut active: Vec<usize> = vec![];
+    let mut next = 0;
+    for window in bounds.windows(2) {
+        let (start, stop) = (window[0], window[1]);
+        active.retain(|&i| end(&funcs[i]) > start);
+        while next < funcs.len() && funcs[next].address <= start {
+            if let Some(&outer) = active.last() {
+                warn!(
+                    "FUNC {} at {:#x} overlaps FUNC {} at {:#x}",
+                    funcs[next].name, funcs[next].address, funcs[outer].name, funcs[outer].address
+                );
+            }
+            active.push(next);
+            next += 1;
+        }
+        // `min_by_key` returns the first minimum, so exact duplicates resolve
+        // to whichever record came first in the file.
+        if let Some(&tightest) = active.iter().min_by_key(|&&i| funcs[i].size) {
+            match segments.last_mut() {
+                Some((range, idx)) if *idx == tightest && range.end + 1 == start => {
+                    range.end = stop - 1;
+                }
+                _ => segments.push((Range::new(start, stop - 1), tightest)),
+            }
+        }
+    }
+
+    // Functions split around a nested one show up in several segments, so
+    // only clone them when they're needed more than once.
+    let mut uses = vec![0usize; funcs.len()];
+    for &(_, i) in segments.iter() {
+        uses[i] += 1;
+    }
+    let mut funcs: Vec<Option<Function>> = funcs.into_iter().map(Some).collect();
+    let entries = segments
+        .into_iter()
+        .filter_map(|(range, i)| {
+            uses[i] -= 1;
+            let func = if uses[i] == 0 {
+                funcs[i].take()
+            } else {
+                funcs[i].clone()
+            };
+            func.map(|f| (range, f))
+        })
+        .collect();
+    (RangeMap::from_sorted_vec(entries), oversized)
+}
+
 // Return a `SymbolFile` given a vec of `Line` data.
-fn symbol_file_from_lines(lines: Vec<Line<'_>>) -> SymbolFile {
+fn symbol_file_from_lines(debug_id: Option<&str>, lines: Vec<Line<'_>>) -> SymbolFile {
     let mut files = HashMap::new();
     let mut publics = vec![];
     let mut funcs = vec![];
-    let mut stack_cfi = vec![];
+    let mut cfi_records = vec![];
+    let mut cfi_spans = vec![];
     let mut stack_win_framedata: Vec<StackInfoWin> = vec![];
     let mut stack_win_fpo: Vec<StackInfoWin> = vec![];
     for line in lines {
@@ -321,23 +453,30 @@ fn symbol_file_from_lines(lines: Vec<Line<'_>>) -> SymbolFile {
                     _ => {}
                 }
             }
-            Line::StackCfi(s) => {
-                stack_cfi.push(s);
+            Line::StackCfi(address, size, text) => {
+                // Records with no size don't cover any addresses, and ones
+                // past the end of the address space are bogus.
+                let end = (size as u64)
+                    .checked_sub(1)
+                    .and_then(|size| address.checked_add(size));
+                if let Some(end) = end {
+                    let range = Range::new(address, end);
+                    let start = cfi_records.len();
+                    cfi_records.extend_from_slice(text);
+                    cfi_spans.push((range, (start, cfi_records.len())));
+                }
             }
         }
     }
     publics.sort();
+    let (functions, oversized_functions) = functions_by_addr(funcs);
     SymbolFile {
+        debug_id: debug_id.map(String::from),
         files,
         publics,
-        functions: funcs
-            .into_iter()
-            .map(|f| (f.memory_range(), f))
-            .into_rangemap_safe(),
-        cfi_stack_info: stack_cfi
-            .into_iter()
-            .map(|s| (s.memory_range(), s))
-            .into_rangemap_safe(),
+        functions,
+        oversized_functions,
+        cfi_stack_info: CfiIndex::new(cfi_records, cfi_spans.into_rangemap_safe()),
         win_stack_framedata_info: stack_win_framedata
             .into_iter()
             .map(|s| (s.memory_range(), s))
@@ -352,13 +491,19 @@ fn symbol_file_from_lines(lines: Vec<Line<'_>>) -> SymbolFile {
 // Matches an entire symbol file.
 named!(symbol_file<&[u8], SymbolFile>,
   chain!(
-    module_line? ~
+    debug_id: module_line? ~
     lines: many0!(line) ,
-    || { symbol_file_from_lines(lines) })
+    || { symbol_file_from_lines(debug_id, lines) })
 );
 
+/// The line number, starting from 1, of the start of `rest`, the unparsed tail of `bytes`.
+fn line_number(bytes: &[u8], rest: &[u8]) -> usize {
+    let parsed = &bytes[..bytes.len().saturating_sub(rest.len())];
+    parsed.iter().filter(|b| **b == b'\n').count() + 1
+}
+
 /// Parse a `SymbolFile` from `bytes`.
-pub fn parse_symbol_bytes(bytes: &[u8]) -> Result<SymbolFile, Error> {
+pub fn parse_symbol_bytes(bytes: &[u8]) -> Result<SymbolFile, SymbolParseError> {
     match symbol_file(&bytes) {
         Done(rest, symfile) => {
             if rest == b"" {
@@ -366,41 +511,56 @@ pub fn parse_symbol_bytes(bytes: &[u8]) -> Result<SymbolFile, Error> {
             } else {
                 // Junk left over, or maybe didn't parse anything.
                 let next_line = rest
-                    .split(|b| *b == b'\r')
+                    .split(|b| *b == b'\r' || *b == b'\n')
                     .next()
                     .map(|bytes| String::from_utf8_lossy(bytes))
                     .unwrap_or(Cow::Borrowed(""));
-                Err(format_err!(
-                    "Failed to parse file, next line was: `{}`",
-                    next_line
-                ))
+                Err(SymbolParseError {
+                    line: line_number(bytes, rest),
+                    reason: format!("unexpected line `{}`", next_line),
+                })
             }
         }
-        Error(e) => Err(format_err!("Failed to parse file: {}", e)),
-        Incomplete(_) => Err(format_err!("Failed to parse file: incomplete data")),
+        // Everything after the optional MODULE line is matched by `many0!`,
+        // which stops instead of failing, so an outright error is at the start.
+        Error(e) => Err(SymbolParseError {
+            line: 1,
+            reason: format!("{}", e),
+        }),
+        Incomplete(_) => Err(SymbolParseError {
+            line: line_number(bytes, b""),
+            reason: String::from("incomplete data"),
+        }),
     }
 }
 
-/// Parse a `SymbolFile` from `path`.
+/// Parse a `SymbolFile` from `path`, decompressing it first if it's compressed.
 pub fn parse_symbol_file(path: &Path) -> Result<SymbolFile, Error> {
     let mut f = File::open(path)?;
     let mut bytes = vec![];
     f.read_to_end(&mut bytes)?;
-    parse_symbol_bytes(&bytes)
+    let bytes = compression::decompress(bytes)?;
+    Ok(parse_symbol_bytes(&bytes)?)
 }
 
 #[test]
 fn test_module_line() {
     let line = b"MODULE Linux x86 D3096ED481217FD4C16B29CD9BC208BA0 firefox-bin\n";
     let rest = &b""[..];
-    assert_eq!(module_line(line), Done(rest, ()));
+    assert_eq!(
+        module_line(line),
+        Done(rest, "D3096ED481217FD4C16B29CD9BC208BA0")
+    );
 }
 
 #[test]
 fn test_module_line_filename_spaces() {
     let line = b"MODULE Windows x86_64 D3096ED481217FD4C16B29CD9BC208BA0 firefox x y z\n";
     let rest = &b""[..];
-    assert_eq!(module_line(line), Done(rest, ()));
+    assert_eq!(
+        module_line(line),
+        Done(rest, "D3096ED481217FD4C16B29CD9BC208BA0")
+    );
 }
 
 /// Sometimes dump_syms on Windows does weird things and produces multiple carriage returns
@@ -409,7 +569,10 @@ fn test_module_line_filename_spaces() {
 fn test_module_line_crcrlf() {
     let line = b"MODULE Windows x86_64 D3096ED481217FD4C16B29CD9BC208BA0 firefox\r\r\n";
     let rest = &b""[..];
-    assert_eq!(module_line(line), Done(rest, ()));
+    assert_eq!(
+        module_line(line),
+        Done(rest, "D3096ED481217FD4C16B29CD9BC208BA0")
+    );
 }
 
 #[test]
@@ -478,7 +641,6 @@ fn test_public_with_m() {
 
 #[test]
 fn test_func_lines_no_lines() {
-    use range_map::RangeMap;
     let line = b"FUNC c184 30 0 nsQueryInterfaceWithError::operator()(nsID const&, void**) const\n";
     let rest = &b""[..];
     assert_eq!(
@@ -712,6 +874,10 @@ STACK CFI deadbeef more rules
 STACK CFI INIT f00f f0 more init rules
 "[..];
     let sym = parse_symbol_bytes(bytes).unwrap();
+    assert_eq!(
+        sym.debug_id.as_ref().unwrap(),
+        "D3096ED481217FD4C16B29CD9BC208BA0"
+    );
     assert_eq!(sym.files.len(), 2);
     assert_eq!(sym.files.get(&0).unwrap(), "foo.c");
     assert_eq!(sym.files.get(&100).unwrap(), "bar.c");
@@ -831,12 +997,8 @@ STACK CFI INIT f00f f0 more init rules
             WinStackThing::AllocatesBasePointer(true)
         );
     }
-    assert_eq!(sym.cfi_stack_info.ranges_values().count(), 2);
-    let cs = sym
-        .cfi_stack_info
-        .ranges_values()
-        .map(|&(_, ref s)| s.clone())
-        .collect::<Vec<_>>();
+    assert_eq!(sym.cfi_stack_info.len(), 2);
+    let cs = sym.cfi_stack_info.iter().collect::<Vec<_>>();
     assert_eq!(
         cs[0],
         StackInfoCfi {
@@ -870,6 +1032,122 @@ STACK CFI INIT f00f f0 more init rules
     );
 }
 
+#[test]
+fn test_cfi_index() {
+    let bytes = b"MODULE Linux x86 D3096ED481217FD4C16B29CD9BC208BA0 firefox-bin
+FUNC 1000 30 10 some func
+STACK CFI INIT 1000 30 .cfa: $esp 4 + .ra: .cfa 4 - ^
+STACK CFI 1001 .cfa: $esp 8 +\r
+STACK CFI 1003 .cfa: $ebp 8 +
+STACK CFI INIT 2000 0 empty rules
+STACK CFI INIT 3000 10 .cfa: $esp 4 +
+";
+    let sym = parse_symbol_bytes(bytes).unwrap();
+    // The empty range isn't indexed.
+    assert_eq!(sym.cfi_stack_info.len(), 2);
+    assert!(sym.cfi_stack_info.get(0x2000).is_none());
+    assert!(sym.cfi_stack_info.get(0x1030).is_none());
+
+    let info = sym.cfi_stack_info.get(0x1010).unwrap();
+    assert_eq!(info.init.address, 0x1000);
+    assert_eq!(info.init.rules, ".cfa: $esp 4 + .ra: .cfa 4 - ^");
+    assert_eq!(info.size, 0x30);
+    assert_eq!(
+        info.add_rules,
+        vec![
+            CfiRules {
+                address: 0x1001,
+                rules: ".cfa: $esp 8 +".to_string(),
+            },
+            CfiRules {
+                address: 0x1003,
+                rules: ".cfa: $ebp 8 +".to_string(),
+            },
+        ]
+    );
+    let info = sym.cfi_stack_info.get(0x300f).unwrap();
+    assert_eq!(info.init.address, 0x3000);
+    assert!(info.add_rules.is_empty());
+
+    // Only the CFI records are kept.
+    let records = &sym.cfi_stack_info.records;
+    assert!(records.starts_with(b"STACK CFI INIT 1000 30"));
+    assert!(!records.windows(4).any(|w| w == b"FUNC"));
+
+    // Ranges past the end of the address space are left out.
+    let bytes = b"MODULE Linux x86 D3096ED481217FD4C16B29CD9BC208BA0 firefox-bin
+STACK CFI INIT ffffffffffffff00 1000 .cfa: $esp 4 +
+STACK CFI INIT fffffffffffffff0 10 .cfa: $esp 4 +
+";
+    let sym = parse_symbol_bytes(bytes).unwrap();
+    assert_eq!(sym.cfi_stack_info.len(), 1);
+    assert!(sym.cfi_stack_info.get(0xffffffffffffff00).is_none());
+    assert_eq!(
+        sym.cfi_stack_info.get(u64::MAX).unwrap().init.address,
+        0xfffffffffffffff0
+    );
+
+    // The old representation is still available.
+    let range_map = sym.cfi_stack_info.to_range_map();
+    assert_eq!(range_map.ranges_values().count(), 1);
+}
+
+#[test]
+fn test_cfi_index_cached() {
+    let bytes = b"MODULE Linux x86 D3096ED481217FD4C16B29CD9BC208BA0 firefox-bin
+STACK CFI INIT 1000 30 .cfa: $esp 4 + .ra: .cfa 4 - ^
+STACK CFI 1001 .cfa: $esp 8 +
+STACK CFI INIT 3000 10 .cfa: $esp 4 +
+";
+    let sym = parse_symbol_bytes(bytes).unwrap();
+    let cache = CfiCache::with_capacity(2);
+    assert!(sym.cfi_stack_info.get_cached(0x2000, &cache).is_none());
+    assert!(cache.is_empty());
+
+    // Addresses in the same range share an entry.
+    let first = sym.cfi_stack_info.get_cached(0x1001, &cache).unwrap();
+    let second = sym.cfi_stack_info.get_cached(0x1020, &cache).unwrap();
+    assert!(std::rc::Rc::ptr_eq(&first, &second));
+    assert_eq!(*first, sym.cfi_stack_info.get(0x1001).unwrap());
+    assert_eq!(cache.len(), 1);
+
+    // Another symbol file's range at the same place in its records doesn't.
+    let other_bytes = b"MODULE Linux x86 D3096ED481217FD4C16B29CD9BC208BA0 firefox-bin
+STACK CFI INIT 1000 30 .cfa: $esp 12 + .ra: .cfa 4 - ^
+";
+    let other_sym = parse_symbol_bytes(other_bytes).unwrap();
+    let other = other_sym.cfi_stack_info.get_cached(0x1001, &cache).unwrap();
+    assert!(!std::rc::Rc::ptr_eq(&first, &other));
+    assert_eq!(*other, other_sym.cfi_stack_info.get(0x1001).unwrap());
+    assert_eq!(cache.len(), 2);
+
+    // A full cache forgets the least recently used range.
+    sym.cfi_stack_info.get_cached(0x1001, &cache).unwrap();
+    sym.cfi_stack_info.get_cached(0x3000, &cache).unwrap();
+    assert_eq!(cache.len(), 2);
+    let again = sym.cfi_stack_info.get_cached(0x1001, &cache).unwrap();
+    assert!(std::rc::Rc::ptr_eq(&first, &again));
+    let other_again = other_sym.cfi_stack_info.get_cached(0x1001, &cache).unwrap();
+    assert!(!std::rc::Rc::ptr_eq(&other, &other_again));
+
+    // A cache with no capacity never holds anything.
+    let cache = CfiCache::with_capacity(0);
+    assert!(sym.cfi_stack_info.get_cached(0x1001, &cache).is_some());
+    assert!(cache.is_empty());
+}
+
+#[test]
+fn test_func_past_end_of_address_space() {
+    let bytes = b"MODULE Linux x86 D3096ED481217FD4C16B29CD9BC208BA0 firefox-bin
+FUNC ffffffffffffff00 1000 0 bogus func
+FUNC fffffffffffffff0 f 0 last func
+";
+    let sym = parse_symbol_bytes(bytes).unwrap();
+    assert_eq!(sym.functions.ranges_values().count(), 1);
+    assert_eq!(sym.functions.get(u64::MAX - 1).unwrap().name, "last func");
+    assert!(sym.functions.get(0xffffffffffffff00).is_none());
+}
+
 /// Test that parsing a symbol file with overlapping FUNC/line data works.
 #[test]
 fn test_parse_with_overlap() {
@@ -903,7 +1181,17 @@ FUNC 1001 10 10 some func overlap contained
         assert_eq!(p.parameter_size, 0x3);
         assert_eq!(p.name, "func 2".to_string());
     }
-    assert_eq!(sym.functions.ranges_values().count(), 1);
+    // Overlapping functions resolve to the tightest one covering each address.
+    assert_eq!(sym.functions.ranges_values().count(), 4);
+    assert_eq!(
+        sym.functions.get(0x1005).unwrap().name,
+        "some func overlap contained"
+    );
+    assert_eq!(sym.functions.get(0x1011).unwrap().name, "some func");
+    assert_eq!(
+        sym.functions.get(0x1030).unwrap().name,
+        "some func overlap end"
+    );
     let funcs = sym
         .functions
         .ranges_values()
@@ -1003,3 +1291,17 @@ FILE"[..]
         "Should fail to parse empty file"
     );
 }
+
+#[test]
+fn test_parse_symbol_bytes_error_line() {
+    let err = parse_symbol_bytes(
+        &b"MODULE Linux x86 abcd1234 foo
+FILE 0 foo.c
+this is some junk
+FILE 1 bar.c
+"[..],
+    )
+    .unwrap_err();
+    assert_eq!(err.line, 3);
+    assert_eq!(err.reason, "unexpected line `this is some junk`");
+}
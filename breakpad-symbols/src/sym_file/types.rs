@@ -1,12 +1,15 @@
 // Copyright 2015 Ted Mielczarek. See the COPYRIGHT
 // file at the top-level directory of this distribution.
 
+use failure::Fail;
 use range_map::{Range, RangeMap};
+use std::cell::RefCell;
 use std::cmp::Ordering;
-use std::collections::HashMap;
+use std::collections::{BTreeMap, HashMap, HashSet};
+use std::rc::Rc;
 
 /// A publicly visible linker symbol.
-#[derive(Debug, Eq, PartialEq)]
+#[derive(Clone, Debug, Eq, PartialEq)]
 pub struct PublicSymbol {
     /// The symbol's address relative to the module's load address.
     pub address: u64,
@@ -98,10 +101,61 @@ pub struct StackInfoCfi {
 
 impl StackInfoCfi {
     pub fn memory_range(&self) -> Range<u64> {
-        Range::new(self.init.address, self.init.address + self.size as u64 - 1)
+        Range::new(
+            self.init.address,
+            self.init.address + (self.size as u64 - 1),
+        )
     }
 }
 
+/// DWARF CFI unwind information, indexed by address.
+///
+/// Symbol files for large modules have a `STACK CFI` record for nearly every
+/// instruction that changes the stack, and unwinding only ever needs a few of
+/// them. So only the address range of each `STACK CFI INIT` record is parsed
+/// up front, and the rules for a range are parsed when it's looked up.
+#[derive(Clone, Debug)]
+pub struct CfiIndex {
+    /// Identifies this index, and its clones, in a `CfiCache`.
+    pub(crate) id: u64,
+    /// The text of the `STACK CFI INIT` records and the `STACK CFI` records that
+    /// follow each of them.
+    pub(crate) records: Vec<u8>,
+    /// Map from address range to the span of `records` describing it.
+    pub(crate) spans: RangeMap<u64, (usize, usize)>,
+}
+
+impl PartialEq for CfiIndex {
+    fn eq(&self, other: &CfiIndex) -> bool {
+        self.records == other.records && self.spans == other.spans
+    }
+}
+
+/// Parsed DWARF CFI, kept so that unwinding through the same function again
+/// doesn't parse its rules again.
+///
+/// Entries are keyed by the `CfiIndex` they came from and the address range
+/// within it, so one cache can be shared by all of the modules in a process,
+/// and by symbol files for the same module from different providers.
+/// The cache holds at most `capacity` address ranges, and forgets the least
+/// recently used one when it's full; a capacity of 0 disables caching.
+#[derive(Debug)]
+pub struct CfiCache {
+    pub(crate) capacity: usize,
+    pub(crate) entries: RefCell<CfiCacheEntries>,
+}
+
+/// The contents of a `CfiCache`.
+#[derive(Debug, Default)]
+pub(crate) struct CfiCacheEntries {
+    /// Map from index and span start to the parsed CFI and when it was last used.
+    pub(crate) by_key: HashMap<(u64, usize), (Rc<StackInfoCfi>, u64)>,
+    /// Map from when each entry was last used to its key, oldest first.
+    pub(crate) by_use: BTreeMap<u64, (u64, usize)>,
+    /// The number of lookups so far, which orders the entries' uses.
+    pub(crate) clock: u64,
+}
+
 /// Specific details about whether the frame uses a base pointer or has a program string to
 /// evaluate.
 #[derive(Clone, Debug, PartialEq, Eq)]
@@ -150,8 +204,10 @@ impl StackInfoWin {
 }
 
 /// A parsed .sym file containing debug symbols.
-#[derive(Debug, PartialEq)]
+#[derive(Clone, Debug, PartialEq)]
 pub struct SymbolFile {
+    /// The debug identifier from the file's `MODULE` record, if it has one.
+    pub debug_id: Option<String>,
     /// The set of source files involved in compilation.
     pub files: HashMap<u32, String>,
     /// Publicly visible symbols.
@@ -159,9 +215,25 @@ pub struct SymbolFile {
     /// Functions.
     pub functions: RangeMap<u64, Function>,
     /// DWARF CFI unwind information.
-    pub cfi_stack_info: RangeMap<u64, StackInfoCfi>,
+    ///
+    /// This used to be a `RangeMap<u64, StackInfoCfi>` with every rule parsed;
+    /// use `CfiIndex::to_range_map` for that.
+    pub cfi_stack_info: CfiIndex,
     /// Windows unwind information (frame data).
     pub win_stack_framedata_info: RangeMap<u64, StackInfoWin>,
     /// Windows unwind information (FPO data).
     pub win_stack_fpo_info: RangeMap<u64, StackInfoWin>,
+    /// The address and size of each FUNC that runs over the next FUNC, whose
+    /// size is probably bogus.
+    pub(crate) oversized_functions: HashSet<(u64, u32)>,
+}
+
+/// An error encountered while parsing a .sym file.
+#[derive(Clone, Debug, Fail, PartialEq)]
+#[fail(display = "Failed to parse symbol file at line {}: {}", line, reason)]
+pub struct SymbolParseError {
+    /// The line the parser stopped at, starting from 1.
+    pub line: usize,
+    /// Why the parser stopped there.
+    pub reason: String,
 }
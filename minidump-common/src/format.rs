@@ -20,6 +20,8 @@ use smart_default::SmartDefault;
 
 /// An offset from the start of the minidump file.
 pub type RVA = u32;
+/// A 64-bit offset from the start of the minidump file.
+pub type RVA64 = u64;
 
 /// The 4-byte magic number at the start of a minidump file.
 ///
@@ -157,6 +159,9 @@ pub enum MINIDUMP_STREAM_TYPE {
     Memory64ListStream = 9,
     CommentStreamA = 10,
     CommentStreamW = 11,
+    /// A list of handles held by the process
+    ///
+    /// See [`MINIDUMP_HANDLE_DATA_STREAM`](struct.MINIDUMP_HANDLE_DATA_STREAM.html).
     HandleDataStream = 12,
     FunctionTable = 13,
     /// The list of executable modules from the process that were unloaded by the time of the crash
@@ -180,6 +185,9 @@ pub enum MINIDUMP_STREAM_TYPE {
     /// See ['MINIDUMP_MEMORY_INFO_LIST'][struct.MINIDUMP_MEMORY_INFO_LIST.html).
     MemoryInfoListStream = 16,
     ThreadInfoListStream = 17,
+    /// Handle operations recorded by Application Verifier's handle tracing
+    ///
+    /// See [`MINIDUMP_HANDLE_OPERATION_LIST`](struct.MINIDUMP_HANDLE_OPERATION_LIST.html).
     HandleOperationListStream = 18,
     TokenStream = 19,
     JavaScriptDataStream = 20,
@@ -302,6 +310,158 @@ pub struct MINIDUMP_UNLOADED_MODULE {
     pub module_name_rva: RVA,
 }
 
+/// The header of the handle data stream.
+///
+/// This struct matches the [Microsoft struct][msdn] of the same name.
+///
+/// The header is followed by `number_of_descriptors` entries of either
+/// [`MINIDUMP_HANDLE_DESCRIPTOR`](struct.MINIDUMP_HANDLE_DESCRIPTOR.html) or
+/// [`MINIDUMP_HANDLE_DESCRIPTOR_2`](struct.MINIDUMP_HANDLE_DESCRIPTOR_2.html), as
+/// indicated by `size_of_descriptor`.
+///
+/// [msdn]: https://docs.microsoft.com/en-us/windows/win32/api/minidumpapiset/ns-minidumpapiset-minidump_handle_data_stream
+#[derive(Debug, Clone, Default, Pread, SizeWith)]
+pub struct MINIDUMP_HANDLE_DATA_STREAM {
+    /// The size of this header, in bytes.
+    pub size_of_header: u32,
+    /// The size of each handle descriptor, in bytes.
+    pub size_of_descriptor: u32,
+    /// The number of handle descriptors following the header.
+    pub number_of_descriptors: u32,
+    pub reserved: u32,
+}
+
+/// Information about a single handle held by the process.
+///
+/// This struct matches the [Microsoft struct][msdn] of the same name.
+///
+/// [msdn]: https://docs.microsoft.com/en-us/windows/win32/api/minidumpapiset/ns-minidumpapiset-minidump_handle_descriptor
+#[derive(Debug, Clone, Default, Pread, SizeWith)]
+pub struct MINIDUMP_HANDLE_DESCRIPTOR {
+    /// The operating system handle value.
+    pub handle: u64,
+    /// An offset to a length-prefixed UTF-16LE string containing the object type name.
+    ///
+    /// This may be zero if there is no type name.
+    pub type_name_rva: RVA,
+    /// An offset to a length-prefixed UTF-16LE string containing the object name.
+    ///
+    /// This may be zero if there is no object name.
+    pub object_name_rva: RVA,
+    /// The object attributes for the handle.
+    pub attributes: u32,
+    /// The access rights granted when the handle was opened.
+    pub granted_access: u32,
+    /// The number of handles to the object.
+    pub handle_count: u32,
+    /// The number of kernel references to the object.
+    pub pointer_count: u32,
+}
+
+/// Information about a single handle held by the process, with object information.
+///
+/// This struct matches the [Microsoft struct][msdn] of the same name.
+///
+/// [msdn]: https://docs.microsoft.com/en-us/windows/win32/api/minidumpapiset/ns-minidumpapiset-minidump_handle_descriptor_2
+#[derive(Debug, Clone, Default, Pread, SizeWith)]
+pub struct MINIDUMP_HANDLE_DESCRIPTOR_2 {
+    /// The operating system handle value.
+    pub handle: u64,
+    /// An offset to a length-prefixed UTF-16LE string containing the object type name.
+    ///
+    /// This may be zero if there is no type name.
+    pub type_name_rva: RVA,
+    /// An offset to a length-prefixed UTF-16LE string containing the object name.
+    ///
+    /// This may be zero if there is no object name.
+    pub object_name_rva: RVA,
+    /// The object attributes for the handle.
+    pub attributes: u32,
+    /// The access rights granted when the handle was opened.
+    pub granted_access: u32,
+    /// The number of handles to the object.
+    pub handle_count: u32,
+    /// The number of kernel references to the object.
+    pub pointer_count: u32,
+    /// An offset to a `MINIDUMP_HANDLE_OBJECT_INFORMATION` list, or zero.
+    pub object_info_rva: RVA,
+    pub reserved0: u32,
+}
+
+/// The header for a list of handle operations recorded by Application Verifier.
+///
+/// This is the format of the [`MINIDUMP_STREAM_TYPE::HandleOperationListStream`]. The header is
+/// followed by `number_of_entries` [`AVRF_HANDLE_OPERATION`] structs.
+///
+/// This struct matches the [Microsoft struct][msdn] of the same name.
+///
+/// [msdn]: https://docs.microsoft.com/en-us/windows/win32/api/minidumpapiset/ns-minidumpapiset-minidump_handle_operation_list
+#[derive(Debug, Clone, Default, Pread, SizeWith)]
+pub struct MINIDUMP_HANDLE_OPERATION_LIST {
+    /// The size of this header, in bytes.
+    pub size_of_header: u32,
+    /// The size of each entry, in bytes.
+    pub size_of_entry: u32,
+    /// The number of entries following the header.
+    pub number_of_entries: u32,
+    pub reserved: u32,
+}
+
+/// The maximum number of return addresses in an [`AVRF_BACKTRACE_INFORMATION`].
+pub const AVRF_MAX_TRACES: usize = 32;
+
+/// A backtrace captured by Application Verifier.
+///
+/// This struct matches the [Microsoft struct][msdn] of the same name.
+///
+/// [msdn]: https://docs.microsoft.com/en-us/windows/win32/api/avrfsdk/ns-avrfsdk-avrf_backtrace_information
+#[derive(Debug, Clone, Pread, SizeWith)]
+pub struct AVRF_BACKTRACE_INFORMATION {
+    /// The number of valid elements in [`AVRF_BACKTRACE_INFORMATION::return_addresses`].
+    pub depth: u32,
+    /// The index of the backtrace in Application Verifier's trace database.
+    pub index: u32,
+    /// The return addresses of the backtrace, innermost first.
+    pub return_addresses: [u64; 32], // AVRF_MAX_TRACES
+}
+
+/// A handle operation recorded by Application Verifier.
+///
+/// This struct matches the [Microsoft struct][msdn] of the same name.
+///
+/// [msdn]: https://docs.microsoft.com/en-us/windows/win32/api/avrfsdk/ns-avrfsdk-avrf_handle_operation
+#[derive(Debug, Clone, Pread, SizeWith)]
+pub struct AVRF_HANDLE_OPERATION {
+    /// The operating system handle value.
+    pub handle: u64,
+    /// The process that performed the operation.
+    pub process_id: u32,
+    /// The thread that performed the operation.
+    pub thread_id: u32,
+    /// The kind of operation.
+    ///
+    /// Possible values are in the [`HandleTraceOperation`] enum.
+    pub operation_type: u32,
+    pub spare0: u32,
+    /// The stack of the thread when it performed the operation.
+    pub back_trace_information: AVRF_BACKTRACE_INFORMATION,
+}
+
+/// Values for [`AVRF_HANDLE_OPERATION::operation_type`]
+///
+/// This enum matches the Microsoft `eHANDLE_TRACE_OPERATIONS` enum.
+#[repr(u32)]
+#[derive(Copy, Clone, PartialEq, Debug, Primitive)]
+pub enum HandleTraceOperation {
+    OperationDbUnused = 0,
+    /// The handle was opened.
+    OperationDbOPEN = 1,
+    /// The handle was closed.
+    OperationDbCLOSE = 2,
+    /// An invalid handle was used.
+    OperationDbBADREF = 3,
+}
+
 /// Version information for a file
 ///
 /// This struct matches the [Microsoft struct][msdn] of the same name.
@@ -332,6 +492,40 @@ pub const VS_FFI_SIGNATURE: u32 = 0xfeef04bd;
 /// The expected value of `VS_FIXEDFILEINFO.struct_version`
 pub const VS_FFI_STRUCVERSION: u32 = 0x00010000;
 
+bitflags! {
+    /// Possible values of [`VS_FIXEDFILEINFO::file_flags`]
+    ///
+    /// Only the flags in [`VS_FIXEDFILEINFO::file_flags_mask`] are meaningful.
+    pub struct FileInfoFlags: u32 {
+        /// The file contains debugging information.
+        const VS_FF_DEBUG        = 0x01;
+        /// The file is a development version, not a released product.
+        const VS_FF_PRERELEASE   = 0x02;
+        /// The file has been modified and isn't identical to the original
+        /// shipping file of the same version number.
+        const VS_FF_PATCHED      = 0x04;
+        /// The file wasn't built using standard release procedures.
+        const VS_FF_PRIVATEBUILD = 0x08;
+        /// The file's version structure was created dynamically.
+        const VS_FF_INFOINFERRED = 0x10;
+        /// The file is a variation of the normal file of the same version number.
+        const VS_FF_SPECIALBUILD = 0x20;
+    }
+}
+
+/// Known values of [`VS_FIXEDFILEINFO::file_type`]
+#[repr(u32)]
+#[derive(Copy, Clone, PartialEq, Debug, Primitive)]
+pub enum FileInfoType {
+    VFT_UNKNOWN = 0,
+    VFT_APP = 1,
+    VFT_DLL = 2,
+    VFT_DRV = 3,
+    VFT_FONT = 4,
+    VFT_VXD = 5,
+    VFT_STATIC_LIB = 7,
+}
+
 /// Known values for the `signature` field of CodeView records
 ///
 /// In addition to the two CodeView record formats used for linking
@@ -575,6 +769,19 @@ pub struct MINIDUMP_THREAD {
     pub thread_context: MINIDUMP_LOCATION_DESCRIPTOR,
 }
 
+/// The name of a single thread from a minidump
+///
+/// This struct matches the [Microsoft struct][msdn] of the same name.
+///
+/// [msdn]: https://docs.microsoft.com/en-us/windows/win32/api/minidumpapiset/ns-minidumpapiset-minidump_thread_name
+#[derive(Debug, Clone, Pread, SizeWith)]
+pub struct MINIDUMP_THREAD_NAME {
+    /// The identifier of the thread
+    pub thread_id: u32,
+    /// The offset of a `MINIDUMP_STRING` containing the thread's name
+    pub thread_name_rva: RVA64,
+}
+
 /// Information about the exception that caused the process to terminate.
 ///
 /// This struct matches the [Microsoft struct][msdn] of the same name.
@@ -630,6 +837,27 @@ pub struct MINIDUMP_EXCEPTION {
     pub exception_information: [u64; 15], // EXCEPTION_MAXIMUM_PARAMETERS
 }
 
+/// An exception record in a 32-bit Windows process's memory.
+///
+/// [`MINIDUMP_EXCEPTION::exception_record`] points at one of these for a nested exception in a
+/// 32-bit process. Records in a 64-bit process's memory have the same layout as
+/// [`MINIDUMP_EXCEPTION`].
+///
+/// This struct matches the [Microsoft struct][msdn] of the same name.
+///
+/// [msdn]: https://docs.microsoft.com/en-us/windows/win32/api/winnt/ns-winnt-exception_record32
+#[derive(Debug, Clone, Pread, SizeWith)]
+pub struct EXCEPTION_RECORD32 {
+    pub exception_code: u32,
+    pub exception_flags: u32,
+    /// The address of the next nested exception's record, or 0.
+    pub exception_record: u32,
+    pub exception_address: u32,
+    /// The number of valid elements in [`EXCEPTION_RECORD32::exception_information`].
+    pub number_parameters: u32,
+    pub exception_information: [u32; 15], // EXCEPTION_MAXIMUM_PARAMETERS
+}
+
 /// Values for [`MINIDUMP_EXCEPTION::exception_code`] for crashes on Windows
 ///
 /// These values primarily come from WinBase.h and WinNT.h, with a few additions.
@@ -973,7 +1201,7 @@ pub struct CONTEXT_ARM64_OLD {
 }
 
 /// aarch64 floating point state
-#[derive(Debug, Clone, Pread, SizeWith)]
+#[derive(Debug, Clone, Default, Pread, SizeWith)]
 pub struct FLOATING_SAVE_AREA_ARM64 {
     pub regs: [u128; 32usize],
     pub fpsr: u32,
@@ -984,7 +1212,7 @@ pub struct FLOATING_SAVE_AREA_ARM64 {
 ///
 /// This is a Breakpad extension, and does not match the definition of `CONTEXT` for aarch64
 /// in WinNT.h.
-#[derive(Debug, Clone, Pread, SizeWith)]
+#[derive(Debug, Clone, Default, Pread, SizeWith)]
 pub struct CONTEXT_ARM64 {
     pub context_flags: u32,
     pub cpsr: u32,
@@ -1018,6 +1246,10 @@ pub struct FLOATING_SAVE_AREA_MIPS {
 /// A MIPS CPU context
 ///
 /// This is a Breakpad extension, as there is no definition of `CONTEXT` for MIPS in WinNT.h.
+///
+/// Breakpad uses this layout for both 32-bit and 64-bit MIPS, the registers are 64 bits wide
+/// either way. `context_flags` contains [`ContextFlagsCpu::CONTEXT_MIPS`] or
+/// [`ContextFlagsCpu::CONTEXT_MIPS64`] to say which it is.
 #[derive(Debug, Clone, Pread, SizeWith)]
 pub struct CONTEXT_MIPS {
     pub context_flags: u32,
@@ -1231,13 +1463,33 @@ pub struct X86CpuInfo {
     pub amd_extended_cpu_features: u32,
 }
 
+bitflags! {
+    /// Some of the possible values of [`X86CpuInfo::feature_information`]
+    ///
+    /// These are the feature bits `cpuid` returns in `edx` for leaf 1, as
+    /// documented in the Intel and AMD manuals.
+    pub struct X86CpuFeatures: u32 {
+        const FPU     = (1 << 0);
+        const TSC     = (1 << 4);
+        const CX8     = (1 << 8);
+        const CMOV    = (1 << 15);
+        const CLFLUSH = (1 << 19);
+        const MMX     = (1 << 23);
+        const FXSR    = (1 << 24);
+        const SSE     = (1 << 25);
+        const SSE2    = (1 << 26);
+        const HTT     = (1 << 28);
+    }
+}
+
 /// Arm-specific CPU information (Breakpad extension)
 #[derive(Debug, Clone, Pread, SizeWith)]
 pub struct ARMCpuInfo {
     pub cpuid: u32,
     /// Hardware capabilities
     ///
-    /// See [`ArmElfHwCaps`](struct.ArmElfHwCaps.html) for possible values.
+    /// See [`ArmElfHwCaps`](struct.ArmElfHwCaps.html) and
+    /// [`Arm64ElfHwCaps`](struct.Arm64ElfHwCaps.html) for possible values.
     pub elf_hwcaps: u32,
 }
 
@@ -1283,6 +1535,39 @@ bitflags! {
     }
 }
 
+bitflags! {
+    /// Possible values of [`ARMCpuInfo::elf_hwcaps`] for 64-bit ARM
+    ///
+    /// This matches the Linux kernel definitions from [<asm/hwcaps.h>](hwcap).
+    ///
+    /// [hwcap]: https://elixir.bootlin.com/linux/latest/source/arch/arm64/include/uapi/asm/hwcap.h
+    pub struct Arm64ElfHwCaps: u32 {
+        const HWCAP_FP       = (1 << 0);
+        const HWCAP_ASIMD    = (1 << 1);
+        const HWCAP_EVTSTRM  = (1 << 2);
+        const HWCAP_AES      = (1 << 3);
+        const HWCAP_PMULL    = (1 << 4);
+        const HWCAP_SHA1     = (1 << 5);
+        const HWCAP_SHA2     = (1 << 6);
+        const HWCAP_CRC32    = (1 << 7);
+        const HWCAP_ATOMICS  = (1 << 8);
+        const HWCAP_FPHP     = (1 << 9);
+        const HWCAP_ASIMDHP  = (1 << 10);
+        const HWCAP_CPUID    = (1 << 11);
+        const HWCAP_ASIMDRDM = (1 << 12);
+        const HWCAP_JSCVT    = (1 << 13);
+        const HWCAP_FCMA     = (1 << 14);
+        const HWCAP_LRCPC    = (1 << 15);
+        const HWCAP_DCPOP    = (1 << 16);
+        const HWCAP_SHA3     = (1 << 17);
+        const HWCAP_SM3      = (1 << 18);
+        const HWCAP_SM4      = (1 << 19);
+        const HWCAP_ASIMDDP  = (1 << 20);
+        const HWCAP_SHA512   = (1 << 21);
+        const HWCAP_SVE      = (1 << 22);
+    }
+}
+
 /// Processor and operating system information
 ///
 /// This struct matches the [Microsoft struct][msdn] of the same name.
@@ -1505,8 +1790,6 @@ multi_structs! {
         pub xstate_data: XSTATE_CONFIG_FEATURE_MSC_INFO,
         pub process_cookie: u32,
     }
-    // TODO: read xstate_data and process the extra XSAVE sections at the
-    // end of each thread's cpu context.
 }
 
 /// A descriptor of the XSAVE context which can be found at the end of
@@ -1635,6 +1918,25 @@ bitflags! {
     }
 }
 
+bitflags! {
+    /// Known flags for `MINIDUMP_MISC_INFO_3.process_execute_flags`
+    ///
+    /// These are the process's data execution prevention (DEP) settings, the
+    /// `MEM_EXECUTE_OPTION_*` values from the Windows DDK.
+    pub struct ProcessExecuteFlags: u32 {
+        /// Executing data is not allowed, DEP is on.
+        const MEM_EXECUTE_OPTION_DISABLE                 = 0x01;
+        /// Executing data is allowed, DEP is off.
+        const MEM_EXECUTE_OPTION_ENABLE                  = 0x02;
+        /// ATL thunks on the heap are not emulated when DEP blocks them.
+        const MEM_EXECUTE_OPTION_DISABLE_THUNK_EMULATION = 0x04;
+        /// The process can't change its DEP settings.
+        const MEM_EXECUTE_OPTION_PERMANENT               = 0x08;
+        const MEM_EXECUTE_OPTION_EXECUTE_DISPATCH_ENABLE = 0x10;
+        const MEM_EXECUTE_OPTION_IMAGE_DISPATCH_ENABLE   = 0x20;
+    }
+}
+
 /// A list of memory regions in a minidump
 ///
 /// This is the format of the [`MINIDUMP_STREAM_TYPE::MemoryInfoListStream`]. The individual
@@ -1911,7 +2213,7 @@ pub struct MINIDUMP_RVA_LIST {
 /// A typed annotation object.
 ///
 /// See <https://crashpad.chromium.org/doxygen/structcrashpad_1_1MinidumpAnnotation.html>
-#[derive(Clone, Debug, Pread)]
+#[derive(Clone, Debug, Pread, SizeWith)]
 pub struct MINIDUMP_ANNOTATION {
     /// RVA of a MinidumpUTF8String containing the name of the annotation.
     pub name: RVA,
@@ -1999,7 +2301,7 @@ impl MINIDUMP_MODULE_CRASHPAD_INFO {
 /// module carried within a minidump file.
 ///
 /// See <https://crashpad.chromium.org/doxygen/structcrashpad_1_1MinidumpModuleCrashpadInfoLink.html>
-#[derive(Clone, Debug, Pread)]
+#[derive(Clone, Debug, Pread, SizeWith)]
 pub struct MINIDUMP_MODULE_CRASHPAD_INFO_LINK {
     /// A link to a MINIDUMP_MODULE structure in the module list stream.
     ///
@@ -75,18 +75,27 @@ where
     V: Clone + Debug + Eq,
 {
     fn into_rangemap_safe(self) -> RangeMap<u64, V> {
+        self.into_rangemap_safe_with_overlaps().0
+    }
+
+    /// Like `into_rangemap_safe`, but also return the entries that were dropped because
+    /// they overlapped an earlier range mapping to a different value.
+    ///
+    /// Entries are considered in order of their range's start address, so the dropped
+    /// entries are returned in that order too.
+    fn into_rangemap_safe_with_overlaps(self) -> (RangeMap<u64, V>, Vec<(Range<u64>, V)>) {
         let mut input: Vec<_> = self.into_iter().collect();
         input.sort_by_key(|x| x.0);
         let mut vec: Vec<(Range<u64>, V)> = Vec::with_capacity(input.len());
+        let mut overlaps = vec![];
         for (range, val) in input.into_iter() {
             if let Some(&mut (ref mut last_range, ref last_val)) = vec.last_mut() {
                 if range.start <= last_range.end && &val != last_val {
-                    //TODO: add a way for callers to do custom logging here? Perhaps
-                    // a callback function?
                     warn!(
                         "overlapping ranges {:?} and {:?} map to values {:?} and {:?}",
                         last_range, range, last_val, val
                     );
+                    overlaps.push((range, val));
                     continue;
                 }
 
@@ -98,7 +107,7 @@ where
 
             vec.push((range, val));
         }
-        RangeMap::from_sorted_vec(vec)
+        (RangeMap::from_sorted_vec(vec), overlaps)
     }
 }
 
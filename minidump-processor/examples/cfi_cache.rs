@@ -0,0 +1,140 @@
+// Copyright 2015 Ted Mielczarek. See the COPYRIGHT
+// file at the top-level directory of this distribution.
+
+//! Time unwinding many threads that are all deep in the same function, with
+//! and without caching its STACK CFI rules.
+//!
+//! Run it with `cargo run --release --example cfi_cache`.
+
+use std::sync::Arc;
+use std::time::{Duration, Instant};
+
+use breakpad_symbols::{
+    CfiCache, FrameSymbolizer, FrameWalker, Module, SymbolFile, SymbolResult, SymbolSupplier,
+    Symbolizer,
+};
+use minidump::format::CONTEXT_AMD64;
+use minidump::{
+    MinidumpContext, MinidumpContextValidity, MinidumpMemory, MinidumpModule, MinidumpModuleList,
+    MinidumpRawContext, MinidumpUnloadedModuleList,
+};
+use minidump_processor::{walk_stack, SymbolProvider};
+
+const MODULE_BASE: u64 = 0x7400c0000000;
+const STACK_BASE: u64 = 0x80000000;
+const THREADS: usize = 256;
+const FRAMES: usize = 64;
+/// The number of `STACK CFI` records following the function's `STACK CFI INIT`.
+const RULES: u64 = 512;
+
+/// Supplies symbols with one big recursive function for every module.
+struct HotFunctionSupplier;
+
+impl SymbolSupplier for HotFunctionSupplier {
+    fn locate_symbols(&self, _module: &dyn Module) -> SymbolResult {
+        let mut sym = String::from(
+            "MODULE windows x86_64 0 hot.pdb\n\
+             FUNC 1000 1000 0 hot\n\
+             STACK CFI INIT 1000 1000 .cfa: $rsp 8 + .ra: .cfa -8 + ^\n",
+        );
+        for i in 0..RULES {
+            sym.push_str(&format!("STACK CFI {:x} .cfa: $rsp 16 +\n", 0x1001 + i));
+        }
+        match SymbolFile::from_bytes(sym.as_bytes()) {
+            Ok(sym) => SymbolResult::Ok(Arc::new(sym)),
+            Err(e) => SymbolResult::Corrupt(e),
+        }
+    }
+}
+
+/// Unwinds with `symbolizer`, keeping the STACK CFI rules it parses in `cache`.
+struct CachingProvider<'a> {
+    symbolizer: &'a Symbolizer,
+    cache: CfiCache,
+}
+
+impl<'a> SymbolProvider for CachingProvider<'a> {
+    fn fill_symbol(&self, module: &dyn Module, frame: &mut dyn FrameSymbolizer) {
+        self.symbolizer.fill_symbol(module, frame);
+    }
+    fn walk_frame(&self, module: &dyn Module, walker: &mut dyn FrameWalker) -> Option<()> {
+        self.symbolizer
+            .walk_frame_with_cache(module, walker, &self.cache)
+    }
+}
+
+/// A stack of `FRAMES` calls to `hot`, each of which returns to `hot`.
+fn stack_bytes() -> Vec<u8> {
+    let return_address = MODULE_BASE + 0x1800;
+    let mut bytes = vec![];
+    for i in 0..FRAMES {
+        let caller = if i + 1 < FRAMES { return_address } else { 0 };
+        bytes.extend_from_slice(&0u64.to_le_bytes());
+        bytes.extend_from_slice(&caller.to_le_bytes());
+    }
+    bytes.extend_from_slice(&[0; 16]);
+    bytes
+}
+
+fn walk_threads<P: SymbolProvider>(
+    symbol_provider: &P,
+    stack: &MinidumpMemory,
+    modules: &MinidumpModuleList,
+) -> (Duration, usize) {
+    let unloaded_modules = MinidumpUnloadedModuleList::new();
+    let start = Instant::now();
+    let mut frames = 0;
+    for _ in 0..THREADS {
+        let context = MinidumpContext {
+            raw: MinidumpRawContext::Amd64(CONTEXT_AMD64 {
+                rip: MODULE_BASE + 0x1800,
+                rsp: STACK_BASE,
+                ..CONTEXT_AMD64::default()
+            }),
+            valid: MinidumpContextValidity::All,
+        };
+        let stack = walk_stack(
+            &Some(&context),
+            Some(stack),
+            modules,
+            &unloaded_modules,
+            symbol_provider,
+        );
+        frames += stack.frames.len();
+    }
+    (start.elapsed(), frames)
+}
+
+fn main() {
+    let bytes = stack_bytes();
+    let stack = MinidumpMemory {
+        desc: Default::default(),
+        base_address: STACK_BASE,
+        size: bytes.len() as u64,
+        bytes: &bytes,
+    };
+    let modules = MinidumpModuleList::from_modules(vec![MinidumpModule::new(
+        MODULE_BASE,
+        0x10000,
+        "hot.dll",
+    )]);
+    let symbolizer = Symbolizer::new(HotFunctionSupplier);
+    // Load the symbols before timing anything.
+    walk_threads(&symbolizer, &stack, &modules);
+
+    let (uncached, uncached_frames) = walk_threads(&symbolizer, &stack, &modules);
+    let provider = CachingProvider {
+        symbolizer: &symbolizer,
+        cache: CfiCache::new(),
+    };
+    let (cached, cached_frames) = walk_threads(&provider, &stack, &modules);
+    assert_eq!(uncached_frames, cached_frames);
+
+    println!(
+        "Unwound {} threads of {} frames each",
+        THREADS,
+        uncached_frames / THREADS
+    );
+    println!("  without a CFI cache: {:?}", uncached);
+    println!("  with a CFI cache:    {:?}", cached);
+}
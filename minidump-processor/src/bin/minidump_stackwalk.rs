@@ -10,7 +10,7 @@ use std::path::PathBuf;
 
 use breakpad_symbols::{HttpSymbolSupplier, SimpleSymbolSupplier, Symbolizer};
 use minidump::*;
-use minidump_processor::{DwarfSymbolizer, MultiSymbolProvider};
+use minidump_processor::{DwarfSymbolizer, MultiSymbolProvider, PrintOptions};
 
 use clap::{crate_authors, crate_version, App, Arg};
 use log::error;
@@ -21,11 +21,13 @@ fn print_minidump_process(
     symbol_paths: Vec<PathBuf>,
     symbol_urls: Vec<String>,
     symbols_cache: Option<PathBuf>,
-    human: bool,
+    human: Option<PrintOptions>,
+    sentry: bool,
     pretty: bool,
 ) {
     if let Ok(dump) = Minidump::read_path(path) {
         let mut provider = MultiSymbolProvider::new();
+        let dwarf_symbolizer = DwarfSymbolizer::with_paths(symbol_paths.clone());
 
         if let Some(symbols_cache) = symbols_cache {
             provider.add(Box::new(Symbolizer::new(HttpSymbolSupplier::new(
@@ -38,13 +40,15 @@ fn print_minidump_process(
                 symbol_paths,
             ))));
         }
-        provider.add(Box::new(DwarfSymbolizer::new()));
+        provider.add(Box::new(dwarf_symbolizer));
 
         match minidump_processor::process_minidump(&dump, &provider) {
             Ok(state) => {
                 let mut stdout = std::io::stdout();
-                if human {
-                    state.print(&mut stdout).unwrap();
+                if let Some(options) = human {
+                    state.print_with_options(&mut stdout, &options).unwrap();
+                } else if sentry {
+                    state.print_sentry_json(&mut stdout, pretty).unwrap();
                 } else {
                     state.print_json(&mut stdout, pretty).unwrap();
                 }
@@ -71,6 +75,25 @@ fn main() {
                 .help("Emit a human-readable report instead")
                 .long("human")
         )
+        .arg(
+            Arg::with_name("color")
+                .help("Highlight the human-readable report with ANSI colors")
+                .long("color")
+                .requires("human")
+                .overrides_with("no-color")
+        )
+        .arg(
+            Arg::with_name("no-color")
+                .help("Don't color the human-readable report (the default)")
+                .long("no-color")
+                .overrides_with("color")
+        )
+        .arg(
+            Arg::with_name("sentry")
+                .help("Emit a Sentry event as JSON instead")
+                .long("sentry")
+                .conflicts_with("human")
+        )
         .arg(
             Arg::with_name("pretty")
                 .help("Pretty-print JSON output.")
@@ -172,7 +195,14 @@ fn main() {
         .unwrap_or_else(Vec::new);
 
     let pretty = matches.is_present("pretty");
-    let human = matches.is_present("human");
+    let human = if matches.is_present("human") {
+        Some(PrintOptions {
+            color: matches.is_present("color"),
+        })
+    } else {
+        None
+    };
+    let sentry = matches.is_present("sentry");
 
     let minidump_path = matches.value_of_os("minidump").map(Path::new).unwrap();
     let symbols_paths = matches
@@ -194,6 +224,7 @@ fn main() {
         symbols_urls,
         symbols_cache,
         human,
+        sentry,
         pretty,
     );
 }
@@ -1,74 +1,240 @@
-use addr2line::{Context, Frame, Location};
+use addr2line::{Context, Frame};
 use failure::{bail, format_err, Error};
 use gimli::{EndianRcSlice, RunTimeEndian};
-use object::{self, Object};
+use log::trace;
+use object::{self, Object, ObjectSegment};
+use scroll::{self, Pread};
 
 use std::cell::RefCell;
 use std::collections::HashMap;
 use std::fs::File;
+use std::path::{Path, PathBuf};
 
-use breakpad_symbols::{FrameSymbolizer, FrameWalker};
+use breakpad_symbols::{FrameSymbolizer, FrameWalker, SymbolStatus};
 use minidump::Module;
 
 use crate::SymbolProvider;
 
+/// A `SymbolProvider` that reads symbols straight from the DWARF debug info in ELF files.
+///
+/// A module's ELF file is looked for in each search path, first in the
+/// `.build-id/xx/yyyy.debug` layout used for separate debug info, then by the
+/// module's file name, and finally at the path the module was loaded from. A file
+/// is only used if its GNU build id matches the module's code identifier, so
+/// symbols are never taken from a different build of the module. The exception
+/// is a module without a build id, for which a file without one at the path the
+/// module was loaded from is used as is.
 #[derive(Default)]
 pub struct DwarfSymbolizer {
-    /// A mapping of lookups of symbol files, where the key is the path to the binary.
-    known_modules: RefCell<HashMap<String, Option<Context<EndianRcSlice<RunTimeEndian>>>>>,
+    /// Directories to search for ELF files.
+    paths: Vec<PathBuf>,
+    /// A mapping of lookups of symbol files, where the key is the module's code file
+    /// and code identifier.
+    known_modules: RefCell<HashMap<(String, String), Option<DwarfModule>>>,
 }
 
-fn locate_symbols(path: &str) -> Result<Context<EndianRcSlice<RunTimeEndian>>, Error> {
+/// The debug info for a module.
+struct DwarfModule {
+    context: Context<EndianRcSlice<RunTimeEndian>>,
+    /// The address the ELF file's first segment expects to be loaded at.
+    image_address: u64,
+}
+
+/// Extract the GNU build id from the contents of an ELF note section, as a hex string.
+fn parse_build_id_note(data: &[u8], little_endian: bool) -> Option<String> {
+    const NT_GNU_BUILD_ID: u32 = 3;
+    let endian = if little_endian {
+        scroll::LE
+    } else {
+        scroll::BE
+    };
+    // Note names and descriptions are padded to 4 bytes.
+    let aligned = |size: u32| (size as usize).checked_add(3).map(|size| size & !3);
+    let mut offset = 0;
+    while offset < data.len() {
+        let name_size: u32 = data.gread_with(&mut offset, endian).ok()?;
+        let desc_size: u32 = data.gread_with(&mut offset, endian).ok()?;
+        let note_type: u32 = data.gread_with(&mut offset, endian).ok()?;
+        let name = data.get(offset..offset.checked_add(name_size as usize)?)?;
+        offset = offset.checked_add(aligned(name_size)?)?;
+        let desc = data.get(offset..offset.checked_add(desc_size as usize)?)?;
+        offset = offset.checked_add(aligned(desc_size)?)?;
+        if note_type == NT_GNU_BUILD_ID && name == b"GNU\0" {
+            return Some(desc.iter().map(|b| format!("{:02x}", b)).collect());
+        }
+    }
+    None
+}
+
+/// Load the debug info in the ELF file at `path` if its build id is `build_id`.
+///
+/// A file without a build id is only used if `allow_missing_build_id` is set.
+fn locate_symbols(
+    path: &Path,
+    build_id: &str,
+    allow_missing_build_id: bool,
+) -> Result<DwarfModule, Error> {
     let f = File::open(path)?;
     let buf = unsafe { memmap::Mmap::map(&f)? };
-    let obj = object::File::parse(&*buf).map_err(|_| format_err!("Failed to parse {}", path))?;
-    if obj.has_debug_symbols() {
-        let context = Context::new(&obj)
-            .map_err(|_| format_err!("Failed to load debug symbols for {}", path))?;
-        Ok(context)
-    } else {
-        //TODO: use moria
-        bail!("No debug symbols in {}", path)
+    let obj = object::File::parse(&*buf)
+        .map_err(|_| format_err!("Failed to parse {}", path.display()))?;
+    let file_build_id = obj
+        .section_data_by_name(".note.gnu.build-id")
+        .and_then(|data| parse_build_id_note(&data, obj.is_little_endian()));
+    match file_build_id {
+        Some(file_build_id) => {
+            if !file_build_id.eq_ignore_ascii_case(build_id) {
+                bail!(
+                    "Build id {} of {} does not match {}",
+                    file_build_id,
+                    path.display(),
+                    build_id
+                );
+            }
+        }
+        None => {
+            if !allow_missing_build_id {
+                bail!("No build id in {}", path.display());
+            }
+        }
+    }
+    if !obj.has_debug_symbols() {
+        bail!("No debug symbols in {}", path.display());
     }
+    let context = Context::new(&obj)
+        .map_err(|_| format_err!("Failed to load debug symbols for {}", path.display()))?;
+    let image_address = obj
+        .segments()
+        .map(|segment| segment.address())
+        .min()
+        .unwrap_or(0);
+    Ok(DwarfModule {
+        context,
+        image_address,
+    })
 }
 
 impl DwarfSymbolizer {
+    /// Create a `DwarfSymbolizer` that only looks at the paths modules were loaded from.
     pub fn new() -> DwarfSymbolizer {
         Default::default()
     }
+
+    /// Create a `DwarfSymbolizer` that also searches `paths` for ELF files.
+    pub fn with_paths(paths: Vec<PathBuf>) -> DwarfSymbolizer {
+        DwarfSymbolizer {
+            paths,
+            ..DwarfSymbolizer::default()
+        }
+    }
+
+    /// The paths where an ELF file for `module` might be, in the order they should be tried.
+    fn candidate_paths(&self, module: &dyn Module, build_id: &str) -> Vec<PathBuf> {
+        let code_file = module.code_file();
+        let file_name = code_file.rsplit('/').next().unwrap_or("");
+        let mut candidates = vec![];
+        if build_id.len() > 2 {
+            let (dir, rest) = build_id.split_at(2);
+            for path in &self.paths {
+                candidates.push(
+                    path.join(".build-id")
+                        .join(dir)
+                        .join(format!("{}.debug", rest)),
+                );
+            }
+        }
+        if !file_name.is_empty() {
+            for path in &self.paths {
+                candidates.push(path.join(file_name));
+            }
+        }
+        candidates.push(PathBuf::from(code_file.as_ref()));
+        candidates
+    }
+
+    /// The key for `module` in `known_modules`.
+    fn key(module: &dyn Module) -> (String, String) {
+        (
+            module.code_file().into_owned(),
+            module.code_identifier().into_owned(),
+        )
+    }
+
+    fn find_module(&self, module: &dyn Module) -> Option<DwarfModule> {
+        let build_id = module.code_identifier().to_lowercase();
+        let code_file = PathBuf::from(module.code_file().as_ref());
+        let has_build_id = module.debug_identifier().is_some();
+        self.candidate_paths(module, &build_id)
+            .iter()
+            .filter(|path| path.is_file())
+            .filter_map(|path| {
+                // ELF files built without a build id can't be matched to the
+                // module, so one is only trusted at the path the module was
+                // loaded from, and only if the module has no build id either.
+                locate_symbols(path, &build_id, !has_build_id && *path == code_file)
+                    .map_err(|e| trace!("{}", e))
+                    .ok()
+            })
+            .next()
+    }
+}
+
+/// Convert an `addr2line` frame's function name and location to what `FrameSymbolizer` takes.
+fn frame_info<R: gimli::Reader>(frame: &Frame<R>) -> Option<(String, Option<&str>, Option<u32>)> {
+    let name = frame.function.as_ref()?.demangle().ok()?.into_owned();
+    let (file, line) = match frame.location {
+        Some(ref location) => (
+            location.file.as_deref(),
+            location.line.map(|line| line as u32),
+        ),
+        None => (None, None),
+    };
+    Some((name, file, line))
 }
 
 impl SymbolProvider for DwarfSymbolizer {
     fn fill_symbol(&self, module: &dyn Module, frame: &mut dyn FrameSymbolizer) {
-        let path = module.code_file();
-        let k = path.as_ref();
-        if !self.known_modules.borrow().contains_key(k) {
-            self.known_modules
-                .borrow_mut()
-                .insert(path.clone().into_owned(), locate_symbols(&path).ok());
+        let mut known_modules = self.known_modules.borrow_mut();
+        let dwarf_module = known_modules
+            .entry(DwarfSymbolizer::key(module))
+            .or_insert_with(|| self.find_module(module));
+        let dwarf_module = match *dwarf_module {
+            Some(ref dwarf_module) => dwarf_module,
+            None => return,
+        };
+        // DWARF addresses are relative to where the ELF file expects to be loaded.
+        let address = match frame
+            .get_instruction()
+            .checked_sub(module.base_address())
+            .and_then(|offset| offset.checked_add(dwarf_module.image_address))
+        {
+            Some(address) => address,
+            None => return,
+        };
+        let mut frames = vec![];
+        if let Ok(mut iter) = dwarf_module.context.find_frames(address) {
+            while let Ok(Some(found)) = iter.next() {
+                frames.push(found);
+            }
+        }
+        // Frames are innermost first, the last one is the function that was
+        // actually called and the rest were inlined into it.
+        let (outer, inlined) = match frames.split_last() {
+            Some(frames) => frames,
+            None => return,
+        };
+        if let Some((name, file, line)) = frame_info(outer) {
+            //TODO: get base address for function
+            //TODO: get parameter size for function?
+            frame.set_function(&name, 0, 0);
+            if let Some(file) = file {
+                //TODO: get base address for line
+                frame.set_source_file(file, line.unwrap_or(0), 0);
+            }
         }
-        if let Some(&mut Some(ref mut map)) = self.known_modules.borrow_mut().get_mut(k) {
-            let addr = frame.get_instruction();
-            if let Ok(mut iter) = map.find_frames(addr) {
-                while let Ok(Some(Frame {
-                    function: Some(func),
-                    location:
-                        Some(Location {
-                            file: Some(source_file),
-                            line,
-                            ..
-                        }),
-                })) = iter.next()
-                {
-                    //TODO: get base address for line
-                    frame.set_source_file(&source_file, line.unwrap_or(0) as u32, 0);
-                    //TODO: get base address for function
-                    //TODO: get parameter size for function?
-                    if let Ok(name) = func.demangle() {
-                        frame.set_function(&name, 0, 0);
-                        break;
-                    }
-                }
+        for inline in inlined {
+            if let Some((name, file, line)) = frame_info(inline) {
+                frame.add_inline_frame(&name, file, line);
             }
         }
     }
@@ -76,4 +242,59 @@ impl SymbolProvider for DwarfSymbolizer {
         // unimplemented
         None
     }
+    fn symbol_status(&self, module: &dyn Module) -> Option<SymbolStatus> {
+        // Files that don't parse or don't match are skipped like missing ones.
+        self.known_modules
+            .borrow()
+            .get(&DwarfSymbolizer::key(module))
+            .map(|dwarf_module| match dwarf_module {
+                Some(_) => SymbolStatus::Loaded,
+                None => SymbolStatus::Missing,
+            })
+    }
+}
+
+#[cfg(test)]
+mod test {
+    use super::*;
+    use test_assembler::*;
+
+    fn build_id_note(endian: Endian, note_type: u32, name: &[u8], desc: &[u8]) -> Section {
+        Section::with_endian(endian)
+            .D32(name.len() as u32)
+            .D32(desc.len() as u32)
+            .D32(note_type)
+            .append_bytes(name)
+            .align(4)
+            .append_bytes(desc)
+            .align(4)
+    }
+
+    #[test]
+    fn test_parse_build_id_note() {
+        let desc = [0xde, 0xad, 0xbe, 0xef, 0x01];
+        for &(endian, little_endian) in &[(Endian::Little, true), (Endian::Big, false)] {
+            // A note of another type, followed by the build id.
+            let data = build_id_note(endian, 1, b"GNU\0", &[1, 2, 3])
+                .append_section(build_id_note(endian, 3, b"GNU\0", &desc))
+                .get_contents()
+                .unwrap();
+            assert_eq!(
+                parse_build_id_note(&data, little_endian),
+                Some("deadbeef01".to_owned())
+            );
+        }
+
+        // Notes from other vendors don't count.
+        let data = build_id_note(Endian::Little, 3, b"Xen\0", &desc)
+            .get_contents()
+            .unwrap();
+        assert_eq!(parse_build_id_note(&data, true), None);
+
+        // Truncated notes don't either.
+        let data = build_id_note(Endian::Little, 3, b"GNU\0", &desc)
+            .get_contents()
+            .unwrap();
+        assert_eq!(parse_build_id_note(&data[..data.len() - 4], true), None);
+    }
 }
@@ -0,0 +1,110 @@
+// Copyright 2015 Ted Mielczarek. See the COPYRIGHT
+// file at the top-level directory of this distribution.
+
+//! Choose which of a process's environment variables go in its report.
+
+use std::fmt;
+use std::sync::Arc;
+
+/// Decides which of the crashed process's environment variables are kept in
+/// the `ProcessState`, and what's kept of their values.
+///
+/// The environment often holds secrets like access tokens, so none of it is
+/// kept unless [`ProcessorOptions::environment`][environment] is set. Any
+/// `Fn(&str, &str) -> Option<String>` is one, so a caller can redact values:
+///
+/// ```
+/// use minidump_processor::{EnvironmentFilter, RuntimeVariables};
+///
+/// let filter = |name: &str, value: &str| {
+///     if name.ends_with("_TOKEN") {
+///         Some("<redacted>".to_owned())
+///     } else {
+///         RuntimeVariables.filter_var(name, value)
+///     }
+/// };
+/// assert_eq!(filter.filter_var("GITHUB_TOKEN", "hunter2"), Some("<redacted>".to_owned()));
+/// assert_eq!(filter.filter_var("LANG", "C.UTF-8"), Some("C.UTF-8".to_owned()));
+/// assert_eq!(filter.filter_var("HOME", "/home/user"), None);
+/// ```
+///
+/// [environment]: struct.ProcessorOptions.html#structfield.environment
+pub trait EnvironmentFilter {
+    /// The value to report for the variable `name`, which was set to `value`,
+    /// or `None` to leave it out of the report.
+    fn filter_var(&self, name: &str, value: &str) -> Option<String>;
+}
+
+impl<F> EnvironmentFilter for F
+where
+    F: Fn(&str, &str) -> Option<String>,
+{
+    fn filter_var(&self, name: &str, value: &str) -> Option<String> {
+        self(name, value)
+    }
+}
+
+/// An `EnvironmentFilter` that can be cloned along with the
+/// [`ProcessorOptions`][options] holding it.
+///
+/// [options]: struct.ProcessorOptions.html
+#[derive(Clone)]
+pub struct SharedEnvironmentFilter(Arc<dyn EnvironmentFilter + Send + Sync>);
+
+impl SharedEnvironmentFilter {
+    /// Wraps `filter` so it can be shared.
+    pub fn new<F>(filter: F) -> SharedEnvironmentFilter
+    where
+        F: EnvironmentFilter + Send + Sync + 'static,
+    {
+        SharedEnvironmentFilter(Arc::new(filter))
+    }
+}
+
+impl fmt::Debug for SharedEnvironmentFilter {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        f.write_str("SharedEnvironmentFilter(..)")
+    }
+}
+
+impl EnvironmentFilter for SharedEnvironmentFilter {
+    fn filter_var(&self, name: &str, value: &str) -> Option<String> {
+        self.0.filter_var(name, value)
+    }
+}
+
+/// An `EnvironmentFilter` that keeps the variables that commonly change how a
+/// Linux program loads and runs, and leaves out the rest.
+///
+/// Those are the dynamic loader's `LD_*` variables, the locale, the time zone,
+/// glibc's allocator tunables, and which display server the program used.
+#[derive(Clone, Copy, Debug, Default)]
+pub struct RuntimeVariables;
+
+impl RuntimeVariables {
+    const NAMES: &'static [&'static str] = &[
+        "DISPLAY",
+        "GLIBC_TUNABLES",
+        "LANG",
+        "LANGUAGE",
+        "TZ",
+        "WAYLAND_DISPLAY",
+        "XDG_CURRENT_DESKTOP",
+        "XDG_SESSION_TYPE",
+    ];
+    const PREFIXES: &'static [&'static str] = &["LC_", "LD_", "MALLOC_"];
+}
+
+impl EnvironmentFilter for RuntimeVariables {
+    fn filter_var(&self, name: &str, value: &str) -> Option<String> {
+        let keep = RuntimeVariables::NAMES.contains(&name)
+            || RuntimeVariables::PREFIXES
+                .iter()
+                .any(|prefix| name.starts_with(prefix));
+        if keep {
+            Some(value.to_owned())
+        } else {
+            None
+        }
+    }
+}
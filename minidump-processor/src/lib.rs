@@ -7,13 +7,19 @@
 //! [`process_minidump`]: fn.process_minidump.html
 
 mod dwarf_symbolizer;
+mod environment;
 mod process_state;
 mod processor;
+mod source_url;
+mod stack_words;
 mod stackwalker;
 mod system_info;
 
 pub use crate::dwarf_symbolizer::DwarfSymbolizer;
+pub use crate::environment::*;
 pub use crate::process_state::*;
 pub use crate::processor::*;
+pub use crate::source_url::*;
+pub use crate::stack_words::*;
 pub use crate::stackwalker::*;
 pub use crate::system_info::*;
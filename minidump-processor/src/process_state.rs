@@ -4,15 +4,26 @@
 //! The state of a process.
 
 use std::borrow::{Borrow, Cow};
-use std::collections::HashSet;
+use std::collections::{HashMap, HashSet};
+use std::convert::TryFrom;
+use std::fmt;
 use std::io;
 use std::io::prelude::*;
 
+use crate::processor::SymbolProvider;
+use crate::source_url::{SharedSourceUrlResolver, SourceUrlResolver};
+use crate::stackwalker;
 use crate::system_info::SystemInfo;
-use breakpad_symbols::FrameSymbolizer;
+use breakpad_symbols::{FrameSymbolizer, SymbolParseError, SymbolStatus};
 use chrono::prelude::*;
-use minidump::system_info::Cpu;
+use chrono::Duration;
+use minidump::format::{
+    ExceptionCodeWindows, MemoryProtection, MemoryState, ProcessExecuteFlags, MINIDUMP_EXCEPTION,
+    MINIDUMP_STREAM_TYPE,
+};
+use minidump::system_info::{Cpu, Os};
 use minidump::*;
+use serde::ser::{Serialize, SerializeMap, SerializeSeq, Serializer};
 use serde_json::json;
 
 /// Indicates how well the instruction pointer derived during
@@ -28,6 +39,9 @@ pub enum FrameTrust {
     CfiScan,
     /// Derived from frame pointer.
     FramePointer,
+    /// Return address taken from where the callee's function entry leaves it,
+    /// because its symbols say the callee hasn't run any of its function yet.
+    FunctionEntry,
     /// Derived from call frame info.
     CallFrameInfo,
     /// Explicitly provided by some external stack walker.
@@ -36,6 +50,30 @@ pub enum FrameTrust {
     Context,
 }
 
+/// How the stack walker got the value of one of a caller's registers.
+#[derive(Copy, Clone, Debug, PartialEq, Eq)]
+pub enum RegisterProvenance {
+    /// Computed from the callee's frame, by CFI or by following frame pointers.
+    Recovered,
+    /// Copied from the callee, which preserves it for its caller.
+    CalleeSaved,
+    /// Found by scanning the stack.
+    Scanned,
+    /// Not known.
+    Unknown,
+}
+
+impl RegisterProvenance {
+    fn json_name(&self) -> &'static str {
+        match *self {
+            RegisterProvenance::Recovered => "recovered",
+            RegisterProvenance::CalleeSaved => "callee_saved",
+            RegisterProvenance::Scanned => "scanned",
+            RegisterProvenance::Unknown => "unknown",
+        }
+    }
+}
+
 /// A single stack frame produced from unwinding a thread's stack.
 #[derive(Debug)]
 pub struct StackFrame {
@@ -58,13 +96,26 @@ pub struct StackFrame {
     //
     // On some architectures, the return address as saved on the stack or in
     // a register is fine for looking up the point of the call. On others, it
-    // requires adjustment. ReturnAddress returns the address as saved by the
-    // machine.
+    // requires adjustment, see `StackFrame::from_caller_context`.
+    // `return_address` returns the address as saved by the machine.
     pub instruction: u64,
 
     // The module in which the instruction resides.
     pub module: Option<MinidumpModule>,
 
+    /// The unloaded modules in which the instruction resides, if it wasn't
+    /// in any loaded module.
+    ///
+    /// Unloaded modules can overlap, so there may be more than one. If symbols
+    /// were found for this frame, they came from the first module.
+    pub unloaded_modules: Vec<MinidumpUnloadedModule>,
+
+    /// The Linux memory mapping in which the instruction resides, if it wasn't
+    /// in any loaded or unloaded module.
+    ///
+    /// This names regions like JIT code and anonymous executable mappings.
+    pub mapping: Option<MinidumpLinuxMapInfo>,
+
     /// The function name, may be omitted if debug symbols are not available.
     pub function_name: Option<String>,
 
@@ -87,12 +138,107 @@ pub struct StackFrame {
     /// are not available.
     pub source_line_base: Option<u64>,
 
+    /// Functions that were inlined into `function_name` at this frame's
+    /// instruction, innermost first.
+    ///
+    /// When this is not empty, `source_file_name` and `source_line` are the
+    /// location of the call to the outermost inlined function.
+    pub inlines: Vec<InlineFrame>,
+
     /// Amount of trust the stack walker has in the instruction pointer
     /// of this frame.
     pub trust: FrameTrust,
 
+    /// `true` if this frame was assigned a module that doesn't contain its
+    /// instruction.
+    ///
+    /// The module and any symbols found in it were cleared, since they
+    /// can't describe this frame.
+    pub inconsistent_module: bool,
+
     /// The CPU context containing register state for this frame.
     pub context: MinidumpContext,
+
+    /// How the stack walker got each of the registers in `context`, if it
+    /// recorded that.
+    ///
+    /// This is `None` for the context frame, whose registers come straight
+    /// from the minidump.
+    pub register_provenance: Option<HashMap<&'static str, RegisterProvenance>>,
+}
+
+/// A function that was inlined into a `StackFrame`'s function.
+#[derive(Clone, Debug, PartialEq)]
+pub struct InlineFrame {
+    /// The name of the inlined function.
+    pub function_name: String,
+    /// The source file of the location within the inlined function, if known.
+    pub source_file_name: Option<String>,
+    /// The (1-based) source line of the location within the inlined function,
+    /// if known.
+    pub source_line: Option<u32>,
+}
+
+/// Where a single address is, as found by [`symbolize_address`][symbolize_address].
+///
+/// [symbolize_address]: fn.symbolize_address.html
+#[derive(Clone, Debug)]
+pub struct SymbolizedLocation {
+    /// The address that was looked up.
+    pub address: u64,
+    /// The module that covers `address`, if it's in a loaded module.
+    pub module: Option<MinidumpModule>,
+    /// The unloaded modules that cover `address`, if it's not in a loaded module.
+    ///
+    /// If symbols were found, they came from the first module.
+    pub unloaded_modules: Vec<MinidumpUnloadedModule>,
+    /// The function containing `address`, if symbols are available.
+    pub function_name: Option<String>,
+    /// The start address of the function, if symbols are available.
+    pub function_base: Option<u64>,
+    /// The source file of `address`, if symbols are available.
+    pub source_file_name: Option<String>,
+    /// The (1-based) source line of `address`, if symbols are available.
+    pub source_line: Option<u32>,
+    /// Functions that were inlined into `function_name` at `address`,
+    /// innermost first, as for a `StackFrame`.
+    pub inlines: Vec<InlineFrame>,
+}
+
+impl SymbolizedLocation {
+    pub(crate) fn new(address: u64) -> SymbolizedLocation {
+        SymbolizedLocation {
+            address,
+            module: None,
+            unloaded_modules: vec![],
+            function_name: None,
+            function_base: None,
+            source_file_name: None,
+            source_line: None,
+            inlines: vec![],
+        }
+    }
+}
+
+impl FrameSymbolizer for SymbolizedLocation {
+    fn get_instruction(&self) -> u64 {
+        self.address
+    }
+    fn set_function(&mut self, name: &str, base: u64, _parameter_size: u32) {
+        self.function_name = Some(String::from(name));
+        self.function_base = Some(base);
+    }
+    fn set_source_file(&mut self, file: &str, line: u32, _base: u64) {
+        self.source_file_name = Some(String::from(file));
+        self.source_line = Some(line);
+    }
+    fn add_inline_frame(&mut self, name: &str, file: Option<&str>, line: Option<u32>) {
+        self.inlines.push(InlineFrame {
+            function_name: String::from(name),
+            source_file_name: file.map(String::from),
+            source_line: line,
+        });
+    }
 }
 
 /// Information about the results of unwinding a thread's stack.
@@ -104,10 +250,31 @@ pub enum CallStackInfo {
     MissingContext,
     /// No stack memory was provided, couldn't unwind past the top frame.
     MissingMemory,
-    /// The CPU type is unsupported.
+    /// The CPU type is unsupported, or the CPU context wasn't recognized.
     UnsupportedCpu,
     /// This thread wrote the minidump, it was skipped.
     DumpThreadSkipped,
+    /// This thread wasn't asked for, so it wasn't walked.
+    ///
+    /// See `ProcessorOptions::walk_threads`.
+    NotWalked,
+    /// Only part of the stack memory was captured, and the walk stopped at
+    /// its end.
+    TruncatedStackMemory,
+}
+
+impl CallStackInfo {
+    fn json_name(&self) -> &'static str {
+        match *self {
+            CallStackInfo::Ok => "ok",
+            CallStackInfo::MissingContext => "missing_context",
+            CallStackInfo::MissingMemory => "missing_memory",
+            CallStackInfo::UnsupportedCpu => "unsupported_cpu",
+            CallStackInfo::DumpThreadSkipped => "dump_thread_skipped",
+            CallStackInfo::NotWalked => "not_walked",
+            CallStackInfo::TruncatedStackMemory => "truncated_stack_memory",
+        }
+    }
 }
 
 /// A stack of `StackFrame`s produced as a result of unwinding a thread.
@@ -119,18 +286,316 @@ pub struct CallStack {
     pub frames: Vec<StackFrame>,
     /// Information about this `CallStack`.
     pub info: CallStackInfo,
+    /// The name of the thread, if known.
+    ///
+    /// Threads with an empty name have no name.
+    pub thread_name: Option<String>,
+    /// If `frames` was truncated, the number of frames the stack had before
+    /// truncation.
+    pub total_frames: Option<usize>,
+    /// The context flags of the thread's CPU context, if they didn't identify
+    /// a known CPU.
+    ///
+    /// Such threads have `CallStackInfo::UnsupportedCpu` and no frames.
+    pub context_flags: Option<u32>,
+    /// How the stackwalker found each frame, and why the other ways of
+    /// unwinding it didn't work, one line per step.
+    ///
+    /// This is only recorded if [`ProcessorOptions::unwind_diagnostics`][diagnostics]
+    /// is set, for debugging wrong stacks.
+    ///
+    /// [diagnostics]: struct.ProcessorOptions.html#structfield.unwind_diagnostics
+    pub unwind_diagnostics: Option<Vec<String>>,
+    /// The thread's id, if the stack belongs to a thread in the thread list.
+    pub thread_id: Option<u32>,
+    /// How many times the thread was suspended, if the thread list says.
+    pub suspend_count: Option<u32>,
+    /// The Windows priority class of the thread's process, if the thread list says.
+    pub priority_class: Option<u32>,
+    /// The Windows priority of the thread within its priority class, if the
+    /// thread list says.
+    pub priority: Option<i32>,
+    /// The address of the thread's Thread Environment Block, if it has one.
+    pub teb: Option<u64>,
+    /// The exception record's context, if the stack was walked from it rather
+    /// than from the thread's own context.
+    ///
+    /// That's only ever the case for the thread that crashed. This is the
+    /// context of the faulting instruction, and the first frame's context.
+    pub exception_context: Option<MinidumpContext>,
+    /// The context the thread list gives the thread, if the stack was walked
+    /// from the exception record's context instead.
+    ///
+    /// When a crash is caught in-process this is usually the context of the
+    /// exception handler, not of the crash. It's kept to compare the two.
+    pub thread_context: Option<MinidumpContext>,
+}
+
+/// Options controlling the JSON output of [`ProcessState::print_json_with_options`][print].
+///
+/// [print]: struct.ProcessState.html#method.print_json_with_options
+#[derive(Clone, Debug)]
+pub struct JsonOptions {
+    /// Whether addresses and offsets are written as hex strings, or as numbers.
+    ///
+    /// Hex strings are what the Socorro schema expects, so this defaults to `true`.
+    pub hex_offsets: bool,
+    /// The order to list the crashing thread's registers in.
+    pub register_order: RegisterOrder,
+    /// Whether every frame gets a `registers` object, rather than only the
+    /// crashing thread's first frame.
+    ///
+    /// Each frame also gets a `register_validity` of `"all"` or a list of the
+    /// registers the stackwalker recovered, and a `register_provenance` saying
+    /// how it recovered each register (`null` for the context frame). This
+    /// makes the output much larger, so it defaults to `false`.
+    pub full_registers: bool,
+    /// Maps source files to URLs, giving frames and inlined functions with
+    /// source information a `file_url` and a `line_url`.
+    ///
+    /// Frames whose source file has no URL don't get them. Defaults to `None`,
+    /// see [`VcsSourceUrls`][vcs] for one that works for Mozilla's symbols.
+    ///
+    /// [vcs]: struct.VcsSourceUrls.html
+    pub source_urls: Option<SharedSourceUrlResolver>,
+    /// Whether the crashing thread gets a `stack_memory` object with its
+    /// captured stack, base64 encoded, and the address it starts at.
+    ///
+    /// That's enough to walk the stack again later without the minidump, but
+    /// it's large and may hold sensitive data, so it defaults to `false`.
+    pub stack_memory: bool,
+}
+
+impl Default for JsonOptions {
+    fn default() -> JsonOptions {
+        JsonOptions {
+            hex_offsets: true,
+            register_order: RegisterOrder::default(),
+            full_registers: false,
+            source_urls: None,
+            stack_memory: false,
+        }
+    }
+}
+
+/// Options controlling the human-readable output of
+/// [`ProcessState::print_with_options`][print].
+///
+/// [print]: struct.ProcessState.html#method.print_with_options
+#[derive(Clone, Copy, Debug, Default)]
+pub struct PrintOptions {
+    /// Whether to highlight the crash reason, thread headings, module and
+    /// function names, and how each frame was found, with ANSI escape codes.
+    ///
+    /// This is for terminals, so it defaults to `false`. Without it, the output
+    /// is the same as `print`'s.
+    pub color: bool,
+}
+
+impl PrintOptions {
+    /// `text`, wrapped in the escape codes for `style` if `color` is on.
+    fn paint<T: fmt::Display>(&self, style: Style, text: T) -> Painted<T> {
+        Painted {
+            style: if self.color { Some(style) } else { None },
+            text,
+        }
+    }
+}
+
+/// How `PrintOptions::color` highlights a part of the report.
+#[derive(Clone, Copy)]
+enum Style {
+    /// Headings and function names.
+    Bold,
+    /// The crash, and frames found by scanning the stack.
+    Red,
+    /// Warnings, and frames found by less reliable means.
+    Yellow,
+    /// Frames found by reliable means.
+    Green,
+    /// Module names.
+    Cyan,
+}
+
+impl Style {
+    /// The ANSI select graphic rendition code of the style.
+    fn code(self) -> &'static str {
+        match self {
+            Style::Bold => "1",
+            Style::Red => "31",
+            Style::Yellow => "33",
+            Style::Green => "32",
+            Style::Cyan => "36",
+        }
+    }
+}
+
+/// Text that's displayed in a `Style`, or plainly if it has none.
+struct Painted<T> {
+    style: Option<Style>,
+    text: T,
+}
+
+impl<T: fmt::Display> fmt::Display for Painted<T> {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        match self.style {
+            Some(style) => write!(f, "\x1b[{}m{}\x1b[0m", style.code(), self.text),
+            None => write!(f, "{}", self.text),
+        }
+    }
+}
+
+/// Options controlling how [`ProcessState::crash_signature_with_options`][signature]
+/// picks the frames of a crash signature.
+///
+/// [signature]: struct.ProcessState.html#method.crash_signature_with_options
+#[derive(Clone, Debug)]
+pub struct SignatureOptions {
+    /// The most frames to put in a signature.
+    pub max_frames: usize,
+    /// Functions to leave out of signatures.
+    ///
+    /// These are functions like `abort` that are on top of the stack in many
+    /// unrelated crashes, so including them would lump those crashes together.
+    /// They're matched against normalized function names, without parameters.
+    /// An entry ending in `*` matches every function starting with the rest of it.
+    pub skip_functions: Vec<String>,
+}
+
+impl Default for SignatureOptions {
+    fn default() -> SignatureOptions {
+        let skip_functions = [
+            "abort",
+            "raise",
+            "__GI_abort",
+            "__GI_raise",
+            "pthread_kill",
+            "__pthread_kill*",
+            "KiFastSystemCallRet",
+            "RaiseException",
+            "RtlRaiseException",
+            "_CxxThrowException",
+            "__cxa_throw",
+            "rust_panic",
+            "__rust_start_panic",
+            "std::panicking::*",
+            "core::panicking::*",
+            "panic_abort::*",
+            "std::process::abort",
+        ];
+        SignatureOptions {
+            max_frames: 3,
+            skip_functions: skip_functions.iter().map(|&name| name.to_owned()).collect(),
+        }
+    }
+}
+
+impl SignatureOptions {
+    /// Whether `function`, a normalized function name, should be left out of signatures.
+    fn skips(&self, function: &str) -> bool {
+        self.skip_functions.iter().any(|skip| {
+            if skip.ends_with('*') {
+                function.starts_with(&skip[..skip.len() - 1])
+            } else {
+                function == skip
+            }
+        })
+    }
+}
+
+/// A copy of the stack memory captured for a thread.
+#[derive(Clone, Debug, PartialEq)]
+pub struct StackMemory {
+    /// The address of the first byte of the stack memory.
+    pub base_address: u64,
+    /// The contents of the stack memory.
+    pub bytes: Vec<u8>,
+}
+
+/// What was found when a module's symbols were looked for.
+///
+/// Both the report and the JSON output describe a module's symbols by this.
+#[derive(Clone, Debug, PartialEq)]
+pub enum ModuleSymbolStatus {
+    /// Symbols were found and loaded.
+    Loaded {
+        /// Whether they were in the disk cache of downloaded symbol files.
+        from_cache: bool,
+        /// The URL they were downloaded from, if they were downloaded.
+        url: Option<String>,
+        /// How long downloading them took, in milliseconds.
+        fetch_ms: Option<f64>,
+    },
+    /// No symbols were found, or they could not be read.
+    Missing,
+    /// A symbol file was found but could not be parsed.
+    Corrupt {
+        /// The first parse error, if the symbol provider knows it.
+        detail: Option<SymbolParseError>,
+    },
+}
+
+impl ModuleSymbolStatus {
+    /// Whether the symbols were loaded, missing, or corrupt.
+    pub fn status(&self) -> SymbolStatus {
+        match *self {
+            ModuleSymbolStatus::Loaded { .. } => SymbolStatus::Loaded,
+            ModuleSymbolStatus::Missing => SymbolStatus::Missing,
+            ModuleSymbolStatus::Corrupt { .. } => SymbolStatus::Corrupt,
+        }
+    }
+
+    /// Why the module has no usable symbols, as the report puts it, or `None`
+    /// if the symbols were loaded.
+    pub fn problem(&self) -> Option<Cow<'static, str>> {
+        match *self {
+            ModuleSymbolStatus::Loaded { .. } => None,
+            ModuleSymbolStatus::Missing => Some(Cow::Borrowed("No symbols")),
+            ModuleSymbolStatus::Corrupt {
+                detail: Some(ref e),
+            } => Some(Cow::Owned(format!("Corrupt symbols at line {}", e.line))),
+            ModuleSymbolStatus::Corrupt { detail: None } => Some(Cow::Borrowed("Corrupt symbols")),
+        }
+    }
+}
+
+/// Statistics about the address space of a process, for diagnosing
+/// out-of-memory crashes caused by address space fragmentation.
+#[derive(Clone, Copy, Debug, Default, PartialEq)]
+pub struct VirtualMemoryStats {
+    /// The size of the largest free region of the address space.
+    pub largest_free_block: u64,
+    /// The total size of free regions too small to allocate from.
+    pub tiny_block_size: u64,
+    /// The total size of write-combined memory.
+    pub write_combine_size: u64,
 }
 
 /// The state of a process as recorded by a `Minidump`.
 pub struct ProcessState {
     /// The PID of the process.
     pub process_id: Option<u32>,
-    /// When the minidump was written.
-    pub time: DateTime<Utc>,
-    /// When the process started, if available
+    /// When the minidump was written, unless the timestamp is implausible.
+    pub time: Option<DateTime<Utc>>,
+    /// When the minidump was written, in seconds since the Unix epoch, exactly
+    /// as its header records it.
+    ///
+    /// Unlike `time`, this is kept even if it's implausible.
+    pub raw_time: u32,
+    /// When the process started, if available and plausible.
     pub process_create_time: Option<DateTime<Utc>>,
+    /// When the process started, in seconds since the Unix epoch, exactly as
+    /// the minidump records it, if it does.
+    pub raw_process_create_time: Option<u32>,
+    /// The process's data execution prevention settings, if available.
+    pub process_execute_flags: Option<ProcessExecuteFlags>,
+    /// Whether the process was a protected process, if available.
+    pub protected_process: Option<bool>,
     /// If the process crashed, a `CrashReason` describing the crash reason.
     pub crash_reason: Option<CrashReason>,
+    /// If the process crashed, the exception record's parameters, interpreted
+    /// for the OS that produced the minidump.
+    pub exception_details: Option<ExceptionDetails>,
     /// The memory address implicated in the crash.
     ///
     /// If the process crashed, and if the crash reason implicates memory,
@@ -139,6 +604,18 @@ pub struct ProcessState {
     /// errors, this will be the address of the instruction that caused the
     /// fault.
     pub crash_address: Option<u64>,
+    /// If the process crashed, the chain of nested exception records, starting
+    /// with the crash's own record.
+    ///
+    /// Nested records that aren't in the dumped memory are missing, so this
+    /// often only has the one.
+    pub exception_chain: Vec<MINIDUMP_EXCEPTION>,
+    /// The exception records of any other exception streams, with the id of
+    /// the thread each one names, in stream directory order.
+    ///
+    /// Some producers write an exception stream for each thread that crashed.
+    /// The crash is described by the last one, which isn't included here.
+    pub other_exceptions: Vec<(u32, MINIDUMP_EXCEPTION)>,
     /// A string describing an assertion that was hit, if present.
     pub assertion: Option<String>,
     /// The index of the thread that requested a dump be written.
@@ -150,9 +627,35 @@ pub struct ProcessState {
     /// extended Breakpad information is present, this field will be
     /// `None`.
     pub requesting_thread: Option<usize>,
+    /// The index of the thread that wrote the dump, which was skipped rather
+    /// than walked.
+    ///
+    /// This is the thread the Breakpad information names, or one whose stack
+    /// [looks like it's writing a dump][dump_writer], unless that's disabled in
+    /// the `ProcessorOptions`.
+    ///
+    /// [dump_writer]: struct.CallStack.html#method.is_dump_writer
+    pub dump_thread: Option<usize>,
+    /// The CPU context from the exception record, if present.
+    ///
+    /// This is the state of the requesting thread when it crashed, and is what
+    /// its stack was walked from. The thread's own context usually describes
+    /// the exception handler instead.
+    pub exception_context: Option<MinidumpContext>,
     /// Stacks for each thread (except possibly the exception handler
     /// thread) at the time of the crash.
     pub threads: Vec<CallStack>,
+    /// The stack memory of the requesting thread, if it was captured.
+    pub requesting_thread_stack: Option<StackMemory>,
+    /// The code around the instruction the crash happened at, if it was captured.
+    ///
+    /// This is up to 16 bytes before the instruction and 32 bytes from it on,
+    /// as far as the dumped memory goes. Minidumps only have it if they
+    /// include module memory or the code was generated at runtime. See
+    /// [`crash_instruction_bytes`][bytes].
+    ///
+    /// [bytes]: #method.crash_instruction_bytes
+    pub crash_instruction_memory: Option<MemoryWindow>,
     // TODO:
     // thread_memory_regions
     /// Information about the system on which the minidump was written.
@@ -160,12 +663,63 @@ pub struct ProcessState {
     /// The modules that were loaded into the process represented by the
     /// `ProcessState`.
     pub modules: MinidumpModuleList,
+    /// What was found when the symbols for each module in `modules` were
+    /// looked for, in the same order. `None` for modules whose symbols were
+    /// never looked for because no frame needed them.
+    pub symbol_status: Vec<Option<ModuleSymbolStatus>>,
     pub unloaded_modules: MinidumpUnloadedModuleList,
-    // modules_without_symbols
-    // modules_with_corrupt_symbols
+    /// The regions of the process's address space and their protection, if the
+    /// minidump describes them.
+    pub memory_info: Option<MinidumpMemoryInfoList>,
+    /// Statistics about the process's address space, if the minidump describes it.
+    pub virtual_memory: Option<VirtualMemoryStats>,
+    /// Streams that are present in the minidump but couldn't be read, and why.
+    ///
+    /// Processing carries on without them, as if they were missing.
+    pub corrupt_streams: Vec<(MINIDUMP_STREAM_TYPE, StreamError)>,
+    /// The process's environment variables that
+    /// [`ProcessorOptions::environment`][options] chose to keep, with the values
+    /// it gave them, in the order they were listed.
+    ///
+    /// `None` if that's not set or the minidump has no environment.
+    ///
+    /// [options]: struct.ProcessorOptions.html#structfield.environment
+    pub environment: Option<Vec<(String, String)>>,
+    /// Metadata the client submitted along with the minidump, such as crash
+    /// annotations or the product name and version.
+    ///
+    /// See [`ProcessState::with_extra_metadata`][extra].
+    ///
+    /// [extra]: struct.ProcessState.html#method.with_extra_metadata
+    pub annotations: Option<serde_json::Value>,
     // exploitability
 }
 
+impl VirtualMemoryStats {
+    /// Compute statistics from the regions of the address space in `memory_info`.
+    pub fn from_memory_info(memory_info: &MinidumpMemoryInfoList) -> VirtualMemoryStats {
+        // Windows reserves address space in 64KiB units, so smaller free
+        // regions are wasted.
+        const ALLOCATION_GRANULARITY: u64 = 0x10000;
+        let mut stats = VirtualMemoryStats::default();
+        for region in memory_info.iter() {
+            let size = region.raw.region_size;
+            if region.is_free() {
+                stats.largest_free_block = stats.largest_free_block.max(size);
+                if size < ALLOCATION_GRANULARITY {
+                    stats.tiny_block_size = stats.tiny_block_size.saturating_add(size);
+                }
+            } else if region
+                .protection()
+                .contains(MemoryProtection::PAGE_WRITECOMBINE)
+            {
+                stats.write_combine_size = stats.write_combine_size.saturating_add(size);
+            }
+        }
+        stats
+    }
+}
+
 impl FrameTrust {
     /// Return a string describing how a stack frame was found
     /// by the stackwalker.
@@ -176,11 +730,23 @@ impl FrameTrust {
             FrameTrust::CallFrameInfo => "call frame info",
             FrameTrust::CfiScan => "call frame info with scanning",
             FrameTrust::FramePointer => "previous frame's frame pointer",
+            FrameTrust::FunctionEntry => "return address at function entry",
             FrameTrust::Scan => "stack scanning",
             FrameTrust::None => "unknown",
         }
     }
 
+    /// How `PrintOptions::color` highlights frames found this way.
+    fn style(&self) -> Style {
+        match *self {
+            FrameTrust::Context | FrameTrust::PreWalked | FrameTrust::CallFrameInfo => Style::Green,
+            FrameTrust::CfiScan | FrameTrust::FramePointer | FrameTrust::FunctionEntry => {
+                Style::Yellow
+            }
+            FrameTrust::Scan | FrameTrust::None => Style::Red,
+        }
+    }
+
     fn json_name(&self) -> &'static str {
         match *self {
             FrameTrust::Context => "context",
@@ -188,33 +754,190 @@ impl FrameTrust {
             FrameTrust::CallFrameInfo => "cfi",
             FrameTrust::CfiScan => "cfi_scan",
             FrameTrust::FramePointer => "frame_pointer",
+            FrameTrust::FunctionEntry => "function_entry",
             FrameTrust::Scan => "scan",
             FrameTrust::None => "non",
         }
     }
+
+    /// The name Sentry uses for this trust level in a frame's `trust` field.
+    fn sentry_name(&self) -> &'static str {
+        match *self {
+            FrameTrust::Context => "context",
+            FrameTrust::PreWalked => "prewalked",
+            FrameTrust::CallFrameInfo => "cfi",
+            FrameTrust::CfiScan => "cfi-scan",
+            FrameTrust::FramePointer => "fp",
+            // Sentry has no name for this. Like frame pointers, it relies on
+            // the code following the calling convention.
+            FrameTrust::FunctionEntry => "fp",
+            FrameTrust::Scan => "scan",
+            FrameTrust::None => "none",
+        }
+    }
 }
 
 impl StackFrame {
     /// Create a `StackFrame` from a `MinidumpContext`.
+    ///
+    /// On 32-bit ARM, the lowest bit of the program counter says whether the
+    /// code is Thumb, and isn't part of the instruction's address. It's cleared
+    /// from `instruction`, which modules and symbols are looked up with, but
+    /// kept in the context's registers.
     pub fn from_context(context: MinidumpContext, trust: FrameTrust) -> StackFrame {
+        let mut instruction = context.get_instruction_pointer();
+        if let MinidumpRawContext::Arm(_) = context.raw {
+            instruction &= !1;
+        }
         StackFrame {
-            instruction: context.get_instruction_pointer(),
+            instruction,
             module: None,
+            unloaded_modules: vec![],
+            mapping: None,
             function_name: None,
             function_base: None,
             parameter_size: None,
             source_file_name: None,
             source_line: None,
             source_line_base: None,
+            inlines: vec![],
             trust,
+            inconsistent_module: false,
             context,
+            register_provenance: None,
         }
     }
 
+    /// Create a `StackFrame` for a caller, from the context the stack walker
+    /// recovered for it.
+    ///
+    /// The context's instruction pointer is the return address, so the frame's
+    /// `instruction` is moved back from it into the instruction that made the
+    /// call. How far depends on the architecture:
+    ///
+    /// * x86 and amd64: one byte, which is within the variable-length `CALL`.
+    /// * ARM: the Thumb bit is cleared, then two bytes, which is within either a
+    ///   Thumb or an ARM `BL`.
+    /// * ARM64 and PowerPC: one instruction, since they're all 4 bytes.
+    /// * MIPS and SPARC: two instructions, past the branch delay slot.
+    ///
+    /// Without this, a call that is the last instruction of a function would be
+    /// attributed to whatever follows it.
+    pub fn from_caller_context(context: MinidumpContext, trust: FrameTrust) -> StackFrame {
+        // `from_context` has already cleared the Thumb bit of ARM addresses.
+        let mut frame = StackFrame::from_context(context, trust);
+        let distance = match frame.context.raw {
+            MinidumpRawContext::X86(_) | MinidumpRawContext::Amd64(_) => 1,
+            MinidumpRawContext::Arm(_) => 2,
+            MinidumpRawContext::Arm64(_)
+            | MinidumpRawContext::OldArm64(_)
+            | MinidumpRawContext::Ppc(_)
+            | MinidumpRawContext::Ppc64(_) => 4,
+            MinidumpRawContext::Mips(_)
+            | MinidumpRawContext::Mips64(_)
+            | MinidumpRawContext::Sparc(_) => 8,
+        };
+        // Leave addresses too small to have a call before them alone, they're
+        // bogus anyway.
+        if let Some(instruction) = frame.instruction.checked_sub(distance) {
+            frame.instruction = instruction;
+        }
+        frame
+    }
+
+    /// How the stack walker got `register`, or `None` if it didn't record
+    /// provenance for this frame.
+    ///
+    /// Registers it didn't record anything for are `RegisterProvenance::Unknown`.
+    pub fn register_provenance(&self, register: &str) -> Option<RegisterProvenance> {
+        self.register_provenance.as_ref().map(|provenance| {
+            provenance
+                .get(register)
+                .cloned()
+                .unwrap_or(RegisterProvenance::Unknown)
+        })
+    }
+
     /// Return the actual return address, as saved on the stack or in a
     /// register. See the comments for `StackFrame::instruction` for details.
     pub fn return_address(&self) -> u64 {
-        self.instruction
+        self.context.get_instruction_pointer()
+    }
+}
+
+/// The frame's location on one line, as minidump_stackwalk writes it:
+/// `module!function [file : line + 0xoffset]`, or as much of that as is known.
+impl fmt::Display for StackFrame {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        self.write_location(f, &PrintOptions::default())
+    }
+}
+
+impl StackFrame {
+    /// Write the frame's location the way `Display` does, highlighted as
+    /// `options` say.
+    fn write_location<W: fmt::Write>(&self, f: &mut W, options: &PrintOptions) -> fmt::Result {
+        let addr = self.instruction;
+        let module: Option<&dyn Module> = match (&self.module, self.unloaded_modules.first()) {
+            (Some(module), _) => Some(module),
+            (None, Some(module)) => Some(module),
+            (None, None) => None,
+        };
+        if let Some(module) = module {
+            if self.module.is_some() {
+                write!(
+                    f,
+                    "{}",
+                    options.paint(Style::Cyan, basename(&module.code_file()))
+                )?;
+            } else {
+                let names = self
+                    .unloaded_modules
+                    .iter()
+                    .map(|module| basename(&module.name))
+                    .collect::<Vec<_>>();
+                write!(
+                    f,
+                    "(unloaded {})",
+                    options.paint(Style::Cyan, names.join("|"))
+                )?;
+            }
+            if let (&Some(ref function), &Some(ref function_base)) =
+                (&self.function_name, &self.function_base)
+            {
+                write!(f, "!{}", options.paint(Style::Bold, function))?;
+                if let (
+                    &Some(ref source_file),
+                    &Some(ref source_line),
+                    &Some(ref source_line_base),
+                ) = (
+                    &self.source_file_name,
+                    &self.source_line,
+                    &self.source_line_base,
+                ) {
+                    write!(
+                        f,
+                        " [{} : {} + {:#x}]",
+                        basename(&source_file),
+                        source_line,
+                        addr - source_line_base
+                    )
+                } else {
+                    write!(f, " + {:#x}", addr - function_base)
+                }
+            } else {
+                write!(f, " + {:#x}", addr - module.base_address())
+            }
+        } else if let Some(ref mapping) = self.mapping {
+            write!(
+                f,
+                "{} + {:#x}",
+                options.paint(Style::Cyan, basename(mapping.name())),
+                addr - mapping.base_address
+            )
+        } else {
+            write!(f, "{:#x}", addr)
+        }
     }
 }
 
@@ -232,6 +955,13 @@ impl FrameSymbolizer for StackFrame {
         self.source_line = Some(line);
         self.source_line_base = Some(base);
     }
+    fn add_inline_frame(&mut self, name: &str, file: Option<&str>, line: Option<u32>) {
+        self.inlines.push(InlineFrame {
+            function_name: String::from(name),
+            source_file_name: file.map(String::from),
+            source_line: line,
+        });
+    }
 }
 
 fn basename(f: &str) -> &str {
@@ -241,6 +971,62 @@ fn basename(f: &str) -> &str {
     }
 }
 
+/// Strip the parameter list and qualifiers from a symbolized function name.
+///
+/// Symbol files for C++ include the parameters in function names, which makes
+/// overloads distinct but also splits up signatures whenever a parameter type
+/// is renamed.
+fn normalize_function(function: &str) -> &str {
+    let function = function.trim_end();
+    let function = if function.ends_with(" const") {
+        &function[..function.len() - " const".len()]
+    } else {
+        function
+    };
+    if !function.ends_with(')') {
+        return function;
+    }
+    // Find the parenthesis that opens the trailing parameter list.
+    let mut depth = 0;
+    for (index, c) in function.char_indices().rev() {
+        match c {
+            ')' => depth += 1,
+            '(' => {
+                depth -= 1;
+                if depth == 0 {
+                    let name = function[..index].trim_end();
+                    // Nothing before it means the name is entirely in parentheses,
+                    // like `(anonymous namespace)`, so it isn't a parameter list.
+                    return if name.is_empty() { function } else { name };
+                }
+            }
+            _ => {}
+        }
+    }
+    function
+}
+
+/// The name of `frame` in a crash signature.
+///
+/// This is `module!function` if the frame was symbolized, `module@0xoffset` if
+/// it's in a module without symbols, or `@0xaddress` if it's in no module.
+fn signature_frame_name(frame: &StackFrame) -> String {
+    let module = match (&frame.module, frame.unloaded_modules.first()) {
+        (Some(module), _) => Some((basename(&module.name), module.base_address())),
+        (None, Some(module)) => Some((basename(&module.name), module.base_address())),
+        (None, None) => None,
+    };
+    match (module, &frame.function_name) {
+        (Some((module, _)), Some(function)) => {
+            format!("{}!{}", module, normalize_function(function))
+        }
+        (Some((module, base)), None) => {
+            format!("{}@{:#x}", module, frame.instruction.wrapping_sub(base))
+        }
+        (None, _) => format!("@{:#x}", frame.instruction),
+    }
+}
+
 fn print_registers<T: Write>(f: &mut T, ctx: &MinidumpContext) -> io::Result<()> {
     let registers: Cow<HashSet<&str>> = match ctx.valid {
         MinidumpContextValidity::All => {
@@ -271,7 +1057,7 @@ fn print_registers<T: Write>(f: &mut T, ctx: &MinidumpContext) -> io::Result<()>
     Ok(())
 }
 
-fn json_registers(ctx: &MinidumpContext) -> serde_json::Value {
+fn json_registers(ctx: &MinidumpContext, order: RegisterOrder) -> serde_json::Value {
     let registers: Cow<HashSet<&str>> = match ctx.valid {
         MinidumpContextValidity::All => {
             let gpr = ctx.general_purpose_registers();
@@ -281,22 +1067,234 @@ fn json_registers(ctx: &MinidumpContext) -> serde_json::Value {
         MinidumpContextValidity::Some(ref which) => Cow::Borrowed(which),
     };
 
-    let mut output = serde_json::Map::new();
-    for &reg in ctx.general_purpose_registers() {
-        if registers.contains(reg) {
-            let reg_val = ctx.format_register(reg);
-            output.insert(String::from(reg), json!(reg_val));
+    ordered_object(
+        ctx.registers_in_order(order)
+            .iter()
+            .filter(|&&reg| registers.contains(reg))
+            .map(|&reg| (reg, json!(ctx.format_register(reg)))),
+    )
+}
+
+/// How each of a frame's registers was recovered, or `null` if that wasn't recorded.
+fn json_register_provenance(frame: &StackFrame, order: RegisterOrder) -> serde_json::Value {
+    if frame.register_provenance.is_none() {
+        return serde_json::Value::Null;
+    }
+    ordered_object(
+        frame
+            .context
+            .registers_in_order(order)
+            .iter()
+            .filter_map(|&reg| {
+                let provenance = frame.register_provenance(reg)?;
+                Some((reg, json!(provenance.json_name())))
+            }),
+    )
+}
+
+/// The key `ordered_object` keeps an object's entries under.
+const ORDERED_OBJECT: &str = "\u{0}ordered";
+
+/// A JSON object whose entries are written in the order `entries` gives them.
+///
+/// `serde_json::Map` sorts its keys, so the entries are kept as
+/// `{ORDERED_OBJECT: [[key, value], ...]}` until `JsonReport` writes them out
+/// as an object.
+fn ordered_object<'a, I>(entries: I) -> serde_json::Value
+where
+    I: Iterator<Item = (&'a str, serde_json::Value)>,
+{
+    let entries = entries
+        .map(|(key, value)| json!([key, value]))
+        .collect::<Vec<_>>();
+    json!({ ORDERED_OBJECT: entries })
+}
+
+/// Writes a report built with `json!`, with the objects from `ordered_object`
+/// in their order.
+struct JsonReport<'a>(&'a serde_json::Value);
+
+impl<'a> Serialize for JsonReport<'a> {
+    fn serialize<S: Serializer>(&self, serializer: S) -> Result<S::Ok, S::Error> {
+        match *self.0 {
+            serde_json::Value::Object(ref object) => {
+                let ordered = match object.get(ORDERED_OBJECT) {
+                    Some(serde_json::Value::Array(entries)) if object.len() == 1 => entries,
+                    _ => {
+                        let mut map = serializer.serialize_map(Some(object.len()))?;
+                        for (key, value) in object {
+                            map.serialize_entry(key, &JsonReport(value))?;
+                        }
+                        return map.end();
+                    }
+                };
+                let mut map = serializer.serialize_map(Some(ordered.len()))?;
+                for entry in ordered {
+                    map.serialize_entry(&entry[0], &JsonReport(&entry[1]))?;
+                }
+                map.end()
+            }
+            serde_json::Value::Array(ref array) => {
+                let mut seq = serializer.serialize_seq(Some(array.len()))?;
+                for value in array {
+                    seq.serialize_element(&JsonReport(value))?;
+                }
+                seq.end()
+            }
+            ref value => value.serialize(serializer),
+        }
+    }
+}
+
+/// Write `report` to `f` with `JsonReport`.
+fn write_json_report<T: Write>(
+    f: &mut T,
+    report: &serde_json::Value,
+    pretty: bool,
+) -> Result<(), serde_json::Error> {
+    if pretty {
+        serde_json::to_writer_pretty(f, &JsonReport(report))
+    } else {
+        serde_json::to_writer(f, &JsonReport(report))
+    }
+}
+
+/// Add the `file_url` and `line_url` of `source_file` and `source_line` to the
+/// JSON object of a frame or inlined function, if `source_urls` knows them.
+fn add_source_urls(
+    json: &mut serde_json::Value,
+    source_urls: &SharedSourceUrlResolver,
+    module: Option<&MinidumpModule>,
+    source_file: Option<&str>,
+    source_line: Option<u32>,
+) {
+    let source_file = match source_file {
+        Some(source_file) => source_file,
+        None => return,
+    };
+    if let Some(url) = source_urls.source_url(module, source_file, None) {
+        json["file_url"] = json!(url);
+    }
+    if let Some(line) = source_line {
+        if let Some(url) = source_urls.source_url(module, source_file, Some(line)) {
+            json["line_url"] = json!(url);
+        }
+    }
+}
+
+/// Which of a context's registers are valid: `"all"`, or a sorted list of their names.
+fn json_register_validity(valid: &MinidumpContextValidity) -> serde_json::Value {
+    match *valid {
+        MinidumpContextValidity::All => json!("all"),
+        MinidumpContextValidity::Some(ref which) => {
+            let mut names = which.iter().collect::<Vec<_>>();
+            names.sort();
+            json!(names)
+        }
+    }
+}
+
+/// Encode `bytes` as standard, padded base64.
+fn base64(bytes: &[u8]) -> String {
+    const ALPHABET: &[u8; 64] = b"ABCDEFGHIJKLMNOPQRSTUVWXYZabcdefghijklmnopqrstuvwxyz0123456789+/";
+    let mut encoded = String::with_capacity(bytes.len().div_ceil(3) * 4);
+    for chunk in bytes.chunks(3) {
+        let group = chunk.iter().enumerate().fold(0u32, |group, (i, &byte)| {
+            group | u32::from(byte) << (16 - 8 * i)
+        });
+        for i in 0..4 {
+            if i <= chunk.len() {
+                let index = (group >> (18 - 6 * i)) & 0x3f;
+                encoded.push(ALPHABET[index as usize] as char);
+            } else {
+                encoded.push('=');
+            }
         }
     }
-    json!(output)
+    encoded
 }
 
+/// How many of a thread's innermost frames are looked at to tell whether it
+/// wrote the minidump.
+const DUMP_WRITER_FRAMES: usize = 16;
+
+/// Functions that only run on the thread writing a minidump.
+const DUMP_WRITER_FUNCTIONS: &[&str] = &[
+    "MiniDumpWriteDump",
+    "google_breakpad::ExceptionHandler::ExceptionHandlerThreadMain",
+    "google_breakpad::ExceptionHandler::WriteMinidumpWithException",
+];
+
+/// Modules that do nothing but write minidumps. `dbghelp.dll` also walks
+/// stacks and loads symbols, so it isn't one.
+const DUMP_WRITER_MODULES: &[&str] = &["dbgcore.dll"];
+
 impl CallStack {
     /// Create a `CallStack` with `info` and no frames.
     pub fn with_info(info: CallStackInfo) -> CallStack {
         CallStack {
             info,
             frames: vec![],
+            thread_name: None,
+            total_frames: None,
+            context_flags: None,
+            unwind_diagnostics: None,
+            thread_id: None,
+            suspend_count: None,
+            priority_class: None,
+            priority: None,
+            teb: None,
+            exception_context: None,
+            thread_context: None,
+        }
+    }
+
+    /// Drop all but the first `max_frames` frames, recording the original number
+    /// of frames in `total_frames`.
+    pub fn truncate_frames(&mut self, max_frames: usize) {
+        if self.frames.len() > max_frames {
+            self.total_frames = Some(self.total_frames.unwrap_or(self.frames.len()));
+            self.frames.truncate(max_frames);
+        }
+    }
+
+    /// Record the id, suspend count, priorities and TEB the thread list gives `thread`.
+    pub(crate) fn set_thread_state(&mut self, thread: &MinidumpThread<'_>) {
+        self.thread_id = Some(thread.raw.thread_id);
+        self.suspend_count = Some(thread.suspend_count());
+        self.priority_class = Some(thread.priority_class());
+        self.priority = Some(thread.priority());
+        self.teb = thread.teb();
+    }
+
+    /// Whether this looks like the stack of the thread that wrote the minidump.
+    ///
+    /// That's the case if one of its innermost frames is in `MiniDumpWriteDump`
+    /// or Breakpad's exception handler thread, going by their symbols, or in
+    /// `dbgcore.dll`, which implements `MiniDumpWriteDump` on recent Windows.
+    pub fn is_dump_writer(&self) -> bool {
+        self.frames.iter().take(DUMP_WRITER_FRAMES).any(|frame| {
+            let in_function = frame.function_name.as_ref().map_or(false, |name| {
+                DUMP_WRITER_FUNCTIONS
+                    .iter()
+                    .any(|function| name.contains(function))
+            });
+            let in_module = frame.module.as_ref().map_or(false, |module| {
+                let code_file = module.code_file();
+                DUMP_WRITER_MODULES
+                    .iter()
+                    .any(|name| basename(&code_file).eq_ignore_ascii_case(name))
+            });
+            in_function || in_module
+        })
+    }
+
+    /// A label for the thread this call stack belongs to, using its name if
+    /// it has one, or `Thread <index>` otherwise.
+    pub fn thread_label(&self, index: usize) -> Cow<'_, str> {
+        match self.thread_name {
+            Some(ref name) => Cow::Borrowed(name),
+            None => Cow::Owned(format!("Thread {}", index)),
         }
     }
 
@@ -305,46 +1303,67 @@ impl CallStack {
     /// This is very verbose, it implements the output format used by
     /// minidump_stackwalk.
     pub fn print<T: Write>(&self, f: &mut T) -> io::Result<()> {
+        self.print_with_options(f, &PrintOptions::default())
+    }
+
+    /// Like [`print`][print], but highlighted as `options` say.
+    ///
+    /// [print]: #method.print
+    pub fn print_with_options<T: Write>(
+        &self,
+        f: &mut T,
+        options: &PrintOptions,
+    ) -> io::Result<()> {
         if self.frames.is_empty() {
             writeln!(f, "<no frames>")?;
         }
+        if let Some(flags) = self.context_flags {
+            writeln!(f, "Unknown CPU context, context flags {:#010x}", flags)?;
+        }
         for (i, frame) in self.frames.iter().enumerate() {
-            let addr = frame.instruction;
-            write!(f, "{:2}  ", i)?;
-            if let Some(ref module) = frame.module {
-                write!(f, "{}", basename(&module.code_file()))?;
-                if let (&Some(ref function), &Some(ref function_base)) =
-                    (&frame.function_name, &frame.function_base)
+            let mut location = String::new();
+            // Writing to a `String` can't fail.
+            frame.write_location(&mut location, options).unwrap();
+            writeln!(f, "{:2}  {}", i, location)?;
+            // Frames outside of any module or mapping have always been
+            // followed by a blank line.
+            if frame.module.is_none()
+                && frame.unloaded_modules.is_empty()
+                && frame.mapping.is_none()
+            {
+                writeln!(f)?;
+            }
+            for inline in &frame.inlines {
+                write!(f, "    inlined: {}", inline.function_name)?;
+                if let (&Some(ref source_file), &Some(ref source_line)) =
+                    (&inline.source_file_name, &inline.source_line)
                 {
-                    write!(f, "!{}", function)?;
-                    if let (
-                        &Some(ref source_file),
-                        &Some(ref source_line),
-                        &Some(ref source_line_base),
-                    ) = (
-                        &frame.source_file_name,
-                        &frame.source_line,
-                        &frame.source_line_base,
-                    ) {
-                        write!(
-                            f,
-                            " [{} : {} + {:#x}]",
-                            basename(&source_file),
-                            source_line,
-                            addr - source_line_base
-                        )?;
-                    } else {
-                        write!(f, " + {:#x}", addr - function_base)?;
-                    }
-                } else {
-                    write!(f, " + {:#x}", addr - module.base_address())?;
+                    write!(f, " [{} : {}]", basename(source_file), source_line)?;
                 }
-            } else {
-                writeln!(f, "{:#x}", addr)?;
+                writeln!(f)?;
             }
-            writeln!(f)?;
             print_registers(f, &frame.context)?;
-            writeln!(f, "    Found by: {}", frame.trust.description())?;
+            writeln!(
+                f,
+                "    Found by: {}",
+                options.paint(frame.trust.style(), frame.trust.description())
+            )?;
+            if frame.inconsistent_module {
+                writeln!(
+                    f,
+                    "    {}",
+                    options.paint(
+                        Style::Yellow,
+                        "WARNING: module did not contain the instruction"
+                    )
+                )?;
+            }
+        }
+        if let Some(total_frames) = self.total_frames {
+            writeln!(f, "({} frames omitted)", total_frames - self.frames.len())?;
+        }
+        if self.info == CallStackInfo::TruncatedStackMemory {
+            writeln!(f, "(the rest of the stack wasn't captured)")?;
         }
         Ok(())
     }
@@ -357,16 +1376,486 @@ fn eq_some<T: PartialEq>(opt: Option<T>, val: T) -> bool {
     }
 }
 
+/// A section of the report written by `ProcessState::print`.
+#[derive(Clone, Copy)]
+enum ReportSection {
+    /// The operating system, CPU and crash details.
+    Summary,
+    /// The stack of the thread that requested the dump, at index `usize` of the threads.
+    RequestingThread(usize),
+    /// The stack of another thread, at index `usize` of the threads.
+    Thread(usize),
+    /// The loaded and unloaded modules.
+    Modules,
+}
+
 impl ProcessState {
+    /// Attach metadata that was submitted along with the minidump.
+    ///
+    /// Crash reports often pair the minidump with a `.extra` or `.json` file of
+    /// annotations from the client. `print_json` writes them under an
+    /// `"annotations"` key, so the report carries both. If metadata was already
+    /// attached and both are JSON objects, their keys are merged, with `json`
+    /// winning on conflicts. Otherwise `json` replaces what was there.
+    pub fn with_extra_metadata(mut self, json: serde_json::Value) -> ProcessState {
+        self.annotations = Some(match (self.annotations.take(), json) {
+            (Some(serde_json::Value::Object(mut existing)), serde_json::Value::Object(extra)) => {
+                existing.extend(extra);
+                serde_json::Value::Object(existing)
+            }
+            (_, json) => json,
+        });
+        self
+    }
+
+    /// How long the process had been running when the minidump was written.
+    ///
+    /// Returns `None` if either time is unknown, or if the process seems to have
+    /// started after the minidump was written, which means the clock changed.
+    pub fn process_uptime(&self) -> Option<Duration> {
+        let uptime = self.time? - self.process_create_time?;
+        if uptime < Duration::zero() {
+            None
+        } else {
+            Some(uptime)
+        }
+    }
+
+    /// `true` if the process seems to have started after the minidump was
+    /// written, so its uptime can't be known.
+    pub fn has_clock_skew(&self) -> bool {
+        match (self.time, self.process_create_time) {
+            (Some(time), Some(create_time)) => create_time > time,
+            _ => false,
+        }
+    }
+
     /// `true` if the minidump was written in response to a process crash.
     pub fn crashed(&self) -> bool {
         self.crash_reason.is_some() && self.crash_address.is_some()
     }
+
+    /// Whether the process crashed by running out of stack.
+    ///
+    /// That's a Windows `EXCEPTION_STACK_OVERFLOW`, or a memory access fault
+    /// just past the end of the crashing thread's stack, which would otherwise
+    /// look like an ordinary bad pointer:
+    ///
+    /// * If the minidump describes the address space, the fault must be in a
+    ///   guard page or an uncommitted part of the allocation holding the stack.
+    ///   Windows reserves each thread's stack as one allocation and commits it
+    ///   from the top down.
+    /// * Otherwise, the fault must be within a page below the stack pointer,
+    ///   where the stack would have grown to.
+    pub fn is_stack_overflow(&self) -> bool {
+        const PAGE_SIZE: u64 = 0x1000;
+
+        if let Some(CrashReason::Windows(ExceptionCodeWindows::EXCEPTION_STACK_OVERFLOW)) =
+            self.crash_reason
+        {
+            return true;
+        }
+        let address = match self.exception_details {
+            Some(ExceptionDetails::AccessViolation { address, .. })
+            | Some(ExceptionDetails::MachBadAccess { address, .. })
+            | Some(ExceptionDetails::SignalFault { address, .. }) => address,
+            _ => return false,
+        };
+        let context = self.exception_context.as_ref().or_else(|| {
+            let stack = self.threads.get(self.requesting_thread?)?;
+            stack.frames.first().map(|frame| &frame.context)
+        });
+        let stack_pointer = match context {
+            Some(context) => context.get_stack_pointer(),
+            None => return false,
+        };
+        match self.memory_info {
+            Some(ref memory_info) => {
+                let (fault, stack) = match (
+                    memory_info.memory_info_at_address(address),
+                    memory_info.memory_info_at_address(stack_pointer),
+                ) {
+                    (Some(fault), Some(stack)) => (fault, stack),
+                    _ => return false,
+                };
+                fault.raw.allocation_base == stack.raw.allocation_base
+                    && (fault.protection().contains(MemoryProtection::PAGE_GUARD)
+                        || !fault.state().contains(MemoryState::MEM_COMMIT))
+            }
+            None => address < stack_pointer && stack_pointer - address < PAGE_SIZE,
+        }
+    }
+
+    /// The name of the module whose address range covers `address`, if any.
+    ///
+    /// Loaded modules are checked first. If `address` isn't in any of them,
+    /// the name of an unloaded module covering it is returned instead.
+    pub fn module_name_at_address(&self, address: u64) -> Option<&str> {
+        match self.modules.module_at_address(address) {
+            Some(module) => Some(&module.name),
+            None => self
+                .unloaded_modules
+                .module_at_address(address)
+                .map(|module| &module.name[..]),
+        }
+    }
+
+    /// Look up the module, function and source line of `address` with
+    /// `symbol_provider`, the same way stack frames are.
+    ///
+    /// See [`symbolize_address`][symbolize_address].
+    ///
+    /// [symbolize_address]: fn.symbolize_address.html
+    pub fn symbolize_address<P>(
+        &self,
+        address: u64,
+        symbol_provider: &P,
+    ) -> Option<SymbolizedLocation>
+    where
+        P: SymbolProvider,
+    {
+        stackwalker::symbolize_address(
+            address,
+            &self.modules,
+            &self.unloaded_modules,
+            symbol_provider,
+        )
+    }
+
+    /// Whether `address` was in executable memory when the minidump was written.
+    ///
+    /// The protection of the region containing `address` is used if the
+    /// minidump describes it. Otherwise an address in a loaded module is
+    /// assumed to be executable. Returns `None` if neither says anything about
+    /// `address`.
+    pub fn is_executable_address(&self, address: u64) -> Option<bool> {
+        let region = self
+            .memory_info
+            .as_ref()
+            .and_then(|memory_info| memory_info.memory_info_at_address(address));
+        match region {
+            Some(region) => Some(region.is_executable()),
+            None => self.modules.module_at_address(address).map(|_| true),
+        }
+    }
+
+    /// Return `len` bytes of the requesting thread's stack, starting at its stack pointer.
+    ///
+    /// Returns `None` if there is no requesting thread, or if its captured stack memory
+    /// does not contain all `len` bytes.
+    pub fn crash_stack_bytes(&self, len: usize) -> Option<Vec<u8>> {
+        let frame = self.threads.get(self.requesting_thread?)?.frames.first()?;
+        let stack = self.requesting_thread_stack.as_ref()?;
+        let offset = frame
+            .context
+            .get_stack_pointer()
+            .checked_sub(stack.base_address)?;
+        let start = usize::try_from(offset).ok()?;
+        let end = start.checked_add(len)?;
+        stack.bytes.get(start..end).map(<[u8]>::to_vec)
+    }
+
+    /// Copy the requesting thread's stack memory from `before` bytes below its stack
+    /// pointer to `after` bytes above it.
+    ///
+    /// The window is clipped to the stack memory that was captured, which usually
+    /// starts at the stack pointer. Returns `None` if there is no requesting thread,
+    /// or if none of the window was captured.
+    pub fn stack_window(&self, before: u64, after: u64) -> Option<MemoryWindow> {
+        let frame = self.threads.get(self.requesting_thread?)?.frames.first()?;
+        let stack = self.requesting_thread_stack.as_ref()?;
+        let sp = frame.context.get_stack_pointer();
+        let stack_end = stack.base_address.saturating_add(stack.bytes.len() as u64);
+        let start = sp.saturating_sub(before).max(stack.base_address);
+        let end = sp.saturating_add(after).min(stack_end);
+        if start >= end {
+            return None;
+        }
+        let offset = usize::try_from(start - stack.base_address).ok()?;
+        let len = usize::try_from(end - start).ok()?;
+        Some(MemoryWindow {
+            range: start..end,
+            bytes: stack.bytes[offset..offset + len].to_vec(),
+        })
+    }
+
+    /// Return the captured bytes of the code at the crash's instruction pointer,
+    /// for disassembling the instruction that crashed.
+    ///
+    /// These run from the instruction to the end of `crash_instruction_memory`,
+    /// which is enough for any instruction. Returns `None` if there is no
+    /// exception context, or if the code at its instruction pointer wasn't
+    /// captured in the dump.
+    pub fn crash_instruction_bytes(&self) -> Option<&[u8]> {
+        let pc = self.exception_context.as_ref()?.get_instruction_pointer();
+        let memory = self.crash_instruction_memory.as_ref()?;
+        if !memory.range.contains(&pc) {
+            return None;
+        }
+        let start = usize::try_from(pc - memory.range.start).ok()?;
+        memory.bytes.get(start..)
+    }
+
+    /// Disassemble the instruction that crashed and return its mnemonic, like
+    /// "mov".
+    ///
+    /// Only x86, amd64, arm and arm64 code can be disassembled. Returns `None`
+    /// for other CPUs, if [`crash_instruction_bytes`][bytes] has nothing, or if
+    /// the bytes aren't a valid instruction.
+    ///
+    /// This needs the `capstone` feature.
+    ///
+    /// [bytes]: #method.crash_instruction_bytes
+    #[cfg(feature = "capstone")]
+    pub fn crash_instruction_mnemonic(&self) -> Option<String> {
+        use capstone::arch::{self, BuildsCapstone};
+        use capstone::Capstone;
+
+        let context = self.exception_context.as_ref()?;
+        let pc = context.get_instruction_pointer();
+        let bytes = self.crash_instruction_bytes()?;
+        let capstone = match context.raw {
+            MinidumpRawContext::X86(_) => Capstone::new()
+                .x86()
+                .mode(arch::x86::ArchMode::Mode32)
+                .build(),
+            MinidumpRawContext::Amd64(_) => Capstone::new()
+                .x86()
+                .mode(arch::x86::ArchMode::Mode64)
+                .build(),
+            MinidumpRawContext::Arm(ref ctx) => {
+                // The T bit of the CPSR says the code is Thumb.
+                let mode = if ctx.cpsr & 0x20 != 0 {
+                    arch::arm::ArchMode::Thumb
+                } else {
+                    arch::arm::ArchMode::Arm
+                };
+                Capstone::new().arm().mode(mode).build()
+            }
+            MinidumpRawContext::Arm64(_) | MinidumpRawContext::OldArm64(_) => Capstone::new()
+                .arm64()
+                .mode(arch::arm64::ArchMode::Arm)
+                .build(),
+            _ => return None,
+        }
+        .ok()?;
+        let instructions = capstone.disasm_count(bytes, pc, 1).ok()?;
+        let instruction = instructions.iter().next()?;
+        instruction.mnemonic().map(String::from)
+    }
+
+    /// The frames of the crashing thread that make up its crash signature.
+    ///
+    /// These are the innermost frames, leaving out any whose function is in
+    /// `options.skip_functions`, up to `options.max_frames` of them. Inlined
+    /// functions aren't included, only the functions they were inlined into.
+    /// This is empty if there is no crashing thread.
+    pub fn signature_frames(&self, options: &SignatureOptions) -> Vec<&StackFrame> {
+        let thread = match self.requesting_thread.and_then(|i| self.threads.get(i)) {
+            Some(thread) => thread,
+            None => return vec![],
+        };
+        thread
+            .frames
+            .iter()
+            .filter(|frame| match frame.function_name {
+                Some(ref function) => !options.skips(normalize_function(function)),
+                None => true,
+            })
+            .take(options.max_frames)
+            .collect()
+    }
+
+    /// A signature for grouping crashes, like Socorro's.
+    ///
+    /// This uses the default [`SignatureOptions`][options]. Returns `None` if
+    /// there are no frames to make a signature from.
+    ///
+    /// [options]: struct.SignatureOptions.html
+    pub fn crash_signature(&self) -> Option<String> {
+        self.crash_signature_with_options(&SignatureOptions::default())
+    }
+
+    /// Like [`crash_signature`][signature], but with frames picked as
+    /// configured by `options`.
+    ///
+    /// The [signature frames][frames] are named `module!function`, or by their
+    /// offset in their module if they have no symbols, and joined with ` | `.
+    /// Parameter lists are left out of function names, so the signature doesn't
+    /// change when they do. A [stack overflow][overflow] starts with
+    /// `stack overflow`, so it isn't grouped with bad pointer crashes in the
+    /// same functions.
+    ///
+    /// [signature]: #method.crash_signature
+    /// [frames]: #method.signature_frames
+    /// [overflow]: #method.is_stack_overflow
+    pub fn crash_signature_with_options(&self, options: &SignatureOptions) -> Option<String> {
+        let frames = self.signature_frames(options);
+        if frames.is_empty() {
+            return None;
+        }
+        let overflow = if self.is_stack_overflow() {
+            Some(String::from("stack overflow"))
+        } else {
+            None
+        };
+        let names = overflow
+            .into_iter()
+            .chain(frames.into_iter().map(signature_frame_name))
+            .collect::<Vec<_>>();
+        Some(names.join(" | "))
+    }
+
+    /// What was found when the symbols for `module`, one of `self.modules`,
+    /// were looked for.
+    ///
+    /// Returns `None` if the module's symbols were never looked for.
+    pub fn module_symbols(&self, module: &MinidumpModule) -> Option<&ModuleSymbolStatus> {
+        self.modules
+            .iter()
+            .position(|m| std::ptr::eq(m, module))
+            .and_then(|i| self.symbol_status.get(i))
+            .and_then(|status| status.as_ref())
+    }
+
+    /// Whether symbols were loaded, missing, or corrupt for `module`, one of `self.modules`.
+    ///
+    /// Returns `None` if the module's symbols were never looked for.
+    pub fn module_symbol_status(&self, module: &MinidumpModule) -> Option<SymbolStatus> {
+        self.module_symbols(module).map(ModuleSymbolStatus::status)
+    }
+
+    /// Why the symbols for `module`, one of `self.modules`, couldn't be parsed.
+    pub fn module_symbol_parse_error(&self, module: &MinidumpModule) -> Option<&SymbolParseError> {
+        match self.module_symbols(module) {
+            Some(ModuleSymbolStatus::Corrupt { detail }) => detail.as_ref(),
+            _ => None,
+        }
+    }
+
+    /// The modules whose symbol files were found but couldn't be parsed, with
+    /// the first parse error if it's known.
+    pub fn modules_with_corrupt_symbols(
+        &self,
+    ) -> Vec<(&MinidumpModule, Option<&SymbolParseError>)> {
+        self.modules
+            .iter()
+            .filter(|module| self.module_symbol_status(module) == Some(SymbolStatus::Corrupt))
+            .map(|module| (module, self.module_symbol_parse_error(module)))
+            .collect()
+    }
     /// Write a human-readable description of the process state to `f`.
     ///
     /// This is very verbose, it implements the output format used by
-    /// minidump_stackwalk.
+    /// minidump_stackwalk. It's the lines of [`report_lines`][lines], written
+    /// one after another.
+    ///
+    /// [lines]: #method.report_lines
     pub fn print<T: Write>(&self, f: &mut T) -> io::Result<()> {
+        self.print_with_options(f, &PrintOptions::default())
+    }
+
+    /// Like [`print`][print], but highlighted as `options` say.
+    ///
+    /// [print]: #method.print
+    pub fn print_with_options<T: Write>(
+        &self,
+        f: &mut T,
+        options: &PrintOptions,
+    ) -> io::Result<()> {
+        for line in self.report_lines_with_options(options) {
+            writeln!(f, "{}", line)?;
+        }
+        Ok(())
+    }
+
+    /// The lines of the report written by [`print`][print], without line endings.
+    ///
+    /// The report is rendered a section at a time as the lines are consumed, so
+    /// consumers can page, filter or colorize a report with thousands of
+    /// threads without buffering all of it. Each thread's stack is one section.
+    ///
+    /// [print]: #method.print
+    pub fn report_lines(&self) -> impl Iterator<Item = String> + '_ {
+        self.report_lines_with_options(&PrintOptions::default())
+    }
+
+    /// The lines of the report written by [`print_with_options`][print].
+    ///
+    /// [print]: #method.print_with_options
+    pub fn report_lines_with_options(
+        &self,
+        options: &PrintOptions,
+    ) -> impl Iterator<Item = String> + '_ {
+        let options = *options;
+        let threads = self
+            .requesting_thread
+            .map(ReportSection::RequestingThread)
+            .into_iter()
+            .chain(
+                self.threads
+                    .iter()
+                    .enumerate()
+                    .filter(move |&(i, stack)| {
+                        // Don't print the requesting thread again, the dump
+                        // thread, or threads that weren't walked.
+                        !eq_some(self.requesting_thread, i)
+                            && stack.info != CallStackInfo::DumpThreadSkipped
+                            && stack.info != CallStackInfo::NotWalked
+                    })
+                    .map(|(i, _)| ReportSection::Thread(i)),
+            );
+        std::iter::once(ReportSection::Summary)
+            .chain(threads)
+            .chain(std::iter::once(ReportSection::Modules))
+            .flat_map(move |section| {
+                let mut text = vec![];
+                // Writing to a `Vec` can't fail.
+                self.print_section(section, &options, &mut text).unwrap();
+                String::from_utf8_lossy(&text)
+                    .lines()
+                    .map(String::from)
+                    .collect::<Vec<_>>()
+            })
+    }
+
+    /// Write one section of the report written by `print` to `f`.
+    fn print_section<T: Write>(
+        &self,
+        section: ReportSection,
+        options: &PrintOptions,
+        f: &mut T,
+    ) -> io::Result<()> {
+        match section {
+            ReportSection::Summary => self.print_summary(options, f),
+            ReportSection::RequestingThread(i) => {
+                let label = format!(
+                    "{} ({})",
+                    self.threads[i].thread_label(i),
+                    if self.crashed() {
+                        "crashed"
+                    } else {
+                        "requested dump, did not crash"
+                    }
+                );
+                writeln!(f, "{}", options.paint(Style::Bold, label))?;
+                self.threads[i].print_with_options(f, options)?;
+                writeln!(f)
+            }
+            ReportSection::Thread(i) => {
+                writeln!(
+                    f,
+                    "{}",
+                    options.paint(Style::Bold, self.threads[i].thread_label(i))
+                )?;
+                self.threads[i].print_with_options(f, options)
+            }
+            ReportSection::Modules => self.print_modules(options, f),
+        }
+    }
+
+    /// Write the system and crash details that start the report to `f`.
+    fn print_summary<T: Write>(&self, options: &PrintOptions, f: &mut T) -> io::Result<()> {
         writeln!(f, "Operating system: {}", self.system_info.os.long_name())?;
         if let Some(ref ver) = self.system_info.os_version {
             writeln!(f, "                  {}", ver)?;
@@ -385,6 +1874,12 @@ impl ProcessState {
                 ""
             }
         )?;
+        if let Some(ref environment) = self.environment {
+            for (i, (name, value)) in environment.iter().enumerate() {
+                let label = if i == 0 { "Environment:" } else { "" };
+                writeln!(f, "{:<13}{}={}", label, name, value)?;
+            }
+        }
         writeln!(f)?;
 
         if let (&Some(ref reason), &Some(ref address)) = (&self.crash_reason, &self.crash_address) {
@@ -393,47 +1888,54 @@ impl ProcessState {
                 "Crash reason:  {}
 Crash address: {:#x}
 ",
-                reason, address
+                options.paint(Style::Red, reason),
+                address
             )?;
+            if self.is_stack_overflow() {
+                writeln!(f, "Stack overflow: the thread ran out of stack")?;
+            }
         } else {
             writeln!(f, "No crash")?;
         }
-        if let Some(ref assertion) = self.assertion {
-            writeln!(f, "Assertion: {}", assertion)?;
-        }
-        if let Some(ref time) = self.process_create_time {
-            let uptime = self.time - *time;
-            writeln!(f, "Process uptime: {} seconds", uptime.num_seconds())?;
-        } else {
-            writeln!(f, "Process uptime: not available")?;
+        for record in self.exception_chain.iter().skip(1) {
+            writeln!(
+                f,
+                "Nested exception: {} at {:#x}",
+                CrashReason::from_record(record, self.system_info.os),
+                ExceptionDetails::from_record(record, self.system_info.os).address()
+            )?;
         }
-        writeln!(f)?;
-
-        if let Some(requesting_thread) = self.requesting_thread {
+        for (thread_id, record) in &self.other_exceptions {
             writeln!(
                 f,
-                "Thread {} ({})",
-                requesting_thread,
-                if self.crashed() {
-                    "crashed"
-                } else {
-                    "requested dump, did not crash"
-                }
+                "Other exception: {} at {:#x} in thread {:#x}",
+                CrashReason::from_record(record, self.system_info.os),
+                ExceptionDetails::from_record(record, self.system_info.os).address(),
+                thread_id
             )?;
-            self.threads[requesting_thread].print(f)?;
-            writeln!(f)?;
         }
-        for (i, stack) in self.threads.iter().enumerate() {
-            if eq_some(self.requesting_thread, i) {
-                // Don't print the requesting thread again,
-                continue;
-            }
-            if stack.info == CallStackInfo::DumpThreadSkipped {
-                continue;
+        if let Some(ref assertion) = self.assertion {
+            writeln!(f, "Assertion: {}", options.paint(Style::Red, assertion))?;
+        }
+        match self.process_uptime() {
+            Some(uptime) => writeln!(f, "Process uptime: {} seconds", uptime.num_seconds())?,
+            None if self.has_clock_skew() => {
+                writeln!(f, "Process uptime: not available (clock skew)")?
             }
-            writeln!(f, "Thread {}", i)?;
-            stack.print(f)?;
+            None => writeln!(f, "Process uptime: not available")?,
+        }
+        if let Some(dump_thread) = self.dump_thread {
+            writeln!(
+                f,
+                "Dump thread: {} (skipped)",
+                self.threads[dump_thread].thread_label(dump_thread)
+            )?;
         }
+        writeln!(f)
+    }
+
+    /// Write the lists of loaded and unloaded modules that end the report to `f`.
+    fn print_modules<T: Write>(&self, options: &PrintOptions, f: &mut T) -> io::Result<()> {
         write!(
             f,
             "
@@ -442,18 +1944,29 @@ Loaded modules:
         )?;
         let main_address = self.modules.main_module().map(|m| m.base_address());
         for module in self.modules.by_addr() {
-            // TODO: missing symbols, corrupt symbols
             write!(
                 f,
                 "{:#010x} - {:#010x}  {}  {}",
                 module.base_address(),
                 module.base_address() + module.size() - 1,
-                basename(&module.code_file()),
+                options.paint(Style::Cyan, basename(&module.code_file())),
                 module.version().unwrap_or(Cow::Borrowed("???"))
             )?;
             if eq_some(main_address, module.base_address()) {
                 write!(f, "  (main)")?;
             }
+            let problem = self
+                .module_symbols(module)
+                .and_then(ModuleSymbolStatus::problem);
+            if let Some(problem) = problem {
+                let warning = format!(
+                    "WARNING: {}, {}, {}",
+                    problem,
+                    basename(&module.debug_file().unwrap_or(Cow::Borrowed(""))),
+                    module.debug_identifier().unwrap_or(Cow::Borrowed(""))
+                );
+                write!(f, "  ({})", options.paint(Style::Yellow, warning))?;
+            }
             writeln!(f)?;
         }
         write!(
@@ -468,18 +1981,43 @@ Unloaded modules:
                 "{:#010x} - {:#010x}  {}",
                 module.base_address(),
                 module.base_address() + module.size() - 1,
-                basename(&module.code_file()),
+                options.paint(Style::Cyan, basename(&module.code_file())),
             )?;
         }
         Ok(())
     }
 
     /// Outputs json in a schema compatible with mozilla's Socorro crash reporting servers.
+    ///
+    /// This uses the default [`JsonOptions`][options]: the crashing thread's registers
+    /// are listed in the conventional order for its CPU, and addresses are hex strings.
+    /// Use [`print_json_with_options`][with_options] to change these.
+    ///
+    /// [options]: struct.JsonOptions.html
+    /// [with_options]: #method.print_json_with_options
     pub fn print_json<T: Write>(&self, f: &mut T, pretty: bool) -> Result<(), serde_json::Error> {
+        self.print_json_with_options(f, pretty, &JsonOptions::default())
+    }
+
+    /// Like [`print_json`][print_json], but formatted as configured by `options`.
+    ///
+    /// [print_json]: #method.print_json
+    pub fn print_json_with_options<T: Write>(
+        &self,
+        f: &mut T,
+        pretty: bool,
+        options: &JsonOptions,
+    ) -> Result<(), serde_json::Error> {
         let sys = &self.system_info;
 
-        // Curry self for use in `map`
-        let json_hex = |val: u64| -> String { self.json_hex(val) };
+        // Curry self and options for use in `map`
+        let json_address = |val: u64| -> serde_json::Value {
+            if options.hex_offsets {
+                json!(self.json_hex(val))
+            } else {
+                json!(val)
+            }
+        };
 
         let mut output = json!({
             // TODO: I guess we should still produce some JSON in some failure modes?
@@ -489,6 +2027,12 @@ Unloaded modules:
                 // Linux | Windows NT | Mac OS X
                 "os": sys.os.long_name(),
                 "os_ver": sys.os_version,
+                // the parts of os_ver, so it needn't be parsed
+                "os_major": sys.os_major,
+                "os_minor": sys.os_minor,
+                "os_build": sys.os_build,
+                // Service Pack 2 | null
+                "os_service_pack": sys.os_service_pack,
                 // x86 | amd64 | arm | ppc | sparc
                 "cpu_arch": sys.cpu.to_string(),
                 "cpu_info": sys.cpu_info,
@@ -498,12 +2042,31 @@ Unloaded modules:
                 "cpu_microcode_version": null,
             },
             "crash_info": {
-                // TODO: Issue #22
-                "type": "TODO",
-                "address": self.crash_address.map(json_hex),
+                // EXCEPTION_ACCESS_VIOLATION_READ | SIGSEGV / SEGV_MAPERR | ...
+                "type": self.crash_reason.map(|reason| reason.to_string()),
+                "address": self.crash_address.map(json_address),
                 // thread index | null
                 "crashing_thread": self.requesting_thread,
+                // thread index | null, the thread that wrote the dump and was skipped
+                "dump_thread": self.dump_thread,
                 "assertion": self.assertion,
+                // whether the crash looks like the thread ran out of stack
+                "stack_overflow": self.is_stack_overflow(),
+                // the crash's exception record, then the records nested in it
+                "exception_chain": self.exception_chain.iter().map(|record| json!({
+                    "type": CrashReason::from_record(record, sys.os).to_string(),
+                    "address": json_address(
+                        ExceptionDetails::from_record(record, sys.os).address()
+                    ),
+                })).collect::<Vec<_>>(),
+                // the exception records of the other exception streams, if any
+                "other_exceptions": self.other_exceptions.iter().map(|(thread_id, record)| json!({
+                    "thread_id": thread_id,
+                    "type": CrashReason::from_record(record, sys.os).to_string(),
+                    "address": json_address(
+                        ExceptionDetails::from_record(record, sys.os).address()
+                    ),
+                })).collect::<Vec<_>>(),
             },
 
             // optional, Linux Standard Base information
@@ -515,96 +2078,157 @@ Unloaded modules:
             //   "description": <string>
             // },
 
-            // the first module is always the main one
-            "main_module": 0,
+            // index of the main module in "modules" | null
+            "main_module": self.modules.main_module_index(),
             // TODO: Issue #171
             "modules_contains_cert_info": false,
-            "modules": self.modules.iter().map(|module| json!({
-                "base_addr": json_hex(module.raw.base_of_image),
-                // filename | empty string
-                "debug_file": basename(module.debug_file().unwrap_or(Cow::Borrowed("")).borrow()),
-                // [[:xdigit:]]{33} | empty string
-                "debug_id": module.debug_identifier().unwrap_or(Cow::Borrowed("")),
-                "end_addr": json_hex(module.raw.base_of_image + module.raw.size_of_image as u64),
-                "filename": module.name,
-                "code_id": module.code_identifier(),
-                "version": module.version(),
-
-                // These are all just metrics for debugging minidump-processor's execution
-
-                // optional, if mdsw looked for the file and it does exist
-                // "loaded_symbols": true,
-                // optional, if mdsw looked for the file and it doesn't exist
-                // "missing_symbols": true,
-                // optional, if mdsw found a file that has parse errors
-                // "corrupt_symbols": true,
-                // optional, whether or not the SYM file was fetched from disk cache
-                // "symbol_disk_cache_hit": <bool>,
-                // optional, time in ms it took to fetch symbol file from url; omitted
-                // if the symbol file was in disk cache
-                // "symbols_fetch_time": <float>,
-                // optional, url of symbol file
-                // "symbol_url": <string>
-
-                // TODO: Issue #171
-                // optional
-                // "cert_subject": <string>
+            "modules": self.modules.iter().map(|module| {
+                let mut module_json = json!({
+                    "base_addr": json_address(module.raw.base_of_image),
+                    // filename | empty string
+                    "debug_file": basename(module.debug_file().unwrap_or(Cow::Borrowed("")).borrow()),
+                    // [[:xdigit:]]{33} | empty string
+                    "debug_id": module.debug_identifier().unwrap_or(Cow::Borrowed("")),
+                    "end_addr": json_address(module.raw.base_of_image + module.raw.size_of_image as u64),
+                    "filename": module.name,
+                    "code_id": module.code_identifier(),
+                    "version": module.version(),
+                    // optional, the structured VS_FIXEDFILEINFO of Windows modules
+                    "version_info": module.version_info().map(|info| json!({
+                        "file_version": info.file_version_string(),
+                        "product_version": info.product_version_string(),
+                        "file_flags": format!("{:#010x}", info.file_flags.bits()),
+                        "file_os": format!("{:#010x}", info.file_os),
+                        "file_type": format!("{:#010x}", info.file_type),
+                        "file_subtype": format!("{:#010x}", info.file_subtype),
+                    })),
 
-            })).collect::<Vec<_>>(),
+                    // TODO: Issue #171
+                    // optional
+                    // "cert_subject": <string>
+
+                });
+                match self.module_symbols(module) {
+                    // optional, if mdsw looked for the file and it does exist
+                    Some(ModuleSymbolStatus::Loaded { from_cache, url, fetch_ms }) => {
+                        module_json["loaded_symbols"] = json!(true);
+                        // These are all just metrics for debugging minidump-processor's execution
+
+                        // optional, whether or not the SYM file was fetched from disk cache
+                        module_json["symbol_disk_cache_hit"] = json!(from_cache);
+                        // optional, time in ms it took to fetch symbol file from url; omitted
+                        // if the symbol file was in disk cache
+                        if let Some(fetch_ms) = fetch_ms {
+                            module_json["symbols_fetch_time"] = json!(fetch_ms);
+                        }
+                        // optional, url of symbol file
+                        if let Some(url) = url {
+                            module_json["symbol_url"] = json!(url);
+                        }
+                    }
+                    // optional, if mdsw looked for the file and it doesn't exist
+                    Some(ModuleSymbolStatus::Missing) => module_json["missing_symbols"] = json!(true),
+                    // optional, if mdsw found a file that has parse errors
+                    Some(ModuleSymbolStatus::Corrupt { detail }) => {
+                        module_json["corrupt_symbols"] = json!(true);
+                        // optional, the first parse error, if it's known
+                        if let Some(e) = detail {
+                            module_json["symbol_parse_error"] = json!({
+                                "line": e.line,
+                                "reason": e.reason,
+                            });
+                        }
+                    }
+                    None => {}
+                }
+                module_json
+            }).collect::<Vec<_>>(),
             "pid": self.process_id,
+            // optional
+            "process_create_time": self.process_create_time.map(|time| time.to_rfc3339()),
             "thread_count": self.threads.len(),
             "threads": self.threads.iter().map(|thread| json!({
                 "frame_count": thread.frames.len(),
-                // TODO: I think this is legacy gunk that we don't ever do?
-                "frames_truncated": false,
+                "frames_truncated": thread.total_frames.is_some(),
                 // optional, if truncated, this is the original total
-                "total_frames": thread.frames.len(),
+                "total_frames": thread.total_frames.unwrap_or(thread.frames.len()),
                 // TODO: Issue #156
                 // optional
                 "last_error_value": null,
-                // TODO: Issue #173
                 // optional
-                "thread_name": null,
+                "thread_name": thread.thread_name,
+                // ok | missing_context | missing_memory | unsupported_cpu | dump_thread_skipped
+                // | not_walked | truncated_stack_memory
+                "stack_info": thread.info.json_name(),
+                // optional, the context flags of an unknown CPU context
+                "context_flags": thread.context_flags.map(|flags| format!("{:#010x}", flags)),
+                // optional, the thread's scheduling state from the thread list
+                "suspend_count": thread.suspend_count,
+                "priority_class": thread.priority_class,
+                "priority": thread.priority,
+                // optional, the address of the thread environment block
+                "teb": thread.teb.map(json_address),
                 "frames": thread.frames.iter().enumerate().map(|(idx, frame)| json!({
                     "frame": idx,
                     // optional
                     "module": frame.module.as_ref().map(|module| basename(&module.name)),
+                    // optional, any unloaded modules containing the instruction
+                    // if it isn't in a loaded module
+                    "unloaded_modules": frame
+                        .unloaded_modules
+                        .iter()
+                        .map(|module| basename(&module.name))
+                        .collect::<Vec<_>>(),
+                    // optional, the memory mapping containing the instruction
+                    // if it isn't in any module
+                    "mapping": frame.mapping.as_ref().map(|mapping| mapping.name()),
                     // optional
                     "function": frame.function_name,
                     // optional
                     "file": frame.source_file_name,
                     // optional
                     "line": frame.source_line,
-                    "offset": json_hex(frame.instruction),
+                    // functions inlined at this frame's instruction, innermost first
+                    "inlines": frame.inlines.iter().map(|inline| json!({
+                        "function": inline.function_name,
+                        // optional
+                        "file": inline.source_file_name,
+                        // optional
+                        "line": inline.source_line,
+                    })).collect::<Vec<_>>(),
+                    "offset": json_address(frame.instruction),
                     // optional
                     "module_offset": frame
                         .module
                         .as_ref()
                         .map(|module| frame.instruction - module.raw.base_of_image)
-                        .map(json_hex),
+                        .map(json_address),
                     // optional
                     "function_offset": frame
                         .function_base
                         .map(|func_base| frame.instruction - func_base)
-                        .map(json_hex),
+                        .map(json_address),
                     "missing_symbols": frame.function_name.is_none(),
-                    // none | scan | cfi_scan | frame_pointer | cfi | context | prewalked
+                    // none | scan | cfi_scan | frame_pointer | function_entry | cfi | context | prewalked
                     "trust": frame.trust.json_name(),
+                    // the frame's module didn't contain its instruction and was cleared
+                    "inconsistent_module": frame.inconsistent_module,
                 })).collect::<Vec<_>>(),
             })).collect::<Vec<_>>(),
 
-            // TODO: Issue #169
-            // "largest_free_vm_block": 0x000000
-            // "tiny_block_size": <int>,
-            // "write_combine_size": <int>,
-
             "unloaded_modules": self.unloaded_modules.iter().map(|module| json!({
-                "base_addr": json_hex(module.raw.base_of_image),
+                "base_addr": json_address(module.raw.base_of_image),
                 "code_id": module.code_identifier(),
-                "end_addr": json_hex(module.raw.base_of_image + module.raw.size_of_image as u64),
+                "end_addr": json_address(module.raw.base_of_image + module.raw.size_of_image as u64),
                 "filename": module.name,
             })).collect::<Vec<_>>(),
 
+            // streams that are present but couldn't be read
+            "corrupt_streams": self.corrupt_streams.iter().map(|(stream_type, error)| json!({
+                "stream_type": format!("{:?}", stream_type),
+                "error": error.to_string(),
+            })).collect::<Vec<_>>(),
+
             "sensitive": {
                 // TODO: Issue #25
                 // low | medium | high | interesting | none | ERROR: *
@@ -612,6 +2236,87 @@ Unloaded modules:
             }
         });
 
+        if self.process_execute_flags.is_some() || self.protected_process.is_some() {
+            // optional, from MINIDUMP_MISC_INFO_3 and later
+            let flags = self.process_execute_flags;
+            output["process_info"] = json!({
+                "execute_flags": flags.map(|flags| format!("{:#010x}", flags.bits())),
+                "dep_enabled": flags
+                    .map(|flags| flags.contains(ProcessExecuteFlags::MEM_EXECUTE_OPTION_DISABLE)),
+                "dep_permanent": flags
+                    .map(|flags| flags.contains(ProcessExecuteFlags::MEM_EXECUTE_OPTION_PERMANENT)),
+                "atl_thunk_emulation": flags
+                    .map(|flags| !flags.contains(ProcessExecuteFlags::MEM_EXECUTE_OPTION_DISABLE_THUNK_EMULATION)),
+                "protected_process": self.protected_process,
+            });
+        }
+
+        if let Some(ref stats) = self.virtual_memory {
+            // optional, only if the minidump describes the address space
+            output["largest_free_vm_block"] = json_address(stats.largest_free_block);
+            output["tiny_block_size"] = json!(stats.tiny_block_size);
+            output["write_combine_size"] = json!(stats.write_combine_size);
+        }
+
+        if options.full_registers {
+            // optional, the registers of every frame, and which of them are valid
+            let threads = output["threads"].as_array_mut().unwrap();
+            for (thread, thread_json) in self.threads.iter().zip(threads) {
+                let frames = thread_json["frames"].as_array_mut().unwrap();
+                for (frame, frame_json) in thread.frames.iter().zip(frames) {
+                    frame_json["registers"] =
+                        json_registers(&frame.context, options.register_order);
+                    frame_json["register_validity"] = json_register_validity(&frame.context.valid);
+                    frame_json["register_provenance"] =
+                        json_register_provenance(frame, options.register_order);
+                }
+            }
+        }
+
+        if let Some(ref source_urls) = options.source_urls {
+            // optional, links to the source of frames and inlined functions
+            let threads = output["threads"].as_array_mut().unwrap();
+            for (thread, thread_json) in self.threads.iter().zip(threads) {
+                let frames = thread_json["frames"].as_array_mut().unwrap();
+                for (frame, frame_json) in thread.frames.iter().zip(frames) {
+                    let module = frame.module.as_ref();
+                    add_source_urls(
+                        frame_json,
+                        source_urls,
+                        module,
+                        frame.source_file_name.as_deref(),
+                        frame.source_line,
+                    );
+                    let inlines = frame_json["inlines"].as_array_mut().unwrap();
+                    for (inline, inline_json) in frame.inlines.iter().zip(inlines) {
+                        add_source_urls(
+                            inline_json,
+                            source_urls,
+                            module,
+                            inline.source_file_name.as_deref(),
+                            inline.source_line,
+                        );
+                    }
+                }
+            }
+        }
+
+        if let Some(ref environment) = self.environment {
+            // optional, only the variables the processor was asked to keep
+            output["environment"] = json!(environment
+                .iter()
+                .map(|(name, value)| json!({
+                    "name": name,
+                    "value": value,
+                }))
+                .collect::<Vec<_>>());
+        }
+
+        if let Some(ref annotations) = self.annotations {
+            // optional, metadata the client submitted along with the minidump
+            output["annotations"] = annotations.clone();
+        }
+
         if let Some(requesting_thread) = self.requesting_thread {
             // Copy the crashing thread into a top-level "crashing_thread" field and:
             // * Add a "thread_index" field to indicate which thread it was
@@ -621,8 +2326,6 @@ Unloaded modules:
             // of a normal "threads" entry, while the original schema strips
             // many of the fields here. We don't to keep things more uniform.
 
-            let registers = json_registers(&self.threads[requesting_thread].frames[0].context);
-
             // Yuck, spidering through json...
             let mut thread =
                 output.get_mut("threads").unwrap().as_array().unwrap()[requesting_thread].clone();
@@ -632,10 +2335,30 @@ Unloaded modules:
                 .unwrap()
                 .as_array_mut()
                 .unwrap();
-            let frame = frames[0].as_object_mut().unwrap();
-
-            frame.insert(String::from("registers"), registers);
+            // The thread has no frames if it wasn't walked.
+            if let Some(first_frame) = self.threads[requesting_thread].frames.first() {
+                let registers = json_registers(&first_frame.context, options.register_order);
+                let frame = frames[0].as_object_mut().unwrap();
+                frame.insert(String::from("registers"), registers);
+            }
             thread_obj.insert(String::from("thread_index"), json!(requesting_thread));
+            if let Some(ref thread_context) = self.threads[requesting_thread].thread_context {
+                // optional, the thread's own registers if the stack was walked
+                // from the exception record's instead
+                let registers = json_registers(thread_context, options.register_order);
+                thread_obj.insert(String::from("thread_registers"), registers);
+            }
+            if options.stack_memory {
+                // optional, the captured stack, if it was asked for
+                let stack_memory = self.requesting_thread_stack.as_ref().map(|stack| {
+                    json!({
+                        "base_address": json_address(stack.base_address),
+                        "size": stack.bytes.len(),
+                        "data": base64(&stack.bytes),
+                    })
+                });
+                thread_obj.insert(String::from("stack_memory"), json!(stack_memory));
+            }
 
             output
                 .as_object_mut()
@@ -643,22 +2366,320 @@ Unloaded modules:
                 .insert(String::from("crashing_thread"), thread);
         }
 
-        if pretty {
-            serde_json::to_writer_pretty(f, &output)
-        } else {
-            serde_json::to_writer(f, &output)
+        write_json_report(f, &output, pretty)
+    }
+
+    /// Write a JSON representation of the process state as a Sentry event.
+    ///
+    /// The crash reason and address are reported as the event's `exception`, with
+    /// the requesting thread's stack as its `stacktrace`, and every thread is listed
+    /// in `threads`. Threads are identified by their index in `threads`. As Sentry
+    /// expects, frames are listed outermost caller first. Loaded modules are listed
+    /// in `debug_meta` so that Sentry can symbolicate frames that have no symbols.
+    pub fn print_sentry_json<T: Write>(
+        &self,
+        f: &mut T,
+        pretty: bool,
+    ) -> Result<(), serde_json::Error> {
+        let sys = &self.system_info;
+        let image_type = match sys.os {
+            Os::Windows => "pe",
+            Os::MacOs | Os::Ios => "macho",
+            _ => "elf",
+        };
+
+        let stacktrace = |thread: &CallStack| {
+            json!({
+                "frames": thread.frames.iter().rev().map(|frame| json!({
+                    "instruction_addr": self.json_hex(frame.instruction),
+                    // optional
+                    "package": frame.module.as_ref().map(|module| &module.name),
+                    // optional
+                    "function": frame.function_name,
+                    // optional
+                    "filename": frame.source_file_name,
+                    // optional
+                    "lineno": frame.source_line,
+                    // none | scan | cfi-scan | fp | cfi | prewalked | context
+                    "trust": frame.trust.sentry_name(),
+                })).collect::<Vec<_>>(),
+            })
+        };
+
+        let mut output = json!({
+            "platform": "native",
+            "level": "fatal",
+            "timestamp": self.time.map(|time| time.to_rfc3339()),
+            "contexts": {
+                "os": {
+                    "name": sys.os.long_name(),
+                    "version": sys.os_version,
+                },
+                "device": {
+                    "arch": sys.cpu.to_string(),
+                },
+            },
+            "threads": {
+                "values": self.threads.iter().enumerate().map(|(idx, thread)| {
+                    let crashed = self.requesting_thread == Some(idx);
+                    let mut thread_json = json!({
+                        "id": thread.thread_id,
+                        // optional
+                        "name": thread.thread_name,
+                        "crashed": crashed,
+                    });
+                    // The crashing thread's stack is the exception's stacktrace.
+                    if !crashed {
+                        thread_json["stacktrace"] = stacktrace(thread);
+                    }
+                    thread_json
+                }).collect::<Vec<_>>(),
+            },
+            "debug_meta": {
+                "images": self.modules.iter().map(|module| json!({
+                    "type": image_type,
+                    "code_file": module.code_file(),
+                    "code_id": module.code_identifier(),
+                    // optional
+                    "debug_file": module.debug_file(),
+                    // optional
+                    "debug_id": module.debug_identifier(),
+                    "image_addr": self.json_hex(module.base_address()),
+                    "image_size": module.size(),
+                })).collect::<Vec<_>>(),
+            },
+        });
+
+        if let Some(reason) = self.crash_reason {
+            // EXCEPTION_ACCESS_VIOLATION_READ | SIGSEGV / SEGV_MAPERR | ...
+            let reason = reason.to_string();
+            let value = match (&self.assertion, self.crash_address) {
+                (Some(assertion), _) => assertion.clone(),
+                (None, Some(address)) => format!("{} at {}", reason, self.json_hex(address)),
+                (None, None) => reason.clone(),
+            };
+            let mut exception = json!({
+                "type": reason,
+                "value": value,
+                "mechanism": {
+                    "type": "minidump",
+                    "handled": false,
+                    "synthetic": true,
+                },
+            });
+            if let Some(requesting_thread) = self.requesting_thread {
+                let thread = &self.threads[requesting_thread];
+                let mut thread_stacktrace = stacktrace(thread);
+                if let Some(frame) = thread.frames.first() {
+                    thread_stacktrace["registers"] =
+                        json_registers(&frame.context, RegisterOrder::default());
+                }
+                exception["thread_id"] = json!(thread.thread_id);
+                exception["stacktrace"] = thread_stacktrace;
+            }
+            output["exception"] = json!({ "values": [exception] });
+        }
+
+        if let Some(ref annotations) = self.annotations {
+            // optional, metadata the client submitted along with the minidump
+            output["extra"] = annotations.clone();
+        }
+
+        write_json_report(f, &output, pretty)
+    }
+
+    /// Write both the human-readable and the JSON descriptions of the process state.
+    ///
+    /// This is equivalent to calling [`print`][print] and then
+    /// [`print_json_with_options`][print_json], and lets callers that want both outputs
+    /// get them from a single processing pass.
+    ///
+    /// [print]: #method.print
+    /// [print_json]: #method.print_json_with_options
+    pub fn write_all<W1, W2>(
+        &self,
+        human: &mut W1,
+        json: &mut W2,
+        pretty: bool,
+        options: &JsonOptions,
+    ) -> io::Result<()>
+    where
+        W1: Write,
+        W2: Write,
+    {
+        self.print(human)?;
+        self.print_json_with_options(json, pretty, options)?;
+        Ok(())
+    }
+
+    // Whether the process used 64-bit pointers.
+    //
+    // This is the system's CPU, unless there's a CPU context that says
+    // otherwise: a 32-bit process on 64-bit Windows (WOW64) reports an amd64
+    // system, but its threads have x86 contexts.
+    fn is_64bit(&self) -> bool {
+        let context = self.exception_context.as_ref().or_else(|| {
+            self.threads
+                .iter()
+                .find_map(|thread| thread.frames.first())
+                .map(|frame| &frame.context)
+        });
+        match context {
+            Some(context) => context.pointer_width() == 8,
+            None => match self.system_info.cpu {
+                Cpu::X86 | Cpu::Ppc | Cpu::Arm | Cpu::Mips => false,
+                Cpu::X86_64
+                | Cpu::Ppc64
+                | Cpu::Sparc
+                | Cpu::Arm64
+                | Cpu::Mips64
+                | Cpu::Unknown(_) => true,
+            },
         }
     }
 
     // Convert an integer to a hex string, with leading 0's for uniform width.
     fn json_hex(&self, val: u64) -> String {
-        match self.system_info.cpu {
-            Cpu::X86 | Cpu::Ppc | Cpu::Sparc | Cpu::Arm => {
-                format!("0x{:08x}", val)
-            }
-            Cpu::X86_64 | Cpu::Ppc64 | Cpu::Arm64 | Cpu::Unknown(_) => {
-                format!("0x{:016x}", val)
-            }
+        if self.is_64bit() {
+            format!("0x{:016x}", val)
+        } else {
+            format!("0x{:08x}", val)
         }
     }
 }
+
+#[cfg(test)]
+mod test {
+    use super::*;
+    use minidump::format::{ArmRegisterNumbers, CONTEXT_AMD64, CONTEXT_ARM, CONTEXT_ARM64};
+    use scroll::{ctx::SizeWith, Pread, LE};
+
+    #[test]
+    fn test_normalize_function() {
+        assert_eq!(normalize_function("main"), "main");
+        assert_eq!(normalize_function("foo(int, char *)"), "foo");
+        assert_eq!(normalize_function("Foo::bar(int) const"), "Foo::bar");
+        assert_eq!(
+            normalize_function("call(void (*)(int), std::vector<int>)"),
+            "call"
+        );
+        assert_eq!(
+            normalize_function("Foo::operator()(int)"),
+            "Foo::operator()"
+        );
+        assert_eq!(
+            normalize_function("(anonymous namespace)"),
+            "(anonymous namespace)"
+        );
+    }
+
+    #[test]
+    fn test_base64() {
+        assert_eq!(base64(b""), "");
+        assert_eq!(base64(b"f"), "Zg==");
+        assert_eq!(base64(b"fo"), "Zm8=");
+        assert_eq!(base64(b"foo"), "Zm9v");
+        assert_eq!(base64(b"foob"), "Zm9vYg==");
+        assert_eq!(base64(b"fooba"), "Zm9vYmE=");
+        assert_eq!(base64(b"foobar"), "Zm9vYmFy");
+        assert_eq!(base64(&[0xff, 0xfe, 0x00]), "//4A");
+    }
+
+    #[test]
+    fn test_signature_options_skips() {
+        let options = SignatureOptions::default();
+        assert!(options.skips("abort"));
+        assert!(options.skips("__pthread_kill_implementation"));
+        assert!(options.skips("std::panicking::begin_panic_handler"));
+        assert!(!options.skips("abort_with_message"));
+        assert!(!options.skips("main"));
+    }
+
+    fn caller_instruction(raw: MinidumpRawContext) -> u64 {
+        StackFrame::from_caller_context(MinidumpContext::from_raw(raw), FrameTrust::Scan)
+            .instruction
+    }
+
+    #[test]
+    fn test_caller_instruction() {
+        let amd64 = CONTEXT_AMD64 {
+            rip: 0x7fff00001235,
+            ..CONTEXT_AMD64::default()
+        };
+        assert_eq!(
+            caller_instruction(MinidumpRawContext::Amd64(amd64)),
+            0x7fff00001234
+        );
+
+        let arm64 = CONTEXT_ARM64 {
+            pc: 0x7fff00001238,
+            ..CONTEXT_ARM64::default()
+        };
+        assert_eq!(
+            caller_instruction(MinidumpRawContext::Arm64(arm64)),
+            0x7fff00001234
+        );
+
+        // Thumb return addresses have their lowest bit set.
+        let bytes = vec![0; CONTEXT_ARM::size_with(&LE)];
+        let mut arm: CONTEXT_ARM = bytes.pread_with(0, LE).unwrap();
+        arm.iregs[ArmRegisterNumbers::ProgramCounter as usize] = 0x1237;
+        assert_eq!(
+            caller_instruction(MinidumpRawContext::Arm(arm.clone())),
+            0x1234
+        );
+        arm.iregs[ArmRegisterNumbers::ProgramCounter as usize] = 0x1238;
+        assert_eq!(caller_instruction(MinidumpRawContext::Arm(arm)), 0x1236);
+
+        // Addresses that can't be after a call are left alone.
+        let amd64 = CONTEXT_AMD64::default();
+        let frame = StackFrame::from_caller_context(
+            MinidumpContext::from_raw(MinidumpRawContext::Amd64(amd64)),
+            FrameTrust::Scan,
+        );
+        assert_eq!(frame.instruction, 0);
+        assert_eq!(frame.return_address(), 0);
+    }
+
+    fn frame(function: Option<&str>, code_file: &str) -> StackFrame {
+        let mut frame = StackFrame::from_context(
+            MinidumpContext::from_raw(MinidumpRawContext::Amd64(CONTEXT_AMD64::default())),
+            FrameTrust::Context,
+        );
+        frame.function_name = function.map(String::from);
+        frame.module = Some(MinidumpModule::new(0x10000, 0x1000, code_file));
+        frame
+    }
+
+    #[test]
+    fn test_is_dump_writer() {
+        let mut stack = CallStack::with_info(CallStackInfo::Ok);
+        assert!(!stack.is_dump_writer());
+        stack.frames = vec![
+            frame(Some("NtWaitForSingleObject"), "C:\\Windows\\ntdll.dll"),
+            frame(None, "C:\\Windows\\dbghelp.dll"),
+            frame(Some("main"), "C:\\test.exe"),
+        ];
+        assert!(!stack.is_dump_writer());
+
+        stack.frames[1].function_name = Some(String::from("MiniDumpWriteDump"));
+        assert!(stack.is_dump_writer());
+
+        stack.frames[1] = frame(None, "C:\\Windows\\System32\\DBGCORE.DLL");
+        assert!(stack.is_dump_writer());
+
+        stack.frames[1] = frame(
+            Some("google_breakpad::ExceptionHandler::ExceptionHandlerThreadMain(void *)"),
+            "C:\\test.exe",
+        );
+        assert!(stack.is_dump_writer());
+
+        // Only the innermost frames count.
+        let mut frames = (0..DUMP_WRITER_FRAMES)
+            .map(|_| frame(Some("work"), "C:\\test.exe"))
+            .collect::<Vec<_>>();
+        frames.push(frame(Some("MiniDumpWriteDump"), "C:\\Windows\\dbghelp.dll"));
+        stack.frames = frames;
+        assert!(!stack.is_dump_writer());
+    }
+}
@@ -1,22 +1,56 @@
 // Copyright 2015 Ted Mielczarek. See the COPYRIGHT
 // file at the top-level directory of this distribution.
 
-use chrono::{TimeZone, Utc};
+use chrono::{DateTime, Duration, TimeZone, Utc};
 use failure::Fail;
+use log::warn;
 
 use std::boxed::Box;
 use std::ops::Deref;
 
-use breakpad_symbols::{FrameSymbolizer, FrameWalker, Symbolizer};
+use breakpad_symbols::{
+    CfiCache, FrameSymbolizer, FrameWalker, SymbolOrigin, SymbolParseError, SymbolStatus,
+    Symbolizer,
+};
+use minidump::format::MINIDUMP_STREAM_TYPE;
 use minidump::{self, *};
 
-use crate::process_state::{CallStack, CallStackInfo, ProcessState};
-use crate::stackwalker;
+use crate::environment::{EnvironmentFilter, SharedEnvironmentFilter};
+use crate::process_state::{
+    CallStack, CallStackInfo, ModuleSymbolStatus, ProcessState, StackMemory, VirtualMemoryStats,
+};
+use crate::stackwalker::{self, StackScan, StackWalkOptions};
 use crate::system_info::SystemInfo;
 
 pub trait SymbolProvider {
     fn fill_symbol(&self, module: &dyn Module, frame: &mut dyn FrameSymbolizer);
     fn walk_frame(&self, module: &dyn Module, walker: &mut dyn FrameWalker) -> Option<()>;
+    /// Like `walk_frame`, but may reuse unwind rules already parsed into `cache`.
+    ///
+    /// Providers that don't parse STACK CFI can leave this alone.
+    fn walk_frame_with_cache(
+        &self,
+        module: &dyn Module,
+        walker: &mut dyn FrameWalker,
+        _cache: &CfiCache,
+    ) -> Option<()> {
+        self.walk_frame(module, walker)
+    }
+    /// Whether symbols for `module` were loaded, missing, or corrupt, or `None`
+    /// if they were never looked for.
+    fn symbol_status(&self, _module: &dyn Module) -> Option<SymbolStatus> {
+        None
+    }
+    /// Why the symbols for `module` couldn't be parsed, if they were found to
+    /// be corrupt and the provider knows.
+    fn symbol_parse_error(&self, _module: &dyn Module) -> Option<SymbolParseError> {
+        None
+    }
+    /// Where the symbols for `module` came from, if they were loaded and the
+    /// provider knows.
+    fn symbol_origin(&self, _module: &dyn Module) -> Option<SymbolOrigin> {
+        None
+    }
 }
 
 impl SymbolProvider for Symbolizer {
@@ -26,6 +60,75 @@ impl SymbolProvider for Symbolizer {
     fn walk_frame(&self, module: &dyn Module, walker: &mut dyn FrameWalker) -> Option<()> {
         self.walk_frame(module, walker)
     }
+    fn walk_frame_with_cache(
+        &self,
+        module: &dyn Module,
+        walker: &mut dyn FrameWalker,
+        cache: &CfiCache,
+    ) -> Option<()> {
+        self.walk_frame_with_cache(module, walker, cache)
+    }
+    fn symbol_status(&self, module: &dyn Module) -> Option<SymbolStatus> {
+        self.symbol_status(module)
+    }
+    fn symbol_parse_error(&self, module: &dyn Module) -> Option<SymbolParseError> {
+        self.symbol_parse_error(module)
+    }
+    fn symbol_origin(&self, module: &dyn Module) -> Option<SymbolOrigin> {
+        self.symbol_origin(module)
+    }
+}
+
+impl<'a, T: SymbolProvider + ?Sized> SymbolProvider for &'a T {
+    fn fill_symbol(&self, module: &dyn Module, frame: &mut dyn FrameSymbolizer) {
+        (**self).fill_symbol(module, frame);
+    }
+    fn walk_frame(&self, module: &dyn Module, walker: &mut dyn FrameWalker) -> Option<()> {
+        (**self).walk_frame(module, walker)
+    }
+    fn walk_frame_with_cache(
+        &self,
+        module: &dyn Module,
+        walker: &mut dyn FrameWalker,
+        cache: &CfiCache,
+    ) -> Option<()> {
+        (**self).walk_frame_with_cache(module, walker, cache)
+    }
+    fn symbol_status(&self, module: &dyn Module) -> Option<SymbolStatus> {
+        (**self).symbol_status(module)
+    }
+    fn symbol_parse_error(&self, module: &dyn Module) -> Option<SymbolParseError> {
+        (**self).symbol_parse_error(module)
+    }
+    fn symbol_origin(&self, module: &dyn Module) -> Option<SymbolOrigin> {
+        (**self).symbol_origin(module)
+    }
+}
+
+impl<T: SymbolProvider + ?Sized> SymbolProvider for Box<T> {
+    fn fill_symbol(&self, module: &dyn Module, frame: &mut dyn FrameSymbolizer) {
+        (**self).fill_symbol(module, frame);
+    }
+    fn walk_frame(&self, module: &dyn Module, walker: &mut dyn FrameWalker) -> Option<()> {
+        (**self).walk_frame(module, walker)
+    }
+    fn walk_frame_with_cache(
+        &self,
+        module: &dyn Module,
+        walker: &mut dyn FrameWalker,
+        cache: &CfiCache,
+    ) -> Option<()> {
+        (**self).walk_frame_with_cache(module, walker, cache)
+    }
+    fn symbol_status(&self, module: &dyn Module) -> Option<SymbolStatus> {
+        (**self).symbol_status(module)
+    }
+    fn symbol_parse_error(&self, module: &dyn Module) -> Option<SymbolParseError> {
+        (**self).symbol_parse_error(module)
+    }
+    fn symbol_origin(&self, module: &dyn Module) -> Option<SymbolOrigin> {
+        (**self).symbol_origin(module)
+    }
 }
 
 #[derive(Default)]
@@ -59,6 +162,106 @@ impl SymbolProvider for MultiSymbolProvider {
         }
         None
     }
+
+    fn walk_frame_with_cache(
+        &self,
+        module: &dyn Module,
+        walker: &mut dyn FrameWalker,
+        cache: &CfiCache,
+    ) -> Option<()> {
+        for p in self.providers.iter() {
+            let result = p.walk_frame_with_cache(module, walker, cache);
+            if result.is_some() {
+                return result;
+            }
+        }
+        None
+    }
+
+    fn symbol_status(&self, module: &dyn Module) -> Option<SymbolStatus> {
+        // Symbols from any provider are good enough, and a corrupt symbol
+        // file is worth reporting even if another provider had nothing.
+        let mut status = None;
+        for p in self.providers.iter() {
+            match p.symbol_status(module) {
+                Some(SymbolStatus::Loaded) => return Some(SymbolStatus::Loaded),
+                Some(SymbolStatus::Corrupt) => status = Some(SymbolStatus::Corrupt),
+                Some(SymbolStatus::Missing) => status = status.or(Some(SymbolStatus::Missing)),
+                None => {}
+            }
+        }
+        status
+    }
+
+    fn symbol_parse_error(&self, module: &dyn Module) -> Option<SymbolParseError> {
+        if self.symbol_status(module) != Some(SymbolStatus::Corrupt) {
+            return None;
+        }
+        self.providers
+            .iter()
+            .find_map(|p| p.symbol_parse_error(module))
+    }
+
+    fn symbol_origin(&self, module: &dyn Module) -> Option<SymbolOrigin> {
+        // The symbols that were used are the first provider's that loaded any.
+        self.providers
+            .iter()
+            .find(|p| p.symbol_status(module) == Some(SymbolStatus::Loaded))
+            .and_then(|p| p.symbol_origin(module))
+    }
+}
+
+/// A `SymbolProvider` that has no symbols at all.
+///
+/// Processing with this is quick and needs no symbol files: stacks are
+/// walked with the frame pointer and stack scanning, so every frame still
+/// gets its `FrameTrust`, and is reported by module and offset with no
+/// function or source information.
+#[derive(Clone, Copy, Debug, Default)]
+pub struct NoopSymbolizer;
+
+impl SymbolProvider for NoopSymbolizer {
+    fn fill_symbol(&self, _module: &dyn Module, _frame: &mut dyn FrameSymbolizer) {}
+    fn walk_frame(&self, _module: &dyn Module, _walker: &mut dyn FrameWalker) -> Option<()> {
+        None
+    }
+}
+
+/// A `SymbolProvider` that unwinds with `provider`, keeping the STACK CFI
+/// rules it parses in `cache`.
+///
+/// Threads often share functions, so this saves parsing the same rules over
+/// and over while processing one minidump.
+struct CfiCachingProvider<'a, P: ?Sized> {
+    provider: &'a P,
+    cache: &'a CfiCache,
+}
+
+impl<'a, P: SymbolProvider + ?Sized> SymbolProvider for CfiCachingProvider<'a, P> {
+    fn fill_symbol(&self, module: &dyn Module, frame: &mut dyn FrameSymbolizer) {
+        self.provider.fill_symbol(module, frame);
+    }
+    fn walk_frame(&self, module: &dyn Module, walker: &mut dyn FrameWalker) -> Option<()> {
+        self.provider
+            .walk_frame_with_cache(module, walker, self.cache)
+    }
+    fn walk_frame_with_cache(
+        &self,
+        module: &dyn Module,
+        walker: &mut dyn FrameWalker,
+        cache: &CfiCache,
+    ) -> Option<()> {
+        self.provider.walk_frame_with_cache(module, walker, cache)
+    }
+    fn symbol_status(&self, module: &dyn Module) -> Option<SymbolStatus> {
+        self.provider.symbol_status(module)
+    }
+    fn symbol_parse_error(&self, module: &dyn Module) -> Option<SymbolParseError> {
+        self.provider.symbol_parse_error(module)
+    }
+    fn symbol_origin(&self, module: &dyn Module) -> Option<SymbolOrigin> {
+        self.provider.symbol_origin(module)
+    }
 }
 
 /// An error encountered during minidump processing.
@@ -72,6 +275,8 @@ pub enum ProcessError {
     MissingSystemInfo,
     #[fail(display = "The thread list stream was not found")]
     MissingThreadList,
+    #[fail(display = "The {:?} is corrupt: {}", _0, _1)]
+    CorruptStream(MINIDUMP_STREAM_TYPE, StreamError),
 }
 
 impl From<minidump::Error> for ProcessError {
@@ -80,8 +285,133 @@ impl From<minidump::Error> for ProcessError {
     }
 }
 
+/// The default value of [`ProcessorOptions::max_frames`][max_frames].
+///
+/// [max_frames]: struct.ProcessorOptions.html#structfield.max_frames
+pub const DEFAULT_MAX_FRAMES: usize = 2048;
+
+/// How many bytes of code before the crashing instruction to keep.
+const CRASH_CODE_BEFORE: u64 = 16;
+/// How many bytes of code from the crashing instruction on to keep, more than
+/// the longest instruction of any supported CPU.
+const CRASH_CODE_AFTER: u64 = 32;
+
+/// Options controlling how [`process_minidump_with_options`][process] processes a minidump.
+///
+/// [process]: fn.process_minidump_with_options.html
+#[derive(Clone, Debug)]
+pub struct ProcessorOptions {
+    /// The maximum number of frames to keep for each thread, or `None` for no limit.
+    ///
+    /// Threads with more frames than this keep their innermost frames, and record
+    /// how many they had in [`CallStack::total_frames`][total_frames]. Frames past
+    /// the limit are walked to count them, but not symbolized. This keeps the
+    /// size of reports bounded for dumps with extremely deep or corrupt stacks.
+    ///
+    /// [total_frames]: struct.CallStack.html#structfield.total_frames
+    pub max_frames: Option<usize>,
+    /// Limits on the size of the module list.
+    ///
+    /// If the module list exceeds them, only the modules that fit are processed.
+    pub module_limits: ModuleListLimits,
+    /// The number of address ranges of parsed STACK CFI rules to keep while
+    /// unwinding, or 0 to parse them every time they're used.
+    ///
+    /// The cache only lasts for one call to `process_minidump_with_options`.
+    pub cfi_cache_capacity: usize,
+    /// Whether to read identifiers for modules the module list has none for
+    /// out of their image headers, if the headers were captured in the
+    /// minidump's memory. It's off by default.
+    ///
+    /// See `MinidumpModule::recover_identifiers`.
+    pub recover_module_identifiers: bool,
+    /// Timestamps in the minidump later than this are treated as garbage and
+    /// left out of the `ProcessState`.
+    ///
+    /// `None`, the default, means a day after the minidump is processed, so
+    /// timestamps from devices whose clocks are only a little fast are kept.
+    pub latest_plausible_time: Option<DateTime<Utc>>,
+    /// Whether to skip a thread that [looks like it wrote the dump][dump_writer]
+    /// when the minidump doesn't say which thread did.
+    ///
+    /// Breakpad records the dump thread, other clients don't. The requesting
+    /// thread is never skipped.
+    ///
+    /// [dump_writer]: struct.CallStack.html#method.is_dump_writer
+    pub detect_dump_thread: bool,
+    /// The indices of the threads to walk, or `None` to walk them all.
+    ///
+    /// Other threads are left with no frames and `CallStackInfo::NotWalked`,
+    /// which saves most of the work of processing dumps with many threads when
+    /// only a few are of interest. See [`process_minidump_thread`][thread].
+    ///
+    /// [thread]: fn.process_minidump_thread.html
+    pub walk_threads: Option<Vec<usize>>,
+    /// Whether to record how each thread's stack was unwound in its
+    /// [`CallStack::unwind_diagnostics`][diagnostics].
+    ///
+    /// That says which way each frame was found and why the others failed,
+    /// which is what you want to know about a wrong stack. It's off by default.
+    ///
+    /// [diagnostics]: struct.CallStack.html#structfield.unwind_diagnostics
+    pub unwind_diagnostics: bool,
+    /// How readily to take words on the stack for return addresses when a
+    /// frame's caller can only be found by scanning.
+    ///
+    /// By default, callers that follow a call instruction or that CFI can
+    /// unwind are preferred over the first word that points into code.
+    pub stack_scan: StackScan,
+    /// Which of the process's environment variables to keep in
+    /// [`ProcessState::environment`][environment], and how much of their values.
+    ///
+    /// The environment comes from Linux minidumps' `LinuxEnviron` stream. It
+    /// can hold secrets, so it's left out by default. See
+    /// [`RuntimeVariables`][runtime] for a filter that keeps the variables most
+    /// likely to matter to a crash.
+    ///
+    /// [environment]: struct.ProcessState.html#structfield.environment
+    /// [runtime]: struct.RuntimeVariables.html
+    pub environment: Option<SharedEnvironmentFilter>,
+}
+
+impl Default for ProcessorOptions {
+    fn default() -> ProcessorOptions {
+        ProcessorOptions {
+            max_frames: Some(DEFAULT_MAX_FRAMES),
+            module_limits: ModuleListLimits::default(),
+            cfi_cache_capacity: CfiCache::DEFAULT_CAPACITY,
+            recover_module_identifiers: false,
+            latest_plausible_time: None,
+            detect_dump_thread: true,
+            walk_threads: None,
+            unwind_diagnostics: false,
+            stack_scan: StackScan::default(),
+            environment: None,
+        }
+    }
+}
+
+/// `time`, unless it's too implausible to be when something actually happened.
+///
+/// Devices whose clock was never set report the Unix epoch, and misconfigured
+/// ones can report times far in the future.
+fn plausible_time(time: DateTime<Utc>, latest: DateTime<Utc>) -> Option<DateTime<Utc>> {
+    if time.timestamp() == 0 {
+        return None;
+    }
+    if time > latest {
+        warn!("Ignoring implausible timestamp {}", time.to_rfc3339());
+        return None;
+    }
+    Some(time)
+}
+
 /// Unwind all threads in `dump` and return a `ProcessState`.
 ///
+/// This uses the default [`ProcessorOptions`][options].
+///
+/// [options]: struct.ProcessorOptions.html
+///
 /// # Examples
 ///
 /// ```
@@ -109,99 +439,319 @@ where
     T: Deref<Target = [u8]> + 'a,
     P: SymbolProvider,
 {
+    process_minidump_with_options(dump, symbol_provider, &ProcessorOptions::default())
+}
+
+/// Unwind only the thread at `thread_index` of `dump`'s thread list and return
+/// a `ProcessState`.
+///
+/// Everything but the other threads' stacks is processed as usual, so this is
+/// much faster than [`process_minidump`][process] for dumps with many threads
+/// when only one is of interest, such as the crashing thread. Symbols loaded
+/// by `symbol_provider` are cached as usual, so walking more threads later
+/// with the same `Symbolizer` doesn't load them again.
+///
+/// This uses the default [`ProcessorOptions`][options] otherwise.
+///
+/// [process]: fn.process_minidump.html
+/// [options]: struct.ProcessorOptions.html
+pub fn process_minidump_thread<'a, T, P>(
+    dump: &Minidump<'a, T>,
+    thread_index: usize,
+    symbol_provider: &P,
+) -> Result<ProcessState, ProcessError>
+where
+    T: Deref<Target = [u8]> + 'a,
+    P: SymbolProvider,
+{
+    let options = ProcessorOptions {
+        walk_threads: Some(vec![thread_index]),
+        ..ProcessorOptions::default()
+    };
+    process_minidump_with_options(dump, symbol_provider, &options)
+}
+
+/// Get the stream `S` from `dump` if it's present.
+///
+/// A stream that's present but can't be read is recorded in `corrupt_streams`
+/// rather than silently treated as missing.
+fn optional_stream<'a, S, T>(
+    dump: &'a Minidump<'a, T>,
+    corrupt_streams: &mut Vec<(MINIDUMP_STREAM_TYPE, StreamError)>,
+) -> Option<S>
+where
+    S: MinidumpStream<'a>,
+    T: Deref<Target = [u8]> + 'a,
+{
+    match dump.get_stream_checked::<S>() {
+        Ok(stream) => Some(stream),
+        Err(StreamError::StreamNotFound) => None,
+        Err(e) => {
+            warn!("{:?} is corrupt: {}", S::STREAM_TYPE, e);
+            corrupt_streams.push((S::STREAM_TYPE, e));
+            None
+        }
+    }
+}
+
+/// What `symbol_provider` found when it looked for `module`'s symbols, or
+/// `None` if it never looked.
+fn module_symbol_status<P>(
+    module: &MinidumpModule,
+    symbol_provider: &P,
+) -> Option<ModuleSymbolStatus>
+where
+    P: SymbolProvider + ?Sized,
+{
+    Some(match symbol_provider.symbol_status(module)? {
+        SymbolStatus::Loaded => {
+            let origin = symbol_provider.symbol_origin(module).unwrap_or_default();
+            ModuleSymbolStatus::Loaded {
+                from_cache: origin.from_cache,
+                url: origin.url,
+                fetch_ms: origin.fetch_ms,
+            }
+        }
+        SymbolStatus::Missing => ModuleSymbolStatus::Missing,
+        SymbolStatus::Corrupt => ModuleSymbolStatus::Corrupt {
+            detail: symbol_provider.symbol_parse_error(module),
+        },
+    })
+}
+
+/// Unwind all threads in `dump` and return a `ProcessState`, as configured by `options`.
+pub fn process_minidump_with_options<'a, T, P>(
+    dump: &Minidump<'a, T>,
+    symbol_provider: &P,
+    options: &ProcessorOptions,
+) -> Result<ProcessState, ProcessError>
+where
+    T: Deref<Target = [u8]> + 'a,
+    P: SymbolProvider,
+{
+    let cfi_cache = CfiCache::with_capacity(options.cfi_cache_capacity);
+    let symbol_provider = &CfiCachingProvider {
+        provider: symbol_provider,
+        cache: &cfi_cache,
+    };
     // Thread list is required for processing.
     let thread_list = dump
-        .get_stream::<MinidumpThreadList>()
-        .or(Err(ProcessError::MissingThreadList))?;
+        .get_stream_checked::<MinidumpThreadList>()
+        .map_err(|e| match e {
+            StreamError::StreamNotFound => ProcessError::MissingThreadList,
+            e => ProcessError::CorruptStream(MinidumpThreadList::STREAM_TYPE, e),
+        })?;
     // System info is required for processing.
-    let dump_system_info = dump
-        .get_stream::<MinidumpSystemInfo>()
-        .or(Err(ProcessError::MissingSystemInfo))?;
+    let dump_system_info =
+        dump.get_stream_checked::<MinidumpSystemInfo>()
+            .map_err(|e| match e {
+                StreamError::StreamNotFound => ProcessError::MissingSystemInfo,
+                e => ProcessError::CorruptStream(MinidumpSystemInfo::STREAM_TYPE, e),
+            })?;
+    // Any other stream can be done without, but is worth reporting if it's corrupt.
+    let mut corrupt_streams = vec![];
+    let latest_plausible_time = options
+        .latest_plausible_time
+        .unwrap_or_else(|| Utc::now() + Duration::days(1));
 
+    let os_service_pack = dump_system_info
+        .csd_version()
+        .map(|string| string.into_owned());
     let mut os_version = format!(
         "{}.{}.{}",
         dump_system_info.raw.major_version,
         dump_system_info.raw.minor_version,
         dump_system_info.raw.build_number
     );
-    if let Some(csd_version) = dump_system_info.csd_version() {
+    if let Some(ref csd_version) = os_service_pack {
         os_version.push(' ');
-        os_version.push_str(&csd_version);
+        os_version.push_str(csd_version);
     }
 
     let cpu_info = dump_system_info
         .cpu_info()
         .map(|string| string.into_owned());
 
+    // The environment is only kept if the caller chose which of it to keep.
+    let environment = options.environment.as_ref().and_then(|filter| {
+        let environ = optional_stream::<MinidumpLinuxEnviron, _>(dump, &mut corrupt_streams)?;
+        Some(
+            environ
+                .vars
+                .iter()
+                .filter_map(|(name, value)| {
+                    let value = filter.filter_var(name, value)?;
+                    Some((name.clone(), value))
+                })
+                .collect(),
+        )
+    });
+
     let system_info = SystemInfo {
         os: dump_system_info.os,
         os_version: Some(os_version),
+        os_major: dump_system_info.raw.major_version,
+        os_minor: dump_system_info.raw.minor_version,
+        os_build: dump_system_info.raw.build_number,
+        os_service_pack,
         cpu: dump_system_info.cpu,
         cpu_info,
         cpu_count: dump_system_info.raw.number_of_processors as usize,
     };
     // Process create time is optional.
-    let (process_id, process_create_time) =
-        if let Ok(misc_info) = dump.get_stream::<MinidumpMiscInfo>() {
-            (
-                misc_info.raw.process_id().cloned(),
-                misc_info.process_create_time(),
-            )
-        } else {
-            (None, None)
-        };
+    let misc_info = optional_stream::<MinidumpMiscInfo, _>(dump, &mut corrupt_streams);
+    let (
+        process_id,
+        raw_process_create_time,
+        process_create_time,
+        process_execute_flags,
+        protected_process,
+    ) = if let Some(misc_info) = misc_info {
+        (
+            misc_info.raw.process_id().cloned(),
+            misc_info.raw.process_create_time().cloned(),
+            misc_info
+                .process_create_time()
+                .and_then(|time| plausible_time(time, latest_plausible_time)),
+            misc_info.process_execute_flags(),
+            misc_info.protected_process(),
+        )
+    } else {
+        (None, None, None, None, None)
+    };
     // If Breakpad info exists in dump, get dump and requesting thread ids.
-    let breakpad_info = dump.get_stream::<MinidumpBreakpadInfo>();
-    let (dump_thread_id, requesting_thread_id) = if let Ok(info) = breakpad_info {
+    let breakpad_info = optional_stream::<MinidumpBreakpadInfo, _>(dump, &mut corrupt_streams);
+    let (dump_thread_id, requesting_thread_id) = if let Some(info) = breakpad_info {
         (info.dump_thread_id, info.requesting_thread_id)
     } else {
         (None, None)
     };
     // Get exception info if it exists.
-    let exception_stream = dump.get_stream::<MinidumpException>().ok();
+    let exception_stream = optional_stream::<MinidumpException, _>(dump, &mut corrupt_streams);
     let exception_ref = exception_stream.as_ref();
-    let (crash_reason, crash_address, crashing_thread_id) = if let Some(exception) = exception_ref {
-        (
-            Some(exception.get_crash_reason(system_info.os)),
-            Some(exception.get_crash_address(system_info.os)),
-            Some(exception.get_crashing_thread_id()),
-        )
-    } else {
-        (None, None, None)
-    };
+    let (crash_reason, exception_details, crashing_thread_id) =
+        if let Some(exception) = exception_ref {
+            (
+                Some(exception.get_crash_reason(system_info.os)),
+                Some(exception.get_exception_details(system_info.os)),
+                Some(exception.get_crashing_thread_id()),
+            )
+        } else {
+            (None, None, None)
+        };
+    let crash_address = exception_details.map(|details| details.address());
     let exception_context = exception_ref.and_then(|e| e.context.as_ref());
+    // The crash is described by the last exception stream, but keep the
+    // records of any others.
+    let mut exception_streams = dump.get_streams::<MinidumpException>();
+    exception_streams.pop();
+    let other_exceptions = exception_streams
+        .into_iter()
+        .filter_map(|exception| match exception {
+            Ok(exception) => Some((exception.thread_id, exception.raw.exception_record)),
+            Err(e) => {
+                warn!("{:?} is corrupt: {}", MinidumpException::STREAM_TYPE, e);
+                corrupt_streams.push((MinidumpException::STREAM_TYPE, e.into()));
+                None
+            }
+        })
+        .collect();
     // Get assertion
-    let assertion = None;
-    let modules = match dump.get_stream::<MinidumpModuleList>() {
-        Ok(module_list) => module_list,
-        // Just give an empty list, simplifies things.
-        Err(_) => MinidumpModuleList::new(),
-    };
-    let unloaded_modules = match dump.get_stream::<MinidumpUnloadedModuleList>() {
+    let assertion = optional_stream::<MinidumpAssertion, _>(dump, &mut corrupt_streams)
+        .map(|assertion| assertion.description());
+    let mut modules = match dump.get_module_list_with_limits(&options.module_limits) {
         Ok(module_list) => module_list,
         // Just give an empty list, simplifies things.
-        Err(_) => MinidumpUnloadedModuleList::new(),
+        Err(minidump::Error::StreamNotFound) => MinidumpModuleList::new(),
+        Err(e) => {
+            warn!("{:?} is corrupt: {}", MinidumpModuleList::STREAM_TYPE, e);
+            corrupt_streams.push((MinidumpModuleList::STREAM_TYPE, e.into()));
+            MinidumpModuleList::new()
+        }
     };
+    if let Err(e) = modules.validate() {
+        warn!("{}", e);
+    }
+    if let Some(image_base) = dump.process_image_base() {
+        modules.set_image_base(image_base);
+    }
+    // Just give an empty list, simplifies things.
+    let unloaded_modules =
+        optional_stream::<MinidumpUnloadedModuleList, _>(dump, &mut corrupt_streams)
+            .unwrap_or_default();
 
-    let memory_list = dump.get_stream::<MinidumpMemoryList>().ok();
+    let memory_list = optional_stream::<MinidumpMemoryList, _>(dump, &mut corrupt_streams);
+    // Some clients leave out module identifiers, but the image headers may be in memory.
+    if options.recover_module_identifiers {
+        if let Some(ref memory_list) = memory_list {
+            modules.recover_identifiers(memory_list);
+        }
+    }
+    // Nested exception records can only be found in the dumped memory.
+    let exception_chain = exception_ref
+        .map(|exception| match memory_list {
+            Some(ref memory_list) => exception.get_exception_chain(memory_list, system_info.cpu),
+            None => vec![exception.raw.exception_record.clone()],
+        })
+        .unwrap_or_default();
+    // The crashing code is only in the memory list if the dump includes module
+    // memory, or the code was generated at runtime.
+    let crash_instruction_memory = exception_context.and_then(|context| {
+        let pc = context.get_instruction_pointer();
+        let start = pc.saturating_sub(CRASH_CODE_BEFORE);
+        let window = memory_list
+            .as_ref()?
+            .memory_window(start, pc - start + CRASH_CODE_AFTER)?;
+        Some(window).filter(|window| window.range.contains(&pc))
+    });
+    // Memory info is optional, but useful for diagnosing out-of-memory crashes
+    // and telling whether an address was executable.
+    let memory_info = optional_stream::<MinidumpMemoryInfoList, _>(dump, &mut corrupt_streams);
+    let virtual_memory = memory_info
+        .as_ref()
+        .map(VirtualMemoryStats::from_memory_info);
+    // Linux memory mappings are optional, they name frames outside of any module.
+    let linux_maps = optional_stream::<MinidumpLinuxMaps, _>(dump, &mut corrupt_streams);
+    // Thread names are optional.
+    let thread_names =
+        optional_stream::<MinidumpThreadNames, _>(dump, &mut corrupt_streams).unwrap_or_default();
+
+    // Prefer the exception stream's thread id over the breakpad info stream's,
+    // unless the exception names a thread that isn't in the thread list. The
+    // exception context still describes the crash then, so it's used for the
+    // thread breakpad says requested the dump.
+    let requesting_thread_id = crashing_thread_id
+        .filter(|&id| thread_list.get_thread(id).is_some())
+        .or_else(|| {
+            if let Some(id) = crashing_thread_id {
+                warn!("Exception thread {:#x} is not in the thread list", id);
+            }
+            requesting_thread_id
+        });
 
     // Get memory list
     let mut threads = vec![];
     let mut requesting_thread = None;
+    let mut requesting_thread_stack = None;
+    let mut dump_thread = None;
     for (i, thread) in thread_list.threads.iter().enumerate() {
         // If this is the thread that wrote the dump, skip processing it.
+        let thread_name = thread_names
+            .get_name(thread.raw.thread_id)
+            .map(String::from);
         if dump_thread_id.is_some() && dump_thread_id.unwrap() == thread.raw.thread_id {
-            threads.push(CallStack::with_info(CallStackInfo::DumpThreadSkipped));
+            dump_thread = Some(i);
+            let mut stack = CallStack::with_info(CallStackInfo::DumpThreadSkipped);
+            stack.thread_name = thread_name;
+            stack.set_thread_state(thread);
+            threads.push(stack);
             continue;
         }
         // If this thread requested the dump then try to use the exception
-        // context if it exists. (prefer the exception stream's thread id over
-        // the breakpad info stream's thread id.)
-        let context = if crashing_thread_id
-            .or(requesting_thread_id)
-            .map(|id| id == thread.raw.thread_id)
-            .unwrap_or(false)
-        {
+        // context if it exists.
+        let from_exception =
+            requesting_thread_id == Some(thread.raw.thread_id) && exception_context.is_some();
+        let context = if requesting_thread_id == Some(thread.raw.thread_id) {
             requesting_thread = Some(i);
             exception_context.or_else(|| thread.context.as_ref())
         } else {
@@ -212,26 +762,119 @@ where
             // Windows probably gave us null RVAs for our stack memory descriptors.
             // If this happens, then we need to look up the memory region by address.
             let stack_addr = thread.raw.stack.start_of_memory_range;
-            memory_list
-                .as_ref()
-                .and_then(|memory| memory.memory_at_address(stack_addr))
+            let memory = memory_list.as_ref()?;
+            memory.memory_at_address(stack_addr).or_else(|| {
+                // Some producers zero the whole descriptor, but still put the
+                // stack in the memory list, so look for the stack pointer.
+                let stack_pointer = context?.get_stack_pointer();
+                memory.memory_at_address(stack_pointer)
+            })
         });
+        if requesting_thread == Some(i) {
+            requesting_thread_stack = stack.map(|memory| StackMemory {
+                base_address: memory.base_address,
+                bytes: memory.bytes.to_vec(),
+            });
+        }
 
-        let stack = stackwalker::walk_stack(&context, stack, &modules, symbol_provider);
+        if let Some(ref walk_threads) = options.walk_threads {
+            if !walk_threads.contains(&i) {
+                let mut stack = CallStack::with_info(CallStackInfo::NotWalked);
+                stack.thread_name = thread_name;
+                stack.set_thread_state(thread);
+                threads.push(stack);
+                continue;
+            }
+        }
+
+        let mut stack = match thread.context_error {
+            // Keep going with the other threads if this one's CPU isn't known.
+            Some(ContextError::UnknownCpuContext(flags)) if context.is_none() => {
+                warn!(
+                    "thread {} has an unknown CPU context, flags {:#010x}",
+                    i, flags
+                );
+                let mut stack = CallStack::with_info(CallStackInfo::UnsupportedCpu);
+                stack.context_flags = Some(flags);
+                stack
+            }
+            _ => stackwalker::walk_stack_with_options(
+                &context,
+                stack,
+                &modules,
+                &unloaded_modules,
+                symbol_provider,
+                &StackWalkOptions {
+                    diagnostics: options.unwind_diagnostics,
+                    scan: options.stack_scan,
+                    memory: memory_list.as_ref(),
+                    memory_info: memory_info.as_ref(),
+                    max_frames: options.max_frames,
+                },
+            ),
+        };
+        // Without Breakpad info, the dump thread can only be told by its stack.
+        if options.detect_dump_thread
+            && dump_thread_id.is_none()
+            && dump_thread.is_none()
+            && requesting_thread != Some(i)
+            && stack.is_dump_writer()
+        {
+            dump_thread = Some(i);
+            let mut stack = CallStack::with_info(CallStackInfo::DumpThreadSkipped);
+            stack.thread_name = thread_name;
+            stack.set_thread_state(thread);
+            threads.push(stack);
+            continue;
+        }
+        if let Some(ref maps) = linux_maps {
+            stackwalker::fill_mappings(&mut stack, maps);
+        }
+        // Keep both contexts of a thread that was walked from the exception's.
+        if from_exception {
+            stack.exception_context = exception_context.cloned();
+            stack.thread_context = thread.context.clone();
+        }
+        stack.thread_name = thread_name;
+        stack.set_thread_state(thread);
         threads.push(stack);
     }
+    let symbol_status = modules
+        .iter()
+        .map(|module| module_symbol_status(module, symbol_provider))
+        .collect();
     // if exploitability enabled, run exploitability analysis
     Ok(ProcessState {
         process_id,
-        time: Utc.timestamp(dump.header.time_date_stamp as i64, 0),
+        time: plausible_time(
+            Utc.timestamp(dump.header.time_date_stamp as i64, 0),
+            latest_plausible_time,
+        ),
+        raw_time: dump.header.time_date_stamp,
         process_create_time,
+        raw_process_create_time,
+        process_execute_flags,
+        protected_process,
         crash_reason,
+        exception_details,
         crash_address,
+        exception_chain,
+        other_exceptions,
         assertion,
         requesting_thread,
+        dump_thread,
         system_info,
         threads,
+        requesting_thread_stack,
+        crash_instruction_memory,
         modules,
+        symbol_status,
         unloaded_modules,
+        memory_info,
+        virtual_memory,
+        corrupt_streams,
+        environment,
+        annotations: None,
+        exception_context: exception_context.cloned(),
     })
 }
@@ -0,0 +1,194 @@
+// Copyright 2015 Ted Mielczarek. See the COPYRIGHT
+// file at the top-level directory of this distribution.
+
+//! Link the source files named in symbols to where they can be browsed.
+
+use minidump::MinidumpModule;
+use std::fmt;
+use std::sync::Arc;
+
+/// Maps the source files named in a module's symbols to URLs, so reports can
+/// link to the source of each frame.
+///
+/// Set one as [`JsonOptions::source_urls`][source_urls] to give frames a
+/// `file_url` and a `line_url` in JSON output. Any
+/// `Fn(Option<&MinidumpModule>, &str, Option<u32>) -> Option<String>` is one.
+///
+/// [source_urls]: struct.JsonOptions.html#structfield.source_urls
+pub trait SourceUrlResolver {
+    /// The URL of `source_file`, as the symbols of `module` name it, or of
+    /// `source_line` in it if that's given, or `None` if it's not known.
+    fn source_url(
+        &self,
+        module: Option<&MinidumpModule>,
+        source_file: &str,
+        source_line: Option<u32>,
+    ) -> Option<String>;
+}
+
+impl<F> SourceUrlResolver for F
+where
+    F: Fn(Option<&MinidumpModule>, &str, Option<u32>) -> Option<String>,
+{
+    fn source_url(
+        &self,
+        module: Option<&MinidumpModule>,
+        source_file: &str,
+        source_line: Option<u32>,
+    ) -> Option<String> {
+        self(module, source_file, source_line)
+    }
+}
+
+/// A `SourceUrlResolver` that can be cloned along with the
+/// [`JsonOptions`][options] holding it.
+///
+/// [options]: struct.JsonOptions.html
+#[derive(Clone)]
+pub struct SharedSourceUrlResolver(Arc<dyn SourceUrlResolver + Send + Sync>);
+
+impl SharedSourceUrlResolver {
+    /// Wraps `resolver` so it can be shared.
+    pub fn new<R>(resolver: R) -> SharedSourceUrlResolver
+    where
+        R: SourceUrlResolver + Send + Sync + 'static,
+    {
+        SharedSourceUrlResolver(Arc::new(resolver))
+    }
+}
+
+impl fmt::Debug for SharedSourceUrlResolver {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        f.write_str("SharedSourceUrlResolver(..)")
+    }
+}
+
+impl SourceUrlResolver for SharedSourceUrlResolver {
+    fn source_url(
+        &self,
+        module: Option<&MinidumpModule>,
+        source_file: &str,
+        source_line: Option<u32>,
+    ) -> Option<String> {
+        self.0.source_url(module, source_file, source_line)
+    }
+}
+
+/// A `SourceUrlResolver` for the version control paths Mozilla's symbol dumping
+/// scripts write into `FILE` records.
+///
+/// Those look like `hg:<repository>:<path>:<revision>` for Mercurial, and
+/// `git:<repository>:<path>:<revision>` for git, where the repository is a host
+/// and path like `hg.mozilla.org/mozilla-central`. Mercurial files link to the
+/// repository's web interface, and git files link the way GitHub and GitLab do.
+/// Other source files have no URL.
+#[derive(Clone, Copy, Debug, Default)]
+pub struct VcsSourceUrls;
+
+impl SourceUrlResolver for VcsSourceUrls {
+    fn source_url(
+        &self,
+        _module: Option<&MinidumpModule>,
+        source_file: &str,
+        source_line: Option<u32>,
+    ) -> Option<String> {
+        let mut parts = source_file.splitn(2, ':');
+        let vcs = parts.next()?;
+        let rest = parts.next()?;
+        let (repository, rest) = split_nonempty(rest, rest.find(':')?)?;
+        let (path, revision) = split_nonempty(rest, rest.rfind(':')?)?;
+        let (url, anchor) = match vcs {
+            "hg" => (
+                format!("https://{}/file/{}/{}", repository, revision, path),
+                "#l",
+            ),
+            "git" => (
+                format!("https://{}/blob/{}/{}", repository, revision, path),
+                "#L",
+            ),
+            _ => return None,
+        };
+        Some(match source_line {
+            Some(line) => format!("{}{}{}", url, anchor, line),
+            None => url,
+        })
+    }
+}
+
+/// Split `s` around the separator at `index`, if neither side is empty.
+fn split_nonempty(s: &str, index: usize) -> Option<(&str, &str)> {
+    let (before, after) = (&s[..index], &s[index + 1..]);
+    if before.is_empty() || after.is_empty() {
+        None
+    } else {
+        Some((before, after))
+    }
+}
+
+#[cfg(test)]
+mod test {
+    use super::*;
+
+    #[test]
+    fn test_vcs_source_urls() {
+        let urls = VcsSourceUrls;
+        assert_eq!(
+            urls.source_url(
+                None,
+                "hg:hg.mozilla.org/mozilla-central:dom/base/Element.cpp:1b2c3d4e5f60",
+                Some(42)
+            )
+            .as_deref(),
+            Some(
+                "https://hg.mozilla.org/mozilla-central/file/1b2c3d4e5f60/dom/base/Element.cpp#l42"
+            )
+        );
+        assert_eq!(
+            urls.source_url(
+                None,
+                "git:github.com/rust-lang/rust:library/core/src/panicking.rs:e1884a8e3c3e",
+                None
+            )
+            .as_deref(),
+            Some(
+                "https://github.com/rust-lang/rust/blob/e1884a8e3c3e/library/core/src/panicking.rs"
+            )
+        );
+        assert_eq!(
+            urls.source_url(
+                None,
+                "git:github.com/rust-lang/rust:library/core/src/panicking.rs:e1884a8e3c3e",
+                Some(7)
+            )
+            .as_deref(),
+            Some("https://github.com/rust-lang/rust/blob/e1884a8e3c3e/library/core/src/panicking.rs#L7")
+        );
+        // Plain paths, unknown schemes and incomplete ones have no URL.
+        assert_eq!(urls.source_url(None, "c:\\src\\test_app.cc", Some(1)), None);
+        assert_eq!(urls.source_url(None, "/build/src/main.c", Some(1)), None);
+        assert_eq!(
+            urls.source_url(None, "svn:example.com/repo:a.c:12", None),
+            None
+        );
+        assert_eq!(
+            urls.source_url(None, "hg:hg.mozilla.org/mozilla-central:a.c", None),
+            None
+        );
+        assert_eq!(
+            urls.source_url(None, "hg:hg.mozilla.org/mozilla-central::abc", None),
+            None
+        );
+    }
+
+    #[test]
+    fn test_closure_source_urls() {
+        let urls = |_: Option<&MinidumpModule>, file: &str, line: Option<u32>| {
+            Some(format!("https://example.com/{}?line={}", file, line?))
+        };
+        assert_eq!(
+            urls.source_url(None, "a.c", Some(3)).as_deref(),
+            Some("https://example.com/a.c?line=3")
+        );
+        assert_eq!(urls.source_url(None, "a.c", None), None);
+    }
+}
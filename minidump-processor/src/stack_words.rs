@@ -0,0 +1,249 @@
+// Copyright 2015 Ted Mielczarek. See the COPYRIGHT
+// file at the top-level directory of this distribution.
+
+//! Classify the words on a thread's stack by what they point at.
+//!
+//! This doesn't unwind anything, so it's just as useful for threads that
+//! unwound cleanly as for those that didn't.
+
+use breakpad_symbols::SimpleFrame;
+use minidump::{MinidumpContext, MinidumpMemory, MinidumpModule, MinidumpModuleList};
+
+use crate::SymbolProvider;
+
+/// What a word on the stack points at.
+#[derive(Clone, Debug)]
+pub enum StackWordKind<'a> {
+    /// Into a function known from its module's symbols, so it could be a
+    /// return address.
+    Function {
+        module: &'a MinidumpModule,
+        /// The name of the function.
+        function: String,
+        /// The address of the start of the function.
+        function_base: u64,
+    },
+    /// Into a module, but not into any function known from its symbols.
+    Module(&'a MinidumpModule),
+    /// Into the thread's stack.
+    Stack,
+    /// Nowhere in particular, it's probably not a pointer.
+    Unknown,
+}
+
+/// A word on a thread's stack.
+#[derive(Clone, Debug)]
+pub struct StackWord<'a> {
+    /// The address of the word on the stack.
+    pub address: u64,
+    /// The value of the word.
+    pub value: u64,
+    /// What `value` points at.
+    pub kind: StackWordKind<'a>,
+}
+
+/// An iterator over the pointer-sized words of a thread's stack.
+///
+/// See [`stack_words`][stack_words].
+///
+/// [stack_words]: fn.stack_words.html
+pub struct StackWords<'a, P> {
+    stack_memory: &'a MinidumpMemory<'a>,
+    modules: &'a MinidumpModuleList,
+    symbol_provider: &'a P,
+    pointer_width: u64,
+    /// The address of the next word.
+    address: u64,
+}
+
+/// Iterate over the words of `stack_memory`, classifying each one by what it
+/// points at.
+///
+/// `context` is the thread's context, which tells how large a word is. Words
+/// are aligned to their size, starting from the lowest address. A word that
+/// points into a module is looked up in `symbol_provider` to see if it's in a
+/// function, which makes it a candidate return address.
+pub fn stack_words<'a, P>(
+    context: &MinidumpContext,
+    stack_memory: &'a MinidumpMemory<'a>,
+    modules: &'a MinidumpModuleList,
+    symbol_provider: &'a P,
+) -> StackWords<'a, P>
+where
+    P: SymbolProvider,
+{
+    let pointer_width = context.pointer_width();
+    // Round the start of the stack up to a whole word.
+    let address = stack_memory
+        .base_address
+        .checked_add(pointer_width - 1)
+        .map(|address| address & !(pointer_width - 1))
+        .unwrap_or(u64::MAX);
+    StackWords {
+        stack_memory,
+        modules,
+        symbol_provider,
+        pointer_width,
+        address,
+    }
+}
+
+impl<'a, P> StackWords<'a, P>
+where
+    P: SymbolProvider,
+{
+    fn classify(&self, value: u64) -> StackWordKind<'a> {
+        let stack = self.stack_memory;
+        if value >= stack.base_address && value - stack.base_address < stack.size {
+            return StackWordKind::Stack;
+        }
+        let module = match self.modules.module_at_address(value) {
+            Some(module) => module,
+            None => return StackWordKind::Unknown,
+        };
+        let mut frame = SimpleFrame::with_instruction(value);
+        self.symbol_provider.fill_symbol(module, &mut frame);
+        match (frame.function, frame.function_base) {
+            (Some(function), Some(function_base)) => StackWordKind::Function {
+                module,
+                function,
+                function_base,
+            },
+            _ => StackWordKind::Module(module),
+        }
+    }
+}
+
+impl<'a, P> Iterator for StackWords<'a, P>
+where
+    P: SymbolProvider,
+{
+    type Item = StackWord<'a>;
+
+    fn next(&mut self) -> Option<StackWord<'a>> {
+        let address = self.address;
+        let value = if self.pointer_width == 8 {
+            self.stack_memory.get_memory_at_address::<u64>(address)?
+        } else {
+            self.stack_memory.get_memory_at_address::<u32>(address)? as u64
+        };
+        self.address = address.checked_add(self.pointer_width)?;
+        Some(StackWord {
+            address,
+            value,
+            kind: self.classify(value),
+        })
+    }
+}
+
+#[cfg(test)]
+mod test {
+    use super::*;
+    use breakpad_symbols::{SimpleSymbolSupplier, Symbolizer};
+    use minidump::format::{CvSignature, CONTEXT_AMD64, CONTEXT_X86, CV_INFO_PDB20};
+    use minidump::{CodeView, MinidumpContextValidity, MinidumpRawContext, Module};
+    use test_assembler::*;
+
+    fn leaf_module() -> MinidumpModule {
+        let mut module = MinidumpModule::new(0x7400c0000000, 0x10000, "c:\\leaf.dll");
+        module.codeview_info = Some(CodeView::Pdb20(CV_INFO_PDB20 {
+            cv_signature: CvSignature::Pdb20 as u32,
+            cv_offset: 0,
+            signature: 0x24681357,
+            age: 1,
+            pdb_file_name: b"leaf.pdb\0".to_vec(),
+        }));
+        module
+    }
+
+    #[test]
+    fn test_stack_words() {
+        let symbol_path =
+            std::path::Path::new(env!("CARGO_MANIFEST_DIR")).join("../testdata/symbols");
+        let symbolizer = Symbolizer::new(SimpleSymbolSupplier::new(vec![symbol_path]));
+        let modules = MinidumpModuleList::from_modules(vec![
+            leaf_module(),
+            MinidumpModule::new(0x7500b0000000, 0x10000, "module2"),
+        ]);
+        let context = MinidumpContext {
+            raw: MinidumpRawContext::Amd64(CONTEXT_AMD64::default()),
+            valid: MinidumpContextValidity::All,
+        };
+        let stack = Section::with_endian(Endian::Little)
+            .D64(0x7400c0000110) // in calls_leaf
+            .D64(0x7400c0000180) // in leaf.dll, between functions
+            .D64(0x7500b0000100) // in module2, which has no symbols
+            .D64(0x80000008) // in the stack
+            .D64(0x1234) // nothing
+            .D32(0); // not a whole word
+        let bytes = stack.get_contents().unwrap();
+        let stack_memory = MinidumpMemory {
+            desc: Default::default(),
+            base_address: 0x80000000,
+            size: bytes.len() as u64,
+            bytes: &bytes,
+        };
+
+        let words = stack_words(&context, &stack_memory, &modules, &symbolizer).collect::<Vec<_>>();
+        assert_eq!(words.len(), 5);
+        assert_eq!(
+            words.iter().map(|w| w.address).collect::<Vec<_>>(),
+            vec![0x80000000, 0x80000008, 0x80000010, 0x80000018, 0x80000020]
+        );
+        assert_eq!(words[0].value, 0x7400c0000110);
+        match words[0].kind {
+            StackWordKind::Function {
+                module,
+                ref function,
+                function_base,
+            } => {
+                assert_eq!(module.code_file(), "c:\\leaf.dll");
+                assert_eq!(function, "calls_leaf");
+                assert_eq!(function_base, 0x7400c0000100);
+            }
+            ref other => panic!("unexpected kind: {:?}", other),
+        }
+        match words[1].kind {
+            StackWordKind::Module(module) => assert_eq!(module.code_file(), "c:\\leaf.dll"),
+            ref other => panic!("unexpected kind: {:?}", other),
+        }
+        match words[2].kind {
+            StackWordKind::Module(module) => assert_eq!(module.code_file(), "module2"),
+            ref other => panic!("unexpected kind: {:?}", other),
+        }
+        assert!(matches!(words[3].kind, StackWordKind::Stack));
+        assert!(matches!(words[4].kind, StackWordKind::Unknown));
+    }
+
+    #[test]
+    fn test_stack_words_unaligned() {
+        let symbolizer = Symbolizer::new(SimpleSymbolSupplier::new(vec![]));
+        let modules = MinidumpModuleList::from_modules(vec![MinidumpModule::new(
+            0x400000, 0x10000, "module1",
+        )]);
+        let context = MinidumpContext {
+            raw: MinidumpRawContext::X86(CONTEXT_X86::default()),
+            valid: MinidumpContextValidity::All,
+        };
+        // Words are 4 bytes on x86, and the stack starts in the middle of one.
+        let stack = Section::with_endian(Endian::Little)
+            .D16(0xffff)
+            .D32(0x401000)
+            .D32(0x1002);
+        let bytes = stack.get_contents().unwrap();
+        let stack_memory = MinidumpMemory {
+            desc: Default::default(),
+            base_address: 0x1002,
+            size: bytes.len() as u64,
+            bytes: &bytes,
+        };
+
+        let words = stack_words(&context, &stack_memory, &modules, &symbolizer).collect::<Vec<_>>();
+        assert_eq!(words.len(), 2);
+        assert_eq!(words[0].address, 0x1004);
+        assert_eq!(words[0].value, 0x401000);
+        assert!(matches!(words[0].kind, StackWordKind::Module(_)));
+        assert_eq!(words[1].address, 0x1008);
+        assert!(matches!(words[1].kind, StackWordKind::Stack));
+    }
+}
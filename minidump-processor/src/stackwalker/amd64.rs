@@ -6,23 +6,86 @@
 // so that it can be copied between the two with minimal changes. It's not
 // worth the effort to *actually* unify the implementations.
 
-use crate::process_state::{FrameTrust, StackFrame};
+use crate::process_state::{FrameTrust, RegisterProvenance, StackFrame};
 use crate::stackwalker::unwind::Unwind;
-use crate::stackwalker::CfiStackWalker;
+use crate::stackwalker::{
+    in_non_executable_memory, CfiStackWalker, StackScan, StackScanner, UnwindDiagnostics,
+    MAX_CFI_CHECKS,
+};
 use crate::SymbolProvider;
 use log::trace;
 use minidump::format::CONTEXT_AMD64;
 use minidump::{
-    MinidumpContext, MinidumpContextValidity, MinidumpMemory, MinidumpModuleList,
-    MinidumpRawContext,
+    CpuContext, MinidumpContext, MinidumpContextValidity, MinidumpMemory, MinidumpMemoryInfoList,
+    MinidumpModuleList, MinidumpRawContext, Module,
 };
-use std::collections::HashSet;
+use std::collections::{HashMap, HashSet};
 
 type Pointer = u64;
 const POINTER_WIDTH: Pointer = 8;
 const INSTRUCTION_REGISTER: &str = "rip";
 const STACK_POINTER_REGISTER: &str = "rsp";
 const FRAME_POINTER_REGISTER: &str = "rbp";
+const CALLEE_SAVED_REGS: &[&str] = &["rbx", "rbp", "r12", "r13", "r14", "r15"];
+
+fn get_caller_of_leaf<P>(
+    ctx: &CONTEXT_AMD64,
+    valid: &MinidumpContextValidity,
+    stack_memory: &MinidumpMemory,
+    modules: &MinidumpModuleList,
+    memory_info: Option<&MinidumpMemoryInfoList>,
+    symbol_provider: &P,
+) -> Option<StackFrame>
+where
+    P: SymbolProvider,
+{
+    trace!("trying to get frame of function at its entry");
+    let last_sp = ctx.get_register(STACK_POINTER_REGISTER, valid)?;
+    // None of the function has run yet, so it hasn't set up a frame. The CALL
+    // that got here has just pushed the return address, and every other
+    // register still has the caller's value. This is the rule CFI gives every
+    // function at its entry, but whether we're at the entry is only known from
+    // the symbols, so the frame gets a trust of its own.
+    //
+    // %ip_new = *(%sp_old)
+    // %sp_new = %sp_old + ptr
+    let caller_ip = stack_memory.get_memory_at_address(last_sp)?;
+    let caller_sp = last_sp + POINTER_WIDTH;
+    // Don't accept obviously wrong instruction pointers.
+    if !instruction_seems_valid(caller_ip, modules, memory_info, symbol_provider) {
+        return None;
+    }
+    // Don't accept obviously wrong stack pointers.
+    if !stack_seems_valid(caller_sp, last_sp, stack_memory) {
+        return None;
+    }
+
+    let mut caller_ctx = CONTEXT_AMD64 {
+        rip: caller_ip,
+        rsp: caller_sp,
+        ..CONTEXT_AMD64::default()
+    };
+    let mut caller_valid = HashSet::new();
+    caller_valid.insert(INSTRUCTION_REGISTER);
+    caller_valid.insert(STACK_POINTER_REGISTER);
+    let mut provenance = HashMap::new();
+    provenance.insert(INSTRUCTION_REGISTER, RegisterProvenance::Recovered);
+    provenance.insert(STACK_POINTER_REGISTER, RegisterProvenance::Recovered);
+    for &reg in CALLEE_SAVED_REGS {
+        if let Some(val) = ctx.get_register(reg, valid) {
+            caller_ctx.set_register(reg, val)?;
+            caller_valid.insert(reg);
+            provenance.insert(reg, RegisterProvenance::CalleeSaved);
+        }
+    }
+    let context = MinidumpContext {
+        raw: MinidumpRawContext::Amd64(caller_ctx),
+        valid: MinidumpContextValidity::Some(caller_valid),
+    };
+    let mut frame = StackFrame::from_caller_context(context, FrameTrust::FunctionEntry);
+    frame.register_provenance = Some(provenance);
+    Some(frame)
+}
 
 fn get_caller_by_frame_pointer<P>(
     ctx: &CONTEXT_AMD64,
@@ -30,6 +93,7 @@ fn get_caller_by_frame_pointer<P>(
     _trust: FrameTrust,
     stack_memory: &MinidumpMemory,
     modules: &MinidumpModuleList,
+    memory_info: Option<&MinidumpMemoryInfoList>,
     symbol_provider: &P,
 ) -> Option<StackFrame>
 where
@@ -85,7 +149,7 @@ where
     // frame pointer is still inside stack memory.
     let _unused: Pointer = stack_memory.get_memory_at_address(caller_bp as u64)?;
     // Don't accept obviously wrong instruction pointers.
-    if !instruction_seems_valid(caller_ip, modules, symbol_provider) {
+    if !instruction_seems_valid(caller_ip, modules, memory_info, symbol_provider) {
         return None;
     }
     // Don't accept obviously wrong stack pointers.
@@ -103,15 +167,20 @@ where
     valid.insert(INSTRUCTION_REGISTER);
     valid.insert(STACK_POINTER_REGISTER);
     valid.insert(FRAME_POINTER_REGISTER);
+    let provenance = valid
+        .iter()
+        .map(|&reg| (reg, RegisterProvenance::Recovered))
+        .collect();
     let context = MinidumpContext {
         raw: MinidumpRawContext::Amd64(caller_ctx),
         valid: MinidumpContextValidity::Some(valid),
     };
-    let mut frame = StackFrame::from_context(context, FrameTrust::FramePointer);
-    adjust_instruction(&mut frame, caller_ip);
+    let mut frame = StackFrame::from_caller_context(context, FrameTrust::FramePointer);
+    frame.register_provenance = Some(provenance);
     Some(frame)
 }
 
+#[allow(clippy::too_many_arguments)]
 fn get_caller_by_cfi<P>(
     ctx: &CONTEXT_AMD64,
     valid: &MinidumpContextValidity,
@@ -119,7 +188,9 @@ fn get_caller_by_cfi<P>(
     stack_memory: &MinidumpMemory,
     grand_callee_frame: Option<&StackFrame>,
     modules: &MinidumpModuleList,
+    memory_info: Option<&MinidumpMemoryInfoList>,
     symbol_provider: &P,
+    diagnostics: &mut UnwindDiagnostics,
 ) -> Option<StackFrame>
 where
     P: SymbolProvider,
@@ -137,7 +208,16 @@ where
 
     let last_sp = ctx.rsp;
     let last_ip = ctx.rip;
-    let module = modules.module_at_address(last_ip as u64)?;
+    let module = match modules.module_at_address(last_ip as u64) {
+        Some(module) => module,
+        None => {
+            diagnostics.note(format_args!(
+                "  call frame info: no module contains ip {:#x}",
+                last_ip
+            ));
+            return None;
+        }
+    };
     trace!("  ...found module");
 
     let grand_callee_parameter_size = grand_callee_frame
@@ -157,35 +237,67 @@ where
         stack_memory,
     };
 
-    symbol_provider.walk_frame(module, &mut stack_walker)?;
+    if symbol_provider
+        .walk_frame(module, &mut stack_walker)
+        .is_none()
+    {
+        if diagnostics.enabled() {
+            diagnostics.note(format_args!(
+                "  call frame info: {} has no usable rules for ip {:#x}",
+                module.code_file(),
+                last_ip
+            ));
+        }
+        return None;
+    }
     let caller_ip = stack_walker.caller_ctx.rip;
     let caller_sp = stack_walker.caller_ctx.rsp;
 
-    // Don't accept obviously wrong instruction pointers.
-    if !instruction_seems_valid(caller_ip, modules, symbol_provider) {
+    // CFI that recovers a caller outside of any module is wrong about this
+    // frame, so don't trust it. The frame pointer or a stack scan may still
+    // find the real caller.
+    if !instruction_seems_valid(caller_ip, modules, memory_info, symbol_provider) {
+        trace!("  ...cfi gave a bogus caller");
+        diagnostics.note(format_args!(
+            "  call frame info: the caller's ip {:#x} isn't in any module",
+            caller_ip
+        ));
         return None;
     }
     // Don't accept obviously wrong stack pointers.
     if !stack_seems_valid(caller_sp, last_sp, stack_memory) {
+        diagnostics.note(format_args!(
+            "  call frame info: the caller's sp {:#x} isn't on the stack above {:#x}",
+            caller_sp, last_sp
+        ));
         return None;
     }
 
+    // Every register the CFI gives the caller is computed by its rules.
+    let provenance = stack_walker
+        .caller_validity
+        .iter()
+        .map(|&reg| (reg, RegisterProvenance::Recovered))
+        .collect();
     let context = MinidumpContext {
         raw: MinidumpRawContext::Amd64(stack_walker.caller_ctx),
         valid: MinidumpContextValidity::Some(stack_walker.caller_validity),
     };
-    let mut frame = StackFrame::from_context(context, FrameTrust::CallFrameInfo);
-    adjust_instruction(&mut frame, caller_ip);
+    let mut frame = StackFrame::from_caller_context(context, FrameTrust::CallFrameInfo);
+    frame.register_provenance = Some(provenance);
     Some(frame)
 }
 
+#[allow(clippy::too_many_arguments)]
 fn get_caller_by_scan<P>(
     ctx: &CONTEXT_AMD64,
     valid: &MinidumpContextValidity,
     trust: FrameTrust,
     stack_memory: &MinidumpMemory,
     modules: &MinidumpModuleList,
+    memory_info: Option<&MinidumpMemoryInfoList>,
     symbol_provider: &P,
+    scanner: &StackScanner,
 ) -> Option<StackFrame>
 where
     P: SymbolProvider,
@@ -196,6 +308,16 @@ where
     // we assume it's an ip value that was pushed by the CALL instruction that created
     // the current frame. The next frame is then assumed to end just before that
     // ip value.
+    //
+    // The frame we find gets `FrameTrust::Scan`, unless `scanner` is to look
+    // for a caller that can be verified. Then a
+    // caller whose return address follows a call instruction, or that CFI
+    // can unwind from, is taken over earlier plausible ones, and gets
+    // `FrameTrust::CfiScan`. Only the first `MAX_CFI_CHECKS` plausible words
+    // that don't follow a call are tried with CFI.
+    if scanner.mode == StackScan::Off {
+        return None;
+    }
     let last_bp = match valid {
         MinidumpContextValidity::All => Some(ctx.rbp),
         MinidumpContextValidity::Some(ref which) => {
@@ -224,10 +346,15 @@ where
         default_scan_range
     };
 
+    let mut first_plausible = None;
+    let mut cfi_checks = 0;
     for i in 0..scan_range {
         let address_of_ip = last_sp + i * POINTER_WIDTH;
-        let caller_ip = stack_memory.get_memory_at_address(address_of_ip as u64)?;
-        if instruction_seems_valid(caller_ip, modules, symbol_provider) {
+        let caller_ip = match stack_memory.get_memory_at_address(address_of_ip as u64) {
+            Some(caller_ip) => caller_ip,
+            None => break,
+        };
+        if instruction_seems_valid(caller_ip, modules, memory_info, symbol_provider) {
             // ip is pushed by CALL, so sp is just address_of_ip + ptr
             let caller_sp = address_of_ip + POINTER_WIDTH;
 
@@ -259,7 +386,10 @@ where
                 let address_of_bp = address_of_ip - POINTER_WIDTH;
                 // Can assume this resolves because we already walked over it when
                 // checking address_of_ip values.
-                let bp = stack_memory.get_memory_at_address(address_of_bp as u64)?;
+                let bp = match stack_memory.get_memory_at_address(address_of_bp as u64) {
+                    Some(bp) => bp,
+                    None => break,
+                };
                 if last_bp == address_of_bp
                     && bp > address_of_ip
                     && bp - address_of_bp <= MAX_REASONABLE_GAP_BETWEEN_FRAMES
@@ -294,23 +424,102 @@ where
             if caller_bp.is_some() {
                 valid.insert(FRAME_POINTER_REGISTER);
             }
+            let provenance = valid
+                .iter()
+                .map(|&reg| (reg, RegisterProvenance::Scanned))
+                .collect();
+            let valid = MinidumpContextValidity::Some(valid);
+            let verified = scanner.mode != StackScan::FirstPlausible
+                && (scanner.follows_call(caller_ip) || {
+                    cfi_checks += 1;
+                    cfi_checks <= MAX_CFI_CHECKS
+                        && cfi_unwinds_caller(
+                            &caller_ctx,
+                            &valid,
+                            stack_memory,
+                            modules,
+                            memory_info,
+                            symbol_provider,
+                        )
+                });
+            let trust = if verified {
+                FrameTrust::CfiScan
+            } else {
+                FrameTrust::Scan
+            };
             let context = MinidumpContext {
                 raw: MinidumpRawContext::Amd64(caller_ctx),
-                valid: MinidumpContextValidity::Some(valid),
+                valid,
             };
-            let mut frame = StackFrame::from_context(context, FrameTrust::Scan);
-            adjust_instruction(&mut frame, caller_ip);
-            return Some(frame);
+            let mut frame = StackFrame::from_caller_context(context, trust);
+            frame.register_provenance = Some(provenance);
+            match scanner.mode {
+                StackScan::FirstPlausible => return Some(frame),
+                _ if verified => return Some(frame),
+                StackScan::PreferVerified if first_plausible.is_none() => {
+                    first_plausible = Some(frame)
+                }
+                _ => {}
+            }
         }
     }
 
-    None
+    first_plausible
+}
+
+/// Whether the CFI for a caller found by scanning can unwind it, which says
+/// the caller's return address really is in a function, and its stack
+/// pointer is where that function expects.
+///
+/// The caller's own caller has to look right too, as it would when walking
+/// by CFI: its instruction pointer in a module and its stack pointer further
+/// up the stack.
+fn cfi_unwinds_caller<P>(
+    caller_ctx: &CONTEXT_AMD64,
+    valid: &MinidumpContextValidity,
+    stack_memory: &MinidumpMemory,
+    modules: &MinidumpModuleList,
+    memory_info: Option<&MinidumpMemoryInfoList>,
+    symbol_provider: &P,
+) -> bool
+where
+    P: SymbolProvider,
+{
+    let module = match modules.module_at_address(caller_ctx.rip) {
+        Some(module) => module,
+        None => return false,
+    };
+    let mut stack_walker = CfiStackWalker {
+        instruction: caller_ctx.rip,
+        grand_callee_parameter_size: 0,
+
+        callee_ctx: caller_ctx,
+        callee_validity: valid,
+
+        caller_ctx: CONTEXT_AMD64::default(),
+        caller_validity: HashSet::new(),
+
+        stack_memory,
+    };
+    if symbol_provider
+        .walk_frame(module, &mut stack_walker)
+        .is_none()
+    {
+        return false;
+    }
+    instruction_seems_valid(
+        stack_walker.caller_ctx.rip,
+        modules,
+        memory_info,
+        symbol_provider,
+    ) && stack_seems_valid(stack_walker.caller_ctx.rsp, caller_ctx.rsp, stack_memory)
 }
 
 #[allow(clippy::match_like_matches_macro)]
 fn instruction_seems_valid<P>(
     instruction: Pointer,
     modules: &MinidumpModuleList,
+    memory_info: Option<&MinidumpMemoryInfoList>,
     _symbol_provider: &P,
 ) -> bool
 where
@@ -319,6 +528,9 @@ where
     if is_non_canonical(instruction) {
         return false;
     }
+    if in_non_executable_memory(instruction, memory_info) {
+        return false;
+    }
     if let Some(_module) = modules.module_at_address(instruction as u64) {
         // TODO: if mapped, check if this instruction actually maps to a function line
         true
@@ -343,16 +555,6 @@ fn stack_seems_valid(
         .is_some()
 }
 
-fn adjust_instruction(frame: &mut StackFrame, caller_ip: Pointer) {
-    // A caller's ip is the return address, which is the instruction
-    // after the CALL that caused us to arrive at the callee. Set
-    // the value to one less than that, so it points within the
-    // CALL instruction.
-    if caller_ip > 0 {
-        frame.instruction = caller_ip as u64 - 1;
-    }
-}
-
 fn is_non_canonical(ptr: Pointer) -> bool {
     // x64 has the notion of a "canonical address", as a result of only 48 bits
     // of a pointer actually being used, because this is all that a 4-level page
@@ -374,10 +576,14 @@ impl Unwind for CONTEXT_AMD64 {
         &self,
         valid: &MinidumpContextValidity,
         trust: FrameTrust,
+        at_function_entry: bool,
         stack_memory: Option<&MinidumpMemory>,
         grand_callee_frame: Option<&StackFrame>,
         modules: &MinidumpModuleList,
+        memory_info: Option<&MinidumpMemoryInfoList>,
         syms: &P,
+        scan: &StackScanner,
+        diagnostics: &mut UnwindDiagnostics,
     ) -> Option<StackFrame>
     where
         P: SymbolProvider,
@@ -385,21 +591,69 @@ impl Unwind for CONTEXT_AMD64 {
         stack_memory
             .as_ref()
             .and_then(|stack| {
-                get_caller_by_cfi(self, valid, trust, stack, grand_callee_frame, modules, syms)
-                    .or_else(|| {
-                        get_caller_by_frame_pointer(self, valid, trust, stack, modules, syms)
-                    })
-                    .or_else(|| get_caller_by_scan(self, valid, trust, stack, modules, syms))
+                // At a function's entry its caller's frame pointer is still in
+                // %rbp, so unwinding by frame pointer would skip the caller.
+                let leaf = if at_function_entry {
+                    let frame = get_caller_of_leaf(self, valid, stack, modules, memory_info, syms);
+                    diagnostics.tried("function entry", frame)
+                } else {
+                    None
+                };
+                leaf.or_else(|| {
+                    let frame = get_caller_by_cfi(
+                        self,
+                        valid,
+                        trust,
+                        stack,
+                        grand_callee_frame,
+                        modules,
+                        memory_info,
+                        syms,
+                        diagnostics,
+                    );
+                    diagnostics.tried("call frame info", frame)
+                })
+                .or_else(|| {
+                    let frame = get_caller_by_frame_pointer(
+                        self,
+                        valid,
+                        trust,
+                        stack,
+                        modules,
+                        memory_info,
+                        syms,
+                    );
+                    diagnostics.tried("frame pointer", frame)
+                })
+                .or_else(|| {
+                    let frame = get_caller_by_scan(
+                        self,
+                        valid,
+                        trust,
+                        stack,
+                        modules,
+                        memory_info,
+                        syms,
+                        scan,
+                    );
+                    diagnostics.tried("stack scanning", frame)
+                })
             })
             .and_then(|frame| {
                 // Treat an instruction address of 0 as end-of-stack.
                 if frame.context.get_instruction_pointer() == 0 {
+                    diagnostics.note(format_args!("  rejected the caller: ip 0 ends the stack"));
                     return None;
                 }
                 // If the new stack pointer is at a lower address than the old,
                 // then that's clearly incorrect. Treat this as end-of-stack to
                 // enforce progress and avoid infinite loops.
                 if frame.context.get_stack_pointer() <= self.rsp {
+                    diagnostics.note(format_args!(
+                        "  rejected the caller: its sp {:#x} isn't above {:#x}",
+                        frame.context.get_stack_pointer(),
+                        self.rsp
+                    ));
                     return None;
                 }
                 Some(frame)
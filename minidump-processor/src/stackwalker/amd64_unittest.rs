@@ -4,13 +4,14 @@
 use crate::process_state::*;
 use crate::stackwalker::walk_stack;
 use breakpad_symbols::{SimpleSymbolSupplier, Symbolizer};
-use minidump::format::CONTEXT_AMD64;
+use minidump::format::{CvSignature, CONTEXT_AMD64, CV_INFO_PDB20};
 use minidump::*;
 use test_assembler::*;
 
 struct TestFixture {
     pub raw: CONTEXT_AMD64,
     pub modules: MinidumpModuleList,
+    pub unloaded_modules: MinidumpUnloadedModuleList,
     pub symbolizer: Symbolizer,
 }
 
@@ -24,6 +25,7 @@ impl TestFixture {
                 MinidumpModule::new(0x00007400c0000000, 0x10000, "module1"),
                 MinidumpModule::new(0x00007500b0000000, 0x10000, "module2"),
             ]),
+            unloaded_modules: MinidumpUnloadedModuleList::new(),
             symbolizer: Symbolizer::new(SimpleSymbolSupplier::new(vec![])),
         }
     }
@@ -46,6 +48,7 @@ impl TestFixture {
             &Some(&context),
             Some(&stack_memory),
             &self.modules,
+            &self.unloaded_modules,
             &self.symbolizer,
         )
     }
@@ -192,6 +195,7 @@ fn test_scan_without_symbols() {
         let f0 = &s.frames[0];
         assert_eq!(f0.trust, FrameTrust::Context);
         assert_eq!(f0.context.valid, MinidumpContextValidity::All);
+        assert_eq!(f0.register_provenance("rip"), None);
     }
 
     {
@@ -213,6 +217,14 @@ fn test_scan_without_symbols() {
         } else {
             unreachable!();
         }
+        assert_eq!(
+            f1.register_provenance("rip"),
+            Some(RegisterProvenance::Scanned)
+        );
+        assert_eq!(
+            f1.register_provenance("rbp"),
+            Some(RegisterProvenance::Scanned)
+        );
     }
 
     {
@@ -235,6 +247,201 @@ fn test_scan_without_symbols() {
     }
 }
 
+// At the first instruction of a function, the function hasn't pushed %rbp
+// yet, so %rbp is still the caller's frame pointer. The caller must be found
+// from the return address at %rsp instead, or it would be skipped.
+#[test]
+fn test_leaf_at_function_entry() {
+    let mut f = TestFixture::new();
+    let symbol_path = std::path::Path::new(env!("CARGO_MANIFEST_DIR")).join("../testdata/symbols");
+    f.symbolizer = Symbolizer::new(SimpleSymbolSupplier::new(vec![symbol_path]));
+    let mut module = MinidumpModule::new(0x00007400c0000000, 0x10000, "c:\\leaf.dll");
+    module.codeview_info = Some(CodeView::Pdb20(CV_INFO_PDB20 {
+        cv_signature: CvSignature::Pdb20 as u32,
+        cv_offset: 0,
+        signature: 0x24681357,
+        age: 1,
+        pdb_file_name: b"leaf.pdb\0".to_vec(),
+    }));
+    f.modules = MinidumpModuleList::from_modules(vec![
+        module,
+        MinidumpModule::new(0x00007500b0000000, 0x10000, "module2"),
+    ]);
+
+    let mut stack = Section::new();
+    let stack_start = 0x8000000080000000;
+    let return_address1 = 0x00007400c0000110;
+    let return_address2 = 0x00007500b0000100;
+    stack.start().set_const(stack_start);
+
+    let frame1_sp = Label::new();
+    let frame1_rbp = Label::new();
+    let frame2_sp = Label::new();
+    let frame2_rbp = Label::new();
+
+    stack = stack
+        // frame 0
+        .D64(return_address1) // return address, pushed by the call
+        // frame 1
+        .mark(&frame1_sp)
+        .append_repeated(16, 0) // space
+        .mark(&frame1_rbp)
+        .D64(&frame2_rbp) // caller-pushed %rbp
+        .D64(return_address2) // return address
+        // frame 2
+        .mark(&frame2_sp)
+        .append_repeated(16, 0) // space
+        .mark(&frame2_rbp) // end of stack
+        .D64(0)
+        .D64(0);
+
+    f.raw.rip = 0x00007400c0000200;
+    f.raw.rsp = stack.start().value().unwrap();
+    f.raw.rbp = frame1_rbp.value().unwrap();
+    f.raw.rbx = 0x1234;
+
+    let s = f.walk_stack(stack);
+    assert_eq!(s.frames.len(), 3);
+
+    {
+        // To avoid reusing locals by mistake
+        let f0 = &s.frames[0];
+        assert_eq!(f0.trust, FrameTrust::Context);
+        assert_eq!(f0.function_name.as_deref(), Some("leaf"));
+    }
+
+    {
+        // To avoid reusing locals by mistake
+        let f1 = &s.frames[1];
+        assert_eq!(f1.trust, FrameTrust::FunctionEntry);
+        assert_eq!(f1.function_name.as_deref(), Some("calls_leaf"));
+        if let MinidumpContextValidity::Some(ref which) = f1.context.valid {
+            assert!(which.contains("rip"));
+            assert!(which.contains("rsp"));
+            assert!(which.contains("rbp"));
+            assert!(which.contains("rbx"));
+            assert!(!which.contains("rax"));
+        } else {
+            unreachable!();
+        }
+        if let MinidumpRawContext::Amd64(ctx) = &f1.context.raw {
+            assert_eq!(ctx.rip, return_address1);
+            assert_eq!(ctx.rsp, frame1_sp.value().unwrap());
+            assert_eq!(ctx.rbp, frame1_rbp.value().unwrap());
+            assert_eq!(ctx.rbx, 0x1234);
+        } else {
+            unreachable!();
+        }
+        // The return address and stack pointer come from the entry rule, the
+        // rest are passed through from the leaf.
+        assert_eq!(
+            f1.register_provenance("rip"),
+            Some(RegisterProvenance::Recovered)
+        );
+        assert_eq!(
+            f1.register_provenance("rsp"),
+            Some(RegisterProvenance::Recovered)
+        );
+        assert_eq!(
+            f1.register_provenance("rbx"),
+            Some(RegisterProvenance::CalleeSaved)
+        );
+        assert_eq!(
+            f1.register_provenance("rax"),
+            Some(RegisterProvenance::Unknown)
+        );
+    }
+
+    {
+        // To avoid reusing locals by mistake
+        let f2 = &s.frames[2];
+        assert_eq!(f2.trust, FrameTrust::FramePointer);
+        assert_eq!(f2.module.as_ref().unwrap().code_file(), "module2");
+        if let MinidumpRawContext::Amd64(ctx) = &f2.context.raw {
+            assert_eq!(ctx.rip, return_address2);
+            assert_eq!(ctx.rsp, frame2_sp.value().unwrap());
+            assert_eq!(ctx.rbp, frame2_rbp.value().unwrap());
+        } else {
+            unreachable!();
+        }
+    }
+}
+
+// When CFI recovers a caller that isn't in any module, the CFI is wrong for
+// this frame. The walker should fall back to the frame pointer, like it does
+// when there's no CFI at all.
+#[test]
+fn test_bogus_cfi_frame_pointer() {
+    let mut f = TestFixture::new();
+    let symbol_path = std::path::Path::new(env!("CARGO_MANIFEST_DIR")).join("../testdata/symbols");
+    f.symbolizer = Symbolizer::new(SimpleSymbolSupplier::new(vec![symbol_path]));
+    let mut module = MinidumpModule::new(0x00007400c0000000, 0x10000, "c:\\bogus_cfi.dll");
+    module.codeview_info = Some(CodeView::Pdb20(CV_INFO_PDB20 {
+        cv_signature: CvSignature::Pdb20 as u32,
+        cv_offset: 0,
+        signature: 0x13579246,
+        age: 1,
+        pdb_file_name: b"bogus_cfi.pdb\0".to_vec(),
+    }));
+    f.modules = MinidumpModuleList::from_modules(vec![
+        module,
+        MinidumpModule::new(0x00007500b0000000, 0x10000, "module2"),
+    ]);
+
+    let mut stack = Section::new();
+    let stack_start = 0x8000000080000000;
+    let return_address = 0x00007500b0000100;
+    stack.start().set_const(stack_start);
+
+    let frame0_rbp = Label::new();
+    let frame1_sp = Label::new();
+    let frame1_rbp = Label::new();
+
+    // The CFI says the return address is right above the first local, but
+    // the function actually keeps a frame pointer further up.
+    stack = stack
+        // frame 0
+        .D64(0) // local
+        .D64(0x1234) // what the CFI thinks is the return address
+        .mark(&frame0_rbp)
+        .D64(&frame1_rbp) // saved %rbp
+        .D64(return_address) // return address, pushed by the call
+        // frame 1
+        .mark(&frame1_sp)
+        .D64(0) // local
+        .mark(&frame1_rbp)
+        .D64(0) // saved %rbp, end of stack
+        .D64(0); // return address, end of stack
+
+    f.raw.rip = 0x00007400c0000110;
+    f.raw.rsp = stack.start().value().unwrap();
+    f.raw.rbp = frame0_rbp.value().unwrap();
+
+    let s = f.walk_stack(stack);
+    assert_eq!(s.frames.len(), 2);
+
+    {
+        // To avoid reusing locals by mistake
+        let f0 = &s.frames[0];
+        assert_eq!(f0.trust, FrameTrust::Context);
+        assert_eq!(f0.function_name.as_deref(), Some("bogus_cfi"));
+    }
+
+    {
+        // To avoid reusing locals by mistake
+        let f1 = &s.frames[1];
+        assert_eq!(f1.trust, FrameTrust::FramePointer);
+        assert_eq!(f1.module.as_ref().unwrap().code_file(), "module2");
+        if let MinidumpRawContext::Amd64(ctx) = &f1.context.raw {
+            assert_eq!(ctx.rip, return_address);
+            assert_eq!(ctx.rsp, frame1_sp.value().unwrap());
+            assert_eq!(ctx.rbp, frame1_rbp.value().unwrap());
+        } else {
+            unreachable!();
+        }
+    }
+}
+
 /*
 // Walk a traditional frame. A traditional frame saves the caller's
 // %ebp just below the return address, and has its own %ebp pointing
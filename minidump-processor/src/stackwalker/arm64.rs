@@ -0,0 +1,494 @@
+// Copyright 2015 Ted Mielczarek. See the COPYRIGHT
+// file at the top-level directory of this distribution.
+
+use crate::process_state::{FrameTrust, RegisterProvenance, StackFrame, SymbolizedLocation};
+use crate::stackwalker::unwind::Unwind;
+use crate::stackwalker::{
+    in_non_executable_memory, CfiStackWalker, StackScanner, UnwindDiagnostics,
+};
+use crate::SymbolProvider;
+use log::trace;
+use minidump::format::{
+    Arm64RegisterNumbers, ContextFlagsCpu, CONTEXT_ARM64, CONTEXT_ARM64_OLD,
+    FLOATING_SAVE_AREA_ARM64,
+};
+use minidump::{
+    CpuContext, MinidumpContext, MinidumpContextValidity, MinidumpMemory, MinidumpMemoryInfoList,
+    MinidumpModuleList, MinidumpRawContext, Module,
+};
+use std::collections::{HashMap, HashSet};
+
+type Pointer = u64;
+const POINTER_WIDTH: Pointer = 8;
+const INSTRUCTION_REGISTER: &str = "pc";
+const STACK_POINTER_REGISTER: &str = "sp";
+const FRAME_POINTER_REGISTER: &str = "x29";
+const LINK_REGISTER: &str = "x30";
+const CALLEE_SAVED_REGS: &[&str] = &[
+    "x19", "x20", "x21", "x22", "x23", "x24", "x25", "x26", "x27", "x28", "x29",
+];
+
+fn get_caller_of_leaf<P>(
+    ctx: &CONTEXT_ARM64,
+    valid: &MinidumpContextValidity,
+    modules: &MinidumpModuleList,
+    memory_info: Option<&MinidumpMemoryInfoList>,
+    symbol_provider: &P,
+) -> Option<StackFrame>
+where
+    P: SymbolProvider,
+{
+    trace!("trying to get frame of function at its entry");
+    // None of the function has run yet, so it hasn't set up a frame or saved
+    // the link register. The return address is still in %lr, and every other
+    // register still has the caller's value. This is the rule CFI gives every
+    // function at its entry, but whether we're at the entry is only known from
+    // the symbols, so the frame gets a trust of its own.
+    //
+    // %pc_new = %lr_old
+    // %sp_new = %sp_old
+    let caller_pc = ctx.get_register(LINK_REGISTER, valid)?;
+    let caller_sp = ctx.get_register(STACK_POINTER_REGISTER, valid)?;
+    // Don't accept obviously wrong instruction pointers.
+    if !instruction_seems_valid(caller_pc, modules, memory_info, symbol_provider) {
+        return None;
+    }
+
+    let mut caller_ctx = CONTEXT_ARM64 {
+        pc: caller_pc,
+        ..CONTEXT_ARM64::default()
+    };
+    caller_ctx.iregs[Arm64RegisterNumbers::StackPointer as usize] = caller_sp;
+    let mut caller_valid = HashSet::new();
+    caller_valid.insert(INSTRUCTION_REGISTER);
+    caller_valid.insert(STACK_POINTER_REGISTER);
+    let mut provenance = HashMap::new();
+    provenance.insert(INSTRUCTION_REGISTER, RegisterProvenance::Recovered);
+    provenance.insert(STACK_POINTER_REGISTER, RegisterProvenance::Recovered);
+    for &reg in CALLEE_SAVED_REGS {
+        if let Some(val) = ctx.get_register(reg, valid) {
+            caller_ctx.set_register(reg, val)?;
+            caller_valid.insert(reg);
+            provenance.insert(reg, RegisterProvenance::CalleeSaved);
+        }
+    }
+    let context = MinidumpContext {
+        raw: MinidumpRawContext::Arm64(caller_ctx),
+        valid: MinidumpContextValidity::Some(caller_valid),
+    };
+    let mut frame = StackFrame::from_caller_context(context, FrameTrust::FunctionEntry);
+    frame.register_provenance = Some(provenance);
+    Some(frame)
+}
+
+fn get_caller_of_frameless_leaf<P>(
+    ctx: &CONTEXT_ARM64,
+    valid: &MinidumpContextValidity,
+    modules: &MinidumpModuleList,
+    memory_info: Option<&MinidumpMemoryInfoList>,
+    symbol_provider: &P,
+) -> Option<StackFrame>
+where
+    P: SymbolProvider,
+{
+    trace!("trying to get frame of a leaf function without a frame record");
+    // A leaf function doesn't have to push a frame record, so %fp may still
+    // point at its caller's, and the return address is still in %lr. A
+    // function that has made calls has a stale return address into itself in
+    // %lr, though, so this is only done if symbols say that %lr points into
+    // another function. The leaf may have moved the stack pointer, but how far
+    // isn't known without CFI.
+    //
+    // %pc_new = %lr_old
+    // %sp_new = %sp_old
+    // %fp_new = %fp_old
+    let last_pc = ctx.get_register(INSTRUCTION_REGISTER, valid)?;
+    let caller_pc = ctx.get_register(LINK_REGISTER, valid)?;
+    let caller_sp = ctx.get_register(STACK_POINTER_REGISTER, valid)?;
+    let caller_fp = ctx.get_register(FRAME_POINTER_REGISTER, valid)?;
+    // Don't accept obviously wrong instruction pointers.
+    if !instruction_seems_valid(caller_pc, modules, memory_info, symbol_provider) {
+        return None;
+    }
+    // The return address follows the call, which is the instruction to look up.
+    let callee_function = function_base(last_pc, modules, symbol_provider);
+    let caller_function = function_base(caller_pc.saturating_sub(4), modules, symbol_provider);
+    match (callee_function, caller_function) {
+        (Some(callee), Some(caller)) if callee != caller => {}
+        _ => return None,
+    }
+
+    let mut caller_ctx = CONTEXT_ARM64 {
+        pc: caller_pc,
+        ..CONTEXT_ARM64::default()
+    };
+    caller_ctx.iregs[Arm64RegisterNumbers::StackPointer as usize] = caller_sp;
+    caller_ctx.iregs[Arm64RegisterNumbers::FramePointer as usize] = caller_fp;
+    let mut caller_valid = HashSet::new();
+    caller_valid.insert(INSTRUCTION_REGISTER);
+    caller_valid.insert(STACK_POINTER_REGISTER);
+    caller_valid.insert(FRAME_POINTER_REGISTER);
+    let mut provenance = HashMap::new();
+    provenance.insert(INSTRUCTION_REGISTER, RegisterProvenance::Recovered);
+    provenance.insert(STACK_POINTER_REGISTER, RegisterProvenance::Recovered);
+    provenance.insert(FRAME_POINTER_REGISTER, RegisterProvenance::CalleeSaved);
+    let context = MinidumpContext {
+        raw: MinidumpRawContext::Arm64(caller_ctx),
+        valid: MinidumpContextValidity::Some(caller_valid),
+    };
+    let mut frame = StackFrame::from_caller_context(context, FrameTrust::FramePointer);
+    frame.register_provenance = Some(provenance);
+    Some(frame)
+}
+
+fn get_caller_by_frame_pointer<P>(
+    ctx: &CONTEXT_ARM64,
+    valid: &MinidumpContextValidity,
+    trust: FrameTrust,
+    stack_memory: &MinidumpMemory,
+    modules: &MinidumpModuleList,
+    memory_info: Option<&MinidumpMemoryInfoList>,
+    symbol_provider: &P,
+) -> Option<StackFrame>
+where
+    P: SymbolProvider,
+{
+    trace!("trying to get frame by frame pointer");
+    if let MinidumpContextValidity::Some(ref which) = valid {
+        if !which.contains(FRAME_POINTER_REGISTER) {
+            return None;
+        }
+        if !which.contains(STACK_POINTER_REGISTER) {
+            return None;
+        }
+    }
+
+    let last_fp = ctx.iregs[Arm64RegisterNumbers::FramePointer as usize];
+    let last_sp = ctx.iregs[Arm64RegisterNumbers::StackPointer as usize];
+    if last_fp == 0 {
+        return None;
+    }
+    // Only the context frame can be in a leaf function, its callers all made
+    // a call.
+    if trust == FrameTrust::Context {
+        if let Some(frame) =
+            get_caller_of_frameless_leaf(ctx, valid, modules, memory_info, symbol_provider)
+        {
+            return Some(frame);
+        }
+    }
+    // Assume that the standard AAPCS64 frame chain is in use.
+    //
+    // A function that maintains a frame pointer stores a "frame record" in
+    // its prologue: a pair of the caller's frame pointer (%x29) and the link
+    // register (%x30) holding the return address, then points %x29 at that
+    // pair. The frame record is at the top of the function's frame, so the
+    // caller's stack pointer is just above it.
+    //
+    // %pc_new = *(%fp_old + ptr)
+    // %sp_new = %fp_old + ptr * 2
+    // %fp_new = *(%fp_old)
+
+    let caller_fp: Pointer = stack_memory.get_memory_at_address(last_fp)?;
+    let caller_pc: Pointer = stack_memory.get_memory_at_address(last_fp + POINTER_WIDTH)?;
+    let caller_sp = last_fp + POINTER_WIDTH * 2;
+
+    // The frame chain ends with a null frame pointer. The caller is still a
+    // real frame, but we can't unwind any further from it.
+    let caller_fp_valid = caller_fp != 0;
+    if caller_fp_valid {
+        // Frame records must be in the stack and get older as we go, otherwise
+        // the chain is corrupt and following it might loop forever.
+        if caller_fp <= last_fp {
+            return None;
+        }
+        let _unused: Pointer = stack_memory.get_memory_at_address(caller_fp)?;
+    }
+    // Don't accept obviously wrong instruction pointers.
+    if !instruction_seems_valid(caller_pc, modules, memory_info, symbol_provider) {
+        return None;
+    }
+    // Don't accept obviously wrong stack pointers.
+    if !stack_seems_valid(caller_sp, last_sp, stack_memory) {
+        return None;
+    }
+
+    let mut caller_ctx = CONTEXT_ARM64 {
+        pc: caller_pc,
+        ..CONTEXT_ARM64::default()
+    };
+    caller_ctx.iregs[Arm64RegisterNumbers::StackPointer as usize] = caller_sp;
+    caller_ctx.iregs[Arm64RegisterNumbers::FramePointer as usize] = caller_fp;
+    let mut valid = HashSet::new();
+    valid.insert(INSTRUCTION_REGISTER);
+    valid.insert(STACK_POINTER_REGISTER);
+    if caller_fp_valid {
+        valid.insert(FRAME_POINTER_REGISTER);
+    }
+    let provenance = valid
+        .iter()
+        .map(|&reg| (reg, RegisterProvenance::Recovered))
+        .collect();
+    let context = MinidumpContext {
+        raw: MinidumpRawContext::Arm64(caller_ctx),
+        valid: MinidumpContextValidity::Some(valid),
+    };
+    let mut frame = StackFrame::from_caller_context(context, FrameTrust::FramePointer);
+    frame.register_provenance = Some(provenance);
+    Some(frame)
+}
+
+#[allow(clippy::too_many_arguments)]
+fn get_caller_by_cfi<P>(
+    ctx: &CONTEXT_ARM64,
+    valid: &MinidumpContextValidity,
+    _trust: FrameTrust,
+    stack_memory: &MinidumpMemory,
+    grand_callee_frame: Option<&StackFrame>,
+    modules: &MinidumpModuleList,
+    memory_info: Option<&MinidumpMemoryInfoList>,
+    symbol_provider: &P,
+    diagnostics: &mut UnwindDiagnostics,
+) -> Option<StackFrame>
+where
+    P: SymbolProvider,
+{
+    trace!("trying to get frame by cfi");
+    if let MinidumpContextValidity::Some(ref which) = valid {
+        if !which.contains(INSTRUCTION_REGISTER) {
+            return None;
+        }
+        if !which.contains(STACK_POINTER_REGISTER) {
+            return None;
+        }
+    }
+    trace!("  ...context was good");
+
+    let last_sp = ctx.iregs[Arm64RegisterNumbers::StackPointer as usize];
+    let last_pc = ctx.pc;
+    let module = match modules.module_at_address(last_pc) {
+        Some(module) => module,
+        None => {
+            diagnostics.note(format_args!(
+                "  call frame info: no module contains pc {:#x}",
+                last_pc
+            ));
+            return None;
+        }
+    };
+    trace!("  ...found module");
+
+    let grand_callee_parameter_size = grand_callee_frame
+        .and_then(|f| f.parameter_size)
+        .unwrap_or(0);
+
+    let mut stack_walker = CfiStackWalker {
+        instruction: last_pc,
+        grand_callee_parameter_size,
+
+        callee_ctx: ctx,
+        callee_validity: valid,
+
+        caller_ctx: CONTEXT_ARM64::default(),
+        caller_validity: HashSet::new(),
+
+        stack_memory,
+    };
+
+    if symbol_provider
+        .walk_frame(module, &mut stack_walker)
+        .is_none()
+    {
+        if diagnostics.enabled() {
+            diagnostics.note(format_args!(
+                "  call frame info: {} has no usable rules for pc {:#x}",
+                module.code_file(),
+                last_pc
+            ));
+        }
+        return None;
+    }
+    let caller_pc = stack_walker.caller_ctx.pc;
+    let caller_sp = stack_walker.caller_ctx.iregs[Arm64RegisterNumbers::StackPointer as usize];
+
+    // Don't accept obviously wrong instruction pointers.
+    if !instruction_seems_valid(caller_pc, modules, memory_info, symbol_provider) {
+        diagnostics.note(format_args!(
+            "  call frame info: the caller's pc {:#x} isn't in any module",
+            caller_pc
+        ));
+        return None;
+    }
+    // Don't accept obviously wrong stack pointers.
+    if !stack_seems_valid(caller_sp, last_sp, stack_memory) {
+        diagnostics.note(format_args!(
+            "  call frame info: the caller's sp {:#x} isn't on the stack above {:#x}",
+            caller_sp, last_sp
+        ));
+        return None;
+    }
+
+    // Every register the CFI gives the caller is computed by its rules.
+    let provenance = stack_walker
+        .caller_validity
+        .iter()
+        .map(|&reg| (reg, RegisterProvenance::Recovered))
+        .collect();
+    let context = MinidumpContext {
+        raw: MinidumpRawContext::Arm64(stack_walker.caller_ctx),
+        valid: MinidumpContextValidity::Some(stack_walker.caller_validity),
+    };
+    let mut frame = StackFrame::from_caller_context(context, FrameTrust::CallFrameInfo);
+    frame.register_provenance = Some(provenance);
+    Some(frame)
+}
+
+/// The start of the function containing `address`, if symbols say.
+fn function_base<P>(
+    address: Pointer,
+    modules: &MinidumpModuleList,
+    symbol_provider: &P,
+) -> Option<u64>
+where
+    P: SymbolProvider,
+{
+    let module = modules.module_at_address(address)?;
+    let mut location = SymbolizedLocation::new(address);
+    symbol_provider.fill_symbol(module, &mut location);
+    location.function_base
+}
+
+fn instruction_seems_valid<P>(
+    instruction: Pointer,
+    modules: &MinidumpModuleList,
+    memory_info: Option<&MinidumpMemoryInfoList>,
+    _symbol_provider: &P,
+) -> bool
+where
+    P: SymbolProvider,
+{
+    if in_non_executable_memory(instruction, memory_info) {
+        return false;
+    }
+    // TODO: if mapped, check if this instruction actually maps to a function line
+    modules.module_at_address(instruction).is_some()
+}
+
+fn stack_seems_valid(
+    caller_sp: Pointer,
+    callee_sp: Pointer,
+    stack_memory: &MinidumpMemory,
+) -> bool {
+    // The stack shouldn't *grow* when we unwind
+    if caller_sp <= callee_sp {
+        return false;
+    }
+
+    // The stack pointer should be in the stack
+    stack_memory
+        .get_memory_at_address::<Pointer>(caller_sp)
+        .is_some()
+}
+
+/// `old` in the current aarch64 context layout, which is the one this unwinds.
+///
+/// The old layout has the same registers, only in a different order, so the
+/// callers of an old context are found like any other's.
+pub(crate) fn context_from_old(old: &CONTEXT_ARM64_OLD) -> CONTEXT_ARM64 {
+    let float_save = { old.float_save };
+    let flags = { old.context_flags } as u32 & !ContextFlagsCpu::CONTEXT_ARM64_OLD.bits();
+    CONTEXT_ARM64 {
+        context_flags: flags | ContextFlagsCpu::CONTEXT_ARM64.bits(),
+        cpsr: old.cpsr,
+        iregs: old.iregs,
+        pc: old.pc,
+        float_save: FLOATING_SAVE_AREA_ARM64 {
+            regs: float_save.regs,
+            fpsr: float_save.fpsr,
+            fpcr: float_save.fpcr,
+        },
+        ..CONTEXT_ARM64::default()
+    }
+}
+
+impl Unwind for CONTEXT_ARM64 {
+    fn get_caller_frame<P>(
+        &self,
+        valid: &MinidumpContextValidity,
+        trust: FrameTrust,
+        at_function_entry: bool,
+        stack_memory: Option<&MinidumpMemory>,
+        grand_callee_frame: Option<&StackFrame>,
+        modules: &MinidumpModuleList,
+        memory_info: Option<&MinidumpMemoryInfoList>,
+        syms: &P,
+        _scan: &StackScanner,
+        diagnostics: &mut UnwindDiagnostics,
+    ) -> Option<StackFrame>
+    where
+        P: SymbolProvider,
+    {
+        stack_memory
+            .as_ref()
+            .and_then(|stack| {
+                // At a function's entry its caller's frame record is still in
+                // %fp, so unwinding by frame pointer would skip the caller.
+                let leaf = if at_function_entry {
+                    let frame = get_caller_of_leaf(self, valid, modules, memory_info, syms);
+                    diagnostics.tried("function entry", frame)
+                } else {
+                    None
+                };
+                leaf.or_else(|| {
+                    let frame = get_caller_by_cfi(
+                        self,
+                        valid,
+                        trust,
+                        stack,
+                        grand_callee_frame,
+                        modules,
+                        memory_info,
+                        syms,
+                        diagnostics,
+                    );
+                    diagnostics.tried("call frame info", frame)
+                })
+                .or_else(|| {
+                    let frame = get_caller_by_frame_pointer(
+                        self,
+                        valid,
+                        trust,
+                        stack,
+                        modules,
+                        memory_info,
+                        syms,
+                    );
+                    diagnostics.tried("frame pointer", frame)
+                })
+            })
+            .and_then(|frame| {
+                // Treat an instruction address of 0 as end-of-stack.
+                if frame.context.get_instruction_pointer() == 0 {
+                    diagnostics.note(format_args!("  rejected the caller: pc 0 ends the stack"));
+                    return None;
+                }
+                // If the new stack pointer is at a lower address than the old,
+                // then that's clearly incorrect. Treat this as end-of-stack to
+                // enforce progress and avoid infinite loops. A leaf function,
+                // or one at its entry, may not have touched the stack, so its
+                // caller has the same stack pointer. Only the context frame
+                // can be in one, so the walk still makes progress.
+                let caller_sp = frame.context.get_stack_pointer();
+                let callee_sp = self.iregs[Arm64RegisterNumbers::StackPointer as usize];
+                if caller_sp < callee_sp || (caller_sp == callee_sp && trust != FrameTrust::Context)
+                {
+                    diagnostics.note(format_args!(
+                        "  rejected the caller: its sp {:#x} isn't above {:#x}",
+                        caller_sp, callee_sp
+                    ));
+                    return None;
+                }
+                Some(frame)
+            })
+    }
+}
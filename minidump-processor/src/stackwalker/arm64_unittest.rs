@@ -0,0 +1,425 @@
+// Copyright 2015 Ted Mielczarek. See the COPYRIGHT
+// file at the top-level directory of this distribution.
+
+use crate::process_state::*;
+use crate::stackwalker::walk_stack;
+use breakpad_symbols::{SimpleSymbolSupplier, Symbolizer};
+use minidump::format::{
+    Arm64RegisterNumbers, CvSignature, CONTEXT_ARM64, CONTEXT_ARM64_OLD, CV_INFO_PDB20,
+    FLOATING_SAVE_AREA_ARM64_OLD,
+};
+use minidump::*;
+use test_assembler::*;
+
+const FP: usize = Arm64RegisterNumbers::FramePointer as usize;
+const SP: usize = Arm64RegisterNumbers::StackPointer as usize;
+
+struct TestFixture {
+    pub raw: CONTEXT_ARM64,
+    pub modules: MinidumpModuleList,
+    pub unloaded_modules: MinidumpUnloadedModuleList,
+    pub symbolizer: Symbolizer,
+}
+
+impl TestFixture {
+    pub fn new() -> TestFixture {
+        TestFixture {
+            raw: CONTEXT_ARM64::default(),
+            // Give the two modules reasonable standard locations and names
+            // for tests to play with.
+            modules: MinidumpModuleList::from_modules(vec![
+                MinidumpModule::new(0x40000000, 0x10000, "module1"),
+                MinidumpModule::new(0x50000000, 0x10000, "module2"),
+            ]),
+            unloaded_modules: MinidumpUnloadedModuleList::new(),
+            symbolizer: Symbolizer::new(SimpleSymbolSupplier::new(vec![])),
+        }
+    }
+
+    pub fn walk_stack(&self, stack: Section) -> CallStack {
+        self.walk_context(MinidumpRawContext::Arm64(self.raw.clone()), stack)
+    }
+
+    /// Walk the stack from `raw` in the old aarch64 context layout.
+    pub fn walk_old_stack(&self, stack: Section) -> CallStack {
+        let old = CONTEXT_ARM64_OLD {
+            context_flags: 0x80000002,
+            iregs: self.raw.iregs,
+            pc: self.raw.pc,
+            cpsr: self.raw.cpsr,
+            float_save: FLOATING_SAVE_AREA_ARM64_OLD {
+                fpsr: 0,
+                fpcr: 0,
+                regs: [0; 32],
+            },
+        };
+        self.walk_context(MinidumpRawContext::OldArm64(old), stack)
+    }
+
+    fn walk_context(&self, raw: MinidumpRawContext, stack: Section) -> CallStack {
+        let context = MinidumpContext {
+            raw,
+            valid: MinidumpContextValidity::All,
+        };
+        let base = stack.start().value().unwrap();
+        let size = stack.size();
+        let stack = stack.get_contents().unwrap();
+        let stack_memory = MinidumpMemory {
+            desc: Default::default(),
+            base_address: base,
+            size,
+            bytes: &stack,
+        };
+        walk_stack(
+            &Some(&context),
+            Some(&stack_memory),
+            &self.modules,
+            &self.unloaded_modules,
+            &self.symbolizer,
+        )
+    }
+}
+
+#[test]
+fn test_simple() {
+    let mut f = TestFixture::new();
+    let stack = Section::new();
+    stack.start().set_const(0x80000000);
+    // Without a frame pointer or call frame information there's no way to
+    // find the context frame's caller.
+    f.raw.pc = 0x40000200;
+
+    let s = f.walk_stack(stack);
+    assert_eq!(s.info, CallStackInfo::Ok);
+    assert_eq!(s.frames.len(), 1);
+    let f = &s.frames[0];
+    let m = f.module.as_ref().unwrap();
+    assert_eq!(m.code_file(), "module1");
+}
+
+#[test]
+fn test_frame_pointer() {
+    // Each frame record holds the caller's frame pointer and the return
+    // address, and the frame pointer points at the frame record.
+    let mut f = TestFixture::new();
+    let mut stack = Section::new();
+    stack.start().set_const(0x80000000);
+
+    let return_address1 = 0x50000100;
+    let return_address2 = 0x50000900;
+    let frame0_fp = Label::new();
+    let frame1_sp = Label::new();
+    let frame1_fp = Label::new();
+    let frame2_sp = Label::new();
+
+    stack = stack
+        // frame 0
+        .append_repeated(16, 0) // space
+        .mark(&frame0_fp)
+        .D64(&frame1_fp) // saved fp
+        .D64(return_address1) // saved lr
+        // frame 1
+        .mark(&frame1_sp)
+        .append_repeated(32, 0) // space
+        .mark(&frame1_fp)
+        .D64(0) // saved fp (end of the frame chain)
+        .D64(return_address2) // saved lr
+        // frame 2
+        .mark(&frame2_sp)
+        .append_repeated(16, 0);
+
+    f.raw.pc = 0x40000200;
+    f.raw.iregs[FP] = frame0_fp.value().unwrap();
+    f.raw.iregs[SP] = stack.start().value().unwrap();
+
+    let s = f.walk_stack(stack);
+    assert_eq!(s.frames.len(), 3);
+
+    {
+        // To avoid reusing locals by mistake
+        let f1 = &s.frames[1];
+        assert_eq!(f1.trust, FrameTrust::FramePointer);
+        assert_eq!(f1.instruction, return_address1 - 4);
+        assert_eq!(f1.module.as_ref().unwrap().code_file(), "module2");
+        if let MinidumpContextValidity::Some(ref which) = f1.context.valid {
+            assert!(which.contains("pc"));
+            assert!(which.contains("sp"));
+            assert!(which.contains("x29"));
+        } else {
+            unreachable!();
+        }
+        if let MinidumpRawContext::Arm64(ctx) = &f1.context.raw {
+            assert_eq!(ctx.pc, return_address1);
+            assert_eq!(ctx.iregs[SP], frame1_sp.value().unwrap());
+            assert_eq!(ctx.iregs[FP], frame1_fp.value().unwrap());
+        } else {
+            unreachable!();
+        }
+    }
+
+    {
+        // To avoid reusing locals by mistake
+        let f2 = &s.frames[2];
+        assert_eq!(f2.trust, FrameTrust::FramePointer);
+        // The frame chain ended here, so the frame pointer isn't known.
+        if let MinidumpContextValidity::Some(ref which) = f2.context.valid {
+            assert!(which.contains("pc"));
+            assert!(which.contains("sp"));
+            assert!(!which.contains("x29"));
+        } else {
+            unreachable!();
+        }
+        if let MinidumpRawContext::Arm64(ctx) = &f2.context.raw {
+            assert_eq!(ctx.pc, return_address2);
+            assert_eq!(ctx.iregs[SP], frame2_sp.value().unwrap());
+        } else {
+            unreachable!();
+        }
+    }
+}
+
+#[test]
+fn test_old_context() {
+    // Contexts in the old layout are unwound like current ones.
+    let mut f = TestFixture::new();
+    let mut stack = Section::new();
+    stack.start().set_const(0x80000000);
+
+    let return_address = 0x50000100;
+    let frame0_fp = Label::new();
+    let frame1_sp = Label::new();
+
+    stack = stack
+        .append_repeated(16, 0) // space
+        .mark(&frame0_fp)
+        .D64(0) // saved fp (end of the frame chain)
+        .D64(return_address) // saved lr
+        .mark(&frame1_sp)
+        .append_repeated(16, 0);
+
+    f.raw.pc = 0x40000200;
+    f.raw.iregs[FP] = frame0_fp.value().unwrap();
+    f.raw.iregs[SP] = stack.start().value().unwrap();
+
+    let s = f.walk_old_stack(stack);
+    assert_eq!(s.info, CallStackInfo::Ok);
+    assert_eq!(s.frames.len(), 2);
+    let f1 = &s.frames[1];
+    assert_eq!(f1.trust, FrameTrust::FramePointer);
+    assert_eq!(f1.module.as_ref().unwrap().code_file(), "module2");
+    assert_eq!(f1.context.get_instruction_pointer(), return_address);
+    assert_eq!(f1.context.get_stack_pointer(), frame1_sp.value().unwrap());
+}
+
+#[test]
+fn test_frame_pointer_loop() {
+    // A frame record whose saved frame pointer doesn't move up the stack
+    // would send the unwinder around in circles, so it must be rejected.
+    let mut f = TestFixture::new();
+    let mut stack = Section::new();
+    stack.start().set_const(0x80000000);
+
+    let frame0_fp = Label::new();
+
+    stack = stack
+        .append_repeated(16, 0) // space
+        .mark(&frame0_fp)
+        .D64(&frame0_fp) // saved fp, pointing at itself
+        .D64(0x50000100) // saved lr
+        .append_repeated(16, 0);
+
+    f.raw.pc = 0x40000200;
+    f.raw.iregs[FP] = frame0_fp.value().unwrap();
+    f.raw.iregs[SP] = stack.start().value().unwrap();
+
+    let s = f.walk_stack(stack);
+    assert_eq!(s.frames.len(), 1);
+}
+
+#[test]
+fn test_frame_pointer_outside_stack() {
+    // The caller's frame record must also be in the stack memory.
+    let mut f = TestFixture::new();
+    let mut stack = Section::new();
+    stack.start().set_const(0x80000000);
+
+    let frame0_fp = Label::new();
+
+    stack = stack
+        .append_repeated(16, 0) // space
+        .mark(&frame0_fp)
+        .D64(0x90000000) // saved fp, past the end of the stack
+        .D64(0x50000100) // saved lr
+        .append_repeated(16, 0);
+
+    f.raw.pc = 0x40000200;
+    f.raw.iregs[FP] = frame0_fp.value().unwrap();
+    f.raw.iregs[SP] = stack.start().value().unwrap();
+
+    let s = f.walk_stack(stack);
+    assert_eq!(s.frames.len(), 1);
+}
+
+#[test]
+fn test_leaf_at_function_entry() {
+    // At the first instruction of a function, the return address is still in
+    // the link register and the frame pointer is still the caller's, so the
+    // caller must be found from the link register or it would be skipped.
+    let mut f = TestFixture::new();
+    let symbol_path = std::path::Path::new(env!("CARGO_MANIFEST_DIR")).join("../testdata/symbols");
+    f.symbolizer = Symbolizer::new(SimpleSymbolSupplier::new(vec![symbol_path]));
+    let mut module = MinidumpModule::new(0x40000000, 0x10000, "c:\\leaf_arm64.dll");
+    module.codeview_info = Some(CodeView::Pdb20(CV_INFO_PDB20 {
+        cv_signature: CvSignature::Pdb20 as u32,
+        cv_offset: 0,
+        signature: 0x24681357,
+        age: 2,
+        pdb_file_name: b"leaf_arm64.pdb\0".to_vec(),
+    }));
+    f.modules = MinidumpModuleList::from_modules(vec![
+        module,
+        MinidumpModule::new(0x50000000, 0x10000, "module2"),
+    ]);
+
+    let mut stack = Section::new();
+    stack.start().set_const(0x80000000);
+
+    let return_address1 = 0x40000110;
+    let return_address2 = 0x50000100;
+    let frame1_fp = Label::new();
+    let frame2_sp = Label::new();
+
+    stack = stack
+        // frames 0 and 1
+        .append_repeated(16, 0) // space
+        .mark(&frame1_fp)
+        .D64(0) // saved fp (end of the frame chain)
+        .D64(return_address2) // saved lr
+        // frame 2
+        .mark(&frame2_sp)
+        .append_repeated(16, 0);
+
+    f.raw.pc = 0x40000200;
+    f.raw.iregs[30] = return_address1;
+    f.raw.iregs[FP] = frame1_fp.value().unwrap();
+    f.raw.iregs[SP] = stack.start().value().unwrap();
+    f.raw.iregs[19] = 0x1234;
+
+    let s = f.walk_stack(stack);
+    assert_eq!(s.frames.len(), 3);
+
+    {
+        // To avoid reusing locals by mistake
+        let f0 = &s.frames[0];
+        assert_eq!(f0.trust, FrameTrust::Context);
+        assert_eq!(f0.function_name.as_deref(), Some("leaf"));
+    }
+
+    {
+        // To avoid reusing locals by mistake
+        let f1 = &s.frames[1];
+        assert_eq!(f1.trust, FrameTrust::FunctionEntry);
+        assert_eq!(f1.instruction, return_address1 - 4);
+        assert_eq!(f1.function_name.as_deref(), Some("calls_leaf"));
+        if let MinidumpContextValidity::Some(ref which) = f1.context.valid {
+            assert!(which.contains("pc"));
+            assert!(which.contains("sp"));
+            assert!(which.contains("x29"));
+            assert!(which.contains("x19"));
+            assert!(!which.contains("x30"));
+        } else {
+            unreachable!();
+        }
+        if let MinidumpRawContext::Arm64(ctx) = &f1.context.raw {
+            assert_eq!(ctx.pc, return_address1);
+            // The leaf function hasn't touched the stack.
+            assert_eq!(ctx.iregs[SP], 0x80000000);
+            assert_eq!(ctx.iregs[FP], frame1_fp.value().unwrap());
+            assert_eq!(ctx.iregs[19], 0x1234);
+        } else {
+            unreachable!();
+        }
+    }
+
+    {
+        // To avoid reusing locals by mistake
+        let f2 = &s.frames[2];
+        assert_eq!(f2.trust, FrameTrust::FramePointer);
+        assert_eq!(f2.module.as_ref().unwrap().code_file(), "module2");
+        if let MinidumpRawContext::Arm64(ctx) = &f2.context.raw {
+            assert_eq!(ctx.pc, return_address2);
+            assert_eq!(ctx.iregs[SP], frame2_sp.value().unwrap());
+        } else {
+            unreachable!();
+        }
+    }
+}
+
+#[test]
+fn test_frameless_leaf() {
+    // A leaf function that doesn't push a frame record leaves the frame
+    // pointer pointing at its caller's, and its return address in the link
+    // register, so the caller must be found from the link register or it
+    // would be skipped.
+    let mut f = TestFixture::new();
+    let symbol_path = std::path::Path::new(env!("CARGO_MANIFEST_DIR")).join("../testdata/symbols");
+    f.symbolizer = Symbolizer::new(SimpleSymbolSupplier::new(vec![symbol_path]));
+    let mut module = MinidumpModule::new(0x40000000, 0x10000, "c:\\leaf_arm64.dll");
+    module.codeview_info = Some(CodeView::Pdb20(CV_INFO_PDB20 {
+        cv_signature: CvSignature::Pdb20 as u32,
+        cv_offset: 0,
+        signature: 0x24681357,
+        age: 2,
+        pdb_file_name: b"leaf_arm64.pdb\0".to_vec(),
+    }));
+    f.modules = MinidumpModuleList::from_modules(vec![
+        module,
+        MinidumpModule::new(0x50000000, 0x10000, "module2"),
+    ]);
+
+    let return_address1 = 0x40000110;
+    let return_address2 = 0x50000100;
+    let frame1_fp = 0x80000010;
+    let stack = || {
+        let stack = Section::new();
+        stack.start().set_const(0x80000000);
+        stack
+            // frames 0 and 1
+            .append_repeated(16, 0) // space
+            .D64(0) // saved fp (end of the frame chain)
+            .D64(return_address2) // saved lr
+            // frame 2
+            .append_repeated(16, 0)
+    };
+
+    // Past the leaf function's first instruction.
+    f.raw.pc = 0x40000208;
+    f.raw.iregs[30] = return_address1;
+    f.raw.iregs[FP] = frame1_fp;
+    f.raw.iregs[SP] = 0x80000000;
+
+    let s = f.walk_stack(stack());
+    assert_eq!(s.frames.len(), 3);
+    {
+        // To avoid reusing locals by mistake
+        let f1 = &s.frames[1];
+        assert_eq!(f1.trust, FrameTrust::FramePointer);
+        assert_eq!(f1.function_name.as_deref(), Some("calls_leaf"));
+        if let MinidumpRawContext::Arm64(ctx) = &f1.context.raw {
+            assert_eq!(ctx.pc, return_address1);
+            assert_eq!(ctx.iregs[SP], 0x80000000);
+            assert_eq!(ctx.iregs[FP], frame1_fp);
+        } else {
+            unreachable!();
+        }
+    }
+    assert_eq!(s.frames[2].instruction, return_address2 - 4);
+
+    // A link register that points into the function itself is left over from
+    // a call it made, so the frame record is used instead.
+    f.raw.iregs[30] = 0x40000204;
+    let s = f.walk_stack(stack());
+    assert_eq!(s.frames.len(), 2);
+    assert_eq!(s.frames[1].trust, FrameTrust::FramePointer);
+    assert_eq!(s.frames[1].instruction, return_address2 - 4);
+}
@@ -0,0 +1,52 @@
+// Copyright 2015 Ted Mielczarek. See the COPYRIGHT
+// file at the top-level directory of this distribution.
+
+use crate::process_state::*;
+use crate::stackwalker::walk_stack;
+use breakpad_symbols::{SimpleSymbolSupplier, Symbolizer};
+use minidump::format::{ArmRegisterNumbers, CvSignature, CONTEXT_ARM, CV_INFO_PDB20};
+use minidump::*;
+use scroll::{ctx::SizeWith, Pread, LE};
+
+const PC: usize = ArmRegisterNumbers::ProgramCounter as usize;
+
+// Stacks can't be unwound on ARM yet, but the context frame should still be
+// symbolized, even when its address has the Thumb bit set.
+#[test]
+fn test_thumb_context_frame() {
+    let symbol_path = std::path::Path::new(env!("CARGO_MANIFEST_DIR")).join("../testdata/symbols");
+    let symbolizer = Symbolizer::new(SimpleSymbolSupplier::new(vec![symbol_path]));
+    let mut module = MinidumpModule::new(0x10000000, 0x10000, "c:\\thumb.dll");
+    module.codeview_info = Some(CodeView::Pdb20(CV_INFO_PDB20 {
+        cv_signature: CvSignature::Pdb20 as u32,
+        cv_offset: 0,
+        signature: 0x13572468,
+        age: 1,
+        pdb_file_name: b"thumb.pdb\0".to_vec(),
+    }));
+    let modules = MinidumpModuleList::from_modules(vec![module]);
+
+    let bytes = vec![0; CONTEXT_ARM::size_with(&LE)];
+    let mut raw: CONTEXT_ARM = bytes.pread_with(0, LE).unwrap();
+    raw.iregs[PC] = 0x1000011f;
+    let context = MinidumpContext::from_raw(MinidumpRawContext::Arm(raw));
+    let s = walk_stack(
+        &Some(&context),
+        None,
+        &modules,
+        &MinidumpUnloadedModuleList::new(),
+        &symbolizer,
+    );
+    assert_eq!(s.frames.len(), 1);
+
+    let f0 = &s.frames[0];
+    assert_eq!(f0.instruction, 0x1000011e);
+    assert_eq!(f0.context.get_instruction_pointer(), 0x1000011f);
+    assert_eq!(f0.function_name.as_deref(), Some("thumb_func"));
+    assert_eq!(f0.function_base, Some(0x10000100));
+
+    let mut output = vec![];
+    s.print(&mut output).unwrap();
+    let output = String::from_utf8(output).unwrap();
+    assert!(output.contains("thumb.dll!thumb_func + 0x1e"));
+}
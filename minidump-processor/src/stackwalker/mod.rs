@@ -4,6 +4,7 @@
 //! Unwind stack frames for a thread.
 
 mod amd64;
+mod arm64;
 mod unwind;
 mod x86;
 
@@ -13,9 +14,11 @@ use minidump::*;
 use scroll::ctx::{SizeWith, TryFromCtx};
 
 use self::unwind::Unwind;
-use breakpad_symbols::FrameWalker;
+use breakpad_symbols::{FrameSymbolizer, FrameWalker, SimpleModule};
+use log::warn;
 use std::collections::HashSet;
 use std::convert::TryFrom;
+use std::fmt;
 
 struct CfiStackWalker<'a, C: CpuContext> {
     instruction: u64,
@@ -74,20 +77,200 @@ where
     }
 }
 
+/// The stackwalker's account of how it unwound a thread, or nothing if it
+/// wasn't asked for one.
+///
+/// Notes are only formatted when diagnostics are enabled, so leaving them off
+/// costs a branch per note.
+pub(crate) struct UnwindDiagnostics {
+    lines: Option<Vec<String>>,
+}
+
+impl UnwindDiagnostics {
+    fn new(enabled: bool) -> UnwindDiagnostics {
+        UnwindDiagnostics {
+            lines: if enabled { Some(vec![]) } else { None },
+        }
+    }
+
+    /// Whether diagnostics are enabled, for notes whose arguments cost
+    /// something to compute.
+    pub(crate) fn enabled(&self) -> bool {
+        self.lines.is_some()
+    }
+
+    /// Record `line`, if diagnostics are enabled.
+    pub(crate) fn note(&mut self, line: fmt::Arguments<'_>) {
+        if let Some(ref mut lines) = self.lines {
+            lines.push(line.to_string());
+        }
+    }
+
+    /// Record whether unwinding by `method` found a `caller`, and pass it on.
+    pub(crate) fn tried(&mut self, method: &str, caller: Option<StackFrame>) -> Option<StackFrame> {
+        match caller {
+            Some(ref frame) => self.note(format_args!(
+                "  {}: found a caller with ip {:#x}, sp {:#x}",
+                method,
+                frame.context.get_instruction_pointer(),
+                frame.context.get_stack_pointer()
+            )),
+            None => self.note(format_args!("  {}: no caller", method)),
+        }
+        caller
+    }
+}
+
+/// How readily the stackwalker takes a word on the stack for a return
+/// address, when nothing better can find a frame's caller.
+///
+/// Scanning only happens on x86 and amd64.
+#[derive(Clone, Copy, Debug, PartialEq, Eq)]
+pub enum StackScan {
+    /// Don't scan, a frame that can't be unwound any other way ends the stack.
+    Off,
+    /// Take the first word that points into a module's code, as Breakpad does.
+    FirstPlausible,
+    /// Prefer a word that points just after a call instruction in the dumped
+    /// code, or that CFI can unwind from, and mark that caller
+    /// `FrameTrust::CfiScan`. Otherwise take the first plausible word.
+    ///
+    /// Stale return addresses left on the stack by earlier calls are often
+    /// the first plausible word, so this finds fewer bogus frames.
+    PreferVerified,
+    /// Only take words that point just after a call instruction or that CFI
+    /// can unwind from.
+    VerifiedOnly,
+}
+
+impl Default for StackScan {
+    fn default() -> StackScan {
+        StackScan::PreferVerified
+    }
+}
+
+/// Options for [`walk_stack_with_options`](fn.walk_stack_with_options.html).
+#[derive(Clone, Copy, Debug, Default)]
+pub struct StackWalkOptions<'a> {
+    /// Whether to record how each frame was found, see
+    /// [`walk_stack_with_diagnostics`](fn.walk_stack_with_diagnostics.html).
+    pub diagnostics: bool,
+    /// How to pick callers out of the stack when scanning for them.
+    pub scan: StackScan,
+    /// The dumped memory, to check for a call instruction before a return
+    /// address found by scanning.
+    pub memory: Option<&'a MinidumpMemoryList<'a>>,
+    /// The process's memory regions, to reject callers whose instruction is
+    /// in memory that isn't executable.
+    pub memory_info: Option<&'a MinidumpMemoryInfoList>,
+    /// The most frames to keep, or `None` for no limit.
+    ///
+    /// The walk goes on past this many frames to count them all in
+    /// [`total_frames`][total_frames], but the frames after them aren't
+    /// symbolized or kept.
+    ///
+    /// [total_frames]: struct.CallStack.html#structfield.total_frames
+    pub max_frames: Option<usize>,
+}
+
+/// Whether `memory_info` says `address` is in memory that isn't executable.
+///
+/// Addresses in regions the minidump doesn't describe aren't ruled out.
+pub(crate) fn in_non_executable_memory(
+    address: u64,
+    memory_info: Option<&MinidumpMemoryInfoList>,
+) -> bool {
+    matches!(
+        memory_info.and_then(|memory_info| memory_info.memory_info_at_address(address)),
+        Some(region) if !region.is_executable()
+    )
+}
+
+/// How many plausible words a single scan asks CFI to unwind from, as each
+/// is a full CFI lookup.
+pub(crate) const MAX_CFI_CHECKS: usize = 8;
+
+/// What the stack scanner needs besides the stack itself.
+pub(crate) struct StackScanner<'a> {
+    pub(crate) mode: StackScan,
+    memory: Option<&'a MinidumpMemoryList<'a>>,
+}
+
+impl<'a> StackScanner<'a> {
+    /// Whether the dumped code just before `return_address` is an x86 or
+    /// amd64 call instruction.
+    pub(crate) fn follows_call(&self, return_address: u64) -> bool {
+        // The longest call is an indirect one with a SIB byte and a 32-bit
+        // displacement, plus a REX prefix that doesn't need checking.
+        const MAX_CALL_LENGTH: u64 = 7;
+        let start = return_address.saturating_sub(MAX_CALL_LENGTH);
+        match self
+            .memory
+            .and_then(|memory| memory.memory_window(start, return_address - start))
+        {
+            Some(window) if window.range.end == return_address => follows_call(&window.bytes),
+            _ => false,
+        }
+    }
+}
+
+/// Whether `code` ends with an x86 or amd64 call instruction.
+fn follows_call(code: &[u8]) -> bool {
+    let byte_before = |n: usize| code.len().checked_sub(n).map(|i| code[i]);
+    // call rel32
+    if byte_before(5) == Some(0xe8) {
+        return true;
+    }
+    // call r/m, an 0xff opcode whose ModRM byte has a reg field of 2. The
+    // ModRM byte, and the SIB byte if there is one, say how long it is.
+    (2..=7).any(|length| {
+        if byte_before(length) != Some(0xff) {
+            return false;
+        }
+        let modrm = match byte_before(length - 1) {
+            Some(modrm) if (modrm >> 3) & 7 == 2 => modrm,
+            _ => return false,
+        };
+        let (mode, rm) = (modrm >> 6, modrm & 7);
+        let has_sib = mode != 3 && rm == 4;
+        let sib_base = if has_sib {
+            match byte_before(length - 2) {
+                Some(sib) => sib & 7,
+                None => return false,
+            }
+        } else {
+            0
+        };
+        let displacement = match mode {
+            0 if rm == 5 || (has_sib && sib_base == 5) => 4,
+            1 => 1,
+            2 => 4,
+            _ => 0,
+        };
+        2 + has_sib as usize + displacement == length
+    })
+}
+
+#[allow(clippy::too_many_arguments)]
 fn get_caller_frame<P>(
     callee_frame: &StackFrame,
     grand_callee_frame: Option<&StackFrame>,
     stack_memory: Option<&MinidumpMemory>,
     modules: &MinidumpModuleList,
+    memory_info: Option<&MinidumpMemoryInfoList>,
     symbol_provider: &P,
+    scanner: &StackScanner,
+    diagnostics: &mut UnwindDiagnostics,
 ) -> Option<StackFrame>
 where
     P: SymbolProvider,
 {
+    // A function that hasn't run its prologue yet has no frame to unwind.
+    let at_function_entry = callee_frame.trust == FrameTrust::Context
+        && callee_frame.function_base == Some(callee_frame.instruction);
     match callee_frame.context.raw {
         /*
         MinidumpRawContext::ARM(ctx) => ctx.get_caller_frame(stack_memory),
-        MinidumpRawContext::ARM64(ctx) => ctx.get_caller_frame(stack_memory),
         MinidumpRawContext::PPC(ctx) => ctx.get_caller_frame(stack_memory),
         MinidumpRawContext::PPC64(ctx) => ctx.get_caller_frame(stack_memory),
         MinidumpRawContext::SPARC(ctx) => ctx.get_caller_frame(stack_memory),
@@ -96,36 +279,274 @@ where
         MinidumpRawContext::Amd64(ref ctx) => ctx.get_caller_frame(
             &callee_frame.context.valid,
             callee_frame.trust,
+            at_function_entry,
+            stack_memory,
+            grand_callee_frame,
+            modules,
+            memory_info,
+            symbol_provider,
+            scanner,
+            diagnostics,
+        ),
+        MinidumpRawContext::Arm64(ref ctx) => ctx.get_caller_frame(
+            &callee_frame.context.valid,
+            callee_frame.trust,
+            at_function_entry,
             stack_memory,
             grand_callee_frame,
             modules,
+            memory_info,
             symbol_provider,
+            scanner,
+            diagnostics,
+        ),
+        MinidumpRawContext::OldArm64(ref ctx) => arm64::context_from_old(ctx).get_caller_frame(
+            &callee_frame.context.valid,
+            callee_frame.trust,
+            at_function_entry,
+            stack_memory,
+            grand_callee_frame,
+            modules,
+            memory_info,
+            symbol_provider,
+            scanner,
+            diagnostics,
         ),
         MinidumpRawContext::X86(ref ctx) => ctx.get_caller_frame(
             &callee_frame.context.valid,
             callee_frame.trust,
+            at_function_entry,
             stack_memory,
             grand_callee_frame,
             modules,
+            memory_info,
             symbol_provider,
+            scanner,
+            diagnostics,
         ),
         _ => None,
     }
 }
 
+/// Whether `get_caller_frame` knows how to unwind from `context`.
+fn supports_unwinding(context: &MinidumpContext) -> bool {
+    match context.raw {
+        MinidumpRawContext::Amd64(_)
+        | MinidumpRawContext::Arm64(_)
+        | MinidumpRawContext::OldArm64(_)
+        | MinidumpRawContext::X86(_) => true,
+        _ => false,
+    }
+}
+
+/// How far past the end of the captured stack memory a frame pointer can point
+/// and still be taken for one: the default size of a Linux main thread's stack.
+const MAX_STACK_SIZE: u64 = 8 * 1024 * 1024;
+
+/// The frame pointer of `context`, if it's valid, and the size of a pointer.
+fn frame_pointer(context: &MinidumpContext) -> Option<(u64, u64)> {
+    match context.raw {
+        MinidumpRawContext::X86(ref ctx) => ctx
+            .get_register("ebp", &context.valid)
+            .map(|fp| (u64::from(fp), 4)),
+        MinidumpRawContext::Amd64(ref ctx) => {
+            ctx.get_register("rbp", &context.valid).map(|fp| (fp, 8))
+        }
+        MinidumpRawContext::Arm64(ref ctx) => {
+            ctx.get_register("x29", &context.valid).map(|fp| (fp, 8))
+        }
+        _ => None,
+    }
+}
+
+/// Whether a stack walk that ended at `frame` stopped because the captured
+/// `stack_memory` ends before the caller's frame, as it does in dumps that
+/// limit how much of each stack they capture.
+///
+/// That's so if the frame's stack pointer is already past the end of the
+/// memory, or if its frame pointer is: a frame pointer points at the caller's
+/// frame pointer and return address, which would be past the end too.
+fn stack_memory_ran_out(frame: &StackFrame, stack_memory: &MinidumpMemory) -> bool {
+    let end = stack_memory.base_address + stack_memory.size;
+    let sp = frame.context.get_stack_pointer();
+    if sp >= end {
+        return true;
+    }
+    match frame_pointer(&frame.context) {
+        Some((fp, pointer_size)) => {
+            fp > sp
+                && fp.saturating_add(pointer_size * 2) > end
+                && fp.saturating_sub(end) < MAX_STACK_SIZE
+        }
+        None => false,
+    }
+}
+
+/// Describe `unloaded` for symbol lookup.
+///
+/// The minidump only records an unloaded module's code file and identifier, so
+/// that's what its symbols are found by. If the same build of the module is
+/// also loaded elsewhere in the process, though, that module's debug file and
+/// identifier tell us where its symbols usually live.
+fn unloaded_symbol_module(
+    unloaded: &MinidumpUnloadedModule,
+    modules: &MinidumpModuleList,
+) -> SimpleModule {
+    let code_file = unloaded.code_file();
+    let code_identifier = unloaded.code_identifier();
+    let loaded = modules.iter().find(|module| {
+        module.code_file().eq_ignore_ascii_case(&code_file)
+            && module
+                .code_identifier()
+                .eq_ignore_ascii_case(&code_identifier)
+    });
+    SimpleModule {
+        base_address: Some(unloaded.base_address()),
+        size: Some(unloaded.size()),
+        code_file: Some(code_file.into_owned()),
+        code_identifier: Some(code_identifier.into_owned()),
+        debug_file: loaded.and_then(|m| m.debug_file()).map(|s| s.into_owned()),
+        debug_id: loaded
+            .and_then(|m| m.debug_identifier())
+            .map(|s| s.into_owned()),
+        version: None,
+    }
+}
+
 fn fill_source_line_info<P>(
     frame: &mut StackFrame,
     modules: &MinidumpModuleList,
+    unloaded_modules: &MinidumpUnloadedModuleList,
     symbol_provider: &P,
 ) where
     P: SymbolProvider,
 {
+    let (module, unloaded) = symbolize(
+        frame,
+        |frame| frame.function_name.is_some(),
+        modules,
+        unloaded_modules,
+        symbol_provider,
+    );
+    frame.module = module;
+    frame.unloaded_modules = unloaded;
+}
+
+/// Fill in the symbols for `frame`'s instruction from the module that covers it.
+///
+/// Returns the loaded module covering the instruction, or else the unloaded
+/// modules that do, the one whose symbols were used first. `has_symbols` says
+/// whether a module filled anything in.
+fn symbolize<F, P>(
+    frame: &mut F,
+    has_symbols: impl Fn(&F) -> bool,
+    modules: &MinidumpModuleList,
+    unloaded_modules: &MinidumpUnloadedModuleList,
+    symbol_provider: &P,
+) -> (Option<MinidumpModule>, Vec<MinidumpUnloadedModule>)
+where
+    F: FrameSymbolizer,
+    P: SymbolProvider,
+{
+    let instruction = frame.get_instruction();
     // Find the module whose address range covers this frame's instruction.
-    if let Some(module) = modules.module_at_address(frame.instruction) {
+    if let Some(module) = modules.module_at_address(instruction) {
+        symbol_provider.fill_symbol(module, frame);
         // FIXME: this shouldn't need to clone, we should be able to use
         // the same lifetime as the module list that's passed in.
-        frame.module = Some(module.clone());
-        symbol_provider.fill_symbol(module, frame);
+        (Some(module.clone()), vec![])
+    } else {
+        // Scanned frames can point at code that has since been unloaded,
+        // symbols for it may still be available. Several modules may have
+        // occupied this address, use the first one that has symbols.
+        let mut unloaded = unloaded_modules
+            .modules_at_address(instruction)
+            .cloned()
+            .collect::<Vec<_>>();
+        for i in 0..unloaded.len() {
+            let module = unloaded_symbol_module(&unloaded[i], modules);
+            symbol_provider.fill_symbol(&module, frame);
+            if has_symbols(frame) {
+                unloaded[..=i].rotate_right(1);
+                break;
+            }
+        }
+        (None, unloaded)
+    }
+}
+
+/// Look up the module, function and source line of a single `address`, the
+/// same way the frames of a stack are.
+///
+/// This is for addresses that aren't part of a stack, like data pointers and
+/// vtables. Returns `None` if no loaded or unloaded module covers `address`.
+pub fn symbolize_address<P>(
+    address: u64,
+    modules: &MinidumpModuleList,
+    unloaded_modules: &MinidumpUnloadedModuleList,
+    symbol_provider: &P,
+) -> Option<SymbolizedLocation>
+where
+    P: SymbolProvider,
+{
+    let mut location = SymbolizedLocation::new(address);
+    let (module, unloaded) = symbolize(
+        &mut location,
+        |location| location.function_name.is_some(),
+        modules,
+        unloaded_modules,
+        symbol_provider,
+    );
+    if module.is_none() && unloaded.is_empty() {
+        return None;
+    }
+    location.module = module;
+    location.unloaded_modules = unloaded;
+    Some(location)
+}
+
+/// Clear the module of any frame whose instruction is outside of it.
+///
+/// Symbols found for such a frame came from the wrong module, so they are
+/// cleared as well, and the frame is marked with `inconsistent_module`.
+fn verify_frame_modules(frames: &mut [StackFrame]) {
+    for frame in frames {
+        let consistent = match frame.module {
+            Some(ref module) => {
+                let base = module.base_address();
+                base <= frame.instruction && frame.instruction - base < module.size()
+            }
+            None => true,
+        };
+        if consistent {
+            continue;
+        }
+        warn!(
+            "frame instruction {:#x} is outside of its module {}",
+            frame.instruction,
+            frame.module.as_ref().unwrap().code_file()
+        );
+        frame.module = None;
+        frame.function_name = None;
+        frame.function_base = None;
+        frame.parameter_size = None;
+        frame.source_file_name = None;
+        frame.source_line = None;
+        frame.source_line_base = None;
+        frame.inlines.clear();
+        frame.inconsistent_module = true;
+    }
+}
+
+/// Find the Linux memory mapping of each frame whose instruction isn't in any module.
+///
+/// The module lists don't cover JIT code or anonymous mappings, but the
+/// process's memory mappings do.
+pub fn fill_mappings(stack: &mut CallStack, maps: &MinidumpLinuxMaps) {
+    for frame in &mut stack.frames {
+        if frame.module.is_none() && frame.unloaded_modules.is_empty() {
+            frame.mapping = maps.map_at_address(frame.instruction).cloned();
+        }
     }
 }
 
@@ -133,38 +554,187 @@ pub fn walk_stack<P>(
     maybe_context: &Option<&MinidumpContext>,
     stack_memory: Option<&MinidumpMemory>,
     modules: &MinidumpModuleList,
+    unloaded_modules: &MinidumpUnloadedModuleList,
+    symbol_provider: &P,
+) -> CallStack
+where
+    P: SymbolProvider,
+{
+    walk_stack_with_diagnostics(
+        maybe_context,
+        stack_memory,
+        modules,
+        unloaded_modules,
+        symbol_provider,
+        false,
+    )
+}
+
+/// Walk a stack like `walk_stack`, and if `diagnostics` is set, record in the
+/// stack's [`unwind_diagnostics`][diagnostics] how each frame was found, and
+/// why the other ways of unwinding it didn't work.
+///
+/// Like `walk_stack`, this scans with `StackScan::FirstPlausible`, as it did
+/// before there was a choice. Use
+/// [`walk_stack_with_options`](fn.walk_stack_with_options.html) for the others.
+///
+/// [diagnostics]: struct.CallStack.html#structfield.unwind_diagnostics
+pub fn walk_stack_with_diagnostics<P>(
+    maybe_context: &Option<&MinidumpContext>,
+    stack_memory: Option<&MinidumpMemory>,
+    modules: &MinidumpModuleList,
+    unloaded_modules: &MinidumpUnloadedModuleList,
+    symbol_provider: &P,
+    diagnostics: bool,
+) -> CallStack
+where
+    P: SymbolProvider,
+{
+    let options = StackWalkOptions {
+        diagnostics,
+        scan: StackScan::FirstPlausible,
+        ..StackWalkOptions::default()
+    };
+    walk_stack_with_options(
+        maybe_context,
+        stack_memory,
+        modules,
+        unloaded_modules,
+        symbol_provider,
+        &options,
+    )
+}
+
+/// Walk a stack like `walk_stack`, as `options` say.
+pub fn walk_stack_with_options<P>(
+    maybe_context: &Option<&MinidumpContext>,
+    stack_memory: Option<&MinidumpMemory>,
+    modules: &MinidumpModuleList,
+    unloaded_modules: &MinidumpUnloadedModuleList,
     symbol_provider: &P,
+    options: &StackWalkOptions,
 ) -> CallStack
 where
     P: SymbolProvider,
 {
+    let mut diagnostics = UnwindDiagnostics::new(options.diagnostics);
+    let scanner = StackScanner {
+        mode: options.scan,
+        memory: options.memory,
+    };
     // Begin with the context frame, and keep getting callers until there are
     // no more.
     let mut frames = vec![];
     let mut info = CallStackInfo::Ok;
-    if let Some(context) = *maybe_context {
-        let ctx = context.clone();
-        let mut maybe_frame = Some(StackFrame::from_context(ctx, FrameTrust::Context));
-        while let Some(mut frame) = maybe_frame {
-            fill_source_line_info(&mut frame, modules, symbol_provider);
+    match *maybe_context {
+        Some(context) if !supports_unwinding(context) => {
+            // We can't unwind this CPU's stacks, but the context frame is still useful.
+            let mut frame = StackFrame::from_context(context.clone(), FrameTrust::Context);
+            fill_source_line_info(&mut frame, modules, unloaded_modules, symbol_provider);
             frames.push(frame);
-            let callee_frame = &frames.last().unwrap();
-            let grand_callee_frame = frames.len().checked_sub(2).and_then(|idx| frames.get(idx));
-            maybe_frame = get_caller_frame(
-                callee_frame,
-                grand_callee_frame,
-                stack_memory,
-                modules,
-                symbol_provider,
-            );
+            info = CallStackInfo::UnsupportedCpu;
+            diagnostics.note(format_args!("frame 0: this CPU's stacks can't be unwound"));
+        }
+        Some(context) => {
+            let ctx = context.clone();
+            let mut maybe_frame = Some(StackFrame::from_context(ctx, FrameTrust::Context));
+            if stack_memory.is_none() {
+                diagnostics.note(format_args!(
+                    "no stack memory, so only the context frame is known"
+                ));
+            }
+            while let Some(mut frame) = maybe_frame {
+                // Frames past the limit are only walked to be counted.
+                let keep = match options.max_frames {
+                    Some(max_frames) => frames.len() < max_frames,
+                    None => true,
+                };
+                if keep {
+                    fill_source_line_info(&mut frame, modules, unloaded_modules, symbol_provider);
+                    diagnostics.note(format_args!(
+                        "frame {}: ip {:#x}, sp {:#x}, {}",
+                        frames.len(),
+                        frame.context.get_instruction_pointer(),
+                        frame.context.get_stack_pointer(),
+                        frame.trust.description()
+                    ));
+                }
+                frames.push(frame);
+                let callee_frame = &frames.last().unwrap();
+                let grand_callee_frame =
+                    frames.len().checked_sub(2).and_then(|idx| frames.get(idx));
+                maybe_frame = get_caller_frame(
+                    callee_frame,
+                    grand_callee_frame,
+                    stack_memory,
+                    modules,
+                    options.memory_info,
+                    symbol_provider,
+                    &scanner,
+                    &mut diagnostics,
+                );
+            }
+            diagnostics.note(format_args!(
+                "no caller of frame {}, the stack ends",
+                frames.len() - 1
+            ));
+            if let Some(stack_memory) = stack_memory {
+                if stack_memory_ran_out(frames.last().unwrap(), stack_memory) {
+                    info = CallStackInfo::TruncatedStackMemory;
+                    diagnostics.note(format_args!(
+                        "the stack memory ends at {:#x}, before the caller's frame",
+                        stack_memory.base_address + stack_memory.size
+                    ));
+                }
+            }
+        }
+        None => {
+            info = CallStackInfo::MissingContext;
+            diagnostics.note(format_args!("no CPU context to start unwinding from"));
+        }
+    }
+    let mut stack = CallStack {
+        frames,
+        info,
+        thread_name: None,
+        total_frames: None,
+        context_flags: None,
+        unwind_diagnostics: None,
+        thread_id: None,
+        suspend_count: None,
+        priority_class: None,
+        priority: None,
+        teb: None,
+        exception_context: None,
+        thread_context: None,
+    };
+    if let Some(max_frames) = options.max_frames {
+        stack.truncate_frames(max_frames);
+        if let Some(total_frames) = stack.total_frames {
+            diagnostics.note(format_args!(
+                "kept the first {} of {} frames",
+                max_frames, total_frames
+            ));
         }
-    } else {
-        info = CallStackInfo::MissingContext;
     }
-    CallStack { frames, info }
+    verify_frame_modules(&mut stack.frames);
+    for (i, frame) in stack.frames.iter().enumerate() {
+        if frame.inconsistent_module {
+            diagnostics.note(format_args!(
+                "frame {}: ip {:#x} is outside of the module it was symbolized with",
+                i, frame.instruction
+            ));
+        }
+    }
+    stack.unwind_diagnostics = diagnostics.lines;
+    stack
 }
 
 #[cfg(test)]
 mod amd64_unittest;
 #[cfg(test)]
+mod arm64_unittest;
+#[cfg(test)]
+mod arm_unittest;
+#[cfg(test)]
 mod x86_unittest;
@@ -2,20 +2,34 @@
 // file at the top-level directory of this distribution.
 
 use crate::process_state::{FrameTrust, StackFrame};
+use crate::stackwalker::{StackScanner, UnwindDiagnostics};
 use crate::SymbolProvider;
-use minidump::{MinidumpContextValidity, MinidumpMemory, MinidumpModuleList};
+use minidump::{
+    MinidumpContextValidity, MinidumpMemory, MinidumpMemoryInfoList, MinidumpModuleList,
+};
 
 /// A trait for things that can unwind to a caller.
 pub trait Unwind {
     /// Get the caller frame of this frame.
+    ///
+    /// `at_function_entry` is set when this is the context frame and it is at the
+    /// first instruction of its function, which hasn't set up its frame yet.
+    /// A caller whose instruction is in a region `memory_info` says isn't
+    /// executable is rejected. `scanner` says how to scan the stack for the
+    /// caller if nothing better finds it. How the caller was found, or why it wasn't, goes in `diagnostics`.
+    #[allow(clippy::too_many_arguments)]
     fn get_caller_frame<P>(
         &self,
         valid: &MinidumpContextValidity,
         trust: FrameTrust,
+        at_function_entry: bool,
         stack_memory: Option<&MinidumpMemory>,
         grand_callee_frame: Option<&StackFrame>,
         modules: &MinidumpModuleList,
+        memory_info: Option<&MinidumpMemoryInfoList>,
         symbol_provider: &P,
+        scanner: &StackScanner,
+        diagnostics: &mut UnwindDiagnostics,
     ) -> Option<StackFrame>
     where
         P: SymbolProvider;
@@ -6,15 +6,18 @@
 // so that it can be copied between the two with minimal changes. It's not
 // worth the effort to *actually* unify the implementations.
 
-use crate::process_state::{FrameTrust, StackFrame};
+use crate::process_state::{FrameTrust, RegisterProvenance, StackFrame};
 use crate::stackwalker::unwind::Unwind;
-use crate::stackwalker::CfiStackWalker;
+use crate::stackwalker::{
+    in_non_executable_memory, CfiStackWalker, StackScan, StackScanner, UnwindDiagnostics,
+    MAX_CFI_CHECKS,
+};
 use crate::SymbolProvider;
 use log::trace;
 use minidump::format::CONTEXT_X86;
 use minidump::{
-    MinidumpContext, MinidumpContextValidity, MinidumpMemory, MinidumpModuleList,
-    MinidumpRawContext,
+    MinidumpContext, MinidumpContextValidity, MinidumpMemory, MinidumpMemoryInfoList,
+    MinidumpModuleList, MinidumpRawContext, Module,
 };
 use std::collections::HashSet;
 
@@ -85,15 +88,20 @@ where
     valid.insert(INSTRUCTION_REGISTER);
     valid.insert(STACK_POINTER_REGISTER);
     valid.insert(FRAME_POINTER_REGISTER);
+    let provenance = valid
+        .iter()
+        .map(|&reg| (reg, RegisterProvenance::Recovered))
+        .collect();
     let context = MinidumpContext {
         raw: MinidumpRawContext::X86(caller_ctx),
         valid: MinidumpContextValidity::Some(valid),
     };
-    let mut frame = StackFrame::from_context(context, FrameTrust::FramePointer);
-    adjust_instruction(&mut frame, caller_ip);
+    let mut frame = StackFrame::from_caller_context(context, FrameTrust::FramePointer);
+    frame.register_provenance = Some(provenance);
     Some(frame)
 }
 
+#[allow(clippy::too_many_arguments)]
 fn get_caller_by_cfi<P>(
     ctx: &CONTEXT_X86,
     valid: &MinidumpContextValidity,
@@ -101,7 +109,9 @@ fn get_caller_by_cfi<P>(
     stack_memory: &MinidumpMemory,
     grand_callee_frame: Option<&StackFrame>,
     modules: &MinidumpModuleList,
+    memory_info: Option<&MinidumpMemoryInfoList>,
     symbol_provider: &P,
+    diagnostics: &mut UnwindDiagnostics,
 ) -> Option<StackFrame>
 where
     P: SymbolProvider,
@@ -119,7 +129,16 @@ where
 
     let last_sp = ctx.esp;
     let last_ip = ctx.eip;
-    let module = modules.module_at_address(last_ip as u64)?;
+    let module = match modules.module_at_address(last_ip as u64) {
+        Some(module) => module,
+        None => {
+            diagnostics.note(format_args!(
+                "  call frame info: no module contains ip {:#x}",
+                last_ip
+            ));
+            return None;
+        }
+    };
     trace!("  ...found module");
 
     let grand_callee_parameter_size = grand_callee_frame
@@ -139,35 +158,67 @@ where
         stack_memory,
     };
 
-    symbol_provider.walk_frame(module, &mut stack_walker)?;
+    if symbol_provider
+        .walk_frame(module, &mut stack_walker)
+        .is_none()
+    {
+        if diagnostics.enabled() {
+            diagnostics.note(format_args!(
+                "  call frame info: {} has no usable rules for ip {:#x}",
+                module.code_file(),
+                last_ip
+            ));
+        }
+        return None;
+    }
     let caller_ip = stack_walker.caller_ctx.eip;
     let caller_sp = stack_walker.caller_ctx.esp;
 
-    // Don't accept obviously wrong instruction pointers.
-    if !instruction_seems_valid(caller_ip, modules, symbol_provider) {
+    // CFI that recovers a caller outside of any module is wrong about this
+    // frame, so don't trust it. The frame pointer or a stack scan may still
+    // find the real caller.
+    if !instruction_seems_valid(caller_ip, modules, memory_info, symbol_provider) {
+        trace!("  ...cfi gave a bogus caller");
+        diagnostics.note(format_args!(
+            "  call frame info: the caller's ip {:#x} isn't in any module",
+            caller_ip
+        ));
         return None;
     }
     // Don't accept obviously wrong stack pointers.
     if !stack_seems_valid(caller_sp, last_sp, stack_memory) {
+        diagnostics.note(format_args!(
+            "  call frame info: the caller's sp {:#x} isn't on the stack above {:#x}",
+            caller_sp, last_sp
+        ));
         return None;
     }
 
+    // Every register the CFI gives the caller is computed by its rules.
+    let provenance = stack_walker
+        .caller_validity
+        .iter()
+        .map(|&reg| (reg, RegisterProvenance::Recovered))
+        .collect();
     let context = MinidumpContext {
         raw: MinidumpRawContext::X86(stack_walker.caller_ctx),
         valid: MinidumpContextValidity::Some(stack_walker.caller_validity),
     };
-    let mut frame = StackFrame::from_context(context, FrameTrust::CallFrameInfo);
-    adjust_instruction(&mut frame, caller_ip);
+    let mut frame = StackFrame::from_caller_context(context, FrameTrust::CallFrameInfo);
+    frame.register_provenance = Some(provenance);
     Some(frame)
 }
 
+#[allow(clippy::too_many_arguments)]
 fn get_caller_by_scan<P>(
     ctx: &CONTEXT_X86,
     valid: &MinidumpContextValidity,
     trust: FrameTrust,
     stack_memory: &MinidumpMemory,
     modules: &MinidumpModuleList,
+    memory_info: Option<&MinidumpMemoryInfoList>,
     symbol_provider: &P,
+    scanner: &StackScanner,
 ) -> Option<StackFrame>
 where
     P: SymbolProvider,
@@ -178,6 +229,16 @@ where
     // we assume it's an ip value that was pushed by the CALL instruction that created
     // the current frame. The next frame is then assumed to end just before that
     // ip value.
+    //
+    // The frame we find gets `FrameTrust::Scan`, unless `scanner` is to look
+    // for a caller that can be verified. Then a
+    // caller whose return address follows a call instruction, or that CFI
+    // can unwind from, is taken over earlier plausible ones, and gets
+    // `FrameTrust::CfiScan`. Only the first `MAX_CFI_CHECKS` plausible words
+    // that don't follow a call are tried with CFI.
+    if scanner.mode == StackScan::Off {
+        return None;
+    }
     let last_bp = match valid {
         MinidumpContextValidity::All => Some(ctx.ebp),
         MinidumpContextValidity::Some(ref which) => {
@@ -206,10 +267,15 @@ where
         default_scan_range
     };
 
+    let mut first_plausible = None;
+    let mut cfi_checks = 0;
     for i in 0..scan_range {
         let address_of_ip = last_sp + i * POINTER_WIDTH;
-        let caller_ip = stack_memory.get_memory_at_address(address_of_ip as u64)?;
-        if instruction_seems_valid(caller_ip, modules, symbol_provider) {
+        let caller_ip = match stack_memory.get_memory_at_address(address_of_ip as u64) {
+            Some(caller_ip) => caller_ip,
+            None => break,
+        };
+        if instruction_seems_valid(caller_ip, modules, memory_info, symbol_provider) {
             // ip is pushed by CALL, so sp is just address_of_ip + ptr
             let caller_sp = address_of_ip + POINTER_WIDTH;
 
@@ -235,7 +301,10 @@ where
             const MAX_REASONABLE_GAP_BETWEEN_FRAMES: Pointer = 128 * 1024;
 
             let address_of_bp = address_of_ip - POINTER_WIDTH;
-            let bp = stack_memory.get_memory_at_address(address_of_bp as u64)?;
+            let bp = match stack_memory.get_memory_at_address(address_of_bp as u64) {
+                Some(bp) => bp,
+                None => break,
+            };
             if bp > address_of_ip && bp - address_of_bp <= MAX_REASONABLE_GAP_BETWEEN_FRAMES {
                 // Sanity check that resulting bp is still inside stack memory.
                 if stack_memory
@@ -268,29 +337,111 @@ where
             if caller_bp.is_some() {
                 valid.insert(FRAME_POINTER_REGISTER);
             }
+            let provenance = valid
+                .iter()
+                .map(|&reg| (reg, RegisterProvenance::Scanned))
+                .collect();
+            let valid = MinidumpContextValidity::Some(valid);
+            let verified = scanner.mode != StackScan::FirstPlausible
+                && (scanner.follows_call(caller_ip as u64) || {
+                    cfi_checks += 1;
+                    cfi_checks <= MAX_CFI_CHECKS
+                        && cfi_unwinds_caller(
+                            &caller_ctx,
+                            &valid,
+                            stack_memory,
+                            modules,
+                            memory_info,
+                            symbol_provider,
+                        )
+                });
+            let trust = if verified {
+                FrameTrust::CfiScan
+            } else {
+                FrameTrust::Scan
+            };
             let context = MinidumpContext {
                 raw: MinidumpRawContext::X86(caller_ctx),
-                valid: MinidumpContextValidity::Some(valid),
+                valid,
             };
-            let mut frame = StackFrame::from_context(context, FrameTrust::Scan);
-            adjust_instruction(&mut frame, caller_ip);
-            return Some(frame);
+            let mut frame = StackFrame::from_caller_context(context, trust);
+            frame.register_provenance = Some(provenance);
+            match scanner.mode {
+                StackScan::FirstPlausible => return Some(frame),
+                _ if verified => return Some(frame),
+                StackScan::PreferVerified if first_plausible.is_none() => {
+                    first_plausible = Some(frame)
+                }
+                _ => {}
+            }
         }
     }
 
-    None
+    first_plausible
+}
+
+/// Whether the CFI for a caller found by scanning can unwind it, which says
+/// the caller's return address really is in a function, and its stack
+/// pointer is where that function expects.
+///
+/// The caller's own caller has to look right too, as it would when walking
+/// by CFI: its instruction pointer in a module and its stack pointer further
+/// up the stack.
+fn cfi_unwinds_caller<P>(
+    caller_ctx: &CONTEXT_X86,
+    valid: &MinidumpContextValidity,
+    stack_memory: &MinidumpMemory,
+    modules: &MinidumpModuleList,
+    memory_info: Option<&MinidumpMemoryInfoList>,
+    symbol_provider: &P,
+) -> bool
+where
+    P: SymbolProvider,
+{
+    let module = match modules.module_at_address(caller_ctx.eip as u64) {
+        Some(module) => module,
+        None => return false,
+    };
+    let mut stack_walker = CfiStackWalker {
+        instruction: caller_ctx.eip as u64,
+        grand_callee_parameter_size: 0,
+
+        callee_ctx: caller_ctx,
+        callee_validity: valid,
+
+        caller_ctx: CONTEXT_X86::default(),
+        caller_validity: HashSet::new(),
+
+        stack_memory,
+    };
+    if symbol_provider
+        .walk_frame(module, &mut stack_walker)
+        .is_none()
+    {
+        return false;
+    }
+    instruction_seems_valid(
+        stack_walker.caller_ctx.eip,
+        modules,
+        memory_info,
+        symbol_provider,
+    ) && stack_seems_valid(stack_walker.caller_ctx.esp, caller_ctx.esp, stack_memory)
 }
 
 #[allow(clippy::match_like_matches_macro)]
 fn instruction_seems_valid<P>(
     instruction: Pointer,
     modules: &MinidumpModuleList,
+    memory_info: Option<&MinidumpMemoryInfoList>,
     _symbol_provider: &P,
 ) -> bool
 where
     P: SymbolProvider,
 {
     // NOTE: x86 has no notion of pointer canonicity (divergence from AMD64)
+    if in_non_executable_memory(u64::from(instruction), memory_info) {
+        return false;
+    }
     if let Some(_module) = modules.module_at_address(instruction as u64) {
         // TODO: if mapped, check if this instruction actually maps to a function line
         true
@@ -315,25 +466,19 @@ fn stack_seems_valid(
         .is_some()
 }
 
-fn adjust_instruction(frame: &mut StackFrame, caller_ip: Pointer) {
-    // A caller's ip is the return address, which is the instruction
-    // after the CALL that caused us to arrive at the callee. Set
-    // the value to one less than that, so it points within the
-    // CALL instruction.
-    if caller_ip > 0 {
-        frame.instruction = caller_ip as u64 - 1;
-    }
-}
-
 impl Unwind for CONTEXT_X86 {
     fn get_caller_frame<P>(
         &self,
         valid: &MinidumpContextValidity,
         trust: FrameTrust,
+        _at_function_entry: bool,
         stack_memory: Option<&MinidumpMemory>,
         grand_callee_frame: Option<&StackFrame>,
         modules: &MinidumpModuleList,
+        memory_info: Option<&MinidumpMemoryInfoList>,
         syms: &P,
+        scan: &StackScanner,
+        diagnostics: &mut UnwindDiagnostics,
     ) -> Option<StackFrame>
     where
         P: SymbolProvider,
@@ -341,21 +486,53 @@ impl Unwind for CONTEXT_X86 {
         stack_memory
             .as_ref()
             .and_then(|stack| {
-                get_caller_by_cfi(self, valid, trust, stack, grand_callee_frame, modules, syms)
+                let cfi = get_caller_by_cfi(
+                    self,
+                    valid,
+                    trust,
+                    stack,
+                    grand_callee_frame,
+                    modules,
+                    memory_info,
+                    syms,
+                    diagnostics,
+                );
+                diagnostics
+                    .tried("call frame info", cfi)
+                    .or_else(|| {
+                        let frame =
+                            get_caller_by_frame_pointer(self, valid, trust, stack, modules, syms);
+                        diagnostics.tried("frame pointer", frame)
+                    })
                     .or_else(|| {
-                        get_caller_by_frame_pointer(self, valid, trust, stack, modules, syms)
+                        let frame = get_caller_by_scan(
+                            self,
+                            valid,
+                            trust,
+                            stack,
+                            modules,
+                            memory_info,
+                            syms,
+                            scan,
+                        );
+                        diagnostics.tried("stack scanning", frame)
                     })
-                    .or_else(|| get_caller_by_scan(self, valid, trust, stack, modules, syms))
             })
             .and_then(|frame| {
                 // Treat an instruction address of 0 as end-of-stack.
                 if frame.context.get_instruction_pointer() == 0 {
+                    diagnostics.note(format_args!("  rejected the caller: ip 0 ends the stack"));
                     return None;
                 }
                 // If the new stack pointer is at a lower address than the old,
                 // then that's clearly incorrect. Treat this as end-of-stack to
                 // enforce progress and avoid infinite loops.
                 if frame.context.get_stack_pointer() as u32 <= self.esp {
+                    diagnostics.note(format_args!(
+                        "  rejected the caller: its sp {:#x} isn't above {:#x}",
+                        frame.context.get_stack_pointer(),
+                        self.esp
+                    ));
                     return None;
                 }
                 Some(frame)
@@ -2,15 +2,16 @@
 // file at the top-level directory of this distribution.
 
 use crate::process_state::*;
-use crate::stackwalker::walk_stack;
+use crate::stackwalker::{fill_mappings, verify_frame_modules, walk_stack};
 use breakpad_symbols::{SimpleSymbolSupplier, Symbolizer};
-use minidump::format::CONTEXT_X86;
+use minidump::format::{CvSignature, CONTEXT_X86, CV_INFO_PDB20};
 use minidump::*;
 use test_assembler::*;
 
 struct TestFixture {
     pub raw: CONTEXT_X86,
     pub modules: MinidumpModuleList,
+    pub unloaded_modules: MinidumpUnloadedModuleList,
     pub symbolizer: Symbolizer,
 }
 
@@ -24,6 +25,7 @@ impl TestFixture {
                 MinidumpModule::new(0x40000000, 0x10000, "module1"),
                 MinidumpModule::new(0x50000000, 0x10000, "module2"),
             ]),
+            unloaded_modules: MinidumpUnloadedModuleList::new(),
             symbolizer: Symbolizer::new(SimpleSymbolSupplier::new(vec![])),
         }
     }
@@ -46,6 +48,7 @@ impl TestFixture {
             &Some(&context),
             Some(&stack_memory),
             &self.modules,
+            &self.unloaded_modules,
             &self.symbolizer,
         )
     }
@@ -252,3 +255,259 @@ fn test_traditional_scan_long_way() {
         }
     }
 }
+
+// Frames in unloaded modules should still be symbolized if symbols can be
+// found for the unloaded module.
+#[test]
+fn test_unloaded_module_symbols() {
+    let mut f = TestFixture::new();
+    let symbol_path = std::path::Path::new(env!("CARGO_MANIFEST_DIR")).join("../testdata/symbols");
+    f.symbolizer = Symbolizer::new(SimpleSymbolSupplier::new(vec![symbol_path]));
+    f.unloaded_modules =
+        MinidumpUnloadedModuleList::from_modules(vec![MinidumpUnloadedModule::new(
+            0x60000000,
+            0x10000,
+            "c:\\unloaded.dll",
+        )]);
+    let frame0_ebp = Label::new();
+    let frame1_ebp = Label::new();
+    let mut stack = Section::new();
+    stack.start().set_const(0x80000000);
+    stack = stack
+        .append_repeated(12, 0) // frame 0: space
+        .mark(&frame0_ebp) // frame 0 %ebp points here
+        .D32(&frame1_ebp) // frame 0: saved %ebp
+        .D32(0x60000209) // frame 0: return address
+        .append_repeated(8, 0) // frame 1: space
+        .mark(&frame1_ebp) // frame 1 %ebp points here
+        .D32(0) // frame 1: saved %ebp (stack end)
+        .D32(0); // frame 1: return address (stack end)
+    f.raw.eip = 0x60000120;
+    f.raw.esp = stack.start().value().unwrap() as u32;
+    f.raw.ebp = frame0_ebp.value().unwrap() as u32;
+    let s = f.walk_stack(stack);
+    assert_eq!(s.frames.len(), 2);
+
+    let f0 = &s.frames[0];
+    assert!(f0.module.is_none());
+    assert_eq!(f0.unloaded_modules[0].code_file(), "c:\\unloaded.dll");
+    assert_eq!(f0.function_name.as_deref(), Some("unloaded_crash"));
+    assert_eq!(f0.function_base, Some(0x60000100));
+
+    let f1 = &s.frames[1];
+    assert_eq!(f1.trust, FrameTrust::FramePointer);
+    assert_eq!(f1.function_name.as_deref(), Some("unloaded_caller"));
+
+    let mut output = vec![];
+    s.print(&mut output).unwrap();
+    let output = String::from_utf8(output).unwrap();
+    assert!(output.contains("(unloaded unloaded.dll)!unloaded_crash + 0x20"));
+}
+
+// When several unloaded modules covered the same address, the frame should
+// list all of them, with the one that provided symbols first.
+#[test]
+fn test_overlapping_unloaded_modules() {
+    let mut f = TestFixture::new();
+    let symbol_path = std::path::Path::new(env!("CARGO_MANIFEST_DIR")).join("../testdata/symbols");
+    f.symbolizer = Symbolizer::new(SimpleSymbolSupplier::new(vec![symbol_path]));
+    f.unloaded_modules = MinidumpUnloadedModuleList::from_modules(vec![
+        MinidumpUnloadedModule::new(0x60000000, 0x20000, "c:\\nosymbols.dll"),
+        MinidumpUnloadedModule::new(0x60000000, 0x10000, "c:\\unloaded.dll"),
+        MinidumpUnloadedModule::new(0x70000000, 0x10000, "c:\\elsewhere.dll"),
+    ]);
+    let mut stack = Section::new();
+    stack.start().set_const(0x80000000);
+    stack = stack.append_repeated(16, 0);
+    f.raw.eip = 0x60000120;
+    f.raw.esp = stack.start().value().unwrap() as u32;
+    f.raw.ebp = 0;
+    let s = f.walk_stack(stack);
+
+    let f0 = &s.frames[0];
+    assert!(f0.module.is_none());
+    let names = f0
+        .unloaded_modules
+        .iter()
+        .map(|module| module.code_file().into_owned())
+        .collect::<Vec<_>>();
+    assert_eq!(names, vec!["c:\\unloaded.dll", "c:\\nosymbols.dll"]);
+    assert_eq!(f0.function_name.as_deref(), Some("unloaded_crash"));
+
+    let mut output = vec![];
+    s.print(&mut output).unwrap();
+    let output = String::from_utf8(output).unwrap();
+    assert!(output.contains("(unloaded unloaded.dll|nosymbols.dll)!unloaded_crash + 0x20"));
+}
+
+// An unloaded module has no debug file or identifier of its own, but if the
+// same build is loaded elsewhere, its symbols can be found through that module.
+#[test]
+fn test_unloaded_module_debug_symbols() {
+    let mut f = TestFixture::new();
+    let symbol_path = std::path::Path::new(env!("CARGO_MANIFEST_DIR")).join("../testdata/symbols");
+    f.symbolizer = Symbolizer::new(SimpleSymbolSupplier::new(vec![symbol_path]));
+    let mut plugin = MinidumpModule::new(0x70000000, 0x10000, "c:\\plugin.dll");
+    plugin.codeview_info = Some(CodeView::Pdb20(CV_INFO_PDB20 {
+        cv_signature: CvSignature::Pdb20 as u32,
+        cv_offset: 0,
+        signature: 0x12345678,
+        age: 1,
+        pdb_file_name: b"plugin.pdb\0".to_vec(),
+    }));
+    f.modules = MinidumpModuleList::from_modules(vec![plugin]);
+    f.unloaded_modules =
+        MinidumpUnloadedModuleList::from_modules(vec![MinidumpUnloadedModule::new(
+            0x60000000,
+            0x10000,
+            "c:\\plugin.dll",
+        )]);
+    let mut stack = Section::new();
+    stack.start().set_const(0x80000000);
+    stack = stack.D32(0).D32(0); // end-of-stack marker
+    f.raw.eip = 0x60000120;
+    f.raw.esp = stack.start().value().unwrap() as u32;
+    f.raw.ebp = 0x80000000;
+    let s = f.walk_stack(stack);
+
+    let f0 = &s.frames[0];
+    assert!(f0.module.is_none());
+    assert_eq!(f0.unloaded_modules[0].code_file(), "c:\\plugin.dll");
+    assert_eq!(f0.function_name.as_deref(), Some("plugin_teardown"));
+    assert_eq!(f0.function_base, Some(0x60000100));
+}
+
+#[test]
+fn test_inconsistent_frame_module() {
+    let f = TestFixture::new();
+    let mut stack = Section::new();
+    stack.start().set_const(0x80000000);
+    stack = stack.D32(0).D32(0); // end-of-stack marker
+    let mut raw = f.raw.clone();
+    raw.eip = 0x40000200;
+    raw.ebp = 0x80000000;
+    let context = MinidumpContext {
+        raw: MinidumpRawContext::X86(raw),
+        valid: MinidumpContextValidity::All,
+    };
+
+    // A frame in module1 that somehow got module2 assigned to it.
+    let mut frame = StackFrame::from_context(context.clone(), FrameTrust::Context);
+    frame.module = Some(MinidumpModule::new(0x50000000, 0x10000, "module2"));
+    frame.function_name = Some("wrong".to_owned());
+    frame.function_base = Some(0x50000100);
+    // A frame whose module is right.
+    let mut good_frame = StackFrame::from_context(context, FrameTrust::Context);
+    good_frame.module = Some(MinidumpModule::new(0x40000000, 0x10000, "module1"));
+    let mut frames = vec![frame, good_frame];
+    verify_frame_modules(&mut frames);
+
+    assert!(frames[0].module.is_none());
+    assert!(frames[0].inconsistent_module);
+    assert_eq!(frames[0].function_name, None);
+    assert_eq!(frames[0].function_base, None);
+    assert_eq!(frames[1].module.as_ref().unwrap().code_file(), "module1");
+    assert!(!frames[1].inconsistent_module);
+
+    let s = CallStack {
+        frames,
+        info: CallStackInfo::Ok,
+        thread_name: None,
+        total_frames: None,
+        context_flags: None,
+        unwind_diagnostics: None,
+        thread_id: None,
+        suspend_count: None,
+        priority_class: None,
+        priority: None,
+        teb: None,
+        exception_context: None,
+        thread_context: None,
+    };
+    let mut output = vec![];
+    s.print(&mut output).unwrap();
+    let output = String::from_utf8(output).unwrap();
+    assert!(output.contains("WARNING: module did not contain the instruction"));
+
+    // Frames from a normal walk are left alone.
+    let s = f.walk_stack(stack);
+    assert!(!s.frames[0].inconsistent_module);
+}
+
+#[test]
+fn test_frame_linux_mapping() {
+    let mut f = TestFixture::new();
+    let mut stack = Section::new();
+    stack.start().set_const(0x80000000);
+    stack = stack.D32(0).D32(0); // end-of-stack marker
+
+    // JIT code that isn't in any module.
+    f.raw.eip = 0x60000120;
+    f.raw.ebp = 0x80000000;
+    let mut s = f.walk_stack(stack);
+    assert!(s.frames[0].module.is_none());
+
+    let maps = MinidumpLinuxMaps::from_maps(vec![
+        MinidumpLinuxMapInfo::parse("40000000-40010000 r-xp 00000000 08:01 1234 module1").unwrap(),
+        MinidumpLinuxMapInfo::parse("60000000-60010000 rwxp 00000000 00:00 0").unwrap(),
+    ]);
+    fill_mappings(&mut s, &maps);
+    let mapping = s.frames[0].mapping.as_ref().unwrap();
+    assert_eq!(mapping.base_address, 0x60000000);
+    assert_eq!(mapping.path, None);
+
+    let mut output = vec![];
+    s.print(&mut output).unwrap();
+    let output = String::from_utf8(output).unwrap();
+    assert!(output.starts_with(" 0  <anonymous> + 0x120\n"));
+}
+
+// A call can be the last instruction of a function, when the compiler knows
+// the callee never returns. The return address is then in the next function,
+// but the caller frame should still be symbolized as the call.
+#[test]
+fn test_call_at_function_boundary() {
+    let mut f = TestFixture::new();
+    let symbol_path = std::path::Path::new(env!("CARGO_MANIFEST_DIR")).join("../testdata/symbols");
+    f.symbolizer = Symbolizer::new(SimpleSymbolSupplier::new(vec![symbol_path]));
+    let mut module = MinidumpModule::new(0x70000000, 0x10000, "c:\\boundary.dll");
+    module.codeview_info = Some(CodeView::Pdb20(CV_INFO_PDB20 {
+        cv_signature: CvSignature::Pdb20 as u32,
+        cv_offset: 0,
+        signature: 0x87654321,
+        age: 1,
+        pdb_file_name: b"boundary.pdb\0".to_vec(),
+    }));
+    f.modules = MinidumpModuleList::from_modules(vec![module]);
+    let frame0_ebp = Label::new();
+    let frame1_ebp = Label::new();
+    let mut stack = Section::new();
+    stack.start().set_const(0x80000000);
+    stack = stack
+        .append_repeated(12, 0) // frame 0: space
+        .mark(&frame0_ebp) // frame 0 %ebp points here
+        .D32(&frame1_ebp) // frame 0: saved %ebp
+        .D32(0x70000110) // frame 0: return address, the start of follows_abort
+        .append_repeated(8, 0) // frame 1: space
+        .mark(&frame1_ebp) // frame 1 %ebp points here
+        .D32(0) // frame 1: saved %ebp (stack end)
+        .D32(0); // frame 1: return address (stack end)
+    f.raw.eip = 0x70000210;
+    f.raw.esp = stack.start().value().unwrap() as u32;
+    f.raw.ebp = frame0_ebp.value().unwrap() as u32;
+    let s = f.walk_stack(stack);
+    assert_eq!(s.frames.len(), 2);
+
+    let f0 = &s.frames[0];
+    assert_eq!(f0.instruction, 0x70000210);
+    assert_eq!(f0.return_address(), 0x70000210);
+    assert_eq!(f0.function_name.as_deref(), Some("abort_impl"));
+    assert_eq!(f0.source_line, Some(30));
+
+    let f1 = &s.frames[1];
+    assert_eq!(f1.instruction, 0x7000010f);
+    assert_eq!(f1.return_address(), 0x70000110);
+    assert_eq!(f1.function_name.as_deref(), Some("exits_through_abort"));
+    assert_eq!(f1.source_file_name.as_deref(), Some("c:\\src\\boundary.c"));
+    assert_eq!(f1.source_line, Some(12));
+}
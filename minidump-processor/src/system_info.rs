@@ -8,6 +8,17 @@ pub struct SystemInfo {
     ///
     /// This may look like "5.1.2600 Service Pack 2" or "10.4.8 8L2127", if present
     pub os_version: Option<String>,
+    /// The major version of the operating system, like 10 for Windows 10
+    pub os_major: u32,
+    /// The minor version of the operating system
+    pub os_minor: u32,
+    /// The build number of the operating system, like 19041 for Windows 10 2004
+    pub os_build: u32,
+    /// The service pack of the operating system, like "Service Pack 2", if present
+    ///
+    /// This is whatever further identifies the version on other operating
+    /// systems, such as the build of macOS, like "8L2127".
+    pub os_service_pack: Option<String>,
     /// The CPU on which the dump was produced
     pub cpu: Cpu,
     /// A string further identifying the specific CPU
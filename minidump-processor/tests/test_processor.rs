@@ -1,10 +1,22 @@
 // Copyright 2015 Ted Mielczarek. See the COPYRIGHT
 // file at the top-level directory of this distribution.
 
-use breakpad_symbols::{SimpleSymbolSupplier, Symbolizer};
+use breakpad_symbols::{
+    CfiCache, FrameSymbolizer, FrameWalker, HttpSymbolSupplier, InMemorySymbolSupplier,
+    SimpleModule, SimpleSymbolSupplier, SymbolFile, SymbolStatus, Symbolizer,
+};
+use chrono::{TimeZone, Utc};
+use minidump::format::{
+    ProcessExecuteFlags, ProcessorArchitecture, MINIDUMP_MEMORY_DESCRIPTOR, MINIDUMP_STREAM_TYPE,
+};
 use minidump::system_info::{Cpu, Os};
 use minidump::*;
-use minidump_processor::{CallStackInfo, FrameTrust};
+use minidump_processor::{
+    CallStack, CallStackInfo, DwarfSymbolizer, EnvironmentFilter, FrameTrust, JsonOptions,
+    ModuleSymbolStatus, MultiSymbolProvider, NoopSymbolizer, PrintOptions, ProcessState,
+    ProcessorOptions, RuntimeVariables, SharedEnvironmentFilter, SharedSourceUrlResolver,
+    SignatureOptions, StackScan, StackWalkOptions, SymbolProvider, VirtualMemoryStats,
+};
 use std::path::{Path, PathBuf};
 
 fn locate_testdata() -> PathBuf {
@@ -38,6 +50,32 @@ fn testdata_symbol_path() -> PathBuf {
     path
 }
 
+fn state_json(state: &ProcessState) -> serde_json::Value {
+    state_json_with_options(state, &JsonOptions::default())
+}
+
+fn state_json_with_options(state: &ProcessState, options: &JsonOptions) -> serde_json::Value {
+    let mut json = vec![];
+    state
+        .print_json_with_options(&mut json, false, options)
+        .unwrap();
+    serde_json::from_slice(&json).unwrap()
+}
+
+/// Process `dump` and parse the JSON output of the result.
+fn process_to_json<T, P>(
+    dump: &Minidump<'_, T>,
+    provider: &P,
+    options: &ProcessorOptions,
+) -> serde_json::Value
+where
+    T: std::ops::Deref<Target = [u8]>,
+    P: SymbolProvider,
+{
+    let state = minidump_processor::process_minidump_with_options(dump, provider, options).unwrap();
+    state_json(&state)
+}
+
 #[test]
 fn test_processor() {
     let dump = read_test_minidump().unwrap();
@@ -47,14 +85,34 @@ fn test_processor() {
     )
     .unwrap();
     assert_eq!(state.system_info.os, Os::Windows);
-    // TODO
-    // assert_eq!(state.system_info.os_version.unwrap(),
-    // "5.1.2600 Service Pack 2");
+    assert_eq!(
+        state.system_info.os_version.as_deref(),
+        Some("5.1.2600 Service Pack 2")
+    );
+    assert_eq!(state.system_info.os_major, 5);
+    assert_eq!(state.system_info.os_minor, 1);
+    assert_eq!(state.system_info.os_build, 2600);
+    assert_eq!(
+        state.system_info.os_service_pack.as_deref(),
+        Some("Service Pack 2")
+    );
     assert_eq!(state.system_info.cpu, Cpu::X86);
     // TODO:
     // assert_eq!(state.system_info.cpu_info.unwrap(),
     // "GenuineIntel family 6 model 13 stepping 8");
+    assert_eq!(
+        state.crash_reason,
+        Some(CrashReason::WindowsAccessViolation(AccessKind::Write))
+    );
     assert_eq!(state.crash_address.unwrap(), 0x45);
+    assert_eq!(state.exception_chain.len(), 1);
+    assert_eq!(
+        state.exception_details.unwrap(),
+        ExceptionDetails::AccessViolation {
+            kind: AccessKind::Write,
+            address: 0x45
+        }
+    );
     assert_eq!(state.threads.len(), 2);
     assert_eq!(state.requesting_thread.unwrap(), 0);
 
@@ -107,6 +165,7 @@ fn test_processor() {
     // The dump thread should have been skipped.
     assert_eq!(state.threads[1].info, CallStackInfo::DumpThreadSkipped);
     assert_eq!(state.threads[1].frames.len(), 0);
+    assert_eq!(state.dump_thread, Some(1));
 }
 
 #[test]
@@ -124,4 +183,2534 @@ fn test_processor_symbols() {
         f0.function_name.as_deref(),
         Some("`anonymous namespace'::CrashFunction")
     );
+    assert_eq!(
+        f0.to_string(),
+        "test_app.exe!`anonymous namespace'::CrashFunction [test_app.cc : 58 + 0x3]"
+    );
+    // kernel32.dll has no symbols.
+    assert_eq!(
+        state.threads[0].frames[3].to_string(),
+        "kernel32.dll + 0x16fd6"
+    );
+
+    // The human-readable output uses the same text for each frame.
+    let mut human = vec![];
+    state.threads[0].print(&mut human).unwrap();
+    let human = String::from_utf8(human).unwrap();
+    assert!(human.starts_with(&format!(" 0  {}\n", f0)));
+
+    // Frames outside of any module are followed by a blank line.
+    let mut state = state;
+    state.threads[0].frames[0].module = None;
+    let mut human = vec![];
+    state.threads[0].print(&mut human).unwrap();
+    let human = String::from_utf8(human).unwrap();
+    assert!(human.starts_with(" 0  0x40429e\n\n"));
+}
+
+#[test]
+fn test_processor_symbolize_address() {
+    let dump = read_test_minidump().unwrap();
+    let symbolizer = Symbolizer::new(SimpleSymbolSupplier::new(vec![testdata_symbol_path()]));
+    let state = minidump_processor::process_minidump(&dump, &symbolizer).unwrap();
+
+    // The crashing instruction resolves the same way its frame did.
+    let location = state.symbolize_address(0x40429e, &symbolizer).unwrap();
+    let f0 = &state.threads[0].frames[0];
+    assert_eq!(location.address, 0x40429e);
+    assert_eq!(
+        location.module.as_ref().map(|module| module.code_file()),
+        f0.module.as_ref().map(|module| module.code_file())
+    );
+    assert_eq!(location.function_name, f0.function_name);
+    assert_eq!(location.function_base, f0.function_base);
+    assert_eq!(location.source_file_name, f0.source_file_name);
+    assert_eq!(location.source_line, Some(58));
+    assert!(location.unloaded_modules.is_empty());
+
+    // kernel32.dll has no symbols, but the module is still found.
+    let kernel32 = state.threads[0].frames[3].instruction;
+    let location = state.symbolize_address(kernel32, &symbolizer).unwrap();
+    assert_eq!(
+        location.module.unwrap().code_file(),
+        "C:\\WINDOWS\\system32\\kernel32.dll"
+    );
+    assert_eq!(location.function_name, None);
+
+    // Addresses outside of every module aren't found.
+    assert!(state.symbolize_address(0x10, &symbolizer).is_none());
+
+    // It works without processing the whole minidump, too.
+    let modules = dump.get_stream::<MinidumpModuleList>().unwrap();
+    let location = minidump_processor::symbolize_address(
+        0x40429e,
+        &modules,
+        &MinidumpUnloadedModuleList::new(),
+        &symbolizer,
+    )
+    .unwrap();
+    assert_eq!(
+        location.function_name.as_deref(),
+        Some("`anonymous namespace'::CrashFunction")
+    );
+}
+
+#[test]
+fn test_processor_dwarf_symbolizer() {
+    let dwarf = locate_testdata().join("dwarf");
+    let build_id = vec![
+        0xa3, 0x03, 0xf9, 0x1c, 0x95, 0x66, 0xb1, 0x17, 0x09, 0xd3, 0x16, 0x91, 0x77, 0x58, 0x25,
+        0x85, 0x78, 0xe1, 0x30, 0x5e,
+    ];
+    let module = |name: &str, build_id: Option<Vec<u8>>| {
+        let mut module = MinidumpModule::new(0x7f0000000000, 0x5000, name);
+        module.codeview_info = build_id.map(|build_id| {
+            CodeView::Elf(format::CV_INFO_ELF {
+                cv_signature: format::CvSignature::Elf as u32,
+                build_id,
+            })
+        });
+        MinidumpModuleList::from_modules(vec![module])
+    };
+    let symbolize = |modules: &MinidumpModuleList| {
+        minidump_processor::symbolize_address(
+            0x7f000000100c,
+            modules,
+            &MinidumpUnloadedModuleList::new(),
+            &DwarfSymbolizer::with_paths(vec![dwarf.clone()]),
+        )
+        .unwrap()
+    };
+
+    // The ELF file is found in the search path by its name and build id.
+    let location = symbolize(&module("/usr/lib/dwarf-test.elf", Some(build_id.clone())));
+    assert_eq!(location.function_name.as_deref(), Some("crash_here"));
+    assert!(location.source_file_name.unwrap().ends_with("dwarf-test.c"));
+    assert_eq!(location.source_line, Some(6));
+
+    // A file without a build id at the module's path is only used if the
+    // module doesn't have one either.
+    let no_id = dwarf.join("dwarf-test-no-id.elf");
+    let no_id = no_id.to_str().unwrap();
+    let location = symbolize(&module(no_id, Some(build_id)));
+    assert_eq!(location.function_name, None);
+    let location = symbolize(&module(no_id, None));
+    assert_eq!(location.function_name.as_deref(), Some("crash_here"));
+}
+
+#[test]
+fn test_processor_crash_signature() {
+    let dump = read_test_minidump().unwrap();
+    let state = minidump_processor::process_minidump(
+        &dump,
+        &Symbolizer::new(SimpleSymbolSupplier::new(vec![testdata_symbol_path()])),
+    )
+    .unwrap();
+    assert_eq!(
+        state.crash_signature().as_deref(),
+        Some("test_app.exe!`anonymous namespace'::CrashFunction | test_app.exe!main | test_app.exe!__tmainCRTStartup")
+    );
+
+    // Skipping the crashing function brings in the frame without symbols.
+    let options = SignatureOptions {
+        skip_functions: vec!["`anonymous namespace'::*".to_owned()],
+        ..SignatureOptions::default()
+    };
+    let frames = state.signature_frames(&options);
+    assert_eq!(frames.len(), 3);
+    assert_eq!(frames[0].function_name.as_deref(), Some("main"));
+    assert_eq!(
+        state.crash_signature_with_options(&options).as_deref(),
+        Some("test_app.exe!main | test_app.exe!__tmainCRTStartup | kernel32.dll@0x16fd6")
+    );
+
+    // Without symbols, frames are named by their offset in their module.
+    let state = minidump_processor::process_minidump(
+        &dump,
+        &Symbolizer::new(SimpleSymbolSupplier::new(vec![])),
+    )
+    .unwrap();
+    let options = SignatureOptions {
+        max_frames: 1,
+        ..SignatureOptions::default()
+    };
+    assert_eq!(
+        state.crash_signature_with_options(&options).as_deref(),
+        Some("test_app.exe@0x429e")
+    );
+}
+
+#[test]
+fn test_processor_in_memory_symbols() {
+    let dump = read_test_minidump().unwrap();
+    let path = testdata_symbol_path()
+        .join("test_app.pdb")
+        .join("5A9832E5287241C1838ED98914E9B7FF1")
+        .join("test_app.sym");
+    let mut supplier = InMemorySymbolSupplier::new();
+    supplier.insert(
+        "test_app.pdb",
+        "5A9832E5287241C1838ED98914E9B7FF1",
+        SymbolFile::from_file(&path).unwrap(),
+    );
+    // Symbol providers can be chosen at runtime.
+    let provider: Box<dyn SymbolProvider> = Box::new(Symbolizer::new(supplier));
+    let state = minidump_processor::process_minidump(&dump, &provider).unwrap();
+    let f0 = &state.threads[0].frames[0];
+    assert_eq!(
+        f0.function_name.as_deref(),
+        Some("`anonymous namespace'::CrashFunction")
+    );
+}
+
+/// A `FrameWalker` for an x86 frame that only knows its `esp`, with a stack
+/// that holds `0xdeadbeef` everywhere.
+struct CfaWalker {
+    instruction: u64,
+    esp: u64,
+    cfa: Option<u64>,
+}
+
+impl FrameWalker for CfaWalker {
+    fn get_instruction(&self) -> u64 {
+        self.instruction
+    }
+    fn get_grand_callee_parameter_size(&self) -> u32 {
+        0
+    }
+    fn get_register_at_address(&self, _address: u64) -> Option<u64> {
+        Some(0xdeadbeef)
+    }
+    fn get_callee_register(&self, name: &str) -> Option<u64> {
+        if name == "esp" {
+            Some(self.esp)
+        } else {
+            None
+        }
+    }
+    fn set_caller_register(&mut self, _name: &str, _val: u64) -> Option<()> {
+        Some(())
+    }
+    fn set_cfa(&mut self, val: u64) -> Option<()> {
+        self.cfa = Some(val);
+        Some(())
+    }
+    fn set_ra(&mut self, _val: u64) -> Option<()> {
+        Some(())
+    }
+}
+
+#[test]
+fn test_processor_cfi_cache_multiple_providers() {
+    // Two providers with symbols for the same module, whose CFI for different
+    // address ranges starts at the same place in each symbol file.
+    let provider = |cfi: &str| -> Box<dyn SymbolProvider> {
+        let text = format!("MODULE windows x86 ABCD1234 app.pdb\n{}\n", cfi);
+        let mut supplier = InMemorySymbolSupplier::new();
+        supplier.insert(
+            "app.pdb",
+            "ABCD1234",
+            SymbolFile::from_bytes(text.as_bytes()).unwrap(),
+        );
+        Box::new(Symbolizer::new(supplier))
+    };
+    let mut providers = MultiSymbolProvider::new();
+    providers.add(provider(
+        "STACK CFI INIT 2000 10 .cfa: $esp 8 + .ra: .cfa 4 - ^",
+    ));
+    providers.add(provider(
+        "STACK CFI INIT 1000 30 .cfa: $esp 16 + .ra: .cfa 4 - ^",
+    ));
+    let module = SimpleModule {
+        base_address: Some(0x10000000),
+        size: Some(0x10000),
+        ..SimpleModule::new("app.pdb", "ABCD1234")
+    };
+
+    let cache = CfiCache::new();
+    let walk = |instruction: u64| {
+        let mut walker = CfaWalker {
+            instruction,
+            esp: 0x1000,
+            cfa: None,
+        };
+        providers
+            .walk_frame_with_cache(&module, &mut walker, &cache)
+            .unwrap();
+        walker.cfa.unwrap()
+    };
+    // Each provider's rules are used for its own range, however the cache was
+    // filled.
+    assert_eq!(walk(0x10002005), 0x1008);
+    assert_eq!(walk(0x10001005), 0x1010);
+    assert_eq!(walk(0x10002005), 0x1008);
+    assert_eq!(cache.len(), 2);
+}
+
+#[test]
+fn test_processor_write_all() {
+    let dump = read_test_minidump().unwrap();
+    let state = minidump_processor::process_minidump(
+        &dump,
+        &Symbolizer::new(SimpleSymbolSupplier::new(vec![])),
+    )
+    .unwrap();
+
+    let mut human = vec![];
+    let mut json = vec![];
+    state
+        .write_all(&mut human, &mut json, false, &JsonOptions::default())
+        .unwrap();
+
+    // Both outputs should match what the individual printers produce.
+    let mut expected_human = vec![];
+    state.print(&mut expected_human).unwrap();
+    let mut expected_json = vec![];
+    state.print_json(&mut expected_json, false).unwrap();
+    assert_eq!(human, expected_human);
+    assert_eq!(json, expected_json);
+
+    let json: serde_json::Value = serde_json::from_slice(&json).unwrap();
+    assert_eq!(json["crash_info"]["address"], "0x00000045");
+    assert_eq!(
+        json["crash_info"]["type"],
+        "EXCEPTION_ACCESS_VIOLATION_WRITE"
+    );
+    // There are no nested exceptions, so the chain is just the crash.
+    let chain = json["crash_info"]["exception_chain"].as_array().unwrap();
+    assert_eq!(chain.len(), 1);
+    assert_eq!(chain[0]["type"], "EXCEPTION_ACCESS_VIOLATION_WRITE");
+    assert_eq!(chain[0]["address"], "0x00000045");
+    assert_eq!(json["thread_count"], 2);
+    assert_eq!(json["threads"][0]["stack_info"], "ok");
+    assert_eq!(json["threads"][1]["stack_info"], "dump_thread_skipped");
+    assert_eq!(json["system_info"]["os_ver"], "5.1.2600 Service Pack 2");
+    assert_eq!(json["system_info"]["os_major"], 5);
+    assert_eq!(json["system_info"]["os_minor"], 1);
+    assert_eq!(json["system_info"]["os_build"], 2600);
+    assert_eq!(json["system_info"]["os_service_pack"], "Service Pack 2");
+    assert!(String::from_utf8(human)
+        .unwrap()
+        .contains("Crash reason:  EXCEPTION_ACCESS_VIOLATION_WRITE\nCrash address: 0x45"));
+
+    // The JSON options are honored.
+    let options = JsonOptions {
+        hex_offsets: false,
+        ..JsonOptions::default()
+    };
+    let mut json = vec![];
+    state
+        .write_all(&mut vec![], &mut json, false, &options)
+        .unwrap();
+    let json: serde_json::Value = serde_json::from_slice(&json).unwrap();
+    assert_eq!(json["crash_info"]["address"], 0x45);
+}
+
+#[test]
+fn test_processor_report_lines() {
+    let dump = read_test_minidump().unwrap();
+    let state = minidump_processor::process_minidump(
+        &dump,
+        &Symbolizer::new(SimpleSymbolSupplier::new(vec![])),
+    )
+    .unwrap();
+
+    let lines = state.report_lines().collect::<Vec<_>>();
+    assert!(lines.iter().all(|line| !line.contains('\n')));
+    assert!(lines[0].starts_with("Operating system: "));
+    let crashed = lines
+        .iter()
+        .position(|line| line == "Thread 0 (crashed)")
+        .unwrap();
+    assert!(lines[crashed + 1].starts_with(" 0  test_app.exe"));
+    assert!(lines.iter().any(|line| line == "Loaded modules:"));
+    // The skipped dump thread isn't in the report.
+    assert!(!lines.iter().any(|line| line.starts_with("Thread 1")));
+
+    // The lines make up exactly what `print` writes.
+    let mut output = vec![];
+    state.print(&mut output).unwrap();
+    let mut expected = lines.join("\n");
+    expected.push('\n');
+    assert_eq!(String::from_utf8(output).unwrap(), expected);
+
+    // Consumers can stop early.
+    let mut crash = state
+        .report_lines()
+        .filter(|line| line.starts_with("Crash reason:"));
+    assert_eq!(
+        crash.next().unwrap(),
+        "Crash reason:  EXCEPTION_ACCESS_VIOLATION_WRITE"
+    );
+}
+
+#[test]
+fn test_processor_print_color() {
+    let dump = read_test_minidump().unwrap();
+    let state = minidump_processor::process_minidump(
+        &dump,
+        &Symbolizer::new(SimpleSymbolSupplier::new(vec![testdata_symbol_path()])),
+    )
+    .unwrap();
+    let mut plain = vec![];
+    state.print(&mut plain).unwrap();
+    let plain = String::from_utf8(plain).unwrap();
+
+    // Without color, the output is unchanged.
+    let mut output = vec![];
+    state
+        .print_with_options(&mut output, &PrintOptions::default())
+        .unwrap();
+    assert_eq!(String::from_utf8(output).unwrap(), plain);
+
+    let options = PrintOptions { color: true };
+    let mut colored = vec![];
+    state.print_with_options(&mut colored, &options).unwrap();
+    let colored = String::from_utf8(colored).unwrap();
+    assert!(colored.contains("Crash reason:  \x1b[31mEXCEPTION_ACCESS_VIOLATION_WRITE\x1b[0m\n"));
+    assert!(colored.contains("\x1b[1mThread 0 (crashed)\x1b[0m\n"));
+    assert!(colored.contains(
+        " 0  \x1b[36mtest_app.exe\x1b[0m!\x1b[1m`anonymous namespace'::CrashFunction\x1b[0m [test_app.cc : 58 + 0x3]\n"
+    ));
+    assert!(
+        colored.contains("    Found by: \x1b[32mgiven as instruction pointer in context\x1b[0m\n")
+    );
+    assert!(colored.contains("    Found by: \x1b[33mprevious frame's frame pointer\x1b[0m\n"));
+
+    // Taking the escape codes out gives the plain report.
+    let mut stripped = String::new();
+    let mut rest = &colored[..];
+    while let Some(start) = rest.find('\x1b') {
+        stripped.push_str(&rest[..start]);
+        rest = &rest[start + rest[start..].find('m').unwrap() + 1..];
+    }
+    stripped.push_str(rest);
+    assert_eq!(stripped, plain);
+
+    // So do the lines of the report.
+    let lines = state
+        .report_lines_with_options(&options)
+        .collect::<Vec<_>>();
+    assert_eq!(lines.join("\n") + "\n", colored);
+}
+
+#[test]
+fn test_processor_thread_names() {
+    let dump = read_test_minidump().unwrap();
+    let mut state = minidump_processor::process_minidump(
+        &dump,
+        &Symbolizer::new(SimpleSymbolSupplier::new(vec![])),
+    )
+    .unwrap();
+
+    // test.dmp has no thread names, so threads fall back to their index.
+    assert_eq!(state.threads[0].thread_name, None);
+    assert_eq!(state.threads[0].thread_label(0), "Thread 0");
+    let mut output = vec![];
+    state.print(&mut output).unwrap();
+    let output = String::from_utf8(output).unwrap();
+    assert!(output.contains("\nThread 0 (crashed)\n"));
+
+    state.threads[0].thread_name = Some("main".to_owned());
+    let mut output = vec![];
+    state.print(&mut output).unwrap();
+    let output = String::from_utf8(output).unwrap();
+    assert!(output.contains("\nmain (crashed)\n"));
+}
+
+/// The register names of the crashing thread's first frame, in the order
+/// they appear in the JSON `report`.
+fn crashing_register_names(report: &[u8]) -> Vec<String> {
+    use serde::de::{Deserialize, Deserializer, IgnoredAny, MapAccess, Visitor};
+
+    struct KeyOrder(Vec<String>);
+
+    impl<'de> Deserialize<'de> for KeyOrder {
+        fn deserialize<D: Deserializer<'de>>(deserializer: D) -> Result<Self, D::Error> {
+            struct KeyOrderVisitor;
+
+            impl<'de> Visitor<'de> for KeyOrderVisitor {
+                type Value = KeyOrder;
+
+                fn expecting(&self, f: &mut std::fmt::Formatter) -> std::fmt::Result {
+                    f.write_str("an object")
+                }
+
+                fn visit_map<A: MapAccess<'de>>(self, mut map: A) -> Result<KeyOrder, A::Error> {
+                    let mut keys = vec![];
+                    while let Some((key, IgnoredAny)) = map.next_entry::<String, IgnoredAny>()? {
+                        keys.push(key);
+                    }
+                    Ok(KeyOrder(keys))
+                }
+            }
+
+            deserializer.deserialize_map(KeyOrderVisitor)
+        }
+    }
+
+    // Only the first frame has `registers`.
+    #[derive(serde::Deserialize)]
+    struct Frame {
+        registers: Option<KeyOrder>,
+    }
+
+    #[derive(serde::Deserialize)]
+    struct Thread {
+        frames: Vec<Frame>,
+    }
+
+    #[derive(serde::Deserialize)]
+    struct Report {
+        crashing_thread: Thread,
+    }
+
+    let report: Report = serde_json::from_slice(report).unwrap();
+    report
+        .crashing_thread
+        .frames
+        .into_iter()
+        .next()
+        .unwrap()
+        .registers
+        .unwrap()
+        .0
+}
+
+#[test]
+fn test_processor_register_order() {
+    let dump = read_test_minidump().unwrap();
+    let mut state = minidump_processor::process_minidump(
+        &dump,
+        &Symbolizer::new(SimpleSymbolSupplier::new(vec![])),
+    )
+    .unwrap();
+
+    // Pretend the crashing thread was running on amd64.
+    state.system_info.cpu = Cpu::X86_64;
+    state.threads[0].frames[0].context =
+        MinidumpContext::from_raw(MinidumpRawContext::Amd64(format::CONTEXT_AMD64::default()));
+
+    let register_names = |order: RegisterOrder| {
+        let mut json = vec![];
+        let options = JsonOptions {
+            register_order: order,
+            ..JsonOptions::default()
+        };
+        state
+            .print_json_with_options(&mut json, false, &options)
+            .unwrap();
+        crashing_register_names(&json)
+    };
+
+    let conventional = register_names(RegisterOrder::Conventional);
+    assert_eq!(
+        conventional,
+        vec![
+            "rip", "rsp", "rbp", "rax", "rbx", "rcx", "rdx", "rsi", "rdi", "r8", "r9", "r10",
+            "r11", "r12", "r13", "r14", "r15",
+        ]
+    );
+
+    // The default order is the conventional one.
+    let mut json = vec![];
+    state.print_json(&mut json, false).unwrap();
+    assert_eq!(crashing_register_names(&json), conventional);
+
+    // The native order is still available.
+    let native = register_names(RegisterOrder::Native);
+    assert_eq!(native.first().map(String::as_str), Some("rax"));
+    assert_eq!(native.last().map(String::as_str), Some("rip"));
+}
+
+#[test]
+fn test_processor_max_frames() {
+    let dump = read_test_minidump().unwrap();
+    let options = ProcessorOptions {
+        max_frames: Some(2),
+        ..ProcessorOptions::default()
+    };
+    let state = minidump_processor::process_minidump_with_options(
+        &dump,
+        &Symbolizer::new(SimpleSymbolSupplier::new(vec![])),
+        &options,
+    )
+    .unwrap();
+
+    // Thread 0 has 4 frames, only the innermost two are kept.
+    assert_eq!(state.threads[0].frames.len(), 2);
+    assert_eq!(state.threads[0].total_frames, Some(4));
+    assert_eq!(
+        state.threads[0].frames[0].context.get_instruction_pointer(),
+        0x0040429e
+    );
+    // The dump thread has no frames to truncate.
+    assert_eq!(state.threads[1].total_frames, None);
+
+    let mut human = vec![];
+    let mut json = vec![];
+    state
+        .write_all(&mut human, &mut json, false, &JsonOptions::default())
+        .unwrap();
+    assert!(String::from_utf8(human)
+        .unwrap()
+        .contains("(2 frames omitted)\n"));
+
+    let json: serde_json::Value = serde_json::from_slice(&json).unwrap();
+    assert_eq!(json["threads"][0]["frame_count"], 2);
+    assert_eq!(json["threads"][0]["frames_truncated"], true);
+    assert_eq!(json["threads"][0]["total_frames"], 4);
+    assert_eq!(json["threads"][1]["frames_truncated"], false);
+
+    // A stack that ends right at the limit isn't truncated.
+    let options = ProcessorOptions {
+        max_frames: Some(4),
+        ..ProcessorOptions::default()
+    };
+    let state = minidump_processor::process_minidump_with_options(
+        &dump,
+        &Symbolizer::new(SimpleSymbolSupplier::new(vec![])),
+        &options,
+    )
+    .unwrap();
+    assert_eq!(state.threads[0].frames.len(), 4);
+    assert_eq!(state.threads[0].total_frames, None);
+}
+
+#[test]
+fn test_processor_cfi_cache_disabled() {
+    let dump = read_test_minidump().unwrap();
+    let symbolizer = Symbolizer::new(SimpleSymbolSupplier::new(vec![testdata_symbol_path()]));
+    let cached = minidump_processor::process_minidump(&dump, &symbolizer).unwrap();
+    let options = ProcessorOptions {
+        cfi_cache_capacity: 0,
+        ..ProcessorOptions::default()
+    };
+    let uncached =
+        minidump_processor::process_minidump_with_options(&dump, &symbolizer, &options).unwrap();
+
+    // Caching doesn't change how threads unwind.
+    let frames = |state: &ProcessState| {
+        state
+            .threads
+            .iter()
+            .map(|thread| {
+                thread
+                    .frames
+                    .iter()
+                    .map(|frame| (frame.context.get_instruction_pointer(), frame.trust))
+                    .collect::<Vec<_>>()
+            })
+            .collect::<Vec<_>>()
+    };
+    assert_eq!(frames(&cached), frames(&uncached));
+}
+
+#[test]
+fn test_processor_module_limits() {
+    let dump = read_test_minidump().unwrap();
+    let module_count = dump
+        .get_stream::<MinidumpModuleList>()
+        .unwrap()
+        .iter()
+        .count();
+    let options = ProcessorOptions {
+        module_limits: ModuleListLimits {
+            max_modules: module_count - 1,
+            ..ModuleListLimits::default()
+        },
+        ..ProcessorOptions::default()
+    };
+    let state = minidump_processor::process_minidump_with_options(
+        &dump,
+        &Symbolizer::new(SimpleSymbolSupplier::new(vec![])),
+        &options,
+    )
+    .unwrap();
+
+    // Processing carries on with the modules that fit, and the list records
+    // the rest were left out.
+    assert_eq!(state.modules.iter().count(), module_count - 1);
+    assert_eq!(state.modules.omitted_count(), 1);
+    match state.modules.validate() {
+        Err(minidump::Error::ModuleListTooLarge { modules, .. }) => {
+            assert_eq!(modules, module_count)
+        }
+        other => panic!("unexpected result: {:?}", other),
+    }
+    assert_eq!(
+        state.threads[0].frames[0]
+            .module
+            .as_ref()
+            .unwrap()
+            .code_file(),
+        "c:\\test_app.exe"
+    );
+}
+
+#[test]
+fn test_processor_crash_stack_bytes() {
+    let dump = read_test_minidump().unwrap();
+    let state = minidump_processor::process_minidump(
+        &dump,
+        &Symbolizer::new(SimpleSymbolSupplier::new(vec![])),
+    )
+    .unwrap();
+
+    // The crashing thread's stack pointer is 0x0012fe84, read the same bytes
+    // straight from the dump to compare.
+    let thread_list = dump.get_stream::<MinidumpThreadList>().unwrap();
+    let memory_list = dump.get_stream::<MinidumpMemoryList>().unwrap_or_default();
+    let stack = thread_list.threads[0]
+        .stack
+        .as_ref()
+        .or_else(|| memory_list.memory_at_address(0x0012fe84))
+        .unwrap();
+    let offset = (0x0012fe84 - stack.base_address) as usize;
+    let expected = &stack.bytes[offset..offset + 32];
+
+    let stack_memory = state.requesting_thread_stack.as_ref().unwrap();
+    assert_eq!(stack_memory.base_address, stack.base_address);
+    assert_eq!(state.crash_stack_bytes(32).as_deref(), Some(expected));
+    assert_eq!(state.crash_stack_bytes(0), Some(vec![]));
+
+    // Asking for more than was captured gives nothing.
+    let remaining = stack.bytes.len() - offset;
+    assert_eq!(
+        state.crash_stack_bytes(remaining).map(|bytes| bytes.len()),
+        Some(remaining)
+    );
+    assert_eq!(state.crash_stack_bytes(remaining + 1), None);
+}
+
+#[test]
+fn test_processor_stack_window() {
+    let dump = read_test_minidump().unwrap();
+    let state = minidump_processor::process_minidump(
+        &dump,
+        &Symbolizer::new(SimpleSymbolSupplier::new(vec![])),
+    )
+    .unwrap();
+    let stack = state.requesting_thread_stack.as_ref().unwrap();
+    let stack_end = stack.base_address + stack.bytes.len() as u64;
+    let sp = 0x0012fe84;
+    let offset = (sp - stack.base_address) as usize;
+
+    let window = state.stack_window(0, 0x20).unwrap();
+    assert_eq!(window.range, sp..sp + 0x20);
+    assert_eq!(&window.bytes[..], &stack.bytes[offset..offset + 0x20]);
+
+    // The window is clipped to the captured stack on both sides.
+    let window = state.stack_window(u64::MAX, u64::MAX).unwrap();
+    assert_eq!(window.range, stack.base_address..stack_end);
+    assert_eq!(window.bytes, stack.bytes);
+
+    // None of an empty window was captured.
+    assert_eq!(state.stack_window(0, 0), None);
+}
+
+#[test]
+fn test_processor_decimal_offsets() {
+    let dump = read_test_minidump().unwrap();
+    let state = minidump_processor::process_minidump(
+        &dump,
+        &Symbolizer::new(SimpleSymbolSupplier::new(vec![])),
+    )
+    .unwrap();
+
+    let options = JsonOptions {
+        hex_offsets: false,
+        ..JsonOptions::default()
+    };
+    let json = state_json_with_options(&state, &options);
+    assert_eq!(json["crash_info"]["address"], 0x45);
+    let frame = &json["threads"][0]["frames"][0];
+    assert_eq!(frame["offset"], 0x0040429e);
+    assert!(frame["module_offset"].is_u64());
+    let module = &json["modules"][0];
+    assert!(module["base_addr"].is_u64());
+    assert!(module["end_addr"].is_u64());
+
+    // Hex strings are still the default.
+    let json = state_json(&state);
+    assert_eq!(json["threads"][0]["frames"][0]["offset"], "0x0040429e");
+}
+
+/// Decode standard, padded base64.
+fn decode_base64(data: &str) -> Vec<u8> {
+    const ALPHABET: &[u8] = b"ABCDEFGHIJKLMNOPQRSTUVWXYZabcdefghijklmnopqrstuvwxyz0123456789+/";
+    let mut bytes = vec![];
+    for chunk in data.as_bytes().chunks(4) {
+        assert_eq!(chunk.len(), 4);
+        let padding = chunk.iter().filter(|&&c| c == b'=').count();
+        let word = chunk.iter().fold(0u32, |word, &c| {
+            let sextet = if c == b'=' {
+                0
+            } else {
+                ALPHABET.iter().position(|&a| a == c).unwrap() as u32
+            };
+            word << 6 | sextet
+        });
+        bytes.extend_from_slice(&word.to_be_bytes()[1..4 - padding]);
+    }
+    bytes
+}
+
+#[test]
+fn test_processor_json_stack_memory() {
+    let dump = read_test_minidump().unwrap();
+    let state = minidump_processor::process_minidump(
+        &dump,
+        &Symbolizer::new(SimpleSymbolSupplier::new(vec![])),
+    )
+    .unwrap();
+
+    // The stack isn't included unless it's asked for.
+    let json = state_json(&state);
+    assert!(json["crashing_thread"].get("stack_memory").is_none());
+
+    let options = JsonOptions {
+        stack_memory: true,
+        ..JsonOptions::default()
+    };
+    let json = state_json_with_options(&state, &options);
+    let stack_memory = &json["crashing_thread"]["stack_memory"];
+    assert_eq!(stack_memory["base_address"], "0x0012f31c");
+    assert_eq!(stack_memory["size"], 0x130000 - 0x12f31c);
+    let stack = state.requesting_thread_stack.as_ref().unwrap();
+    let data = stack_memory["data"].as_str().unwrap();
+    assert_eq!(data.len(), stack.bytes.len().div_ceil(3) * 4);
+    assert_eq!(decode_base64(data), stack.bytes);
+    // Other threads don't get one.
+    assert!(json["threads"][0].get("stack_memory").is_none());
+}
+
+#[test]
+fn test_processor_module_version_info() {
+    let dump = read_test_minidump().unwrap();
+    let json = process_to_json(
+        &dump,
+        &Symbolizer::new(SimpleSymbolSupplier::new(vec![])),
+        &ProcessorOptions::default(),
+    );
+
+    // test_app.exe has no version information.
+    assert_eq!(json["modules"][0]["filename"], r"c:\test_app.exe");
+    assert_eq!(json["modules"][0]["version_info"], serde_json::Value::Null);
+
+    let psapi = &json["modules"][12];
+    assert_eq!(psapi["filename"], r"C:\WINDOWS\system32\psapi.dll");
+    assert_eq!(
+        psapi["version_info"],
+        serde_json::json!({
+            "file_version": "5.1.2600.2180",
+            "product_version": "5.1.2600.2180",
+            "file_flags": "0x00000000",
+            "file_os": "0x00040004",
+            "file_type": "0x00000002",
+            "file_subtype": "0x00000000",
+        })
+    );
+}
+
+#[test]
+fn test_processor_process_info() {
+    let path = locate_testdata().join("invalid-parameter.dmp");
+    let dump = Minidump::read_path(&path).unwrap();
+    let state = minidump_processor::process_minidump(
+        &dump,
+        &Symbolizer::new(SimpleSymbolSupplier::new(vec![])),
+    )
+    .unwrap();
+    assert_eq!(
+        state.process_execute_flags,
+        Some(
+            ProcessExecuteFlags::MEM_EXECUTE_OPTION_DISABLE
+                | ProcessExecuteFlags::MEM_EXECUTE_OPTION_DISABLE_THUNK_EMULATION
+                | ProcessExecuteFlags::MEM_EXECUTE_OPTION_PERMANENT
+        )
+    );
+    assert_eq!(state.protected_process, Some(false));
+    assert_eq!(
+        state.assertion.as_deref(),
+        Some(
+            r"format != nullptr in function common_vfprintf, line 32 in file minkernel\crts\ucrt\src\appcrt\stdio\output.cpp"
+        )
+    );
+
+    let json = state_json(&state);
+    let process_info = &json["process_info"];
+    assert_eq!(process_info["execute_flags"], "0x0000000d");
+    assert_eq!(process_info["dep_enabled"], true);
+    assert_eq!(process_info["dep_permanent"], true);
+    assert_eq!(process_info["atl_thunk_emulation"], false);
+    assert_eq!(process_info["protected_process"], false);
+
+    // test.dmp only has a MINIDUMP_MISC_INFO, which doesn't have these.
+    let dump = read_test_minidump().unwrap();
+    let state = minidump_processor::process_minidump(
+        &dump,
+        &Symbolizer::new(SimpleSymbolSupplier::new(vec![])),
+    )
+    .unwrap();
+    assert_eq!(state.process_execute_flags, None);
+    assert_eq!(state.protected_process, None);
+    let json = state_json(&state);
+    assert!(json.get("process_info").is_none());
+}
+
+#[test]
+fn test_processor_corrupt_symbols() {
+    let dump = read_test_minidump().unwrap();
+    let path = locate_testdata().join("corrupt-symbols");
+    let state = minidump_processor::process_minidump(
+        &dump,
+        &Symbolizer::new(SimpleSymbolSupplier::new(vec![path])),
+    )
+    .unwrap();
+    assert_eq!(state.threads[0].frames[0].function_name, None);
+
+    let main_module = state.modules.main_module().unwrap();
+    assert_eq!(main_module.code_file(), "c:\\test_app.exe");
+    assert_eq!(
+        state.module_symbol_status(main_module),
+        Some(SymbolStatus::Corrupt)
+    );
+    // The detail points at the first line that couldn't be parsed.
+    let error = state.module_symbol_parse_error(main_module).unwrap();
+    assert_eq!(error.line, 4);
+    let kernel32 = state.modules.module_at_address(0x7c816fd7).unwrap();
+    assert_eq!(
+        state.module_symbol_status(kernel32),
+        Some(SymbolStatus::Missing)
+    );
+    assert_eq!(state.module_symbol_parse_error(kernel32), None);
+
+    let corrupt = state.modules_with_corrupt_symbols();
+    assert_eq!(corrupt.len(), 1);
+    assert!(std::ptr::eq(corrupt[0].0, main_module));
+    assert_eq!(corrupt[0].1, Some(error));
+
+    let json = state_json(&state);
+    let main_module = &json["modules"][json["main_module"].as_u64().unwrap() as usize];
+    assert_eq!(main_module["corrupt_symbols"], true);
+    assert_eq!(main_module["missing_symbols"], serde_json::Value::Null);
+    assert_eq!(main_module["loaded_symbols"], serde_json::Value::Null);
+    assert_eq!(main_module["symbol_parse_error"]["line"], 4);
+    assert_eq!(main_module["symbol_parse_error"]["reason"], error.reason);
+}
+
+#[test]
+fn test_processor_module_symbols() {
+    let dump = read_test_minidump().unwrap();
+    // Symbols found in the download cache say so, nothing is downloaded.
+    let supplier = HttpSymbolSupplier::new(vec![], testdata_symbol_path(), vec![]);
+    let state = minidump_processor::process_minidump(&dump, &Symbolizer::new(supplier)).unwrap();
+
+    let main_module = state.modules.main_module().unwrap();
+    assert_eq!(
+        state.module_symbols(main_module),
+        Some(&ModuleSymbolStatus::Loaded {
+            from_cache: true,
+            url: None,
+            fetch_ms: None,
+        })
+    );
+    assert_eq!(
+        state.module_symbol_status(main_module),
+        Some(SymbolStatus::Loaded)
+    );
+    let kernel32 = state.modules.module_at_address(0x7c816fd7).unwrap();
+    assert_eq!(
+        state.module_symbols(kernel32),
+        Some(&ModuleSymbolStatus::Missing)
+    );
+    assert_eq!(
+        ModuleSymbolStatus::Missing.problem().as_deref(),
+        Some("No symbols")
+    );
+
+    // The report and the JSON agree on every module's symbols.
+    let lines = state.report_lines().collect::<Vec<_>>();
+    let json = state_json(&state);
+    for (module, module_json) in state
+        .modules
+        .iter()
+        .zip(json["modules"].as_array().unwrap())
+    {
+        let warned = lines.iter().any(|line| {
+            line.starts_with(&format!("{:#010x} - ", module.base_address()))
+                && line.contains("WARNING: No symbols")
+        });
+        assert_eq!(warned, module_json["missing_symbols"] == true);
+        match state.module_symbols(module) {
+            Some(ModuleSymbolStatus::Loaded { .. }) => {
+                assert_eq!(module_json["loaded_symbols"], true);
+                assert_eq!(module_json["symbol_disk_cache_hit"], true);
+                assert_eq!(module_json["symbol_url"], serde_json::Value::Null);
+                assert_eq!(module_json["symbols_fetch_time"], serde_json::Value::Null);
+            }
+            Some(ModuleSymbolStatus::Missing) => assert!(warned),
+            Some(ModuleSymbolStatus::Corrupt { .. }) => unreachable!(),
+            None => {
+                assert!(!warned);
+                assert_eq!(module_json["loaded_symbols"], serde_json::Value::Null);
+            }
+        }
+    }
+}
+
+fn memory_info(
+    base_address: u64,
+    region_size: u64,
+    state: u32,
+    protection: u32,
+) -> MinidumpMemoryInfo {
+    MinidumpMemoryInfo {
+        raw: format::MINIDUMP_MEMORY_INFO {
+            base_address,
+            allocation_base: base_address,
+            allocation_protection: protection,
+            __alignment1: 0,
+            region_size,
+            state,
+            protection,
+            _type: 0,
+            __alignment2: 0,
+        },
+    }
+}
+
+#[test]
+fn test_processor_virtual_memory_stats() {
+    use format::{MemoryProtection, MemoryState};
+    let free = MemoryState::MEM_FREE.bits();
+    let commit = MemoryState::MEM_COMMIT.bits();
+    let noaccess = MemoryProtection::PAGE_NOACCESS.bits();
+    let readwrite = MemoryProtection::PAGE_READWRITE.bits();
+    let write_combine =
+        (MemoryProtection::PAGE_READWRITE | MemoryProtection::PAGE_WRITECOMBINE).bits();
+    let memory_info = MinidumpMemoryInfoList::from_regions(vec![
+        memory_info(0x10000, 0x1000, commit, readwrite),
+        memory_info(0x11000, 0xf000, free, noaccess),
+        memory_info(0x20000, 0x20000, commit, write_combine),
+        memory_info(0x40000, 0x4000, commit, write_combine),
+        memory_info(0x44000, 0xc000, free, noaccess),
+        memory_info(0x50000, 0x7ffb0000, free, noaccess),
+    ]);
+    let stats = VirtualMemoryStats::from_memory_info(&memory_info);
+    assert_eq!(
+        stats,
+        VirtualMemoryStats {
+            largest_free_block: 0x7ffb0000,
+            tiny_block_size: 0xf000 + 0xc000,
+            write_combine_size: 0x24000,
+        }
+    );
+
+    // The test dump has no memory info, so the statistics are left out.
+    let dump = read_test_minidump().unwrap();
+    let mut state = minidump_processor::process_minidump(
+        &dump,
+        &Symbolizer::new(SimpleSymbolSupplier::new(vec![])),
+    )
+    .unwrap();
+    assert_eq!(state.virtual_memory, None);
+    let json = state_json(&state);
+    assert!(json.get("largest_free_vm_block").is_none());
+    assert!(json.get("tiny_block_size").is_none());
+    assert!(json.get("write_combine_size").is_none());
+
+    state.virtual_memory = Some(stats);
+    let json = state_json(&state);
+    assert_eq!(json["largest_free_vm_block"], "0x7ffb0000");
+    assert_eq!(json["tiny_block_size"], 0x1b000);
+    assert_eq!(json["write_combine_size"], 0x24000);
+}
+
+#[test]
+fn test_processor_is_executable_address() {
+    use format::{MemoryProtection, MemoryState};
+    let dump = read_test_minidump().unwrap();
+    let mut state = minidump_processor::process_minidump(
+        &dump,
+        &Symbolizer::new(SimpleSymbolSupplier::new(vec![])),
+    )
+    .unwrap();
+    // Without memory info, only module ranges say anything.
+    assert!(state.memory_info.is_none());
+    assert_eq!(state.is_executable_address(0x40429e), Some(true));
+    assert_eq!(state.is_executable_address(0x10), None);
+
+    let commit = MemoryState::MEM_COMMIT.bits();
+    let execute_read = MemoryProtection::PAGE_EXECUTE_READ.bits();
+    let readwrite = MemoryProtection::PAGE_READWRITE.bits();
+    state.memory_info = Some(MinidumpMemoryInfoList::from_regions(vec![
+        memory_info(0x10000, 0x1000, commit, readwrite),
+        memory_info(0x404000, 0x1000, commit, execute_read),
+        memory_info(0x405000, 0x1000, commit, readwrite),
+    ]));
+    // Heap memory isn't executable.
+    assert_eq!(state.is_executable_address(0x10010), Some(false));
+    assert_eq!(state.is_executable_address(0x40429e), Some(true));
+    // The protection wins over the module's range.
+    assert_eq!(state.is_executable_address(0x405010), Some(false));
+    // Addresses outside the described regions fall back to the modules.
+    assert_eq!(state.is_executable_address(0x401000), Some(true));
+    assert_eq!(state.is_executable_address(0x20000), None);
+}
+
+#[test]
+fn test_processor_stack_overflow() {
+    use format::{ExceptionCodeWindows, MemoryProtection, MemoryState};
+    fn stack_overflow_json(state: &ProcessState) -> serde_json::Value {
+        state_json(state)["crash_info"]["stack_overflow"].clone()
+    }
+    fn is_overflow_in_report(state: &ProcessState) -> bool {
+        state
+            .report_lines()
+            .any(|line| line.starts_with("Stack overflow:"))
+    }
+
+    let dump = read_test_minidump().unwrap();
+    let mut state = minidump_processor::process_minidump(
+        &dump,
+        &Symbolizer::new(SimpleSymbolSupplier::new(vec![])),
+    )
+    .unwrap();
+    // A write to 0x45 is a null pointer, nowhere near the stack at 0x12fe84.
+    assert!(!state.is_stack_overflow());
+    assert_eq!(stack_overflow_json(&state), false);
+    assert!(!is_overflow_in_report(&state));
+    assert_eq!(
+        state.crash_signature().as_deref(),
+        Some("test_app.exe@0x429e | test_app.exe@0x41ff | test_app.exe@0x53eb")
+    );
+
+    // Without memory info, a fault just below the stack pointer is an overflow.
+    let overflow = |address| {
+        Some(ExceptionDetails::AccessViolation {
+            kind: AccessKind::Write,
+            address,
+        })
+    };
+    state.exception_details = overflow(0x12fe80);
+    assert!(state.is_stack_overflow());
+    assert_eq!(
+        state.crash_signature().as_deref(),
+        Some("stack overflow | test_app.exe@0x429e | test_app.exe@0x41ff | test_app.exe@0x53eb")
+    );
+    // The stack doesn't grow up.
+    state.exception_details = overflow(0x12fe88);
+    assert!(!state.is_stack_overflow());
+
+    // With it, the fault must be in the guard page or the reserved part of the
+    // stack's allocation.
+    let reserve = MemoryState::MEM_RESERVE.bits();
+    let commit = MemoryState::MEM_COMMIT.bits();
+    let readwrite = MemoryProtection::PAGE_READWRITE.bits();
+    let guard = readwrite | MemoryProtection::PAGE_GUARD.bits();
+    let mut regions = vec![
+        memory_info(0x30000, 0xfd000, reserve, 0),
+        memory_info(0x12d000, 0x1000, commit, guard),
+        memory_info(0x12e000, 0x2000, commit, readwrite),
+        memory_info(0x130000, 0x1000, commit, readwrite),
+    ];
+    for region in &mut regions[..3] {
+        region.raw.allocation_base = 0x30000;
+    }
+    state.memory_info = Some(MinidumpMemoryInfoList::from_regions(regions));
+    state.exception_details = overflow(0x12dff8);
+    state.crash_address = Some(0x12dff8);
+    assert!(state.is_stack_overflow());
+    assert_eq!(stack_overflow_json(&state), true);
+    assert!(is_overflow_in_report(&state));
+    state.exception_details = overflow(0x12cff8);
+    assert!(state.is_stack_overflow());
+    // Committed stack and other allocations aren't.
+    state.exception_details = overflow(0x12fe80);
+    assert!(!state.is_stack_overflow());
+    state.exception_details = overflow(0x130010);
+    assert!(!state.is_stack_overflow());
+
+    // Windows says so itself.
+    state.exception_details = None;
+    state.crash_reason = Some(CrashReason::Windows(
+        ExceptionCodeWindows::EXCEPTION_STACK_OVERFLOW,
+    ));
+    assert!(state.is_stack_overflow());
+}
+
+#[test]
+fn test_processor_implausible_times() {
+    use scroll::{Pwrite, LE};
+
+    fn process_with_time(time_date_stamp: u32, options: &ProcessorOptions) -> ProcessState {
+        let mut bytes = std::fs::read(locate_testdata().join("test.dmp")).unwrap();
+        bytes.pwrite_with(time_date_stamp, 20, LE).unwrap();
+        let dump = Minidump::read(bytes).unwrap();
+        minidump_processor::process_minidump_with_options(
+            &dump,
+            &Symbolizer::new(SimpleSymbolSupplier::new(vec![])),
+            options,
+        )
+        .unwrap()
+    }
+    fn uptime_line(state: &ProcessState) -> String {
+        state
+            .report_lines()
+            .find(|line| line.starts_with("Process uptime:"))
+            .unwrap()
+    }
+
+    let options = ProcessorOptions::default();
+    let dump = read_test_minidump().unwrap();
+    let state = minidump_processor::process_minidump(
+        &dump,
+        &Symbolizer::new(SimpleSymbolSupplier::new(vec![])),
+    )
+    .unwrap();
+    let time = state.time.unwrap().timestamp() as u32;
+    let create_time = state.process_create_time.unwrap().timestamp() as u32;
+    assert_eq!(state.raw_time, time);
+    assert_eq!(state.raw_process_create_time, Some(create_time));
+    assert!(state.process_uptime().is_some());
+    assert!(!state.has_clock_skew());
+
+    // A clock that was never set.
+    let state = process_with_time(0, &options);
+    assert_eq!(state.time, None);
+    assert_eq!(state.raw_time, 0);
+    assert_eq!(state.process_uptime(), None);
+    assert_eq!(uptime_line(&state), "Process uptime: not available");
+
+    // A time far in the future, which is only kept if the caller allows it.
+    let state = process_with_time(0xffff_ffff, &options);
+    assert_eq!(state.time, None);
+    assert_eq!(state.raw_time, 0xffff_ffff);
+    assert_eq!(uptime_line(&state), "Process uptime: not available");
+    let far_future = ProcessorOptions {
+        latest_plausible_time: Some(Utc.timestamp_opt(0xffff_ffff, 0).unwrap()),
+        ..ProcessorOptions::default()
+    };
+    let state = process_with_time(0xffff_ffff, &far_future);
+    assert_eq!(state.time.unwrap().timestamp(), 0xffff_ffff);
+
+    // A dump written before the process started.
+    let state = process_with_time(create_time - 100, &options);
+    assert_eq!(state.process_uptime(), None);
+    assert!(state.has_clock_skew());
+    assert_eq!(
+        uptime_line(&state),
+        "Process uptime: not available (clock skew)"
+    );
+
+    let state = process_with_time(time, &options);
+    assert_eq!(
+        uptime_line(&state),
+        format!("Process uptime: {} seconds", time - create_time)
+    );
+}
+
+#[test]
+fn test_processor_corrupt_streams() {
+    use format::MINIDUMP_STREAM_TYPE;
+    use scroll::{Pread, Pwrite, LE};
+
+    // The test dump's streams are all fine.
+    let dump = read_test_minidump().unwrap();
+    let state = minidump_processor::process_minidump(
+        &dump,
+        &Symbolizer::new(SimpleSymbolSupplier::new(vec![])),
+    )
+    .unwrap();
+    assert!(state.corrupt_streams.is_empty());
+    assert!(state.process_id.is_some());
+
+    // Truncate its misc info stream to a single byte.
+    let mut bytes = std::fs::read(locate_testdata().join("test.dmp")).unwrap();
+    let stream_count: u32 = bytes.pread_with(8, LE).unwrap();
+    let directory: u32 = bytes.pread_with(12, LE).unwrap();
+    let entry = (0..stream_count as usize)
+        .map(|i| directory as usize + i * 12)
+        .find(|&entry| {
+            bytes.pread_with::<u32>(entry, LE).unwrap()
+                == MINIDUMP_STREAM_TYPE::MiscInfoStream as u32
+        })
+        .unwrap();
+    bytes.pwrite_with(1u32, entry + 4, LE).unwrap();
+
+    let dump = Minidump::read(bytes).unwrap();
+    let state = minidump_processor::process_minidump(
+        &dump,
+        &Symbolizer::new(SimpleSymbolSupplier::new(vec![])),
+    )
+    .unwrap();
+    assert_eq!(state.corrupt_streams.len(), 1);
+    assert_eq!(
+        state.corrupt_streams[0].0,
+        MINIDUMP_STREAM_TYPE::MiscInfoStream
+    );
+    match state.corrupt_streams[0].1 {
+        StreamError::StreamReadFailure(_) => {}
+        ref e => panic!("Unexpected stream error {:?}", e),
+    }
+    // The rest of the dump is still processed.
+    assert_eq!(state.process_id, None);
+    assert_eq!(state.threads.len(), 2);
+
+    let json = state_json(&state);
+    assert_eq!(json["corrupt_streams"][0]["stream_type"], "MiscInfoStream");
+}
+
+#[test]
+fn test_processor_extra_metadata() {
+    let dump = read_test_minidump().unwrap();
+    let state = minidump_processor::process_minidump(
+        &dump,
+        &Symbolizer::new(SimpleSymbolSupplier::new(vec![])),
+    )
+    .unwrap();
+    assert_eq!(state.annotations, None);
+    let json = state_json(&state);
+    assert!(json.get("annotations").is_none());
+
+    let state = state
+        .with_extra_metadata(serde_json::json!({
+            "ProductName": "TestApp",
+            "Version": "1.0",
+        }))
+        .with_extra_metadata(serde_json::json!({
+            "Version": "1.1",
+            "BuildID": "20200101000000",
+        }));
+    let json = state_json(&state);
+    assert_eq!(
+        json["annotations"],
+        serde_json::json!({
+            "ProductName": "TestApp",
+            "Version": "1.1",
+            "BuildID": "20200101000000",
+        })
+    );
+    // The dump analysis is still there.
+    assert_eq!(json["thread_count"], 2);
+}
+
+#[test]
+fn test_processor_module_name_at_address() {
+    let dump = read_test_minidump().unwrap();
+    let state = minidump_processor::process_minidump(
+        &dump,
+        &Symbolizer::new(SimpleSymbolSupplier::new(vec![])),
+    )
+    .unwrap();
+    assert_eq!(
+        state.module_name_at_address(0x0040429e),
+        Some("c:\\test_app.exe")
+    );
+    assert_eq!(
+        state.module_name_at_address(0x7c816fd7),
+        Some("C:\\WINDOWS\\system32\\kernel32.dll")
+    );
+    assert_eq!(state.module_name_at_address(0x45), None);
+}
+
+/// Find the offset of the directory entry for `stream_type` in the minidump `bytes`.
+fn stream_directory_entry(bytes: &[u8], stream_type: MINIDUMP_STREAM_TYPE) -> usize {
+    use scroll::{Pread, LE};
+    let stream_count: u32 = bytes.pread_with(8, LE).unwrap();
+    let directory: u32 = bytes.pread_with(12, LE).unwrap();
+    (0..stream_count as usize)
+        .map(|i| directory as usize + i * 12)
+        .find(|&entry| bytes.pread_with::<u32>(entry, LE).unwrap() == stream_type as u32)
+        .unwrap()
+}
+
+#[test]
+fn test_processor_thread_state() {
+    use scroll::{Pread, Pwrite, LE};
+    let mut bytes = std::fs::read(locate_testdata().join("test.dmp")).unwrap();
+    // Breakpad leaves the scheduling state zeroed, so fill in the first thread's.
+    let entry = stream_directory_entry(&bytes, MINIDUMP_STREAM_TYPE::ThreadListStream);
+    let thread_list: u32 = bytes.pread_with(entry + 8, LE).unwrap();
+    let thread = thread_list as usize + 4;
+    bytes.pwrite_with(1u32, thread + 4, LE).unwrap();
+    bytes.pwrite_with(0x20u32, thread + 8, LE).unwrap();
+    bytes.pwrite_with(-2i32, thread + 12, LE).unwrap();
+    let dump = Minidump::read(bytes).unwrap();
+    let state = minidump_processor::process_minidump(
+        &dump,
+        &Symbolizer::new(SimpleSymbolSupplier::new(vec![])),
+    )
+    .unwrap();
+    let thread = &state.threads[0];
+    assert_eq!(thread.suspend_count, Some(1));
+    assert_eq!(thread.priority_class, Some(0x20));
+    assert_eq!(thread.priority, Some(-2));
+    assert_eq!(thread.teb, Some(0x7ffdf000));
+    assert_eq!(state.threads[1].suspend_count, Some(0));
+    assert_eq!(state.threads[1].teb, Some(0x7ffde000));
+
+    let json = state_json(&state);
+    let thread = &json["threads"][0];
+    assert_eq!(thread["suspend_count"], 1);
+    assert_eq!(thread["priority_class"], 0x20);
+    assert_eq!(thread["priority"], -2);
+    assert_eq!(thread["teb"], "0x7ffdf000");
+}
+
+#[test]
+fn test_processor_crash_instruction_bytes() {
+    use scroll::{Pread, Pwrite, LE};
+    let dump = read_test_minidump().unwrap();
+    let state = minidump_processor::process_minidump(
+        &dump,
+        &Symbolizer::new(SimpleSymbolSupplier::new(vec![])),
+    )
+    .unwrap();
+    // test.dmp doesn't include module memory.
+    assert_eq!(state.crash_instruction_memory, None);
+    assert_eq!(state.crash_instruction_bytes(), None);
+
+    // Point the crash at the stack, which was captured.
+    let mut bytes = std::fs::read(locate_testdata().join("test.dmp")).unwrap();
+    let entry = stream_directory_entry(&bytes, MINIDUMP_STREAM_TYPE::ExceptionStream);
+    let exception: u32 = bytes.pread_with(entry + 8, LE).unwrap();
+    let context: u32 = bytes.pread_with(exception as usize + 164, LE).unwrap();
+    let eip = context as usize + 0xb8;
+    assert_eq!(bytes.pread_with::<u32>(eip, LE).unwrap(), 0x40429e);
+    bytes.pwrite_with(0x12fe90u32, eip, LE).unwrap();
+    let dump = Minidump::read(bytes).unwrap();
+    let state = minidump_processor::process_minidump(
+        &dump,
+        &Symbolizer::new(SimpleSymbolSupplier::new(vec![])),
+    )
+    .unwrap();
+    let memory = state.crash_instruction_memory.as_ref().unwrap();
+    assert_eq!(memory.range, 0x12fe80..0x12feb0);
+    let stack = state.requesting_thread_stack.as_ref().unwrap();
+    let offset = (0x12fe90 - stack.base_address) as usize;
+    assert_eq!(
+        state.crash_instruction_bytes().unwrap(),
+        &stack.bytes[offset..offset + 32]
+    );
+}
+
+#[cfg(feature = "capstone")]
+#[test]
+fn test_processor_crash_instruction_mnemonic() {
+    use scroll::{Pread, Pwrite, LE};
+    let dump = read_test_minidump().unwrap();
+    let state = minidump_processor::process_minidump(
+        &dump,
+        &Symbolizer::new(SimpleSymbolSupplier::new(vec![])),
+    )
+    .unwrap();
+    // test.dmp doesn't include module memory.
+    assert_eq!(state.crash_instruction_mnemonic(), None);
+
+    // Point the crash at the stack, which was captured, and put a
+    // `mov dword ptr [eax], 0` there.
+    let mut bytes = std::fs::read(locate_testdata().join("test.dmp")).unwrap();
+    let entry = stream_directory_entry(&bytes, MINIDUMP_STREAM_TYPE::ExceptionStream);
+    let exception: u32 = bytes.pread_with(entry + 8, LE).unwrap();
+    let context: u32 = bytes.pread_with(exception as usize + 164, LE).unwrap();
+    bytes
+        .pwrite_with(0x12fe90u32, context as usize + 0xb8, LE)
+        .unwrap();
+    let entry = stream_directory_entry(&bytes, MINIDUMP_STREAM_TYPE::MemoryListStream);
+    let memory_list: u32 = bytes.pread_with(entry + 8, LE).unwrap();
+    let count: u32 = bytes.pread_with(memory_list as usize, LE).unwrap();
+    let code = (0..count as usize)
+        .find_map(|i| {
+            let descriptor = memory_list as usize + 4 + i * 16;
+            let start: u64 = bytes.pread_with(descriptor, LE).unwrap();
+            let size: u32 = bytes.pread_with(descriptor + 8, LE).unwrap();
+            let rva: u32 = bytes.pread_with(descriptor + 12, LE).unwrap();
+            if start <= 0x12fe90 && 0x12fe90 < start + u64::from(size) {
+                Some(rva as usize + (0x12fe90 - start) as usize)
+            } else {
+                None
+            }
+        })
+        .unwrap();
+    bytes[code..code + 6].copy_from_slice(&[0xc7, 0x00, 0x00, 0x00, 0x00, 0x00]);
+
+    let dump = Minidump::read(bytes).unwrap();
+    let state = minidump_processor::process_minidump(
+        &dump,
+        &Symbolizer::new(SimpleSymbolSupplier::new(vec![])),
+    )
+    .unwrap();
+    assert_eq!(state.crash_instruction_mnemonic().as_deref(), Some("mov"));
+}
+
+#[test]
+fn test_processor_wow64_hex_width() {
+    // Claim the system is amd64, as a 32-bit process on 64-bit Windows would.
+    use scroll::{Pread, Pwrite, LE};
+    let mut bytes = std::fs::read(locate_testdata().join("test.dmp")).unwrap();
+    let entry = stream_directory_entry(&bytes, MINIDUMP_STREAM_TYPE::SystemInfoStream);
+    let system_info: u32 = bytes.pread_with(entry + 8, LE).unwrap();
+    bytes
+        .pwrite_with(
+            ProcessorArchitecture::PROCESSOR_ARCHITECTURE_AMD64 as u16,
+            system_info as usize,
+            LE,
+        )
+        .unwrap();
+
+    let dump = Minidump::read(bytes).unwrap();
+    let state = minidump_processor::process_minidump(
+        &dump,
+        &Symbolizer::new(SimpleSymbolSupplier::new(vec![])),
+    )
+    .unwrap();
+    assert_eq!(state.system_info.cpu, Cpu::X86_64);
+
+    // The contexts are still x86, so addresses are 32-bit.
+    let json = state_json(&state);
+    assert_eq!(json["crash_info"]["address"], "0x00000045");
+    assert_eq!(json["threads"][0]["frames"][0]["offset"], "0x0040429e");
+    let main_module = &json["modules"][json["main_module"].as_u64().unwrap() as usize];
+    assert_eq!(main_module["base_addr"], "0x00400000");
+}
+
+#[test]
+fn test_processor_recover_module_identifiers() {
+    // Clear the last module's timestamp and move it to the start of a
+    // captured memory region, where its PE header is put.
+    use scroll::{Pread, Pwrite, LE};
+    let mut bytes = std::fs::read(locate_testdata().join("test.dmp")).unwrap();
+    let entry = stream_directory_entry(&bytes, MINIDUMP_STREAM_TYPE::MemoryListStream);
+    let memory_list: u32 = bytes.pread_with(entry + 8, LE).unwrap();
+    let count: u32 = bytes.pread_with(memory_list as usize, LE).unwrap();
+    let descriptor = memory_list as usize + 4 + (count as usize - 1) * 16;
+    let base: u64 = bytes.pread_with(descriptor, LE).unwrap();
+    let rva: u32 = bytes.pread_with(descriptor + 12, LE).unwrap();
+    let header = rva as usize;
+    bytes[header..header + 0x100].copy_from_slice(&[0; 0x100]);
+    bytes[header..header + 2].copy_from_slice(b"MZ");
+    bytes.pwrite_with(0x40u32, header + 0x3c, LE).unwrap();
+    bytes[header + 0x40..header + 0x44].copy_from_slice(b"PE\0\0");
+    bytes.pwrite_with(0x5e1f00du32, header + 0x48, LE).unwrap();
+    bytes.pwrite_with(0x10bu16, header + 0x58, LE).unwrap();
+
+    let entry = stream_directory_entry(&bytes, MINIDUMP_STREAM_TYPE::ModuleListStream);
+    let module_list: u32 = bytes.pread_with(entry + 8, LE).unwrap();
+    let count: u32 = bytes.pread_with(module_list as usize, LE).unwrap();
+    let module = module_list as usize + 4 + (count as usize - 1) * 108;
+    bytes.pwrite_with(base, module, LE).unwrap();
+    bytes.pwrite_with(0x1000u32, module + 8, LE).unwrap();
+    bytes.pwrite_with(0u32, module + 16, LE).unwrap();
+    let dump = Minidump::read(bytes).unwrap();
+
+    let symbolizer = Symbolizer::new(SimpleSymbolSupplier::new(vec![]));
+    let code_identifier = |options: &ProcessorOptions| {
+        let state =
+            minidump_processor::process_minidump_with_options(&dump, &symbolizer, options).unwrap();
+        state
+            .modules
+            .module_at_address(base)
+            .unwrap()
+            .code_identifier()
+            .into_owned()
+    };
+
+    // The header is only read when it's asked for.
+    assert_eq!(
+        code_identifier(&ProcessorOptions::default()),
+        "000000001000"
+    );
+    let options = ProcessorOptions {
+        recover_module_identifiers: true,
+        ..ProcessorOptions::default()
+    };
+    assert_eq!(code_identifier(&options), "05E1F00D1000");
+}
+
+#[test]
+fn test_processor_other_exceptions() {
+    // Add an exception stream for another thread ahead of the crash's own.
+    use scroll::{Pread, Pwrite, LE};
+    let mut bytes = std::fs::read(locate_testdata().join("test.dmp")).unwrap();
+    let entry = stream_directory_entry(&bytes, MINIDUMP_STREAM_TYPE::ExceptionStream);
+    let size: u32 = bytes.pread_with(entry + 4, LE).unwrap();
+    let exception: u32 = bytes.pread_with(entry + 8, LE).unwrap();
+    let unused = stream_directory_entry(&bytes, MINIDUMP_STREAM_TYPE::UnusedStream);
+    bytes
+        .pwrite_with(MINIDUMP_STREAM_TYPE::ExceptionStream as u32, unused, LE)
+        .unwrap();
+    bytes.pwrite_with(size, unused + 4, LE).unwrap();
+    bytes.pwrite_with(exception, unused + 8, LE).unwrap();
+    let mut other = bytes[exception as usize..(exception + size) as usize].to_vec();
+    other.pwrite_with(0x1234u32, 0, LE).unwrap();
+    other.pwrite_with(0xc00000fdu32, 8, LE).unwrap();
+    other.pwrite_with(0x1000u64, 24, LE).unwrap();
+    bytes
+        .pwrite_with(bytes.len() as u32, entry + 8, LE)
+        .unwrap();
+    bytes.extend_from_slice(&other);
+    let dump = Minidump::read(bytes).unwrap();
+
+    let state = minidump_processor::process_minidump(
+        &dump,
+        &Symbolizer::new(SimpleSymbolSupplier::new(vec![])),
+    )
+    .unwrap();
+    // The crash is still described by the last exception stream.
+    assert_eq!(
+        state.crash_reason,
+        Some(CrashReason::WindowsAccessViolation(AccessKind::Write))
+    );
+    assert_eq!(state.crash_address, Some(0x45));
+    assert_eq!(state.other_exceptions.len(), 1);
+    let (thread_id, ref record) = state.other_exceptions[0];
+    assert_eq!(thread_id, 0x1234);
+    assert_eq!(record.exception_code, 0xc00000fd);
+
+    let report = state.report_lines().collect::<Vec<_>>();
+    assert!(report.contains(
+        &"Other exception: EXCEPTION_STACK_OVERFLOW at 0x1000 in thread 0x1234".to_owned()
+    ));
+    let json = state_json(&state);
+    let other = &json["crash_info"]["other_exceptions"];
+    assert_eq!(other.as_array().unwrap().len(), 1);
+    assert_eq!(other[0]["thread_id"], 0x1234);
+    assert_eq!(other[0]["type"], "EXCEPTION_STACK_OVERFLOW");
+    assert_eq!(other[0]["address"], "0x00001000");
+}
+
+#[test]
+fn test_processor_environment() {
+    // Give the test dump an environment in place of one of its unused streams.
+    use scroll::{Pwrite, LE};
+    let mut bytes = std::fs::read(locate_testdata().join("test.dmp")).unwrap();
+    let environ = b"HOME=/home/user\0LD_LIBRARY_PATH=/opt/app/lib\0API_TOKEN=hunter2\0LANG\0";
+    let entry = stream_directory_entry(&bytes, MINIDUMP_STREAM_TYPE::UnusedStream);
+    let (size, offset) = (environ.len() as u32, bytes.len() as u32);
+    bytes
+        .pwrite_with(MINIDUMP_STREAM_TYPE::LinuxEnviron as u32, entry, LE)
+        .unwrap();
+    bytes.pwrite_with(size, entry + 4, LE).unwrap();
+    bytes.pwrite_with(offset, entry + 8, LE).unwrap();
+    bytes.extend_from_slice(environ);
+    let dump = Minidump::read(bytes).unwrap();
+    assert_eq!(
+        dump.get_stream::<MinidumpLinuxEnviron>()
+            .unwrap()
+            .get("API_TOKEN"),
+        Some("hunter2")
+    );
+
+    // None of it is kept unless it's asked for.
+    let symbolizer = Symbolizer::new(SimpleSymbolSupplier::new(vec![]));
+    let state = minidump_processor::process_minidump(&dump, &symbolizer).unwrap();
+    assert_eq!(state.environment, None);
+    let json = state_json(&state);
+    assert!(json.get("environment").is_none());
+
+    let options = ProcessorOptions {
+        environment: Some(SharedEnvironmentFilter::new(|name: &str, value: &str| {
+            if name.ends_with("_TOKEN") {
+                Some("<redacted>".to_owned())
+            } else {
+                RuntimeVariables.filter_var(name, value)
+            }
+        })),
+        ..ProcessorOptions::default()
+    };
+    let state =
+        minidump_processor::process_minidump_with_options(&dump, &symbolizer, &options).unwrap();
+    assert_eq!(
+        state.environment,
+        Some(vec![
+            ("LD_LIBRARY_PATH".to_owned(), "/opt/app/lib".to_owned()),
+            ("API_TOKEN".to_owned(), "<redacted>".to_owned()),
+            ("LANG".to_owned(), "".to_owned()),
+        ])
+    );
+
+    let report = state.report_lines().collect::<Vec<_>>();
+    assert!(report.contains(&"Environment: LD_LIBRARY_PATH=/opt/app/lib".to_owned()));
+    assert!(report.contains(&"             API_TOKEN=<redacted>".to_owned()));
+    assert!(!report.iter().any(|line| line.contains("hunter2")));
+
+    let json = state_json(&state);
+    assert_eq!(
+        json["environment"][1],
+        serde_json::json!({ "name": "API_TOKEN", "value": "<redacted>" })
+    );
+    assert_eq!(json["environment"].as_array().unwrap().len(), 3);
+}
+
+#[test]
+fn test_processor_exception_context() {
+    let dump = read_test_minidump().unwrap();
+    let state = minidump_processor::process_minidump(
+        &dump,
+        &Symbolizer::new(SimpleSymbolSupplier::new(vec![])),
+    )
+    .unwrap();
+    // The crashing thread's own context is in the exception handler, the
+    // exception record's context is where it crashed.
+    let thread_list = dump.get_stream::<MinidumpThreadList>().unwrap();
+    let thread_context = thread_list.threads[0].context.as_ref().unwrap();
+    assert_eq!(thread_context.get_instruction_pointer(), 0x7c90eb94);
+    let exception_context = state.exception_context.as_ref().unwrap();
+    assert_eq!(exception_context.get_instruction_pointer(), 0x0040429e);
+    assert_eq!(state.requesting_thread, Some(0));
+    let f0 = &state.threads[0].frames[0];
+    assert_eq!(f0.trust, FrameTrust::Context);
+    assert_eq!(f0.context.get_instruction_pointer(), 0x0040429e);
+    // The crashing thread keeps both, other threads were walked from their own.
+    let crashing = &state.threads[0];
+    assert_eq!(
+        crashing
+            .exception_context
+            .as_ref()
+            .unwrap()
+            .get_instruction_pointer(),
+        0x0040429e
+    );
+    assert_eq!(
+        crashing
+            .thread_context
+            .as_ref()
+            .unwrap()
+            .get_instruction_pointer(),
+        0x7c90eb94
+    );
+    assert!(state.threads[1].exception_context.is_none());
+    assert!(state.threads[1].thread_context.is_none());
+
+    let json = state_json(&state);
+    let crashing_thread = &json["crashing_thread"];
+    assert_eq!(
+        crashing_thread["frames"][0]["registers"]["eip"],
+        "0x0040429e"
+    );
+    assert_eq!(crashing_thread["thread_registers"]["eip"], "0x7c90eb94");
+    assert!(json["threads"][0].get("thread_registers").is_none());
+
+    // Point the exception record at a thread that isn't in the thread list.
+    use scroll::{Pread, Pwrite, LE};
+    let mut bytes = std::fs::read(locate_testdata().join("test.dmp")).unwrap();
+    let entry = stream_directory_entry(&bytes, MINIDUMP_STREAM_TYPE::ExceptionStream);
+    let exception_rva: u32 = bytes.pread_with(entry + 8, LE).unwrap();
+    bytes
+        .pwrite_with(0x9999u32, exception_rva as usize, LE)
+        .unwrap();
+
+    // The breakpad info's requesting thread gets the crash instead.
+    let dump = Minidump::read(bytes).unwrap();
+    let state = minidump_processor::process_minidump(
+        &dump,
+        &Symbolizer::new(SimpleSymbolSupplier::new(vec![])),
+    )
+    .unwrap();
+    assert_eq!(state.requesting_thread, Some(0));
+    let f0 = &state.threads[0].frames[0];
+    assert_eq!(f0.context.get_instruction_pointer(), 0x0040429e);
+}
+
+#[test]
+fn test_processor_full_registers() {
+    let dump = read_test_minidump().unwrap();
+    let state = minidump_processor::process_minidump(
+        &dump,
+        &Symbolizer::new(SimpleSymbolSupplier::new(vec![])),
+    )
+    .unwrap();
+
+    // By default only the crashing thread's first frame has registers.
+    let json = state_json(&state);
+    let frames = &json["threads"][0]["frames"];
+    assert!(frames[0].get("registers").is_none());
+    assert!(frames[3].get("registers").is_none());
+    let crashing_frame = &json["crashing_thread"]["frames"][0];
+    assert!(crashing_frame.get("registers").is_some());
+
+    let options = JsonOptions {
+        full_registers: true,
+        ..JsonOptions::default()
+    };
+    let json = state_json_with_options(&state, &options);
+    let frames = &json["threads"][0]["frames"];
+    assert_eq!(frames[0]["registers"]["eip"], "0x0040429e");
+    assert_eq!(frames[0]["register_validity"], "all");
+    // Callers only have the registers the stackwalker recovered.
+    assert_eq!(frames[3]["registers"]["eip"], "0x7c816fd7");
+    assert_eq!(frames[3]["registers"]["esp"], "0x0012ffc8");
+    let validity = frames[3]["register_validity"].as_array().unwrap();
+    assert!(validity.contains(&serde_json::json!("eip")));
+    assert!(validity.contains(&serde_json::json!("esp")));
+    assert!(validity.contains(&serde_json::json!("ebp")));
+    assert!(!validity.contains(&serde_json::json!("eax")));
+    // The context frame's registers come from the minidump, the callers' are
+    // marked with how the stackwalker recovered them.
+    assert_eq!(frames[0]["register_provenance"], serde_json::Value::Null);
+    let provenance = &frames[3]["register_provenance"];
+    assert_eq!(provenance["eip"], "recovered");
+    assert_eq!(provenance["esp"], "recovered");
+    assert_eq!(provenance["ebp"], "recovered");
+    assert_eq!(provenance["eax"], "unknown");
+    assert_eq!(
+        json["crashing_thread"]["frames"][3]["registers"],
+        frames[3]["registers"]
+    );
+}
+
+#[test]
+fn test_processor_sentry_json() {
+    let dump = read_test_minidump().unwrap();
+    let state = minidump_processor::process_minidump(
+        &dump,
+        &Symbolizer::new(SimpleSymbolSupplier::new(vec![testdata_symbol_path()])),
+    )
+    .unwrap();
+    let mut json = vec![];
+    state.print_sentry_json(&mut json, false).unwrap();
+    let json: serde_json::Value = serde_json::from_slice(&json).unwrap();
+    let thread_list = dump.get_stream::<MinidumpThreadList>().unwrap();
+    let crashing_thread_id = thread_list.threads[0].raw.thread_id;
+
+    let exception = &json["exception"]["values"][0];
+    assert_eq!(exception["type"], "EXCEPTION_ACCESS_VIOLATION_WRITE");
+    assert_eq!(
+        exception["value"],
+        "EXCEPTION_ACCESS_VIOLATION_WRITE at 0x00000045"
+    );
+    assert_eq!(exception["thread_id"], crashing_thread_id);
+
+    // Frames are listed outermost first, so the crashing frame is last.
+    let frames = exception["stacktrace"]["frames"].as_array().unwrap();
+    assert_eq!(frames.len(), 4);
+    let crashing_frame = &frames[3];
+    assert_eq!(crashing_frame["instruction_addr"], "0x0040429e");
+    assert_eq!(
+        crashing_frame["function"],
+        "`anonymous namespace'::CrashFunction"
+    );
+    assert_eq!(crashing_frame["package"], "c:\\test_app.exe");
+    assert_eq!(crashing_frame["trust"], "context");
+    assert!(crashing_frame["filename"].is_string());
+    assert!(crashing_frame["lineno"].is_number());
+    assert_eq!(frames[0]["trust"], "fp");
+    assert_eq!(exception["stacktrace"]["registers"]["eip"], "0x0040429e");
+
+    // The crashing thread's stack is only given in the exception.
+    let threads = json["threads"]["values"].as_array().unwrap();
+    assert_eq!(threads.len(), 2);
+    assert_eq!(threads[0]["id"], crashing_thread_id);
+    assert_eq!(threads[0]["crashed"], true);
+    assert!(threads[0].get("stacktrace").is_none());
+    assert_eq!(threads[1]["crashed"], false);
+    assert!(threads[1]["stacktrace"]["frames"].is_array());
+
+    let images = json["debug_meta"]["images"].as_array().unwrap();
+    assert_eq!(images.len(), state.modules.iter().count());
+    assert_eq!(images[0]["type"], "pe");
+    assert_eq!(images[0]["code_file"], "c:\\test_app.exe");
+    assert_eq!(images[0]["image_addr"], "0x00400000");
+}
+
+#[test]
+fn test_processor_breakpad_info_without_exception() {
+    // Hide the exception stream, as in a dump written without crashing.
+    use scroll::{Pwrite, LE};
+    let mut bytes = std::fs::read(locate_testdata().join("test.dmp")).unwrap();
+    let entry = stream_directory_entry(&bytes, MINIDUMP_STREAM_TYPE::ExceptionStream);
+    bytes
+        .pwrite_with(MINIDUMP_STREAM_TYPE::UnusedStream as u32, entry, LE)
+        .unwrap();
+
+    let dump = Minidump::read(bytes).unwrap();
+    let state = minidump_processor::process_minidump(
+        &dump,
+        &Symbolizer::new(SimpleSymbolSupplier::new(vec![])),
+    )
+    .unwrap();
+    assert_eq!(state.crash_reason, None);
+    assert_eq!(state.crash_address, None);
+    assert!(state.exception_context.is_none());
+    // The breakpad info still says which thread requested the dump, and which
+    // thread wrote it.
+    assert_eq!(state.requesting_thread, Some(0));
+    assert_eq!(state.threads[0].info, CallStackInfo::Ok);
+    assert_eq!(state.threads[1].info, CallStackInfo::DumpThreadSkipped);
+    // Without the exception context, the requesting thread is walked from its
+    // own context, in the exception handler.
+    let f0 = &state.threads[0].frames[0];
+    assert_eq!(f0.context.get_instruction_pointer(), 0x7c90eb94);
+}
+
+/// Symbolizes every frame as being in `MiniDumpWriteDump`.
+struct DumpWriterSymbols;
+
+impl SymbolProvider for DumpWriterSymbols {
+    fn fill_symbol(&self, module: &dyn Module, frame: &mut dyn FrameSymbolizer) {
+        frame.set_function("MiniDumpWriteDump", module.base_address(), 0);
+    }
+    fn walk_frame(&self, _module: &dyn Module, _walker: &mut dyn FrameWalker) -> Option<()> {
+        None
+    }
+}
+
+#[test]
+fn test_processor_detect_dump_thread() {
+    // Hide the breakpad info stream, which says which thread wrote the dump.
+    use scroll::{Pwrite, LE};
+    let mut bytes = std::fs::read(locate_testdata().join("test.dmp")).unwrap();
+    let entry = stream_directory_entry(&bytes, MINIDUMP_STREAM_TYPE::BreakpadInfoStream);
+    bytes
+        .pwrite_with(MINIDUMP_STREAM_TYPE::UnusedStream as u32, entry, LE)
+        .unwrap();
+    let dump = Minidump::read(bytes).unwrap();
+
+    // Every thread looks like it's writing the dump, but the crashing thread
+    // is never skipped.
+    let state = minidump_processor::process_minidump(&dump, &DumpWriterSymbols).unwrap();
+    assert_eq!(state.requesting_thread, Some(0));
+    assert_eq!(state.threads[0].info, CallStackInfo::Ok);
+    assert_eq!(state.threads[1].info, CallStackInfo::DumpThreadSkipped);
+    assert_eq!(state.dump_thread, Some(1));
+    assert!(state
+        .report_lines()
+        .any(|line| line == "Dump thread: Thread 1 (skipped)"));
+    let json = state_json(&state);
+    assert_eq!(json["crash_info"]["dump_thread"], 1);
+
+    let options = ProcessorOptions {
+        detect_dump_thread: false,
+        ..ProcessorOptions::default()
+    };
+    let state =
+        minidump_processor::process_minidump_with_options(&dump, &DumpWriterSymbols, &options)
+            .unwrap();
+    assert_eq!(state.threads[1].info, CallStackInfo::Ok);
+    assert_eq!(state.dump_thread, None);
+}
+
+/// Check that the report `print` writes for each of the test minidumps, with
+/// the test symbols, matches the stored report in `testdata/reports`, byte for
+/// byte.
+///
+/// After an intended change to the report format, run the test with
+/// `BLESS_REPORTS=1` set to rewrite the stored reports, and review the diff.
+#[test]
+fn test_processor_print_reports() {
+    let testdata = locate_testdata();
+    let symbolizer = Symbolizer::new(SimpleSymbolSupplier::new(vec![testdata_symbol_path()]));
+    let bless = std::env::var_os("BLESS_REPORTS").is_some();
+    for name in &["test.dmp", "simple-crashpad.dmp", "invalid-parameter.dmp"] {
+        let dump = Minidump::read_path(testdata.join(name)).unwrap();
+        let mut report = vec![];
+        minidump_processor::process_minidump(&dump, &symbolizer)
+            .unwrap()
+            .print(&mut report)
+            .unwrap();
+        // Processing the same dump again must give the same report.
+        let mut again = vec![];
+        minidump_processor::process_minidump(&dump, &symbolizer)
+            .unwrap()
+            .print(&mut again)
+            .unwrap();
+        assert_eq!(report, again, "{} has a nondeterministic report", name);
+
+        let path = testdata.join("reports").join(format!("{}.txt", name));
+        if bless {
+            std::fs::write(&path, &report).unwrap();
+            continue;
+        }
+        let expected = std::fs::read(&path).unwrap();
+        if report != expected {
+            panic!(
+                "The report for {} doesn't match {:?}, got:\n{}",
+                name,
+                path,
+                String::from_utf8_lossy(&report)
+            );
+        }
+    }
+}
+
+#[test]
+fn test_processor_source_urls() {
+    let dump = read_test_minidump().unwrap();
+    let state = minidump_processor::process_minidump(
+        &dump,
+        &Symbolizer::new(SimpleSymbolSupplier::new(vec![testdata_symbol_path()])),
+    )
+    .unwrap();
+
+    // No URLs unless asked for.
+    let json = state_json(&state);
+    assert!(json["threads"][0]["frames"][0].get("file_url").is_none());
+
+    let source_urls = |module: Option<&MinidumpModule>, file: &str, line: Option<u32>| {
+        let module = module?.code_file();
+        let file = file.rsplit('\\').next().unwrap();
+        Some(match line {
+            Some(line) => format!("https://example.com/{}/{}#{}", module, file, line),
+            None => format!("https://example.com/{}/{}", module, file),
+        })
+    };
+    let options = JsonOptions {
+        source_urls: Some(SharedSourceUrlResolver::new(source_urls)),
+        ..JsonOptions::default()
+    };
+    let json = state_json_with_options(&state, &options);
+    let frames = &json["threads"][0]["frames"];
+    assert_eq!(
+        frames[0]["file_url"],
+        "https://example.com/c:\\test_app.exe/test_app.cc"
+    );
+    assert_eq!(
+        frames[0]["line_url"],
+        "https://example.com/c:\\test_app.exe/test_app.cc#58"
+    );
+    assert_eq!(
+        json["crashing_thread"]["frames"][0]["line_url"],
+        frames[0]["line_url"]
+    );
+    // kernel32.dll has no symbols, so there's no source to link to.
+    assert!(frames[3].get("file_url").is_none());
+    assert!(frames[3].get("line_url").is_none());
+}
+
+#[test]
+fn test_processor_walk_one_thread() {
+    let dump = read_test_minidump().unwrap();
+    let symbolizer = Symbolizer::new(SimpleSymbolSupplier::new(vec![testdata_symbol_path()]));
+    let all = minidump_processor::process_minidump(&dump, &symbolizer).unwrap();
+    let state = minidump_processor::process_minidump_thread(&dump, 0, &symbolizer).unwrap();
+    assert_eq!(state.threads.len(), 2);
+    assert_eq!(state.threads[0].info, CallStackInfo::Ok);
+    assert_eq!(state.threads[0].frames.len(), all.threads[0].frames.len());
+    assert_eq!(
+        state.threads[0].frames[0].function_name.as_deref(),
+        Some("`anonymous namespace'::CrashFunction")
+    );
+    assert_eq!(state.crash_signature(), all.crash_signature());
+
+    // The crashing thread is still the requesting thread when it isn't walked.
+    let options = ProcessorOptions {
+        walk_threads: Some(vec![]),
+        ..ProcessorOptions::default()
+    };
+    let state =
+        minidump_processor::process_minidump_with_options(&dump, &symbolizer, &options).unwrap();
+    assert_eq!(state.requesting_thread, Some(0));
+    assert_eq!(state.threads[0].info, CallStackInfo::NotWalked);
+    assert!(state.threads[0].frames.is_empty());
+    let json = state_json(&state);
+    assert_eq!(json["threads"][0]["stack_info"], "not_walked");
+    assert_eq!(json["crashing_thread"]["frame_count"], 0);
+}
+
+#[test]
+fn test_processor_unknown_cpu_context() {
+    // Give the crashing thread an Alpha context, which isn't supported, both
+    // in the thread list and in the exception stream.
+    use scroll::{Pread, Pwrite, LE};
+    const CONTEXT_ALPHA_CONTROL: u32 = 0x0002_0001;
+    let mut bytes = std::fs::read(locate_testdata().join("test.dmp")).unwrap();
+    let entry = stream_directory_entry(&bytes, MINIDUMP_STREAM_TYPE::ThreadListStream);
+    let thread_list: u32 = bytes.pread_with(entry + 8, LE).unwrap();
+    let thread_context: u32 = bytes.pread_with(thread_list as usize + 4 + 44, LE).unwrap();
+    let entry = stream_directory_entry(&bytes, MINIDUMP_STREAM_TYPE::ExceptionStream);
+    let exception: u32 = bytes.pread_with(entry + 8, LE).unwrap();
+    let exception_context: u32 = bytes.pread_with(exception as usize + 164, LE).unwrap();
+    for &context in &[thread_context, exception_context] {
+        bytes
+            .pwrite_with(CONTEXT_ALPHA_CONTROL, context as usize, LE)
+            .unwrap();
+    }
+
+    let dump = Minidump::read(bytes).unwrap();
+    let state = minidump_processor::process_minidump(
+        &dump,
+        &Symbolizer::new(SimpleSymbolSupplier::new(vec![])),
+    )
+    .unwrap();
+    // The crashing thread has no frames, but the dump is still processed.
+    assert_eq!(state.requesting_thread, Some(0));
+    assert_eq!(state.threads[0].info, CallStackInfo::UnsupportedCpu);
+    assert!(state.threads[0].frames.is_empty());
+    assert_eq!(state.threads[0].context_flags, Some(CONTEXT_ALPHA_CONTROL));
+    assert_eq!(state.crash_address, Some(0x45));
+
+    let mut report = vec![];
+    state.print(&mut report).unwrap();
+    let report = String::from_utf8(report).unwrap();
+    assert!(report.contains("Unknown CPU context, context flags 0x00020001"));
+
+    let json = state_json(&state);
+    assert_eq!(json["threads"][0]["stack_info"], "unsupported_cpu");
+    assert_eq!(json["threads"][0]["context_flags"], "0x00020001");
+    assert_eq!(json["threads"][1]["context_flags"], serde_json::Value::Null);
+}
+
+#[test]
+fn test_processor_walk_unsupported_cpu() {
+    use scroll::ctx::SizeWith;
+    use scroll::{Pread, Pwrite, LE};
+
+    // Give simple-crashpad.dmp's amd64 thread a SPARC thread and a copy of
+    // itself for company, in a new thread list at the end of the dump.
+    let mut bytes = std::fs::read(locate_testdata().join("simple-crashpad.dmp")).unwrap();
+    let stream_count: u32 = bytes.pread_with(8, LE).unwrap();
+    let directory: u32 = bytes.pread_with(12, LE).unwrap();
+    let entry = (0..stream_count as usize)
+        .map(|i| directory as usize + i * 12)
+        .find(|&entry| {
+            bytes.pread_with::<u32>(entry, LE).unwrap()
+                == MINIDUMP_STREAM_TYPE::ThreadListStream as u32
+        })
+        .unwrap();
+    let thread_list: u32 = bytes.pread_with(entry + 8, LE).unwrap();
+    let thread_size = format::MINIDUMP_THREAD::size_with(&LE);
+    let thread_start = thread_list as usize + 4;
+    let amd64_thread = bytes[thread_start..thread_start + thread_size].to_vec();
+
+    let mut sparc = vec![0; format::CONTEXT_SPARC::size_with(&LE)];
+    sparc
+        .pwrite_with(format::ContextFlagsCpu::CONTEXT_SPARC.bits(), 0, LE)
+        .unwrap();
+    let sparc_rva = bytes.len() as u32;
+    bytes.extend_from_slice(&sparc);
+    let mut sparc_thread = amd64_thread.clone();
+    sparc_thread.pwrite_with(2u32, 0, LE).unwrap();
+    // thread_context
+    sparc_thread
+        .pwrite_with(sparc.len() as u32, thread_size - 8, LE)
+        .unwrap();
+    sparc_thread
+        .pwrite_with(sparc_rva, thread_size - 4, LE)
+        .unwrap();
+    let mut other_amd64_thread = amd64_thread.clone();
+    other_amd64_thread.pwrite_with(3u32, 0, LE).unwrap();
+
+    let new_list = bytes.len() as u32;
+    bytes.extend_from_slice(&3u32.to_le_bytes());
+    bytes.extend_from_slice(&amd64_thread);
+    bytes.extend_from_slice(&sparc_thread);
+    bytes.extend_from_slice(&other_amd64_thread);
+    bytes
+        .pwrite_with(4 + 3 * thread_size as u32, entry + 4, LE)
+        .unwrap();
+    bytes.pwrite_with(new_list, entry + 8, LE).unwrap();
+
+    let dump = Minidump::read(bytes).unwrap();
+    let state = minidump_processor::process_minidump(&dump, &NoopSymbolizer).unwrap();
+    assert_eq!(state.threads.len(), 3);
+    // The SPARC thread can't be unwound, but its context frame is kept.
+    let sparc_stack = &state.threads[1];
+    assert_eq!(sparc_stack.info, CallStackInfo::UnsupportedCpu);
+    assert_eq!(sparc_stack.frames.len(), 1);
+    assert_eq!(sparc_stack.frames[0].trust, FrameTrust::Context);
+    // The amd64 threads are still unwound.
+    for &i in &[0, 2] {
+        assert_ne!(state.threads[i].info, CallStackInfo::UnsupportedCpu);
+        assert!(state.threads[i].frames.len() > 1);
+        assert_eq!(state.threads[i].frames.len(), state.threads[0].frames.len());
+    }
+
+    let json = state_json(&state);
+    assert_eq!(json["threads"][1]["stack_info"], "unsupported_cpu");
+    assert_eq!(json["threads"][2]["stack_info"], "ok");
+    // Printing must not choke on the unsupported registers.
+    state.print(&mut vec![]).unwrap();
+}
+
+#[test]
+fn test_processor_unwind_diagnostics() {
+    let dump = read_test_minidump().unwrap();
+    let symbolizer = Symbolizer::new(SimpleSymbolSupplier::new(vec![]));
+    let state = minidump_processor::process_minidump(&dump, &symbolizer).unwrap();
+    assert!(state
+        .threads
+        .iter()
+        .all(|thread| thread.unwind_diagnostics.is_none()));
+
+    let options = ProcessorOptions {
+        unwind_diagnostics: true,
+        ..ProcessorOptions::default()
+    };
+    let state =
+        minidump_processor::process_minidump_with_options(&dump, &symbolizer, &options).unwrap();
+    let lines = state.threads[0].unwind_diagnostics.as_ref().unwrap();
+    assert_eq!(
+        lines[0],
+        "frame 0: ip 0x40429e, sp 0x12fe84, given as instruction pointer in context"
+    );
+    // Without symbols there's no CFI, so every frame comes from the frame pointer.
+    assert_eq!(
+        lines[1],
+        "  call frame info: c:\\test_app.exe has no usable rules for ip 0x40429e"
+    );
+    assert_eq!(lines[2], "  call frame info: no caller");
+    assert_eq!(
+        lines[3],
+        "  frame pointer: found a caller with ip 0x404200, sp 0x12fe90"
+    );
+    assert_eq!(
+        lines[4],
+        "frame 1: ip 0x404200, sp 0x12fe90, previous frame's frame pointer"
+    );
+    // The outermost frame's frame pointer gives a caller at 0.
+    assert!(lines.contains(&"  rejected the caller: ip 0 ends the stack".to_string()));
+    assert_eq!(
+        lines
+            .iter()
+            .filter(|line| line.starts_with("frame "))
+            .count(),
+        state.threads[0].frames.len()
+    );
+    assert_eq!(
+        lines.last().unwrap(),
+        "no caller of frame 3, the stack ends"
+    );
+}
+
+#[test]
+fn test_processor_stack_from_memory_list() {
+    // Zero the crashing thread's stack descriptor. Its stack is still in the
+    // memory list, and is found by the stack pointer.
+    use scroll::{Pread, Pwrite, LE};
+    let mut bytes = std::fs::read(locate_testdata().join("test.dmp")).unwrap();
+    let entry = stream_directory_entry(&bytes, MINIDUMP_STREAM_TYPE::ThreadListStream);
+    let thread_list: u32 = bytes.pread_with(entry + 8, LE).unwrap();
+    let stack = thread_list as usize + 4 + 24;
+    let stack_base: u64 = bytes.pread_with(stack, LE).unwrap();
+    assert_eq!(stack_base, 0x12f31c);
+    bytes.pwrite_with(0u64, stack, LE).unwrap();
+    bytes.pwrite_with(0u32, stack + 8, LE).unwrap();
+    bytes.pwrite_with(0u32, stack + 12, LE).unwrap();
+
+    let dump = Minidump::read(bytes).unwrap();
+    let thread_list = dump.get_stream::<MinidumpThreadList>().unwrap();
+    assert!(thread_list.threads[0].stack.is_none());
+    let state = minidump_processor::process_minidump(
+        &dump,
+        &Symbolizer::new(SimpleSymbolSupplier::new(vec![testdata_symbol_path()])),
+    )
+    .unwrap();
+    let stack = &state.threads[0];
+    assert_eq!(stack.info, CallStackInfo::Ok);
+    assert_eq!(stack.frames.len(), 4);
+    assert_eq!(stack.frames[1].function_name.as_deref(), Some("main"));
+    let requesting_stack = state.requesting_thread_stack.as_ref().unwrap();
+    assert_eq!(requesting_stack.base_address, 0x12f31c);
+}
+
+#[test]
+fn test_processor_truncated_stack_memory() {
+    // Capture only the crashing thread's stack up to its caller's frame,
+    // the way dumps that limit their size do.
+    use scroll::{Pread, Pwrite, LE};
+    let mut bytes = std::fs::read(locate_testdata().join("test.dmp")).unwrap();
+    let entry = stream_directory_entry(&bytes, MINIDUMP_STREAM_TYPE::ThreadListStream);
+    let thread_list: u32 = bytes.pread_with(entry + 8, LE).unwrap();
+    let stack = thread_list as usize + 4 + 24;
+    let stack_base: u64 = bytes.pread_with(stack, LE).unwrap();
+    assert_eq!(stack_base, 0x12f31c);
+    bytes
+        .pwrite_with((0x12fe90 - stack_base) as u32, stack + 8, LE)
+        .unwrap();
+
+    let dump = Minidump::read(bytes).unwrap();
+    let state = minidump_processor::process_minidump(
+        &dump,
+        &Symbolizer::new(SimpleSymbolSupplier::new(vec![testdata_symbol_path()])),
+    )
+    .unwrap();
+    let stack = &state.threads[0];
+    assert_eq!(stack.info, CallStackInfo::TruncatedStackMemory);
+    assert_eq!(stack.frames.len(), 2);
+    assert_eq!(stack.frames[1].function_name.as_deref(), Some("main"));
+
+    let mut report = vec![];
+    stack.print(&mut report).unwrap();
+    let report = String::from_utf8(report).unwrap();
+    assert!(report.ends_with("(the rest of the stack wasn't captured)\n"));
+
+    // The whole stack is walked as usual.
+    let dump = read_test_minidump().unwrap();
+    let state = minidump_processor::process_minidump(
+        &dump,
+        &Symbolizer::new(SimpleSymbolSupplier::new(vec![testdata_symbol_path()])),
+    )
+    .unwrap();
+    assert_eq!(state.threads[0].info, CallStackInfo::Ok);
+    assert_eq!(state.threads[0].frames.len(), 4);
+}
+
+#[test]
+fn test_processor_no_symbols() {
+    let dump = read_test_minidump().unwrap();
+    let state = minidump_processor::process_minidump(&dump, &NoopSymbolizer).unwrap();
+
+    // The stack is walked just as it is when no symbol files are found.
+    let frames = &state.threads[0].frames;
+    assert_eq!(frames.len(), 4);
+    assert_eq!(frames[0].trust, FrameTrust::Context);
+    assert!(frames[1..]
+        .iter()
+        .all(|frame| frame.trust == FrameTrust::FramePointer));
+    assert_eq!(frames[1].return_address(), 0x404200);
+    assert!(frames.iter().all(|frame| frame.function_name.is_none()));
+
+    let lines = state.report_lines().collect::<Vec<_>>();
+    assert!(lines.iter().any(|line| line == " 0  test_app.exe + 0x429e"));
+    assert!(lines.iter().any(|line| line == " 1  test_app.exe + 0x41ff"));
+
+    let json = state_json(&state);
+    let json_frames = json["crashing_thread"]["frames"].as_array().unwrap();
+    assert_eq!(json_frames.len(), 4);
+    assert!(json_frames
+        .iter()
+        .all(|frame| frame["missing_symbols"] == true && frame["module_offset"].is_string()));
+    assert_eq!(json_frames[0]["module_offset"], "0x0000429e");
+    assert_eq!(json_frames[0]["trust"], "context");
+}
+
+#[test]
+fn test_processor_stack_scan() {
+    let dump = read_test_minidump().unwrap();
+    let thread_list = dump.get_stream::<MinidumpThreadList>().unwrap();
+    let modules = dump.get_stream::<MinidumpModuleList>().unwrap();
+    let exception = dump.get_stream::<MinidumpException>().unwrap();
+    let stack = thread_list.threads[0].stack.as_ref();
+    // Without a frame pointer, the crashing frame's caller can only be
+    // found by scanning.
+    let mut context = exception.context.clone().unwrap();
+    if let MinidumpRawContext::X86(ref mut ctx) = context.raw {
+        ctx.ebp = 0;
+    }
+    let walk = |scan: StackScan, memory: Option<&MinidumpMemoryList>| {
+        let stack = minidump_processor::walk_stack_with_options(
+            &Some(&context),
+            stack,
+            &modules,
+            &MinidumpUnloadedModuleList::new(),
+            &NoopSymbolizer,
+            &StackWalkOptions {
+                scan,
+                memory,
+                ..StackWalkOptions::default()
+            },
+        );
+        stack
+            .frames
+            .iter()
+            .skip(1)
+            .map(|frame| (frame.return_address(), frame.trust))
+            .next()
+    };
+
+    assert_eq!(
+        ProcessorOptions::default().stack_scan,
+        StackScan::PreferVerified
+    );
+    assert_eq!(walk(StackScan::Off, None), None);
+    // The first word on the stack that points into test_app.exe.
+    assert_eq!(
+        walk(StackScan::FirstPlausible, None),
+        Some((0x404200, FrameTrust::Scan))
+    );
+    // Nothing can be verified without code or CFI.
+    assert_eq!(
+        walk(StackScan::PreferVerified, None),
+        Some((0x404200, FrameTrust::Scan))
+    );
+    assert_eq!(walk(StackScan::VerifiedOnly, None), None);
+
+    // A later word that follows a `call dword ptr [disp32]` is preferred.
+    let call = [0xff, 0x15, 0x00, 0x10, 0x40, 0x00];
+    let code = vec![MinidumpMemory {
+        desc: MINIDUMP_MEMORY_DESCRIPTOR::default(),
+        base_address: 0x404240 - call.len() as u64,
+        size: call.len() as u64,
+        bytes: &call,
+    }];
+    let code = MinidumpMemoryList::from_regions(code);
+    assert_eq!(
+        walk(StackScan::FirstPlausible, Some(&code)),
+        Some((0x404200, FrameTrust::Scan))
+    );
+    assert_eq!(
+        walk(StackScan::PreferVerified, Some(&code)),
+        Some((0x404240, FrameTrust::CfiScan))
+    );
+    assert_eq!(
+        walk(StackScan::VerifiedOnly, Some(&code)),
+        Some((0x404240, FrameTrust::CfiScan))
+    );
+
+    // A direct call before the first word verifies it.
+    let call = [0xe8, 0x00, 0x01, 0x00, 0x00];
+    let code = vec![MinidumpMemory {
+        desc: MINIDUMP_MEMORY_DESCRIPTOR::default(),
+        base_address: 0x404200 - call.len() as u64,
+        size: call.len() as u64,
+        bytes: &call,
+    }];
+    let code = MinidumpMemoryList::from_regions(code);
+    assert_eq!(
+        walk(StackScan::PreferVerified, Some(&code)),
+        Some((0x404200, FrameTrust::CfiScan))
+    );
+}
+
+#[test]
+fn test_processor_stack_scan_cfi_caller() {
+    let dump = read_test_minidump().unwrap();
+    let thread_list = dump.get_stream::<MinidumpThreadList>().unwrap();
+    let modules = dump.get_stream::<MinidumpModuleList>().unwrap();
+    let exception = dump.get_stream::<MinidumpException>().unwrap();
+    let stack = thread_list.threads[0].stack.as_ref();
+    let mut context = exception.context.clone().unwrap();
+    if let MinidumpRawContext::X86(ref mut ctx) = context.raw {
+        ctx.ebp = 0;
+    }
+    // CFI for the function the first scanned word returns into, which gives
+    // its own caller the return address `ra`.
+    let walk = |ra: u64| {
+        let text = format!(
+            "MODULE windows x86 5A9832E5287241C1838ED98914E9B7FF1 test_app.pdb\n\
+             STACK CFI INIT 4200 10 .cfa: $esp 4 + .ra: {}\n",
+            ra
+        );
+        let mut supplier = InMemorySymbolSupplier::new();
+        supplier.insert(
+            "test_app.pdb",
+            "5A9832E5287241C1838ED98914E9B7FF1",
+            SymbolFile::from_bytes(text.as_bytes()).unwrap(),
+        );
+        let stack = minidump_processor::walk_stack_with_options(
+            &Some(&context),
+            stack,
+            &modules,
+            &MinidumpUnloadedModuleList::new(),
+            &Symbolizer::new(supplier),
+            &StackWalkOptions {
+                scan: StackScan::PreferVerified,
+                ..StackWalkOptions::default()
+            },
+        );
+        stack
+            .frames
+            .get(1)
+            .map(|frame| (frame.return_address(), frame.trust))
+    };
+
+    // CFI that unwinds to a caller in test_app.exe verifies the word.
+    assert_eq!(walk(0x402000), Some((0x404200, FrameTrust::CfiScan)));
+    // CFI that unwinds to nowhere doesn't, so it's only the first plausible one.
+    assert_eq!(walk(0), Some((0x404200, FrameTrust::Scan)));
+}
+
+#[test]
+fn test_processor_walk_non_executable_caller() {
+    use format::{MemoryProtection, MemoryState};
+    let dump = read_test_minidump().unwrap();
+    let thread_list = dump.get_stream::<MinidumpThreadList>().unwrap();
+    let modules = dump.get_stream::<MinidumpModuleList>().unwrap();
+    let exception = dump.get_stream::<MinidumpException>().unwrap();
+    let stack = thread_list.threads[0].stack.as_ref();
+    // Without a frame pointer, the crashing frame's caller can only be
+    // found by scanning.
+    let mut context = exception.context.clone().unwrap();
+    if let MinidumpRawContext::X86(ref mut ctx) = context.raw {
+        ctx.ebp = 0;
+    }
+    let walk = |memory_info: Option<&MinidumpMemoryInfoList>| {
+        let stack = minidump_processor::walk_stack_with_options(
+            &Some(&context),
+            stack,
+            &modules,
+            &MinidumpUnloadedModuleList::new(),
+            &NoopSymbolizer,
+            &StackWalkOptions {
+                scan: StackScan::FirstPlausible,
+                memory_info,
+                ..StackWalkOptions::default()
+            },
+        );
+        stack
+            .frames
+            .get(1)
+            .map(|frame| frame.context.get_instruction_pointer())
+    };
+
+    assert_eq!(walk(None), Some(0x404200));
+
+    // A word pointing into a module's memory that isn't executable isn't
+    // taken for a return address.
+    let commit = MemoryState::MEM_COMMIT.bits();
+    let readwrite = MemoryProtection::PAGE_READWRITE.bits();
+    let execute_read = MemoryProtection::PAGE_EXECUTE_READ.bits();
+    let data = MinidumpMemoryInfoList::from_regions(vec![memory_info(
+        0x404000, 0x1000, commit, readwrite,
+    )]);
+    // The scan goes on to a word pointing into ntdll.dll.
+    assert_eq!(walk(Some(&data)), Some(0x7c9131dc));
+
+    // Executable memory is still fine.
+    let code = MinidumpMemoryInfoList::from_regions(vec![memory_info(
+        0x404000,
+        0x1000,
+        commit,
+        execute_read,
+    )]);
+    assert_eq!(walk(Some(&code)), Some(0x404200));
+}
+
+#[test]
+fn test_processor_legacy_walk_stack_scan() {
+    // `walk_stack` keeps taking the first plausible word, even when CFI
+    // would verify it.
+    let dump = read_test_minidump().unwrap();
+    let thread_list = dump.get_stream::<MinidumpThreadList>().unwrap();
+    let modules = dump.get_stream::<MinidumpModuleList>().unwrap();
+    let exception = dump.get_stream::<MinidumpException>().unwrap();
+    let stack = thread_list.threads[0].stack.as_ref();
+    let mut context = exception.context.clone().unwrap();
+    if let MinidumpRawContext::X86(ref mut ctx) = context.raw {
+        ctx.ebp = 0;
+    }
+    let mut supplier = InMemorySymbolSupplier::new();
+    supplier.insert(
+        "test_app.pdb",
+        "5A9832E5287241C1838ED98914E9B7FF1",
+        SymbolFile::from_bytes(
+            b"MODULE windows x86 5A9832E5287241C1838ED98914E9B7FF1 test_app.pdb
+STACK CFI INIT 4200 10 .cfa: $esp 4 + .ra: 4202496
+",
+        )
+        .unwrap(),
+    );
+    let symbolizer = Symbolizer::new(supplier);
+    let caller = |stack: CallStack| (stack.frames[1].return_address(), stack.frames[1].trust);
+
+    let legacy = minidump_processor::walk_stack(
+        &Some(&context),
+        stack,
+        &modules,
+        &MinidumpUnloadedModuleList::new(),
+        &symbolizer,
+    );
+    assert_eq!(caller(legacy), (0x404200, FrameTrust::Scan));
+    let verified = minidump_processor::walk_stack_with_options(
+        &Some(&context),
+        stack,
+        &modules,
+        &MinidumpUnloadedModuleList::new(),
+        &symbolizer,
+        &StackWalkOptions::default(),
+    );
+    assert_eq!(caller(verified), (0x404200, FrameTrust::CfiScan));
 }
@@ -320,8 +320,8 @@ pub fn dump_minidump_stack() -> Result<(), Error> {
     let memory_list = dump.get_stream::<MinidumpMemoryList>()?;
     let sys_info = dump.get_stream::<MinidumpSystemInfo>()?;
     let wordsize = match sys_info.cpu {
-        Cpu::X86 | Cpu::Ppc | Cpu::Sparc | Cpu::Arm => 4,
-        Cpu::X86_64 | Cpu::Ppc64 | Cpu::Arm64 => 8,
+        Cpu::X86 | Cpu::Ppc | Cpu::Sparc | Cpu::Arm | Cpu::Mips => 4,
+        Cpu::X86_64 | Cpu::Ppc64 | Cpu::Arm64 | Cpu::Mips64 => 8,
         Cpu::Unknown(u) => bail!("Unknown cpu: {:#x}", u),
     };
     // TODO: provide a commandline option for the address.
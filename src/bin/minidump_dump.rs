@@ -6,9 +6,13 @@ use std::io::{self, Write};
 use std::path::Path;
 use std::str;
 
+use minidump::system_info::Os;
 use minidump::*;
 
-const USAGE: &str = "Usage: minidump_dump <minidump>";
+const USAGE: &str = "Usage: minidump_dump [--json] <minidump>";
+
+/// How many bytes of memory to print on either side of the crash address.
+const CRASH_MEMORY_RADIUS: u64 = 64;
 
 macro_rules! streams {
     ( $( $x:ident ),* ) => {
@@ -34,6 +38,21 @@ fn print_minidump_dump(path: &Path) {
 
             if let Ok(exception) = dump.get_stream::<MinidumpException>() {
                 exception.print(stdout).unwrap();
+                let os = dump
+                    .get_stream::<MinidumpSystemInfo>()
+                    .map(|system_info| system_info.os)
+                    .unwrap_or(Os::Unknown(0));
+                let crash_address = exception.get_crash_address(os);
+                let start = crash_address.saturating_sub(CRASH_MEMORY_RADIUS);
+                if let Ok(memory_list) = dump.get_stream::<MinidumpMemoryList<'_>>() {
+                    if let Some(window) = memory_list.memory_window(start, CRASH_MEMORY_RADIUS * 2)
+                    {
+                        writeln!(stdout, "Memory around crash address {:#x}", crash_address)
+                            .unwrap();
+                        window.print(stdout).unwrap();
+                        writeln!(stdout).unwrap();
+                    }
+                }
             }
             if let Ok(assertion) = dump.get_stream::<MinidumpAssertion>() {
                 assertion.print(stdout).unwrap();
@@ -47,6 +66,13 @@ fn print_minidump_dump(path: &Path) {
             if let Ok(breakpad_info) = dump.get_stream::<MinidumpBreakpadInfo>() {
                 breakpad_info.print(stdout).unwrap();
             }
+            if let Ok(handle_data) = dump.get_stream::<MinidumpHandleDataStream>() {
+                handle_data.print(stdout).unwrap();
+            }
+            if let Ok(handle_operations) = dump.get_stream::<MinidumpHandleOperationList>() {
+                let modules = dump.get_stream::<MinidumpModuleList>().ok();
+                handle_operations.print(stdout, modules.as_ref()).unwrap();
+            }
             // TODO: MemoryInfoList
             match dump.get_stream::<MinidumpCrashpadInfo>() {
                 Ok(crashpad_info) => crashpad_info.print(stdout).unwrap(),
@@ -68,12 +94,18 @@ fn print_minidump_dump(path: &Path) {
             if let Ok(thread_list) = dump.get_stream::<MinidumpThreadList<'_>>() {
                 thread_list.print(stdout).unwrap();
             }
+            if let Ok(thread_names) = dump.get_stream::<MinidumpThreadNames>() {
+                thread_names.print(stdout).unwrap();
+            }
             if let Ok(module_list) = dump.get_stream::<MinidumpModuleList>() {
                 module_list.print(stdout).unwrap();
             }
             if let Ok(memory_list) = dump.get_stream::<MinidumpMemoryList<'_>>() {
                 memory_list.print(stdout).unwrap();
             }
+            if let Ok(memory_info_list) = dump.get_stream::<MinidumpMemoryInfoList>() {
+                memory_info_list.print(stdout).unwrap();
+            }
             // TODO: MemoryList
         }
         Err(err) => {
@@ -83,11 +115,45 @@ fn print_minidump_dump(path: &Path) {
     }
 }
 
+#[cfg(feature = "json")]
+fn print_minidump_json(path: &Path) {
+    match Minidump::read_path(path) {
+        Ok(dump) => {
+            let stdout = &mut std::io::stdout();
+            dump.print_json(stdout, true).unwrap();
+            writeln!(stdout).unwrap();
+        }
+        Err(err) => {
+            let mut stderr = std::io::stderr();
+            writeln!(&mut stderr, "Error reading dump: {:?}", err).unwrap();
+        }
+    }
+}
+
+#[cfg(not(feature = "json"))]
+fn print_minidump_json(_path: &Path) {
+    let mut stderr = std::io::stderr();
+    writeln!(
+        &mut stderr,
+        "--json needs minidump_dump to be built with the `json` feature"
+    )
+    .unwrap();
+}
+
 #[cfg_attr(test, allow(dead_code))]
 fn main() {
-    if let Some(dump_arg) = env::args().nth(1) {
+    let mut args = env::args().skip(1).peekable();
+    let json = args.peek().map_or(false, |arg| arg == "--json");
+    if json {
+        args.next();
+    }
+    if let Some(dump_arg) = args.next() {
         let path = Path::new(&dump_arg);
-        print_minidump_dump(&path);
+        if json {
+            print_minidump_json(path);
+        } else {
+            print_minidump_dump(path);
+        }
     } else {
         let mut stderr = std::io::stderr();
         writeln!(&mut stderr, "{}", USAGE).unwrap();
@@ -11,6 +11,7 @@ use std::io::prelude::*;
 use std::mem;
 
 use crate::iostuff::*;
+use crate::system_info::Cpu;
 use minidump_common::format as md;
 use minidump_common::format::ContextFlagsCpu;
 
@@ -26,6 +27,7 @@ pub enum MinidumpRawContext {
     Arm64(md::CONTEXT_ARM64),
     OldArm64(md::CONTEXT_ARM64_OLD),
     Mips(md::CONTEXT_MIPS),
+    Mips64(md::CONTEXT_MIPS),
 }
 
 /// Generic over the specifics of a CPU context.
@@ -187,6 +189,44 @@ impl CpuContext for md::CONTEXT_AMD64 {
     }
 }
 
+impl CpuContext for md::CONTEXT_MIPS {
+    type Register = u64;
+
+    fn get_register_always(&self, reg: &str) -> u64 {
+        match reg {
+            "pc" => self.epc,
+            _ => match MIPS_REGS[..32].iter().position(|val| *val == reg) {
+                Some(idx) => self.iregs[idx],
+                None => unreachable!("Invalid MIPS register!"),
+            },
+        }
+    }
+
+    fn set_register(&mut self, reg: &str, val: Self::Register) -> Option<()> {
+        match reg {
+            "pc" => self.epc = val,
+            _ => {
+                let idx = MIPS_REGS[..32].iter().position(|val| *val == reg)?;
+                self.iregs[idx] = val;
+            }
+        }
+        Some(())
+    }
+
+    fn memoize_register(&self, reg: &str) -> Option<&'static str> {
+        let idx = MIPS_REGS.iter().position(|val| *val == reg)?;
+        Some(MIPS_REGS[idx])
+    }
+
+    fn stack_pointer_register_name(&self) -> &'static str {
+        "sp"
+    }
+
+    fn instruction_pointer_register_name(&self) -> &'static str {
+        "pc"
+    }
+}
+
 impl CpuContext for md::CONTEXT_ARM64_OLD {
     type Register = u64;
 
@@ -416,15 +456,54 @@ pub struct MinidumpContext {
     pub valid: MinidumpContextValidity,
 }
 
+/// The extended processor state (the XSAVE area) saved along with an x86 or amd64 context.
+///
+/// Windows appends it to a context when `CONTEXT_XSTATE` is set in the context flags. Where
+/// each feature's registers are is described by the `xstate_data` of the minidump's misc
+/// info, see [`Minidump::thread_xstate`][thread_xstate].
+///
+/// [thread_xstate]: struct.Minidump.html#method.thread_xstate
+#[derive(Debug, Clone)]
+pub struct MinidumpXState<'a> {
+    /// The whole context record, which the feature offsets are relative to.
+    context: &'a [u8],
+    /// The offset and size of each feature in `context`.
+    config: md::XSTATE_CONFIG_FEATURE_MSC_INFO,
+}
+
 /// Errors encountered while reading a `MinidumpContext`.
 #[derive(Debug)]
 pub enum ContextError {
     /// Failed to read data.
     ReadFailure,
-    /// Encountered an unknown CPU context.
-    UnknownCpuContext,
+    /// Encountered an unknown CPU context, with these context flags.
+    UnknownCpuContext(u32),
+}
+
+/// The order in which to list a context's general-purpose registers.
+#[derive(Copy, Clone, Debug, PartialEq)]
+pub enum RegisterOrder {
+    /// The order debuggers conventionally display registers in, with the instruction
+    /// pointer, stack pointer and frame pointer first.
+    Conventional,
+    /// The order returned by `MinidumpContext::general_purpose_registers`.
+    Native,
 }
 
+impl Default for RegisterOrder {
+    fn default() -> RegisterOrder {
+        RegisterOrder::Conventional
+    }
+}
+
+/// General-purpose registers for MIPS, named by their o32 ABI names like Breakpad does,
+/// followed by the program counter.
+static MIPS_REGS: [&str; 33] = [
+    "zero", "at", "v0", "v1", "a0", "a1", "a2", "a3", "t0", "t1", "t2", "t3", "t4", "t5", "t6",
+    "t7", "s0", "s1", "s2", "s3", "s4", "s5", "s6", "s7", "t8", "t9", "k0", "k1", "gp", "sp", "fp",
+    "ra", "pc",
+];
+
 /// General-purpose registers for x86.
 static X86_REGS: [&str; 10] = [
     "eip", "esp", "ebp", "ebx", "esi", "edi", "eax", "ecx", "edx", "efl",
@@ -436,15 +515,154 @@ static X86_64_REGS: [&str; 17] = [
     "r14", "r15", "rip",
 ];
 
+/// General-purpose registers for x86-64, in conventional order.
+static X86_64_CONVENTIONAL_REGS: [&str; 17] = [
+    "rip", "rsp", "rbp", "rax", "rbx", "rcx", "rdx", "rsi", "rdi", "r8", "r9", "r10", "r11", "r12",
+    "r13", "r14", "r15",
+];
+
 /// General-purpose registers for aarch64.
 static ARM64_REGS: [&str; 33] = [
     "x0", "x1", "x2", "x3", "x4", "x5", "x6", "x7", "x8", "x9", "x10", "x11", "x12", "x13", "x14",
     "x15", "x16", "x17", "x18", "x19", "x20", "x21", "x22", "x23", "x24", "x25", "x26", "x27",
     "x28", "x29", "x30", "x31", "pc",
 ];
+
+/// The `context_flags` bit that marks an x86 or amd64 context as having extended state.
+const CONTEXT_XSTATE: u32 = 0x40;
+
+/// The `context_flags` bit that marks an arm64 context, old or current, as having
+/// its floating-point registers.
+const CONTEXT_ARM64_FLOATING_POINT: u32 = 0x4;
+
+/// Where the XMM registers are in an `FXSAVE` area, like `CONTEXT_AMD64::float_save`.
+const FXSAVE_XMM_OFFSET: usize = 160;
+
 //======================================================
 // Implementations
 
+impl<'a> MinidumpXState<'a> {
+    /// Create a `MinidumpXState` from the bytes of a whole context record, laid out as
+    /// described by `config`.
+    pub fn new(
+        context: &'a [u8],
+        config: md::XSTATE_CONFIG_FEATURE_MSC_INFO,
+    ) -> MinidumpXState<'a> {
+        MinidumpXState { context, config }
+    }
+
+    /// The saved state of the feature at `index`.
+    ///
+    /// Returns `None` if the feature isn't enabled, or isn't within the context record.
+    pub fn feature(&self, index: md::XstateFeatureIndex) -> Option<&'a [u8]> {
+        let index = index as usize;
+        if self.config.enabled_features & (1 << index) == 0 {
+            return None;
+        }
+        let feature = self.config.features[index];
+        if feature.size == 0 {
+            return None;
+        }
+        let start = feature.offset as usize;
+        self.context
+            .get(start..start.checked_add(feature.size as usize)?)
+    }
+}
+
+/// Decode the saved PSTATE in an aarch64 `cpsr` for `MinidumpContext::print`.
+///
+/// The condition flags come first, as `NZCV` with `-` for a clear flag. Next is the
+/// exception level and stack pointer: `EL1h` runs on SP_EL1 and `EL1t` on SP_EL0.
+/// Then come the masked exceptions (`DAIF`), the branch type checked by BTI, and
+/// the names of any other state bits that are set. Return address signing keeps
+/// no state here, only in the signed pointers themselves.
+fn decode_arm64_cpsr(cpsr: u32) -> String {
+    let bits = |names: &[(u32, char)]| -> String {
+        names
+            .iter()
+            .map(|&(bit, name)| if cpsr & (1 << bit) != 0 { name } else { '-' })
+            .collect()
+    };
+    let mut decoded = bits(&[(31, 'N'), (30, 'Z'), (29, 'C'), (28, 'V')]);
+    if cpsr & (1 << 4) != 0 {
+        // The rest of the bits are laid out differently for AArch32 code.
+        decoded.push_str(" AArch32");
+        return decoded;
+    }
+    let stack = if cpsr & 1 != 0 { 'h' } else { 't' };
+    decoded.push_str(&format!(
+        " EL{}{} DAIF={} BTYPE={}",
+        (cpsr >> 2) & 0x3,
+        stack,
+        bits(&[(9, 'D'), (8, 'A'), (7, 'I'), (6, 'F')]),
+        (cpsr >> 10) & 0x3
+    ));
+    for &(bit, name) in &[
+        (25, "TCO"),
+        (24, "DIT"),
+        (23, "UAO"),
+        (22, "PAN"),
+        (21, "SS"),
+        (20, "IL"),
+        (12, "SSBS"),
+    ] {
+        if cpsr & (1 << bit) != 0 {
+            decoded.push(' ');
+            decoded.push_str(name);
+        }
+    }
+    decoded
+}
+
+/// Decode an x86 `eflags` or x86-64 `rflags` for `MinidumpContext::print`.
+///
+/// Lists the names of the flags that are set, like `[ PF ZF IF ]`, with the I/O
+/// privilege level spelled out as `IOPL=n` when it isn't 0.
+fn decode_eflags(eflags: u32) -> String {
+    let mut decoded = String::from("[");
+    for &(bit, name) in &[
+        (0, "CF"),
+        (2, "PF"),
+        (4, "AF"),
+        (6, "ZF"),
+        (7, "SF"),
+        (8, "TF"),
+        (9, "IF"),
+        (10, "DF"),
+        (11, "OF"),
+        (14, "NT"),
+        (16, "RF"),
+        (17, "VM"),
+        (18, "AC"),
+        (19, "VIF"),
+        (20, "VIP"),
+        (21, "ID"),
+    ] {
+        if eflags & (1 << bit) != 0 {
+            decoded.push(' ');
+            decoded.push_str(name);
+        }
+    }
+    let iopl = (eflags >> 12) & 0x3;
+    if iopl != 0 {
+        decoded.push_str(&format!(" IOPL={}", iopl));
+    }
+    decoded.push_str(" ]");
+    decoded
+}
+
+/// Whether `bytes` holds an amd64 context with extended state appended to it.
+fn is_amd64_xstate(bytes: &[u8], endian: scroll::Endian) -> bool {
+    // The flags follow the six 64-bit p*_home registers.
+    match bytes.pread_with::<u32>(6 * mem::size_of::<u64>(), endian) {
+        Ok(flags) => {
+            ContextFlagsCpu::from_flags(flags).contains(ContextFlagsCpu::CONTEXT_AMD64)
+                && flags & CONTEXT_XSTATE != 0
+        }
+        Err(_) => false,
+    }
+}
+
 impl MinidumpContext {
     /// Return a MinidumpContext given a `MinidumpRawContext`.
     pub fn from_raw(raw: MinidumpRawContext) -> MinidumpContext {
@@ -454,6 +672,31 @@ impl MinidumpContext {
         }
     }
 
+    /// Read a `MinidumpContext` for a thread on a `cpu` system from `bytes`.
+    ///
+    /// `endian` should be the byte order of the minidump. Contexts are normally stored in that
+    /// order, but a dump written on a different machine than the one that crashed may hold a
+    /// context in the crashing CPU's native order instead (e.g. a big-endian PPC context in a
+    /// little-endian minidump). If the context flags don't identify a known CPU when read as
+    /// `endian`, the context is decoded with the opposite byte order, and that's only accepted
+    /// if it's a context for `cpu`.
+    pub fn read_for_cpu(
+        bytes: &[u8],
+        endian: scroll::Endian,
+        cpu: Cpu,
+    ) -> Result<MinidumpContext, ContextError> {
+        MinidumpContext::read(bytes, endian).or_else(|e| {
+            let swapped = match endian {
+                scroll::Endian::Little => scroll::Endian::Big,
+                scroll::Endian::Big => scroll::Endian::Little,
+            };
+            match MinidumpContext::read(bytes, swapped) {
+                Ok(context) if context.cpu() == cpu => Ok(context),
+                _ => Err(e),
+            }
+        })
+    }
+
     /// Read a `MinidumpContext` from `bytes`.
     pub fn read(bytes: &[u8], endian: scroll::Endian) -> Result<MinidumpContext, ContextError> {
         // Some contexts don't have a context flags word at the beginning,
@@ -463,7 +706,9 @@ impl MinidumpContext {
             let ctx: md::CONTEXT_AMD64 = bytes
                 .gread_with(&mut offset, endian)
                 .or(Err(ContextError::ReadFailure))?;
-            if ContextFlagsCpu::from_flags(ctx.context_flags) != ContextFlagsCpu::CONTEXT_AMD64 {
+            if !ContextFlagsCpu::from_flags(ctx.context_flags)
+                .contains(ContextFlagsCpu::CONTEXT_AMD64)
+            {
                 return Err(ContextError::ReadFailure);
             } else {
                 return Ok(MinidumpContext::from_raw(MinidumpRawContext::Amd64(ctx)));
@@ -472,8 +717,8 @@ impl MinidumpContext {
             let ctx: md::CONTEXT_PPC64 = bytes
                 .gread_with(&mut offset, endian)
                 .or(Err(ContextError::ReadFailure))?;
-            if ContextFlagsCpu::from_flags(ctx.context_flags as u32)
-                != ContextFlagsCpu::CONTEXT_PPC64
+            if !ContextFlagsCpu::from_flags(ctx.context_flags as u32)
+                .contains(ContextFlagsCpu::CONTEXT_PPC64)
             {
                 return Err(ContextError::ReadFailure);
             } else {
@@ -483,13 +728,21 @@ impl MinidumpContext {
             let ctx: md::CONTEXT_ARM64_OLD = bytes
                 .gread_with(&mut offset, endian)
                 .or(Err(ContextError::ReadFailure))?;
-            if ContextFlagsCpu::from_flags(ctx.context_flags as u32)
-                != ContextFlagsCpu::CONTEXT_ARM64_OLD
+            if !ContextFlagsCpu::from_flags(ctx.context_flags as u32)
+                .contains(ContextFlagsCpu::CONTEXT_ARM64_OLD)
             {
                 return Err(ContextError::ReadFailure);
             } else {
                 return Ok(MinidumpContext::from_raw(MinidumpRawContext::OldArm64(ctx)));
             }
+        } else if bytes.len() > mem::size_of::<md::CONTEXT_AMD64>()
+            && is_amd64_xstate(bytes, endian)
+        {
+            // Extended state is appended to the context, so it's larger than usual.
+            let ctx: md::CONTEXT_AMD64 = bytes
+                .gread_with(&mut offset, endian)
+                .or(Err(ContextError::ReadFailure))?;
+            return Ok(MinidumpContext::from_raw(MinidumpRawContext::Amd64(ctx)));
         }
 
         // For everything else, read the flags and determine context
@@ -499,46 +752,107 @@ impl MinidumpContext {
             .or(Err(ContextError::ReadFailure))?;
         // Seek back, the flags are also part of the RawContext structs.
         offset = 0;
+        // Contexts can have other flags set along with the CPU type, like
+        // CONTEXT_XSTATE or Windows' CONTEXT_EXCEPTION_REPORTING. Some of those
+        // share bits with other CPU types, so look for the CPU types that don't
+        // collide with them first.
         // TODO: handle dumps with MD_CONTEXT_ARM_OLD
-        match ContextFlagsCpu::from_flags(flags) {
-            ContextFlagsCpu::CONTEXT_X86 => {
-                let ctx: md::CONTEXT_X86 = bytes
-                    .gread_with(&mut offset, endian)
-                    .or(Err(ContextError::ReadFailure))?;
-                Ok(MinidumpContext::from_raw(MinidumpRawContext::X86(ctx)))
-            }
-            ContextFlagsCpu::CONTEXT_PPC => {
-                let ctx: md::CONTEXT_PPC = bytes
-                    .gread_with(&mut offset, endian)
-                    .or(Err(ContextError::ReadFailure))?;
-                Ok(MinidumpContext::from_raw(MinidumpRawContext::Ppc(ctx)))
-            }
-            ContextFlagsCpu::CONTEXT_SPARC => {
-                let ctx: md::CONTEXT_SPARC = bytes
-                    .gread_with(&mut offset, endian)
-                    .or(Err(ContextError::ReadFailure))?;
-                Ok(MinidumpContext::from_raw(MinidumpRawContext::Sparc(ctx)))
-            }
-            ContextFlagsCpu::CONTEXT_ARM => {
-                let ctx: md::CONTEXT_ARM = bytes
-                    .gread_with(&mut offset, endian)
-                    .or(Err(ContextError::ReadFailure))?;
-                Ok(MinidumpContext::from_raw(MinidumpRawContext::Arm(ctx)))
-            }
-            ContextFlagsCpu::CONTEXT_MIPS => {
-                let ctx: md::CONTEXT_MIPS = bytes
-                    .gread_with(&mut offset, endian)
-                    .or(Err(ContextError::ReadFailure))?;
-                Ok(MinidumpContext::from_raw(MinidumpRawContext::Mips(ctx)))
-            }
-            ContextFlagsCpu::CONTEXT_ARM64 => {
-                let ctx: md::CONTEXT_ARM64 = bytes
-                    .gread_with(&mut offset, endian)
-                    .or(Err(ContextError::ReadFailure))?;
-                Ok(MinidumpContext::from_raw(MinidumpRawContext::Arm64(ctx)))
+        let cpu = ContextFlagsCpu::from_flags(flags);
+        if cpu.contains(ContextFlagsCpu::CONTEXT_X86) {
+            let ctx: md::CONTEXT_X86 = bytes
+                .gread_with(&mut offset, endian)
+                .or(Err(ContextError::ReadFailure))?;
+            Ok(MinidumpContext::from_raw(MinidumpRawContext::X86(ctx)))
+        } else if cpu.contains(ContextFlagsCpu::CONTEXT_ARM64) {
+            let ctx: md::CONTEXT_ARM64 = bytes
+                .gread_with(&mut offset, endian)
+                .or(Err(ContextError::ReadFailure))?;
+            Ok(MinidumpContext::from_raw(MinidumpRawContext::Arm64(ctx)))
+        } else if cpu.contains(ContextFlagsCpu::CONTEXT_MIPS) {
+            let ctx: md::CONTEXT_MIPS = bytes
+                .gread_with(&mut offset, endian)
+                .or(Err(ContextError::ReadFailure))?;
+            Ok(MinidumpContext::from_raw(MinidumpRawContext::Mips(ctx)))
+        } else if cpu.contains(ContextFlagsCpu::CONTEXT_MIPS64) {
+            let ctx: md::CONTEXT_MIPS = bytes
+                .gread_with(&mut offset, endian)
+                .or(Err(ContextError::ReadFailure))?;
+            Ok(MinidumpContext::from_raw(MinidumpRawContext::Mips64(ctx)))
+        } else if cpu.contains(ContextFlagsCpu::CONTEXT_PPC) {
+            let ctx: md::CONTEXT_PPC = bytes
+                .gread_with(&mut offset, endian)
+                .or(Err(ContextError::ReadFailure))?;
+            Ok(MinidumpContext::from_raw(MinidumpRawContext::Ppc(ctx)))
+        } else if cpu.contains(ContextFlagsCpu::CONTEXT_SPARC) {
+            let ctx: md::CONTEXT_SPARC = bytes
+                .gread_with(&mut offset, endian)
+                .or(Err(ContextError::ReadFailure))?;
+            Ok(MinidumpContext::from_raw(MinidumpRawContext::Sparc(ctx)))
+        } else if cpu.contains(ContextFlagsCpu::CONTEXT_ARM) {
+            let ctx: md::CONTEXT_ARM = bytes
+                .gread_with(&mut offset, endian)
+                .or(Err(ContextError::ReadFailure))?;
+            Ok(MinidumpContext::from_raw(MinidumpRawContext::Arm(ctx)))
+        } else {
+            Err(ContextError::UnknownCpuContext(flags))
+        }
+    }
+
+    /// Whether extended processor state was saved along with this context.
+    ///
+    /// See [`MinidumpXState`][xstate].
+    ///
+    /// [xstate]: struct.MinidumpXState.html
+    pub fn has_xstate(&self) -> bool {
+        let flags = match self.raw {
+            MinidumpRawContext::Amd64(ref ctx) => ctx.context_flags,
+            MinidumpRawContext::X86(ref ctx) => ctx.context_flags,
+            _ => return false,
+        };
+        flags & CONTEXT_XSTATE != 0
+    }
+
+    /// The YMM registers, from this context's XMM registers and their upper halves in
+    /// `xstate`.
+    ///
+    /// Returns `None` if this isn't an x86 or amd64 context, or `xstate` doesn't have
+    /// the AVX state.
+    pub fn ymm_registers(&self, xstate: &MinidumpXState<'_>) -> Option<Vec<[u8; 32]>> {
+        let (fxsave, count): (&[u8], usize) = match self.raw {
+            MinidumpRawContext::Amd64(ref ctx) => (&ctx.float_save, 16),
+            MinidumpRawContext::X86(ref ctx) => (&ctx.extended_registers, 8),
+            _ => return None,
+        };
+        let upper = xstate.feature(md::XstateFeatureIndex::GSSE_AND_AVX)?;
+        (0..count)
+            .map(|i| {
+                let lower_start = FXSAVE_XMM_OFFSET + i * 16;
+                let mut ymm = [0; 32];
+                ymm[..16].copy_from_slice(fxsave.get(lower_start..lower_start + 16)?);
+                ymm[16..].copy_from_slice(upper.get(i * 16..(i + 1) * 16)?);
+                Some(ymm)
+            })
+            .collect()
+    }
+
+    /// The SIMD and floating-point registers `v0` to `v31` of an aarch64 context.
+    ///
+    /// Returns `None` for other CPUs, or if the context flags say the
+    /// floating-point registers weren't captured. Minidumps only record these
+    /// 128-bit registers: neither Breakpad nor Crashpad define a layout for the
+    /// wider SVE state, so a context with SVE registers is read like any other.
+    pub fn vector_registers(&self) -> Option<Vec<u128>> {
+        let (flags, regs) = match self.raw {
+            MinidumpRawContext::Arm64(ref ctx) => {
+                (u64::from(ctx.context_flags), ctx.float_save.regs)
             }
-            _ => Err(ContextError::UnknownCpuContext),
+            MinidumpRawContext::OldArm64(ref ctx) => (ctx.context_flags, { ctx.float_save }.regs),
+            _ => return None,
+        };
+        if flags & u64::from(CONTEXT_ARM64_FLOATING_POINT) == 0 {
+            return None;
         }
+        Some(regs.to_vec())
     }
 
     pub fn get_instruction_pointer(&self) -> u64 {
@@ -553,7 +867,7 @@ impl MinidumpContext {
             MinidumpRawContext::Ppc64(ref ctx) => ctx.srr0,
             MinidumpRawContext::Sparc(ref ctx) => ctx.pc,
             MinidumpRawContext::X86(ref ctx) => ctx.eip as u64,
-            MinidumpRawContext::Mips(ref ctx) => ctx.epc,
+            MinidumpRawContext::Mips(ref ctx) | MinidumpRawContext::Mips64(ref ctx) => ctx.epc,
         }
     }
 
@@ -579,12 +893,46 @@ impl MinidumpContext {
                 ctx.g_r[md::SparcRegisterNumbers::StackPointer as usize]
             }
             MinidumpRawContext::X86(ref ctx) => ctx.esp as u64,
-            MinidumpRawContext::Mips(ref ctx) => {
+            MinidumpRawContext::Mips(ref ctx) | MinidumpRawContext::Mips64(ref ctx) => {
                 ctx.iregs[md::MipsRegisterNumbers::StackPointer as usize]
             }
         }
     }
 
+    /// The CPU this context is from.
+    pub(crate) fn cpu(&self) -> Cpu {
+        match self.raw {
+            MinidumpRawContext::X86(_) => Cpu::X86,
+            MinidumpRawContext::Amd64(_) => Cpu::X86_64,
+            MinidumpRawContext::Ppc(_) => Cpu::Ppc,
+            MinidumpRawContext::Ppc64(_) => Cpu::Ppc64,
+            MinidumpRawContext::Sparc(_) => Cpu::Sparc,
+            MinidumpRawContext::Arm(_) => Cpu::Arm,
+            MinidumpRawContext::Arm64(_) | MinidumpRawContext::OldArm64(_) => Cpu::Arm64,
+            MinidumpRawContext::Mips(_) => Cpu::Mips,
+            MinidumpRawContext::Mips64(_) => Cpu::Mips64,
+        }
+    }
+
+    /// The size of a pointer, in bytes, on the CPU this context is from.
+    ///
+    /// SPARC contexts are for 64-bit SPARC V9 processes, all their registers are
+    /// 64 bits wide.
+    pub fn pointer_width(&self) -> u64 {
+        match self.raw {
+            MinidumpRawContext::X86(_)
+            | MinidumpRawContext::Ppc(_)
+            | MinidumpRawContext::Arm(_)
+            | MinidumpRawContext::Mips(_) => 4,
+            MinidumpRawContext::Amd64(_)
+            | MinidumpRawContext::Ppc64(_)
+            | MinidumpRawContext::Sparc(_)
+            | MinidumpRawContext::Arm64(_)
+            | MinidumpRawContext::OldArm64(_)
+            | MinidumpRawContext::Mips64(_) => 8,
+        }
+    }
+
     pub fn format_register(&self, reg: &str) -> String {
         match self.raw {
             MinidumpRawContext::Amd64(ref ctx) => ctx.format_register(reg),
@@ -595,21 +943,41 @@ impl MinidumpContext {
             MinidumpRawContext::Ppc64(_) => unimplemented!(),
             MinidumpRawContext::Sparc(_) => unimplemented!(),
             MinidumpRawContext::X86(ref ctx) => ctx.format_register(reg),
-            MinidumpRawContext::Mips(_) => unimplemented!(),
+            // 32-bit MIPS registers are kept sign-extended to 64 bits.
+            MinidumpRawContext::Mips(ref ctx) => {
+                format!("{:#010x}", ctx.get_register_always(reg) as u32)
+            }
+            MinidumpRawContext::Mips64(ref ctx) => ctx.format_register(reg),
         }
     }
 
+    /// The names of the general-purpose registers of this context.
+    ///
+    /// This is empty for CPUs whose registers can't be formatted yet.
     pub fn general_purpose_registers(&self) -> &'static [&'static str] {
         match self.raw {
             MinidumpRawContext::Amd64(_) => &X86_64_REGS[..],
-            MinidumpRawContext::Arm(_) => unimplemented!(),
             MinidumpRawContext::Arm64(_) => &ARM64_REGS[..],
             MinidumpRawContext::OldArm64(_) => &ARM64_REGS[..],
-            MinidumpRawContext::Ppc(_) => unimplemented!(),
-            MinidumpRawContext::Ppc64(_) => unimplemented!(),
-            MinidumpRawContext::Sparc(_) => unimplemented!(),
             MinidumpRawContext::X86(_) => &X86_REGS[..],
-            MinidumpRawContext::Mips(_) => unimplemented!(),
+            MinidumpRawContext::Mips(_) | MinidumpRawContext::Mips64(_) => &MIPS_REGS[..],
+            MinidumpRawContext::Arm(_)
+            | MinidumpRawContext::Ppc(_)
+            | MinidumpRawContext::Ppc64(_)
+            | MinidumpRawContext::Sparc(_) => &[],
+        }
+    }
+
+    /// The general-purpose registers of this context, listed in `order`.
+    ///
+    /// x86 and aarch64 already list their registers conventionally, so only x86-64
+    /// differs between the two orders.
+    pub fn registers_in_order(&self, order: RegisterOrder) -> &'static [&'static str] {
+        match (order, &self.raw) {
+            (RegisterOrder::Conventional, MinidumpRawContext::Amd64(_)) => {
+                &X86_64_CONVENTIONAL_REGS[..]
+            }
+            _ => self.general_purpose_registers(),
         }
     }
 
@@ -672,6 +1040,7 @@ impl MinidumpContext {
   eip                          = {:#x}
   cs                           = {:#x}
   eflags                       = {:#x}
+  eflags (decoded)             = {}
   esp                          = {:#x}
   ss                           = {:#x}
   extended_registers[{:3}]      = 0x"#,
@@ -690,6 +1059,7 @@ impl MinidumpContext {
                     raw.eip,
                     raw.cs,
                     raw.eflags,
+                    decode_eflags(raw.eflags),
                     raw.esp,
                     raw.ss,
                     raw.extended_registers.len(),
@@ -722,6 +1092,7 @@ impl MinidumpContext {
   gs            = {:#x}
   ss            = {:#x}
   eflags        = {:#x}
+  eflags (decoded) = {}
   dr0           = {:#x}
   dr1           = {:#x}
   dr2           = {:#x}
@@ -762,6 +1133,7 @@ impl MinidumpContext {
                     raw.gs,
                     raw.ss,
                     raw.eflags,
+                    decode_eflags(raw.eflags),
                     raw.dr0,
                     raw.dr1,
                     raw.dr2,
@@ -830,10 +1202,14 @@ impl MinidumpContext {
                 write!(
                     f,
                     r#"  cpsr                 = {:#x}
+  cpsr (decoded)       = {}
   float_save.fpsr     = {:#x}
   float_save.fpcr     = {:#x}
 "#,
-                    raw.cpsr, raw.float_save.fpsr, raw.float_save.fpcr
+                    raw.cpsr,
+                    decode_arm64_cpsr(raw.cpsr),
+                    raw.float_save.fpsr,
+                    raw.float_save.fpcr
                 )?;
                 for (i, reg) in raw.float_save.regs.iter().enumerate() {
                     writeln!(f, "  float_save.regs[{:2}] = {:#x}", i, reg)?;
@@ -854,10 +1230,12 @@ impl MinidumpContext {
                 write!(
                     f,
                     r#"  cpsr                 = {:#x}
+  cpsr (decoded)       = {}
   float_save.fpsr     = {:#x}
   float_save.fpcr     = {:#x}
 "#,
                     { raw.cpsr },
+                    decode_arm64_cpsr(raw.cpsr),
                     { raw.float_save }.fpsr,
                     { raw.float_save }.fpcr
                 )?;
@@ -865,8 +1243,58 @@ impl MinidumpContext {
                     writeln!(f, "  float_save.regs[{:2}] = {:#x}", i, reg)?;
                 }
             }
-            MinidumpRawContext::Mips(_) => {
-                unimplemented!();
+            MinidumpRawContext::Mips(ref raw) | MinidumpRawContext::Mips64(ref raw) => {
+                // 32-bit MIPS registers are kept sign-extended to 64 bits.
+                let (name, word): (_, fn(u64) -> u64) = match self.raw {
+                    MinidumpRawContext::Mips(_) => ("CONTEXT_MIPS", |val| val as u32 as u64),
+                    _ => ("CONTEXT_MIPS64", |val| val),
+                };
+                write!(
+                    f,
+                    r#"{}
+  context_flags        = {:#x}
+"#,
+                    name, raw.context_flags
+                )?;
+                for (i, reg) in raw.iregs.iter().enumerate() {
+                    writeln!(f, "  iregs[{:2}]            = {:#x}", i, word(*reg))?;
+                }
+                write!(
+                    f,
+                    r#"  mdhi                 = {:#x}
+  mdlo                 = {:#x}
+"#,
+                    word(raw.mdhi),
+                    word(raw.mdlo)
+                )?;
+                for (i, (hi, lo)) in raw.hi.iter().zip(raw.lo.iter()).enumerate() {
+                    writeln!(f, "  hi[{}]                = {:#x}", i, hi)?;
+                    writeln!(f, "  lo[{}]                = {:#x}", i, lo)?;
+                }
+                write!(
+                    f,
+                    r#"  dsp_control          = {:#x}
+  epc                  = {:#x}
+  badvaddr             = {:#x}
+  status               = {:#x}
+  cause                = {:#x}
+"#,
+                    raw.dsp_control,
+                    word(raw.epc),
+                    word(raw.badvaddr),
+                    raw.status,
+                    raw.cause
+                )?;
+                for (i, reg) in raw.float_save.regs.iter().enumerate() {
+                    writeln!(f, "  float_save.regs[{:2}] = {:#x}", i, reg)?;
+                }
+                write!(
+                    f,
+                    r#"  float_save.fpcsr     = {:#x}
+  float_save.fir       = {:#x}
+"#,
+                    raw.float_save.fpcsr, raw.float_save.fir
+                )?;
             }
         }
         Ok(())
@@ -0,0 +1,248 @@
+// Copyright 2015 Ted Mielczarek. See the COPYRIGHT
+// file at the top-level directory of this distribution.
+
+//! Reading a module's identifiers out of its image header, for modules whose
+//! header was captured in a minidump's memory.
+
+use scroll::ctx::{SizeWith, TryFromCtx};
+use scroll::{Pread, BE, LE};
+use std::convert::TryFrom;
+
+use crate::minidump::{read_codeview_bytes, CodeView, MinidumpMemoryList};
+use minidump_common::format as md;
+
+/// The identifiers found in a module's image header.
+pub(crate) struct ImageIds {
+    /// The module's CodeView record, or the equivalent for non-PE images.
+    pub codeview: Option<CodeView>,
+    /// The PE header's timestamp, which is part of the code identifier.
+    pub time_date_stamp: Option<u32>,
+}
+
+/// The memory of a module image, as far as it was captured.
+struct ImageMemory<'a, 'mdmp> {
+    memory: &'a MinidumpMemoryList<'mdmp>,
+    base: u64,
+}
+
+impl<'a, 'mdmp> ImageMemory<'a, 'mdmp> {
+    /// The `len` bytes at `offset` into the image, if they were all captured.
+    fn bytes(&self, offset: u64, len: usize) -> Option<&'mdmp [u8]> {
+        let address = self.base.checked_add(offset)?;
+        let region = self.memory.memory_at_address(address)?;
+        let start = usize::try_from(address - region.base_address).ok()?;
+        region.bytes.get(start..start.checked_add(len)?)
+    }
+
+    /// Read a `T` at `offset` into the image.
+    fn read<T>(&self, offset: u64, endian: scroll::Endian) -> Option<T>
+    where
+        T: TryFromCtx<'mdmp, scroll::Endian, [u8], Error = scroll::Error>,
+        T: SizeWith<scroll::Endian>,
+    {
+        self.bytes(offset, T::size_with(&endian))?
+            .pread_with(0, endian)
+            .ok()
+    }
+}
+
+/// Read the identifiers of the module named `name` from its image header at
+/// `base`, if the header was captured in `memory`.
+///
+/// PE, ELF and Mach-O images are understood.
+pub(crate) fn read_image_ids(
+    memory: &MinidumpMemoryList<'_>,
+    base: u64,
+    name: &str,
+) -> Option<ImageIds> {
+    let image = ImageMemory { memory, base };
+    read_pe_ids(&image)
+        .or_else(|| read_elf_ids(&image))
+        .or_else(|| read_macho_ids(&image, name))
+}
+
+/// Read the timestamp and CodeView record of a PE image.
+fn read_pe_ids(image: &ImageMemory<'_, '_>) -> Option<ImageIds> {
+    const IMAGE_DIRECTORY_ENTRY_DEBUG: u64 = 6;
+    const IMAGE_DEBUG_TYPE_CODEVIEW: u32 = 2;
+    const DEBUG_DIRECTORY_SIZE: usize = 28;
+
+    if image.bytes(0, 2)? != b"MZ" {
+        return None;
+    }
+    let pe = u64::from(image.read::<u32>(0x3c, LE)?);
+    if image.bytes(pe, 4)? != b"PE\0\0" {
+        return None;
+    }
+    let time_date_stamp = image.read::<u32>(pe + 8, LE)?;
+    // The optional header follows the signature and the 20 byte file header.
+    // Where its data directories start depends on whether it's PE32 or PE32+.
+    let optional = pe + 24;
+    let (count_offset, directories_offset) = match image.read::<u16>(optional, LE)? {
+        0x10b => (92, 96),
+        0x20b => (108, 112),
+        _ => return None,
+    };
+    let directory_count = u64::from(image.read::<u32>(optional + count_offset, LE)?);
+    let codeview = if directory_count > IMAGE_DIRECTORY_ENTRY_DEBUG {
+        let debug = optional + directories_offset + IMAGE_DIRECTORY_ENTRY_DEBUG * 8;
+        let rva = image.read::<u32>(debug, LE)?;
+        let size = image.read::<u32>(debug + 4, LE)?;
+        let entries = image.bytes(u64::from(rva), size as usize)?;
+        entries
+            .chunks_exact(DEBUG_DIRECTORY_SIZE)
+            .find_map(|entry| {
+                if entry.pread_with::<u32>(12, LE).ok()? != IMAGE_DEBUG_TYPE_CODEVIEW {
+                    return None;
+                }
+                let data_size = entry.pread_with::<u32>(16, LE).ok()?;
+                let data_rva = entry.pread_with::<u32>(20, LE).ok()?;
+                let bytes = image.bytes(u64::from(data_rva), data_size as usize)?;
+                match read_codeview_bytes(bytes, LE).ok()? {
+                    CodeView::Unknown(_) => None,
+                    codeview => Some(codeview),
+                }
+            })
+    } else {
+        None
+    };
+    Some(ImageIds {
+        codeview,
+        time_date_stamp: Some(time_date_stamp),
+    })
+}
+
+/// Read the GNU build id of an ELF image.
+fn read_elf_ids(image: &ImageMemory<'_, '_>) -> Option<ImageIds> {
+    const PT_LOAD: u32 = 1;
+    const PT_NOTE: u32 = 4;
+
+    let ident = image.bytes(0, 16)?;
+    if &ident[..4] != b"\x7fELF" {
+        return None;
+    }
+    let endian = match ident[5] {
+        1 => LE,
+        2 => BE,
+        _ => return None,
+    };
+    let is_64 = match ident[4] {
+        1 => false,
+        2 => true,
+        _ => return None,
+    };
+    let (phoff, phentsize, phnum) = if is_64 {
+        (
+            image.read::<u64>(0x20, endian)?,
+            image.read::<u16>(0x36, endian)?,
+            image.read::<u16>(0x38, endian)?,
+        )
+    } else {
+        (
+            u64::from(image.read::<u32>(0x1c, endian)?),
+            image.read::<u16>(0x2a, endian)?,
+            image.read::<u16>(0x2c, endian)?,
+        )
+    };
+    // The type, address and size of each segment.
+    let segments = (0..u64::from(phnum))
+        .map(|i| {
+            let header = phoff.checked_add(i * u64::from(phentsize))?;
+            let segment_type = image.read::<u32>(header, endian)?;
+            if is_64 {
+                Some((
+                    segment_type,
+                    image.read::<u64>(header.checked_add(16)?, endian)?,
+                    image.read::<u64>(header.checked_add(32)?, endian)?,
+                ))
+            } else {
+                Some((
+                    segment_type,
+                    u64::from(image.read::<u32>(header.checked_add(8)?, endian)?),
+                    u64::from(image.read::<u32>(header.checked_add(16)?, endian)?),
+                ))
+            }
+        })
+        .collect::<Option<Vec<_>>>()?;
+    // The first loadable segment is the one mapped at the module's base.
+    let (_, load_address, _) = segments.iter().find(|segment| segment.0 == PT_LOAD)?;
+    let load_address = load_address & !0xfff;
+    let build_id = segments
+        .iter()
+        .filter(|segment| segment.0 == PT_NOTE)
+        .find_map(|&(_, address, size)| {
+            let offset = address.checked_sub(load_address)?;
+            let notes = image.bytes(offset, usize::try_from(size).ok()?)?;
+            find_build_id(notes, endian)
+        })?;
+    Some(ImageIds {
+        codeview: Some(CodeView::Elf(md::CV_INFO_ELF {
+            cv_signature: md::CvSignature::Elf as u32,
+            build_id,
+        })),
+        time_date_stamp: None,
+    })
+}
+
+/// Find the GNU build id note among the ELF `notes`.
+fn find_build_id(notes: &[u8], endian: scroll::Endian) -> Option<Vec<u8>> {
+    const NT_GNU_BUILD_ID: u32 = 3;
+    // Note names and contents are padded to four bytes.
+    let align = |n: usize| n.checked_add(3).map(|n| n & !3);
+
+    let mut offset = 0;
+    while offset < notes.len() {
+        let name_size = notes.gread_with::<u32>(&mut offset, endian).ok()? as usize;
+        let desc_size = notes.gread_with::<u32>(&mut offset, endian).ok()? as usize;
+        let note_type = notes.gread_with::<u32>(&mut offset, endian).ok()?;
+        let name = notes.get(offset..offset.checked_add(name_size)?)?;
+        offset = align(offset + name_size)?;
+        let desc = notes.get(offset..offset.checked_add(desc_size)?)?;
+        offset = align(offset + desc_size)?;
+        if note_type == NT_GNU_BUILD_ID && name == b"GNU\0" {
+            return Some(desc.to_vec());
+        }
+    }
+    None
+}
+
+/// Read the UUID of a Mach-O image, stored the way Breakpad does: as the GUID of
+/// a PDB 7.0 record, named after the module's file.
+fn read_macho_ids(image: &ImageMemory<'_, '_>, name: &str) -> Option<ImageIds> {
+    const LC_UUID: u32 = 0x1b;
+
+    let (endian, header_size) = match image.read::<u32>(0, LE)? {
+        0xfeed_face => (LE, 28),
+        0xfeed_facf => (LE, 32),
+        0xcefa_edfe => (BE, 28),
+        0xcffa_edfe => (BE, 32),
+        _ => return None,
+    };
+    let command_count = image.read::<u32>(16, endian)?;
+    let mut offset = header_size;
+    for _ in 0..command_count {
+        let command = image.read::<u32>(offset, endian)?;
+        let command_size = image.read::<u32>(offset + 4, endian)?;
+        if command == LC_UUID {
+            // Reading the UUID as a big-endian GUID formats it like the UUID.
+            let signature = image.bytes(offset + 8, 16)?.pread_with(0, BE).ok()?;
+            let file_name = name.rsplit('/').next().unwrap_or(name);
+            let mut pdb_file_name = file_name.as_bytes().to_vec();
+            pdb_file_name.push(0);
+            return Some(ImageIds {
+                codeview: Some(CodeView::Pdb70(md::CV_INFO_PDB70 {
+                    cv_signature: md::CvSignature::Pdb70 as u32,
+                    signature,
+                    age: 0,
+                    pdb_file_name,
+                })),
+                time_date_stamp: None,
+            });
+        }
+        if command_size == 0 {
+            return None;
+        }
+        offset += u64::from(command_size);
+    }
+    None
+}
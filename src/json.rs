@@ -0,0 +1,401 @@
+// Copyright 2015 Ted Mielczarek. See the COPYRIGHT
+// file at the top-level directory of this distribution.
+
+//! A faithful JSON dump of a minidump's streams, as this crate parses them.
+
+use num_traits::FromPrimitive;
+use serde_json::{json, Value};
+use std::io::Write;
+use std::ops::Deref;
+
+use crate::minidump::*;
+use minidump_common::format::{MINIDUMP_ANNOTATION, MINIDUMP_STREAM_TYPE};
+use minidump_common::traits::Module;
+
+impl<'a, T> Minidump<'a, T>
+where
+    T: Deref<Target = [u8]> + 'a,
+{
+    /// Describe the minidump's header and every stream in it as JSON.
+    ///
+    /// Unlike the output of `minidump-processor`, this isn't a crash report: it's
+    /// what each stream contains, as this crate parses it. Every entry of the stream
+    /// directory is listed, each with its `type` and `size`, in directory order
+    /// except that entries of a type that appears more than once are listed
+    /// together. Streams of the types this crate parses also have a `name` and
+    /// their contents, under a key named after the stream, or an `error` if they
+    /// couldn't be parsed. Other streams look like
+    /// `{ "type": N, "size": M, "unparsed": true }`.
+    ///
+    /// Addresses, flags and codes are written as hex strings.
+    pub fn to_json(&'a self) -> Value {
+        let header = &self.header;
+        json!({
+            "header": {
+                "signature": hex(header.signature),
+                "version": hex(header.version),
+                "stream_count": header.stream_count,
+                "stream_directory_rva": hex(header.stream_directory_rva),
+                "checksum": hex(header.checksum),
+                "time_date_stamp": header.time_date_stamp,
+                "flags": hex(header.flags),
+            },
+            "streams": self
+                .stream_types()
+                .into_iter()
+                .flat_map(|stream_type| self.streams_json(stream_type))
+                .collect::<Vec<_>>(),
+        })
+    }
+
+    /// Write the JSON description of the minidump from [`to_json`][to_json] to `f`.
+    ///
+    /// [to_json]: #method.to_json
+    pub fn print_json<W: Write>(
+        &'a self,
+        f: &mut W,
+        pretty: bool,
+    ) -> Result<(), serde_json::Error> {
+        let json = self.to_json();
+        if pretty {
+            serde_json::to_writer_pretty(f, &json)
+        } else {
+            serde_json::to_writer(f, &json)
+        }
+    }
+
+    /// Describe every stream of `stream_type` in the directory.
+    fn streams_json(&'a self, stream_type: u32) -> Vec<Value> {
+        use MINIDUMP_STREAM_TYPE::*;
+
+        let known = MINIDUMP_STREAM_TYPE::from_u32(stream_type);
+        let (key, contents) = match known {
+            Some(ThreadListStream) => ("threads", self.contents(thread_list_json)),
+            Some(ModuleListStream) => ("modules", self.contents(module_list_json)),
+            Some(MemoryListStream) => ("memory", self.contents(memory_list_json)),
+            Some(ExceptionStream) => ("exception", self.contents(exception_json)),
+            Some(SystemInfoStream) => ("system_info", self.contents(system_info_json)),
+            Some(HandleDataStream) => ("handles", self.contents(handle_data_json)),
+            Some(UnloadedModuleListStream) => {
+                ("unloaded_modules", self.contents(unloaded_module_list_json))
+            }
+            Some(MiscInfoStream) => ("misc_info", self.contents(misc_info_json)),
+            Some(MemoryInfoListStream) => ("memory_info", self.contents(memory_info_list_json)),
+            Some(ThreadNamesStream) => ("thread_names", self.contents(thread_names_json)),
+            Some(BreakpadInfoStream) => ("breakpad_info", self.contents(breakpad_info_json)),
+            Some(AssertionInfoStream) => ("assertion", self.contents(assertion_json)),
+            Some(LinuxCmdLine) => ("cmdline", self.contents(linux_cmdline_json)),
+            Some(LinuxEnviron) => ("environ", self.contents(linux_environ_json)),
+            Some(LinuxMaps) => ("maps", self.contents(linux_maps_json)),
+            Some(CrashpadInfoStream) => (
+                "crashpad_info",
+                self.contents(|info| crashpad_info_json(info, self.bytes(), self.endian)),
+            ),
+            _ => ("", vec![]),
+        };
+        let mut contents = contents.into_iter();
+        self.get_raw_streams(stream_type)
+            .into_iter()
+            .map(|bytes| {
+                // Each entry's contents, in the same order.
+                let contents = contents.next();
+                let size = match bytes {
+                    Ok(bytes) => bytes.len(),
+                    Err(e) => {
+                        return json!({
+                            "type": stream_type,
+                            "error": e.to_string(),
+                        })
+                    }
+                };
+                let contents = match contents {
+                    Some(contents) => contents,
+                    None => {
+                        return json!({
+                            "type": stream_type,
+                            "size": size,
+                            "unparsed": true,
+                        })
+                    }
+                };
+                let mut json = json!({
+                    "type": stream_type,
+                    "name": format!("{:?}", known.unwrap()),
+                    "size": size,
+                });
+                match contents {
+                    Ok(contents) => json[key] = contents,
+                    Err(e) => json["error"] = json!(e.to_string()),
+                }
+                json
+            })
+            .collect()
+    }
+
+    /// Read every stream `S` and describe each with `describe`.
+    fn contents<S, F>(&'a self, describe: F) -> Vec<Result<Value, Error>>
+    where
+        S: MinidumpStream<'a>,
+        F: Fn(&S) -> Value,
+    {
+        self.get_streams::<S>()
+            .into_iter()
+            .map(|stream| stream.map(|stream| describe(&stream)))
+            .collect()
+    }
+}
+
+fn hex<N: std::fmt::LowerHex>(n: N) -> String {
+    format!("{:#x}", n)
+}
+
+fn context_json(context: &MinidumpContext) -> Value {
+    let registers = context
+        .general_purpose_registers()
+        .iter()
+        .map(|&reg| (reg.to_owned(), json!(context.format_register(reg))))
+        .collect::<serde_json::Map<_, _>>();
+    json!({
+        "instruction_pointer": hex(context.get_instruction_pointer()),
+        "stack_pointer": hex(context.get_stack_pointer()),
+        "registers": registers,
+    })
+}
+
+fn memory_json(memory: &MinidumpMemory<'_>) -> Value {
+    json!({
+        "base_address": hex(memory.base_address),
+        "size": memory.size,
+    })
+}
+
+fn thread_list_json(thread_list: &MinidumpThreadList<'_>) -> Value {
+    json!(thread_list
+        .threads
+        .iter()
+        .map(|thread| json!({
+            "thread_id": thread.raw.thread_id,
+            "suspend_count": thread.suspend_count(),
+            "priority_class": thread.priority_class(),
+            "priority": thread.priority(),
+            "teb": hex(thread.raw.teb),
+            "stack": thread.stack.as_ref().map(memory_json),
+            "context": thread.context.as_ref().map(context_json),
+            "context_error": thread.context_error.as_ref().map(|e| match *e {
+                ContextError::ReadFailure => "read failure".to_owned(),
+                ContextError::UnknownCpuContext(flags) => {
+                    format!("unknown CPU context, flags {:#010x}", flags)
+                }
+            }),
+        }))
+        .collect::<Vec<_>>())
+}
+
+fn module_json<M: Module>(module: &M) -> Value {
+    json!({
+        "base_address": hex(module.base_address()),
+        "size": module.size(),
+        "code_file": module.code_file(),
+        "code_identifier": module.code_identifier(),
+        "debug_file": module.debug_file(),
+        "debug_identifier": module.debug_identifier(),
+        "version": module.version(),
+    })
+}
+
+fn module_list_json(module_list: &MinidumpModuleList) -> Value {
+    json!(module_list.iter().map(module_json).collect::<Vec<_>>())
+}
+
+fn unloaded_module_list_json(module_list: &MinidumpUnloadedModuleList) -> Value {
+    json!(module_list.iter().map(module_json).collect::<Vec<_>>())
+}
+
+fn memory_list_json(memory_list: &MinidumpMemoryList<'_>) -> Value {
+    json!(memory_list.iter().map(memory_json).collect::<Vec<_>>())
+}
+
+fn memory_info_list_json(memory_info_list: &MinidumpMemoryInfoList) -> Value {
+    json!(memory_info_list
+        .iter()
+        .map(|info| json!({
+            "base_address": hex(info.raw.base_address),
+            "allocation_base": hex(info.raw.allocation_base),
+            "allocation_protection": hex(info.raw.allocation_protection),
+            "region_size": info.raw.region_size,
+            "state": hex(info.raw.state),
+            "protection": hex(info.raw.protection),
+            "type": hex(info.raw._type),
+        }))
+        .collect::<Vec<_>>())
+}
+
+fn exception_json(exception: &MinidumpException) -> Value {
+    let record = &exception.raw.exception_record;
+    let parameters = record
+        .exception_information
+        .iter()
+        .take(record.number_parameters as usize)
+        .map(|&parameter| hex(parameter))
+        .collect::<Vec<_>>();
+    json!({
+        "thread_id": exception.thread_id,
+        "exception_code": hex(record.exception_code),
+        "exception_flags": hex(record.exception_flags),
+        "exception_record": hex(record.exception_record),
+        "exception_address": hex(record.exception_address),
+        "parameters": parameters,
+        "context": exception.context.as_ref().map(context_json),
+    })
+}
+
+fn system_info_json(system_info: &MinidumpSystemInfo) -> Value {
+    let raw = &system_info.raw;
+    json!({
+        "processor_architecture": raw.processor_architecture,
+        "processor_level": raw.processor_level,
+        "processor_revision": hex(raw.processor_revision),
+        "number_of_processors": raw.number_of_processors,
+        "product_type": raw.product_type,
+        "major_version": raw.major_version,
+        "minor_version": raw.minor_version,
+        "build_number": raw.build_number,
+        "platform_id": hex(raw.platform_id),
+        "suite_mask": hex(raw.suite_mask),
+        "csd_version": system_info.csd_version(),
+        "cpu_info": system_info.cpu_info(),
+        "os": system_info.os.long_name(),
+        "cpu": system_info.cpu.to_string(),
+    })
+}
+
+fn misc_info_json(misc_info: &MinidumpMiscInfo) -> Value {
+    let raw = &misc_info.raw;
+    json!({
+        "flags1": raw.flags1().map(|&flags| hex(flags)),
+        "process_id": raw.process_id(),
+        "process_create_time": misc_info.process_create_time().map(|time| time.to_rfc3339()),
+        "process_user_time": misc_info.process_user_time().map(|time| time.as_secs()),
+        "process_kernel_time": misc_info.process_kernel_time().map(|time| time.as_secs()),
+        "processor_max_mhz": raw.processor_max_mhz(),
+        "processor_current_mhz": raw.processor_current_mhz(),
+        "processor_mhz_limit": raw.processor_mhz_limit(),
+        "process_integrity_level": raw.process_integrity_level().map(|&level| hex(level)),
+        "process_execute_flags": raw.process_execute_flags().map(|&flags| hex(flags)),
+        "protected_process": misc_info.protected_process(),
+        "time_zone_id": raw.time_zone_id(),
+        "build_string": misc_info.build_string(),
+        "dbg_bld_str": misc_info.dbg_bld_str(),
+        "process_cookie": raw.process_cookie().map(|&cookie| hex(cookie)),
+    })
+}
+
+fn handle_data_json(handle_data: &MinidumpHandleDataStream) -> Value {
+    json!(handle_data
+        .iter()
+        .map(|handle| json!({
+            "handle": hex(handle.handle()),
+            "type_name": handle.type_name,
+            "object_name": handle.object_name,
+            "attributes": hex(handle.attributes()),
+            "granted_access": hex(handle.granted_access()),
+            "handle_count": handle.handle_count(),
+            "pointer_count": handle.pointer_count(),
+        }))
+        .collect::<Vec<_>>())
+}
+
+fn thread_names_json(thread_names: &MinidumpThreadNames) -> Value {
+    json!(thread_names
+        .iter()
+        .map(|(thread_id, name)| json!({
+            "thread_id": thread_id,
+            "name": name,
+        }))
+        .collect::<Vec<_>>())
+}
+
+fn breakpad_info_json(breakpad_info: &MinidumpBreakpadInfo) -> Value {
+    json!({
+        "dump_thread_id": breakpad_info.dump_thread_id,
+        "requesting_thread_id": breakpad_info.requesting_thread_id,
+    })
+}
+
+fn assertion_json(assertion: &MinidumpAssertion) -> Value {
+    json!({
+        "expression": assertion.expression(),
+        "function": assertion.function(),
+        "file": assertion.file(),
+        "line": assertion.raw.line,
+        "type": assertion.raw._type,
+    })
+}
+
+fn linux_cmdline_json(cmdline: &MinidumpLinuxCmdLine) -> Value {
+    json!(cmdline.args)
+}
+
+fn linux_environ_json(environ: &MinidumpLinuxEnviron) -> Value {
+    json!(environ
+        .vars
+        .iter()
+        .map(|(name, value)| json!({
+            "name": name,
+            "value": value,
+        }))
+        .collect::<Vec<_>>())
+}
+
+fn linux_maps_json(maps: &MinidumpLinuxMaps) -> Value {
+    json!(maps
+        .iter()
+        .map(|map| json!({
+            "base_address": hex(map.base_address),
+            "end_address": hex(map.end_address),
+            "permissions": map.permissions,
+            "offset": hex(map.offset),
+            "path": map.path,
+        }))
+        .collect::<Vec<_>>())
+}
+
+fn crashpad_info_json(
+    crashpad_info: &MinidumpCrashpadInfo,
+    all: &[u8],
+    endian: scroll::Endian,
+) -> Value {
+    // Annotations that aren't strings are described by their type and value's
+    // bytes, as the minidump doesn't say how to read them.
+    let annotation_json = |annotation: &MinidumpAnnotation| match *annotation {
+        MinidumpAnnotation::String(ref string) => json!(string),
+        MinidumpAnnotation::Invalid => json!({
+            "type": MINIDUMP_ANNOTATION::TYPE_INVALID,
+        }),
+        MinidumpAnnotation::UserDefined(ref raw) | MinidumpAnnotation::Unsupported(ref raw) => {
+            json!({
+                "type": raw.ty,
+                "value": read_byte_array(raw.value as usize, all, endian).map(bytes_to_hex),
+            })
+        }
+    };
+    json!({
+        "report_id": crashpad_info.raw.report_id.to_string(),
+        "client_id": crashpad_info.raw.client_id.to_string(),
+        "simple_annotations": crashpad_info.simple_annotations,
+        "module_list": crashpad_info
+            .module_list
+            .iter()
+            .map(|module| json!({
+                "module_index": module.module_index,
+                "list_annotations": module.list_annotations,
+                "simple_annotations": module.simple_annotations,
+                "annotation_objects": module
+                    .annotation_objects
+                    .iter()
+                    .map(|(name, annotation)| (name.clone(), annotation_json(annotation)))
+                    .collect::<serde_json::Map<_, _>>(),
+            }))
+            .collect::<Vec<_>>(),
+    })
+}
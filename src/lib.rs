@@ -27,7 +27,10 @@ doc_comment::doctest!("../README.md");
 pub use scroll::Endian;
 
 mod context;
+mod image;
 mod iostuff;
+#[cfg(feature = "json")]
+mod json;
 mod minidump;
 
 pub use minidump_common::format;
@@ -11,20 +11,25 @@ use scroll::ctx::{SizeWith, TryFromCtx};
 use scroll::{self, Pread, BE, LE};
 use std::borrow::Cow;
 use std::boxed::Box;
+use std::cmp;
 use std::collections::BTreeMap;
 use std::collections::HashMap;
+use std::collections::HashSet;
 use std::fmt;
 use std::fs::File;
 use std::io;
 use std::io::prelude::*;
+use std::io::SeekFrom;
 use std::iter;
 use std::marker::PhantomData;
 use std::mem;
 use std::ops::Deref;
 use std::path::Path;
 use std::str;
+use std::time::Duration;
 
 pub use crate::context::*;
+use crate::image;
 use crate::system_info::{Cpu, Os};
 use minidump_common::format as md;
 use minidump_common::format::{CvSignature, MINIDUMP_STREAM_TYPE};
@@ -35,8 +40,10 @@ use range_map::{Range, RangeMap};
 ///
 /// The `Minidump` struct represents the parsed header and
 /// indices contained at the start of a minidump file. It can be instantiated
-/// by calling the [`Minidump::read`][read] or
-/// [`Minidump::read_path`][read_path] methods.
+/// by calling the [`Minidump::read`][read] or [`Minidump::read_path`][read_path]
+/// methods. A minidump embedded in a larger buffer can be read in place with
+/// [`Minidump::read_at`][read_at], and one in any `Read + Seek` can be read on
+/// demand with [`Minidump::read_from`][read_from].
 ///
 /// # Examples
 ///
@@ -51,12 +58,16 @@ use range_map::{Range, RangeMap};
 ///
 /// [read]: struct.Minidump.html#method.read
 /// [read_path]: struct.Minidump.html#method.read_path
+/// [read_from]: struct.Minidump.html#method.read_from
+/// [read_at]: struct.Minidump.html#method.read_at
 #[derive(Debug)]
 pub struct Minidump<'a, T>
 where
     T: Deref<Target = [u8]> + 'a,
 {
     data: T,
+    /// Where the minidump starts in `data`.
+    offset: usize,
     /// The raw minidump header from the file.
     pub header: md::MINIDUMP_HEADER,
     streams: HashMap<u32, (u32, md::MINIDUMP_DIRECTORY)>,
@@ -87,6 +98,21 @@ pub enum Error {
         expected, actual
     )]
     StreamSizeMismatch { expected: usize, actual: usize },
+    #[fail(
+        display = "Stream truncated: expected {} bytes, found {} bytes",
+        expected, actual
+    )]
+    StreamTruncated { expected: usize, actual: usize },
+    #[fail(
+        display = "Module count mismatch: {} modules declared, {} present",
+        declared, actual
+    )]
+    ModuleCountMismatch { declared: usize, actual: usize },
+    #[fail(
+        display = "Module list too large: {} modules using {} bytes",
+        modules, bytes
+    )]
+    ModuleListTooLarge { modules: usize, bytes: usize },
     #[fail(display = "Stream not found")]
     StreamNotFound,
     #[fail(display = "Module read failure")]
@@ -99,9 +125,127 @@ pub enum Error {
     CodeViewReadFailure,
 }
 
-/* TODO
-pub struct MinidumpMemoryInfoList;
-*/
+/// Why a stream couldn't be gotten from a `Minidump`.
+///
+/// Unlike `Error`, this keeps a stream that's absent apart from one that's
+/// present but corrupt. See [`Minidump::get_stream_checked`][checked].
+///
+/// [checked]: struct.Minidump.html#method.get_stream_checked
+#[derive(Debug, Fail, PartialEq)]
+pub enum StreamError {
+    /// The minidump doesn't contain the stream.
+    #[fail(display = "Stream not found")]
+    StreamNotFound,
+    /// The stream is present but couldn't be read.
+    #[fail(display = "Error reading stream: {}", _0)]
+    StreamReadFailure(Error),
+    /// The stream is present but has a version we don't understand.
+    #[fail(display = "Stream version mismatch")]
+    VersionMismatch,
+}
+
+impl From<Error> for StreamError {
+    fn from(err: Error) -> StreamError {
+        match err {
+            Error::StreamNotFound => StreamError::StreamNotFound,
+            Error::VersionMismatch => StreamError::VersionMismatch,
+            err => StreamError::StreamReadFailure(err),
+        }
+    }
+}
+
+/// How serious a problem found by [`Minidump::validate`][validate] is.
+///
+/// [validate]: struct.Minidump.html#method.validate
+#[derive(Clone, Copy, Debug, PartialEq)]
+pub enum Severity {
+    /// The minidump can still be processed, but some of its contents were ignored.
+    Warning,
+    /// Processing the minidump will fail or give wrong results.
+    Error,
+}
+
+/// A structural problem found by [`Minidump::validate`][validate].
+///
+/// [validate]: struct.Minidump.html#method.validate
+#[derive(Clone, Debug, Fail, PartialEq)]
+pub enum ValidationIssue {
+    /// More than one directory entry has this stream type. Only one of them is read.
+    #[fail(display = "Stream type {:#x} appears {} times", stream_type, count)]
+    DuplicateStream { stream_type: u32, count: usize },
+    /// A directory entry's data extends past the end of the minidump.
+    #[fail(
+        display = "Stream type {:#x} at {:#x} with size {:#x} is past the end of the minidump",
+        stream_type, rva, data_size
+    )]
+    StreamOutOfBounds {
+        stream_type: u32,
+        rva: u32,
+        data_size: u32,
+    },
+    /// A stream needed to process the minidump is missing.
+    #[fail(display = "Missing required stream {:?}", _0)]
+    MissingStream(MINIDUMP_STREAM_TYPE),
+    /// A module overlaps another module. It's left out of address lookups.
+    #[fail(display = "Module {} at {:#x} overlaps another module", name, base)]
+    OverlappingModule { name: String, base: u64 },
+    /// A memory region overlaps another region. It's left out of address lookups.
+    #[fail(display = "Memory region at {:#x} overlaps another region", _0)]
+    OverlappingMemory(u64),
+    /// A thread's context is for a different CPU than the system info says.
+    ///
+    /// This is only a warning for a 32-bit context on the matching 64-bit CPU,
+    /// such as a WOW64 thread.
+    #[fail(
+        display = "Thread {} has a {} context, but the system info CPU is {}",
+        thread_id, context_cpu, cpu
+    )]
+    ContextCpuMismatch {
+        thread_id: u32,
+        context_cpu: Cpu,
+        cpu: Cpu,
+    },
+}
+
+impl ValidationIssue {
+    /// How serious this problem is.
+    pub fn severity(&self) -> Severity {
+        match *self {
+            ValidationIssue::DuplicateStream { .. }
+            | ValidationIssue::OverlappingModule { .. }
+            | ValidationIssue::OverlappingMemory(_) => Severity::Warning,
+            ValidationIssue::ContextCpuMismatch {
+                context_cpu, cpu, ..
+            } => match (context_cpu, cpu) {
+                (Cpu::X86, Cpu::X86_64) | (Cpu::Arm, Cpu::Arm64) => Severity::Warning,
+                _ => Severity::Error,
+            },
+            ValidationIssue::StreamOutOfBounds { .. } | ValidationIssue::MissingStream(_) => {
+                Severity::Error
+            }
+        }
+    }
+}
+
+/// The state of a region of the process's address space.
+#[derive(Debug, Clone)]
+pub struct MinidumpMemoryInfo {
+    /// The `MINIDUMP_MEMORY_INFO` direct from the minidump file.
+    pub raw: md::MINIDUMP_MEMORY_INFO,
+}
+
+/// A description of the whole address space of the process that wrote the minidump.
+///
+/// Unlike a `MinidumpMemoryList`, this includes regions whose contents weren't
+/// captured, as well as unallocated ones.
+#[derive(Debug, Clone)]
+pub struct MinidumpMemoryInfoList {
+    /// The regions, in the order they were stored in the minidump.
+    regions: Vec<MinidumpMemoryInfo>,
+    /// Map from address range to index in regions. Use
+    /// `MinidumpMemoryInfoList::memory_info_at_address`.
+    regions_by_addr: RangeMap<u64, usize>,
+}
 
 /// The fundamental unit of data in a `Minidump`.
 pub trait MinidumpStream<'a>: Sized {
@@ -141,13 +285,100 @@ pub struct MinidumpModule {
     pub misc_info: Option<md::IMAGE_DEBUG_MISC>,
 }
 
+/// The version numbers and flags of a Windows module, from its `VS_FIXEDFILEINFO`.
+///
+/// See [`MinidumpModule::version_info`][version_info].
+///
+/// [version_info]: struct.MinidumpModule.html#method.version_info
+#[derive(Copy, Clone, Debug, PartialEq, Eq)]
+pub struct ModuleVersionInfo {
+    /// The file's version, most significant part first.
+    pub file_version: [u16; 4],
+    /// The version of the product the file is distributed with, most significant
+    /// part first.
+    pub product_version: [u16; 4],
+    /// The flags that `file_flags_mask` says are valid.
+    pub file_flags: md::FileInfoFlags,
+    /// The operating system the file was designed for, a `VOS_*` value.
+    pub file_os: u32,
+    /// The kind of file, a [`FileInfoType`][file_type] value.
+    ///
+    /// [file_type]: ../minidump_common/format/enum.FileInfoType.html
+    pub file_type: u32,
+    /// The function of the file, for drivers and fonts.
+    pub file_subtype: u32,
+}
+
+impl ModuleVersionInfo {
+    /// The file version as `a.b.c.d`, the way [`Module::version`][version] gives it.
+    ///
+    /// [version]: trait.Module.html#tymethod.version
+    pub fn file_version_string(&self) -> String {
+        version_string(self.file_version)
+    }
+
+    /// The product version as `a.b.c.d`.
+    pub fn product_version_string(&self) -> String {
+        version_string(self.product_version)
+    }
+}
+
+/// Split the two halves of a `VS_FIXEDFILEINFO` version into its four parts.
+fn version_parts(hi: u32, lo: u32) -> [u16; 4] {
+    [
+        (hi >> 16) as u16,
+        (hi & 0xffff) as u16,
+        (lo >> 16) as u16,
+        (lo & 0xffff) as u16,
+    ]
+}
+
+fn version_string(parts: [u16; 4]) -> String {
+    format!("{}.{}.{}.{}", parts[0], parts[1], parts[2], parts[3])
+}
+
+/// Limits on the size of a module list, to bound the resources spent parsing it.
+///
+/// Real minidumps contain at most a few thousand modules, so the defaults only cut short
+/// module lists that are malformed or adversarial. See
+/// [`MinidumpModuleList::read_with_limits`][read].
+///
+/// [read]: struct.MinidumpModuleList.html#method.read_with_limits
+#[derive(Copy, Clone, Debug, PartialEq)]
+pub struct ModuleListLimits {
+    /// The maximum number of modules.
+    pub max_modules: usize,
+    /// The maximum number of bytes used by the module list: the stream itself plus each
+    /// module's CodeView and miscellaneous debug records.
+    pub max_bytes: usize,
+}
+
+impl Default for ModuleListLimits {
+    fn default() -> ModuleListLimits {
+        ModuleListLimits {
+            max_modules: 65536,
+            max_bytes: 64 * 1024 * 1024,
+        }
+    }
+}
+
 /// A list of `MinidumpModule`s contained in a `Minidump`.
 #[derive(Debug, Clone)]
 pub struct MinidumpModuleList {
     /// The modules, in the order they were stored in the minidump.
     modules: Vec<MinidumpModule>,
+    /// The number of modules the stream header claimed to contain.
+    declared_count: usize,
+    /// If the module list exceeded its `ModuleListLimits`, the number of modules
+    /// present and the number of bytes they used.
+    too_large: Option<(usize, usize)>,
     /// Map from address range to index in modules. Use `MinidumpModuleList::module_at_address`.
     modules_by_addr: RangeMap<u64, usize>,
+    /// Indices in modules of the modules left out of `modules_by_addr` because they
+    /// overlapped another module.
+    overlapping: Vec<usize>,
+    /// The address the process image was loaded at, if known.
+    image_base: Option<u64>,
 }
 
 /// An executable or shared library that was once loaded into the process, but was unloaded
@@ -177,6 +408,9 @@ pub struct MinidumpThread<'a> {
     pub raw: md::MINIDUMP_THREAD,
     /// The CPU context for the thread, if present.
     pub context: Option<MinidumpContext>,
+    /// Why the thread's CPU context couldn't be read, if it was present but
+    /// `context` is `None`.
+    pub context_error: Option<ContextError>,
     /// The stack memory for the thread, if present.
     pub stack: Option<MinidumpMemory<'a>>,
 }
@@ -190,6 +424,135 @@ pub struct MinidumpThreadList<'a> {
     thread_ids: HashMap<u32, usize>,
 }
 
+/// A thread, along with what the rest of a `Minidump` records about it.
+///
+/// See [`Minidump::threads`][threads].
+///
+/// [threads]: struct.Minidump.html#method.threads
+#[derive(Debug, Clone)]
+pub struct ThreadView<'a> {
+    /// The thread's id.
+    pub id: u32,
+    /// The thread's name, if the minidump has a thread names stream that names it.
+    pub name: Option<String>,
+    /// The CPU context for the thread, if present.
+    pub context: Option<MinidumpContext>,
+    /// The stack memory for the thread, from the thread list or the memory list.
+    pub stack_memory: Option<MinidumpMemory<'a>>,
+    /// The address of the thread's Thread Environment Block, if known.
+    pub teb: Option<u64>,
+}
+
+/// A module found in the Windows loader's list of modules in a process's memory.
+///
+/// See [`Minidump::peb_modules`][peb_modules].
+///
+/// [peb_modules]: struct.Minidump.html#method.peb_modules
+#[derive(Debug, Clone, PartialEq)]
+pub struct PebModule {
+    /// The address the module was loaded at.
+    pub base_address: u64,
+    /// The size of the module's image in memory.
+    pub size: u64,
+    /// The full path of the module, if its memory was dumped.
+    pub name: Option<String>,
+}
+
+/// Offsets of the fields that are read from the Windows loader's data structures.
+struct LdrLayout {
+    /// `Ldr` in the PEB.
+    peb_ldr: u64,
+    /// `InLoadOrderModuleList` in `PEB_LDR_DATA`.
+    in_load_order_module_list: u64,
+    /// `DllBase` in `LDR_DATA_TABLE_ENTRY`.
+    dll_base: u64,
+    /// `SizeOfImage` in `LDR_DATA_TABLE_ENTRY`.
+    size_of_image: u64,
+    /// `FullDllName` in `LDR_DATA_TABLE_ENTRY`.
+    full_dll_name: u64,
+    /// `Buffer` in `UNICODE_STRING`.
+    string_buffer: u64,
+}
+
+const LDR_LAYOUT_32: LdrLayout = LdrLayout {
+    peb_ldr: 0x0c,
+    in_load_order_module_list: 0x0c,
+    dll_base: 0x18,
+    size_of_image: 0x20,
+    full_dll_name: 0x24,
+    string_buffer: 0x04,
+};
+
+const LDR_LAYOUT_64: LdrLayout = LdrLayout {
+    peb_ldr: 0x18,
+    in_load_order_module_list: 0x10,
+    dll_base: 0x30,
+    size_of_image: 0x40,
+    full_dll_name: 0x48,
+    string_buffer: 0x08,
+};
+
+/// The names of threads in a `Minidump`.
+///
+/// Windows 10 and later record thread descriptions set with `SetThreadDescription`
+/// in the `ThreadNamesStream`.
+#[derive(Debug, Clone, Default)]
+pub struct MinidumpThreadNames {
+    /// A map of thread id to thread name.
+    names: BTreeMap<u32, String>,
+}
+
+/// A memory mapping of a Linux process, from a line of `/proc/self/maps`.
+#[derive(Debug, Clone, PartialEq)]
+pub struct MinidumpLinuxMapInfo {
+    /// The address the mapping starts at.
+    pub base_address: u64,
+    /// The address just past the end of the mapping.
+    pub end_address: u64,
+    /// The mapping's permissions, like `r-xp`.
+    pub permissions: String,
+    /// The offset into the mapped file the mapping starts at.
+    pub offset: u64,
+    /// The mapped file, or a pseudo-path like `[stack]`. Anonymous mappings have none.
+    pub path: Option<String>,
+}
+
+/// The memory mappings of a Linux process.
+///
+/// Breakpad writes the contents of `/proc/self/maps` to the `LinuxMaps` stream. It
+/// covers regions that aren't in the module list, like JIT code and anonymous mappings.
+#[derive(Debug, Clone)]
+pub struct MinidumpLinuxMaps {
+    /// The mappings, in the order they were listed.
+    maps: Vec<MinidumpLinuxMapInfo>,
+    /// Map from address range to index in maps. Use `MinidumpLinuxMaps::map_at_address`.
+    maps_by_addr: RangeMap<u64, usize>,
+}
+
+/// The command line of a Linux process.
+///
+/// Breakpad writes the contents of `/proc/self/cmdline` to the `LinuxCmdLine`
+/// stream. On Android, an app's processes are renamed to the app's package, so
+/// the program is the package name, like `org.mozilla.firefox`.
+#[derive(Debug, Clone, Default)]
+pub struct MinidumpLinuxCmdLine {
+    /// The program and its arguments.
+    pub args: Vec<String>,
+}
+
+/// The environment of a Linux process.
+///
+/// Breakpad writes the contents of `/proc/self/environ` to the `LinuxEnviron`
+/// stream. That's the environment the process started with, so variables it
+/// set itself later aren't in it.
+#[derive(Debug, Clone, Default)]
+pub struct MinidumpLinuxEnviron {
+    /// The variables' names and values, in the order they were listed.
+    ///
+    /// Entries without a `=` are taken as a name with an empty value.
+    pub vars: Vec<(String, String)>,
+}
+
 /// Information about the system that generated the minidump.
 #[derive(Debug)]
 pub struct MinidumpSystemInfo {
@@ -206,10 +569,12 @@ pub struct MinidumpSystemInfo {
     /// An x86 (not x64!) CPU vendor name that is stored in `raw` but in a way
     /// that's
     cpu_info: Option<String>,
+    /// The names of the CPU's features that are decoded from `raw`.
+    cpu_features: Vec<&'static str>,
 }
 
 /// A region of memory from the process that wrote the minidump.
-#[derive(Debug)]
+#[derive(Debug, Clone)]
 pub struct MinidumpMemory<'a> {
     /// The raw `MINIDUMP_MEMORY_DESCRIPTOR` from the minidump.
     pub desc: md::MINIDUMP_MEMORY_DESCRIPTOR,
@@ -221,6 +586,19 @@ pub struct MinidumpMemory<'a> {
     pub bytes: &'a [u8],
 }
 
+/// A copy of the dumped memory in a range of addresses.
+///
+/// See [`MinidumpMemoryList::memory_window`][window].
+///
+/// [window]: struct.MinidumpMemoryList.html#method.memory_window
+#[derive(Debug, Clone, PartialEq)]
+pub struct MemoryWindow {
+    /// The addresses covered by `bytes`.
+    pub range: std::ops::Range<u64>,
+    /// The contents of the memory.
+    pub bytes: Vec<u8>,
+}
+
 #[allow(clippy::large_enum_variant)]
 #[derive(Debug)]
 pub enum RawMiscInfo {
@@ -238,6 +616,51 @@ pub struct MinidumpMiscInfo {
     pub raw: RawMiscInfo,
 }
 
+/// The raw handle descriptor, whose layout depends on the writer.
+#[derive(Debug, Clone)]
+pub enum RawHandleDescriptor {
+    HandleDescriptor(md::MINIDUMP_HANDLE_DESCRIPTOR),
+    HandleDescriptor2(md::MINIDUMP_HANDLE_DESCRIPTOR_2),
+}
+
+/// A handle that was open in the process that wrote the minidump.
+#[derive(Debug, Clone)]
+pub struct MinidumpHandleDescriptor {
+    /// The `MINIDUMP_HANDLE_DESCRIPTOR` direct from the minidump file.
+    pub raw: RawHandleDescriptor,
+    /// The name of the handle's object type, if present.
+    pub type_name: Option<String>,
+    /// The name of the handle's object, if present.
+    pub object_name: Option<String>,
+}
+
+/// A list of handles that were open in the process that wrote the minidump.
+#[derive(Debug, Clone)]
+pub struct MinidumpHandleDataStream {
+    /// The handles, in the order they were present in the `Minidump`.
+    pub handles: Vec<MinidumpHandleDescriptor>,
+}
+
+/// A handle operation recorded by Application Verifier's handle tracing.
+#[derive(Debug, Clone)]
+pub struct MinidumpHandleOperation {
+    /// The `AVRF_HANDLE_OPERATION` direct from the minidump file.
+    pub raw: md::AVRF_HANDLE_OPERATION,
+    /// The kind of operation, if it's a known one.
+    pub operation: Option<md::HandleTraceOperation>,
+}
+
+/// A list of handle operations recorded by Application Verifier's handle tracing.
+///
+/// This is only present in minidumps of processes with Application Verifier's
+/// handle checks enabled. The operations show where handles were opened and
+/// closed, which helps find double closes and uses of closed handles.
+#[derive(Debug, Clone)]
+pub struct MinidumpHandleOperationList {
+    /// The operations, in the order they were present in the `Minidump`.
+    pub operations: Vec<MinidumpHandleOperation>,
+}
+
 /// Additional information about process state.
 ///
 /// MinidumpBreakpadInfo wraps MINIDUMP_BREAKPAD_INFO, which is an optional stream
@@ -253,11 +676,76 @@ pub struct MinidumpBreakpadInfo {
 }
 
 /// The reason for a process crash.
+///
+/// This classifies the exception record by the OS that produced the minidump. Its `Display`
+/// impl gives the conventional name of the crash, e.g. `EXCEPTION_ACCESS_VIOLATION_READ`,
+/// `SIGSEGV / SEGV_MAPERR` or `EXC_BAD_ACCESS / KERN_INVALID_ADDRESS`.
 #[derive(Copy, Clone, Debug, PartialEq)]
 pub enum CrashReason {
+    /// A Windows access violation, with the kind of access that faulted.
+    WindowsAccessViolation(AccessKind),
+    /// A Windows in-page error, with the kind of access that faulted.
+    WindowsInPageError(AccessKind),
+    /// Any other known Windows exception, identified by its `NTSTATUS` code.
+    Windows(md::ExceptionCodeWindows),
+    /// A Windows exception with an unrecognized code.
+    WindowsUnknown(u32),
+    /// A Linux or Android signal, with its `si_code`.
+    Linux(md::ExceptionCodeLinux, u32),
+    /// A Linux or Android signal with an unrecognized number, with its `si_code`.
+    LinuxUnknown(u32, u32),
+    /// A macOS or iOS Mach exception, with its code.
+    Mac(md::ExceptionCodeMac, u32),
+    /// A Mach exception with an unrecognized type, with its code.
+    MacUnknown(u32, u32),
+    /// An exception from an OS this crate doesn't know how to interpret.
     Unknown,
 }
 
+/// The kind of memory access that caused a fault.
+#[derive(Copy, Clone, Debug, PartialEq)]
+pub enum AccessKind {
+    Read,
+    Write,
+    Execute,
+    /// The platform reported an access kind this crate doesn't know about.
+    Unknown(u64),
+}
+
+/// The parameters of an exception record, interpreted for the platform that produced it.
+///
+/// The meaning of `MINIDUMP_EXCEPTION::exception_information` and of the other exception record
+/// fields depends on the OS and the exception code. Use
+/// [`MinidumpException::get_exception_details`][details] to decode them.
+///
+/// [details]: struct.MinidumpException.html#method.get_exception_details
+#[derive(Copy, Clone, Debug, PartialEq)]
+pub enum ExceptionDetails {
+    /// A Windows `EXCEPTION_ACCESS_VIOLATION`.
+    AccessViolation { kind: AccessKind, address: u64 },
+    /// A Windows `EXCEPTION_IN_PAGE_ERROR`, with the `NTSTATUS` of the failed page-in.
+    InPageError {
+        kind: AccessKind,
+        address: u64,
+        status: u32,
+    },
+    /// A macOS `EXC_BAD_ACCESS` Mach exception, with its `kern_return_t` code.
+    MachBadAccess { code: u32, address: u64 },
+    /// A Linux `SIGSEGV` or `SIGBUS`, with its `si_code`.
+    SignalFault {
+        signal: u32,
+        code: u32,
+        address: u64,
+    },
+    /// Any other exception, which has no structured parameters.
+    Other { code: u32, address: u64 },
+}
+
+/// The most exception records [`MinidumpException::get_exception_chain`][chain] will return.
+///
+/// [chain]: struct.MinidumpException.html#method.get_exception_chain
+pub const MAX_EXCEPTION_CHAIN_LENGTH: usize = 32;
+
 /// Information about the exception that caused the minidump to be generated.
 ///
 /// `MinidumpException` wraps `MINIDUMP_EXCEPTION_STREAM`, which contains information
@@ -288,6 +776,9 @@ pub struct MinidumpMemoryList<'a> {
     regions: Vec<MinidumpMemory<'a>>,
     /// Map from address range to index in regions. Use `MinidumpMemoryList::memory_at_address`.
     regions_by_addr: RangeMap<u64, usize>,
+    /// Indices in regions of the regions left out of `regions_by_addr` because they
+    /// overlapped another region.
+    overlapping: Vec<usize>,
 }
 
 /// Information about an assertion that caused a crash.
@@ -369,6 +860,40 @@ fn format_system_time(time: &md::SYSTEMTIME) -> String {
     }
 }
 
+/// How many frames of the crashing thread `Minidump::fingerprint` includes.
+const FINGERPRINT_FRAMES: usize = 5;
+/// How many words of the crashing thread's stack `Minidump::fingerprint` looks
+/// through for its callers' return addresses.
+const FINGERPRINT_SCAN_WORDS: u64 = 160;
+
+/// A 64-bit FNV-1a hasher, used for `Minidump::fingerprint`.
+///
+/// The standard library's hashers don't promise stable output across Rust releases, but
+/// fingerprints need to be comparable between runs.
+struct Fnv1a(u64);
+
+impl Fnv1a {
+    fn new() -> Fnv1a {
+        Fnv1a(0xcbf2_9ce4_8422_2325)
+    }
+
+    fn write(&mut self, bytes: &[u8]) {
+        for &byte in bytes {
+            self.0 ^= u64::from(byte);
+            self.0 = self.0.wrapping_mul(0x0000_0100_0000_01b3);
+        }
+    }
+
+    fn write_u64(&mut self, val: u64) {
+        self.write(&val.to_le_bytes());
+    }
+
+    fn write_str(&mut self, s: &str) {
+        self.write_u64(s.len() as u64);
+        self.write(s.as_bytes());
+    }
+}
+
 /// Produce a slice of `bytes` corresponding to the offset and size in `loc`, or an
 /// `Error` if the data is not fully contained within `bytes`.
 fn location_slice<'a>(
@@ -382,6 +907,29 @@ fn location_slice<'a>(
         .ok_or(Error::StreamReadFailure)
 }
 
+/// Check that `count` entries of `entry_size` bytes starting at `offset` fit within `bytes`.
+///
+/// List counts come straight from the minidump, so they need to be validated before
+/// they're used to size allocations or drive reads.
+fn ensure_list_fits(
+    bytes: &[u8],
+    offset: usize,
+    count: u32,
+    entry_size: usize,
+) -> Result<(), Error> {
+    let expected = (count as usize)
+        .checked_mul(entry_size)
+        .and_then(|size| size.checked_add(offset))
+        .ok_or(Error::StreamReadFailure)?;
+    if bytes.len() < expected {
+        return Err(Error::StreamTruncated {
+            expected,
+            actual: bytes.len(),
+        });
+    }
+    Ok(())
+}
+
 /// Read a u32 length-prefixed UTF-16 string from `bytes` at `offset`.
 fn read_string_utf16(
     offset: &mut usize,
@@ -402,6 +950,17 @@ fn read_string_utf16(
     }
 }
 
+/// Read the byte array at `offset` in `bytes`: a 32-bit length, then that many bytes.
+#[cfg(feature = "json")]
+pub(crate) fn read_byte_array(
+    mut offset: usize,
+    bytes: &[u8],
+    endian: scroll::Endian,
+) -> Option<&[u8]> {
+    let length: u32 = bytes.gread_with(&mut offset, endian).ok()?;
+    bytes.get(offset..offset.checked_add(length as usize)?)
+}
+
 #[inline]
 fn read_string_utf8_unterminated<'a>(
     offset: &mut usize,
@@ -434,11 +993,46 @@ fn string_from_bytes_nul(bytes: &[u8]) -> Option<Cow<'_, str>> {
 }
 
 /// Format `bytes` as a String of hex digits
-fn bytes_to_hex(bytes: &[u8]) -> String {
+pub(crate) fn bytes_to_hex(bytes: &[u8]) -> String {
     let hex_bytes: Vec<String> = bytes.iter().map(|b| format!("{:02x}", b)).collect();
     hex_bytes.join("")
 }
 
+/// The CPU the system info stream of the minidump `all` says it's from, if it has one.
+///
+/// This is for stream readers that need to know, since they only get the minidump's bytes.
+fn system_info_cpu(all: &[u8], endian: scroll::Endian) -> Option<Cpu> {
+    let header: md::MINIDUMP_HEADER = all.pread_with(0, endian).ok()?;
+    let mut offset = header.stream_directory_rva as usize;
+    for _ in 0..header.stream_count {
+        let dir: md::MINIDUMP_DIRECTORY = all.gread_with(&mut offset, endian).ok()?;
+        if dir.stream_type == MINIDUMP_STREAM_TYPE::SystemInfoStream as u32 {
+            let raw: md::MINIDUMP_SYSTEM_INFO = location_slice(all, &dir.location)
+                .ok()?
+                .pread_with(0, endian)
+                .ok()?;
+            return Some(Cpu::from_processor_architecture(raw.processor_architecture));
+        }
+    }
+    None
+}
+
+/// Read a thread context from `bytes`, in the minidump `all`.
+///
+/// If the minidump has system info, a context in the opposite byte order from the
+/// minidump's is accepted if it's for the system's CPU. See
+/// [`MinidumpContext::read_for_cpu`](struct.MinidumpContext.html#method.read_for_cpu).
+fn read_context(
+    bytes: &[u8],
+    all: &[u8],
+    endian: scroll::Endian,
+) -> Result<MinidumpContext, ContextError> {
+    MinidumpContext::read(bytes, endian).or_else(|e| match system_info_cpu(all, endian) {
+        Some(cpu) => MinidumpContext::read_for_cpu(bytes, endian, cpu),
+        None => Err(e),
+    })
+}
+
 /// Attempt to read a CodeView record from `data` at `location`
 fn read_codeview(
     location: &md::MINIDUMP_LOCATION_DESCRIPTOR,
@@ -446,6 +1040,14 @@ fn read_codeview(
     endian: scroll::Endian,
 ) -> Result<CodeView, failure::Error> {
     let bytes = location_slice(data, location)?;
+    read_codeview_bytes(bytes, endian)
+}
+
+/// Attempt to read a CodeView record from all of `bytes`
+pub(crate) fn read_codeview_bytes(
+    bytes: &[u8],
+    endian: scroll::Endian,
+) -> Result<CodeView, failure::Error> {
     // The CodeView data can be one of a few different formats. Try to read the
     // signature first to figure out what format the data is.
     let signature: u32 = bytes.pread_with(0, endian)?;
@@ -477,6 +1079,92 @@ impl MinidumpModule {
         }
     }
 
+    /// The module's structured version information, if it has any.
+    ///
+    /// This is `None` unless the `MINIDUMP_MODULE`'s `VS_FIXEDFILEINFO` has the
+    /// expected signature, which non-Windows modules usually don't.
+    pub fn version_info(&self) -> Option<ModuleVersionInfo> {
+        let info = &self.raw.version_info;
+        if info.signature != md::VS_FFI_SIGNATURE || info.struct_version != md::VS_FFI_STRUCVERSION
+        {
+            return None;
+        }
+        Some(ModuleVersionInfo {
+            file_version: version_parts(info.file_version_hi, info.file_version_lo),
+            product_version: version_parts(info.product_version_hi, info.product_version_lo),
+            file_flags: md::FileInfoFlags::from_bits_truncate(
+                info.file_flags & info.file_flags_mask,
+            ),
+            file_os: info.file_os,
+            file_type: info.file_type,
+            file_subtype: info.file_subtype,
+        })
+    }
+
+    /// The module's debug identifier in the canonical form Breakpad symbol files are stored under.
+    ///
+    /// This is the CodeView record's GUID and age as uppercase hex, 33 characters for the usual
+    /// PDB 7.0 record with an age below 16. ELF build ids are treated as a GUID with an age of
+    /// zero, which is what `dump_syms` does. `debug_identifier` may write the age in lowercase,
+    /// which symbol servers won't match.
+    pub fn breakpad_debug_id(&self) -> Option<String> {
+        self.debug_identifier().map(|id| id.to_uppercase())
+    }
+
+    /// The module's code identifier in the form symbol servers expect for modules from `os`.
+    ///
+    /// * Windows: the PE timestamp as 8 uppercase hex digits followed by the image size in
+    ///   lowercase hex, as used by Microsoft's symbol server.
+    /// * Linux and Android: the full GNU build id as lowercase hex.
+    /// * macOS and iOS: the Mach-O UUID as 32 uppercase hex digits. Breakpad stores it as the
+    ///   GUID of a PDB 7.0 record.
+    ///
+    /// Returns `None` if the module doesn't carry the identifier `os` uses, for example an ELF
+    /// module whose build id was only written as a GUID.
+    pub fn breakpad_code_id(&self, os: Os) -> Option<String> {
+        match (os, &self.codeview_info) {
+            (_, Some(CodeView::Elf(ref raw))) if !raw.build_id.is_empty() => {
+                Some(bytes_to_hex(&raw.build_id))
+            }
+            (Os::Windows, _) => Some(format!(
+                "{0:08X}{1:x}",
+                self.raw.time_date_stamp, self.raw.size_of_image
+            )),
+            (Os::MacOs, Some(CodeView::Pdb70(ref raw)))
+            | (Os::Ios, Some(CodeView::Pdb70(ref raw))) => Some(format!("{:#}", raw.signature)),
+            _ => None,
+        }
+    }
+
+    /// Fill in this module's identifiers from its image header, if the module list left them out.
+    ///
+    /// Some minidump writers don't record a CodeView record for every module. If the start of
+    /// the module's image was captured in `memory`, its identifiers can be read from the image
+    /// header instead: the CodeView debug directory entry of a PE image, the GNU build id note of
+    /// an ELF image, or the `LC_UUID` load command of a Mach-O image. A PE image's timestamp also
+    /// fills in a missing code identifier. Identifiers the module list did record are kept.
+    ///
+    /// Returns `true` if anything was filled in.
+    pub fn recover_identifiers(&mut self, memory: &MinidumpMemoryList<'_>) -> bool {
+        if self.codeview_info.is_some() && self.raw.time_date_stamp != 0 {
+            return false;
+        }
+        let ids = match image::read_image_ids(memory, self.raw.base_of_image, &self.name) {
+            Some(ids) => ids,
+            None => return false,
+        };
+        let mut recovered = false;
+        if let (None, Some(codeview)) = (&self.codeview_info, ids.codeview) {
+            self.codeview_info = Some(codeview);
+            recovered = true;
+        }
+        if let (0, Some(time_date_stamp)) = (self.raw.time_date_stamp, ids.time_date_stamp) {
+            self.raw.time_date_stamp = time_date_stamp;
+            recovered = true;
+        }
+        recovered
+    }
+
     /// Read additional data to construct a `MinidumpModule` from `bytes` using the information
     /// from the module list in `raw`.
     pub fn read(
@@ -647,7 +1335,11 @@ impl MinidumpModule {
     }
 
     fn memory_range(&self) -> Range<u64> {
-        Range::new(self.base_address(), self.base_address() + self.size() - 1)
+        Range::new(
+            self.base_address(),
+            self.base_address()
+                .saturating_add(self.size().saturating_sub(1)),
+        )
     }
 }
 
@@ -716,20 +1408,8 @@ impl Module for MinidumpModule {
         }
     }
     fn version(&self) -> Option<Cow<'_, str>> {
-        if self.raw.version_info.signature == md::VS_FFI_SIGNATURE
-            && self.raw.version_info.struct_version == md::VS_FFI_STRUCVERSION
-        {
-            let ver = format!(
-                "{}.{}.{}.{}",
-                self.raw.version_info.file_version_hi >> 16,
-                self.raw.version_info.file_version_hi & 0xffff,
-                self.raw.version_info.file_version_lo >> 16,
-                self.raw.version_info.file_version_lo & 0xffff
-            );
-            Some(Cow::Owned(ver))
-        } else {
-            None
-        }
+        self.version_info()
+            .map(|info| Cow::Owned(info.file_version_string()))
     }
 }
 
@@ -789,7 +1469,11 @@ impl MinidumpUnloadedModule {
     }
 
     fn memory_range(&self) -> Range<u64> {
-        Range::new(self.base_address(), self.base_address() + self.size() - 1)
+        Range::new(
+            self.base_address(),
+            self.base_address()
+                .saturating_add(self.size().saturating_sub(1)),
+        )
     }
 }
 
@@ -841,7 +1525,7 @@ where
         None => return Err(Error::StreamReadFailure),
     };
     if bytes.len() < counted_size {
-        return Err(Error::StreamSizeMismatch {
+        return Err(Error::StreamTruncated {
             expected: counted_size,
             actual: bytes.len(),
         });
@@ -870,6 +1554,39 @@ where
     Ok(raw_entries)
 }
 
+/// Like `read_stream_list`, but reads as many entries as fit in `bytes` if the stream is
+/// shorter than its count claims, rather than failing.
+///
+/// Returns the entries that were read along with the count the stream declared.
+fn read_partial_stream_list<'a, T>(
+    offset: &mut usize,
+    bytes: &'a [u8],
+    endian: scroll::Endian,
+) -> Result<(Vec<T>, usize), Error>
+where
+    T: TryFromCtx<'a, scroll::Endian, [u8], Error = scroll::Error>,
+    T: SizeWith<scroll::Endian>,
+{
+    let declared: u32 = bytes
+        .pread_with(*offset, endian)
+        .or(Err(Error::StreamReadFailure))?;
+    let declared = declared as usize;
+    let entry_size = <T>::size_with(&endian);
+    let available = bytes.len().saturating_sub(*offset + mem::size_of::<u32>()) / entry_size;
+    if declared <= available {
+        return read_stream_list(offset, bytes, endian).map(|entries| (entries, declared));
+    }
+    *offset += mem::size_of::<u32>();
+    let mut raw_entries = Vec::with_capacity(available);
+    for _ in 0..available {
+        let raw: T = bytes
+            .gread_with(offset, endian)
+            .or(Err(Error::StreamReadFailure))?;
+        raw_entries.push(raw);
+    }
+    Ok((raw_entries, declared))
+}
+
 fn read_ex_stream_list<'a, T>(
     offset: &mut usize,
     bytes: &'a [u8],
@@ -920,7 +1637,7 @@ where
         None => return Err(Error::StreamReadFailure),
     };
     if bytes.len() < stream_size {
-        return Err(Error::StreamSizeMismatch {
+        return Err(Error::StreamTruncated {
             expected: stream_size,
             actual: bytes.len(),
         });
@@ -947,31 +1664,85 @@ impl MinidumpModuleList {
     pub fn new() -> MinidumpModuleList {
         MinidumpModuleList {
             modules: vec![],
+            declared_count: 0,
+            too_large: None,
             modules_by_addr: RangeMap::new(),
+            overlapping: vec![],
+            image_base: None,
         }
     }
     /// Create a `MinidumpModuleList` from a list of `MinidumpModule`s.
     pub fn from_modules(modules: Vec<MinidumpModule>) -> MinidumpModuleList {
-        let modules_by_addr = modules
+        let (modules_by_addr, overlaps) = modules
             .iter()
             .enumerate()
             .map(|(i, module)| (module.memory_range(), i))
-            .into_rangemap_safe();
+            .into_rangemap_safe_with_overlaps();
+        let mut overlapping = overlaps.into_iter().map(|(_, i)| i).collect::<Vec<_>>();
+        overlapping.sort_unstable();
         MinidumpModuleList {
+            declared_count: modules.len(),
+            too_large: None,
             modules,
             modules_by_addr,
+            overlapping,
+            image_base: None,
         }
     }
 
     /// Returns the module corresponding to the main executable.
+    ///
+    /// If the address the process image was loaded at is known (see
+    /// [`set_image_base`][set_image_base]), this is the module loaded there. Otherwise
+    /// it is the module at the lowest address.
+    ///
+    /// [set_image_base]: #method.set_image_base
     pub fn main_module(&self) -> Option<&MinidumpModule> {
-        // The main code module is the first one present in a minidump file's
-        // MINIDUMP_MODULEList.
-        if !self.modules.is_empty() {
-            Some(&self.modules[0])
-        } else {
-            None
-        }
+        self.main_module_index().map(|index| &self.modules[index])
+    }
+
+    /// Returns the index of the main module in the order returned by [`iter`][iter].
+    ///
+    /// See [`main_module`][main_module] for how the main module is chosen.
+    ///
+    /// [iter]: #method.iter
+    /// [main_module]: #method.main_module
+    pub fn main_module_index(&self) -> Option<usize> {
+        let by_image_base = self.image_base.and_then(|image_base| {
+            self.modules
+                .iter()
+                .position(|module| module.base_address() == image_base)
+        });
+        by_image_base.or_else(|| {
+            self.modules
+                .iter()
+                .enumerate()
+                .min_by_key(|(_, module)| module.base_address())
+                .map(|(index, _)| index)
+        })
+    }
+
+    /// Fill in missing module identifiers from image headers captured in `memory`.
+    ///
+    /// See [`MinidumpModule::recover_identifiers`][recover]. Returns the number of modules
+    /// that had identifiers filled in.
+    ///
+    /// [recover]: struct.MinidumpModule.html#method.recover_identifiers
+    pub fn recover_identifiers(&mut self, memory: &MinidumpMemoryList<'_>) -> usize {
+        self.modules
+            .iter_mut()
+            .map(|module| module.recover_identifiers(memory))
+            .filter(|&recovered| recovered)
+            .count()
+    }
+
+    /// Set the address the process image was loaded at, which identifies the main module.
+    ///
+    /// [`Minidump::process_image_base`][process_image_base] can provide this.
+    ///
+    /// [process_image_base]: struct.Minidump.html#method.process_image_base
+    pub fn set_image_base(&mut self, image_base: u64) {
+        self.image_base = Some(image_base);
     }
 
     /// Return a `MinidumpModule` whose address range covers `address`.
@@ -993,18 +1764,71 @@ impl MinidumpModuleList {
             .map(move |&(_, index)| &self.modules[index])
     }
 
-    /// Write a human-readable description of this `MinidumpModuleList` to `f`.
+    /// Iterate over the modules that overlapped another module, in the order they were
+    /// stored in the minidump.
     ///
-    /// This is very verbose, it is the format used by `minidump_dump`.
-    pub fn print<T: Write>(&self, f: &mut T) -> io::Result<()> {
-        write!(
-            f,
-            "MinidumpModuleList
-  module_count = {}
+    /// These modules are not returned by [`module_at_address`][module_at_address] or
+    /// [`by_addr`][by_addr]. A well-formed minidump has none, so any modules here
+    /// indicate a corrupt dump.
+    ///
+    /// [module_at_address]: #method.module_at_address
+    /// [by_addr]: #method.by_addr
+    pub fn overlapping_modules(&self) -> impl Iterator<Item = &MinidumpModule> {
+        self.overlapping
+            .iter()
+            .map(move |&index| &self.modules[index])
+    }
 
-",
+    /// The number of modules the stream header claimed to contain.
+    ///
+    /// This can exceed the number of modules actually present if the stream was truncated.
+    pub fn declared_count(&self) -> usize {
+        self.declared_count
+    }
+
+    /// The number of modules left out because the module list exceeded its
+    /// [`ModuleListLimits`][limits].
+    ///
+    /// [limits]: struct.ModuleListLimits.html
+    pub fn omitted_count(&self) -> usize {
+        self.too_large
+            .map_or(0, |(modules, _)| modules - self.modules.len())
+    }
+
+    /// Check that every module the stream header declared was present and read.
+    ///
+    /// Truncated module lists are read as far as possible, so this is the way to find out
+    /// that some modules are missing. Returns `Error::ModuleListTooLarge` if some were
+    /// left out because of the module list's limits, or `Error::ModuleCountMismatch`
+    /// if they weren't in the stream.
+    pub fn validate(&self) -> Result<(), Error> {
+        if let Some((modules, bytes)) = self.too_large {
+            return Err(Error::ModuleListTooLarge { modules, bytes });
+        }
+        if self.declared_count != self.modules.len() {
+            return Err(Error::ModuleCountMismatch {
+                declared: self.declared_count,
+                actual: self.modules.len(),
+            });
+        }
+        Ok(())
+    }
+
+    /// Write a human-readable description of this `MinidumpModuleList` to `f`.
+    ///
+    /// This is very verbose, it is the format used by `minidump_dump`.
+    pub fn print<T: Write>(&self, f: &mut T) -> io::Result<()> {
+        write!(
+            f,
+            "MinidumpModuleList
+  module_count = {}
+",
             self.modules.len()
         )?;
+        if let Err(e) = self.validate() {
+            writeln!(f, "  warning: {}", e)?;
+        }
+        writeln!(f)?;
         for (i, module) in self.modules.iter().enumerate() {
             writeln!(f, "module[{}]", i)?;
             module.print(f)?;
@@ -1019,16 +1843,50 @@ impl Default for MinidumpModuleList {
     }
 }
 
-impl<'a> MinidumpStream<'a> for MinidumpModuleList {
-    const STREAM_TYPE: MINIDUMP_STREAM_TYPE = MINIDUMP_STREAM_TYPE::ModuleListStream;
-
-    fn read(
-        bytes: &'a [u8],
-        all: &'a [u8],
+impl MinidumpModuleList {
+    /// Read a `MinidumpModuleList` from the module list stream `bytes`, enforcing `limits`.
+    ///
+    /// `all` is the full contents of the minidump, as for
+    /// [`MinidumpStream::read`][read]. If the modules present in the stream exceed
+    /// `limits`, only the modules before the first one that doesn't fit are read;
+    /// [`omitted_count`][omitted] says how many were left out. Reading the stream with
+    /// [`Minidump::get_stream`][get_stream] uses the default limits.
+    ///
+    /// [read]: trait.MinidumpStream.html#tymethod.read
+    /// [omitted]: #method.omitted_count
+    /// [get_stream]: struct.Minidump.html#method.get_stream
+    pub fn read_with_limits(
+        bytes: &[u8],
+        all: &[u8],
         endian: scroll::Endian,
+        limits: &ModuleListLimits,
     ) -> Result<MinidumpModuleList, Error> {
         let mut offset = 0;
-        let raw_modules: Vec<md::MINIDUMP_MODULE> = read_stream_list(&mut offset, bytes, endian)?;
+        let (mut raw_modules, declared_count): (Vec<md::MINIDUMP_MODULE>, _) =
+            read_partial_stream_list(&mut offset, bytes, endian)?;
+        let record_bytes = |raw: &md::MINIDUMP_MODULE| {
+            (raw.cv_record.data_size as usize).saturating_add(raw.misc_record.data_size as usize)
+        };
+        let total_bytes = raw_modules.iter().fold(bytes.len(), |total, raw| {
+            total.saturating_add(record_bytes(raw))
+        });
+        // Keep the modules before the first one that doesn't fit within the limits.
+        let present = raw_modules.len();
+        let mut used = bytes.len();
+        let fitting = raw_modules
+            .iter()
+            .take(limits.max_modules)
+            .take_while(|raw| {
+                used = used.saturating_add(record_bytes(raw));
+                used <= limits.max_bytes
+            })
+            .count();
+        raw_modules.truncate(fitting);
+        let too_large = if fitting < present {
+            Some((present, total_bytes))
+        } else {
+            None
+        };
         // read auxiliary data for each module
         let mut modules = Vec::with_capacity(raw_modules.len());
         for raw in raw_modules.into_iter() {
@@ -1041,7 +1899,23 @@ impl<'a> MinidumpStream<'a> for MinidumpModuleList {
             }
             modules.push(MinidumpModule::read(raw, all, endian)?);
         }
-        Ok(MinidumpModuleList::from_modules(modules))
+        Ok(MinidumpModuleList {
+            declared_count,
+            too_large,
+            ..MinidumpModuleList::from_modules(modules)
+        })
+    }
+}
+
+impl<'a> MinidumpStream<'a> for MinidumpModuleList {
+    const STREAM_TYPE: MINIDUMP_STREAM_TYPE = MINIDUMP_STREAM_TYPE::ModuleListStream;
+
+    fn read(
+        bytes: &'a [u8],
+        all: &'a [u8],
+        endian: scroll::Endian,
+    ) -> Result<MinidumpModuleList, Error> {
+        MinidumpModuleList::read_with_limits(bytes, all, endian, &ModuleListLimits::default())
     }
 }
 
@@ -1067,12 +1941,32 @@ impl MinidumpUnloadedModuleList {
     }
 
     /// Return a `MinidumpUnloadedModule` whose address range covers `address`.
+    ///
+    /// Unloaded modules can overlap, in which case only one of them is found here. Use
+    /// [`modules_at_address`][modules_at_address] to find all of them.
+    ///
+    /// [modules_at_address]: #method.modules_at_address
     pub fn module_at_address(&self, address: u64) -> Option<&MinidumpUnloadedModule> {
         self.modules_by_addr
             .get(address)
             .map(|&index| &self.modules[index])
     }
 
+    /// Iterate over all the `MinidumpUnloadedModule`s whose address ranges cover `address`,
+    /// in the order they were stored in the minidump.
+    ///
+    /// Different modules may have been loaded at the same address at different times,
+    /// so there can be more than one.
+    pub fn modules_at_address(
+        &self,
+        address: u64,
+    ) -> impl Iterator<Item = &MinidumpUnloadedModule> {
+        self.modules.iter().filter(move |module| {
+            let range = module.memory_range();
+            module.size() > 0 && range.start <= address && address <= range.end
+        })
+    }
+
     /// Iterate over the modules in arbitrary order.
     pub fn iter(&self) -> impl Iterator<Item = &MinidumpUnloadedModule> {
         self.modules.iter()
@@ -1150,6 +2044,14 @@ impl<'a> MinidumpMemory<'a> {
             // catch it.
             return Err(Error::MemoryReadFailure);
         }
+        if desc
+            .start_of_memory_range
+            .checked_add(desc.memory.data_size as u64)
+            .is_none()
+        {
+            // The region would wrap around the address space.
+            return Err(Error::MemoryReadFailure);
+        }
         let bytes = location_slice(data, &desc.memory).or(Err(Error::StreamReadFailure))?;
         Ok(MinidumpMemory {
             desc: *desc,
@@ -1170,7 +2072,8 @@ impl<'a> MinidumpMemory<'a> {
     {
         let in_range = |a: u64| a >= self.base_address && a < (self.base_address + self.size);
         let size = <T>::size_with(&LE);
-        if !in_range(addr) || !in_range(addr + size as u64 - 1) {
+        let last = addr.checked_add(size as u64 - 1)?;
+        if !in_range(addr) || !in_range(last) {
             return None;
         }
         let start = (addr - self.base_address) as usize;
@@ -1210,7 +2113,43 @@ Memory
     }
 
     fn memory_range(&self) -> Range<u64> {
-        Range::new(self.base_address, self.base_address + self.size - 1)
+        Range::new(
+            self.base_address,
+            self.base_address
+                .saturating_add(self.size.saturating_sub(1)),
+        )
+    }
+}
+
+impl MemoryWindow {
+    /// Write the contents of this `MemoryWindow` to `f` as a hexdump.
+    ///
+    /// Each line has the address of its first byte, up to 16 bytes in hex, and
+    /// the same bytes as ASCII, with `.` for unprintable bytes.
+    pub fn print<T: Write>(&self, f: &mut T) -> io::Result<()> {
+        const BYTES_PER_LINE: usize = 16;
+        for (i, line) in self.bytes.chunks(BYTES_PER_LINE).enumerate() {
+            let address = self.range.start + (i * BYTES_PER_LINE) as u64;
+            write!(f, "{:#018x} ", address)?;
+            for byte in line {
+                write!(f, " {:02x}", byte)?;
+            }
+            for _ in line.len()..BYTES_PER_LINE {
+                write!(f, "   ")?;
+            }
+            let ascii: String = line
+                .iter()
+                .map(|&byte| {
+                    if byte.is_ascii_graphic() || byte == b' ' {
+                        byte as char
+                    } else {
+                        '.'
+                    }
+                })
+                .collect();
+            writeln!(f, "  |{}|", ascii)?;
+        }
+        Ok(())
     }
 }
 
@@ -1237,19 +2176,23 @@ impl<'mdmp> MinidumpMemoryList<'mdmp> {
         MinidumpMemoryList {
             regions: vec![],
             regions_by_addr: RangeMap::new(),
+            overlapping: vec![],
         }
     }
 
     /// Create a `MinidumpMemoryList` from a list of `MinidumpMemory`s.
     pub fn from_regions(regions: Vec<MinidumpMemory<'mdmp>>) -> MinidumpMemoryList<'mdmp> {
-        let regions_by_addr = regions
+        let (regions_by_addr, overlaps) = regions
             .iter()
             .enumerate()
             .map(|(i, region)| (region.memory_range(), i))
-            .into_rangemap_safe();
+            .into_rangemap_safe_with_overlaps();
+        let mut overlapping = overlaps.into_iter().map(|(_, i)| i).collect::<Vec<_>>();
+        overlapping.sort_unstable();
         MinidumpMemoryList {
             regions,
             regions_by_addr,
+            overlapping,
         }
     }
 
@@ -1283,6 +2226,66 @@ impl<'mdmp> MinidumpMemoryList<'mdmp> {
         }
     }
 
+    /// Copy up to `size` bytes of memory starting at `address`.
+    ///
+    /// The window is clipped to the memory that was dumped: the result starts at
+    /// the first dumped address in the window and runs through adjacent regions
+    /// until a gap or the end of the window. Return `None` if none of the window
+    /// was dumped.
+    pub fn memory_window(&self, address: u64, size: u64) -> Option<MemoryWindow> {
+        let end = address.saturating_add(size);
+        let mut window: Option<MemoryWindow> = None;
+        for region in self.by_addr() {
+            let region_end = region.base_address.saturating_add(region.size);
+            if region_end <= address {
+                continue;
+            }
+            if region.base_address >= end {
+                break;
+            }
+            let start = cmp::max(address, region.base_address);
+            let stop = cmp::min(end, region_end);
+            let offset = (start - region.base_address) as usize;
+            let bytes = match region.bytes.get(offset..offset + (stop - start) as usize) {
+                Some(bytes) => bytes,
+                None => break,
+            };
+            match window {
+                None => {
+                    window = Some(MemoryWindow {
+                        range: start..stop,
+                        bytes: bytes.to_vec(),
+                    })
+                }
+                Some(ref mut window) if window.range.end == start => {
+                    window.range.end = stop;
+                    window.bytes.extend_from_slice(bytes);
+                }
+                Some(_) => break,
+            }
+        }
+        window
+    }
+
+    /// Iterate over the memory regions that overlapped another region, in the order
+    /// contained in the minidump.
+    ///
+    /// These regions are not returned by [`memory_at_address`][memory_at_address] or
+    /// [`by_addr`][by_addr]. A well-formed minidump has none, so any regions here
+    /// indicate a corrupt dump.
+    ///
+    /// [memory_at_address]: #method.memory_at_address
+    /// [by_addr]: #method.by_addr
+    pub fn overlapping_regions<'slf>(&'slf self) -> MemoryRegions<'slf, 'mdmp> {
+        MemoryRegions {
+            iter: Box::new(
+                self.overlapping
+                    .iter()
+                    .map(move |&index| &self.regions[index]),
+            ),
+        }
+    }
+
     /// Write a human-readable description of this `MinidumpMemoryList` to `f`.
     ///
     /// This is very verbose, it is the format used by `minidump_dump`.
@@ -1335,6 +2338,32 @@ impl<'a> MinidumpStream<'a> for MinidumpMemoryList<'a> {
 }
 
 impl<'a> MinidumpThread<'a> {
+    /// How many times this thread has been suspended. It's suspended if this is
+    /// greater than zero.
+    pub fn suspend_count(&self) -> u32 {
+        self.raw.suspend_count
+    }
+
+    /// The priority class of this thread's process, a Windows `*_PRIORITY_CLASS`
+    /// value such as `0x20` for `NORMAL_PRIORITY_CLASS`.
+    pub fn priority_class(&self) -> u32 {
+        self.raw.priority_class
+    }
+
+    /// The priority of this thread relative to its priority class, a Windows
+    /// `THREAD_PRIORITY_*` value such as `-2` for `THREAD_PRIORITY_LOWEST`.
+    pub fn priority(&self) -> i32 {
+        self.raw.priority as i32
+    }
+
+    /// The address of this thread's Thread Environment Block, if it has one.
+    ///
+    /// Windows minidumps always record it. Other platforms leave it zeroed,
+    /// or may store a pointer to the thread's thread-local storage instead.
+    pub fn teb(&self) -> Option<u64> {
+        Some(self.raw.teb).filter(|&teb| teb != 0)
+    }
+
     /// Write a human-readable description of this `MinidumpThread` to `f`.
     ///
     /// This is very verbose, it is the format used by `minidump_dump`.
@@ -1367,6 +2396,8 @@ impl<'a> MinidumpThread<'a> {
         )?;
         if let Some(ref ctx) = self.context {
             ctx.print(f)?;
+        } else if let Some(ContextError::UnknownCpuContext(flags)) = self.context_error {
+            write!(f, "  (unknown CPU context, flags {:#010x})\n\n", flags)?;
         } else {
             write!(f, "  (no context)\n\n")?;
         }
@@ -1397,7 +2428,14 @@ impl<'a> MinidumpStream<'a> for MinidumpThreadList<'a> {
         for raw in raw_threads.into_iter() {
             thread_ids.insert(raw.thread_id, threads.len());
             let context_data = location_slice(all, &raw.thread_context)?;
-            let context = MinidumpContext::read(context_data, endian).ok();
+            let (context, context_error) = if context_data.is_empty() {
+                (None, None)
+            } else {
+                match read_context(context_data, all, endian) {
+                    Ok(context) => (Some(context), None),
+                    Err(e) => (None, Some(e)),
+                }
+            };
 
             // If this fails, it's ok. That probably means the RVA was null
             // and we need to lookup the stack's memory by address in the
@@ -1406,6 +2444,7 @@ impl<'a> MinidumpStream<'a> for MinidumpThreadList<'a> {
             threads.push(MinidumpThread {
                 raw,
                 context,
+                context_error,
                 stack,
             });
         }
@@ -1443,179 +2482,673 @@ impl<'a> MinidumpThreadList<'a> {
     }
 }
 
-impl<'a> MinidumpStream<'a> for MinidumpSystemInfo {
-    const STREAM_TYPE: MINIDUMP_STREAM_TYPE = MINIDUMP_STREAM_TYPE::SystemInfoStream;
+impl<'a> MinidumpStream<'a> for MinidumpThreadNames {
+    const STREAM_TYPE: MINIDUMP_STREAM_TYPE = MINIDUMP_STREAM_TYPE::ThreadNamesStream;
 
     fn read(
-        bytes: &[u8],
-        _all: &[u8],
+        bytes: &'a [u8],
+        all: &'a [u8],
         endian: scroll::Endian,
-    ) -> Result<MinidumpSystemInfo, Error> {
-        use std::fmt::Write;
-
-        let raw: md::MINIDUMP_SYSTEM_INFO = bytes
-            .pread_with(0, endian)
-            .or(Err(Error::StreamReadFailure))?;
-        let os = Os::from_platform_id(raw.platform_id);
-        let cpu = Cpu::from_processor_architecture(raw.processor_architecture);
+    ) -> Result<MinidumpThreadNames, Error> {
+        let mut offset = 0;
+        let raw_names: Vec<md::MINIDUMP_THREAD_NAME> =
+            read_stream_list(&mut offset, bytes, endian)?;
+        let mut names = BTreeMap::new();
+        for raw in raw_names {
+            let mut offset = raw.thread_name_rva as usize;
+            // Skip over names that can't be read rather than failing the whole stream.
+            if let Ok(name) = read_string_utf16(&mut offset, all, endian) {
+                names.insert(raw.thread_id, name);
+            }
+        }
+        Ok(MinidumpThreadNames { names })
+    }
+}
 
-        let mut csd_offset = raw.csd_version_rva as usize;
-        let csd_version = read_string_utf16(&mut csd_offset, bytes, endian).ok();
+impl MinidumpThreadNames {
+    /// Get the name of the thread with id `thread_id`.
+    ///
+    /// Threads that have no name, or an empty name, return `None`.
+    pub fn get_name(&self, thread_id: u32) -> Option<&str> {
+        self.names
+            .get(&thread_id)
+            .map(|name| name.as_str())
+            .filter(|name| !name.is_empty())
+    }
 
-        // self.raw.cpu.data is actually a union which we resolve here.
-        let cpu_info = match cpu {
-            Cpu::X86 | Cpu::X86_64 => {
-                let mut cpu_info = String::new();
+    /// Iterate over the thread ids and names, in thread id order.
+    pub fn iter(&self) -> impl Iterator<Item = (u32, &str)> {
+        self.names.iter().map(|(&id, name)| (id, name.as_str()))
+    }
 
-                if let Cpu::X86 = cpu {
-                    // The vendor's ID is an ascii string but we need to flatten out the u32's into u8's
-                    let x86_info: md::X86CpuInfo = raw
-                        .cpu
-                        .data
-                        .pread_with(0, endian)
-                        .or(Err(Error::StreamReadFailure))?;
+    /// Write a human-readable description of this `MinidumpThreadNames` to `f`.
+    ///
+    /// This is very verbose, it is the format used by `minidump_dump`.
+    pub fn print<T: Write>(&self, f: &mut T) -> io::Result<()> {
+        write!(
+            f,
+            r#"MinidumpThreadNames
+  thread_count = {}
 
-                    cpu_info.extend(
-                        x86_info
-                            .vendor_id
-                            .iter()
-                            .flat_map(|i| std::array::IntoIter::new(i.to_le_bytes()))
-                            .map(char::from),
-                    );
-                    cpu_info.push(' ');
-                }
+"#,
+            self.names.len()
+        )?;
 
-                write!(
-                    &mut cpu_info,
-                    "family {} model {} stepping {}",
-                    raw.processor_level,
-                    (raw.processor_revision >> 8) & 0xff,
-                    raw.processor_revision & 0xff
-                )
-                .unwrap();
+        for (thread_id, name) in &self.names {
+            writeln!(f, "thread_name[{:#x}] = \"{}\"", thread_id, name)?;
+        }
+        writeln!(f)?;
+        Ok(())
+    }
+}
 
-                Some(cpu_info)
-            }
-            Cpu::Arm => {
-                let arm_info: md::ARMCpuInfo = raw
-                    .cpu
-                    .data
-                    .pread_with(0, endian)
-                    .or(Err(Error::StreamReadFailure))?;
+impl MinidumpLinuxMapInfo {
+    /// Parse a line of `/proc/self/maps`, like
+    /// `7f0000000000-7f0000021000 r-xp 00000000 08:01 1234    /lib/libc.so.6`.
+    ///
+    /// Returns `None` if the line isn't in that format.
+    pub fn parse(line: &str) -> Option<MinidumpLinuxMapInfo> {
+        let mut fields = line.splitn(6, ' ');
+        let mut range = fields.next()?.splitn(2, '-');
+        let base_address = u64::from_str_radix(range.next()?, 16).ok()?;
+        let end_address = u64::from_str_radix(range.next()?, 16).ok()?;
+        let permissions = fields.next()?.to_owned();
+        let offset = u64::from_str_radix(fields.next()?, 16).ok()?;
+        // The device and inode aren't interesting.
+        fields.next()?;
+        fields.next()?;
+        let path = fields
+            .next()
+            .map(|path| path.trim())
+            .filter(|path| !path.is_empty())
+            .map(String::from);
+        if end_address < base_address || permissions.len() != 4 {
+            return None;
+        }
+        Some(MinidumpLinuxMapInfo {
+            base_address,
+            end_address,
+            permissions,
+            offset,
+            path,
+        })
+    }
 
-                // There is no good list of implementer id values, but the following
-                // pages provide some help:
-                //   http://comments.gmane.org/gmane.linux.linaro.devel/6903
-                //   http://forum.xda-developers.com/archive/index.php/t-480226.html
-                let vendors = [
-                    (0x41, "ARM"),
-                    (0x51, "Qualcomm"),
-                    (0x56, "Marvell"),
-                    (0x69, "Intel/Marvell"),
-                ];
-                let parts = [
-                    (0x4100c050, "Cortex-A5"),
-                    (0x4100c080, "Cortex-A8"),
-                    (0x4100c090, "Cortex-A9"),
-                    (0x4100c0f0, "Cortex-A15"),
-                    (0x4100c140, "Cortex-R4"),
-                    (0x4100c150, "Cortex-R5"),
-                    (0x4100b360, "ARM1136"),
-                    (0x4100b560, "ARM1156"),
-                    (0x4100b760, "ARM1176"),
-                    (0x4100b020, "ARM11-MPCore"),
-                    (0x41009260, "ARM926"),
-                    (0x41009460, "ARM946"),
-                    (0x41009660, "ARM966"),
-                    (0x510006f0, "Krait"),
-                    (0x510000f0, "Scorpion"),
-                ];
-                let features = [
-                    (md::ArmElfHwCaps::HWCAP_SWP, "swp"),
-                    (md::ArmElfHwCaps::HWCAP_HALF, "half"),
-                    (md::ArmElfHwCaps::HWCAP_THUMB, "thumb"),
-                    (md::ArmElfHwCaps::HWCAP_26BIT, "26bit"),
-                    (md::ArmElfHwCaps::HWCAP_FAST_MULT, "fastmult"),
-                    (md::ArmElfHwCaps::HWCAP_FPA, "fpa"),
-                    (md::ArmElfHwCaps::HWCAP_VFP, "vfpv2"),
-                    (md::ArmElfHwCaps::HWCAP_EDSP, "edsp"),
-                    (md::ArmElfHwCaps::HWCAP_JAVA, "java"),
-                    (md::ArmElfHwCaps::HWCAP_IWMMXT, "iwmmxt"),
-                    (md::ArmElfHwCaps::HWCAP_CRUNCH, "crunch"),
-                    (md::ArmElfHwCaps::HWCAP_THUMBEE, "thumbee"),
-                    (md::ArmElfHwCaps::HWCAP_NEON, "neon"),
-                    (md::ArmElfHwCaps::HWCAP_VFPv3, "vfpv3"),
-                    (md::ArmElfHwCaps::HWCAP_VFPv3D16, "vfpv3d16"),
-                    (md::ArmElfHwCaps::HWCAP_TLS, "tls"),
-                    (md::ArmElfHwCaps::HWCAP_VFPv4, "vfpv4"),
-                    (md::ArmElfHwCaps::HWCAP_IDIVA, "idiva"),
-                    (md::ArmElfHwCaps::HWCAP_IDIVT, "idivt"),
-                ];
+    /// The size of the mapping in bytes.
+    pub fn size(&self) -> u64 {
+        self.end_address - self.base_address
+    }
 
-                let mut cpu_info = format!("ARMv{}", raw.processor_level);
+    /// `true` if the mapping's memory can be executed.
+    pub fn is_executable(&self) -> bool {
+        self.permissions.as_bytes().get(2) == Some(&b'x')
+    }
 
-                // Try to extract out known vendor/part names from the cpuid,
-                // falling back to just reporting the raw value.
-                let cpuid = arm_info.cpuid;
-                if cpuid != 0 {
-                    let vendor_id = (cpuid >> 24) & 0xff;
-                    let part_id = cpuid & 0xff00fff0;
+    /// A name for the mapping: its path, or `<anonymous>` if it doesn't have one.
+    pub fn name(&self) -> &str {
+        self.path.as_deref().unwrap_or("<anonymous>")
+    }
+}
 
-                    if let Some(&(_, vendor)) = vendors.iter().find(|&&(id, _)| id == vendor_id) {
-                        write!(&mut cpu_info, " {}", vendor).unwrap();
-                    } else {
-                        write!(&mut cpu_info, " vendor(0x{:x})", vendor_id).unwrap();
-                    }
+impl Default for MinidumpLinuxMaps {
+    fn default() -> Self {
+        Self::new()
+    }
+}
 
-                    if let Some(&(_, part)) = parts.iter().find(|&&(id, _)| id == part_id) {
-                        write!(&mut cpu_info, " {}", part).unwrap();
-                    } else {
-                        write!(&mut cpu_info, " part(0x{:x})", part_id).unwrap();
-                    }
-                }
+impl<'a> MinidumpStream<'a> for MinidumpLinuxMaps {
+    const STREAM_TYPE: MINIDUMP_STREAM_TYPE = MINIDUMP_STREAM_TYPE::LinuxMaps;
 
-                // Report all the known hardware features.
-                let elf_hwcaps = md::ArmElfHwCaps::from_bits_truncate(arm_info.elf_hwcaps);
-                if !elf_hwcaps.is_empty() {
-                    cpu_info.push_str(" features: ");
+    fn read(
+        bytes: &'a [u8],
+        _all: &'a [u8],
+        _endian: scroll::Endian,
+    ) -> Result<MinidumpLinuxMaps, Error> {
+        // Skip over lines that can't be parsed rather than failing the whole stream.
+        let maps = String::from_utf8_lossy(bytes)
+            .lines()
+            .filter_map(MinidumpLinuxMapInfo::parse)
+            .collect();
+        Ok(MinidumpLinuxMaps::from_maps(maps))
+    }
+}
 
-                    // Iterator::intersperse is still unstable, so do it manually
-                    let mut comma = "";
-                    for &(_, feature) in features
-                        .iter()
-                        .filter(|&&(feature, _)| elf_hwcaps.contains(feature))
-                    {
-                        cpu_info.push_str(comma);
-                        cpu_info.push_str(feature);
-                        comma = ",";
-                    }
-                }
+impl<'a> MinidumpStream<'a> for MinidumpLinuxCmdLine {
+    const STREAM_TYPE: MINIDUMP_STREAM_TYPE = MINIDUMP_STREAM_TYPE::LinuxCmdLine;
 
-                Some(cpu_info)
-            }
-            _ => None,
+    fn read(
+        bytes: &'a [u8],
+        _all: &'a [u8],
+        _endian: scroll::Endian,
+    ) -> Result<MinidumpLinuxCmdLine, Error> {
+        // The arguments are each terminated by a NUL, though the last one may
+        // have been truncated.
+        let bytes = bytes.strip_suffix(b"\0").unwrap_or(bytes);
+        let args = if bytes.is_empty() {
+            vec![]
+        } else {
+            bytes
+                .split(|&b| b == 0)
+                .map(|arg| String::from_utf8_lossy(arg).into_owned())
+                .collect()
         };
+        Ok(MinidumpLinuxCmdLine { args })
+    }
+}
 
-        Ok(MinidumpSystemInfo {
-            raw,
-            os,
-            cpu,
-            csd_version,
-            cpu_info,
-        })
+impl MinidumpLinuxCmdLine {
+    /// The program that was run, if the command line isn't empty.
+    pub fn program(&self) -> Option<&str> {
+        self.args.first().map(String::as_str)
     }
 }
 
-impl MinidumpSystemInfo {
-    /// Write a human-readable description of this `MinidumpSystemInfo` to `f`.
-    ///
-    /// This is very verbose, it is the format used by `minidump_dump`.
-    pub fn print<T: Write>(&self, f: &mut T) -> io::Result<()> {
-        write!(
-            f,
-            "MINIDUMP_SYSTEM_INFO
-  processor_architecture                     = {:#x}
-  processor_level                            = {}
+impl<'a> MinidumpStream<'a> for MinidumpLinuxEnviron {
+    const STREAM_TYPE: MINIDUMP_STREAM_TYPE = MINIDUMP_STREAM_TYPE::LinuxEnviron;
+
+    fn read(
+        bytes: &'a [u8],
+        _all: &'a [u8],
+        _endian: scroll::Endian,
+    ) -> Result<MinidumpLinuxEnviron, Error> {
+        // The entries are each terminated by a NUL, and the name of each is
+        // everything before its first `=`.
+        let vars = bytes
+            .split(|&b| b == 0)
+            .filter(|entry| !entry.is_empty())
+            .map(|entry| {
+                let entry = String::from_utf8_lossy(entry);
+                match entry.find('=') {
+                    Some(i) => (entry[..i].to_owned(), entry[i + 1..].to_owned()),
+                    None => (entry.into_owned(), String::new()),
+                }
+            })
+            .collect();
+        Ok(MinidumpLinuxEnviron { vars })
+    }
+}
+
+impl MinidumpLinuxEnviron {
+    /// The value of the variable `name`, if it was set.
+    ///
+    /// If it was listed more than once, this is the first value, which is the
+    /// one `getenv` returns.
+    pub fn get(&self, name: &str) -> Option<&str> {
+        self.vars
+            .iter()
+            .find(|(var, _)| var == name)
+            .map(|(_, value)| value.as_str())
+    }
+}
+
+impl MinidumpLinuxMaps {
+    /// Return an empty `MinidumpLinuxMaps`.
+    pub fn new() -> MinidumpLinuxMaps {
+        MinidumpLinuxMaps {
+            maps: vec![],
+            maps_by_addr: RangeMap::new(),
+        }
+    }
+
+    /// Create a `MinidumpLinuxMaps` from a list of `MinidumpLinuxMapInfo`s.
+    pub fn from_maps(maps: Vec<MinidumpLinuxMapInfo>) -> MinidumpLinuxMaps {
+        let maps_by_addr = maps
+            .iter()
+            .enumerate()
+            .filter(|(_, map)| map.size() > 0)
+            .map(|(i, map)| (Range::new(map.base_address, map.end_address - 1), i))
+            .into_rangemap_safe();
+        MinidumpLinuxMaps { maps, maps_by_addr }
+    }
+
+    /// Return the mapping containing `address`, if one exists.
+    pub fn map_at_address(&self, address: u64) -> Option<&MinidumpLinuxMapInfo> {
+        self.maps_by_addr
+            .get(address)
+            .map(|&index| &self.maps[index])
+    }
+
+    /// Iterate over the mappings in the order they were listed.
+    pub fn iter(&self) -> impl Iterator<Item = &MinidumpLinuxMapInfo> {
+        self.maps.iter()
+    }
+}
+
+impl MinidumpMemoryInfo {
+    /// The state of the pages in this region.
+    pub fn state(&self) -> md::MemoryState {
+        md::MemoryState::from_bits_truncate(self.raw.state)
+    }
+
+    /// The access protection of the pages in this region.
+    pub fn protection(&self) -> md::MemoryProtection {
+        md::MemoryProtection::from_bits_truncate(self.raw.protection)
+    }
+
+    /// Whether this region is unallocated.
+    pub fn is_free(&self) -> bool {
+        self.state().contains(md::MemoryState::MEM_FREE)
+    }
+
+    /// Whether code in this region could be executed when the minidump was written.
+    ///
+    /// The region's pages must be committed and have one of the `PAGE_EXECUTE*`
+    /// protections.
+    pub fn is_executable(&self) -> bool {
+        let execute = md::MemoryProtection::PAGE_EXECUTE
+            | md::MemoryProtection::PAGE_EXECUTE_READ
+            | md::MemoryProtection::PAGE_EXECUTE_READWRITE
+            | md::MemoryProtection::PAGE_EXECUTE_WRITECOPY;
+        self.state().contains(md::MemoryState::MEM_COMMIT) && self.protection().intersects(execute)
+    }
+
+    /// Whether `address` is in this region.
+    pub fn contains(&self, address: u64) -> bool {
+        address >= self.raw.base_address && address - self.raw.base_address < self.raw.region_size
+    }
+
+    /// Write a human-readable description of this `MinidumpMemoryInfo` to `f`.
+    ///
+    /// This is very verbose, it is the format used by `minidump_dump`.
+    pub fn print<T: Write>(&self, f: &mut T) -> io::Result<()> {
+        write!(
+            f,
+            r#"MINIDUMP_MEMORY_INFO
+  base_address          = {:#x}
+  allocation_base       = {:#x}
+  allocation_protection = {:#x}
+  region_size           = {:#x}
+  state                 = {:#x}
+  protection            = {:#x}
+  type                  = {:#x}
+
+"#,
+            self.raw.base_address,
+            self.raw.allocation_base,
+            self.raw.allocation_protection,
+            self.raw.region_size,
+            self.raw.state,
+            self.raw.protection,
+            self.raw._type,
+        )
+    }
+}
+
+impl<'a> MinidumpStream<'a> for MinidumpMemoryInfoList {
+    const STREAM_TYPE: MINIDUMP_STREAM_TYPE = MINIDUMP_STREAM_TYPE::MemoryInfoListStream;
+
+    fn read(
+        bytes: &'a [u8],
+        _all: &'a [u8],
+        endian: scroll::Endian,
+    ) -> Result<MinidumpMemoryInfoList, Error> {
+        let mut offset = 0;
+        let header: md::MINIDUMP_MEMORY_INFO_LIST = bytes
+            .gread_with(&mut offset, endian)
+            .or(Err(Error::StreamReadFailure))?;
+        let size_of_entry = <md::MINIDUMP_MEMORY_INFO>::size_with(&endian);
+        if header.size_of_entry as usize != size_of_entry {
+            return Err(Error::StreamReadFailure);
+        }
+        let stream_size = match header
+            .number_of_entries
+            .checked_mul(size_of_entry as u64)
+            .and_then(|v| v.checked_add(header.size_of_header as u64))
+        {
+            Some(s) => s,
+            None => return Err(Error::StreamReadFailure),
+        };
+        if (bytes.len() as u64) < stream_size {
+            return Err(Error::StreamTruncated {
+                expected: stream_size as usize,
+                actual: bytes.len(),
+            });
+        }
+        // Skip over any header fields we don't know about.
+        offset = header.size_of_header as usize;
+        let mut regions = Vec::with_capacity(header.number_of_entries as usize);
+        for _ in 0..header.number_of_entries {
+            let raw: md::MINIDUMP_MEMORY_INFO = bytes
+                .gread_with(&mut offset, endian)
+                .or(Err(Error::StreamReadFailure))?;
+            regions.push(MinidumpMemoryInfo { raw });
+        }
+        Ok(MinidumpMemoryInfoList::from_regions(regions))
+    }
+}
+
+impl Default for MinidumpMemoryInfoList {
+    fn default() -> Self {
+        Self::new()
+    }
+}
+
+impl MinidumpMemoryInfoList {
+    /// Return an empty `MinidumpMemoryInfoList`.
+    pub fn new() -> MinidumpMemoryInfoList {
+        MinidumpMemoryInfoList {
+            regions: vec![],
+            regions_by_addr: RangeMap::new(),
+        }
+    }
+
+    /// Create a `MinidumpMemoryInfoList` from a list of `MinidumpMemoryInfo`s.
+    pub fn from_regions(regions: Vec<MinidumpMemoryInfo>) -> MinidumpMemoryInfoList {
+        let regions_by_addr = regions
+            .iter()
+            .enumerate()
+            .filter_map(|(i, region)| {
+                let base = region.raw.base_address;
+                let last = base.checked_add(region.raw.region_size.checked_sub(1)?)?;
+                Some((Range::new(base, last), i))
+            })
+            .into_rangemap_safe();
+        MinidumpMemoryInfoList {
+            regions,
+            regions_by_addr,
+        }
+    }
+
+    /// Iterate over the regions in the order contained in the minidump.
+    pub fn iter(&self) -> impl Iterator<Item = &MinidumpMemoryInfo> {
+        self.regions.iter()
+    }
+
+    /// The region containing `address`, if the minidump describes one.
+    pub fn memory_info_at_address(&self, address: u64) -> Option<&MinidumpMemoryInfo> {
+        self.regions_by_addr
+            .get(address)
+            .map(|&index| &self.regions[index])
+    }
+
+    /// Write a human-readable description of this `MinidumpMemoryInfoList` to `f`.
+    ///
+    /// This is very verbose, it is the format used by `minidump_dump`.
+    pub fn print<T: Write>(&self, f: &mut T) -> io::Result<()> {
+        write!(
+            f,
+            r#"MinidumpMemoryInfoList
+  region_count = {}
+
+"#,
+            self.regions.len()
+        )?;
+
+        for (i, region) in self.regions.iter().enumerate() {
+            writeln!(f, "region[{}]", i)?;
+            region.print(f)?;
+        }
+        Ok(())
+    }
+}
+
+/// Describe an ARM cpu from its architecture version, its `MIDR` register, and
+/// the names of its hardware features.
+fn arm_cpu_info(processor_level: u16, cpuid: u32, features: &[&str]) -> String {
+    use std::fmt::Write;
+
+    // There is no good list of implementer id values, but the following
+    // pages provide some help:
+    //   http://comments.gmane.org/gmane.linux.linaro.devel/6903
+    //   http://forum.xda-developers.com/archive/index.php/t-480226.html
+    let vendors = [
+        (0x41, "ARM"),
+        (0x51, "Qualcomm"),
+        (0x56, "Marvell"),
+        (0x69, "Intel/Marvell"),
+    ];
+    let parts = [
+        (0x4100c050, "Cortex-A5"),
+        (0x4100c080, "Cortex-A8"),
+        (0x4100c090, "Cortex-A9"),
+        (0x4100c0f0, "Cortex-A15"),
+        (0x4100c140, "Cortex-R4"),
+        (0x4100c150, "Cortex-R5"),
+        (0x4100b360, "ARM1136"),
+        (0x4100b560, "ARM1156"),
+        (0x4100b760, "ARM1176"),
+        (0x4100b020, "ARM11-MPCore"),
+        (0x41009260, "ARM926"),
+        (0x41009460, "ARM946"),
+        (0x41009660, "ARM966"),
+        (0x4100d030, "Cortex-A53"),
+        (0x4100d040, "Cortex-A35"),
+        (0x4100d050, "Cortex-A55"),
+        (0x4100d070, "Cortex-A57"),
+        (0x4100d080, "Cortex-A72"),
+        (0x4100d090, "Cortex-A73"),
+        (0x4100d0a0, "Cortex-A75"),
+        (0x4100d0b0, "Cortex-A76"),
+        (0x510006f0, "Krait"),
+        (0x510000f0, "Scorpion"),
+    ];
+
+    let mut cpu_info = format!("ARMv{}", processor_level);
+
+    // Try to extract out known vendor/part names from the cpuid,
+    // falling back to just reporting the raw value.
+    if cpuid != 0 {
+        let vendor_id = (cpuid >> 24) & 0xff;
+        let part_id = cpuid & 0xff00fff0;
+
+        if let Some(&(_, vendor)) = vendors.iter().find(|&&(id, _)| id == vendor_id) {
+            write!(&mut cpu_info, " {}", vendor).unwrap();
+        } else {
+            write!(&mut cpu_info, " vendor(0x{:x})", vendor_id).unwrap();
+        }
+
+        if let Some(&(_, part)) = parts.iter().find(|&&(id, _)| id == part_id) {
+            write!(&mut cpu_info, " {}", part).unwrap();
+        } else {
+            write!(&mut cpu_info, " part(0x{:x})", part_id).unwrap();
+        }
+    }
+
+    // Report all the known hardware features.
+    if !features.is_empty() {
+        cpu_info.push_str(" features: ");
+        cpu_info.push_str(&features.join(","));
+    }
+    cpu_info
+}
+
+impl<'a> MinidumpStream<'a> for MinidumpSystemInfo {
+    const STREAM_TYPE: MINIDUMP_STREAM_TYPE = MINIDUMP_STREAM_TYPE::SystemInfoStream;
+
+    fn read(bytes: &[u8], all: &[u8], endian: scroll::Endian) -> Result<MinidumpSystemInfo, Error> {
+        use std::fmt::Write;
+
+        let raw: md::MINIDUMP_SYSTEM_INFO = bytes
+            .pread_with(0, endian)
+            .or(Err(Error::StreamReadFailure))?;
+        let os = Os::from_platform_id(raw.platform_id);
+        let cpu = Cpu::from_processor_architecture(raw.processor_architecture);
+
+        // The CSD version is an RVA into the whole dump, not into this stream.
+        let mut csd_offset = raw.csd_version_rva as usize;
+        let csd_version = read_string_utf16(&mut csd_offset, all, endian).ok();
+
+        // self.raw.cpu.data is actually a union which we resolve here.
+        let mut cpu_features = vec![];
+        let cpu_info = match cpu {
+            Cpu::X86 | Cpu::X86_64 => {
+                let mut cpu_info = String::new();
+
+                // Only 32-bit x86 dumps have the results of `cpuid`.
+                let x86_info = if let Cpu::X86 = cpu {
+                    let x86_info: md::X86CpuInfo = raw
+                        .cpu
+                        .data
+                        .pread_with(0, endian)
+                        .or(Err(Error::StreamReadFailure))?;
+                    Some(x86_info)
+                } else {
+                    None
+                };
+
+                if let Some(ref x86_info) = x86_info {
+                    // The vendor's ID is an ascii string but we need to flatten out the u32's into u8's
+                    cpu_info.extend(
+                        x86_info
+                            .vendor_id
+                            .iter()
+                            .flat_map(|i| std::array::IntoIter::new(i.to_le_bytes()))
+                            .map(char::from),
+                    );
+                    cpu_info.push(' ');
+                }
+
+                write!(
+                    &mut cpu_info,
+                    "family {} model {} stepping {}",
+                    raw.processor_level,
+                    (raw.processor_revision >> 8) & 0xff,
+                    raw.processor_revision & 0xff
+                )
+                .unwrap();
+
+                if let Some(x86_info) = x86_info {
+                    let features = [
+                        (md::X86CpuFeatures::FPU, "fpu"),
+                        (md::X86CpuFeatures::TSC, "tsc"),
+                        (md::X86CpuFeatures::CX8, "cx8"),
+                        (md::X86CpuFeatures::CMOV, "cmov"),
+                        (md::X86CpuFeatures::CLFLUSH, "clflush"),
+                        (md::X86CpuFeatures::MMX, "mmx"),
+                        (md::X86CpuFeatures::FXSR, "fxsr"),
+                        (md::X86CpuFeatures::SSE, "sse"),
+                        (md::X86CpuFeatures::SSE2, "sse2"),
+                        (md::X86CpuFeatures::HTT, "ht"),
+                    ];
+                    let feature_information =
+                        md::X86CpuFeatures::from_bits_truncate(x86_info.feature_information);
+                    cpu_features.extend(
+                        features
+                            .iter()
+                            .filter(|&&(feature, _)| feature_information.contains(feature))
+                            .map(|&(_, name)| name),
+                    );
+                }
+
+                Some(cpu_info)
+            }
+            Cpu::Arm => {
+                let arm_info: md::ARMCpuInfo = raw
+                    .cpu
+                    .data
+                    .pread_with(0, endian)
+                    .or(Err(Error::StreamReadFailure))?;
+
+                let features = [
+                    (md::ArmElfHwCaps::HWCAP_SWP, "swp"),
+                    (md::ArmElfHwCaps::HWCAP_HALF, "half"),
+                    (md::ArmElfHwCaps::HWCAP_THUMB, "thumb"),
+                    (md::ArmElfHwCaps::HWCAP_26BIT, "26bit"),
+                    (md::ArmElfHwCaps::HWCAP_FAST_MULT, "fastmult"),
+                    (md::ArmElfHwCaps::HWCAP_FPA, "fpa"),
+                    (md::ArmElfHwCaps::HWCAP_VFP, "vfpv2"),
+                    (md::ArmElfHwCaps::HWCAP_EDSP, "edsp"),
+                    (md::ArmElfHwCaps::HWCAP_JAVA, "java"),
+                    (md::ArmElfHwCaps::HWCAP_IWMMXT, "iwmmxt"),
+                    (md::ArmElfHwCaps::HWCAP_CRUNCH, "crunch"),
+                    (md::ArmElfHwCaps::HWCAP_THUMBEE, "thumbee"),
+                    (md::ArmElfHwCaps::HWCAP_NEON, "neon"),
+                    (md::ArmElfHwCaps::HWCAP_VFPv3, "vfpv3"),
+                    (md::ArmElfHwCaps::HWCAP_VFPv3D16, "vfpv3d16"),
+                    (md::ArmElfHwCaps::HWCAP_TLS, "tls"),
+                    (md::ArmElfHwCaps::HWCAP_VFPv4, "vfpv4"),
+                    (md::ArmElfHwCaps::HWCAP_IDIVA, "idiva"),
+                    (md::ArmElfHwCaps::HWCAP_IDIVT, "idivt"),
+                ];
+
+                let elf_hwcaps = md::ArmElfHwCaps::from_bits_truncate(arm_info.elf_hwcaps);
+                cpu_features.extend(
+                    features
+                        .iter()
+                        .filter(|&&(feature, _)| elf_hwcaps.contains(feature))
+                        .map(|&(_, name)| name),
+                );
+                Some(arm_cpu_info(
+                    raw.processor_level,
+                    arm_info.cpuid,
+                    &cpu_features,
+                ))
+            }
+            // Windows uses `OtherCpuInfo` for 64-bit ARM, the Breakpad extension
+            // is only written on other platforms.
+            Cpu::Arm64 if os != Os::Windows => {
+                let arm_info: md::ARMCpuInfo = raw
+                    .cpu
+                    .data
+                    .pread_with(0, endian)
+                    .or(Err(Error::StreamReadFailure))?;
+
+                let features = [
+                    (md::Arm64ElfHwCaps::HWCAP_FP, "fp"),
+                    (md::Arm64ElfHwCaps::HWCAP_ASIMD, "asimd"),
+                    (md::Arm64ElfHwCaps::HWCAP_EVTSTRM, "evtstrm"),
+                    (md::Arm64ElfHwCaps::HWCAP_AES, "aes"),
+                    (md::Arm64ElfHwCaps::HWCAP_PMULL, "pmull"),
+                    (md::Arm64ElfHwCaps::HWCAP_SHA1, "sha1"),
+                    (md::Arm64ElfHwCaps::HWCAP_SHA2, "sha2"),
+                    (md::Arm64ElfHwCaps::HWCAP_CRC32, "crc32"),
+                    (md::Arm64ElfHwCaps::HWCAP_ATOMICS, "atomics"),
+                    (md::Arm64ElfHwCaps::HWCAP_FPHP, "fphp"),
+                    (md::Arm64ElfHwCaps::HWCAP_ASIMDHP, "asimdhp"),
+                    (md::Arm64ElfHwCaps::HWCAP_CPUID, "cpuid"),
+                    (md::Arm64ElfHwCaps::HWCAP_ASIMDRDM, "asimdrdm"),
+                    (md::Arm64ElfHwCaps::HWCAP_JSCVT, "jscvt"),
+                    (md::Arm64ElfHwCaps::HWCAP_FCMA, "fcma"),
+                    (md::Arm64ElfHwCaps::HWCAP_LRCPC, "lrcpc"),
+                    (md::Arm64ElfHwCaps::HWCAP_DCPOP, "dcpop"),
+                    (md::Arm64ElfHwCaps::HWCAP_SHA3, "sha3"),
+                    (md::Arm64ElfHwCaps::HWCAP_SM3, "sm3"),
+                    (md::Arm64ElfHwCaps::HWCAP_SM4, "sm4"),
+                    (md::Arm64ElfHwCaps::HWCAP_ASIMDDP, "asimddp"),
+                    (md::Arm64ElfHwCaps::HWCAP_SHA512, "sha512"),
+                    (md::Arm64ElfHwCaps::HWCAP_SVE, "sve"),
+                ];
+
+                let elf_hwcaps = md::Arm64ElfHwCaps::from_bits_truncate(arm_info.elf_hwcaps);
+                cpu_features.extend(
+                    features
+                        .iter()
+                        .filter(|&&(feature, _)| elf_hwcaps.contains(feature))
+                        .map(|&(_, name)| name),
+                );
+                // Writers don't always fill in the architecture version, but
+                // 64-bit ARM is at least ARMv8.
+                let level = if raw.processor_level == 0 {
+                    8
+                } else {
+                    raw.processor_level
+                };
+                Some(arm_cpu_info(level, arm_info.cpuid, &cpu_features))
+            }
+            _ => None,
+        };
+
+        Ok(MinidumpSystemInfo {
+            raw,
+            os,
+            cpu,
+            csd_version,
+            cpu_info,
+            cpu_features,
+        })
+    }
+}
+
+impl MinidumpSystemInfo {
+    /// Write a human-readable description of this `MinidumpSystemInfo` to `f`.
+    ///
+    /// This is very verbose, it is the format used by `minidump_dump`.
+    pub fn print<T: Write>(&self, f: &mut T) -> io::Result<()> {
+        write!(
+            f,
+            "MINIDUMP_SYSTEM_INFO
+  processor_architecture                     = {:#x}
+  processor_level                            = {}
   processor_revision                         = {:#x}
   number_of_processors                       = {}
   product_type                               = {}
@@ -1658,6 +3191,15 @@ impl MinidumpSystemInfo {
     pub fn cpu_info(&self) -> Option<Cow<str>> {
         self.cpu_info.as_deref().map(Cow::Borrowed)
     }
+
+    /// Returns the names of the cpu's features, as Linux's `/proc/cpuinfo` names
+    /// them.
+    ///
+    /// These are only known for 32-bit x86 and for ARM. ARM's are also listed
+    /// in `cpu_info`.
+    pub fn cpu_features(&self) -> &[&'static str] {
+        &self.cpu_features
+    }
 }
 
 // Generates an accessor for a MISC_INFO field with two possible syntaxes:
@@ -1781,12 +3323,21 @@ impl<'a> MinidumpStream<'a> for MinidumpMiscInfo {
     const STREAM_TYPE: MINIDUMP_STREAM_TYPE = MINIDUMP_STREAM_TYPE::MiscInfoStream;
 
     fn read(bytes: &[u8], _all: &[u8], endian: scroll::Endian) -> Result<MinidumpMiscInfo, Error> {
-        // The misc info has gone through several revisions, so try to read the largest known
-        // struct possible.
+        // The misc info has gone through several revisions, and the writer records which one it
+        // used in `size_of_info`. The stream itself may be padded, so trust `size_of_info` over
+        // the stream size, but never read past the end of the stream.
+        let size_of_info: u32 = bytes
+            .pread_with(0, endian)
+            .or(Err(Error::StreamReadFailure))?;
+        let size = match size_of_info as usize {
+            0 => bytes.len(),
+            size => cmp::min(size, bytes.len()),
+        };
+        // Read the largest known struct that fits.
         macro_rules! do_read {
             ($(($t:ty, $variant:ident),)+) => {
                 $(
-                    if bytes.len() >= <$t>::size_with(&endian) {
+                    if size >= <$t>::size_with(&endian) {
                         return Ok(MinidumpMiscInfo {
                             raw: RawMiscInfo::$variant(bytes.pread_with(0, endian).or(Err(Error::StreamReadFailure))?),
                         });
@@ -1807,12 +3358,55 @@ impl<'a> MinidumpStream<'a> for MinidumpMiscInfo {
 }
 
 impl MinidumpMiscInfo {
+    /// When the process was created, if available.
     pub fn process_create_time(&self) -> Option<DateTime<Utc>> {
         self.raw
             .process_create_time()
             .map(|t| Utc.timestamp(*t as i64, 0))
     }
 
+    /// The amount of time the process has spent executing in user mode, if available.
+    pub fn process_user_time(&self) -> Option<Duration> {
+        self.raw
+            .process_user_time()
+            .map(|&t| Duration::from_secs(t as u64))
+    }
+
+    /// The amount of time the process has spent executing in kernel mode, if available.
+    pub fn process_kernel_time(&self) -> Option<Duration> {
+        self.raw
+            .process_kernel_time()
+            .map(|&t| Duration::from_secs(t as u64))
+    }
+
+    /// The process's data execution prevention settings, if available.
+    pub fn process_execute_flags(&self) -> Option<md::ProcessExecuteFlags> {
+        self.raw
+            .process_execute_flags()
+            .map(|&flags| md::ProcessExecuteFlags::from_bits_truncate(flags))
+    }
+
+    /// Whether the process was a protected process, if available.
+    pub fn protected_process(&self) -> Option<bool> {
+        self.raw
+            .protected_process()
+            .map(|&protected| protected != 0)
+    }
+
+    /// The build string of the OS that wrote the minidump, if available.
+    pub fn build_string(&self) -> Option<String> {
+        self.raw
+            .build_string()
+            .and_then(|string| utf16_to_string(&string[..]))
+    }
+
+    /// The build string of the debugging library that wrote the minidump, if available.
+    pub fn dbg_bld_str(&self) -> Option<String> {
+        self.raw
+            .dbg_bld_str()
+            .and_then(|string| utf16_to_string(&string[..]))
+    }
+
     /// Write a human-readable description of this `MinidumpMiscInfo` to `f`.
     ///
     /// This is very verbose, it is the format used by `minidump_dump`.
@@ -1896,20 +3490,12 @@ impl MinidumpMiscInfo {
         }
 
         write!(f, "  build_string                 = ")?;
-        match self
-            .raw
-            .build_string()
-            .and_then(|string| utf16_to_string(&string[..]))
-        {
+        match self.build_string() {
             Some(build_string) => writeln!(f, "{}", build_string)?,
             None => writeln!(f, "(invalid)")?,
         }
         write!(f, "  dbg_bld_str                  = ")?;
-        match self
-            .raw
-            .dbg_bld_str()
-            .and_then(|string| utf16_to_string(&string[..]))
-        {
+        match self.dbg_bld_str() {
             Some(dbg_bld_str) => writeln!(f, "{}", dbg_bld_str)?,
             None => writeln!(f, "(invalid)")?,
         }
@@ -1937,6 +3523,333 @@ impl MinidumpMiscInfo {
     }
 }
 
+macro_rules! handle_accessors {
+    ($($(#[$attr:meta])* $name:ident -> $t:ty,)+) => {
+        $(
+            $(#[$attr])*
+            pub fn $name(&self) -> $t {
+                match self.raw {
+                    RawHandleDescriptor::HandleDescriptor(ref raw) => raw.$name,
+                    RawHandleDescriptor::HandleDescriptor2(ref raw) => raw.$name,
+                }
+            }
+        )+
+    };
+}
+
+impl MinidumpHandleDescriptor {
+    handle_accessors!(
+        /// The operating system handle value.
+        handle -> u64,
+        /// An offset to the name of the handle's object type, or zero.
+        type_name_rva -> md::RVA,
+        /// An offset to the name of the handle's object, or zero.
+        object_name_rva -> md::RVA,
+        /// The object attributes for the handle.
+        attributes -> u32,
+        /// The access rights granted when the handle was opened.
+        granted_access -> u32,
+        /// The number of handles to the object.
+        handle_count -> u32,
+        /// The number of kernel references to the object.
+        pointer_count -> u32,
+    );
+
+    /// Read a handle descriptor and its names from `bytes` at `offset`.
+    ///
+    /// `size_of_descriptor` selects between the `MINIDUMP_HANDLE_DESCRIPTOR`
+    /// and `MINIDUMP_HANDLE_DESCRIPTOR_2` layouts.
+    fn read(
+        bytes: &[u8],
+        offset: usize,
+        size_of_descriptor: usize,
+        all: &[u8],
+        endian: scroll::Endian,
+    ) -> Result<MinidumpHandleDescriptor, Error> {
+        let raw = if size_of_descriptor == md::MINIDUMP_HANDLE_DESCRIPTOR_2::size_with(&endian) {
+            RawHandleDescriptor::HandleDescriptor2(
+                bytes
+                    .pread_with(offset, endian)
+                    .or(Err(Error::StreamReadFailure))?,
+            )
+        } else {
+            RawHandleDescriptor::HandleDescriptor(
+                bytes
+                    .pread_with(offset, endian)
+                    .or(Err(Error::StreamReadFailure))?,
+            )
+        };
+        let mut descriptor = MinidumpHandleDescriptor {
+            raw,
+            type_name: None,
+            object_name: None,
+        };
+        let read_name = |rva: md::RVA| {
+            if rva == 0 {
+                return None;
+            }
+            let mut offset = rva as usize;
+            read_string_utf16(&mut offset, all, endian).ok()
+        };
+        descriptor.type_name = read_name(descriptor.type_name_rva());
+        descriptor.object_name = read_name(descriptor.object_name_rva());
+        Ok(descriptor)
+    }
+
+    /// Write a human-readable description of this `MinidumpHandleDescriptor` to `f`.
+    ///
+    /// This is very verbose, it is the format used by `minidump_dump`.
+    pub fn print<T: Write>(&self, f: &mut T) -> io::Result<()> {
+        write!(
+            f,
+            "MINIDUMP_HANDLE_DESCRIPTOR
+  handle                          = {:#x}
+  (type_name)                     = \"{}\"
+  (object_name)                   = \"{}\"
+  attributes                      = {:#x}
+  granted_access                  = {:#x}
+  handle_count                    = {}
+  pointer_count                   = {}
+",
+            self.handle(),
+            self.type_name.as_deref().unwrap_or(""),
+            self.object_name.as_deref().unwrap_or(""),
+            self.attributes(),
+            self.granted_access(),
+            self.handle_count(),
+            self.pointer_count(),
+        )?;
+        if let RawHandleDescriptor::HandleDescriptor2(ref raw) = self.raw {
+            writeln!(
+                f,
+                "  object_info_rva                 = {:#x}",
+                raw.object_info_rva
+            )?;
+        }
+        writeln!(f)?;
+        Ok(())
+    }
+}
+
+impl<'a> MinidumpStream<'a> for MinidumpHandleDataStream {
+    const STREAM_TYPE: MINIDUMP_STREAM_TYPE = MINIDUMP_STREAM_TYPE::HandleDataStream;
+
+    fn read(
+        bytes: &'a [u8],
+        all: &'a [u8],
+        endian: scroll::Endian,
+    ) -> Result<MinidumpHandleDataStream, Error> {
+        let header: md::MINIDUMP_HANDLE_DATA_STREAM = bytes
+            .pread_with(0, endian)
+            .or(Err(Error::StreamReadFailure))?;
+        let size_of_descriptor = header.size_of_descriptor as usize;
+        if size_of_descriptor != md::MINIDUMP_HANDLE_DESCRIPTOR::size_with(&endian)
+            && size_of_descriptor != md::MINIDUMP_HANDLE_DESCRIPTOR_2::size_with(&endian)
+        {
+            return Err(Error::StreamReadFailure);
+        }
+        let size_of_header = header.size_of_header as usize;
+        ensure_list_fits(
+            bytes,
+            size_of_header,
+            header.number_of_descriptors,
+            size_of_descriptor,
+        )?;
+
+        let handles = (0..header.number_of_descriptors as usize)
+            .map(|i| {
+                let offset = size_of_header + i * size_of_descriptor;
+                MinidumpHandleDescriptor::read(bytes, offset, size_of_descriptor, all, endian)
+            })
+            .collect::<Result<Vec<_>, _>>()?;
+        Ok(MinidumpHandleDataStream { handles })
+    }
+}
+
+impl MinidumpHandleDataStream {
+    /// Iterate over the handles in the order they were stored in the minidump.
+    pub fn iter(&self) -> impl Iterator<Item = &MinidumpHandleDescriptor> {
+        self.handles.iter()
+    }
+
+    /// Write a human-readable description of this `MinidumpHandleDataStream` to `f`.
+    ///
+    /// This is very verbose, it is the format used by `minidump_dump`.
+    pub fn print<T: Write>(&self, f: &mut T) -> io::Result<()> {
+        write!(
+            f,
+            "MinidumpHandleDataStream
+  handle_count = {}
+
+",
+            self.handles.len()
+        )?;
+        for (i, handle) in self.handles.iter().enumerate() {
+            writeln!(f, "handle[{}]", i)?;
+            handle.print(f)?;
+        }
+        Ok(())
+    }
+}
+
+impl MinidumpHandleOperation {
+    /// The operating system handle value.
+    pub fn handle(&self) -> u64 {
+        self.raw.handle
+    }
+
+    /// The return addresses of the stack the operation was performed from, innermost first.
+    pub fn backtrace(&self) -> &[u64] {
+        let addresses = &self.raw.back_trace_information.return_addresses;
+        let depth = cmp::min(
+            self.raw.back_trace_information.depth as usize,
+            addresses.len(),
+        );
+        &addresses[..depth]
+    }
+
+    /// The backtrace's return addresses, with the module each one is in, if any.
+    pub fn backtrace_modules<'m>(
+        &self,
+        modules: &'m MinidumpModuleList,
+    ) -> Vec<(u64, Option<&'m MinidumpModule>)> {
+        self.backtrace()
+            .iter()
+            .map(|&address| (address, modules.module_at_address(address)))
+            .collect()
+    }
+
+    /// Write a human-readable description of this `MinidumpHandleOperation` to `f`.
+    ///
+    /// If `modules` is given, the backtrace's return addresses are shown as
+    /// offsets in the modules they're in.
+    ///
+    /// This is very verbose, it is the format used by `minidump_dump`.
+    pub fn print<T: Write>(
+        &self,
+        f: &mut T,
+        modules: Option<&MinidumpModuleList>,
+    ) -> io::Result<()> {
+        let operation = match self.operation {
+            Some(operation) => format!("{:?}", operation),
+            None => String::from("unknown"),
+        };
+        write!(
+            f,
+            "AVRF_HANDLE_OPERATION
+  handle                              = {:#x}
+  process_id                          = {:#x}
+  thread_id                           = {:#x}
+  operation_type                      = {} ({})
+  back_trace_information.depth        = {}
+  back_trace_information.index        = {:#x}
+",
+            self.raw.handle,
+            self.raw.process_id,
+            self.raw.thread_id,
+            self.raw.operation_type,
+            operation,
+            self.raw.back_trace_information.depth,
+            self.raw.back_trace_information.index,
+        )?;
+        for (i, &address) in self.backtrace().iter().enumerate() {
+            write!(
+                f,
+                "  back_trace_information.return_addresses[{:2}] = {:#x}",
+                i, address
+            )?;
+            if let Some(module) = modules.and_then(|modules| modules.module_at_address(address)) {
+                write!(
+                    f,
+                    " ({} + {:#x})",
+                    module.code_file(),
+                    address - module.base_address()
+                )?;
+            }
+            writeln!(f)?;
+        }
+        writeln!(f)?;
+        Ok(())
+    }
+}
+
+impl<'a> MinidumpStream<'a> for MinidumpHandleOperationList {
+    const STREAM_TYPE: MINIDUMP_STREAM_TYPE = MINIDUMP_STREAM_TYPE::HandleOperationListStream;
+
+    fn read(
+        bytes: &'a [u8],
+        _all: &'a [u8],
+        endian: scroll::Endian,
+    ) -> Result<MinidumpHandleOperationList, Error> {
+        let header: md::MINIDUMP_HANDLE_OPERATION_LIST = bytes
+            .pread_with(0, endian)
+            .or(Err(Error::StreamReadFailure))?;
+        let size_of_entry = header.size_of_entry as usize;
+        if size_of_entry < md::AVRF_HANDLE_OPERATION::size_with(&endian) {
+            return Err(Error::StreamReadFailure);
+        }
+        let size_of_header = header.size_of_header as usize;
+        ensure_list_fits(
+            bytes,
+            size_of_header,
+            header.number_of_entries,
+            size_of_entry,
+        )?;
+
+        let operations = (0..header.number_of_entries as usize)
+            .map(|i| {
+                let offset = size_of_header + i * size_of_entry;
+                let raw: md::AVRF_HANDLE_OPERATION = bytes
+                    .pread_with(offset, endian)
+                    .or(Err(Error::StreamReadFailure))?;
+                let operation = md::HandleTraceOperation::from_u32(raw.operation_type);
+                Ok(MinidumpHandleOperation { raw, operation })
+            })
+            .collect::<Result<Vec<_>, Error>>()?;
+        Ok(MinidumpHandleOperationList { operations })
+    }
+}
+
+impl MinidumpHandleOperationList {
+    /// Iterate over the operations in the order they were stored in the minidump.
+    pub fn iter(&self) -> impl Iterator<Item = &MinidumpHandleOperation> {
+        self.operations.iter()
+    }
+
+    /// The operations on `handle`, in the order they were stored in the minidump.
+    pub fn operations_on(&self, handle: u64) -> impl Iterator<Item = &MinidumpHandleOperation> {
+        self.operations
+            .iter()
+            .filter(move |operation| operation.handle() == handle)
+    }
+
+    /// Write a human-readable description of this `MinidumpHandleOperationList` to `f`.
+    ///
+    /// If `modules` is given, backtraces are shown as offsets in the modules
+    /// they're in.
+    ///
+    /// This is very verbose, it is the format used by `minidump_dump`.
+    pub fn print<T: Write>(
+        &self,
+        f: &mut T,
+        modules: Option<&MinidumpModuleList>,
+    ) -> io::Result<()> {
+        write!(
+            f,
+            "MinidumpHandleOperationList
+  operation_count = {}
+
+",
+            self.operations.len()
+        )?;
+        for (i, operation) in self.operations.iter().enumerate() {
+            writeln!(f, "operation[{}]", i)?;
+            operation.print(f, modules)?;
+        }
+        Ok(())
+    }
+}
+
 impl<'a> MinidumpStream<'a> for MinidumpBreakpadInfo {
     const STREAM_TYPE: MINIDUMP_STREAM_TYPE = MINIDUMP_STREAM_TYPE::BreakpadInfoStream;
 
@@ -1997,9 +3910,217 @@ impl MinidumpBreakpadInfo {
 
 impl CrashReason {
     /// Get a `CrashReason` from a `MINIDUMP_EXCEPTION_STREAM` for a given `Os`.
-    fn from_exception(_raw: &md::MINIDUMP_EXCEPTION_STREAM, _os: Os) -> CrashReason {
-        // TODO: flesh this out
-        CrashReason::Unknown
+    fn from_exception(raw: &md::MINIDUMP_EXCEPTION_STREAM, os: Os) -> CrashReason {
+        CrashReason::from_record(&raw.exception_record, os)
+    }
+
+    /// Get a `CrashReason` from a single exception record for a given `Os`.
+    ///
+    /// This is useful for the nested records of
+    /// [`MinidumpException::get_exception_chain`][chain].
+    ///
+    /// [chain]: struct.MinidumpException.html#method.get_exception_chain
+    pub fn from_record(record: &md::MINIDUMP_EXCEPTION, os: Os) -> CrashReason {
+        let code = record.exception_code;
+        let flags = record.exception_flags;
+        match os {
+            Os::Windows => match ExceptionDetails::from_record(record, os) {
+                ExceptionDetails::AccessViolation { kind, .. } => {
+                    CrashReason::WindowsAccessViolation(kind)
+                }
+                ExceptionDetails::InPageError { kind, .. } => CrashReason::WindowsInPageError(kind),
+                _ => match md::ExceptionCodeWindows::from_u32(code) {
+                    Some(exception) => CrashReason::Windows(exception),
+                    None => CrashReason::WindowsUnknown(code),
+                },
+            },
+            Os::MacOs | Os::Ios => match md::ExceptionCodeMac::from_u32(code) {
+                Some(exception) => CrashReason::Mac(exception, flags),
+                None => CrashReason::MacUnknown(code, flags),
+            },
+            Os::Linux | Os::Android => match md::ExceptionCodeLinux::from_u32(code) {
+                Some(signal) => CrashReason::Linux(signal, flags),
+                None => CrashReason::LinuxUnknown(code, flags),
+            },
+            _ => CrashReason::Unknown,
+        }
+    }
+
+    /// The name of the exception or signal, without any subcode.
+    ///
+    /// For example `EXCEPTION_ACCESS_VIOLATION`, `SIGSEGV` or `EXC_BAD_ACCESS`. Exceptions
+    /// with unrecognized codes are `unknown`. Unlike the `Display` output this is a fixed
+    /// set of strings, suitable for grouping crashes.
+    pub fn as_str(&self) -> &'static str {
+        match *self {
+            CrashReason::WindowsAccessViolation(_) => "EXCEPTION_ACCESS_VIOLATION",
+            CrashReason::WindowsInPageError(_) => "EXCEPTION_IN_PAGE_ERROR",
+            CrashReason::Windows(exception) => windows_exception_name(exception),
+            CrashReason::Linux(signal, _) => linux_signal_name(signal),
+            CrashReason::Mac(exception, _) => mac_exception_name(exception),
+            CrashReason::WindowsUnknown(_)
+            | CrashReason::LinuxUnknown(..)
+            | CrashReason::MacUnknown(..)
+            | CrashReason::Unknown => "unknown",
+        }
+    }
+
+    /// The raw exception code: an `NTSTATUS` on Windows, a signal number on Linux, or a
+    /// Mach exception type on macOS.
+    pub fn code(&self) -> Option<u32> {
+        match *self {
+            CrashReason::WindowsAccessViolation(_) => {
+                Some(md::ExceptionCodeWindows::EXCEPTION_ACCESS_VIOLATION as u32)
+            }
+            CrashReason::WindowsInPageError(_) => {
+                Some(md::ExceptionCodeWindows::EXCEPTION_IN_PAGE_ERROR as u32)
+            }
+            CrashReason::Windows(exception) => Some(exception as u32),
+            CrashReason::WindowsUnknown(code) => Some(code),
+            CrashReason::Linux(signal, _) => Some(signal as u32),
+            CrashReason::LinuxUnknown(signal, _) => Some(signal),
+            CrashReason::Mac(exception, _) => Some(exception as u32),
+            CrashReason::MacUnknown(exception, _) => Some(exception),
+            CrashReason::Unknown => None,
+        }
+    }
+}
+
+fn windows_exception_name(exception: md::ExceptionCodeWindows) -> &'static str {
+    use md::ExceptionCodeWindows::*;
+    match exception {
+        DBG_CONTROL_C => "DBG_CONTROL_C",
+        EXCEPTION_GUARD_PAGE => "EXCEPTION_GUARD_PAGE",
+        EXCEPTION_DATATYPE_MISALIGNMENT => "EXCEPTION_DATATYPE_MISALIGNMENT",
+        EXCEPTION_BREAKPOINT => "EXCEPTION_BREAKPOINT",
+        EXCEPTION_SINGLE_STEP => "EXCEPTION_SINGLE_STEP",
+        EXCEPTION_ACCESS_VIOLATION => "EXCEPTION_ACCESS_VIOLATION",
+        EXCEPTION_IN_PAGE_ERROR => "EXCEPTION_IN_PAGE_ERROR",
+        EXCEPTION_INVALID_HANDLE => "EXCEPTION_INVALID_HANDLE",
+        EXCEPTION_ILLEGAL_INSTRUCTION => "EXCEPTION_ILLEGAL_INSTRUCTION",
+        EXCEPTION_NONCONTINUABLE_EXCEPTION => "EXCEPTION_NONCONTINUABLE_EXCEPTION",
+        EXCEPTION_INVALID_DISPOSITION => "EXCEPTION_INVALID_DISPOSITION",
+        EXCEPTION_BOUNDS_EXCEEDED => "EXCEPTION_BOUNDS_EXCEEDED",
+        EXCEPTION_FLT_DENORMAL_OPERAND => "EXCEPTION_FLT_DENORMAL_OPERAND",
+        EXCEPTION_FLT_DIVIDE_BY_ZERO => "EXCEPTION_FLT_DIVIDE_BY_ZERO",
+        EXCEPTION_FLT_INEXACT_RESULT => "EXCEPTION_FLT_INEXACT_RESULT",
+        EXCEPTION_FLT_INVALID_OPERATION => "EXCEPTION_FLT_INVALID_OPERATION",
+        EXCEPTION_FLT_OVERFLOW => "EXCEPTION_FLT_OVERFLOW",
+        EXCEPTION_FLT_STACK_CHECK => "EXCEPTION_FLT_STACK_CHECK",
+        EXCEPTION_FLT_UNDERFLOW => "EXCEPTION_FLT_UNDERFLOW",
+        EXCEPTION_INT_DIVIDE_BY_ZERO => "EXCEPTION_INT_DIVIDE_BY_ZERO",
+        EXCEPTION_INT_OVERFLOW => "EXCEPTION_INT_OVERFLOW",
+        EXCEPTION_PRIV_INSTRUCTION => "EXCEPTION_PRIV_INSTRUCTION",
+        EXCEPTION_STACK_OVERFLOW => "EXCEPTION_STACK_OVERFLOW",
+        EXCEPTION_POSSIBLE_DEADLOCK => "EXCEPTION_POSSIBLE_DEADLOCK",
+        STATUS_STACK_BUFFER_OVERRUN => "STATUS_STACK_BUFFER_OVERRUN",
+        STATUS_HEAP_CORRUPTION => "STATUS_HEAP_CORRUPTION",
+        OUT_OF_MEMORY => "OUT_OF_MEMORY",
+        UNHANDLED_CPP_EXCEPTION => "UNHANDLED_CPP_EXCEPTION",
+        SIMULATED => "SIMULATED",
+    }
+}
+
+fn linux_signal_name(signal: md::ExceptionCodeLinux) -> &'static str {
+    use md::ExceptionCodeLinux::*;
+    match signal {
+        SIGHUP => "SIGHUP",
+        SIGINT => "SIGINT",
+        SIGQUIT => "SIGQUIT",
+        SIGILL => "SIGILL",
+        SIGTRAP => "SIGTRAP",
+        SIGABRT => "SIGABRT",
+        SIGBUS => "SIGBUS",
+        SIGFPE => "SIGFPE",
+        SIGKILL => "SIGKILL",
+        SIGUSR1 => "SIGUSR1",
+        SIGSEGV => "SIGSEGV",
+        SIGUSR2 => "SIGUSR2",
+        SIGPIPE => "SIGPIPE",
+        SIGALRM => "SIGALRM",
+        SIGTERM => "SIGTERM",
+        SIGSTKFLT => "SIGSTKFLT",
+        SIGCHLD => "SIGCHLD",
+        SIGCONT => "SIGCONT",
+        SIGSTOP => "SIGSTOP",
+        SIGTSTP => "SIGTSTP",
+        SIGTTIN => "SIGTTIN",
+        SIGTTOU => "SIGTTOU",
+        SIGURG => "SIGURG",
+        SIGXCPU => "SIGXCPU",
+        SIGXFSZ => "SIGXFSZ",
+        SIGVTALRM => "SIGVTALRM",
+        SIGPROF => "SIGPROF",
+        SIGWINCH => "SIGWINCH",
+        SIGIO => "SIGIO",
+        SIGPWR => "SIGPWR",
+        SIGSYS => "SIGSYS",
+        DUMP_REQUESTED => "DUMP_REQUESTED",
+    }
+}
+
+/// The name of a signal's `si_code`, for the signals whose codes are signal-specific.
+///
+/// Returns `None` for signals whose codes aren't worth describing, and `Some(None)` for
+/// unrecognized codes of the others.
+fn linux_signal_code_name(
+    signal: md::ExceptionCodeLinux,
+    code: u32,
+) -> Option<Option<&'static str>> {
+    use md::ExceptionCodeLinux::*;
+    let names: &[&str] = match signal {
+        SIGILL => &[
+            "ILL_ILLOPC",
+            "ILL_ILLOPN",
+            "ILL_ILLADR",
+            "ILL_ILLTRP",
+            "ILL_PRVOPC",
+            "ILL_PRVREG",
+            "ILL_COPROC",
+            "ILL_BADSTK",
+        ],
+        SIGFPE => &[
+            "FPE_INTDIV",
+            "FPE_INTOVF",
+            "FPE_FLTDIV",
+            "FPE_FLTOVF",
+            "FPE_FLTUND",
+            "FPE_FLTRES",
+            "FPE_FLTINV",
+            "FPE_FLTSUB",
+        ],
+        SIGSEGV => &["SEGV_MAPERR", "SEGV_ACCERR", "SEGV_BNDERR", "SEGV_PKUERR"],
+        SIGBUS => &[
+            "BUS_ADRALN",
+            "BUS_ADRERR",
+            "BUS_OBJERR",
+            "BUS_MCEERR_AR",
+            "BUS_MCEERR_AO",
+        ],
+        _ => return None,
+    };
+    // Codes are numbered from 1.
+    Some(
+        (code as usize)
+            .checked_sub(1)
+            .and_then(|index| names.get(index))
+            .cloned(),
+    )
+}
+
+fn mac_exception_name(exception: md::ExceptionCodeMac) -> &'static str {
+    use md::ExceptionCodeMac::*;
+    match exception {
+        EXC_BAD_ACCESS => "EXC_BAD_ACCESS",
+        EXC_BAD_INSTRUCTION => "EXC_BAD_INSTRUCTION",
+        EXC_ARITHMETIC => "EXC_ARITHMETIC",
+        EXC_EMULATION => "EXC_EMULATION",
+        EXC_SOFTWARE => "EXC_SOFTWARE",
+        EXC_BREAKPOINT => "EXC_BREAKPOINT",
+        EXC_SYSCALL => "EXC_SYSCALL",
+        EXC_MACH_SYSCALL => "EXC_MACH_SYSCALL",
+        EXC_RPC_ALERT => "EXC_RPC_ALERT",
+        SIMULATED => "SIMULATED",
     }
 }
 
@@ -2007,17 +4128,162 @@ impl fmt::Display for CrashReason {
     /// A string describing the crash reason.
     ///
     /// This is OS- and possibly CPU-specific.
-    /// For example, "EXCEPTION_ACCESS_VIOLATION" (Windows),
-    /// "EXC_BAD_ACCESS / KERN_INVALID_ADDRESS" (Mac OS X), "SIGSEGV"
+    /// For example, "EXCEPTION_ACCESS_VIOLATION_READ" (Windows),
+    /// "EXC_BAD_ACCESS / KERN_INVALID_ADDRESS" (Mac OS X), "SIGSEGV / SEGV_MAPERR"
     /// (other Unix).
     fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
-        write!(
-            f,
-            "{}",
-            match *self {
-                CrashReason::Unknown => "unknown",
+        match *self {
+            CrashReason::WindowsAccessViolation(kind) | CrashReason::WindowsInPageError(kind) => {
+                write!(f, "{}{}", self.as_str(), kind.suffix())
             }
-        )
+            CrashReason::Linux(signal, code) => match linux_signal_code_name(signal, code) {
+                Some(Some(name)) => write!(f, "{} / {}", self.as_str(), name),
+                Some(None) => write!(f, "{} / {:#x}", self.as_str(), code),
+                None => write!(f, "{}", self.as_str()),
+            },
+            CrashReason::Mac(md::ExceptionCodeMac::EXC_BAD_ACCESS, code) => match code {
+                1 => write!(f, "EXC_BAD_ACCESS / KERN_INVALID_ADDRESS"),
+                2 => write!(f, "EXC_BAD_ACCESS / KERN_PROTECTION_FAILURE"),
+                _ => write!(f, "EXC_BAD_ACCESS / {:#010x}", code),
+            },
+            CrashReason::WindowsUnknown(code)
+            | CrashReason::LinuxUnknown(code, _)
+            | CrashReason::MacUnknown(code, _) => write!(f, "{:#010x}", code),
+            CrashReason::Windows(_) | CrashReason::Mac(..) | CrashReason::Unknown => {
+                write!(f, "{}", self.as_str())
+            }
+        }
+    }
+}
+
+impl AccessKind {
+    /// Get an `AccessKind` from the first parameter of a Windows access violation.
+    fn from_windows(value: u64) -> AccessKind {
+        match value {
+            0 => AccessKind::Read,
+            1 => AccessKind::Write,
+            8 => AccessKind::Execute,
+            _ => AccessKind::Unknown(value),
+        }
+    }
+
+    fn suffix(&self) -> &'static str {
+        match *self {
+            AccessKind::Read => "_READ",
+            AccessKind::Write => "_WRITE",
+            AccessKind::Execute => "_EXEC",
+            AccessKind::Unknown(_) => "",
+        }
+    }
+}
+
+impl ExceptionDetails {
+    /// Interpret the exception record in `raw` for a given `Os`.
+    pub fn from_exception(raw: &md::MINIDUMP_EXCEPTION_STREAM, os: Os) -> ExceptionDetails {
+        ExceptionDetails::from_record(&raw.exception_record, os)
+    }
+
+    /// Interpret a single exception record for a given `Os`.
+    pub fn from_record(record: &md::MINIDUMP_EXCEPTION, os: Os) -> ExceptionDetails {
+        let params = &record.exception_information[..cmp::min(
+            record.number_parameters as usize,
+            record.exception_information.len(),
+        )];
+        let code = record.exception_code;
+        match os {
+            Os::Windows => match (md::ExceptionCodeWindows::from_u32(code), params) {
+                (
+                    Some(md::ExceptionCodeWindows::EXCEPTION_ACCESS_VIOLATION),
+                    [kind, address, ..],
+                ) => ExceptionDetails::AccessViolation {
+                    kind: AccessKind::from_windows(*kind),
+                    address: *address,
+                },
+                (
+                    Some(md::ExceptionCodeWindows::EXCEPTION_IN_PAGE_ERROR),
+                    [kind, address, status, ..],
+                ) => ExceptionDetails::InPageError {
+                    kind: AccessKind::from_windows(*kind),
+                    address: *address,
+                    status: *status as u32,
+                },
+                _ => ExceptionDetails::Other {
+                    code,
+                    address: record.exception_address,
+                },
+            },
+            // Breakpad stores the Mach exception code in `exception_flags` and the
+            // subcode, usually the faulting address, in `exception_address`.
+            Os::MacOs | Os::Ios => match md::ExceptionCodeMac::from_u32(code) {
+                Some(md::ExceptionCodeMac::EXC_BAD_ACCESS) => ExceptionDetails::MachBadAccess {
+                    code: record.exception_flags,
+                    address: record.exception_address,
+                },
+                _ => ExceptionDetails::Other {
+                    code,
+                    address: record.exception_address,
+                },
+            },
+            // Breakpad stores the signal's `si_code` in `exception_flags` and `si_addr`
+            // in `exception_address`.
+            Os::Linux | Os::Android => match md::ExceptionCodeLinux::from_u32(code) {
+                Some(md::ExceptionCodeLinux::SIGSEGV) | Some(md::ExceptionCodeLinux::SIGBUS) => {
+                    ExceptionDetails::SignalFault {
+                        signal: code,
+                        code: record.exception_flags,
+                        address: record.exception_address,
+                    }
+                }
+                _ => ExceptionDetails::Other {
+                    code,
+                    address: record.exception_address,
+                },
+            },
+            _ => ExceptionDetails::Other {
+                code,
+                address: record.exception_address,
+            },
+        }
+    }
+
+    /// The memory address implicated in the exception.
+    ///
+    /// For memory access faults this is the address that was accessed, otherwise it is the
+    /// address of the instruction that raised the exception.
+    pub fn address(&self) -> u64 {
+        match *self {
+            ExceptionDetails::AccessViolation { address, .. }
+            | ExceptionDetails::InPageError { address, .. }
+            | ExceptionDetails::MachBadAccess { address, .. }
+            | ExceptionDetails::SignalFault { address, .. }
+            | ExceptionDetails::Other { address, .. } => address,
+        }
+    }
+}
+
+impl fmt::Display for ExceptionDetails {
+    /// A string describing the exception, such as `EXCEPTION_ACCESS_VIOLATION_WRITE`,
+    /// `EXC_BAD_ACCESS / KERN_INVALID_ADDRESS`, or `SIGSEGV / SEGV_MAPERR`.
+    ///
+    /// This matches the `Display` output of the corresponding `CrashReason`.
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        let reason = match *self {
+            ExceptionDetails::AccessViolation { kind, .. } => {
+                CrashReason::WindowsAccessViolation(kind)
+            }
+            ExceptionDetails::InPageError { kind, .. } => CrashReason::WindowsInPageError(kind),
+            ExceptionDetails::MachBadAccess { code, .. } => {
+                CrashReason::Mac(md::ExceptionCodeMac::EXC_BAD_ACCESS, code)
+            }
+            ExceptionDetails::SignalFault { signal, code, .. } => {
+                match md::ExceptionCodeLinux::from_u32(signal) {
+                    Some(signal) => CrashReason::Linux(signal, code),
+                    None => CrashReason::LinuxUnknown(signal, code),
+                }
+            }
+            ExceptionDetails::Other { code, .. } => return write!(f, "{:#010x}", code),
+        };
+        write!(f, "{}", reason)
     }
 }
 
@@ -2033,7 +4299,7 @@ impl<'a> MinidumpStream<'a> for MinidumpException {
             .pread_with(0, endian)
             .or(Err(Error::StreamReadFailure))?;
         let context_data = location_slice(all, &raw.thread_context)?;
-        let context = MinidumpContext::read(context_data, endian).ok();
+        let context = read_context(context_data, all, endian).ok();
         let thread_id = raw.thread_id;
         Ok(MinidumpException {
             raw,
@@ -2046,18 +4312,12 @@ impl<'a> MinidumpStream<'a> for MinidumpException {
 impl MinidumpException {
     /// Get the crash address for an exception.
     pub fn get_crash_address(&self, os: Os) -> u64 {
-        match (
-            os,
-            md::ExceptionCodeWindows::from_u32(self.raw.exception_record.exception_code),
-        ) {
-            (Os::Windows, Some(md::ExceptionCodeWindows::EXCEPTION_ACCESS_VIOLATION))
-            | (Os::Windows, Some(md::ExceptionCodeWindows::EXCEPTION_IN_PAGE_ERROR))
-                if self.raw.exception_record.number_parameters >= 2 =>
-            {
-                self.raw.exception_record.exception_information[1]
-            }
-            _ => self.raw.exception_record.exception_address,
-        }
+        self.get_exception_details(os).address()
+    }
+
+    /// Interpret the exception record's parameters for a given `Os`.
+    pub fn get_exception_details(&self, os: Os) -> ExceptionDetails {
+        ExceptionDetails::from_exception(&self.raw, os)
     }
 
     /// Get the crash reason for an exception.
@@ -2069,6 +4329,71 @@ impl MinidumpException {
         self.thread_id
     }
 
+    /// Get the chain of nested exception records, starting with this exception's own.
+    ///
+    /// Each record's `exception_record` field is the address of the next record in the
+    /// process's memory, so only records in `memory_list` can be found. `cpu` is the
+    /// system's CPU, which tells how the records are laid out. The exception context's
+    /// CPU is used instead if there is one, since a 32-bit process's records are 32-bit
+    /// even on a 64-bit system.
+    ///
+    /// The chain stops at a null address, at a record that isn't in the dumped memory,
+    /// at a record that's already in the chain, or after [`MAX_EXCEPTION_CHAIN_LENGTH`]
+    /// records, so a corrupt chain can't go on forever.
+    ///
+    /// [`MAX_EXCEPTION_CHAIN_LENGTH`]: constant.MAX_EXCEPTION_CHAIN_LENGTH.html
+    pub fn get_exception_chain(
+        &self,
+        memory_list: &MinidumpMemoryList<'_>,
+        cpu: Cpu,
+    ) -> Vec<md::MINIDUMP_EXCEPTION> {
+        let is_64bit = match self.context {
+            Some(ref context) => context.pointer_width() == 8,
+            None => !matches!(cpu, Cpu::X86 | Cpu::Arm | Cpu::Ppc | Cpu::Mips),
+        };
+        let mut chain = vec![self.raw.exception_record.clone()];
+        let mut visited = HashSet::new();
+        while chain.len() < MAX_EXCEPTION_CHAIN_LENGTH {
+            let address = chain[chain.len() - 1].exception_record;
+            if address == 0 || !visited.insert(address) {
+                break;
+            }
+            let memory = match memory_list.memory_at_address(address) {
+                Some(memory) => memory,
+                None => break,
+            };
+            let record = if is_64bit {
+                memory.get_memory_at_address::<md::MINIDUMP_EXCEPTION>(address)
+            } else {
+                memory
+                    .get_memory_at_address::<md::EXCEPTION_RECORD32>(address)
+                    .map(|raw| {
+                        let mut exception_information = [0; 15];
+                        for (param, &raw_param) in exception_information
+                            .iter_mut()
+                            .zip(raw.exception_information.iter())
+                        {
+                            *param = raw_param as u64;
+                        }
+                        md::MINIDUMP_EXCEPTION {
+                            exception_code: raw.exception_code,
+                            exception_flags: raw.exception_flags,
+                            exception_record: raw.exception_record as u64,
+                            exception_address: raw.exception_address as u64,
+                            number_parameters: raw.number_parameters,
+                            __align: 0,
+                            exception_information,
+                        }
+                    })
+            };
+            match record {
+                Some(record) => chain.push(record),
+                None => break,
+            }
+        }
+        chain
+    }
+
     /// Write a human-readable description of this `MinidumpException` to `f`.
     ///
     /// This is very verbose, it is the format used by `minidump_dump`.
@@ -2157,14 +4482,48 @@ impl MinidumpAssertion {
         utf16_to_string(&self.raw.file)
     }
 
-    /// Write a human-readable description of this `MinidumpAssertion` to `f`.
+    /// A one-line description of the assertion, the way Breakpad's processor
+    /// describes it.
     ///
-    /// This is very verbose, it is the format used by `minidump_dump`.
-    pub fn print<T: Write>(&self, f: &mut T) -> io::Result<()> {
-        write!(
-            f,
-            "MDAssertion
-  expression                                 = {}
+    /// This names the expression, function and source location when they're
+    /// known, and otherwise the kind of assertion.
+    pub fn description(&self) -> String {
+        let mut description = self.expression().unwrap_or_default();
+        if let Some(function) = self.function().filter(|function| !function.is_empty()) {
+            if !description.is_empty() {
+                description.push_str(" in ");
+            }
+            description.push_str("function ");
+            description.push_str(&function);
+        }
+        if let Some(file) = self.file().filter(|file| !file.is_empty()) {
+            if !description.is_empty() {
+                description.push_str(", ");
+            }
+            description.push_str(&format!("line {} in file {}", self.raw.line, file));
+        }
+        if description.is_empty() {
+            description = match md::AssertionType::from_u32(self.raw._type) {
+                Some(md::AssertionType::InvalidParameter) => {
+                    "Invalid parameter passed to library function".to_owned()
+                }
+                Some(md::AssertionType::PureVirtualCall) => {
+                    "Pure virtual function called".to_owned()
+                }
+                _ => format!("Unknown assertion type {:#010x}", self.raw._type),
+            };
+        }
+        description
+    }
+
+    /// Write a human-readable description of this `MinidumpAssertion` to `f`.
+    ///
+    /// This is very verbose, it is the format used by `minidump_dump`.
+    pub fn print<T: Write>(&self, f: &mut T) -> io::Result<()> {
+        write!(
+            f,
+            "MDAssertion
+  expression                                 = {}
   function                                   = {}
   file                                       = {}
   line                                       = {}
@@ -2196,6 +4555,7 @@ fn read_string_list(
     let count: u32 = data
         .gread_with(&mut offset, endian)
         .or(Err(Error::StreamReadFailure))?;
+    ensure_list_fits(data, offset, count, mem::size_of::<md::RVA>())?;
 
     let mut strings = Vec::with_capacity(count as usize);
     for _ in 0..count {
@@ -2230,6 +4590,12 @@ fn read_simple_string_dictionary(
     let count: u32 = data
         .gread_with(&mut offset, endian)
         .or(Err(Error::StreamReadFailure))?;
+    ensure_list_fits(
+        data,
+        offset,
+        count,
+        md::MINIDUMP_SIMPLE_STRING_DICTIONARY_ENTRY::size_with(&endian),
+    )?;
 
     for _ in 0..count {
         let entry: md::MINIDUMP_SIMPLE_STRING_DICTIONARY_ENTRY = data
@@ -2264,6 +4630,12 @@ fn read_annotation_objects(
     let count: u32 = data
         .gread_with(&mut offset, endian)
         .or(Err(Error::StreamReadFailure))?;
+    ensure_list_fits(
+        data,
+        offset,
+        count,
+        md::MINIDUMP_ANNOTATION::size_with(&endian),
+    )?;
 
     for _ in 0..count {
         let raw: md::MINIDUMP_ANNOTATION = data
@@ -2334,6 +4706,12 @@ fn read_crashpad_module_links(
     let count: u32 = data
         .gread_with(&mut offset, endian)
         .or(Err(Error::StreamReadFailure))?;
+    ensure_list_fits(
+        data,
+        offset,
+        count,
+        md::MINIDUMP_MODULE_CRASHPAD_INFO_LINK::size_with(&endian),
+    )?;
 
     let mut module_links = Vec::with_capacity(count as usize);
     for _ in 0..count {
@@ -2443,6 +4821,162 @@ impl MinidumpCrashpadInfo {
     }
 }
 
+impl<'a> Minidump<'a, Vec<u8>> {
+    /// Read a minidump from `reader`, starting at its current position, with its
+    /// streams read on demand.
+    ///
+    /// This is for minidumps that aren't files on disk or already in memory, such as
+    /// an entry in an archive or the body of a network response wrapped to support
+    /// seeking. See [`MinidumpReader`][reader] for what can be read from it.
+    ///
+    /// [reader]: struct.MinidumpReader.html
+    pub fn read_from<R>(reader: R) -> Result<MinidumpReader<R>, Error>
+    where
+        R: Read + Seek,
+    {
+        MinidumpReader::new(reader)
+    }
+}
+
+/// A minidump read from a `Read + Seek` source, with each stream read when it's asked for.
+///
+/// The header and stream directory are read and checked up front, so something
+/// that isn't a minidump is rejected after reading a few bytes. After that, each
+/// stream's body is read with a seek and a read of just that stream, so only the
+/// streams that are used are read, and a stream's claimed size only costs as much
+/// as the data that's actually there.
+///
+/// Each read is a seek on `R`, so reading many small pieces is slower than slicing
+/// a [`Minidump`][minidump] in memory or mapped from a file with
+/// [`Minidump::read_path`][read_path], which should be preferred for files on disk.
+/// Parsed streams refer to data anywhere in the minidump, so getting them needs
+/// all of it: [`into_minidump`][into_minidump] reads the rest for that.
+///
+/// [minidump]: struct.Minidump.html
+/// [read_path]: struct.Minidump.html#method.read_path
+/// [into_minidump]: #method.into_minidump
+#[derive(Debug)]
+pub struct MinidumpReader<R> {
+    reader: R,
+    /// Where the minidump starts in `reader`.
+    start: u64,
+    /// The raw minidump header.
+    pub header: md::MINIDUMP_HEADER,
+    /// The stream directory, in order.
+    directory: Vec<md::MINIDUMP_DIRECTORY>,
+    /// The endianness of this minidump.
+    pub endian: scroll::Endian,
+}
+
+impl<R> MinidumpReader<R>
+where
+    R: Read + Seek,
+{
+    /// Read the header and stream directory of the minidump that starts at
+    /// `reader`'s current position.
+    pub fn new(mut reader: R) -> Result<MinidumpReader<R>, Error> {
+        let start = reader.stream_position().or(Err(Error::IoError))?;
+        let mut header = vec![0; md::MINIDUMP_HEADER::size_with(&LE)];
+        reader
+            .read_exact(&mut header)
+            .or(Err(Error::MissingHeader))?;
+        let (header, endian) = read_header(&header)?;
+
+        reader
+            .seek(SeekFrom::Start(start + header.stream_directory_rva as u64))
+            .or(Err(Error::MissingDirectory))?;
+        // The stream count isn't trusted to size anything: a garbage count
+        // stops at the end of the input.
+        let mut entry = vec![0; md::MINIDUMP_DIRECTORY::size_with(&endian)];
+        let mut directory = vec![];
+        for _ in 0..header.stream_count {
+            reader
+                .read_exact(&mut entry)
+                .or(Err(Error::MissingDirectory))?;
+            directory.push(
+                entry
+                    .pread_with(0, endian)
+                    .or(Err(Error::MissingDirectory))?,
+            );
+        }
+        Ok(MinidumpReader {
+            reader,
+            start,
+            header,
+            directory,
+            endian,
+        })
+    }
+
+    /// The types of the streams in the minidump, in stream directory order.
+    ///
+    /// Unlike [`Minidump::stream_types`][stream_types], a type that appears more
+    /// than once in the directory is listed each time.
+    ///
+    /// [stream_types]: struct.Minidump.html#method.stream_types
+    pub fn stream_types(&self) -> Vec<u32> {
+        self.directory.iter().map(|dir| dir.stream_type).collect()
+    }
+
+    /// Read the raw data of a stream.
+    ///
+    /// If the stream type appears more than once in the directory, this reads
+    /// the last one, like [`Minidump::get_raw_stream`][get_raw_stream].
+    ///
+    /// [get_raw_stream]: struct.Minidump.html#method.get_raw_stream
+    pub fn read_raw_stream<S>(&mut self, stream_type: S) -> Result<Vec<u8>, Error>
+    where
+        S: Into<u32>,
+    {
+        let stream_type = stream_type.into();
+        let location = self
+            .directory
+            .iter()
+            .rev()
+            .find(|dir| dir.stream_type == stream_type)
+            .ok_or(Error::StreamNotFound)?
+            .location;
+        self.read_location(&location)
+    }
+
+    /// Read the data `location` refers to, such as a stream or memory region.
+    pub fn read_location(
+        &mut self,
+        location: &md::MINIDUMP_LOCATION_DESCRIPTOR,
+    ) -> Result<Vec<u8>, Error> {
+        let truncated = |actual: u64| Error::StreamTruncated {
+            expected: (location.rva as usize).saturating_add(location.data_size as usize),
+            actual: actual as usize,
+        };
+        self.reader
+            .seek(SeekFrom::Start(self.start + location.rva as u64))
+            .or(Err(Error::IoError))?;
+        // Only as much memory as the data that's there is taken, whatever
+        // size the location claims.
+        let mut data = vec![];
+        let read = (&mut self.reader)
+            .take(location.data_size as u64)
+            .read_to_end(&mut data)
+            .or(Err(Error::IoError))?;
+        if read != location.data_size as usize {
+            return Err(truncated(location.rva as u64 + read as u64));
+        }
+        Ok(data)
+    }
+
+    /// Read the rest of the minidump into memory, to get its parsed streams.
+    ///
+    /// This takes as much memory as the minidump is large.
+    pub fn into_minidump<'a>(mut self) -> Result<Minidump<'a, Vec<u8>>, Error> {
+        self.reader
+            .seek(SeekFrom::Start(self.start))
+            .or(Err(Error::IoError))?;
+        let mut data = vec![];
+        self.reader.read_to_end(&mut data).or(Err(Error::IoError))?;
+        Minidump::read(data)
+    }
+}
+
 impl<'a> Minidump<'a, Mmap> {
     /// Read a `Minidump` from a `Path` to a file on disk.
     ///
@@ -2457,6 +4991,108 @@ impl<'a> Minidump<'a, Mmap> {
     }
 }
 
+#[cfg(feature = "mmap")]
+impl<'a> Minidump<'a, memmap2::Mmap> {
+    /// Read a `Minidump` by memory-mapping the file at `path`.
+    ///
+    /// Only the header and stream directory are read up front. Streams are
+    /// parsed from the mapping as they are requested with [`get_stream`], so
+    /// large full-memory dumps can be processed without reading the whole file
+    /// into memory.
+    ///
+    /// [`get_stream`]: #method.get_stream
+    pub fn read_mmap<P>(path: P) -> Result<Minidump<'a, memmap2::Mmap>, Error>
+    where
+        P: AsRef<Path>,
+    {
+        let f = File::open(path).or(Err(Error::FileNotFound))?;
+        let mmap = unsafe { memmap2::Mmap::map(&f).or(Err(Error::IoError))? };
+        Minidump::read(mmap)
+    }
+}
+
+/// Read a pointer from the dumped memory at `address`.
+fn read_pointer(memory_list: &MinidumpMemoryList<'_>, address: u64, is_64bit: bool) -> Option<u64> {
+    let memory = memory_list.memory_at_address(address)?;
+    if is_64bit {
+        memory.get_memory_at_address::<u64>(address)
+    } else {
+        memory
+            .get_memory_at_address::<u32>(address)
+            .map(|pointer| pointer as u64)
+    }
+}
+
+/// Read the Windows `UNICODE_STRING` at `address` from the dumped memory.
+fn read_unicode_string(
+    memory_list: &MinidumpMemoryList<'_>,
+    address: u64,
+    layout: &LdrLayout,
+    is_64bit: bool,
+) -> Option<String> {
+    // `Length` is in bytes, and doesn't include a terminating null.
+    let length = memory_list
+        .memory_at_address(address)?
+        .get_memory_at_address::<u16>(address)?;
+    let buffer = read_pointer(
+        memory_list,
+        address.checked_add(layout.string_buffer)?,
+        is_64bit,
+    )?;
+    let window = memory_list.memory_window(buffer, length as u64)?;
+    if window.range.start != buffer || window.bytes.len() != length as usize {
+        return None;
+    }
+    let chars = window
+        .bytes
+        .chunks_exact(2)
+        .map(|c| u16::from_le_bytes([c[0], c[1]]))
+        .collect::<Vec<_>>();
+    Some(String::from_utf16_lossy(&chars))
+}
+
+/// Find where a minidump starts in `data`, for minidumps embedded in a larger
+/// container that doesn't record their offset.
+///
+/// This looks for the first minidump signature, in either byte order, that's
+/// followed by the rest of a header this crate can read. Pass the offset to
+/// [`Minidump::read_at`][read_at]. Data that merely happens to contain the
+/// signature can be mistaken for a minidump, so prefer an offset the container
+/// records if it has one.
+///
+/// [read_at]: struct.Minidump.html#method.read_at
+pub fn find_minidump_header(data: &[u8]) -> Option<usize> {
+    let signature = md::MINIDUMP_SIGNATURE.to_le_bytes();
+    let swapped = md::MINIDUMP_SIGNATURE.to_be_bytes();
+    data.windows(signature.len())
+        .enumerate()
+        .filter(|&(_, window)| window == signature || window == swapped)
+        .map(|(offset, _)| offset)
+        .find(|&offset| read_header(&data[offset..]).is_ok())
+}
+
+/// Read the header at the start of `data`, and work out the minidump's endianness from it.
+fn read_header(data: &[u8]) -> Result<(md::MINIDUMP_HEADER, scroll::Endian), Error> {
+    let mut endian = LE;
+    let mut header: md::MINIDUMP_HEADER =
+        data.pread_with(0, endian).or(Err(Error::MissingHeader))?;
+    if header.signature != md::MINIDUMP_SIGNATURE {
+        if header.signature.swap_bytes() != md::MINIDUMP_SIGNATURE {
+            return Err(Error::HeaderMismatch);
+        }
+        // Try again with big-endian.
+        endian = BE;
+        header = data.pread_with(0, endian).or(Err(Error::MissingHeader))?;
+        if header.signature != md::MINIDUMP_SIGNATURE {
+            return Err(Error::HeaderMismatch);
+        }
+    }
+    if (header.version & 0x0000ffff) != md::MINIDUMP_VERSION {
+        return Err(Error::VersionMismatch);
+    }
+    Ok((header, endian))
+}
+
 impl<'a, T> Minidump<'a, T>
 where
     T: Deref<Target = [u8]> + 'a,
@@ -2466,38 +5102,51 @@ where
     /// Typically this will be a `Vec<u8>` or `&[u8]` with the full contents of the minidump,
     /// but you can also use something like `memmap::Mmap`.
     pub fn read(data: T) -> Result<Minidump<'a, T>, Error> {
-        let mut offset = 0;
-        let mut endian = LE;
-        let mut header: md::MINIDUMP_HEADER = data
-            .gread_with(&mut offset, endian)
-            .or(Err(Error::MissingHeader))?;
-        if header.signature != md::MINIDUMP_SIGNATURE {
-            if header.signature.swap_bytes() != md::MINIDUMP_SIGNATURE {
-                return Err(Error::HeaderMismatch);
-            }
-            // Try again with big-endian.
-            endian = BE;
-            offset = 0;
-            header = data
-                .gread_with(&mut offset, endian)
-                .or(Err(Error::MissingHeader))?;
-            if header.signature != md::MINIDUMP_SIGNATURE {
-                return Err(Error::HeaderMismatch);
-            }
-        }
-        if (header.version & 0x0000ffff) != md::MINIDUMP_VERSION {
-            return Err(Error::VersionMismatch);
+        Minidump::read_at(data, 0)
+    }
+
+    /// Read a `Minidump` that starts `offset` bytes into the provided `data`.
+    ///
+    /// This is for minidumps embedded in a larger container, such as a crash
+    /// report envelope, so they can be read without copying them out. The
+    /// minidump's header must be at `offset`, and everything its stream
+    /// directory refers to must follow it in `data`: the offsets in a minidump
+    /// are relative to its header, so that's what they're read relative to.
+    /// Whatever comes before `offset` or after the end of the minidump is
+    /// ignored.
+    ///
+    /// Passing the minidump's part of a buffer to [`read`][read] as a `&[u8]`
+    /// works too, when borrowing it is convenient. If the container doesn't say
+    /// where the minidump starts, [`find_minidump_header`][find] can look for it.
+    ///
+    /// [read]: #method.read
+    /// [find]: fn.find_minidump_header.html
+    pub fn read_at(data: T, offset: usize) -> Result<Minidump<'a, T>, Error> {
+        let bytes = data.get(offset..).ok_or(Error::MissingHeader)?;
+        let (header, endian) = read_header(bytes)?;
+        // Make sure the whole stream directory is present before trusting the
+        // stream count for anything.
+        let mut directory_offset = header.stream_directory_rva as usize;
+        if ensure_list_fits(
+            bytes,
+            directory_offset,
+            header.stream_count,
+            md::MINIDUMP_DIRECTORY::size_with(&endian),
+        )
+        .is_err()
+        {
+            return Err(Error::MissingDirectory);
         }
         let mut streams = HashMap::with_capacity(header.stream_count as usize);
-        offset = header.stream_directory_rva as usize;
         for i in 0..header.stream_count {
-            let dir: md::MINIDUMP_DIRECTORY = data
-                .gread_with(&mut offset, endian)
+            let dir: md::MINIDUMP_DIRECTORY = bytes
+                .gread_with(&mut directory_offset, endian)
                 .or(Err(Error::MissingDirectory))?;
             streams.insert(dir.stream_type, (i, dir));
         }
         Ok(Minidump {
             data,
+            offset,
             header,
             streams,
             endian,
@@ -2505,6 +5154,11 @@ where
         })
     }
 
+    /// The minidump's contents, from its header on.
+    pub(crate) fn bytes(&self) -> &[u8] {
+        &self.data[self.offset..]
+    }
+
     /// Get a known stream of data from the minidump.
     ///
     /// For streams known to this module whose types implement the
@@ -2523,15 +5177,219 @@ where
         match self.get_raw_stream(S::STREAM_TYPE) {
             Err(e) => Err(e),
             Ok(bytes) => {
-                let all_bytes = self.data.deref();
+                let all_bytes = self.bytes();
                 S::read(bytes, all_bytes, self.endian)
             }
         }
     }
 
+    /// Get a known stream of data from the minidump, telling a stream that's absent
+    /// apart from one that's corrupt.
+    ///
+    /// This is like [`get_stream`][get_stream], for callers that treat those
+    /// cases differently.
+    ///
+    /// [get_stream]: #method.get_stream
+    pub fn get_stream_checked<S>(&'a self) -> Result<S, StreamError>
+    where
+        S: MinidumpStream<'a>,
+    {
+        Ok(self.get_stream::<S>()?)
+    }
+
+    /// Get the module list, enforcing `limits` on its size.
+    ///
+    /// This is like `get_stream::<MinidumpModuleList>()`, which uses the default
+    /// [`ModuleListLimits`][limits].
+    ///
+    /// [limits]: struct.ModuleListLimits.html
+    pub fn get_module_list_with_limits(
+        &'a self,
+        limits: &ModuleListLimits,
+    ) -> Result<MinidumpModuleList, Error> {
+        let bytes = self.get_raw_stream(MinidumpModuleList::STREAM_TYPE)?;
+        MinidumpModuleList::read_with_limits(bytes, self.bytes(), self.endian, limits)
+    }
+
+    /// The address the process image was loaded at, if the minidump records it.
+    ///
+    /// This is currently only known for Linux minidumps with an auxiliary vector
+    /// stream. The main executable's program headers (`AT_PHDR`) follow its ELF
+    /// header in the first page of its image, so rounding their address down to a
+    /// page boundary gives the image base.
+    pub fn process_image_base(&'a self) -> Option<u64> {
+        const AT_NULL: u64 = 0;
+        const AT_PHDR: u64 = 3;
+        const PAGE_MASK: u64 = !0xfff;
+
+        let system_info = self.get_stream::<MinidumpSystemInfo>().ok()?;
+        let is_64bit = matches!(
+            system_info.cpu,
+            Cpu::X86_64 | Cpu::Arm64 | Cpu::Ppc64 | Cpu::Mips64
+        );
+        let auxv = self.get_raw_stream(MINIDUMP_STREAM_TYPE::LinuxAuxv).ok()?;
+        let mut offset = 0;
+        loop {
+            let (key, value) = if is_64bit {
+                let key: u64 = auxv.gread_with(&mut offset, self.endian).ok()?;
+                let value: u64 = auxv.gread_with(&mut offset, self.endian).ok()?;
+                (key, value)
+            } else {
+                let key: u32 = auxv.gread_with(&mut offset, self.endian).ok()?;
+                let value: u32 = auxv.gread_with(&mut offset, self.endian).ok()?;
+                (key as u64, value as u64)
+            };
+            match key {
+                AT_NULL => return None,
+                AT_PHDR => return Some(value & PAGE_MASK),
+                _ => {}
+            }
+        }
+    }
+
+    /// The address of the Process Environment Block of the process that wrote the minidump.
+    ///
+    /// This is only known for Windows minidumps. It isn't stored in the minidump
+    /// directly, but every thread's TEB points to it, so it's read from the first
+    /// TEB whose memory was captured.
+    pub fn peb(&'a self) -> Option<u64> {
+        let is_64bit = self.windows_is_64bit()?;
+        // The offset of `ProcessEnvironmentBlock` in the TEB.
+        let peb_offset = if is_64bit { 0x60 } else { 0x30 };
+        let thread_list = self.get_stream::<MinidumpThreadList<'_>>().ok()?;
+        let memory_list = self.get_stream::<MinidumpMemoryList<'_>>().ok()?;
+        thread_list
+            .threads
+            .iter()
+            .filter_map(|thread| thread.teb()?.checked_add(peb_offset))
+            .find_map(|address| read_pointer(&memory_list, address, is_64bit))
+    }
+
+    /// The modules in the Windows loader's list of modules, read from the dumped memory.
+    ///
+    /// The loader keeps the modules a process has loaded in the `InLoadOrderModuleList`
+    /// of the PEB's loader data. Unlike [`MinidumpModuleList`][module_list], which the
+    /// minidump writer produced, this is read from the process's own memory, so
+    /// comparing the two can reveal a tampered module list. The walk stops at the
+    /// first entry whose memory wasn't dumped, so the result may be incomplete.
+    ///
+    /// Return `None` if the PEB or its loader data weren't dumped.
+    ///
+    /// [module_list]: struct.MinidumpModuleList.html
+    pub fn peb_modules(&'a self) -> Option<Vec<PebModule>> {
+        let is_64bit = self.windows_is_64bit()?;
+        let layout = if is_64bit {
+            &LDR_LAYOUT_64
+        } else {
+            &LDR_LAYOUT_32
+        };
+        let memory_list = self.get_stream::<MinidumpMemoryList<'_>>().ok()?;
+        let pointer_at = |address: u64| read_pointer(&memory_list, address, is_64bit);
+        let read_entry = |entry: u64| {
+            // `InLoadOrderLinks` is the first field of the entry, and links to the next one.
+            let next = pointer_at(entry)?;
+            let base_address = pointer_at(entry.checked_add(layout.dll_base)?)?;
+            let size_address = entry.checked_add(layout.size_of_image)?;
+            let size = memory_list
+                .memory_at_address(size_address)?
+                .get_memory_at_address::<u32>(size_address)?;
+            let name = entry
+                .checked_add(layout.full_dll_name)
+                .and_then(|address| read_unicode_string(&memory_list, address, layout, is_64bit));
+            let module = PebModule {
+                base_address,
+                size: size as u64,
+                name,
+            };
+            Some((module, next))
+        };
+
+        let ldr = pointer_at(self.peb()?.checked_add(layout.peb_ldr)?)?;
+        let head = ldr.checked_add(layout.in_load_order_module_list)?;
+        let mut entry = pointer_at(head)?;
+        let mut modules = vec![];
+        let mut visited = HashSet::new();
+        // The list is circular, so it ends back at its head. A corrupt list
+        // could loop elsewhere, so stop at any entry that was already seen too.
+        while entry != head && visited.insert(entry) {
+            match read_entry(entry) {
+                Some((module, next)) => {
+                    modules.push(module);
+                    entry = next;
+                }
+                None => break,
+            }
+        }
+        Some(modules)
+    }
+
+    /// Whether the Windows process that wrote the minidump had 64-bit pointers.
+    ///
+    /// Return `None` for minidumps from other platforms.
+    fn windows_is_64bit(&'a self) -> Option<bool> {
+        let system_info = self.get_stream::<MinidumpSystemInfo>().ok()?;
+        if system_info.os != Os::Windows {
+            return None;
+        }
+        match system_info.cpu {
+            Cpu::X86 | Cpu::Arm => Some(false),
+            Cpu::X86_64 | Cpu::Arm64 => Some(true),
+            _ => None,
+        }
+    }
+
+    /// The extended processor state saved with `thread`'s context, if any.
+    ///
+    /// This is only present in x86 and amd64 minidumps whose contexts have
+    /// [`has_xstate`][has_xstate] set, and whose misc info stream says where each
+    /// feature is.
+    ///
+    /// [has_xstate]: struct.MinidumpContext.html#method.has_xstate
+    pub fn thread_xstate(&'a self, thread: &MinidumpThread<'_>) -> Option<MinidumpXState<'a>> {
+        if !thread.context.as_ref()?.has_xstate() {
+            return None;
+        }
+        let misc_info = self.get_stream::<MinidumpMiscInfo>().ok()?;
+        let config = misc_info.raw.xstate_data()?.clone();
+        let context = location_slice(self.bytes(), &thread.raw.thread_context).ok()?;
+        Some(MinidumpXState::new(context, config))
+    }
+
+    /// Iterate over the threads in the thread list, with their names and stacks.
+    ///
+    /// This joins the thread list with the thread names stream and the memory
+    /// list. Threads whose stack isn't stored with them, which Windows sometimes
+    /// does, get it from the memory list by address.
+    pub fn threads(&'a self) -> Result<impl Iterator<Item = ThreadView<'a>>, Error> {
+        let thread_list = self.get_stream::<MinidumpThreadList<'a>>()?;
+        let thread_names = self.get_stream::<MinidumpThreadNames>().unwrap_or_default();
+        let memory_list = self.get_stream::<MinidumpMemoryList<'a>>().ok();
+        Ok(thread_list.threads.into_iter().map(move |thread| {
+            let id = thread.raw.thread_id;
+            let teb = thread.teb();
+            let stack_address = thread.raw.stack.start_of_memory_range;
+            let stack_memory = thread.stack.or_else(|| {
+                memory_list
+                    .as_ref()?
+                    .memory_at_address(stack_address)
+                    .cloned()
+            });
+            ThreadView {
+                id,
+                name: thread_names.get_name(id).map(String::from),
+                context: thread.context,
+                stack_memory,
+                teb,
+            }
+        }))
+    }
+
     /// Get a stream of raw data from the minidump.
     ///
-    /// This can be used to get the contents of arbitrary minidump streams.
+    /// This can be used to get the contents of arbitrary minidump streams,
+    /// including vendor-specific ones this crate doesn't know about. The bytes
+    /// are borrowed from the minidump's data, not copied. Use
+    /// [`stream_types`][stream_types] to find out which streams are present.
     /// For streams of known types you almost certainly want to use
     /// [`get_stream`][get_stream] instead.
     ///
@@ -2540,17 +5398,299 @@ where
     /// This is a consequence of how this struct relies on [Deref] to access the data.
     ///
     /// [get_stream]: #get_stream
+    /// [stream_types]: #method.stream_types
     pub fn get_raw_stream<S>(&'a self, stream_type: S) -> Result<&'a [u8], Error>
     where
         S: Into<u32>,
     {
         match self.streams.get(&stream_type.into()) {
             None => Err(Error::StreamNotFound),
-            Some(&(_, ref dir)) => {
-                let bytes = self.data.deref();
-                location_slice(bytes, &dir.location)
+            Some(&(_, ref dir)) => self.directory_slice(dir),
+        }
+    }
+
+    /// Get every stream of a known type from the minidump, in stream directory order.
+    ///
+    /// A minidump normally has at most one stream of each type, and
+    /// [`get_stream`][get_stream] reads the last one if there are more. Some
+    /// producers write several, such as an exception stream for each thread that
+    /// crashed. Each one is read separately, so a corrupt stream doesn't hide the
+    /// others.
+    ///
+    /// [get_stream]: #method.get_stream
+    pub fn get_streams<S>(&'a self) -> Vec<Result<S, Error>>
+    where
+        S: MinidumpStream<'a>,
+    {
+        let all_bytes = self.bytes();
+        self.get_raw_streams(S::STREAM_TYPE)
+            .into_iter()
+            .map(|bytes| S::read(bytes?, all_bytes, self.endian))
+            .collect()
+    }
+
+    /// Get the raw data of every stream of a type, in stream directory order.
+    ///
+    /// This is to [`get_raw_stream`][get_raw_stream] what
+    /// [`get_streams`][get_streams] is to [`get_stream`][get_stream].
+    ///
+    /// [get_raw_stream]: #method.get_raw_stream
+    /// [get_streams]: #method.get_streams
+    /// [get_stream]: #method.get_stream
+    pub fn get_raw_streams<S>(&'a self, stream_type: S) -> Vec<Result<&'a [u8], Error>>
+    where
+        S: Into<u32>,
+    {
+        let stream_type = stream_type.into();
+        if !self.streams.contains_key(&stream_type) {
+            return vec![];
+        }
+        // `streams` only keeps one entry per type, so read the directory again.
+        let bytes = self.bytes();
+        let mut offset = self.header.stream_directory_rva as usize;
+        let mut streams = vec![];
+        for _ in 0..self.header.stream_count {
+            let dir: md::MINIDUMP_DIRECTORY = match bytes.gread_with(&mut offset, self.endian) {
+                Ok(dir) => dir,
+                Err(_) => break,
+            };
+            if dir.stream_type == stream_type {
+                streams.push(self.directory_slice(&dir));
+            }
+        }
+        streams
+    }
+
+    /// The data a stream directory entry points to.
+    fn directory_slice(&'a self, dir: &md::MINIDUMP_DIRECTORY) -> Result<&'a [u8], Error> {
+        let bytes = self.bytes();
+        // Fails if the directory points past the end of the file.
+        location_slice(bytes, &dir.location).map_err(|_| Error::StreamTruncated {
+            expected: (dir.location.rva as usize).saturating_add(dir.location.data_size as usize),
+            actual: bytes.len(),
+        })
+    }
+
+    /// The types of the streams in the minidump, in stream directory order.
+    ///
+    /// This includes types that aren't in [`MINIDUMP_STREAM_TYPE`][types], such
+    /// as vendor-specific streams. If a type appears more than once in the
+    /// directory, only the entry that [`get_raw_stream`][get_raw_stream] uses is
+    /// listed.
+    ///
+    /// [types]: ../minidump_common/format/enum.MINIDUMP_STREAM_TYPE.html
+    /// [get_raw_stream]: #method.get_raw_stream
+    pub fn stream_types(&self) -> Vec<u32> {
+        let mut streams = self
+            .streams
+            .iter()
+            .map(|(&stream_type, &(index, _))| (index, stream_type))
+            .collect::<Vec<_>>();
+        streams.sort_unstable();
+        streams
+            .into_iter()
+            .map(|(_, stream_type)| stream_type)
+            .collect()
+    }
+
+    /// Check the minidump for structural problems, without processing it.
+    ///
+    /// This walks the stream directory and a few of the streams, and reports:
+    ///
+    /// * stream types that appear more than once in the directory
+    /// * streams that extend past the end of the minidump
+    /// * missing thread list and system info streams
+    /// * modules and memory regions that overlap each other
+    /// * thread contexts for a different CPU than the system info's
+    ///
+    /// Problems are returned in that order. An empty list means none were found, not that
+    /// every stream is readable.
+    pub fn validate(&'a self) -> Vec<ValidationIssue> {
+        let mut issues = vec![];
+        let bytes = self.bytes();
+
+        // `streams` only keeps one entry per type, so read the directory again.
+        let mut offset = self.header.stream_directory_rva as usize;
+        let mut counts = BTreeMap::new();
+        let mut out_of_bounds = vec![];
+        for _ in 0..self.header.stream_count {
+            let dir: md::MINIDUMP_DIRECTORY = match bytes.gread_with(&mut offset, self.endian) {
+                Ok(dir) => dir,
+                Err(_) => break,
+            };
+            if dir.stream_type == MINIDUMP_STREAM_TYPE::UnusedStream as u32 {
+                continue;
+            }
+            *counts.entry(dir.stream_type).or_insert(0) += 1;
+            if location_slice(bytes, &dir.location).is_err() {
+                out_of_bounds.push(ValidationIssue::StreamOutOfBounds {
+                    stream_type: dir.stream_type,
+                    rva: dir.location.rva,
+                    data_size: dir.location.data_size,
+                });
+            }
+        }
+        issues.extend(
+            counts
+                .into_iter()
+                .filter(|&(_, count)| count > 1)
+                .map(|(stream_type, count)| ValidationIssue::DuplicateStream {
+                    stream_type,
+                    count,
+                }),
+        );
+        issues.extend(out_of_bounds);
+
+        for &stream_type in &[
+            MINIDUMP_STREAM_TYPE::ThreadListStream,
+            MINIDUMP_STREAM_TYPE::SystemInfoStream,
+        ] {
+            if !self.streams.contains_key(&(stream_type as u32)) {
+                issues.push(ValidationIssue::MissingStream(stream_type));
+            }
+        }
+
+        if let Ok(modules) = self.get_stream::<MinidumpModuleList>() {
+            issues.extend(modules.overlapping_modules().map(|module| {
+                ValidationIssue::OverlappingModule {
+                    name: module.name.clone(),
+                    base: module.base_address(),
+                }
+            }));
+        }
+        if let Ok(memory) = self.get_stream::<MinidumpMemoryList>() {
+            issues.extend(
+                memory
+                    .overlapping_regions()
+                    .map(|region| ValidationIssue::OverlappingMemory(region.base_address)),
+            );
+        }
+
+        let cpu = self
+            .get_stream::<MinidumpSystemInfo>()
+            .ok()
+            .map(|system_info| system_info.cpu);
+        if let (Some(cpu), Ok(threads)) = (cpu, self.get_stream::<MinidumpThreadList>()) {
+            for thread in &threads.threads {
+                let context_cpu = match thread.context {
+                    Some(ref context) => context.cpu(),
+                    None => continue,
+                };
+                if !matches!(cpu, Cpu::Unknown(_)) && context_cpu != cpu {
+                    issues.push(ValidationIssue::ContextCpuMismatch {
+                        thread_id: thread.raw.thread_id,
+                        context_cpu,
+                        cpu,
+                    });
+                }
+            }
+        }
+        issues
+    }
+
+    /// Compute a fingerprint of the crash recorded in this minidump.
+    ///
+    /// Minidumps of the same crash produce the same fingerprint even if they were written at
+    /// different times or by different machines, so this can be used to find duplicate
+    /// reports. The fingerprint is computed from, and only from:
+    ///
+    /// * The OS and CPU from the system info stream.
+    /// * Each loaded module's code file name, without its directory, and its debug
+    ///   identifier, or its code identifier if it has no debug identifier. Modules are
+    ///   sorted first, so the order they were loaded in doesn't matter.
+    /// * The exception code and flags from the exception stream.
+    /// * The crashing instruction pointer, as an offset into the module containing it,
+    ///   or as an absolute address if it isn't in any module.
+    /// * The return addresses of up to 4 of its callers, as offsets into their modules.
+    ///   These are the first words on the crashing thread's stack, from its stack
+    ///   pointer on, that point into a loaded module.
+    ///
+    /// Streams that are missing or fail to parse are skipped. Timestamps, module load
+    /// addresses, thread ids and the rest of memory never contribute. The callers are
+    /// found the way a stack walker scans for them, without unwind info, so stale
+    /// return addresses left on the stack can be among them. Walking the stack properly
+    /// is the job of the `minidump-processor` crate.
+    pub fn fingerprint(&'a self) -> u64 {
+        let mut hasher = Fnv1a::new();
+
+        if let Ok(system_info) = self.get_stream::<MinidumpSystemInfo>() {
+            hasher.write_str(&system_info.os.to_string());
+            hasher.write_str(&system_info.cpu.to_string());
+        }
+
+        let modules = self.get_stream::<MinidumpModuleList>().unwrap_or_default();
+        let mut identities = modules
+            .iter()
+            .map(|module| {
+                let code_file = module.code_file();
+                let name = code_file
+                    .rsplit(|c| c == '/' || c == '\\')
+                    .next()
+                    .unwrap_or("")
+                    .to_owned();
+                let id = module
+                    .debug_identifier()
+                    .unwrap_or_else(|| module.code_identifier());
+                (name, id.into_owned(), module.base_address())
+            })
+            .collect::<Vec<_>>();
+        identities.sort();
+        hasher.write_u64(identities.len() as u64);
+        for (name, id, _) in &identities {
+            hasher.write_str(name);
+            hasher.write_str(id);
+        }
+
+        if let Ok(exception) = self.get_stream::<MinidumpException>() {
+            let record = &exception.raw.exception_record;
+            hasher.write_u64(u64::from(record.exception_code));
+            hasher.write_u64(u64::from(record.exception_flags));
+
+            let threads = self.get_stream::<MinidumpThreadList<'_>>().ok();
+            let thread = threads
+                .as_ref()
+                .and_then(|threads| threads.get_thread(exception.thread_id));
+            let context = exception
+                .context
+                .or_else(|| thread.and_then(|thread| thread.context.clone()));
+            if let Some(context) = context {
+                let mut write_address = |address: u64| match modules.module_at_address(address) {
+                    Some(module) => {
+                        let base = module.base_address();
+                        let index = identities
+                            .iter()
+                            .position(|&(_, _, addr)| addr == base)
+                            .unwrap_or(0);
+                        hasher.write_u64(index as u64);
+                        hasher.write_u64(address - base);
+                    }
+                    None => {
+                        hasher.write_u64(u64::max_value());
+                        hasher.write_u64(address);
+                    }
+                };
+                write_address(context.get_instruction_pointer());
+
+                if let Some(stack) = thread.and_then(|thread| thread.stack.as_ref()) {
+                    let width = context.pointer_width();
+                    let sp = context.get_stack_pointer();
+                    (0..FINGERPRINT_SCAN_WORDS)
+                        .filter_map(|i| {
+                            let address = sp.checked_add(i * width)?;
+                            if width == 4 {
+                                stack.get_memory_at_address::<u32>(address).map(u64::from)
+                            } else {
+                                stack.get_memory_at_address::<u64>(address)
+                            }
+                        })
+                        .filter(|&word| modules.module_at_address(word).is_some())
+                        .take(FINGERPRINT_FRAMES - 1)
+                        .for_each(&mut write_address);
+                }
             }
         }
+
+        hasher.0
     }
 
     /// Write a verbose description of the `Minidump` to `f`.
@@ -2623,9 +5763,10 @@ MDRawDirectory
 mod test {
     use super::*;
     use crate::synth_minidump::{
-        self, AnnotationValue, CrashpadInfo, DumpString, Memory, MiscFieldsBuildString,
-        MiscFieldsPowerInfo, MiscFieldsProcessTimes, MiscFieldsTimeZone, MiscInfo5Fields,
-        MiscStream, Module as SynthModule, ModuleCrashpadInfo, SimpleStream, SynthMinidump, Thread,
+        self, AnnotationValue, CrashpadInfo, DumpSection, DumpString, Exception, Memory,
+        MemoryInfoList, MiscFieldsBuildString, MiscFieldsPowerInfo, MiscFieldsProcessTimes,
+        MiscFieldsTimeZone, MiscInfo5Fields, MiscStream, Module as SynthModule, ModuleCrashpadInfo,
+        SimpleStream, SynthMinidump, SystemInfo, Thread, ThreadName,
         UnloadedModule as SynthUnloadedModule, STOCK_VERSION_INFO,
     };
     use md::GUID;
@@ -2649,6 +5790,10 @@ mod test {
             dump.get_raw_stream(STREAM_TYPE).unwrap(),
             &[0x88, 0x77, 0x66, 0x55]
         );
+        // The stream is borrowed from the minidump's data.
+        let data = dump.data.as_ptr_range();
+        assert!(data.contains(&dump.get_raw_stream(STREAM_TYPE).unwrap().as_ptr()));
+        assert_eq!(dump.stream_types(), vec![STREAM_TYPE]);
 
         assert_eq!(
             dump.get_raw_stream(0xaabbccddu32),
@@ -2704,6 +5849,7 @@ mod test {
             .add(cv_record);
         let dump = read_synth_dump(dump).unwrap();
         let module_list = dump.get_stream::<MinidumpModuleList>().unwrap();
+        assert_eq!(module_list.validate(), Ok(()));
         let modules = module_list.iter().collect::<Vec<_>>();
         assert_eq!(modules.len(), 1);
         assert_eq!(modules[0].base_address(), 0xa90206ca83eb2852);
@@ -2718,6 +5864,68 @@ mod test {
         );
     }
 
+    #[test]
+    fn test_module_list_limits() {
+        let name = DumpString::new("module", Endian::Little);
+        let cv_record = Section::with_endian(Endian::Little)
+            .D32(md::CvSignature::Pdb70 as u32)
+            .append_repeated(0, 0x100);
+        let mut dump = SynthMinidump::with_endian(Endian::Little);
+        for i in 0..3 {
+            let module = SynthModule::new(
+                Endian::Little,
+                0x10000 * (i + 1),
+                0x1000,
+                &name,
+                0xb1054d2a,
+                0x34571371,
+                None,
+            )
+            .cv_record(&cv_record);
+            dump = dump.add_module(module);
+        }
+        let dump = read_synth_dump(dump.add(name).add(cv_record)).unwrap();
+
+        // The defaults don't get in the way.
+        let module_list = dump
+            .get_module_list_with_limits(&ModuleListLimits::default())
+            .unwrap();
+        assert_eq!(module_list.iter().count(), 3);
+
+        assert_eq!(module_list.omitted_count(), 0);
+        assert_eq!(module_list.validate(), Ok(()));
+
+        // Each module cites 0x104 bytes of CodeView data on top of the stream.
+        let stream_size = 4 + 3 * md::MINIDUMP_MODULE::size_with(&LE);
+        let too_large = Err(Error::ModuleListTooLarge {
+            modules: 3,
+            bytes: stream_size + 3 * 0x104,
+        });
+
+        // The modules that fit are kept.
+        let limits = ModuleListLimits {
+            max_modules: 2,
+            ..ModuleListLimits::default()
+        };
+        let module_list = dump.get_module_list_with_limits(&limits).unwrap();
+        let bases = module_list
+            .iter()
+            .map(|module| module.base_address())
+            .collect::<Vec<_>>();
+        assert_eq!(bases, vec![0x10000, 0x20000]);
+        assert_eq!(module_list.omitted_count(), 1);
+        assert_eq!(module_list.validate(), too_large);
+
+        let limits = ModuleListLimits {
+            max_bytes: stream_size + 2 * 0x104 - 1,
+            ..ModuleListLimits::default()
+        };
+        let module_list = dump.get_module_list_with_limits(&limits).unwrap();
+        assert_eq!(module_list.iter().count(), 1);
+        assert_eq!(module_list.omitted_count(), 2);
+        assert_eq!(module_list.validate(), too_large);
+    }
+
     #[test]
     fn test_unloaded_module_list() {
         let name = DumpString::new("single module", Endian::Little);
@@ -2744,13 +5952,83 @@ mod test {
     }
 
     #[test]
-    fn test_module_list_overlap() {
-        let name1 = DumpString::new("module 1", Endian::Little);
-        let name2 = DumpString::new("module 2", Endian::Little);
-        let name3 = DumpString::new("module 3", Endian::Little);
-        let name4 = DumpString::new("module 4", Endian::Little);
-        let name5 = DumpString::new("module 5", Endian::Little);
-        let cv_record = Section::with_endian(Endian::Little)
+    fn test_main_module_image_base() {
+        let lib_name = DumpString::new("/usr/lib/libc.so.6", Endian::Little);
+        let main_name = DumpString::new("/usr/bin/app", Endian::Little);
+        // The library is at the lowest address, but isn't the first module.
+        let lib = SynthModule::new(Endian::Little, 0x10000, 0x4000, &lib_name, 0, 0, None);
+        let main = SynthModule::new(Endian::Little, 0x400000, 0x8000, &main_name, 0, 0, None);
+        let system_info = SystemInfo::new(
+            Endian::Little,
+            md::ProcessorArchitecture::PROCESSOR_ARCHITECTURE_AMD64 as u16,
+            md::PlatformId::Linux as u32,
+        );
+        let auxv = SimpleStream {
+            stream_type: MINIDUMP_STREAM_TYPE::LinuxAuxv as u32,
+            section: Section::with_endian(Endian::Little)
+                .D64(6) // AT_PAGESZ
+                .D64(0x1000)
+                .D64(3) // AT_PHDR
+                .D64(0x400040)
+                .D64(0) // AT_NULL
+                .D64(0),
+        };
+        let dump = SynthMinidump::with_endian(Endian::Little)
+            .add_module(main)
+            .add_module(lib)
+            .add_stream(system_info)
+            .add_stream(auxv)
+            .add(lib_name)
+            .add(main_name);
+        let dump = read_synth_dump(dump).unwrap();
+        let mut module_list = dump.get_stream::<MinidumpModuleList>().unwrap();
+
+        // Without an image base, the lowest module is assumed to be the main one.
+        assert_eq!(module_list.main_module_index(), Some(1));
+
+        let image_base = dump.process_image_base();
+        assert_eq!(image_base, Some(0x400000));
+        module_list.set_image_base(image_base.unwrap());
+        assert_eq!(module_list.main_module_index(), Some(0));
+        assert_eq!(
+            module_list.main_module().unwrap().code_file(),
+            "/usr/bin/app"
+        );
+
+        // An image base that matches no module falls back to the lowest module.
+        module_list.set_image_base(0x500000);
+        assert_eq!(module_list.main_module_index(), Some(1));
+    }
+
+    #[test]
+    fn test_unloaded_modules_at_address() {
+        let modules = MinidumpUnloadedModuleList::from_modules(vec![
+            MinidumpUnloadedModule::new(0x1000, 0x1000, "a"),
+            MinidumpUnloadedModule::new(0x1800, 0x1000, "b"),
+            MinidumpUnloadedModule::new(0x3000, 0x1000, "c"),
+            MinidumpUnloadedModule::new(0x1900, 0, "empty"),
+        ]);
+        let names_at = |address| {
+            modules
+                .modules_at_address(address)
+                .map(|module| module.name.as_str())
+                .collect::<Vec<_>>()
+        };
+        assert_eq!(names_at(0x1000), vec!["a"]);
+        assert_eq!(names_at(0x1900), vec!["a", "b"]);
+        assert_eq!(names_at(0x1fff), vec!["a", "b"]);
+        assert_eq!(names_at(0x2000), vec!["b"]);
+        assert!(names_at(0x2800).is_empty());
+    }
+
+    #[test]
+    fn test_module_list_overlap() {
+        let name1 = DumpString::new("module 1", Endian::Little);
+        let name2 = DumpString::new("module 2", Endian::Little);
+        let name3 = DumpString::new("module 3", Endian::Little);
+        let name4 = DumpString::new("module 4", Endian::Little);
+        let name5 = DumpString::new("module 5", Endian::Little);
+        let cv_record = Section::with_endian(Endian::Little)
             .D32(md::CvSignature::Pdb70 as u32) // signature
             // signature, a GUID
             .D32(0xabcd1234)
@@ -2847,6 +6125,11 @@ mod test {
 
         // module_at_address should discard overlapping modules.
         assert_eq!(module_list.by_addr().count(), 2);
+        let overlapping = module_list
+            .overlapping_modules()
+            .map(|module| module.code_file().into_owned())
+            .collect::<Vec<_>>();
+        assert_eq!(overlapping, vec!["module 2", "module 3", "module 4"]);
         assert_eq!(
             module_list
                 .module_at_address(0x100001000)
@@ -2880,6 +6163,68 @@ mod test {
         assert_eq!(&regions[0].bytes, &CONTENTS);
     }
 
+    #[test]
+    fn test_memory_window() {
+        let memory1 = Memory::with_section(
+            Section::with_endian(Endian::Little).append_repeated(1, 0x100),
+            0x1000,
+        );
+        // memory2 is right after memory1.
+        let memory2 = Memory::with_section(
+            Section::with_endian(Endian::Little).append_repeated(2, 0x100),
+            0x1100,
+        );
+        // memory3 is after a gap.
+        let memory3 = Memory::with_section(
+            Section::with_endian(Endian::Little).append_repeated(3, 0x100),
+            0x1300,
+        );
+        let dump = SynthMinidump::with_endian(Endian::Little)
+            .add_memory(memory3)
+            .add_memory(memory1)
+            .add_memory(memory2);
+        let dump = read_synth_dump(dump).unwrap();
+        let memory_list = dump.get_stream::<MinidumpMemoryList<'_>>().unwrap();
+
+        // Within a single region.
+        let window = memory_list.memory_window(0x1010, 0x10).unwrap();
+        assert_eq!(window.range, 0x1010..0x1020);
+        assert_eq!(window.bytes, vec![1; 0x10]);
+
+        // Straddling two adjacent regions.
+        let window = memory_list.memory_window(0x10f8, 0x10).unwrap();
+        assert_eq!(window.range, 0x10f8..0x1108);
+        assert_eq!(&window.bytes[..8], &[1; 8]);
+        assert_eq!(&window.bytes[8..], &[2; 8]);
+
+        // Starting before any dumped memory.
+        let window = memory_list.memory_window(0xff0, 0x20).unwrap();
+        assert_eq!(window.range, 0x1000..0x1010);
+        assert_eq!(window.bytes, vec![1; 0x10]);
+
+        // Running into a gap.
+        let window = memory_list.memory_window(0x11f0, 0x200).unwrap();
+        assert_eq!(window.range, 0x11f0..0x1200);
+        assert_eq!(window.bytes, vec![2; 0x10]);
+
+        // Entirely in a gap, or past the end.
+        assert_eq!(memory_list.memory_window(0x1200, 0x100), None);
+        assert_eq!(memory_list.memory_window(0x2000, 0x10), None);
+        assert_eq!(memory_list.memory_window(u64::MAX, 0x10), None);
+
+        let window = MemoryWindow {
+            range: 0x1000..0x1012,
+            bytes: b"hello, world!\0\x01\x02\x03\x04".to_vec(),
+        };
+        let mut out = vec![];
+        window.print(&mut out).unwrap();
+        assert_eq!(
+            String::from_utf8(out).unwrap(),
+            "0x0000000000001000  68 65 6c 6c 6f 2c 20 77 6f 72 6c 64 21 00 01 02  |hello, world!...|\n\
+             0x0000000000001010  03 04                                            |..|\n"
+        );
+    }
+
     #[test]
     fn test_memory_list_lifetimes() {
         // A memory list should not own any of the minidump data.
@@ -2946,6 +6291,14 @@ mod test {
 
         // memory_at_address should discard overlapping regions.
         assert_eq!(memory_list.by_addr().count(), 2);
+        let overlapping = memory_list
+            .overlapping_regions()
+            .map(|region| (region.base_address, region.size))
+            .collect::<Vec<_>>();
+        assert_eq!(
+            overlapping,
+            vec![(0x1000, 0x1000), (0x1001, 0x1000), (0x1001, 0x100)]
+        );
         let m1 = memory_list.memory_at_address(0x1a00).unwrap();
         assert_eq!(m1.base_address, 0x1000);
         assert_eq!(m1.size, 0x1000);
@@ -2986,6 +6339,151 @@ mod test {
         );
     }
 
+    #[test]
+    fn test_system_info_x86_cpu_info() {
+        for &endian in &[Endian::Little, Endian::Big] {
+            // "GenuineIntel", as `cpuid` returns it in ebx, edx, ecx.
+            let cpu = Section::with_endian(endian)
+                .D32(0x756e6547)
+                .D32(0x49656e69)
+                .D32(0x6c65746e)
+                .D32(0x000306a9) // version_information
+                .D32(0x07810111) // feature_information
+                .D32(0); // amd_extended_cpu_features
+            let system_info = SystemInfo::with_cpu(
+                endian,
+                md::ProcessorArchitecture::PROCESSOR_ARCHITECTURE_INTEL as u16,
+                md::PlatformId::VER_PLATFORM_WIN32_NT as u32,
+                6,
+                0x3a09,
+                cpu,
+            );
+            let dump = SynthMinidump::with_endian(endian).add_stream(system_info);
+            let dump = read_synth_dump(dump).unwrap();
+            let system_info = dump.get_stream::<MinidumpSystemInfo>().unwrap();
+            assert_eq!(system_info.cpu, Cpu::X86);
+            assert_eq!(
+                system_info.cpu_info().unwrap(),
+                "GenuineIntel family 6 model 58 stepping 9"
+            );
+            assert_eq!(
+                system_info.cpu_features(),
+                &["fpu", "tsc", "cx8", "mmx", "fxsr", "sse", "sse2"]
+            );
+        }
+
+        // There's no `cpuid` information for amd64, so there's no vendor or features.
+        let system_info = SystemInfo::with_cpu(
+            Endian::Little,
+            md::ProcessorArchitecture::PROCESSOR_ARCHITECTURE_AMD64 as u16,
+            md::PlatformId::VER_PLATFORM_WIN32_NT as u32,
+            6,
+            0x9e0a,
+            Section::with_endian(Endian::Little).D64(0x10427f4c).D64(1),
+        );
+        let dump = SynthMinidump::with_endian(Endian::Little).add_stream(system_info);
+        let dump = read_synth_dump(dump).unwrap();
+        let system_info = dump.get_stream::<MinidumpSystemInfo>().unwrap();
+        assert_eq!(
+            system_info.cpu_info().unwrap(),
+            "family 6 model 158 stepping 10"
+        );
+        assert!(system_info.cpu_features().is_empty());
+    }
+
+    #[test]
+    fn test_system_info_arm_cpu_info() {
+        for &endian in &[Endian::Little, Endian::Big] {
+            let hwcaps = md::ArmElfHwCaps::HWCAP_HALF
+                | md::ArmElfHwCaps::HWCAP_THUMB
+                | md::ArmElfHwCaps::HWCAP_NEON
+                | md::ArmElfHwCaps::HWCAP_VFPv3;
+            let cpu = Section::with_endian(endian)
+                .D32(0x410fc090) // cpuid
+                .D32(hwcaps.bits());
+            let system_info = SystemInfo::with_cpu(
+                endian,
+                md::ProcessorArchitecture::PROCESSOR_ARCHITECTURE_ARM as u16,
+                md::PlatformId::Android as u32,
+                7,
+                0,
+                cpu,
+            );
+            let dump = SynthMinidump::with_endian(endian).add_stream(system_info);
+            let dump = read_synth_dump(dump).unwrap();
+            let system_info = dump.get_stream::<MinidumpSystemInfo>().unwrap();
+            assert_eq!(system_info.cpu, Cpu::Arm);
+            assert_eq!(
+                system_info.cpu_info().unwrap(),
+                "ARMv7 ARM Cortex-A9 features: half,thumb,neon,vfpv3"
+            );
+            assert_eq!(
+                system_info.cpu_features(),
+                &["half", "thumb", "neon", "vfpv3"]
+            );
+        }
+
+        // Unknown vendors and parts are reported by their id.
+        let cpu = Section::with_endian(Endian::Little).D32(0x7a0f1230).D32(0);
+        let system_info = SystemInfo::with_cpu(
+            Endian::Little,
+            md::ProcessorArchitecture::PROCESSOR_ARCHITECTURE_ARM as u16,
+            md::PlatformId::Linux as u32,
+            6,
+            0,
+            cpu,
+        );
+        let dump = SynthMinidump::with_endian(Endian::Little).add_stream(system_info);
+        let dump = read_synth_dump(dump).unwrap();
+        let system_info = dump.get_stream::<MinidumpSystemInfo>().unwrap();
+        assert_eq!(
+            system_info.cpu_info().unwrap(),
+            "ARMv6 vendor(0x7a) part(0x7a001230)"
+        );
+    }
+
+    #[test]
+    fn test_system_info_arm64_cpu_info() {
+        let hwcaps = md::Arm64ElfHwCaps::HWCAP_FP
+            | md::Arm64ElfHwCaps::HWCAP_ASIMD
+            | md::Arm64ElfHwCaps::HWCAP_AES
+            | md::Arm64ElfHwCaps::HWCAP_CRC32;
+        let cpu = Section::with_endian(Endian::Little)
+            .D32(0x410fd034) // cpuid
+            .D32(hwcaps.bits());
+        let system_info = SystemInfo::with_cpu(
+            Endian::Little,
+            md::ProcessorArchitecture::PROCESSOR_ARCHITECTURE_ARM64 as u16,
+            md::PlatformId::Android as u32,
+            0,
+            0,
+            cpu,
+        );
+        let dump = SynthMinidump::with_endian(Endian::Little).add_stream(system_info);
+        let dump = read_synth_dump(dump).unwrap();
+        let system_info = dump.get_stream::<MinidumpSystemInfo>().unwrap();
+        assert_eq!(system_info.cpu, Cpu::Arm64);
+        assert_eq!(
+            system_info.cpu_info().unwrap(),
+            "ARMv8 ARM Cortex-A53 features: fp,asimd,aes,crc32"
+        );
+        assert_eq!(system_info.cpu_features(), &["fp", "asimd", "aes", "crc32"]);
+
+        // Windows doesn't use the Breakpad extension for 64-bit ARM.
+        let system_info = SystemInfo::with_cpu(
+            Endian::Little,
+            md::ProcessorArchitecture::PROCESSOR_ARCHITECTURE_ARM64 as u16,
+            md::PlatformId::VER_PLATFORM_WIN32_NT as u32,
+            8,
+            0,
+            Section::with_endian(Endian::Little).D64(0x1234).D64(0),
+        );
+        let dump = SynthMinidump::with_endian(Endian::Little).add_stream(system_info);
+        let dump = read_synth_dump(dump).unwrap();
+        let system_info = dump.get_stream::<MinidumpSystemInfo>().unwrap();
+        assert_eq!(system_info.cpu_info(), None);
+    }
+
     #[test]
     fn test_misc_info_large() {
         const PID: u32 = 0x1234abcd;
@@ -3017,6 +6515,105 @@ mod test {
         );
     }
 
+    #[test]
+    fn test_misc_info_versions() {
+        const PROCESS_TIMES: MiscFieldsProcessTimes = MiscFieldsProcessTimes {
+            process_create_time: 0x5f000000,
+            process_user_time: 12,
+            process_kernel_time: 34,
+        };
+        let build_string = ascii_string_to_utf16("15.0.19041.1");
+
+        for version in 1..=5 {
+            let mut misc = MiscStream::new(Endian::Little);
+            misc.process_times = Some(PROCESS_TIMES);
+            if version >= 2 {
+                misc.power_info = Some(MiscFieldsPowerInfo::default());
+            }
+            if version >= 3 {
+                misc.process_integrity_level = Some(0x2000);
+                misc.process_execute_flags = Some(0x9);
+                misc.protected_process = Some(0);
+            }
+            if version >= 4 {
+                let mut build_strings = MiscFieldsBuildString::default();
+                build_strings.build_string[..build_string.len()].copy_from_slice(&build_string);
+                misc.build_strings = Some(build_strings);
+            }
+            if version >= 5 {
+                misc.misc_5 = Some(MiscInfo5Fields::default());
+            }
+            let dump = SynthMinidump::with_endian(Endian::Little).add_stream(misc);
+            let dump = read_synth_dump(dump).unwrap();
+            let misc = dump.get_stream::<MinidumpMiscInfo>().unwrap();
+
+            let (expected_size, parsed_version) = match misc.raw {
+                RawMiscInfo::MiscInfo(_) => (md::MINIDUMP_MISC_INFO::size_with(&LE), 1),
+                RawMiscInfo::MiscInfo2(_) => (md::MINIDUMP_MISC_INFO_2::size_with(&LE), 2),
+                RawMiscInfo::MiscInfo3(_) => (md::MINIDUMP_MISC_INFO_3::size_with(&LE), 3),
+                RawMiscInfo::MiscInfo4(_) => (md::MINIDUMP_MISC_INFO_4::size_with(&LE), 4),
+                RawMiscInfo::MiscInfo5(_) => (md::MINIDUMP_MISC_INFO_5::size_with(&LE), 5),
+            };
+            assert_eq!(parsed_version, version);
+            assert_eq!(*misc.raw.size_of_info().unwrap() as usize, expected_size);
+            assert_eq!(
+                misc.process_create_time().unwrap(),
+                Utc.timestamp(PROCESS_TIMES.process_create_time as i64, 0)
+            );
+            assert_eq!(misc.process_user_time(), Some(Duration::from_secs(12)));
+            assert_eq!(misc.process_kernel_time(), Some(Duration::from_secs(34)));
+            if version >= 3 {
+                assert_eq!(
+                    misc.process_execute_flags(),
+                    Some(
+                        md::ProcessExecuteFlags::MEM_EXECUTE_OPTION_DISABLE
+                            | md::ProcessExecuteFlags::MEM_EXECUTE_OPTION_PERMANENT
+                    )
+                );
+                assert_eq!(misc.protected_process(), Some(false));
+            } else {
+                assert_eq!(misc.process_execute_flags(), None);
+                assert_eq!(misc.protected_process(), None);
+            }
+            if version >= 4 {
+                assert_eq!(misc.build_string().unwrap(), "15.0.19041.1");
+            } else {
+                assert_eq!(misc.build_string(), None);
+            }
+        }
+    }
+
+    #[test]
+    fn test_misc_info_padded_stream() {
+        // A MINIDUMP_MISC_INFO in a stream that has been padded out past the size of a
+        // MINIDUMP_MISC_INFO_2. `size_of_info` should determine the version, not the stream size.
+        let flags = md::MiscInfoFlags::MINIDUMP_MISC1_PROCESS_TIMES
+            | md::MiscInfoFlags::MINIDUMP_MISC1_PROCESSOR_POWER_INFO;
+        let section = Section::with_endian(Endian::Little)
+            .D32(md::MINIDUMP_MISC_INFO::size_with(&LE) as u32) // size_of_info
+            .D32(flags.bits()) // flags1
+            .D32(0) // process_id
+            .D32(0x5f000000) // process_create_time
+            .D32(1) // process_user_time
+            .D32(2) // process_kernel_time
+            .append_repeated(0xff, md::MINIDUMP_MISC_INFO_2::size_with(&LE));
+        let dump = SynthMinidump::with_endian(Endian::Little).add_stream(SimpleStream {
+            stream_type: MINIDUMP_STREAM_TYPE::MiscInfoStream as u32,
+            section,
+        });
+        let dump = read_synth_dump(dump).unwrap();
+        let misc = dump.get_stream::<MinidumpMiscInfo>().unwrap();
+        match misc.raw {
+            RawMiscInfo::MiscInfo(_) => {}
+            ref other => panic!("unexpected misc info version: {:?}", other),
+        }
+        assert_eq!(
+            misc.process_create_time().unwrap(),
+            Utc.timestamp(0x5f000000, 0)
+        );
+        assert_eq!(misc.raw.processor_max_mhz(), None);
+    }
+
     fn ascii_string_to_utf16(input: &str) -> Vec<u16> {
         input.chars().map(|c| c as u16).collect()
     }
@@ -3221,6 +6818,198 @@ mod test {
         assert_eq!(xstate_iter.next(), None);
     }
 
+    #[test]
+    fn test_module_breakpad_ids() {
+        let guid = GUID {
+            data1: 0x1234abcd,
+            data2: 0x5678,
+            data3: 0x9abc,
+            data4: [0xde, 0xf0, 0x01, 0x23, 0x45, 0x67, 0x89, 0xab],
+        };
+
+        // A PE module with a PDB 7.0 record, and an age that needs two hex digits.
+        let mut pe = MinidumpModule::new(0x10000000, 0x2a000, "c:\\foo.dll");
+        pe.raw.time_date_stamp = 0x5e1f00d;
+        pe.codeview_info = Some(CodeView::Pdb70(md::CV_INFO_PDB70 {
+            cv_signature: md::CvSignature::Pdb70 as u32,
+            signature: guid,
+            age: 0x1a,
+            pdb_file_name: b"foo.pdb\0".to_vec(),
+        }));
+        assert_eq!(
+            pe.breakpad_debug_id().unwrap(),
+            "1234ABCD56789ABCDEF00123456789AB1A"
+        );
+        assert_eq!(pe.breakpad_code_id(Os::Windows).unwrap(), "05E1F00D2a000");
+
+        // An old PE module with a PDB 2.0 record.
+        let mut pe = MinidumpModule::new(0x10000000, 0x2a000, "c:\\bar.dll");
+        pe.codeview_info = Some(CodeView::Pdb20(md::CV_INFO_PDB20 {
+            cv_signature: md::CvSignature::Pdb20 as u32,
+            cv_offset: 0,
+            signature: 0xabcdef01,
+            age: 0xb,
+            pdb_file_name: b"bar.pdb\0".to_vec(),
+        }));
+        assert_eq!(pe.breakpad_debug_id().unwrap(), "ABCDEF01B");
+
+        // An ELF module with a build id.
+        let mut elf = MinidumpModule::new(0x10000000, 0x2a000, "/lib/libfoo.so");
+        elf.codeview_info = Some(CodeView::Elf(md::CV_INFO_ELF {
+            cv_signature: md::CvSignature::Elf as u32,
+            build_id: vec![
+                0x00, 0x01, 0x02, 0x03, 0x04, 0x05, 0x06, 0x07, 0x08, 0x09, 0x0a, 0x0b, 0x0c, 0x0d,
+                0x0e, 0x0f, 0xaa, 0xbb, 0xcc, 0xdd,
+            ],
+        }));
+        assert_eq!(
+            elf.breakpad_debug_id().unwrap(),
+            "030201000504070608090A0B0C0D0E0F0"
+        );
+        assert_eq!(
+            elf.breakpad_code_id(Os::Linux).unwrap(),
+            "000102030405060708090a0b0c0d0e0faabbccdd"
+        );
+
+        // A Mach-O module, whose UUID is stored as a PDB 7.0 record.
+        let mut macho = MinidumpModule::new(0x10000000, 0x2a000, "/usr/lib/libfoo.dylib");
+        macho.codeview_info = Some(CodeView::Pdb70(md::CV_INFO_PDB70 {
+            cv_signature: md::CvSignature::Pdb70 as u32,
+            signature: guid,
+            age: 0,
+            pdb_file_name: b"libfoo.dylib\0".to_vec(),
+        }));
+        assert_eq!(
+            macho.breakpad_debug_id().unwrap(),
+            "1234ABCD56789ABCDEF00123456789AB0"
+        );
+        assert_eq!(
+            macho.breakpad_code_id(Os::MacOs).unwrap(),
+            "1234ABCD56789ABCDEF00123456789AB"
+        );
+
+        // Linux modules with no build id don't have a code id.
+        let linux = MinidumpModule::new(0x10000000, 0x2a000, "/lib/libbar.so");
+        assert_eq!(linux.breakpad_debug_id(), None);
+        assert_eq!(linux.breakpad_code_id(Os::Linux), None);
+    }
+
+    #[test]
+    fn test_module_recover_identifiers() {
+        fn put(bytes: &mut [u8], offset: usize, data: &[u8]) {
+            bytes[offset..offset + data.len()].copy_from_slice(data);
+        }
+        fn region(base_address: u64, bytes: &[u8]) -> MinidumpMemory<'_> {
+            MinidumpMemory {
+                desc: Default::default(),
+                base_address,
+                size: bytes.len() as u64,
+                bytes,
+            }
+        }
+
+        // A PE32+ image with a PDB 7.0 CodeView record in its debug directory.
+        let mut pe = vec![0; 0x400];
+        put(&mut pe, 0, b"MZ");
+        put(&mut pe, 0x3c, &0x80u32.to_le_bytes());
+        put(&mut pe, 0x80, b"PE\0\0");
+        put(&mut pe, 0x88, &0x5e1f00du32.to_le_bytes()); // time_date_stamp
+        put(&mut pe, 0x98, &0x20bu16.to_le_bytes()); // optional header magic
+        put(&mut pe, 0x104, &16u32.to_le_bytes()); // number_of_rva_and_sizes
+        put(&mut pe, 0x138, &0x200u32.to_le_bytes()); // debug directory rva
+        put(&mut pe, 0x13c, &28u32.to_le_bytes()); // debug directory size
+        let mut codeview = b"RSDS".to_vec();
+        codeview.extend_from_slice(&[
+            0xcd, 0xab, 0x34, 0x12, 0x78, 0x56, 0xbc, 0x9a, 0xde, 0xf0, 0x01, 0x23, 0x45, 0x67,
+            0x89, 0xab,
+        ]);
+        codeview.extend_from_slice(&2u32.to_le_bytes()); // age
+        codeview.extend_from_slice(b"foo.pdb\0");
+        put(&mut pe, 0x20c, &2u32.to_le_bytes()); // IMAGE_DEBUG_TYPE_CODEVIEW
+        put(&mut pe, 0x210, &(codeview.len() as u32).to_le_bytes());
+        put(&mut pe, 0x214, &0x300u32.to_le_bytes());
+        put(&mut pe, 0x300, &codeview);
+
+        // A 64-bit ELF image with a GNU build id note.
+        let mut elf = vec![0; 0x400];
+        put(&mut elf, 0, b"\x7fELF\x02\x01");
+        put(&mut elf, 0x20, &0x40u64.to_le_bytes()); // e_phoff
+        put(&mut elf, 0x36, &56u16.to_le_bytes()); // e_phentsize
+        put(&mut elf, 0x38, &2u16.to_le_bytes()); // e_phnum
+        put(&mut elf, 0x40, &1u32.to_le_bytes()); // PT_LOAD at 0
+        put(&mut elf, 0x60, &0x1000u64.to_le_bytes());
+        put(&mut elf, 0x78, &4u32.to_le_bytes()); // PT_NOTE
+        put(&mut elf, 0x88, &0x200u64.to_le_bytes());
+        put(&mut elf, 0x98, &36u64.to_le_bytes());
+        put(&mut elf, 0x200, &4u32.to_le_bytes()); // name size
+        put(&mut elf, 0x204, &20u32.to_le_bytes()); // desc size
+        put(&mut elf, 0x208, &3u32.to_le_bytes()); // NT_GNU_BUILD_ID
+        put(&mut elf, 0x20c, b"GNU\0");
+        put(&mut elf, 0x210, &(0..20).collect::<Vec<u8>>());
+
+        // A 64-bit Mach-O image with a segment, then its UUID.
+        let mut macho = vec![0; 0x400];
+        put(&mut macho, 0, &0xfeed_facfu32.to_le_bytes());
+        put(&mut macho, 16, &2u32.to_le_bytes()); // ncmds
+        put(&mut macho, 32, &0x19u32.to_le_bytes()); // LC_SEGMENT_64
+        put(&mut macho, 36, &72u32.to_le_bytes());
+        put(&mut macho, 104, &0x1bu32.to_le_bytes()); // LC_UUID
+        put(&mut macho, 108, &24u32.to_le_bytes());
+        put(
+            &mut macho,
+            112,
+            &[
+                0x12, 0x34, 0xab, 0xcd, 0x56, 0x78, 0x9a, 0xbc, 0xde, 0xf0, 0x01, 0x23, 0x45, 0x67,
+                0x89, 0xab,
+            ],
+        );
+
+        let memory = MinidumpMemoryList::from_regions(vec![
+            region(0x10000000, &pe),
+            region(0x20000000, &elf),
+            region(0x30000000, &macho),
+        ]);
+        let mut modules = MinidumpModuleList::from_modules(vec![
+            MinidumpModule::new(0x10000000, 0x2a000, "c:\\foo.dll"),
+            MinidumpModule::new(0x20000000, 0x1000, "/lib/libfoo.so"),
+            MinidumpModule::new(0x30000000, 0x1000, "/usr/lib/libfoo.dylib"),
+            // This module's header wasn't captured.
+            MinidumpModule::new(0x40000000, 0x1000, "c:\\bar.dll"),
+        ]);
+        assert_eq!(modules.recover_identifiers(&memory), 3);
+
+        let pe = modules.module_at_address(0x10000000).unwrap();
+        assert_eq!(pe.debug_file().unwrap(), "foo.pdb");
+        assert_eq!(
+            pe.breakpad_debug_id().unwrap(),
+            "1234ABCD56789ABCDEF00123456789AB2"
+        );
+        assert_eq!(pe.code_identifier(), "05E1F00D2a000");
+
+        let elf = modules.module_at_address(0x20000000).unwrap();
+        assert_eq!(
+            elf.breakpad_debug_id().unwrap(),
+            "030201000504070608090A0B0C0D0E0F0"
+        );
+        assert_eq!(
+            elf.breakpad_code_id(Os::Linux).unwrap(),
+            "000102030405060708090a0b0c0d0e0f10111213"
+        );
+
+        let macho = modules.module_at_address(0x30000000).unwrap();
+        assert_eq!(macho.debug_file().unwrap(), "libfoo.dylib");
+        assert_eq!(
+            macho.breakpad_debug_id().unwrap(),
+            "1234ABCD56789ABCDEF00123456789AB0"
+        );
+
+        let missing = modules.module_at_address(0x40000000).unwrap();
+        assert_eq!(missing.debug_identifier(), None);
+
+        // Identifiers that are already known are left alone.
+        assert_eq!(modules.recover_identifiers(&memory), 0);
+    }
+
     #[test]
     fn test_elf_build_id() {
         // Add a module with a long ELF build id
@@ -3326,6 +7115,278 @@ mod test {
         assert_eq!(stack.size, 0x1000);
     }
 
+    #[test]
+    fn test_thread_teb_and_peb() {
+        let context =
+            synth_minidump::amd64_context(Endian::Little, 0x1234abcd1234abcd, 0x1000000010000000);
+        let stack = Memory::with_section(
+            Section::with_endian(Endian::Little).append_repeated(0, 0x1000),
+            0x1000000010000000,
+        );
+        let teb = Memory::with_section(
+            Section::with_endian(Endian::Little)
+                .append_repeated(0, 0x60)
+                .D64(0x7ff612340000) // ProcessEnvironmentBlock
+                .append_repeated(0, 0x18),
+            0x7ff600001000,
+        );
+        let thread = Thread::with_teb(Endian::Little, 0x1234, 0x7ff600001000, &stack, &context);
+        let system_info = SystemInfo::new(
+            Endian::Little,
+            md::ProcessorArchitecture::PROCESSOR_ARCHITECTURE_AMD64 as u16,
+            md::PlatformId::VER_PLATFORM_WIN32_NT as u32,
+        );
+        let dump = SynthMinidump::with_endian(Endian::Little)
+            .add_thread(thread)
+            .add(context)
+            .add_memory(stack)
+            .add_memory(teb)
+            .add_stream(system_info);
+        let dump = read_synth_dump(dump).unwrap();
+        let thread_list = dump.get_stream::<MinidumpThreadList<'_>>().unwrap();
+        let thread = &thread_list.threads[0];
+        assert_eq!(thread.teb(), Some(thread.raw.teb));
+        assert_eq!(thread.teb(), Some(0x7ff600001000));
+        assert_eq!(dump.peb(), Some(0x7ff612340000));
+    }
+
+    #[test]
+    fn test_thread_without_teb() {
+        let context = synth_minidump::x86_context(Endian::Little, 0xabcd1234, 0x1010);
+        let stack = Memory::with_section(
+            Section::with_endian(Endian::Little).append_repeated(0, 0x1000),
+            0x1000,
+        );
+        let thread = Thread::new(Endian::Little, 0x1234, &stack, &context);
+        let system_info = SystemInfo::new(
+            Endian::Little,
+            md::ProcessorArchitecture::PROCESSOR_ARCHITECTURE_INTEL as u16,
+            md::PlatformId::VER_PLATFORM_WIN32_NT as u32,
+        );
+        let dump = SynthMinidump::with_endian(Endian::Little)
+            .add_thread(thread)
+            .add(context)
+            .add_memory(stack)
+            .add_stream(system_info);
+        let dump = read_synth_dump(dump).unwrap();
+        let thread_list = dump.get_stream::<MinidumpThreadList<'_>>().unwrap();
+        assert_eq!(thread_list.threads[0].teb(), None);
+        assert_eq!(dump.peb(), None);
+        assert_eq!(dump.peb_modules(), None);
+    }
+
+    #[test]
+    fn test_peb_modules() {
+        const TEB: u64 = 0x7ff600001000;
+        const PEB: u64 = 0x7ff612340000;
+        const LDR: u64 = 0x7ff612350000;
+        const ENTRY1: u64 = 0x7ff612360000;
+        const ENTRY2: u64 = 0x7ff612360100;
+        const NAME1: u64 = 0x7ff612370000;
+        const NAME2: u64 = 0x7ff612380000;
+        // An `LDR_DATA_TABLE_ENTRY` whose `InLoadOrderLinks` point at `next`.
+        let ldr_entry = |next: u64, base: u64, size: u32, name_length: u16, name: u64| {
+            Section::with_endian(Endian::Little)
+                .D64(next) // InLoadOrderLinks.Flink
+                .D64(LDR + 0x10) // InLoadOrderLinks.Blink
+                .append_repeated(0, 0x20)
+                .D64(base) // DllBase
+                .D64(0) // EntryPoint
+                .D32(size) // SizeOfImage
+                .D32(0)
+                .D16(name_length) // FullDllName.Length
+                .D16(name_length) // FullDllName.MaximumLength
+                .D32(0)
+                .D64(name) // FullDllName.Buffer
+        };
+        let utf16 = |s: &str| {
+            s.encode_utf16()
+                .flat_map(|c| c.to_le_bytes().to_vec())
+                .collect::<Vec<u8>>()
+        };
+        let name1 = utf16("C:\\test\\app.exe");
+
+        let context =
+            synth_minidump::amd64_context(Endian::Little, 0x1234abcd1234abcd, 0x1000000010000000);
+        let stack = Memory::with_section(
+            Section::with_endian(Endian::Little).append_repeated(0, 0x1000),
+            0x1000000010000000,
+        );
+        let teb = Memory::with_section(
+            Section::with_endian(Endian::Little)
+                .append_repeated(0, 0x60)
+                .D64(PEB), // ProcessEnvironmentBlock
+            TEB,
+        );
+        let peb = Memory::with_section(
+            Section::with_endian(Endian::Little)
+                .append_repeated(0, 0x18)
+                .D64(LDR), // Ldr
+            PEB,
+        );
+        let ldr = Memory::with_section(
+            Section::with_endian(Endian::Little)
+                .append_repeated(0, 0x10)
+                .D64(ENTRY1) // InLoadOrderModuleList.Flink
+                .D64(ENTRY2), // InLoadOrderModuleList.Blink
+            LDR,
+        );
+        // The second entry's name wasn't dumped, and the entry it links to
+        // wasn't either.
+        let entries = Memory::with_section(
+            ldr_entry(ENTRY2, 0x7ff700000000, 0x10000, name1.len() as u16, NAME1)
+                .append_repeated(0, 0x100 - 0x58)
+                .append_section(ldr_entry(0x7ff6dead0000, 0x7ff800000000, 0x20000, 8, NAME2)),
+            ENTRY1,
+        );
+        let names = Memory::with_section(
+            Section::with_endian(Endian::Little).append_bytes(&name1),
+            NAME1,
+        );
+        let thread = Thread::with_teb(Endian::Little, 0x1234, TEB, &stack, &context);
+        let system_info = SystemInfo::new(
+            Endian::Little,
+            md::ProcessorArchitecture::PROCESSOR_ARCHITECTURE_AMD64 as u16,
+            md::PlatformId::VER_PLATFORM_WIN32_NT as u32,
+        );
+        let dump = SynthMinidump::with_endian(Endian::Little)
+            .add_thread(thread)
+            .add(context)
+            .add_memory(stack)
+            .add_memory(teb)
+            .add_memory(peb)
+            .add_memory(ldr)
+            .add_memory(entries)
+            .add_memory(names)
+            .add_stream(system_info);
+        let dump = read_synth_dump(dump).unwrap();
+        let modules = dump.peb_modules().unwrap();
+        assert_eq!(
+            modules,
+            vec![
+                PebModule {
+                    base_address: 0x7ff700000000,
+                    size: 0x10000,
+                    name: Some(String::from("C:\\test\\app.exe")),
+                },
+                PebModule {
+                    base_address: 0x7ff800000000,
+                    size: 0x20000,
+                    name: None,
+                },
+            ]
+        );
+    }
+
+    #[test]
+    fn test_validate() {
+        // A well-formed dump has no issues.
+        let context = synth_minidump::x86_context(Endian::Little, 0xabcd1234, 0x1010);
+        let stack = Memory::with_section(
+            Section::with_endian(Endian::Little).append_repeated(0, 0x1000),
+            0x1000,
+        );
+        let thread = Thread::new(Endian::Little, 0x1234, &stack, &context);
+        let system_info = SystemInfo::new(
+            Endian::Little,
+            md::ProcessorArchitecture::PROCESSOR_ARCHITECTURE_INTEL as u16,
+            md::PlatformId::VER_PLATFORM_WIN32_NT as u32,
+        );
+        let dump = SynthMinidump::with_endian(Endian::Little)
+            .add_thread(thread)
+            .add(context)
+            .add_memory(stack)
+            .add_stream(system_info);
+        let dump = read_synth_dump(dump).unwrap();
+        assert_eq!(dump.validate(), vec![]);
+
+        // An x86 context and a ppc context on an amd64 system, overlapping memory,
+        // and a duplicate stream.
+        let context = synth_minidump::x86_context(Endian::Little, 0xabcd1234, 0x1010);
+        let ppc_context = synth_minidump::ppc_context(Endian::Little, 0xabcd1234, 0x1010);
+        let stack = Memory::with_section(
+            Section::with_endian(Endian::Little).append_repeated(0, 0x1000),
+            0x1000,
+        );
+        let overlapping = Memory::with_section(
+            Section::with_endian(Endian::Little).append_repeated(0, 0x100),
+            0x1800,
+        );
+        let thread = Thread::new(Endian::Little, 0x1234, &stack, &context);
+        let ppc_thread = Thread::new(Endian::Little, 0x5678, &stack, &ppc_context);
+        let system_info = SystemInfo::new(
+            Endian::Little,
+            md::ProcessorArchitecture::PROCESSOR_ARCHITECTURE_AMD64 as u16,
+            md::PlatformId::VER_PLATFORM_WIN32_NT as u32,
+        );
+        const STREAM_TYPE: u32 = 0x11223344;
+        let dump = SynthMinidump::with_endian(Endian::Little)
+            .add_thread(thread)
+            .add_thread(ppc_thread)
+            .add(context)
+            .add(ppc_context)
+            .add_memory(stack)
+            .add_memory(overlapping)
+            .add_stream(system_info)
+            .add_stream(SimpleStream {
+                stream_type: STREAM_TYPE,
+                section: Section::with_endian(Endian::Little).D32(1),
+            })
+            .add_stream(SimpleStream {
+                stream_type: STREAM_TYPE,
+                section: Section::with_endian(Endian::Little).D32(2),
+            });
+        let dump = read_synth_dump(dump).unwrap();
+        let issues = dump.validate();
+        assert_eq!(
+            issues,
+            vec![
+                ValidationIssue::DuplicateStream {
+                    stream_type: STREAM_TYPE,
+                    count: 2,
+                },
+                ValidationIssue::OverlappingMemory(0x1800),
+                ValidationIssue::ContextCpuMismatch {
+                    thread_id: 0x1234,
+                    context_cpu: Cpu::X86,
+                    cpu: Cpu::X86_64,
+                },
+                ValidationIssue::ContextCpuMismatch {
+                    thread_id: 0x5678,
+                    context_cpu: Cpu::Ppc,
+                    cpu: Cpu::X86_64,
+                },
+            ]
+        );
+        assert_eq!(issues[0].severity(), Severity::Warning);
+        // WOW64 threads are expected, other CPUs are not.
+        assert_eq!(issues[2].severity(), Severity::Warning);
+        assert_eq!(issues[3].severity(), Severity::Error);
+
+        // A stream past the end of the file, and no thread list or system info.
+        let dump = SynthMinidump::with_endian(Endian::Little).add_stream(SimpleStream {
+            stream_type: STREAM_TYPE,
+            section: Section::with_endian(Endian::Little).D32(1),
+        });
+        let mut bytes = dump.finish().unwrap();
+        let directory = bytes.pread_with::<u32>(12, LE).unwrap() as usize;
+        let rva = bytes.pread_with::<u32>(directory + 8, LE).unwrap();
+        bytes[directory + 4..directory + 8].copy_from_slice(&0x1000u32.to_le_bytes());
+        let dump = Minidump::read(bytes).unwrap();
+        assert_eq!(
+            dump.validate(),
+            vec![
+                ValidationIssue::StreamOutOfBounds {
+                    stream_type: STREAM_TYPE,
+                    rva,
+                    data_size: 0x1000,
+                },
+                ValidationIssue::MissingStream(MINIDUMP_STREAM_TYPE::ThreadListStream),
+                ValidationIssue::MissingStream(MINIDUMP_STREAM_TYPE::SystemInfoStream),
+            ]
+        );
+    }
+
     #[test]
     fn test_thread_list_amd64() {
         let context =
@@ -3358,9 +7419,1170 @@ mod test {
     }
 
     #[test]
-    fn test_crashpad_info_missing() {
-        let dump = SynthMinidump::with_endian(Endian::Little);
-        let dump = read_synth_dump(dump).unwrap();
+    fn test_context_extra_flags() {
+        // Windows sets flags like these along with the CPU type. The exception reporting
+        // bit is the same as CONTEXT_ARM64_OLD's.
+        const CONTEXT_XSTATE: u32 = 0x40;
+        const CONTEXT_EXCEPTION_REPORTING: u32 = 0x80000000;
+        let extra_flags = CONTEXT_XSTATE | CONTEXT_EXCEPTION_REPORTING;
+
+        let mut bytes = synth_minidump::amd64_context(Endian::Little, 0x1234abcd1234abcd, 0x1000)
+            .get_contents()
+            .unwrap();
+        let flags: u32 = bytes.pread_with(0x30, LE).unwrap();
+        bytes[0x30..0x34].copy_from_slice(&(flags | extra_flags).to_le_bytes());
+        match MinidumpContext::read(&bytes, LE).unwrap().raw {
+            MinidumpRawContext::Amd64(raw) => assert_eq!(raw.rip, 0x1234abcd1234abcd),
+            _ => panic!("Got unexpected raw context type!"),
+        }
+
+        let mut bytes = synth_minidump::x86_context(Endian::Little, 0xabcd1234, 0x1000)
+            .get_contents()
+            .unwrap();
+        let flags: u32 = bytes.pread_with(0, LE).unwrap();
+        bytes[0..4].copy_from_slice(&(flags | extra_flags).to_le_bytes());
+        match MinidumpContext::read(&bytes, LE).unwrap().raw {
+            MinidumpRawContext::X86(raw) => assert_eq!(raw.eip, 0xabcd1234),
+            _ => panic!("Got unexpected raw context type!"),
+        }
+
+        let bytes = Section::with_endian(Endian::Little)
+            .D32(0x400002 | extra_flags) // context_flags: CONTEXT_ARM64 | integer registers
+            .append_repeated(0, md::CONTEXT_ARM64::size_with(&LE) - 4)
+            .get_contents()
+            .unwrap();
+        match MinidumpContext::read(&bytes, LE).unwrap().raw {
+            MinidumpRawContext::Arm64(_) => {}
+            _ => panic!("Got unexpected raw context type!"),
+        }
+    }
+
+    #[test]
+    fn test_context_unknown_cpu() {
+        // CONTEXT_ALPHA | CONTEXT_CONTROL, in a context the size of an x86 one.
+        let bytes = Section::with_endian(Endian::Little)
+            .D32(0x20001)
+            .append_repeated(0, md::CONTEXT_X86::size_with(&LE) - 4)
+            .get_contents()
+            .unwrap();
+        match MinidumpContext::read(&bytes, LE) {
+            Err(ContextError::UnknownCpuContext(flags)) => assert_eq!(flags, 0x20001),
+            other => panic!("Got unexpected result: {:?}", other),
+        }
+    }
+
+    #[test]
+    fn test_thread_xstate() {
+        const CONTEXT_XSTATE: u32 = 0x40;
+        let context_size = md::CONTEXT_AMD64::size_with(&LE);
+        // xmm0 is at offset 160 of the FXSAVE area, which follows 256 bytes of registers.
+        let xmm_offset = 256 + 160;
+
+        let mut bytes = synth_minidump::amd64_context(Endian::Little, 0x1234abcd1234abcd, 0x1000)
+            .get_contents()
+            .unwrap();
+        let flags: u32 = bytes.pread_with(0x30, LE).unwrap();
+        bytes[0x30..0x34].copy_from_slice(&(flags | CONTEXT_XSTATE).to_le_bytes());
+        for i in 0..16 {
+            let start = xmm_offset + i * 16;
+            bytes[start..start + 16].copy_from_slice(&[i as u8; 16]);
+        }
+        // The upper halves of the YMM registers.
+        for i in 0..16 {
+            bytes.extend_from_slice(&[0x80 | i as u8; 16]);
+        }
+        let context = Section::with_endian(Endian::Little).append_bytes(&bytes);
+
+        let avx = md::XstateFeatureIndex::GSSE_AND_AVX as usize;
+        let mut features = [md::XSTATE_FEATURE::default(); 64];
+        features[avx] = md::XSTATE_FEATURE {
+            offset: context_size as u32,
+            size: 256,
+        };
+        let mut misc = MiscStream::new(Endian::Little);
+        misc.misc_5 = Some(MiscInfo5Fields {
+            xstate_data: md::XSTATE_CONFIG_FEATURE_MSC_INFO {
+                context_size: bytes.len() as u32,
+                enabled_features: 1 << avx,
+                features,
+                ..md::XSTATE_CONFIG_FEATURE_MSC_INFO::default()
+            },
+            process_cookie: None,
+        });
+
+        let stack = Memory::with_section(
+            Section::with_endian(Endian::Little).append_repeated(0, 0x1000),
+            0x1000,
+        );
+        let thread = Thread::new(Endian::Little, 0x1234, &stack, &context);
+        let dump = SynthMinidump::with_endian(Endian::Little)
+            .add_thread(thread)
+            .add(context)
+            .add_memory(stack)
+            .add_stream(misc);
+        let dump = read_synth_dump(dump).unwrap();
+        let thread_list = dump.get_stream::<MinidumpThreadList<'_>>().unwrap();
+        let thread = &thread_list.threads[0];
+        let context = thread
+            .context
+            .as_ref()
+            .expect("Should have a thread context");
+        assert!(context.has_xstate());
+        assert_eq!(context.get_instruction_pointer(), 0x1234abcd1234abcd);
+
+        let xstate = dump
+            .thread_xstate(thread)
+            .expect("Should have extended state");
+        assert_eq!(xstate.feature(md::XstateFeatureIndex::LEGACY_SSE), None);
+        let ymm = context.ymm_registers(&xstate).unwrap();
+        assert_eq!(ymm.len(), 16);
+        for (i, reg) in ymm.iter().enumerate() {
+            assert_eq!(&reg[..16], &[i as u8; 16]);
+            assert_eq!(&reg[16..], &[0x80 | i as u8; 16]);
+        }
+
+        // Without the flag there's no extended state, even if it's described.
+        let context = synth_minidump::amd64_context(Endian::Little, 0x1234abcd1234abcd, 0x1000);
+        let stack = Memory::with_section(
+            Section::with_endian(Endian::Little).append_repeated(0, 0x1000),
+            0x1000,
+        );
+        let thread = Thread::new(Endian::Little, 0x1234, &stack, &context);
+        let mut misc = MiscStream::new(Endian::Little);
+        misc.misc_5 = Some(MiscInfo5Fields::default());
+        let dump = SynthMinidump::with_endian(Endian::Little)
+            .add_thread(thread)
+            .add(context)
+            .add_memory(stack)
+            .add_stream(misc);
+        let dump = read_synth_dump(dump).unwrap();
+        let thread_list = dump.get_stream::<MinidumpThreadList<'_>>().unwrap();
+        let thread = &thread_list.threads[0];
+        assert!(!thread.context.as_ref().unwrap().has_xstate());
+        assert!(dump.thread_xstate(thread).is_none());
+    }
+
+    #[test]
+    fn test_thread_list_ppc_big_endian() {
+        // A big-endian context is decoded correctly both in a big-endian dump and in a
+        // little-endian one from a PPC system.
+        for &dump_endian in &[Endian::Big, Endian::Little] {
+            let context = synth_minidump::ppc_context(Endian::Big, 0x10001234, 0x7fff0000);
+            let stack = Memory::with_section(
+                Section::with_endian(dump_endian).append_repeated(0, 0x1000),
+                0x7fff0000,
+            );
+            let thread = Thread::new(dump_endian, 0x1234, &stack, &context);
+            let system_info = SystemInfo::new(
+                dump_endian,
+                md::ProcessorArchitecture::PROCESSOR_ARCHITECTURE_PPC as u16,
+                md::PlatformId::MacOs as u32,
+            );
+            let dump = SynthMinidump::with_endian(dump_endian)
+                .add_thread(thread)
+                .add(context)
+                .add_memory(stack)
+                .add_stream(system_info);
+            let dump = read_synth_dump(dump).unwrap();
+            let thread_list = dump.get_stream::<MinidumpThreadList<'_>>().unwrap();
+            assert_eq!(thread_list.threads.len(), 1);
+            let context = thread_list.threads[0]
+                .context
+                .as_ref()
+                .expect("Should have a thread context");
+            match context.raw {
+                MinidumpRawContext::Ppc(ref raw) => assert_eq!(raw.context_flags, 0x20000007),
+                _ => panic!("Got unexpected raw context type!"),
+            }
+            assert_eq!(context.get_instruction_pointer(), 0x10001234);
+            assert_eq!(context.get_stack_pointer(), 0x7fff0000);
+        }
+
+        // A byte-swapped context isn't trusted if the system info is for another CPU.
+        let context = synth_minidump::ppc_context(Endian::Big, 0x10001234, 0x7fff0000);
+        let stack = Memory::with_section(
+            Section::with_endian(Endian::Little).append_repeated(0, 0x1000),
+            0x7fff0000,
+        );
+        let thread = Thread::new(Endian::Little, 0x1234, &stack, &context);
+        let system_info = SystemInfo::new(
+            Endian::Little,
+            md::ProcessorArchitecture::PROCESSOR_ARCHITECTURE_INTEL as u16,
+            md::PlatformId::VER_PLATFORM_WIN32_NT as u32,
+        );
+        let dump = SynthMinidump::with_endian(Endian::Little)
+            .add_thread(thread)
+            .add(context)
+            .add_memory(stack)
+            .add_stream(system_info);
+        let dump = read_synth_dump(dump).unwrap();
+        let thread_list = dump.get_stream::<MinidumpThreadList<'_>>().unwrap();
+        assert!(thread_list.threads[0].context.is_none());
+        assert!(thread_list.threads[0].context_error.is_some());
+    }
+
+    #[test]
+    fn test_thread_list_mips64() {
+        let context = synth_minidump::mips64_context(Endian::Little, 0x120001234, 0x7fff00000000);
+        let stack = Memory::with_section(
+            Section::with_endian(Endian::Little).append_repeated(0, 0x1000),
+            0x7fff00000000,
+        );
+        let thread = Thread::new(Endian::Little, 0x1234, &stack, &context);
+        let system_info = SystemInfo::new(
+            Endian::Little,
+            md::ProcessorArchitecture::PROCESSOR_ARCHITECTURE_MIPS64 as u16,
+            md::PlatformId::Linux as u32,
+        );
+        let dump = SynthMinidump::with_endian(Endian::Little)
+            .add_thread(thread)
+            .add(context)
+            .add_memory(stack)
+            .add_stream(system_info);
+        let dump = read_synth_dump(dump).unwrap();
+        let system_info = dump.get_stream::<MinidumpSystemInfo>().unwrap();
+        assert_eq!(system_info.cpu, Cpu::Mips64);
+        let thread_list = dump.get_stream::<MinidumpThreadList<'_>>().unwrap();
+        let context = thread_list.threads[0]
+            .context
+            .as_ref()
+            .expect("Should have a thread context");
+        match context.raw {
+            MinidumpRawContext::Mips64(ref raw) => {
+                assert_eq!(raw.context_flags, 0x8000e);
+                assert_eq!(raw.get_register("sp", &context.valid), Some(0x7fff00000000));
+                assert_eq!(raw.get_register("pc", &context.valid), Some(0x120001234));
+                assert_eq!(raw.memoize_register("ra"), Some("ra"));
+                assert_eq!(raw.memoize_register("eip"), None);
+            }
+            _ => panic!("Got unexpected raw context type!"),
+        }
+        assert_eq!(context.get_instruction_pointer(), 0x120001234);
+        assert_eq!(context.get_stack_pointer(), 0x7fff00000000);
+        assert_eq!(context.general_purpose_registers().len(), 33);
+        assert_eq!(context.format_register("sp"), "0x00007fff00000000");
+
+        let mut output = vec![];
+        context.print(&mut output).unwrap();
+        let output = String::from_utf8(output).unwrap();
+        assert!(output.starts_with("CONTEXT_MIPS64\n"));
+        assert!(output.contains("  epc                  = 0x120001234\n"));
+    }
+
+    #[test]
+    fn test_thread_list_mips() {
+        let context = synth_minidump::mips_context(Endian::Little, 0x80001234, 0x7fff0000);
+        let stack = Memory::with_section(
+            Section::with_endian(Endian::Little).append_repeated(0, 0x1000),
+            0x7fff0000,
+        );
+        let thread = Thread::new(Endian::Little, 0x1234, &stack, &context);
+        let dump = SynthMinidump::with_endian(Endian::Little)
+            .add_thread(thread)
+            .add(context)
+            .add_memory(stack);
+        let dump = read_synth_dump(dump).unwrap();
+        let thread_list = dump.get_stream::<MinidumpThreadList<'_>>().unwrap();
+        let context = thread_list.threads[0]
+            .context
+            .as_ref()
+            .expect("Should have a thread context");
+        match context.raw {
+            MinidumpRawContext::Mips(ref raw) => assert_eq!(raw.context_flags, 0x4000e),
+            _ => panic!("Got unexpected raw context type!"),
+        }
+        // Registers are formatted at their natural 32-bit width.
+        assert_eq!(context.general_purpose_registers().len(), 33);
+        assert_eq!(context.format_register("sp"), "0x7fff0000");
+        assert_eq!(context.format_register("pc"), "0x80001234");
+
+        let mut output = vec![];
+        context.print(&mut output).unwrap();
+        let output = String::from_utf8(output).unwrap();
+        assert!(output.starts_with("CONTEXT_MIPS\n"));
+        assert!(output.contains("  iregs[29]            = 0x7fff0000\n"));
+        assert!(output.contains("  epc                  = 0x80001234\n"));
+    }
+
+    #[test]
+    fn test_context_print_eflags() {
+        let print = |raw| {
+            let mut output = vec![];
+            MinidumpContext::from_raw(raw).print(&mut output).unwrap();
+            String::from_utf8(output).unwrap()
+        };
+        let output = print(MinidumpRawContext::X86(md::CONTEXT_X86 {
+            eflags: 0x10246,
+            ..Default::default()
+        }));
+        assert!(output.contains("  eflags                       = 0x10246\n"));
+        assert!(output.contains("  eflags (decoded)             = [ PF ZF IF RF ]\n"));
+        // Carry, sign, overflow and direction, at I/O privilege level 3.
+        let output = print(MinidumpRawContext::Amd64(md::CONTEXT_AMD64 {
+            eflags: 0x3e83,
+            ..Default::default()
+        }));
+        assert!(output.contains("  eflags        = 0x3e83\n"));
+        assert!(output.contains("  eflags (decoded) = [ CF SF IF DF OF IOPL=3 ]\n"));
+        let output = print(MinidumpRawContext::Amd64(md::CONTEXT_AMD64::default()));
+        assert!(output.contains("  eflags (decoded) = [ ]\n"));
+    }
+
+    #[test]
+    fn test_context_arm64_print_cpsr() {
+        let print = |cpsr| {
+            let context = MinidumpContext::from_raw(MinidumpRawContext::Arm64(md::CONTEXT_ARM64 {
+                cpsr,
+                ..Default::default()
+            }));
+            let mut output = vec![];
+            context.print(&mut output).unwrap();
+            String::from_utf8(output).unwrap()
+        };
+        // Z and C set, running at EL0 with nothing masked.
+        let output = print(0x6000_0000);
+        assert!(output.contains("  cpsr                 = 0x60000000\n"));
+        assert!(output.contains("  cpsr (decoded)       = -ZC- EL0t DAIF=---- BTYPE=0\n"));
+        // N set, EL1 on its own stack with IRQ and FIQ masked, PAN on, in a BTI landing pad.
+        let output = print(0x8040_0cc5);
+        assert!(output.contains("  cpsr (decoded)       = N--- EL1h DAIF=--IF BTYPE=3 PAN\n"));
+        // AArch32 state.
+        let output = print(0x1d0);
+        assert!(output.contains("  cpsr (decoded)       = ---- AArch32\n"));
+    }
+
+    #[test]
+    fn test_context_arm64_vector_registers() {
+        // CONTEXT_ARM64 | CONTEXT_ARM64_FLOATING_POINT
+        let mut raw = md::CONTEXT_ARM64 {
+            context_flags: 0x400004,
+            ..md::CONTEXT_ARM64::default()
+        };
+        raw.float_save.regs[0] = 1;
+        raw.float_save.regs[31] = u128::max_value();
+        let context = MinidumpContext::from_raw(MinidumpRawContext::Arm64(raw.clone()));
+        let regs = context.vector_registers().unwrap();
+        assert_eq!(regs.len(), 32);
+        assert_eq!(regs[0], 1);
+        assert_eq!(regs[31], u128::max_value());
+        // Without the floating-point flag, the registers weren't captured.
+        raw.context_flags = 0x400002;
+        let context = MinidumpContext::from_raw(MinidumpRawContext::Arm64(raw));
+        assert_eq!(context.vector_registers(), None);
+
+        // The old layout uses the same flag.
+        let mut regs = [0; 32];
+        regs[1] = 2;
+        let mut old = md::CONTEXT_ARM64_OLD {
+            context_flags: 0x80000004,
+            iregs: [0; 32],
+            pc: 0,
+            cpsr: 0,
+            float_save: md::FLOATING_SAVE_AREA_ARM64_OLD {
+                fpsr: 0,
+                fpcr: 0,
+                regs,
+            },
+        };
+        let context = MinidumpContext::from_raw(MinidumpRawContext::OldArm64(old));
+        let regs = context.vector_registers().unwrap();
+        assert_eq!(regs.len(), 32);
+        assert_eq!(regs[1], 2);
+        old.context_flags = 0x80000002;
+        let context = MinidumpContext::from_raw(MinidumpRawContext::OldArm64(old));
+        assert_eq!(context.vector_registers(), None);
+
+        let context =
+            MinidumpContext::from_raw(MinidumpRawContext::Amd64(md::CONTEXT_AMD64::default()));
+        assert_eq!(context.vector_registers(), None);
+    }
+
+    fn fingerprint_dump(module_base: u64, exception_code: u32, caller_offset: u32) -> Vec<u8> {
+        let name = DumpString::new("c:\\app\\app.exe", Endian::Little);
+        let module = SynthModule::new(
+            Endian::Little,
+            module_base,
+            0x10000,
+            &name,
+            0xb1054d2a,
+            0x34571371,
+            Some(&STOCK_VERSION_INFO),
+        );
+        let context =
+            synth_minidump::x86_context(Endian::Little, module_base as u32 + 0x1234, 0x1000);
+        // A word that isn't in any module, then the caller's return address.
+        let stack = Memory::with_section(
+            Section::with_endian(Endian::Little)
+                .D32(0x10)
+                .D32(module_base as u32 + caller_offset)
+                .append_repeated(0, 0x100),
+            0x1000,
+        );
+        let thread = Thread::new(Endian::Little, 0x1234, &stack, &context);
+        let exception = Exception::new(
+            Endian::Little,
+            0x1234,
+            exception_code,
+            0,
+            0x45,
+            &[1, 0x45],
+            &context,
+        );
+        SynthMinidump::with_endian(Endian::Little)
+            .add_module(module)
+            .add(name)
+            .add_stream(exception)
+            .add_thread(thread)
+            .add(context)
+            .add_memory(stack)
+            .finish()
+            .unwrap()
+    }
+
+    #[test]
+    fn test_fingerprint() {
+        let access_violation = md::ExceptionCodeWindows::EXCEPTION_ACCESS_VIOLATION as u32;
+        let bytes = fingerprint_dump(0x400000, access_violation, 0x5678);
+        let dump = Minidump::read(bytes.clone()).unwrap();
+        let fingerprint = dump.fingerprint();
+
+        // Only the header's time_date_stamp differs.
+        let mut later = bytes.clone();
+        later[20..24].copy_from_slice(&0x5f5e100u32.to_le_bytes());
+        assert_ne!(bytes, later);
+        let later = Minidump::read(later).unwrap();
+        assert_ne!(later.header.time_date_stamp, 1262805309);
+        assert_eq!(later.fingerprint(), fingerprint);
+
+        // The module being loaded elsewhere doesn't matter either.
+        let relocated =
+            Minidump::read(fingerprint_dump(0x800000, access_violation, 0x5678)).unwrap();
+        assert_eq!(relocated.fingerprint(), fingerprint);
+
+        // A different crash reason does.
+        let breakpoint = md::ExceptionCodeWindows::EXCEPTION_BREAKPOINT as u32;
+        let other = Minidump::read(fingerprint_dump(0x400000, breakpoint, 0x5678)).unwrap();
+        assert_ne!(other.fingerprint(), fingerprint);
+
+        // So does crashing at the same instruction, called from somewhere else.
+        let other = Minidump::read(fingerprint_dump(0x400000, access_violation, 0x9abc)).unwrap();
+        assert_ne!(other.fingerprint(), fingerprint);
+    }
+
+    #[test]
+    fn test_get_stream_checked() {
+        let dump = SynthMinidump::with_endian(Endian::Little)
+            // Too short to be a MINIDUMP_MISC_INFO.
+            .add_stream(SimpleStream {
+                stream_type: MINIDUMP_STREAM_TYPE::MiscInfoStream as u32,
+                section: Section::with_endian(Endian::Little).D32(0),
+            })
+            // A version of 0 is invalid.
+            .add_stream(SimpleStream {
+                stream_type: MINIDUMP_STREAM_TYPE::CrashpadInfoStream as u32,
+                section: Section::with_endian(Endian::Little)
+                    .append_repeated(0, md::MINIDUMP_CRASHPAD_INFO::size_with(&LE)),
+            });
+        let dump = read_synth_dump(dump).unwrap();
+        assert_eq!(
+            dump.get_stream_checked::<MinidumpBreakpadInfo>()
+                .unwrap_err(),
+            StreamError::StreamNotFound
+        );
+        assert!(matches!(
+            dump.get_stream_checked::<MinidumpMiscInfo>(),
+            Err(StreamError::StreamReadFailure(_))
+        ));
+        assert_eq!(
+            dump.get_stream_checked::<MinidumpCrashpadInfo>()
+                .unwrap_err(),
+            StreamError::VersionMismatch
+        );
+    }
+
+    #[test]
+    fn test_linux_maps() {
+        let maps = "\
+00400000-00452000 r-xp 00000000 08:02 173521      /usr/bin/dbus-daemon
+00651000-00652000 r--p 00051000 08:02 173521      /usr/bin/dbus-daemon
+garbage
+3d33600000-3d33601000 rwxp 00000000 00:00 0 
+7fff4c9e8000-7fff4ca09000 rw-p 00000000 00:00 0                          [stack]
+7f0e0a000000-7f0e0a000000 r--p 00000000 00:00 0
+";
+        let dump = SynthMinidump::with_endian(Endian::Little).add_stream(SimpleStream {
+            stream_type: MINIDUMP_STREAM_TYPE::LinuxMaps as u32,
+            section: Section::new().append_bytes(maps.as_bytes()),
+        });
+        let dump = read_synth_dump(dump).unwrap();
+        let maps = dump.get_stream::<MinidumpLinuxMaps>().unwrap();
+        // The line that doesn't parse is skipped.
+        assert_eq!(maps.iter().count(), 5);
+
+        let map = maps.map_at_address(0x00400123).unwrap();
+        assert_eq!(
+            *map,
+            MinidumpLinuxMapInfo {
+                base_address: 0x00400000,
+                end_address: 0x00452000,
+                permissions: "r-xp".to_owned(),
+                offset: 0,
+                path: Some("/usr/bin/dbus-daemon".to_owned()),
+            }
+        );
+        assert!(map.is_executable());
+        let map = maps.map_at_address(0x00651fff).unwrap();
+        assert_eq!(map.offset, 0x51000);
+        assert!(!map.is_executable());
+
+        // An anonymous executable mapping, like JIT code.
+        let map = maps.map_at_address(0x3d33600800).unwrap();
+        assert_eq!(map.path, None);
+        assert_eq!(map.name(), "<anonymous>");
+        assert!(map.is_executable());
+
+        assert_eq!(
+            maps.map_at_address(0x7fff4ca00000).unwrap().name(),
+            "[stack]"
+        );
+        // The end address isn't part of a mapping, and empty mappings contain nothing.
+        assert_eq!(maps.map_at_address(0x00452000), None);
+        assert_eq!(maps.map_at_address(0x7f0e0a000000), None);
+    }
+
+    #[test]
+    fn test_linux_cmdline() {
+        let read_cmdline = |cmdline: &[u8]| {
+            let dump = SynthMinidump::with_endian(Endian::Little).add_stream(SimpleStream {
+                stream_type: MINIDUMP_STREAM_TYPE::LinuxCmdLine as u32,
+                section: Section::new().append_bytes(cmdline),
+            });
+            let dump = read_synth_dump(dump).unwrap();
+            dump.get_stream::<MinidumpLinuxCmdLine>().unwrap()
+        };
+
+        let cmdline = read_cmdline(b"/usr/bin/dbus-daemon\0--system\0--nofork\0");
+        assert_eq!(cmdline.program(), Some("/usr/bin/dbus-daemon"));
+        assert_eq!(
+            cmdline.args,
+            vec!["/usr/bin/dbus-daemon", "--system", "--nofork"]
+        );
+        // An Android app's process, whose last argument was truncated.
+        let cmdline = read_cmdline(b"org.mozilla.firefox\0--trunc");
+        assert_eq!(cmdline.program(), Some("org.mozilla.firefox"));
+        assert_eq!(cmdline.args, vec!["org.mozilla.firefox", "--trunc"]);
+
+        let cmdline = read_cmdline(b"");
+        assert_eq!(cmdline.program(), None);
+        assert!(cmdline.args.is_empty());
+    }
+
+    #[test]
+    fn test_linux_environ() {
+        let read_environ = |environ: &[u8]| {
+            let dump = SynthMinidump::with_endian(Endian::Little).add_stream(SimpleStream {
+                stream_type: MINIDUMP_STREAM_TYPE::LinuxEnviron as u32,
+                section: Section::new().append_bytes(environ),
+            });
+            let dump = read_synth_dump(dump).unwrap();
+            dump.get_stream::<MinidumpLinuxEnviron>().unwrap()
+        };
+
+        let environ =
+            read_environ(b"HOME=/home/user\0LD_LIBRARY_PATH=/opt/a=b\0EMPTY=\0HOME=/root\0");
+        assert_eq!(
+            environ.vars,
+            vec![
+                ("HOME".to_owned(), "/home/user".to_owned()),
+                ("LD_LIBRARY_PATH".to_owned(), "/opt/a=b".to_owned()),
+                ("EMPTY".to_owned(), "".to_owned()),
+                ("HOME".to_owned(), "/root".to_owned()),
+            ]
+        );
+        assert_eq!(environ.get("HOME"), Some("/home/user"));
+        assert_eq!(environ.get("EMPTY"), Some(""));
+        assert_eq!(environ.get("PATH"), None);
+
+        // Malformed entries are a name without a value, and a truncated last
+        // entry is kept.
+        let environ = read_environ(b"\0MALFORMED\0\0LANG=C.UT");
+        assert_eq!(
+            environ.vars,
+            vec![
+                ("MALFORMED".to_owned(), "".to_owned()),
+                ("LANG".to_owned(), "C.UT".to_owned()),
+            ]
+        );
+
+        assert!(read_environ(b"").vars.is_empty());
+    }
+
+    #[test]
+    fn test_thread_names() {
+        let name1 = DumpString::new("main", Endian::Little);
+        let name2 = DumpString::new("", Endian::Little);
+        let dump = SynthMinidump::with_endian(Endian::Little)
+            .add_thread_name(ThreadName::new(Endian::Little, 1, &name1))
+            .add_thread_name(ThreadName::new(Endian::Little, 2, &name2))
+            .add(name1)
+            .add(name2);
+        let dump = read_synth_dump(dump).unwrap();
+        let thread_names = dump.get_stream::<MinidumpThreadNames>().unwrap();
+        assert_eq!(thread_names.get_name(1), Some("main"));
+        // Empty names are treated as no name at all.
+        assert_eq!(thread_names.get_name(2), None);
+        assert_eq!(thread_names.get_name(3), None);
+    }
+
+    #[test]
+    fn test_threads() {
+        let name = DumpString::new("main", Endian::Little);
+        let context1 = synth_minidump::x86_context(Endian::Little, 0xabcd1234, 0x1010);
+        let stack1 = Memory::with_section(
+            Section::with_endian(Endian::Little).append_repeated(0, 0x100),
+            0x1000,
+        );
+        let thread1 = Thread::with_teb(Endian::Little, 1, 0x7ffde000, &stack1, &context1);
+        // A thread whose stack is only in the memory list.
+        let context2 = synth_minidump::x86_context(Endian::Little, 0xabcd5678, 0x2010);
+        let stack2 = Memory::with_section(Section::with_endian(Endian::Little), 0x2000);
+        let stack2_memory = Memory::with_section(
+            Section::with_endian(Endian::Little).append_repeated(0, 0x100),
+            0x2000,
+        );
+        let thread2 = Thread::new(Endian::Little, 2, &stack2, &context2);
+        let dump = SynthMinidump::with_endian(Endian::Little)
+            .add_thread(thread1)
+            .add_thread(thread2)
+            .add_thread_name(ThreadName::new(Endian::Little, 1, &name))
+            .add(name)
+            .add(context1)
+            .add(context2)
+            .add(stack2)
+            .add_memory(stack1)
+            .add_memory(stack2_memory);
+        let dump = read_synth_dump(dump).unwrap();
+        let threads = dump.threads().unwrap().collect::<Vec<_>>();
+        assert_eq!(threads.len(), 2);
+
+        assert_eq!(threads[0].id, 1);
+        assert_eq!(threads[0].name.as_deref(), Some("main"));
+        assert_eq!(threads[0].teb, Some(0x7ffde000));
+        let context = threads[0].context.as_ref().unwrap();
+        assert_eq!(context.get_instruction_pointer(), 0xabcd1234);
+        let stack = threads[0].stack_memory.as_ref().unwrap();
+        assert_eq!(stack.base_address, 0x1000);
+        assert_eq!(stack.size, 0x100);
+
+        assert_eq!(threads[1].id, 2);
+        assert_eq!(threads[1].name, None);
+        assert_eq!(threads[1].teb, None);
+        let context = threads[1].context.as_ref().unwrap();
+        assert_eq!(context.get_instruction_pointer(), 0xabcd5678);
+        let stack = threads[1].stack_memory.as_ref().unwrap();
+        assert_eq!(stack.base_address, 0x2000);
+        assert_eq!(stack.size, 0x100);
+
+        // There's nothing to iterate over without a thread list.
+        let dump = read_synth_dump(SynthMinidump::with_endian(Endian::Little)).unwrap();
+        assert!(dump.threads().is_err());
+    }
+
+    #[test]
+    fn test_memory_info_list() {
+        let list = MemoryInfoList::new(Endian::Little)
+            .add(0x10000, 0x1000, 0x1000, 0x4) // MEM_COMMIT, PAGE_READWRITE
+            .add(0x11000, 0xf000, 0x10000, 0x1); // MEM_FREE, PAGE_NOACCESS
+        let dump = SynthMinidump::with_endian(Endian::Little).add_stream(list);
+        let dump = read_synth_dump(dump).unwrap();
+        let memory_info = dump.get_stream::<MinidumpMemoryInfoList>().unwrap();
+        let regions = memory_info.iter().collect::<Vec<_>>();
+        assert_eq!(regions.len(), 2);
+        assert_eq!(regions[0].raw.base_address, 0x10000);
+        assert_eq!(regions[0].raw.region_size, 0x1000);
+        assert!(!regions[0].is_free());
+        assert_eq!(
+            regions[0].protection(),
+            md::MemoryProtection::PAGE_READWRITE
+        );
+        assert_eq!(regions[1].raw.base_address, 0x11000);
+        assert!(regions[1].is_free());
+        assert!(!regions[0].is_executable());
+        assert!(!regions[1].is_executable());
+
+        let region = memory_info.memory_info_at_address(0x10fff).unwrap();
+        assert_eq!(region.raw.base_address, 0x10000);
+        let region = memory_info.memory_info_at_address(0x11000).unwrap();
+        assert_eq!(region.raw.base_address, 0x11000);
+        assert!(memory_info.memory_info_at_address(0x20000).is_none());
+        assert!(memory_info.memory_info_at_address(0xffff).is_none());
+
+        // Regions needn't be in address order, and empty ones contain nothing.
+        let mut regions = memory_info.iter().cloned().collect::<Vec<_>>();
+        regions.reverse();
+        regions[0].raw.region_size = 0;
+        let memory_info = MinidumpMemoryInfoList::from_regions(regions);
+        let region = memory_info.memory_info_at_address(0x10000).unwrap();
+        assert_eq!(region.raw.base_address, 0x10000);
+        assert!(memory_info.memory_info_at_address(0x11000).is_none());
+    }
+
+    #[test]
+    fn test_memory_info_executable() {
+        let list = MemoryInfoList::new(Endian::Little)
+            .add(0x10000, 0x1000, 0x1000, 0x20) // MEM_COMMIT, PAGE_EXECUTE_READ
+            .add(0x11000, 0x1000, 0x2000, 0x20) // MEM_RESERVE, PAGE_EXECUTE_READ
+            .add(0x12000, 0x1000, 0x1000, 0x104); // MEM_COMMIT, PAGE_READWRITE | PAGE_GUARD
+        let dump = SynthMinidump::with_endian(Endian::Little).add_stream(list);
+        let dump = read_synth_dump(dump).unwrap();
+        let memory_info = dump.get_stream::<MinidumpMemoryInfoList>().unwrap();
+        let executable = memory_info
+            .iter()
+            .map(|region| region.is_executable())
+            .collect::<Vec<_>>();
+        assert_eq!(executable, vec![true, false, false]);
+    }
+
+    fn handle_data_stream(descriptor_size: u32, extended: bool) -> SynthMinidump {
+        let type_name = DumpString::new("Event", Endian::Little);
+        let object_name = DumpString::new("\\BaseNamedObjects\\foo", Endian::Little);
+        let mut section = Section::with_endian(Endian::Little)
+            .D32(16) // size_of_header
+            .D32(descriptor_size) // size_of_descriptor
+            .D32(2) // number_of_descriptors
+            .D32(0); // reserved
+        for &(handle, name) in &[(0x4u64, Some(&object_name)), (0x8u64, None)] {
+            section = section
+                .D64(handle) // handle
+                .D32(&type_name.file_offset()); // type_name_rva
+            section = match name {
+                Some(name) => section.D32(&name.file_offset()), // object_name_rva
+                None => section.D32(0),
+            };
+            section = section
+                .D32(0x2) // attributes
+                .D32(0x1f0003) // granted_access
+                .D32(3) // handle_count
+                .D32(7); // pointer_count
+            if extended {
+                section = section
+                    .D32(0) // object_info_rva
+                    .D32(0); // reserved0
+            }
+        }
+        SynthMinidump::with_endian(Endian::Little)
+            .add_stream(SimpleStream {
+                stream_type: MINIDUMP_STREAM_TYPE::HandleDataStream as u32,
+                section,
+            })
+            .add(type_name)
+            .add(object_name)
+    }
+
+    #[test]
+    fn test_handle_data_stream() {
+        for &(size, extended) in &[(32, false), (40, true)] {
+            let dump = read_synth_dump(handle_data_stream(size, extended)).unwrap();
+            let handle_data = dump.get_stream::<MinidumpHandleDataStream>().unwrap();
+            assert_eq!(handle_data.handles.len(), 2);
+
+            let handle = &handle_data.handles[0];
+            match handle.raw {
+                RawHandleDescriptor::HandleDescriptor(_) => assert!(!extended),
+                RawHandleDescriptor::HandleDescriptor2(_) => assert!(extended),
+            }
+            assert_eq!(handle.handle(), 0x4);
+            assert_eq!(handle.type_name.as_deref(), Some("Event"));
+            assert_eq!(
+                handle.object_name.as_deref(),
+                Some("\\BaseNamedObjects\\foo")
+            );
+            assert_eq!(handle.attributes(), 0x2);
+            assert_eq!(handle.granted_access(), 0x1f0003);
+            assert_eq!(handle.handle_count(), 3);
+            assert_eq!(handle.pointer_count(), 7);
+
+            let handle = &handle_data.handles[1];
+            assert_eq!(handle.handle(), 0x8);
+            assert_eq!(handle.type_name.as_deref(), Some("Event"));
+            assert_eq!(handle.object_name, None);
+        }
+    }
+
+    #[test]
+    fn test_handle_data_stream_bad_descriptor_size() {
+        let dump = read_synth_dump(handle_data_stream(36, true)).unwrap();
+        assert_eq!(
+            dump.get_stream::<MinidumpHandleDataStream>().err(),
+            Some(Error::StreamReadFailure)
+        );
+    }
+
+    fn handle_operation_list(entry_size: u32) -> SynthMinidump {
+        let mut section = Section::with_endian(Endian::Little)
+            .D32(16) // size_of_header
+            .D32(entry_size) // size_of_entry
+            .D32(2) // number_of_entries
+            .D32(0); // reserved
+        for &(operation, backtrace) in &[(1, &[0x401234u64, 0x7ff00010][..]), (2, &[0x402000][..])]
+        {
+            section = section
+                .D64(0x4) // handle
+                .D32(0x1000) // process_id
+                .D32(0x1004) // thread_id
+                .D32(operation) // operation_type
+                .D32(0) // spare0
+                .D32(backtrace.len() as u32) // depth
+                .D32(0x10); // index
+            section = backtrace
+                .iter()
+                .fold(section, |section, &address| section.D64(address))
+                .append_repeated(0, 8 * (md::AVRF_MAX_TRACES - backtrace.len()));
+        }
+        SynthMinidump::with_endian(Endian::Little).add_stream(SimpleStream {
+            stream_type: MINIDUMP_STREAM_TYPE::HandleOperationListStream as u32,
+            section,
+        })
+    }
+
+    #[test]
+    fn test_handle_operation_list() {
+        let dump = read_synth_dump(handle_operation_list(288)).unwrap();
+        let operations = dump.get_stream::<MinidumpHandleOperationList>().unwrap();
+        assert_eq!(operations.operations.len(), 2);
+        assert_eq!(operations.operations_on(0x4).count(), 2);
+        assert_eq!(operations.operations_on(0x8).count(), 0);
+
+        let open = &operations.operations[0];
+        assert_eq!(open.handle(), 0x4);
+        assert_eq!(open.raw.thread_id, 0x1004);
+        assert_eq!(
+            open.operation,
+            Some(md::HandleTraceOperation::OperationDbOPEN)
+        );
+        assert_eq!(open.backtrace(), &[0x401234, 0x7ff00010]);
+
+        let modules = MinidumpModuleList::from_modules(vec![MinidumpModule::new(
+            0x400000,
+            0x10000,
+            "c:\\app\\app.exe",
+        )]);
+        let resolved = open.backtrace_modules(&modules);
+        assert_eq!(resolved[0].0, 0x401234);
+        assert_eq!(resolved[0].1.unwrap().code_file(), "c:\\app\\app.exe");
+        assert!(resolved[1].1.is_none());
+
+        let close = &operations.operations[1];
+        assert_eq!(
+            close.operation,
+            Some(md::HandleTraceOperation::OperationDbCLOSE)
+        );
+        assert_eq!(close.backtrace(), &[0x402000]);
+
+        let mut output = vec![];
+        operations.print(&mut output, Some(&modules)).unwrap();
+        let output = String::from_utf8(output).unwrap();
+        assert!(output.contains("operation_type                      = 2 (OperationDbCLOSE)"));
+        assert!(output.contains("= 0x401234 (c:\\app\\app.exe + 0x1234)"));
+    }
+
+    #[test]
+    fn test_handle_operation_list_bad_entry_size() {
+        let dump = read_synth_dump(handle_operation_list(280)).unwrap();
+        assert_eq!(
+            dump.get_stream::<MinidumpHandleOperationList>().err(),
+            Some(Error::StreamReadFailure)
+        );
+    }
+
+    fn exception_stream(
+        code: u32,
+        flags: u32,
+        address: u64,
+        params: &[u64],
+    ) -> md::MINIDUMP_EXCEPTION_STREAM {
+        let mut exception_information = [0; 15];
+        exception_information[..params.len()].copy_from_slice(params);
+        md::MINIDUMP_EXCEPTION_STREAM {
+            thread_id: 1,
+            __align: 0,
+            exception_record: md::MINIDUMP_EXCEPTION {
+                exception_code: code,
+                exception_flags: flags,
+                exception_record: 0,
+                exception_address: address,
+                number_parameters: params.len() as u32,
+                __align: 0,
+                exception_information,
+            },
+            thread_context: md::MINIDUMP_LOCATION_DESCRIPTOR::default(),
+        }
+    }
+
+    #[test]
+    fn test_exception_details_windows() {
+        let access_violation = md::ExceptionCodeWindows::EXCEPTION_ACCESS_VIOLATION as u32;
+        for &(param, kind, name) in &[
+            (0, AccessKind::Read, "EXCEPTION_ACCESS_VIOLATION_READ"),
+            (1, AccessKind::Write, "EXCEPTION_ACCESS_VIOLATION_WRITE"),
+            (8, AccessKind::Execute, "EXCEPTION_ACCESS_VIOLATION_EXEC"),
+            (3, AccessKind::Unknown(3), "EXCEPTION_ACCESS_VIOLATION"),
+        ] {
+            let raw = exception_stream(access_violation, 0, 0x1000, &[param, 0x45]);
+            let details = ExceptionDetails::from_exception(&raw, Os::Windows);
+            assert_eq!(
+                details,
+                ExceptionDetails::AccessViolation {
+                    kind,
+                    address: 0x45
+                }
+            );
+            assert_eq!(details.to_string(), name);
+            assert_eq!(details.address(), 0x45);
+        }
+
+        let in_page_error = md::ExceptionCodeWindows::EXCEPTION_IN_PAGE_ERROR as u32;
+        let raw = exception_stream(in_page_error, 0, 0x1000, &[0, 0x2000, 0xc000009c]);
+        let details = ExceptionDetails::from_exception(&raw, Os::Windows);
+        assert_eq!(
+            details,
+            ExceptionDetails::InPageError {
+                kind: AccessKind::Read,
+                address: 0x2000,
+                status: 0xc000009c,
+            }
+        );
+        assert_eq!(details.to_string(), "EXCEPTION_IN_PAGE_ERROR_READ");
+
+        // Without parameters there's nothing to decode.
+        let raw = exception_stream(access_violation, 0, 0x1000, &[]);
+        let details = ExceptionDetails::from_exception(&raw, Os::Windows);
+        assert_eq!(
+            details,
+            ExceptionDetails::Other {
+                code: access_violation,
+                address: 0x1000
+            }
+        );
+    }
+
+    #[test]
+    fn test_crash_reason() {
+        let reason = |code: u32, flags: u32, params: &[u64], os: Os| {
+            CrashReason::from_exception(&exception_stream(code, flags, 0x1000, params), os)
+        };
+
+        let access_violation = md::ExceptionCodeWindows::EXCEPTION_ACCESS_VIOLATION as u32;
+        let crash = reason(access_violation, 0, &[0, 0], Os::Windows);
+        assert_eq!(crash, CrashReason::WindowsAccessViolation(AccessKind::Read));
+        assert_eq!(crash.to_string(), "EXCEPTION_ACCESS_VIOLATION_READ");
+        assert_eq!(crash.as_str(), "EXCEPTION_ACCESS_VIOLATION");
+        assert_eq!(crash.code(), Some(access_violation));
+
+        let stack_overflow = md::ExceptionCodeWindows::EXCEPTION_STACK_OVERFLOW as u32;
+        let crash = reason(stack_overflow, 0, &[], Os::Windows);
+        assert_eq!(
+            crash,
+            CrashReason::Windows(md::ExceptionCodeWindows::EXCEPTION_STACK_OVERFLOW)
+        );
+        assert_eq!(crash.to_string(), "EXCEPTION_STACK_OVERFLOW");
+
+        let crash = reason(0x12345678, 0, &[], Os::Windows);
+        assert_eq!(crash, CrashReason::WindowsUnknown(0x12345678));
+        assert_eq!(crash.to_string(), "0x12345678");
+        assert_eq!(crash.as_str(), "unknown");
+
+        let sigsegv = md::ExceptionCodeLinux::SIGSEGV as u32;
+        let crash = reason(sigsegv, 1, &[], Os::Linux);
+        assert_eq!(
+            crash,
+            CrashReason::Linux(md::ExceptionCodeLinux::SIGSEGV, 1)
+        );
+        assert_eq!(crash.to_string(), "SIGSEGV / SEGV_MAPERR");
+        assert_eq!(crash.as_str(), "SIGSEGV");
+        assert_eq!(
+            reason(sigsegv, 0x80, &[], Os::Android).to_string(),
+            "SIGSEGV / 0x80"
+        );
+
+        let sigfpe = md::ExceptionCodeLinux::SIGFPE as u32;
+        assert_eq!(
+            reason(sigfpe, 1, &[], Os::Linux).to_string(),
+            "SIGFPE / FPE_INTDIV"
+        );
+        let sigabrt = md::ExceptionCodeLinux::SIGABRT as u32;
+        assert_eq!(
+            reason(sigabrt, 0xfffffffa, &[], Os::Linux).to_string(),
+            "SIGABRT"
+        );
+
+        let bad_access = md::ExceptionCodeMac::EXC_BAD_ACCESS as u32;
+        let crash = reason(bad_access, 2, &[], Os::MacOs);
+        assert_eq!(
+            crash,
+            CrashReason::Mac(md::ExceptionCodeMac::EXC_BAD_ACCESS, 2)
+        );
+        assert_eq!(
+            crash.to_string(),
+            "EXC_BAD_ACCESS / KERN_PROTECTION_FAILURE"
+        );
+        let breakpoint = md::ExceptionCodeMac::EXC_BREAKPOINT as u32;
+        assert_eq!(
+            reason(breakpoint, 1, &[], Os::Ios).to_string(),
+            "EXC_BREAKPOINT"
+        );
+
+        let crash = reason(sigsegv, 0, &[], Os::Solaris);
+        assert_eq!(crash, CrashReason::Unknown);
+        assert_eq!(crash.to_string(), "unknown");
+        assert_eq!(crash.code(), None);
+    }
+
+    #[test]
+    fn test_exception_details_mac_linux() {
+        let bad_access = md::ExceptionCodeMac::EXC_BAD_ACCESS as u32;
+        let raw = exception_stream(bad_access, 1, 0x45, &[]);
+        let details = ExceptionDetails::from_exception(&raw, Os::MacOs);
+        assert_eq!(
+            details,
+            ExceptionDetails::MachBadAccess {
+                code: 1,
+                address: 0x45
+            }
+        );
+        assert_eq!(details.to_string(), "EXC_BAD_ACCESS / KERN_INVALID_ADDRESS");
+
+        let sigsegv = md::ExceptionCodeLinux::SIGSEGV as u32;
+        let raw = exception_stream(sigsegv, 2, 0x45, &[]);
+        let details = ExceptionDetails::from_exception(&raw, Os::Linux);
+        assert_eq!(
+            details,
+            ExceptionDetails::SignalFault {
+                signal: sigsegv,
+                code: 2,
+                address: 0x45
+            }
+        );
+        assert_eq!(details.to_string(), "SIGSEGV / SEGV_ACCERR");
+
+        let sigabrt = md::ExceptionCodeLinux::SIGABRT as u32;
+        let raw = exception_stream(sigabrt, 0, 0x1234, &[]);
+        let details = ExceptionDetails::from_exception(&raw, Os::Linux);
+        assert_eq!(details.address(), 0x1234);
+    }
+
+    fn exception_record32(code: u32, next: u32, address: u32, parameters: &[u32]) -> Section {
+        let section = Section::with_endian(Endian::Little)
+            .D32(code)
+            .D32(1) // exception_flags
+            .D32(next)
+            .D32(address)
+            .D32(parameters.len() as u32);
+        parameters
+            .iter()
+            .fold(section, |section, &param| section.D32(param))
+            .append_repeated(0, 4 * (15 - parameters.len()))
+    }
+
+    #[test]
+    fn test_multiple_exceptions() {
+        let context = synth_minidump::x86_context(Endian::Little, 0xabcd1234, 0x1010);
+        let first = Exception::new(Endian::Little, 0x1234, 0xc0000005, 0, 0x45, &[], &context);
+        let second = Exception::new(Endian::Little, 0x5678, 0xc0000409, 0, 0x67, &[], &context);
+        let dump = SynthMinidump::with_endian(Endian::Little)
+            .add_stream(first)
+            .add_stream(second)
+            .add(context);
+        let dump = read_synth_dump(dump).unwrap();
+
+        let exceptions = dump.get_streams::<MinidumpException>();
+        assert_eq!(exceptions.len(), 2);
+        let thread_ids = exceptions
+            .iter()
+            .map(|exception| exception.as_ref().unwrap().thread_id)
+            .collect::<Vec<_>>();
+        assert_eq!(thread_ids, vec![0x1234, 0x5678]);
+        assert_eq!(
+            dump.get_raw_streams(MINIDUMP_STREAM_TYPE::ExceptionStream)
+                .len(),
+            2
+        );
+        // `get_stream` still reads the last one.
+        let exception = dump.get_stream::<MinidumpException>().unwrap();
+        assert_eq!(exception.thread_id, 0x5678);
+
+        assert!(dump.get_streams::<MinidumpAssertion>().is_empty());
+    }
+
+    #[test]
+    fn test_exception_chain() {
+        let cpp_exception = 0xe06d7363;
+        let access_violation = md::ExceptionCodeWindows::EXCEPTION_ACCESS_VIOLATION as u32;
+        let record_size = md::EXCEPTION_RECORD32::size_with(&LE) as u32;
+        // The second nested record points back at the first.
+        let records = Memory::with_section(
+            exception_record32(
+                cpp_exception,
+                0x10000 + record_size,
+                0x401000,
+                &[0x19930520],
+            )
+            .append_section(exception_record32(
+                access_violation,
+                0x10000,
+                0x402000,
+                &[1, 0x45],
+            )),
+            0x10000,
+        );
+        let context = synth_minidump::x86_context(Endian::Little, 0x403000, 0);
+        let exception = Exception::with_nested(
+            Endian::Little,
+            0x1234,
+            cpp_exception,
+            1,
+            0x404000,
+            &[],
+            0x10000,
+            &context,
+        );
+        let dump = SynthMinidump::with_endian(Endian::Little)
+            .add_stream(exception)
+            .add(context)
+            .add_memory(records);
+        let dump = read_synth_dump(dump).unwrap();
+        let exception = dump.get_stream::<MinidumpException>().unwrap();
+        let memory_list = dump.get_stream::<MinidumpMemoryList<'_>>().unwrap();
+
+        // The exception's context says the process is 32-bit, whatever the system is.
+        let chain = exception.get_exception_chain(&memory_list, Cpu::X86_64);
+        // The loop back to the first nested record ends the chain.
+        assert_eq!(chain.len(), 3);
+        assert_eq!(chain[0].exception_address, 0x404000);
+        assert_eq!(chain[1].exception_code, cpp_exception);
+        assert_eq!(chain[1].exception_address, 0x401000);
+        assert_eq!(chain[1].number_parameters, 1);
+        assert_eq!(chain[1].exception_information[0], 0x19930520);
+        assert_eq!(chain[2].exception_code, access_violation);
+        assert_eq!(chain[2].exception_record, 0x10000);
+        assert_eq!(
+            ExceptionDetails::from_record(&chain[2], Os::Windows),
+            ExceptionDetails::AccessViolation {
+                kind: AccessKind::Write,
+                address: 0x45
+            }
+        );
+
+        // A nested record outside the dumped memory ends the chain.
+        let memory_list = MinidumpMemoryList::new();
+        let chain = exception.get_exception_chain(&memory_list, Cpu::X86);
+        assert_eq!(chain.len(), 1);
+    }
+
+    #[test]
+    fn test_crashpad_info_missing() {
+        let dump = SynthMinidump::with_endian(Endian::Little);
+        let dump = read_synth_dump(dump).unwrap();
 
         assert!(matches!(
             dump.get_stream::<MinidumpCrashpadInfo>(),
@@ -3429,4 +8651,150 @@ mod test {
             MinidumpAnnotation::Invalid
         );
     }
+    #[test]
+    fn test_huge_stream_count() {
+        let header = Section::with_endian(Endian::Little)
+            .D32(md::MINIDUMP_SIGNATURE)
+            .D32(md::MINIDUMP_VERSION)
+            .D32(0xffffffff) // stream_count
+            .D32(32) // stream_directory_rva
+            .D32(0) // checksum
+            .D32(0) // time_date_stamp
+            .D64(0); // flags
+        let dump = Minidump::read(header.get_contents().unwrap());
+        assert_eq!(dump.err(), Some(Error::MissingDirectory));
+    }
+
+    #[test]
+    fn test_module_list_truncated() {
+        // A module list that claims far more entries than it contains.
+        let dump = SynthMinidump::with_endian(Endian::Little).add_stream(SimpleStream {
+            stream_type: MINIDUMP_STREAM_TYPE::ModuleListStream as u32,
+            section: Section::with_endian(Endian::Little).D32(0x10000000),
+        });
+        let dump = read_synth_dump(dump).unwrap();
+        let module_list = dump.get_stream::<MinidumpModuleList>().unwrap();
+        assert_eq!(module_list.iter().count(), 0);
+        assert_eq!(module_list.declared_count(), 0x10000000);
+        assert_eq!(
+            module_list.validate(),
+            Err(Error::ModuleCountMismatch {
+                declared: 0x10000000,
+                actual: 0
+            })
+        );
+    }
+
+    #[test]
+    fn test_module_list_count_mismatch() {
+        let name = DumpString::new("single module", Endian::Little);
+        let module = SynthModule::new(
+            Endian::Little,
+            0xa90206ca83eb2852,
+            0xada542bd,
+            &name,
+            0xb1054d2a,
+            0x34571371,
+            None,
+        );
+        // Claim three modules, but only include one.
+        let section = Section::with_endian(Endian::Little)
+            .D32(3)
+            .mark(&module.file_offset())
+            .append_section(module);
+        let dump = SynthMinidump::with_endian(Endian::Little)
+            .add_stream(SimpleStream {
+                stream_type: MINIDUMP_STREAM_TYPE::ModuleListStream as u32,
+                section,
+            })
+            .add(name);
+        let dump = read_synth_dump(dump).unwrap();
+        let module_list = dump.get_stream::<MinidumpModuleList>().unwrap();
+        let modules = module_list.iter().collect::<Vec<_>>();
+        assert_eq!(modules.len(), 1);
+        assert_eq!(modules[0].base_address(), 0xa90206ca83eb2852);
+        assert_eq!(modules[0].code_file(), "single module");
+        assert_eq!(module_list.declared_count(), 3);
+        assert_eq!(
+            module_list.validate(),
+            Err(Error::ModuleCountMismatch {
+                declared: 3,
+                actual: 1
+            })
+        );
+
+        let mut output = vec![];
+        module_list.print(&mut output).unwrap();
+        let output = String::from_utf8(output).unwrap();
+        assert!(output.contains("warning: Module count mismatch: 3 modules declared, 1 present"));
+    }
+
+    #[test]
+    fn test_memory_list_truncated() {
+        let dump = SynthMinidump::with_endian(Endian::Little).add_stream(SimpleStream {
+            stream_type: MINIDUMP_STREAM_TYPE::MemoryListStream as u32,
+            section: Section::with_endian(Endian::Little).D32(0xffffffff),
+        });
+        let dump = read_synth_dump(dump).unwrap();
+        match dump.get_stream::<MinidumpMemoryList<'_>>() {
+            Err(Error::StreamTruncated { .. }) => {}
+            other => panic!("unexpected result: {:?}", other.map(|_| ())),
+        }
+    }
+
+    #[test]
+    fn test_memory_wraps_address_space() {
+        let memory = Memory::with_section(
+            Section::with_endian(Endian::Little).append_repeated(0, 0x10),
+            0xfffffffffffffff8,
+        );
+        let dump = SynthMinidump::with_endian(Endian::Little).add_memory(memory);
+        let dump = read_synth_dump(dump).unwrap();
+        // The bogus region is skipped rather than panicking on overflow.
+        let memory_list = dump.get_stream::<MinidumpMemoryList<'_>>().unwrap();
+        assert_eq!(memory_list.iter().count(), 0);
+    }
+
+    #[test]
+    fn test_truncated_dump_does_not_panic() {
+        let name = DumpString::new("module", Endian::Little);
+        let module = SynthModule::new(
+            Endian::Little,
+            0x1000,
+            0x1000,
+            &name,
+            0xb1054d2a,
+            0x34571371,
+            Some(&STOCK_VERSION_INFO),
+        );
+        let memory = Memory::with_section(
+            Section::with_endian(Endian::Little).append_bytes(b"memory_contents"),
+            0x2000,
+        );
+        let crashpad_info = CrashpadInfo::new(Endian::Little)
+            .add_module(
+                ModuleCrashpadInfo::new(0, Endian::Little)
+                    .add_list_annotation("annotation")
+                    .add_simple_annotation("simple", "module")
+                    .add_annotation_object("string", AnnotationValue::String("value".to_owned())),
+            )
+            .add_simple_annotation("simple", "info");
+        let dump = SynthMinidump::with_endian(Endian::Little)
+            .add_module(module)
+            .add(name)
+            .add_memory(memory)
+            .add_crashpad_info(crashpad_info);
+        let bytes = dump.finish().unwrap();
+
+        // Every prefix of the dump should either parse or return an error.
+        for len in 0..bytes.len() {
+            let dump = match Minidump::read(bytes[..len].to_vec()) {
+                Ok(dump) => dump,
+                Err(_) => continue,
+            };
+            let _ = dump.get_stream::<MinidumpModuleList>();
+            let _ = dump.get_stream::<MinidumpMemoryList<'_>>();
+            let _ = dump.get_stream::<MinidumpCrashpadInfo>();
+        }
+    }
 }
@@ -33,6 +33,8 @@ pub struct SynthMinidump {
     unloaded_module_list: Option<ExListStream<UnloadedModule>>,
     /// List of threads in this minidump.
     thread_list: Option<ListStream<Thread>>,
+    /// List of thread names in this minidump.
+    thread_names_list: Option<ListStream<ThreadName>>,
     /// List of memory regions in this minidump.
     memory_list: Option<ListStream<Section>>,
     /// Crashpad extension containing annotations.
@@ -166,6 +168,10 @@ impl SynthMinidump {
                 md::MINIDUMP_STREAM_TYPE::ThreadListStream,
                 endian,
             )),
+            thread_names_list: Some(ListStream::new(
+                md::MINIDUMP_STREAM_TYPE::ThreadNamesStream,
+                endian,
+            )),
             memory_list: Some(ListStream::new(
                 md::MINIDUMP_STREAM_TYPE::MemoryListStream,
                 endian,
@@ -232,6 +238,17 @@ impl SynthMinidump {
         self
     }
 
+    /// Add `thread_name` to `self`, adding it to the thread names stream as well.
+    ///
+    /// The name's `DumpString` must be added separately.
+    pub fn add_thread_name(mut self, thread_name: ThreadName) -> SynthMinidump {
+        self.thread_names_list = self
+            .thread_names_list
+            .take()
+            .map(|thread_names_list| thread_names_list.add(thread_name));
+        self
+    }
+
     /// Add crashpad module and annotation extension information.
     pub fn add_crashpad_info(mut self, crashpad_info: CrashpadInfo) -> Self {
         self.crashpad_info = Some(crashpad_info);
@@ -285,6 +302,9 @@ impl SynthMinidump {
         // Add thread list stream if any threads were added.
         let threads = self.thread_list.take();
         self = self.finish_list(threads);
+        // Add thread names stream if any names were added.
+        let thread_names = self.thread_names_list.take();
+        self = self.finish_list(thread_names);
         // Add crashpad info stream if any.
         if let Some(crashpad_info) = self.crashpad_info.take() {
             self = self.add_stream(crashpad_info);
@@ -802,6 +822,14 @@ pub struct Thread {
 
 impl Thread {
     pub fn new<T>(endian: Endian, id: u32, stack: &Memory, context: &T) -> Thread
+    where
+        T: DumpSection,
+    {
+        Thread::with_teb(endian, id, 0, stack, context)
+    }
+
+    /// Create a thread whose Thread Environment Block is at `teb`.
+    pub fn with_teb<T>(endian: Endian, id: u32, teb: u64, stack: &Memory, context: &T) -> Thread
     where
         T: DumpSection,
     {
@@ -810,7 +838,7 @@ impl Thread {
             .D32(0) // suspend_count
             .D32(0) // priority_class
             .D32(0) // priority
-            .D64(0) // teb
+            .D64(teb)
             .cite_memory(stack)
             .cite_location(context);
         Thread { section }
@@ -825,6 +853,241 @@ impl From<Thread> for Section {
     }
 }
 
+/// An exception stream.
+pub struct Exception {
+    section: Section,
+}
+
+impl Exception {
+    /// Create an exception stream for thread `thread_id` with the given exception `code`,
+    /// `flags`, `address` and `parameters`, citing `context` as the thread's context.
+    pub fn new<T>(
+        endian: Endian,
+        thread_id: u32,
+        code: u32,
+        flags: u32,
+        address: u64,
+        parameters: &[u64],
+        context: &T,
+    ) -> Exception
+    where
+        T: DumpSection,
+    {
+        Exception::with_nested(
+            endian, thread_id, code, flags, address, parameters, 0, context,
+        )
+    }
+
+    /// Like `new`, but the exception record points at a nested exception record at
+    /// address `nested` in the process's memory.
+    #[allow(clippy::too_many_arguments)]
+    pub fn with_nested<T>(
+        endian: Endian,
+        thread_id: u32,
+        code: u32,
+        flags: u32,
+        address: u64,
+        parameters: &[u64],
+        nested: u64,
+        context: &T,
+    ) -> Exception
+    where
+        T: DumpSection,
+    {
+        // EXCEPTION_MAXIMUM_PARAMETERS
+        const MAX_PARAMETERS: usize = 15;
+        assert!(parameters.len() <= MAX_PARAMETERS);
+        let section = Section::with_endian(endian)
+            .D32(thread_id)
+            .D32(0) // __align
+            .D32(code)
+            .D32(flags)
+            .D64(nested) // exception_record
+            .D64(address)
+            .D32(parameters.len() as u32)
+            .D32(0); // __align
+        let section = parameters
+            .iter()
+            .fold(section, |section, &param| section.D64(param))
+            .append_repeated(
+                0,
+                mem::size_of::<u64>() * (MAX_PARAMETERS - parameters.len()),
+            )
+            .cite_location(context);
+        assert_eq!(
+            section.size(),
+            md::MINIDUMP_EXCEPTION_STREAM::size_with(&LE) as u64
+        );
+        Exception { section }
+    }
+}
+
+impl_dumpsection!(Exception);
+
+impl From<Exception> for Section {
+    fn from(exception: Exception) -> Self {
+        exception.section
+    }
+}
+
+impl Stream for Exception {
+    fn stream_type(&self) -> u32 {
+        md::MINIDUMP_STREAM_TYPE::ExceptionStream as u32
+    }
+}
+
+/// A system info stream.
+pub struct SystemInfo {
+    section: Section,
+}
+
+impl SystemInfo {
+    /// Create a system info stream for a system with the given processor architecture
+    /// and platform id, leaving everything else zeroed.
+    pub fn new(endian: Endian, processor_architecture: u16, platform_id: u32) -> SystemInfo {
+        SystemInfo::with_cpu(
+            endian,
+            processor_architecture,
+            platform_id,
+            0,
+            0,
+            Section::with_endian(endian),
+        )
+    }
+
+    /// Create a system info stream like `new`, but with the given processor level
+    /// and revision, and `cpu` as the contents of the `CPU_INFORMATION` union.
+    pub fn with_cpu(
+        endian: Endian,
+        processor_architecture: u16,
+        platform_id: u32,
+        processor_level: u16,
+        processor_revision: u16,
+        cpu: Section,
+    ) -> SystemInfo {
+        let cpu_size = md::CPU_INFORMATION::size_with(&LE) as u64;
+        assert!(cpu.size() <= cpu_size);
+        let padding = cpu_size - cpu.size();
+        let section = Section::with_endian(endian)
+            .D16(processor_architecture)
+            .D16(processor_level)
+            .D16(processor_revision)
+            .D8(1) // number_of_processors
+            .D8(0) // product_type
+            .D32(0) // major_version
+            .D32(0) // minor_version
+            .D32(0) // build_number
+            .D32(platform_id)
+            .D32(0) // csd_version_rva
+            .D16(0) // suite_mask
+            .D16(0) // reserved2
+            .append_section(cpu)
+            .append_repeated(0, padding as usize);
+        assert_eq!(
+            section.size(),
+            md::MINIDUMP_SYSTEM_INFO::size_with(&LE) as u64
+        );
+        SystemInfo { section }
+    }
+}
+
+impl_dumpsection!(SystemInfo);
+
+impl From<SystemInfo> for Section {
+    fn from(system_info: SystemInfo) -> Self {
+        system_info.section
+    }
+}
+
+impl Stream for SystemInfo {
+    fn stream_type(&self) -> u32 {
+        md::MINIDUMP_STREAM_TYPE::SystemInfoStream as u32
+    }
+}
+
+/// A memory info list stream.
+///
+/// The header of this stream counts its entries with a 64-bit field, unlike the
+/// other extended list streams, so it can't be built with `ExListStream`.
+pub struct MemoryInfoList {
+    section: Section,
+    /// The number of entries.
+    count: u64,
+    /// The number of entries, as a `Label`.
+    count_label: Label,
+}
+
+impl MemoryInfoList {
+    pub fn new(endian: Endian) -> MemoryInfoList {
+        let count_label = Label::new();
+        let section = Section::with_endian(endian)
+            .D32(md::MINIDUMP_MEMORY_INFO_LIST::size_with(&LE) as u32)
+            .D32(md::MINIDUMP_MEMORY_INFO::size_with(&LE) as u32)
+            .D64(&count_label);
+        MemoryInfoList {
+            section,
+            count: 0,
+            count_label,
+        }
+    }
+
+    /// Append a region of `region_size` bytes at `base_address` with the given
+    /// state and protection, leaving everything else zeroed.
+    #[allow(clippy::should_implement_trait)]
+    pub fn add(mut self, base_address: u64, region_size: u64, state: u32, protection: u32) -> Self {
+        self.section = self
+            .section
+            .D64(base_address)
+            .D64(0) // allocation_base
+            .D32(0) // allocation_protection
+            .D32(0) // __alignment1
+            .D64(region_size)
+            .D32(state)
+            .D32(protection)
+            .D32(0) // _type
+            .D32(0); // __alignment2
+        self.count += 1;
+        self
+    }
+}
+
+impl_dumpsection!(MemoryInfoList);
+
+impl From<MemoryInfoList> for Section {
+    fn from(list: MemoryInfoList) -> Self {
+        list.count_label.set_const(list.count);
+        list.section
+    }
+}
+
+impl Stream for MemoryInfoList {
+    fn stream_type(&self) -> u32 {
+        md::MINIDUMP_STREAM_TYPE::MemoryInfoListStream as u32
+    }
+}
+
+/// An entry in the thread names stream.
+pub struct ThreadName {
+    section: Section,
+}
+
+impl ThreadName {
+    pub fn new(endian: Endian, id: u32, name: &DumpString) -> ThreadName {
+        let section = Section::with_endian(endian)
+            .D32(id)
+            .D64(&name.file_offset());
+        ThreadName { section }
+    }
+}
+
+impl_dumpsection!(ThreadName);
+
+impl From<ThreadName> for Section {
+    fn from(thread_name: ThreadName) -> Self {
+        thread_name.section
+    }
+}
+
 /// A range of memory contents.
 pub struct Memory {
     section: Section,
@@ -1160,6 +1423,55 @@ pub fn amd64_context(endian: Endian, rip: u64, rsp: u64) -> Section {
     section
 }
 
+/// Populate a `CONTEXT_PPC` struct with the given `endian`, `srr0`, and stack pointer (`r1`).
+pub fn ppc_context(endian: Endian, srr0: u32, r1: u32) -> Section {
+    let section = Section::with_endian(endian)
+        .D32(0x20000007) // context_flags: CONTEXT_PPC | base, float and vector registers
+        .D32(srr0)
+        .D32(0) // srr1
+        .D32(0) // gpr[0]
+        .D32(r1) // gpr[1]
+        .append_repeated(0, mem::size_of::<u32>() * 30) // gpr[2-31]
+        .append_repeated(0, mem::size_of::<u32>() * 6) // cr, xer, lr, ctr, mq, vrsave
+        .append_repeated(0, md::FLOATING_SAVE_AREA_PPC::size_with(&LE)) // float_save
+        .append_repeated(0, md::VECTOR_SAVE_AREA_PPC::size_with(&LE)); // vector_save
+    assert_eq!(section.size(), md::CONTEXT_PPC::size_with(&LE) as u64);
+    section
+}
+
+/// Populate a 64-bit `CONTEXT_MIPS` struct with the given `endian`, `epc`, and stack pointer
+/// (`sp`).
+pub fn mips64_context(endian: Endian, epc: u64, sp: u64) -> Section {
+    mips_context_with_flags(endian, 0x8000e, epc, sp)
+}
+
+/// Populate a 32-bit `CONTEXT_MIPS` struct with the given `endian`, `epc`, and stack pointer
+/// (`sp`), sign-extended to 64 bits the way Breakpad writes them.
+pub fn mips_context(endian: Endian, epc: u32, sp: u32) -> Section {
+    let sign_extend = |val: u32| val as i32 as i64 as u64;
+    mips_context_with_flags(endian, 0x4000e, sign_extend(epc), sign_extend(sp))
+}
+
+fn mips_context_with_flags(endian: Endian, context_flags: u32, epc: u64, sp: u64) -> Section {
+    let section = Section::with_endian(endian)
+        .D32(context_flags) // CONTEXT_MIPS(64) | integer, float and DSP registers
+        .D32(0) // _pad0
+        .append_repeated(0, mem::size_of::<u64>() * 29) // iregs[0-28]
+        .D64(sp) // iregs[29]
+        .append_repeated(0, mem::size_of::<u64>() * 2) // iregs[30-31]
+        .append_repeated(0, mem::size_of::<u64>() * 2) // mdhi, mdlo
+        .append_repeated(0, mem::size_of::<u32>() * 6) // hi, lo
+        .D32(0) // dsp_control
+        .D32(0) // _pad1
+        .D64(epc)
+        .D64(0) // badvaddr
+        .D32(0) // status
+        .D32(0) // cause
+        .append_repeated(0, md::FLOATING_SAVE_AREA_MIPS::size_with(&LE)); // float_save
+    assert_eq!(section.size(), md::CONTEXT_MIPS::size_with(&LE) as u64);
+    section
+}
+
 pub struct SectionRef {
     section: Section,
     data_section: Section,
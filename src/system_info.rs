@@ -93,6 +93,8 @@ pub enum Cpu {
     Sparc,
     Arm,
     Arm64,
+    Mips,
+    Mips64,
     Unknown(u16),
 }
 
@@ -111,6 +113,8 @@ impl Cpu {
             Some(PROCESSOR_ARCHITECTURE_ARM64) | Some(PROCESSOR_ARCHITECTURE_ARM64_OLD) => {
                 Cpu::Arm64
             }
+            Some(PROCESSOR_ARCHITECTURE_MIPS) => Cpu::Mips,
+            Some(PROCESSOR_ARCHITECTURE_MIPS64) => Cpu::Mips64,
             _ => Cpu::Unknown(arch),
         }
     }
@@ -129,6 +133,8 @@ impl fmt::Display for Cpu {
                 Cpu::Sparc => "sparc",
                 Cpu::Arm => "arm",
                 Cpu::Arm64 => "arm64",
+                Cpu::Mips => "mips",
+                Cpu::Mips64 => "mips64",
                 Cpu::Unknown(_) => "unknown",
             }
         )
@@ -0,0 +1,8 @@
+/* Built with:
+ *   cc -gdwarf-4 -O0 -fdebug-prefix-map=$PWD=. -shared -nostdlib -Wl,--build-id=sha1 -o dwarf-test.elf dwarf-test.c
+ *   cc -gdwarf-4 -O0 -fdebug-prefix-map=$PWD=. -shared -nostdlib -Wl,--build-id=none -o dwarf-test-no-id.elf dwarf-test.c
+ */
+int crash_here(int *p) {
+  *p = 0;
+  return 1;
+}
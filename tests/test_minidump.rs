@@ -9,7 +9,7 @@ use minidump_common::format as md;
 use minidump_common::traits::Module;
 use num_traits::cast::FromPrimitive;
 use std::fs::File;
-use std::io::Read;
+use std::io::{Cursor, Read, Seek, SeekFrom};
 use std::path::PathBuf;
 
 fn get_test_minidump_path(filename: &str) -> PathBuf {
@@ -40,6 +40,130 @@ fn test_minidump_read() {
     let _dump = Minidump::read(buf).unwrap();
 }
 
+#[test]
+fn test_minidump_read_from() {
+    let path = get_test_minidump_path("test.dmp");
+    let dump = read_test_minidump().unwrap();
+    let mut reader = Minidump::read_from(File::open(&path).unwrap()).unwrap();
+    assert_eq!(reader.header.stream_count, dump.header.stream_count);
+    // Every directory entry is listed, even the duplicates `Minidump` collapses.
+    let stream_types = reader.stream_types();
+    assert_eq!(stream_types.len(), dump.header.stream_count as usize);
+    assert!(dump.stream_types().iter().all(|t| stream_types.contains(t)));
+    let module_list = reader
+        .read_raw_stream(md::MINIDUMP_STREAM_TYPE::ModuleListStream)
+        .unwrap();
+    assert_eq!(
+        module_list,
+        dump.get_raw_stream(md::MINIDUMP_STREAM_TYPE::ModuleListStream)
+            .unwrap()
+    );
+    assert_eq!(
+        reader
+            .read_raw_stream(md::MINIDUMP_STREAM_TYPE::JavaScriptDataStream)
+            .unwrap_err(),
+        Error::StreamNotFound
+    );
+    let dump = reader.into_minidump().unwrap();
+    let module_list: MinidumpModuleList = dump.get_stream().unwrap();
+    assert_eq!(module_list.iter().count(), 13);
+
+    // The minidump doesn't have to be at the start of the reader.
+    let mut bytes = b"some other data".to_vec();
+    File::open(&path).unwrap().read_to_end(&mut bytes).unwrap();
+    let mut cursor = Cursor::new(bytes);
+    cursor.seek(SeekFrom::Start(15)).unwrap();
+    let mut reader = Minidump::read_from(cursor).unwrap();
+    assert_eq!(
+        reader
+            .read_raw_stream(md::MINIDUMP_STREAM_TYPE::ModuleListStream)
+            .unwrap(),
+        module_list_bytes(&path)
+    );
+    let dump = reader.into_minidump().unwrap();
+    let module_list: MinidumpModuleList = dump.get_stream().unwrap();
+    assert_eq!(module_list.iter().count(), 13);
+
+    // Something that isn't a minidump is rejected.
+    let cursor = Cursor::new(b"this is not a minidump, it's too short".to_vec());
+    assert_eq!(
+        Minidump::read_from(cursor).unwrap_err(),
+        Error::HeaderMismatch
+    );
+    let mut bytes = vec![];
+    File::open(&path).unwrap().read_to_end(&mut bytes).unwrap();
+    let full = bytes.clone();
+    bytes.truncate(16);
+    assert_eq!(
+        Minidump::read_from(Cursor::new(bytes)).unwrap_err(),
+        Error::MissingHeader
+    );
+
+    // A stream that runs past the end of the input is only an error when
+    // it's read, and doesn't take the memory its size claims.
+    let directory_end =
+        dump.header.stream_directory_rva as usize + dump.header.stream_count as usize * 12;
+    let mut reader = Minidump::read_from(Cursor::new(&full[..directory_end])).unwrap();
+    match reader.read_raw_stream(md::MINIDUMP_STREAM_TYPE::ModuleListStream) {
+        Err(Error::StreamTruncated { .. }) => {}
+        other => panic!("expected a truncated stream, got {:?}", other),
+    }
+}
+
+fn module_list_bytes(path: &PathBuf) -> Vec<u8> {
+    let dump = Minidump::read_path(path).unwrap();
+    dump.get_raw_stream(md::MINIDUMP_STREAM_TYPE::ModuleListStream)
+        .unwrap()
+        .to_vec()
+}
+
+#[test]
+fn test_minidump_read_at() {
+    let path = get_test_minidump_path("test.dmp");
+    let mut minidump = vec![];
+    File::open(&path)
+        .unwrap()
+        .read_to_end(&mut minidump)
+        .unwrap();
+    // Wrap the minidump in a container with data on either side of it.
+    let mut container = b"envelope header".to_vec();
+    container.extend_from_slice(&minidump);
+    container.extend_from_slice(b"envelope trailer");
+
+    assert_eq!(find_minidump_header(&container), Some(15));
+    assert_eq!(find_minidump_header(&minidump), Some(0));
+    let dump = Minidump::read_at(&container[..], 15).unwrap();
+    let module_list: MinidumpModuleList = dump.get_stream().unwrap();
+    assert_eq!(module_list.iter().count(), 13);
+    let thread_list: MinidumpThreadList = dump.get_stream().unwrap();
+    assert_eq!(thread_list.threads[0].stack.as_ref().unwrap().size, 3300);
+    // A sub-slice starting at the header works the same.
+    let dump = Minidump::read(&container[15..]).unwrap();
+    let module_list: MinidumpModuleList = dump.get_stream().unwrap();
+    assert_eq!(module_list.iter().count(), 13);
+
+    // The header has to be at the offset.
+    assert_eq!(
+        Minidump::read_at(&container[..], 0).unwrap_err(),
+        Error::HeaderMismatch
+    );
+    assert_eq!(
+        Minidump::read_at(&container[..], container.len() + 1).unwrap_err(),
+        Error::MissingHeader
+    );
+    // A signature without a header after it isn't a minidump.
+    assert_eq!(find_minidump_header(b"MDMP, but not a minidump"), None);
+}
+
+#[cfg(feature = "mmap")]
+#[test]
+fn test_minidump_read_mmap() {
+    let path = get_test_minidump_path("test.dmp");
+    let dump = Minidump::read_mmap(&path).unwrap();
+    let module_list: MinidumpModuleList = dump.get_stream().unwrap();
+    assert_eq!(module_list.iter().count(), 13);
+}
+
 #[test]
 fn test_module_list() {
     let dump = read_test_minidump().unwrap();
@@ -61,6 +185,7 @@ fn test_module_list() {
         "5A9832E5287241C1838ED98914E9B7FF1"
     );
     assert!(modules[0].version().is_none());
+    assert!(modules[0].version_info().is_none());
 
     assert_eq!(modules[12].base_address(), 0x76bf0000);
     assert_eq!(modules[12].size(), 0xb000);
@@ -72,6 +197,15 @@ fn test_module_list() {
         "A5C3A1F9689F43D8AD228A09293889702"
     );
     assert_eq!(modules[12].version().unwrap(), "5.1.2600.2180");
+    let version_info = modules[12].version_info().unwrap();
+    assert_eq!(version_info.file_version, [5, 1, 2600, 2180]);
+    assert_eq!(version_info.product_version_string(), "5.1.2600.2180");
+    assert_eq!(version_info.file_flags, md::FileInfoFlags::empty());
+    assert_eq!(version_info.file_os, 0x40004);
+    assert_eq!(
+        md::FileInfoType::from_u32(version_info.file_type),
+        Some(md::FileInfoType::VFT_DLL)
+    );
 
     assert_eq!(
         module_files,
@@ -125,7 +259,17 @@ fn test_system_info() {
         system_info.cpu_info().unwrap(),
         "GenuineIntel family 6 model 13 stepping 8"
     );
-    assert_eq!(system_info.csd_version(), None);
+    assert_eq!(system_info.csd_version().as_deref(), Some("Service Pack 2"));
+}
+
+#[test]
+fn test_system_info_cpu_features() {
+    let dump = read_test_minidump().unwrap();
+    let system_info = dump.get_stream::<MinidumpSystemInfo>().unwrap();
+    assert_eq!(
+        system_info.cpu_features(),
+        &["fpu", "tsc", "cx8", "cmov", "clflush", "mmx", "fxsr", "sse", "sse2"]
+    );
 }
 
 #[test]
@@ -196,6 +340,10 @@ fn test_assertion() {
         md::AssertionType::from_u32(assertion.raw._type),
         Some(md::AssertionType::InvalidParameter)
     );
+    assert_eq!(
+        assertion.description(),
+        r"format != nullptr in function common_vfprintf, line 32 in file minkernel\crts\ucrt\src\appcrt\stdio\output.cpp"
+    );
 }
 
 #[test]
@@ -207,6 +355,7 @@ fn test_exception() {
     if let Some(ref ctx) = exception.context {
         assert_eq!(ctx.get_instruction_pointer(), 0x40429e);
         assert_eq!(ctx.get_stack_pointer(), 0x12fe84);
+        assert_eq!(ctx.pointer_width(), 4);
         if let MinidumpContext {
             raw: MinidumpRawContext::X86(ref raw),
             ref valid,
@@ -232,6 +381,11 @@ fn test_thread_list() {
     assert_eq!(threads[1].raw.thread_id, 0x11c0);
     let id = threads[1].raw.thread_id;
     assert_eq!(thread_list.get_thread(id).unwrap().raw.thread_id, id);
+    // Breakpad doesn't record scheduling state.
+    assert_eq!(threads[0].suspend_count(), 0);
+    assert_eq!(threads[0].priority_class(), 0);
+    assert_eq!(threads[0].priority(), 0);
+    assert_eq!(threads[0].teb(), Some(0x7ffdf000));
     if let Some(ref ctx) = threads[0].context {
         assert_eq!(ctx.get_instruction_pointer(), 0x7c90eb94);
         assert_eq!(ctx.get_stack_pointer(), 0x12f320);
@@ -277,3 +431,46 @@ fn test_empty_minidump() {
         Err(e) => assert_eq!(e, Error::MissingHeader),
     }
 }
+
+#[cfg(feature = "json")]
+#[test]
+fn test_json() {
+    let dump = read_test_minidump().unwrap();
+    let mut json = vec![];
+    dump.print_json(&mut json, false).unwrap();
+    let json: serde_json::Value = serde_json::from_slice(&json).unwrap();
+    assert_eq!(json["header"]["signature"], "0x504d444d");
+    assert_eq!(json["header"]["stream_count"], 9);
+
+    // Every directory entry is listed, including both unused ones.
+    let streams = json["streams"].as_array().unwrap();
+    assert_eq!(streams.len(), 9);
+    let thread_list = &streams[0];
+    assert_eq!(thread_list["type"], 3);
+    assert_eq!(thread_list["name"], "ThreadListStream");
+    assert_eq!(thread_list["size"], 100);
+    let thread = &thread_list["threads"][0];
+    assert_eq!(thread["thread_id"], 3060);
+    assert_eq!(thread["stack"]["base_address"], "0x12f31c");
+    assert_eq!(thread["context"]["registers"]["eip"], "0x7c90eb94");
+
+    let modules = &streams[1]["modules"];
+    assert_eq!(modules.as_array().unwrap().len(), 13);
+    assert_eq!(modules[0]["code_file"], "c:\\test_app.exe");
+    assert_eq!(modules[0]["base_address"], "0x400000");
+
+    let exception = &streams[3]["exception"];
+    assert_eq!(exception["exception_code"], "0xc0000005");
+    assert_eq!(exception["parameters"], serde_json::json!(["0x1", "0x45"]));
+    assert_eq!(streams[4]["system_info"]["csd_version"], "Service Pack 2");
+    assert_eq!(streams[5]["misc_info"]["process_id"], 3932);
+    assert_eq!(streams[6]["breakpad_info"]["requesting_thread_id"], 3060);
+
+    // Unused entries aren't streams this crate parses.
+    for stream in &streams[7..] {
+        assert_eq!(
+            *stream,
+            serde_json::json!({ "type": 0, "size": 0, "unparsed": true })
+        );
+    }
+}